@@ -36,8 +36,8 @@ function M.setup(opts)
   -- -----------------------------------------------------------------
   -- Autocommand: detect hutt compose files and configure the buffer.
   --
-  -- Hutt writes temp files matching /tmp/hutt-compose-*.eml (or the
-  -- OS temp dir equivalent).  We match on the pattern and also on
+  -- Hutt writes temp files named hutt-compose-<pid>.eml in
+  -- $XDG_RUNTIME_DIR/hutt (or its compose_dir setting).  We match on the pattern and also on
   -- any *.eml file inside a directory whose name starts with "hutt-".
   -- -----------------------------------------------------------------
   local augroup = vim.api.nvim_create_augroup("Hutt", { clear = true })
@@ -1,7 +1,7 @@
 -- hutt/send.lua — Send / discard helpers for the hutt compose flow.
 --
 -- Hutt's compose mechanism works like this:
---   1. Hutt writes a temp file at /tmp/hutt-compose-<pid>.eml
+--   1. Hutt writes a private temp file, $XDG_RUNTIME_DIR/hutt/hutt-compose-<pid>.eml
 --   2. It launches $EDITOR (nvim) on that file
 --   3. On editor exit it checks whether the file's mtime changed
 --      - mtime changed  -> read the file and send via SMTP
@@ -0,0 +1,259 @@
+#!/usr/bin/env python3
+"""Convert mu4e bookmarks into hutt smart folders.
+
+Reads bookmark definitions from an Emacs init fragment, in any of the forms
+mu4e has used:
+
+    (setq mu4e-bookmarks
+          '((:name "Unread" :query "flag:unread AND NOT flag:trashed" :key ?u)
+            (:name "Today" :query "date:today..now" :key ?t)))
+    (add-to-list 'mu4e-bookmarks
+                 (make-mu4e-bookmark :name "Big" :query "size:5M..500M" :key ?b))
+    ("flag:flagged" "Flagged messages" ?f)          ; pre-1.0 list form
+
+or from a simple exported list with one `name<TAB>query` pair per line
+(use --tsv).
+
+Usage:
+    # Show the bookmarks found in your init file
+    ./scripts/mu4e-import.py ~/.emacs.d/init.el
+
+    # Print them as hutt smart_folders TOML
+    ./scripts/mu4e-import.py ~/.emacs.d/init.el --hutt
+
+    # Add them to an account's smart folders file (existing names are kept)
+    ./scripts/mu4e-import.py ~/.emacs.d/init.el --account Work --write
+
+    # Read a tab-separated list from stdin
+    ./scripts/mu4e-import.py --tsv - < bookmarks.tsv
+"""
+
+import argparse
+import json
+import os
+import re
+import sys
+from pathlib import Path
+
+
+def tokenize(text):
+    """Split Emacs Lisp source into parens, strings and atoms.
+
+    Strings are returned as ("str", value); everything else as plain
+    strings. Comments and quote characters are dropped.
+    """
+    tokens = []
+    i = 0
+    while i < len(text):
+        c = text[i]
+        if c.isspace() or c in "'`,":
+            i += 1
+        elif c == ";":
+            end = text.find("\n", i)
+            i = len(text) if end < 0 else end
+        elif c in "()[]":
+            tokens.append({"[": "(", "]": ")"}.get(c, c))
+            i += 1
+        elif c == '"':
+            i += 1
+            value = []
+            while i < len(text) and text[i] != '"':
+                if text[i] == "\\" and i + 1 < len(text):
+                    i += 1
+                    value.append({"n": "\n", "t": "\t"}.get(text[i], text[i]))
+                else:
+                    value.append(text[i])
+                i += 1
+            tokens.append(("str", "".join(value)))
+            i += 1
+        elif c == "?":
+            # Character literal such as ?u or ?\C-x
+            m = re.match(r"\?(\\.|[^\s()])[^\s()]*", text[i:])
+            tokens.append(m.group(0) if m else c)
+            i += len(m.group(0)) if m else 1
+        else:
+            m = re.match(r"[^\s()\[\]\"';]+", text[i:])
+            tokens.append(m.group(0))
+            i += len(m.group(0))
+    return tokens
+
+
+def parse_lists(tokens):
+    """Build nested lists from tokens. Unbalanced parens are tolerated."""
+    root = []
+    stack = [root]
+    for tok in tokens:
+        if tok == "(":
+            new = []
+            stack[-1].append(new)
+            stack.append(new)
+        elif tok == ")":
+            if len(stack) > 1:
+                stack.pop()
+        else:
+            stack[-1].append(tok)
+    return root
+
+
+def is_str(tok):
+    return isinstance(tok, tuple) and tok[0] == "str"
+
+
+def walk(node):
+    """Yield every list nested anywhere inside node, node included."""
+    if isinstance(node, list):
+        yield node
+        for child in node:
+            yield from walk(child)
+
+
+def bookmark_from_list(lst, notes):
+    """Return a bookmark dict if this list is a mu4e bookmark, else None."""
+    if ":name" in lst and ":query" in lst:
+        def value(key):
+            idx = lst.index(key) + 1
+            return lst[idx] if idx < len(lst) else None
+
+        name, query = value(":name"), value(":query")
+        if not is_str(name):
+            return None
+        if not is_str(query):
+            notes.append(f"{name[1]}: query is Lisp code, not a string; skipped")
+            return None
+        hidden = value(":hide") == "t" if ":hide" in lst else False
+        return {"name": name[1], "query": query[1], "hidden": hidden}
+    # Pre-1.0 form: ("query" "name" ?key)
+    if len(lst) == 3 and is_str(lst[0]) and is_str(lst[1]) and str(lst[2]).startswith("?"):
+        return {"name": lst[1][1], "query": lst[0][1], "hidden": False}
+    return None
+
+
+def parse_elisp(text):
+    """Find all bookmarks in an Emacs Lisp fragment."""
+    notes = []
+    bookmarks = []
+    for lst in walk(parse_lists(tokenize(text))):
+        bm = bookmark_from_list(lst, notes)
+        if bm and not any(b["name"] == bm["name"] for b in bookmarks):
+            bookmarks.append(bm)
+    return bookmarks, notes
+
+
+def parse_tsv(text):
+    """Parse `name<TAB>query` lines. Blank lines and # comments are skipped."""
+    bookmarks = []
+    for line in text.splitlines():
+        if not line.strip() or line.lstrip().startswith("#"):
+            continue
+        name, sep, query = line.partition("\t")
+        if sep and name.strip() and query.strip():
+            bookmarks.append({"name": name.strip(), "query": query.strip(), "hidden": False})
+    return bookmarks, []
+
+
+def toml_string(s):
+    return '"' + s.replace("\\", "\\\\").replace('"', '\\"') + '"'
+
+
+def format_hutt_toml(bookmarks):
+    """Format bookmarks as hutt smart_folders TOML entries."""
+    lines = []
+    for b in bookmarks:
+        lines.append("[[folders]]")
+        lines.append(f"name = {toml_string(b['name'])}")
+        lines.append(f"query = {toml_string(b['query'])}")
+        lines.append("")
+    return "\n".join(lines)
+
+
+def smart_folders_path(account):
+    """Path of hutt's smart folders file for an account."""
+    config = os.environ.get("XDG_CONFIG_HOME") or str(Path.home() / ".config")
+    return Path(config) / "hutt" / f"smart_folders.{account}.toml"
+
+
+def existing_names(path):
+    """Names of the smart folders already in a hutt smart folders file."""
+    if not path.exists():
+        return set()
+    names = re.findall(r'^\s*name\s*=\s*"((?:[^"\\]|\\.)*)"', path.read_text(), re.M)
+    return {n.replace('\\"', '"').replace("\\\\", "\\") for n in names}
+
+
+def main():
+    parser = argparse.ArgumentParser(
+        description="Convert mu4e bookmarks into hutt smart folders"
+    )
+    parser.add_argument("file", help="Emacs init file or fragment ('-' for stdin)")
+    parser.add_argument(
+        "--tsv",
+        action="store_true",
+        help="Input is name<TAB>query lines instead of Emacs Lisp",
+    )
+    parser.add_argument(
+        "--hutt",
+        action="store_true",
+        help="Output as hutt smart_folders TOML",
+    )
+    parser.add_argument(
+        "--json",
+        action="store_true",
+        help="Output as JSON",
+    )
+    parser.add_argument(
+        "--account",
+        help="hutt account name whose smart folders file to update (with --write)",
+    )
+    parser.add_argument(
+        "--write",
+        action="store_true",
+        help="Append new smart folders to the account's file",
+    )
+    parser.add_argument(
+        "--include-hidden",
+        action="store_true",
+        help="Include bookmarks marked :hide t",
+    )
+    args = parser.parse_args()
+
+    if args.write and not args.account:
+        print("Error: --write needs --account NAME", file=sys.stderr)
+        sys.exit(1)
+
+    text = sys.stdin.read() if args.file == "-" else Path(args.file).expanduser().read_text()
+    bookmarks, notes = parse_tsv(text) if args.tsv else parse_elisp(text)
+    if not args.include_hidden:
+        bookmarks = [b for b in bookmarks if not b["hidden"]]
+    for n in notes:
+        print(f"⚠ {n}", file=sys.stderr)
+
+    if not bookmarks:
+        print("No bookmarks found.", file=sys.stderr)
+        sys.exit(1)
+
+    if args.write:
+        path = smart_folders_path(args.account)
+        have = existing_names(path)
+        new = [b for b in bookmarks if b["name"] not in have]
+        for b in bookmarks:
+            if b["name"] in have:
+                print(f"  {b['name']}: already exists, kept", file=sys.stderr)
+        if new:
+            path.parent.mkdir(parents=True, exist_ok=True)
+            existing = path.read_text() if path.exists() else ""
+            sep = "\n" if existing and not existing.endswith("\n\n") else ""
+            path.write_text(existing + sep + format_hutt_toml(new))
+        print(f"Added {len(new)} smart folder(s) to {path}", file=sys.stderr)
+    elif args.json:
+        print(json.dumps(bookmarks, indent=2))
+    elif args.hutt:
+        print(format_hutt_toml(bookmarks))
+    else:
+        for b in bookmarks:
+            hidden = " [hidden]" if b["hidden"] else ""
+            print(f"  @{b['name']}{hidden}")
+            print(f"    {b['query']}")
+
+
+if __name__ == "__main__":
+    main()
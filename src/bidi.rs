@@ -0,0 +1,223 @@
+//! Right-to-left text (Arabic, Hebrew, ...) in message bodies. Terminals
+//! draw characters in the order they are written, so lines holding
+//! right-to-left text are put into visual order here, and the lines of
+//! right-to-left paragraphs are right-aligned.
+
+use unicode_bidi::{bidi_class, BidiClass, Level, ParagraphBidiInfo};
+
+use crate::mime_render::{RenderedMessage, RichSpan};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn label(self) -> &'static str {
+        match self {
+            Direction::Ltr => "left-to-right",
+            Direction::Rtl => "right-to-left",
+        }
+    }
+}
+
+/// Direction of the first strongly directional character in `text`.
+pub fn strong_direction(text: &str) -> Option<Direction> {
+    text.chars().find_map(|c| match bidi_class(c) {
+        BidiClass::L => Some(Direction::Ltr),
+        BidiClass::R | BidiClass::AL => Some(Direction::Rtl),
+        _ => None,
+    })
+}
+
+fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            bidi_class(c),
+            BidiClass::R | BidiClass::AL | BidiClass::RLE | BidiClass::RLO | BidiClass::RLI
+        )
+    })
+}
+
+fn line_text(spans: &[RichSpan]) -> String {
+    spans.iter().map(|s| s.text.as_str()).collect()
+}
+
+/// Lay out a rendered body for display, `width` columns wide. Each
+/// paragraph (lines up to a blank one) takes the direction of its first
+/// strong character, unless `force` sets one for the whole message.
+pub fn apply(rendered: &mut RenderedMessage, width: u16, force: Option<Direction>) {
+    let texts: Vec<String> = rendered.lines.iter().map(|l| line_text(l)).collect();
+    let mut direction = None;
+    for (i, text) in texts.iter().enumerate() {
+        if text.trim().is_empty() {
+            direction = None;
+            continue;
+        }
+        let direction = *direction.get_or_insert_with(|| {
+            force
+                .or_else(|| {
+                    texts[i..]
+                        .iter()
+                        .take_while(|t| !t.trim().is_empty())
+                        .find_map(|t| strong_direction(t))
+                })
+                .unwrap_or(Direction::Ltr)
+        });
+        if direction == Direction::Ltr && !has_rtl(text) {
+            continue;
+        }
+
+        let (mut spans, starts) = reorder(&rendered.lines[i], direction);
+        let len = text.chars().count();
+        let pad = match direction {
+            Direction::Rtl => (width as usize).saturating_sub(len),
+            Direction::Ltr => 0,
+        };
+        if pad > 0 {
+            spans.insert(0, RichSpan {
+                text: " ".repeat(pad),
+                kind: crate::mime_render::SpanKind::Normal,
+            });
+        }
+        // Links start at span boundaries; follow their spans
+        let mut old_starts = Vec::with_capacity(starts.len());
+        let mut col = 0;
+        for span in &rendered.lines[i] {
+            old_starts.push(col);
+            col += span.text.chars().count();
+        }
+        for link in rendered.links.iter_mut().filter(|l| l.line == i) {
+            if let Some(span) = old_starts.iter().position(|&c| c == link.col_start) {
+                let start = starts[span] + pad;
+                link.col_end = start + (link.col_end - link.col_start);
+                link.col_start = start;
+            }
+        }
+        rendered.lines[i] = spans;
+    }
+}
+
+/// Put a line's characters in visual order for a paragraph running in
+/// `direction`, keeping each character's span kind. Also returns the new
+/// column each original span starts at.
+fn reorder(spans: &[RichSpan], direction: Direction) -> (Vec<RichSpan>, Vec<usize>) {
+    let text = line_text(spans);
+    let owner: Vec<usize> = spans
+        .iter()
+        .enumerate()
+        .flat_map(|(i, s)| std::iter::repeat_n(i, s.text.len()))
+        .collect();
+    let level = match direction {
+        Direction::Ltr => Level::ltr(),
+        Direction::Rtl => Level::rtl(),
+    };
+    let info = ParagraphBidiInfo::new(&text, Some(level));
+    let (levels, runs) = info.visual_runs(0..text.len());
+
+    let mut out: Vec<RichSpan> = Vec::new();
+    let mut starts = vec![usize::MAX; spans.len()];
+    let mut last_owner = None;
+    let mut col = 0;
+    for run in runs {
+        let mut clusters = clusters(&text, run.start, &text[run.clone()]);
+        if levels[run.start].is_rtl() {
+            clusters.reverse();
+        }
+        for (byte, cluster) in clusters {
+            let o = owner[byte];
+            if last_owner != Some(o) {
+                out.push(RichSpan { text: String::new(), kind: spans[o].kind.clone() });
+                last_owner = Some(o);
+                starts[o] = starts[o].min(col);
+            }
+            let span = out.last_mut().expect("span pushed above");
+            if levels[run.start].is_rtl() {
+                span.text.extend(cluster.chars().map(mirror));
+            } else {
+                span.text.push_str(cluster);
+            }
+            col += cluster.chars().count();
+        }
+    }
+    for start in starts.iter_mut().filter(|s| **s == usize::MAX) {
+        *start = 0;
+    }
+    (out, starts)
+}
+
+/// Split a run into characters with their combining marks, which must
+/// stay after their base character when the run is reversed. Each comes
+/// with its byte offset in the line (`offset` is the run's).
+fn clusters<'a>(text: &'a str, offset: usize, run: &'a str) -> Vec<(usize, &'a str)> {
+    let mut clusters: Vec<(usize, &str)> = Vec::new();
+    for (i, c) in run.char_indices() {
+        let start = offset + i;
+        let end = start + c.len_utf8();
+        match clusters.last_mut() {
+            Some((from, cluster)) if bidi_class(c) == BidiClass::NSM => {
+                *cluster = &text[*from..end];
+            }
+            _ => clusters.push((start, &text[start..end])),
+        }
+    }
+    clusters
+}
+
+/// Brackets face the other way in right-to-left text.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '\u{ab}' => '\u{bb}',
+        '\u{bb}' => '\u{ab}',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mime_render::render_plain_text;
+
+    fn plain(rendered: &RenderedMessage) -> Vec<String> {
+        rendered.lines.iter().map(|l| line_text(l)).collect()
+    }
+
+    #[test]
+    fn detects_and_reorders_rtl_paragraphs() {
+        let text = "שלום (עולם)\nhttps://example.com/x שלום\n\nHello שלום world";
+        let mut rendered = render_plain_text(text, 40);
+        apply(&mut rendered, 30, None);
+        let lines = plain(&rendered);
+        // Right-aligned, reversed, brackets mirrored
+        assert_eq!(lines[0], format!("{}(םלוע) םולש", " ".repeat(19)));
+        // The link keeps reading left to right, to the right of the Hebrew
+        assert_eq!(lines[1].trim_start(), "םולש https://example.com/x");
+        let link = &rendered.links[0];
+        assert_eq!(link.line, 1);
+        let start = lines[1].chars().position(|c| c == 'h').unwrap();
+        assert_eq!((link.col_start, link.col_end), (start, start + 21));
+        // An English paragraph stays left-aligned; only the Hebrew turns
+        assert_eq!(lines[3], "Hello םולש world");
+
+        let mut forced = render_plain_text("Hello", 40);
+        apply(&mut forced, 10, Some(Direction::Rtl));
+        assert_eq!(plain(&forced)[0], "     Hello");
+    }
+
+    #[test]
+    fn combining_marks_stay_on_their_letter() {
+        // Hebrew letters with vowel points
+        let mut rendered = render_plain_text("שָׁלוֹם", 40);
+        apply(&mut rendered, 7, None);
+        assert_eq!(plain(&rendered)[0], "\u{5dd}\u{5d5}\u{5b9}\u{5dc}\u{5e9}\u{5b8}\u{5c1}");
+    }
+}
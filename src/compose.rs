@@ -1,9 +1,11 @@
-use anyhow::{Context, Result};
-use chrono::Utc;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::SystemTime;
+use unicode_width::UnicodeWidthStr;
 
 use crate::envelope::{Address, Envelope};
 
@@ -22,7 +24,7 @@ pub enum ComposePending {
     /// Build context from current selection (normal keybinding path).
     Kind(ComposeKind),
     /// Pre-built context (from IPC compose URL).
-    Ready(ComposeContext),
+    Ready(Box<ComposeContext>),
 }
 
 /// Everything needed to build the compose buffer.
@@ -37,6 +39,35 @@ pub struct ComposeContext {
     pub in_reply_to: Option<String>,
     pub references: Vec<String>,
     pub original_path: Option<std::path::PathBuf>,
+    /// Date of the message being replied to or forwarded.
+    pub original_date: Option<DateTime<Utc>>,
+    /// Mailing list posting address of the original (from List-Post).
+    pub list_address: Option<String>,
+    /// Reply-To of the original, when it sets one.
+    pub reply_to: Vec<Address>,
+    pub bcc: Vec<Address>,
+    /// Files to attach, written as `Attach:` lines in the compose buffer.
+    pub attachments: Vec<std::path::PathBuf>,
+    /// Address to send from instead of the account's, e.g. the plus
+    /// address the original was delivered to.
+    pub from: Option<String>,
+}
+
+/// A structured compose request, as sent by `hutt compose --json` or a
+/// browser extension through the HTTP API.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComposeRequest {
+    /// Recipients, as `addr@example.com` or `Name <addr@example.com>`
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    /// Absolute paths of files to attach
+    pub attachments: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
 }
 
 impl ComposeContext {
@@ -66,16 +97,14 @@ impl ComposeContext {
             .join("\n");
 
         // Build references chain: existing References + this Message-Id
-        let mut references = Vec::new();
-        // We'd populate from the original message headers if available;
-        // for now just include the message-id.
-        references.push(envelope.message_id.clone());
+        let parent = read_parent_headers(&envelope.path);
+        let references = reply_references(parent.references, &envelope.message_id);
 
         Self {
             kind,
             to: envelope.from.clone(),
             cc: if reply_all {
-                envelope.to.clone()
+                envelope.to.iter().chain(&envelope.cc).cloned().collect()
             } else {
                 Vec::new()
             },
@@ -84,6 +113,12 @@ impl ComposeContext {
             in_reply_to: Some(envelope.message_id.clone()),
             references,
             original_path: Some(envelope.path.clone()),
+            original_date: Some(envelope.date),
+            list_address: parent.list_address,
+            reply_to: parent.reply_to,
+            bcc: Vec::new(),
+            attachments: Vec::new(),
+            from: None,
         }
     }
 
@@ -120,6 +155,12 @@ impl ComposeContext {
             in_reply_to: None,
             references: Vec::new(),
             original_path: Some(envelope.path.clone()),
+            original_date: Some(envelope.date),
+            list_address: None,
+            reply_to: Vec::new(),
+            bcc: Vec::new(),
+            attachments: Vec::new(),
+            from: None,
         }
     }
 
@@ -134,8 +175,114 @@ impl ComposeContext {
             in_reply_to: None,
             references: Vec::new(),
             original_path: None,
+            original_date: None,
+            list_address: None,
+            reply_to: Vec::new(),
+            bcc: Vec::new(),
+            attachments: Vec::new(),
+            from: None,
+        }
+    }
+
+    /// Build a new-message context from a structured compose request.
+    pub fn from_request(req: &ComposeRequest) -> Self {
+        let addresses = |list: &[String]| -> Vec<Address> {
+            list.iter()
+                .map(|a| a.trim())
+                .filter(|a| !a.is_empty())
+                .map(|a| Address { name: None, email: a.to_string() })
+                .collect()
+        };
+        Self {
+            to: addresses(&req.to),
+            cc: addresses(&req.cc),
+            bcc: addresses(&req.bcc),
+            subject: req.subject.clone(),
+            quoted_body: req.body.clone(),
+            attachments: req.attachments.iter().map(std::path::PathBuf::from).collect(),
+            ..Self::new_message()
+        }
+    }
+}
+
+/// Headers of the message being replied to that the reply needs.
+#[derive(Debug, Default)]
+struct ParentHeaders {
+    /// Thread ancestry: References, or In-Reply-To when References is absent.
+    /// Bare message-ids.
+    references: Vec<String>,
+    /// Posting address from List-Post, if the parent came via a mailing list.
+    list_address: Option<String>,
+    reply_to: Vec<Address>,
+}
+
+/// Read the headers of the message at `path` that a reply builds on.
+/// An unreadable or unparsable file yields empty headers.
+fn read_parent_headers(path: &Path) -> ParentHeaders {
+    let Ok(raw) = fs::read(path) else {
+        return ParentHeaders::default();
+    };
+    let Some(message) = mail_parser::MessageParser::default().parse(&raw) else {
+        return ParentHeaders::default();
+    };
+    let references = message
+        .references()
+        .as_text_list()
+        .or_else(|| message.in_reply_to().as_text_list())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|id| bare_msgid(id).to_string())
+        .collect();
+    let list_address = message.header_raw("List-Post").and_then(parse_list_post);
+    let reply_to = message
+        .reply_to()
+        .map(|addrs| {
+            addrs
+                .iter()
+                .filter_map(|a| Some(Address { name: a.name().map(str::to_string), email: a.address()?.to_string() }))
+                .collect()
+        })
+        .unwrap_or_default();
+    ParentHeaders { references, list_address, reply_to }
+}
+
+/// Extract the address from a List-Post value like `<mailto:list@example.org>`.
+/// Returns None for `NO` (announce-only lists) or non-mailto URLs.
+fn parse_list_post(value: &str) -> Option<String> {
+    let start = value.find("mailto:")? + "mailto:".len();
+    let rest = &value[start..];
+    let end = rest.find(['>', '?', ',']).unwrap_or(rest.len());
+    let addr = rest[..end].trim();
+    if addr.is_empty() {
+        None
+    } else {
+        Some(addr.to_string())
+    }
+}
+
+/// Build a reply's References per RFC 5322 §3.6.4: the parent's chain
+/// followed by the parent's own Message-ID, without duplicates.
+fn reply_references(parent_refs: Vec<String>, parent_id: &str) -> Vec<String> {
+    let mut references: Vec<String> = Vec::with_capacity(parent_refs.len() + 1);
+    for id in parent_refs
+        .into_iter()
+        .chain(std::iter::once(bare_msgid(parent_id).to_string()))
+    {
+        if !id.is_empty() && !references.contains(&id) {
+            references.push(id);
         }
     }
+    references
+}
+
+/// Strip surrounding whitespace and angle brackets from a message-id.
+fn bare_msgid(id: &str) -> &str {
+    id.trim().trim_start_matches('<').trim_end_matches('>')
+}
+
+/// Format a message-id as an RFC 5322 `msg-id` (`<id>`).
+fn format_msgid(id: &str) -> String {
+    format!("<{}>", bare_msgid(id))
 }
 
 /// Format a single Address as an RFC 2822 mailbox string.
@@ -155,19 +302,76 @@ fn format_address_list(addrs: &[Address]) -> String {
         .join(", ")
 }
 
-/// Remove `from_email` from an address list (used for ReplyAll to avoid mailing yourself).
+/// Remove `from_email`, and its plus-addressed variants, from an address
+/// list (used for ReplyAll to avoid mailing yourself).
 fn remove_self(addrs: &[Address], from_email: &str) -> Vec<Address> {
+    let own = strip_plus_tag(from_email);
     addrs
         .iter()
-        .filter(|a| !a.email.eq_ignore_ascii_case(from_email))
+        .filter(|a| !strip_plus_tag(&a.email).eq_ignore_ascii_case(&own))
         .cloned()
         .collect()
 }
 
+/// Everyone a reply-all addresses: the original sender, then its To and
+/// Cc, each once and without ourselves.
+pub fn reply_all_recipients(ctx: &ComposeContext, from_email: &str) -> Vec<Address> {
+    let mut recipients: Vec<Address> = Vec::new();
+    for address in remove_self(&ctx.to, from_email).into_iter().chain(remove_self(&ctx.cc, from_email)) {
+        if !recipients.iter().any(|r| r.email.eq_ignore_ascii_case(&address.email)) {
+            recipients.push(address);
+        }
+    }
+    recipients
+}
+
+/// Whether the original's Reply-To was pointed back at its mailing list
+/// while people are among the recipients: a reply meant for one of them
+/// would go to everyone on the list.
+pub fn reply_to_munged(ctx: &ComposeContext, recipients: &[Address]) -> bool {
+    let Some(list) = &ctx.list_address else {
+        return false;
+    };
+    !ctx.reply_to.is_empty()
+        && ctx.reply_to.iter().all(|a| a.email.eq_ignore_ascii_case(list))
+        && recipients.iter().any(|a| !a.email.eq_ignore_ascii_case(list))
+}
+
+/// The tag of a plus address: `shop` for `me+shop@example.com`.
+pub fn plus_tag(email: &str) -> Option<&str> {
+    let (local, _) = email.split_once('@')?;
+    let (_, tag) = local.split_once('+')?;
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// `email` without its plus tag.
+fn strip_plus_tag(email: &str) -> String {
+    let Some((local, domain)) = email.split_once('@') else {
+        return email.to_string();
+    };
+    match local.split_once('+') {
+        Some((user, tag)) if !tag.is_empty() => format!("{}@{}", user, domain),
+        _ => email.to_string(),
+    }
+}
+
+/// The plus-addressed variant of `own` that `envelope` was sent to, if
+/// any: the address to reply from.
+pub fn plus_address(envelope: &Envelope, own: &str) -> Option<String> {
+    envelope
+        .to
+        .iter()
+        .chain(&envelope.cc)
+        .map(|a| a.email.as_str())
+        .find(|email| plus_tag(email).is_some() && strip_plus_tag(email).eq_ignore_ascii_case(own))
+        .map(String::from)
+}
+
 /// Build the content of the compose temp file: RFC 2822-style headers followed
 /// by a blank line and the body.
 pub fn build_compose_file(ctx: &ComposeContext, from_email: &str) -> Result<String> {
     let mut out = String::new();
+    let from_email = ctx.from.as_deref().unwrap_or(from_email);
 
     // From
     out.push_str(&format!("From: {}\n", from_email));
@@ -179,9 +383,7 @@ pub fn build_compose_file(ctx: &ComposeContext, from_email: &str) -> Result<Stri
         }
         ComposeKind::ReplyAll => {
             // To = original From + original To, minus ourselves
-            let mut to_addrs = ctx.to.clone();
-            to_addrs.extend(remove_self(&ctx.cc, from_email));
-            let to_addrs = remove_self(&to_addrs, from_email);
+            let to_addrs = reply_all_recipients(ctx, from_email);
             out.push_str(&format!("To: {}\n", format_address_list(&to_addrs)));
             // Cc = original Cc (if we had it, passed through ctx.cc for ReplyAll
             // is actually the original To; a future iteration may separate these)
@@ -194,6 +396,12 @@ pub fn build_compose_file(ctx: &ComposeContext, from_email: &str) -> Result<Stri
     // Cc (for ReplyAll we might have Cc addresses)
     // For now Cc is left empty in the compose buffer for the user to fill in;
     // ReplyAll merges To+Cc into the To line above.
+    if matches!(ctx.kind, ComposeKind::NewMessage | ComposeKind::Forward) && !ctx.cc.is_empty() {
+        out.push_str(&format!("Cc: {}\n", format_address_list(&ctx.cc)));
+    }
+    if !ctx.bcc.is_empty() {
+        out.push_str(&format!("Bcc: {}\n", format_address_list(&ctx.bcc)));
+    }
 
     // Subject
     out.push_str(&format!("Subject: {}\n", ctx.subject));
@@ -206,12 +414,17 @@ pub fn build_compose_file(ctx: &ComposeContext, from_email: &str) -> Result<Stri
 
     // In-Reply-To
     if let Some(ref irt) = ctx.in_reply_to {
-        out.push_str(&format!("In-Reply-To: {}\n", irt));
+        out.push_str(&format!("In-Reply-To: {}\n", format_msgid(irt)));
     }
 
     // References
     if !ctx.references.is_empty() {
-        out.push_str(&format!("References: {}\n", ctx.references.join(" ")));
+        let refs: Vec<String> = ctx.references.iter().map(|r| format_msgid(r)).collect();
+        out.push_str(&format!("References: {}\n", refs.join(" ")));
+    }
+
+    for path in &ctx.attachments {
+        out.push_str(&format!("Attach: {}\n", path.display()));
     }
 
     // Blank line separating headers from body
@@ -226,9 +439,514 @@ pub fn build_compose_file(ctx: &ComposeContext, from_email: &str) -> Result<Stri
     Ok(out)
 }
 
+/// Thresholds for the pre-send checks in [`reply_warnings`].
+#[derive(Debug, Clone, Copy)]
+pub struct SendChecks<'a> {
+    /// Warn when replying to a message older than this many days (0 = off).
+    pub old_reply_days: u32,
+    /// Warn when To+Cc+Bcc has more than this many addresses (0 = off).
+    pub max_recipients: usize,
+    /// Warn about recipients outside these domains (empty = off).
+    pub internal_domains: &'a [String],
+    /// Addresses we've corresponded with, best first; recipients a typo
+    /// away from one of them are flagged (empty = off).
+    pub known_addresses: &'a [String],
+    /// Warn when the attached files add up to more than this many MB
+    /// (0 = off).
+    pub max_attachments_mb: u64,
+}
+
+/// Contextual warnings to show before sending the edited message `content`
+/// composed from `ctx`. An empty result means nothing looks amiss.
+pub fn reply_warnings(
+    ctx: &ComposeContext,
+    content: &str,
+    now: DateTime<Utc>,
+    checks: SendChecks<'_>,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let is_reply = matches!(ctx.kind, ComposeKind::Reply | ComposeKind::ReplyAll);
+    let Some(message) = mail_parser::MessageParser::default().parse(content.as_bytes()) else {
+        return warnings;
+    };
+    // Addresses parsed properly, so a quoted "Doe, John" stays one
+    let header_emails = |names: &[&str]| -> Vec<String> {
+        names
+            .iter()
+            .flat_map(|name| message.header_values(*name))
+            .filter_map(|value| value.as_address())
+            .flat_map(|addrs| addrs.iter())
+            .filter_map(|a| a.address())
+            .map(str::to_string)
+            .collect()
+    };
+
+    if is_reply && checks.old_reply_days > 0 {
+        if let Some(date) = ctx.original_date {
+            let age = now.signed_duration_since(date).num_days();
+            if age > checks.old_reply_days as i64 {
+                warnings.push(format!("Replying to a message from {} days ago", age));
+            }
+        }
+    }
+
+    if matches!(ctx.kind, ComposeKind::Reply) {
+        if let Some(ref list) = ctx.list_address {
+            let to_list = header_emails(&["To"])
+                .iter()
+                .any(|a| a.to_lowercase().contains(&list.to_lowercase()));
+            if to_list {
+                warnings.push(format!("Plain reply is addressed to mailing list {}", list));
+            }
+        }
+    }
+
+    if checks.max_recipients > 0 {
+        let count = header_emails(&["To", "Cc", "Bcc"]).len();
+        if count > checks.max_recipients {
+            warnings.push(format!("{} recipients (more than {})", count, checks.max_recipients));
+        }
+    }
+
+    if !checks.internal_domains.is_empty() {
+        let internal = |email: &str| is_internal(email, checks.internal_domains);
+        let external: Vec<String> = header_emails(&["To", "Cc", "Bcc"])
+            .into_iter()
+            .filter(|email| !internal(email))
+            .collect();
+        if !external.is_empty() {
+            // A reply whose original recipients were all internal
+            let original: Vec<&Address> = ctx.to.iter().chain(&ctx.cc).collect();
+            let was_internal = is_reply
+                && !original.is_empty()
+                && original.iter().all(|a| internal(&a.email));
+            if was_internal {
+                warnings.push(format!("Internal thread now includes {}", external.join(", ")));
+            } else {
+                warnings.push(format!("External recipients: {}", external.join(", ")));
+            }
+        }
+    }
+
+    if !checks.known_addresses.is_empty() {
+        for email in header_emails(&["To", "Cc", "Bcc"]) {
+            if let Some(known) = near_miss(&email, checks.known_addresses) {
+                warnings.push(format!("{} \u{2014} did you mean {}?", email, known));
+            }
+        }
+    }
+
+    if checks.max_attachments_mb > 0 {
+        let total = attachments_size(content);
+        if total > checks.max_attachments_mb * 1024 * 1024 {
+            warnings.push(format!(
+                "Attachments total {} (more than {} MB)",
+                crate::mime_render::format_size(total as usize),
+                checks.max_attachments_mb
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// The files named in `content`'s `Attach:` headers, as written.
+pub fn attachment_paths(content: &str) -> Vec<String> {
+    let Ok(parsed) = crate::send::parse_composed_message(content) else {
+        return Vec::new();
+    };
+    parsed
+        .headers
+        .into_iter()
+        .filter(|(name, value)| name.eq_ignore_ascii_case("attach") && !value.is_empty())
+        .map(|(_, value)| value)
+        .collect()
+}
+
+/// Total size in bytes of the files attached to `content`. Files that
+/// can't be read count as nothing; sending reports them.
+pub fn attachments_size(content: &str) -> u64 {
+    attachment_paths(content)
+        .iter()
+        .filter_map(|path| fs::metadata(crate::config::expand_tilde(path)).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// `content` with each attachment in `links` (path as written, link)
+/// dropped from the headers and listed with its link at the end of the
+/// body instead.
+pub fn link_attachments(content: &str, links: &[(String, String)]) -> String {
+    if links.is_empty() {
+        return content.to_string();
+    }
+    let (headers, body) = content.split_once("\n\n").unwrap_or((content, ""));
+    let headers: Vec<&str> = headers
+        .lines()
+        .filter(|line| {
+            let Some((name, value)) = line.split_once(':') else {
+                return true;
+            };
+            !(name.trim().eq_ignore_ascii_case("attach") && links.iter().any(|(path, _)| path == value.trim()))
+        })
+        .collect();
+    let mut out = format!("{}\n\n{}", headers.join("\n"), body.trim_end_matches('\n'));
+    out.push_str("\n\n");
+    for (path, link) in links {
+        let name = Path::new(path).file_name().map_or(path.clone(), |n| n.to_string_lossy().into_owned());
+        out.push_str(&format!("{}: {}\n", name, link));
+    }
+    out
+}
+
+/// Upload the file at `path` with `upload_command` and return the link
+/// it prints (the last non-empty line of its output). `{path}` in the
+/// command is replaced by the quoted path; without it the path is added
+/// to the end.
+pub fn upload_attachment(upload_command: &str, path: &str) -> Result<String> {
+    let quoted = crate::config::shell_quote(&crate::config::expand_tilde(path));
+    let command = if upload_command.contains("{path}") {
+        upload_command.replace("{path}", &quoted)
+    } else {
+        format!("{} {}", upload_command, quoted)
+    };
+    let output = Command::new("sh")
+        .args(["-c", &command])
+        .output()
+        .with_context(|| format!("failed to run upload_command for {}", path))?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("upload_command failed for {}: {}", path, err.trim());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(String::from)
+        .with_context(|| format!("upload_command printed no link for {}", path))
+}
+
+/// The known address `email` looks like a typo of: not itself known, but
+/// within a couple of edits of one that is. The first (best-ranked) of the
+/// closest matches wins.
+fn near_miss<'a>(email: &str, known: &'a [String]) -> Option<&'a str> {
+    let email = email.to_lowercase();
+    if known.iter().any(|k| k.eq_ignore_ascii_case(&email)) {
+        return None;
+    }
+    // One edit in short addresses, two from 12 characters up
+    let max = (email.chars().count() / 6).clamp(1, 2);
+    let mut best: Option<(usize, &str)> = None;
+    for k in known {
+        let d = edit_distance(&email, &k.to_lowercase());
+        if d <= max && best.is_none_or(|(b, _)| d < b) {
+            best = Some((d, k));
+        }
+    }
+    best.map(|(_, k)| k)
+}
+
+/// Levenshtein distance between two strings, by characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            row.push(substitute.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Whether `email` is in one of `domains` or a subdomain of one.
+fn is_internal(email: &str, domains: &[String]) -> bool {
+    let Some((_, domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+    let domain = domain.to_lowercase();
+    domains.iter().any(|d| {
+        let d = d.trim_start_matches('@').to_lowercase();
+        domain == d || domain.strip_suffix(&d).is_some_and(|rest| rest.ends_with('.'))
+    })
+}
+
+/// Wrap body lines of a composed message longer than `width` columns at
+/// word boundaries, so long lines typed in the editor go out as wrapped
+/// mail. Headers, quoted (`>`) and indented lines, and everything from the
+/// `-- ` signature separator on are left alone, as are the line breaks
+/// already in the text. List items wrap with a hanging indent. A `width`
+/// of 0 returns `content` unchanged.
+pub fn wrap_body(content: &str, width: usize) -> String {
+    if width == 0 {
+        return content.to_string();
+    }
+    let Some((headers, body)) = content.split_once("\n\n") else {
+        return content.to_string();
+    };
+    let mut out = format!("{}\n\n", headers);
+    let mut in_signature = false;
+    for (i, line) in body.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        in_signature |= line == "-- ";
+        let keep = in_signature
+            || line.starts_with('>')
+            || line.starts_with([' ', '\t'])
+            || line.width() <= width;
+        if keep {
+            out.push_str(line);
+        } else {
+            out.push_str(&wrap_line(line, width));
+        }
+    }
+    out
+}
+
+/// Break one line into lines of at most `width` columns. A word wider
+/// than that gets a line of its own.
+fn wrap_line(line: &str, width: usize) -> String {
+    let indent = " ".repeat(list_marker_width(line));
+    let mut out = String::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+    let mut has_words = false;
+    for word in line.split(' ').filter(|w| !w.is_empty()) {
+        let word_width = word.width();
+        if has_words && current_width + 1 + word_width > width {
+            out.push_str(&current);
+            out.push('\n');
+            current = indent.clone();
+            current_width = indent.len();
+            has_words = false;
+        }
+        if has_words {
+            current.push(' ');
+            current_width += 1;
+        }
+        current.push_str(word);
+        current_width += word_width;
+        has_words = true;
+    }
+    out.push_str(&current);
+    out
+}
+
+/// Width of a list marker (`- `, `* `, `+ `, `1. `, `2) `) starting `line`,
+/// or 0 if it doesn't start a list item.
+fn list_marker_width(line: &str) -> usize {
+    let Some((marker, _)) = line.split_once(' ') else {
+        return 0;
+    };
+    let numbered = marker
+        .strip_suffix(['.', ')'])
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    if matches!(marker, "-" | "*" | "+") || numbered {
+        marker.len() + 1
+    } else {
+        0
+    }
+}
+
+/// The editor command to run: the configured one, or `$VISUAL` then
+/// `$EDITOR` when the config leaves it unset or empty, falling back to
+/// `nvim` when neither is set.
+pub fn resolve_editor(configured: &str) -> String {
+    resolve_editor_from(
+        configured,
+        std::env::var("VISUAL").ok().as_deref(),
+        std::env::var("EDITOR").ok().as_deref(),
+    )
+}
+
+fn resolve_editor_from(configured: &str, visual: Option<&str>, editor: Option<&str>) -> String {
+    [Some(configured), visual, editor]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+        .unwrap_or("nvim")
+        .to_string()
+}
+
+/// Split a command line into arguments, shell-style: whitespace separates
+/// arguments, single quotes are literal, double quotes allow `\"` and `\\`
+/// escapes, and a backslash outside quotes escapes the next character.
+pub fn split_command_line(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!("unterminated single quote in: {}", line),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => anyhow::bail!("unterminated double quote in: {}", line),
+                        },
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!("unterminated double quote in: {}", line),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                if let Some(c) = chars.next() {
+                    current.push(c);
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Build the editor's argv. `{file}` in any argument is replaced with the
+/// file path; without a placeholder the path is appended as the last
+/// argument.
+fn editor_argv(editor: &str, file_path: &Path) -> Result<Vec<String>> {
+    let file = file_path.to_string_lossy();
+    let mut args = split_command_line(editor)?;
+    anyhow::ensure!(!args.is_empty(), "editor command is empty");
+    if args.iter().any(|a| a.contains("{file}")) {
+        for arg in &mut args {
+            *arg = arg.replace("{file}", &file);
+        }
+    } else {
+        args.push(file.into_owned());
+    }
+    Ok(args)
+}
+
+/// Directory for compose temp files: `compose_dir` from the config, or
+/// `$XDG_RUNTIME_DIR/hutt`, falling back to a per-user directory in the
+/// system temp dir.
+pub fn compose_dir(configured: Option<&str>) -> PathBuf {
+    if let Some(dir) = configured {
+        PathBuf::from(crate::config::expand_tilde(dir))
+    } else if let Ok(dir) = std::env::var("XDG_RUNTIME_DIR") {
+        PathBuf::from(dir).join("hutt")
+    } else {
+        let uid = unsafe { libc::getuid() };
+        std::env::temp_dir().join(format!("hutt-{}", uid))
+    }
+}
+
+/// Write a compose buffer to `hutt-compose-<pid>.eml` in `dir`, readable
+/// only by the current user. The directory is created mode 0700. One that
+/// already exists must be ours and private (an existing one of ours is
+/// tightened): in a shared temp dir, another user could have made it.
+pub fn create_compose_file(dir: &Path, content: &str) -> Result<PathBuf> {
+    use std::io::Write;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let private = || -> Result<()> {
+        // Not followed, so a symlink planted in its place is refused
+        let meta = fs::symlink_metadata(dir)?;
+        if !meta.file_type().is_dir() {
+            bail!("not a directory");
+        }
+        if meta.uid() != unsafe { libc::getuid() } {
+            bail!("owned by another user");
+        }
+        if meta.mode() & 0o077 != 0 {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(())
+    };
+    private().with_context(|| format!("refusing to write drafts to {}", dir.display()))?;
+
+    let path = dir.join(format!("hutt-compose-{}.eml", std::process::id()));
+    // A leftover file from an earlier compose in this process is replaced;
+    // removing it first means the new one is always created with 0600.
+    let _ = fs::remove_file(&path);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    file.write_all(content.as_bytes())?;
+    Ok(path)
+}
+
+/// Remove compose temp files left in `dir` by hutt processes that are no
+/// longer running (e.g. after a crash). Returns how many were removed.
+pub fn sweep_stale_compose_files(dir: &Path) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(pid) = name
+            .strip_prefix("hutt-compose-")
+            .and_then(|rest| rest.strip_suffix(".eml"))
+            .and_then(|pid| pid.parse::<i32>().ok())
+        else {
+            continue;
+        };
+        if pid != std::process::id() as i32 && !process_alive(pid)
+            && fs::remove_file(entry.path()).is_ok()
+        {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Whether a process with this pid exists.
+fn process_alive(pid: i32) -> bool {
+    // Signal 0 checks for existence; EPERM means it exists but isn't ours.
+    let ret = unsafe { libc::kill(pid, 0) };
+    ret == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
 /// Launch an external editor on the given file path, blocking until the editor
 /// exits. Returns `true` if the file was modified (mtime changed).
 ///
+/// The editor string is resolved with [`resolve_editor`] and split with
+/// [`split_command_line`]; see [`editor_argv`] for the `{file}` placeholder.
+///
 /// `env_vars` are set only on the child process, not the hutt process
 /// (avoids thread-safety issues with `std::env::set_var`).
 pub fn launch_editor(
@@ -240,13 +958,14 @@ pub fn launch_editor(
         .and_then(|m| m.modified())
         .unwrap_or(SystemTime::UNIX_EPOCH);
 
-    let parts: Vec<&str> = editor.split_whitespace().collect();
-    let (cmd, args) = parts
+    let editor = resolve_editor(editor);
+    let argv = editor_argv(&editor, file_path)?;
+    let (cmd, args) = argv
         .split_first()
         .context("editor command is empty")?;
 
     let mut command = Command::new(cmd);
-    command.args(args).arg(file_path);
+    command.args(args);
     for (k, v) in env_vars {
         command.env(k, v);
     }
@@ -265,11 +984,89 @@ pub fn launch_editor(
     Ok(mtime_after != mtime_before)
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn wrap_body_long_lines() {
+        let content = "To: bob@example.com\nSubject: A very long subject line that must not be wrapped at all\n\n\
+            The quick brown fox jumps over the lazy dog again and again.\n\
+            Short line\n\
+            > quoted text that is long enough to need wrapping but stays as it is\n\
+            - a list item long enough that it wraps onto a second line\n\
+            12. numbered item that also goes on for a bit too long\n\
+            \x20   indented code that runs past the wrap column untouched\n\
+            see https://example.com/a/very/long/url/that/does/not/fit/anywhere\n\
+            -- \n\
+            A signature line that is longer than the wrap width is left alone";
+        let wrapped = wrap_body(content, 30);
+        let expected = "To: bob@example.com\nSubject: A very long subject line that must not be wrapped at all\n\n\
+            The quick brown fox jumps over\n\
+            the lazy dog again and again.\n\
+            Short line\n\
+            > quoted text that is long enough to need wrapping but stays as it is\n\
+            - a list item long enough that\n  it wraps onto a second line\n\
+            12. numbered item that also\n    goes on for a bit too long\n\
+            \x20   indented code that runs past the wrap column untouched\n\
+            see\nhttps://example.com/a/very/long/url/that/does/not/fit/anywhere\n\
+            -- \n\
+            A signature line that is longer than the wrap width is left alone";
+        assert_eq!(wrapped, expected);
+        assert_eq!(wrap_body(content, 0), content);
+    }
+
+    #[test]
+    fn test_build_from_request() {
+        let req: ComposeRequest = serde_json::from_str(
+            r#"{"to": ["Bob <bob@example.com>"], "cc": ["carol@example.com"],
+                "bcc": ["me@example.com"], "subject": "Trip",
+                "body": "See attached.", "attachments": ["/tmp/itinerary.pdf"]}"#,
+        )
+        .unwrap();
+        let content = build_compose_file(&ComposeContext::from_request(&req), "me@example.com").unwrap();
+        assert!(content.contains("To: Bob <bob@example.com>\n"));
+        assert!(content.contains("Cc: carol@example.com\n"));
+        assert!(content.contains("Bcc: me@example.com\n"));
+        assert!(content.contains("Subject: Trip\n"));
+        assert!(content.contains("Attach: /tmp/itinerary.pdf\n\nSee attached.\n"));
+
+        // Unknown fields are an error, not silently dropped
+        assert!(serde_json::from_str::<ComposeRequest>(r#"{"too": ["x@y"]}"#).is_err());
+    }
+
+    #[test]
+    fn compose_file_is_private_and_stale_ones_are_swept() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir()
+            .join(format!("hutt-compose-test-{}", std::process::id()))
+            .join("hutt");
+        let path = create_compose_file(&dir, "Subject: hi\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Subject: hi\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+
+        // pid_max is at most 2^22, so this pid can't be running
+        let stale = dir.join("hutt-compose-99999999.eml");
+        fs::write(&stale, "old draft").unwrap();
+        fs::write(dir.join("notes.txt"), "keep").unwrap();
+        assert_eq!(sweep_stale_compose_files(&dir), 1);
+        assert!(!stale.exists());
+        // Our own file and unrelated files are left alone
+        assert!(path.exists());
+        assert!(dir.join("notes.txt").exists());
+
+        // Opened up, it's made private again; a symlink to it is refused
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        create_compose_file(&dir, "Subject: hi\n").unwrap();
+        assert_eq!(fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+        let link = dir.with_file_name("planted");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        assert!(create_compose_file(&link, "Subject: hi\n").is_err());
+
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
     #[test]
     fn test_build_new_message() {
         let ctx = ComposeContext::new_message();
@@ -293,10 +1090,16 @@ mod tests {
                 name: None,
                 email: "user@example.com".to_string(),
             }],
+            cc: vec![],
+            bcc: vec![],
             date: Utc::now(),
             flags: vec![],
             maildir: "/Inbox".to_string(),
             path: std::path::PathBuf::from("/tmp/test"),
+            size: 0,
+            list: None,
+            priority: crate::envelope::Priority::Normal,
+            tags: vec![],
             thread_meta: crate::envelope::ThreadMeta::default(),
         };
 
@@ -306,10 +1109,244 @@ mod tests {
         assert!(content.contains("To: Alice <alice@example.com>"));
         assert!(content.contains("Subject: Re: Hello"));
         assert!(content.contains("In-Reply-To: <abc@example.com>"));
+        assert!(content.contains("References: <abc@example.com>"));
         assert!(content.contains("> Hello world"));
         assert!(content.contains("> How are you?"));
     }
 
+    #[test]
+    fn test_reply_from_plus_address() {
+        let addr = |email: &str| Address { name: None, email: email.to_string() };
+        let envelope = Envelope {
+            message_id: "<order@shop.example>".to_string(),
+            subject: "Your order".to_string(),
+            from: vec![addr("orders@shop.example")],
+            to: vec![addr("Me+Shop@example.com"), addr("other+x@example.com")],
+            cc: vec![addr("me@example.com")],
+            ..Envelope::default()
+        };
+        assert_eq!(plus_tag("me+shop@example.com"), Some("shop"));
+        assert_eq!(plus_tag("me+@example.com"), None);
+        assert_eq!(plus_tag("me@exa+mple.com"), None);
+        assert_eq!(plus_address(&envelope, "me@example.com").as_deref(), Some("Me+Shop@example.com"));
+        assert_eq!(plus_address(&envelope, "you@example.com"), None);
+
+        let mut ctx = ComposeContext::reply(&envelope, "", true);
+        ctx.from = plus_address(&envelope, "me@example.com");
+        let content = build_compose_file(&ctx, "me@example.com").unwrap();
+        assert!(content.starts_with("From: Me+Shop@example.com\n"));
+        // Neither our plain nor our plussed address is replied to
+        assert!(content.contains("To: orders@shop.example, other+x@example.com\n"));
+    }
+
+    #[test]
+    fn test_reply_references_chain() {
+        let dir = std::env::temp_dir().join(format!("hutt-test-refs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("parent.eml");
+        fs::write(
+            &path,
+            "From: alice@example.com\r\n\
+             Message-ID: <c@example.com>\r\n\
+             In-Reply-To: <b@example.com>\r\n\
+             References: <a@example.com>\r\n <b@example.com>\r\n\
+             Subject: Re: Hello\r\n\
+             \r\n\
+             Body\r\n",
+        )
+        .unwrap();
+
+        let parent = read_parent_headers(&path);
+        let refs = reply_references(parent.references, "c@example.com");
+        assert_eq!(refs, vec!["a@example.com", "b@example.com", "c@example.com"]);
+        assert_eq!(parent.list_address, None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reply_references_dedup_and_brackets() {
+        let refs = reply_references(
+            vec!["a@example.com".to_string(), "b@example.com".to_string()],
+            "<b@example.com>",
+        );
+        assert_eq!(refs, vec!["a@example.com", "b@example.com"]);
+
+        // Unreadable parent: just the parent's id
+        let refs = reply_references(read_parent_headers(Path::new("/nonexistent")).references, "x@y");
+        assert_eq!(refs, vec!["x@y"]);
+        assert_eq!(format_msgid("x@y"), "<x@y>");
+        assert_eq!(format_msgid("<x@y>"), "<x@y>");
+    }
+
+    #[test]
+    fn test_parse_list_post() {
+        assert_eq!(
+            parse_list_post("<mailto:dev@lists.example.org>"),
+            Some("dev@lists.example.org".to_string())
+        );
+        assert_eq!(
+            parse_list_post("<mailto:dev@lists.example.org?subject=help>"),
+            Some("dev@lists.example.org".to_string())
+        );
+        assert_eq!(parse_list_post("NO"), None);
+    }
+
+    #[test]
+    fn test_reply_all_recipients_and_munged_reply_to() {
+        let dir = std::env::temp_dir().join(format!("hutt-test-replyto-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("msg");
+        fs::write(
+            &path,
+            "From: Alice <alice@x.org>\nTo: dev@lists.example.org, me@example.com\nCc: Bob <BOB@x.org>, bob@x.org\n\
+             Reply-To: dev@lists.example.org\nList-Post: <mailto:dev@lists.example.org>\nSubject: hi\n\nbody\n",
+        )
+        .unwrap();
+        let addr = |email: &str| Address { name: None, email: email.to_string() };
+        let envelope = Envelope {
+            path: path.clone(),
+            from: vec![addr("alice@x.org")],
+            to: vec![addr("dev@lists.example.org"), addr("me@example.com")],
+            cc: vec![addr("BOB@x.org"), addr("bob@x.org")],
+            ..Envelope::default()
+        };
+        let ctx = ComposeContext::reply(&envelope, "", true);
+        let recipients = reply_all_recipients(&ctx, "me@example.com");
+        let emails: Vec<&str> = recipients.iter().map(|a| a.email.as_str()).collect();
+        assert_eq!(emails, ["alice@x.org", "dev@lists.example.org", "BOB@x.org"]);
+        assert!(reply_to_munged(&ctx, &recipients));
+        assert!(!reply_to_munged(&ctx, &[addr("dev@lists.example.org")]));
+
+        fs::write(&path, "From: alice@x.org\nList-Post: <mailto:dev@lists.example.org>\nSubject: hi\n\nbody\n").unwrap();
+        let ctx = ComposeContext::reply(&envelope, "", true);
+        assert!(!reply_to_munged(&ctx, &recipients));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reply_warnings() {
+        let now = Utc::now();
+        let mut ctx = ComposeContext::new_message();
+        ctx.kind = ComposeKind::Reply;
+        ctx.original_date = Some(now - chrono::Duration::days(90));
+        ctx.list_address = Some("dev@lists.example.org".to_string());
+        let checks = SendChecks { old_reply_days: 30, max_recipients: 2, internal_domains: &[], known_addresses: &[], max_attachments_mb: 0 };
+
+        let content = "From: me@example.com\n\
+                       To: Dev List <dev@lists.example.org>, a@x.org\n\
+                       Cc: b@x.org\n\
+                       Subject: Re: hi\n\n\
+                       body\n";
+        let warnings = reply_warnings(&ctx, content, now, checks);
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[0].contains("90 days"));
+        assert!(warnings[1].contains("dev@lists.example.org"));
+        assert!(warnings[2].contains("3 recipients"));
+
+        // Reply-all to the list is deliberate; recent message, few recipients
+        ctx.kind = ComposeKind::ReplyAll;
+        ctx.original_date = Some(now);
+        let checks = SendChecks { old_reply_days: 30, max_recipients: 10, internal_domains: &[], known_addresses: &[], max_attachments_mb: 0 };
+        assert!(reply_warnings(&ctx, content, now, checks).is_empty());
+
+        // A comma inside a quoted name doesn't make two recipients
+        let checks = SendChecks { old_reply_days: 0, max_recipients: 2, internal_domains: &[], known_addresses: &[], max_attachments_mb: 0 };
+        let quoted = "To: \"Doe, John\" <j@x.org>, \"Roe, Jane\" <jr@x.org>\nSubject: hi\n\nbody\n";
+        assert!(reply_warnings(&ctx, quoted, now, checks).is_empty());
+    }
+
+    #[test]
+    fn test_external_recipient_warnings() {
+        let now = Utc::now();
+        let domains = vec!["example.com".to_string()];
+        let checks = SendChecks {
+            old_reply_days: 0,
+            max_recipients: 0,
+            internal_domains: &domains,
+            known_addresses: &[],
+            max_attachments_mb: 0,
+        };
+        let addr = |email: &str| Address { name: None, email: email.to_string() };
+        let mut ctx = ComposeContext::new_message();
+
+        let internal_only = "To: Bob <bob@example.com>\nCc: ops@eu.example.com\nSubject: hi\n\nbody\n";
+        assert!(reply_warnings(&ctx, internal_only, now, checks).is_empty());
+
+        let content = "To: Bob <bob@example.com>, Eve <eve@example.com.evil.net>\n\
+                       Bcc: carol@other.org\nSubject: hi\n\nbody\n";
+        assert_eq!(
+            reply_warnings(&ctx, content, now, checks),
+            vec!["External recipients: eve@example.com.evil.net, carol@other.org"]
+        );
+
+        // Replying to an all-internal thread
+        ctx.kind = ComposeKind::ReplyAll;
+        ctx.to = vec![addr("bob@example.com")];
+        ctx.cc = vec![addr("ops@eu.example.com")];
+        let warnings = reply_warnings(&ctx, content, now, checks);
+        assert!(warnings[0].starts_with("Internal thread now includes"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_large_attachments_warned_and_linked() {
+        let dir = std::env::temp_dir().join(format!("hutt-upload-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let video = dir.join("talk.mp4");
+        fs::File::create(&video).unwrap().set_len(3 * 1024 * 1024).unwrap();
+        let video = video.display().to_string();
+        let content = format!("From: me@example.com\nTo: a@x.org\nAttach: {}\nAttach: /no/such/file\nSubject: Talk\n\nHere it is.\n", video);
+
+        let ctx = ComposeContext::new_message();
+        let checks = |mb| SendChecks { old_reply_days: 0, max_recipients: 0, internal_domains: &[], known_addresses: &[], max_attachments_mb: mb };
+        assert_eq!(attachment_paths(&content), [video.as_str(), "/no/such/file"]);
+        assert_eq!(
+            reply_warnings(&ctx, &content, Utc::now(), checks(2)),
+            ["Attachments total 3.0 MB (more than 2 MB)"]
+        );
+        assert!(reply_warnings(&ctx, &content, Utc::now(), checks(5)).is_empty());
+
+        let link = upload_attachment("printf 'uploading\\n\\nhttps://files.example.com/%s\\n' \"$(basename {path})\"", &video).unwrap();
+        assert_eq!(link, "https://files.example.com/talk.mp4");
+        assert!(upload_attachment("false", &video).is_err());
+
+        let linked = link_attachments(&content, &[(video.clone(), link)]);
+        assert_eq!(
+            linked,
+            "From: me@example.com\nTo: a@x.org\nAttach: /no/such/file\nSubject: Talk\n\nHere it is.\n\ntalk.mp4: https://files.example.com/talk.mp4\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_misspelled_recipient_warnings() {
+        let now = Utc::now();
+        let known = vec![
+            "alice@example.com".to_string(),
+            "alicia@example.com".to_string(),
+            "bo@x.io".to_string(),
+        ];
+        let checks = SendChecks {
+            old_reply_days: 0,
+            max_recipients: 0,
+            internal_domains: &[],
+            known_addresses: &known,
+            max_attachments_mb: 0,
+        };
+        let ctx = ComposeContext::new_message();
+
+        let content = "To: Alice <alice@exampel.com>, alicia@example.com\n\
+                       Cc: carol@elsewhere.org, bo@x.co\nSubject: hi\n\nbody\n";
+        assert_eq!(
+            reply_warnings(&ctx, content, now, checks),
+            vec![
+                "alice@exampel.com \u{2014} did you mean alice@example.com?",
+                "bo@x.co \u{2014} did you mean bo@x.io?",
+            ]
+        );
+        assert!(reply_warnings(&ctx, "To: ALICE@example.com\n\nbody\n", now, checks).is_empty());
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
     #[test]
     fn test_build_forward() {
         let envelope = Envelope {
@@ -324,10 +1361,16 @@ mod tests {
                 name: None,
                 email: "user@example.com".to_string(),
             }],
+            cc: vec![],
+            bcc: vec![],
             date: Utc::now(),
             flags: vec![],
             maildir: "/Inbox".to_string(),
             path: std::path::PathBuf::from("/tmp/test"),
+            size: 0,
+            list: None,
+            priority: crate::envelope::Priority::Normal,
+            tags: vec![],
             thread_meta: crate::envelope::ThreadMeta::default(),
         };
 
@@ -353,4 +1396,41 @@ mod tests {
         };
         assert_eq!(format_address(&bare), "bare@example.com");
     }
+
+    #[test]
+    fn editor_resolution_falls_back_to_env() {
+        assert_eq!(resolve_editor_from("emacs -nw", Some("code"), None), "emacs -nw");
+        assert_eq!(resolve_editor_from("", Some("code --wait"), Some("vim")), "code --wait");
+        assert_eq!(resolve_editor_from("  ", Some(""), Some("vim")), "vim");
+        assert_eq!(resolve_editor_from("", None, None), "nvim");
+    }
+
+    #[test]
+    fn command_line_splitting() {
+        assert_eq!(
+            split_command_line(r#"nvim -c 'set tw=72' "+normal G" a\ b"#).unwrap(),
+            vec!["nvim", "-c", "set tw=72", "+normal G", "a b"]
+        );
+        assert_eq!(split_command_line(r#"ed "say \"hi\"" ''"#).unwrap(), vec!["ed", "say \"hi\"", ""]);
+        assert!(split_command_line("nvim 'oops").is_err());
+        assert!(split_command_line("   ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn editor_argv_placeholder() {
+        let path = Path::new("/tmp/hutt-compose.eml");
+        assert_eq!(
+            editor_argv("nvim +", path).unwrap(),
+            vec!["nvim", "+", "/tmp/hutt-compose.eml"]
+        );
+        assert_eq!(
+            editor_argv("kitty --title 'hutt compose' nvim {file}", path).unwrap(),
+            vec!["kitty", "--title", "hutt compose", "nvim", "/tmp/hutt-compose.eml"]
+        );
+        assert_eq!(
+            editor_argv("emacsclient --eval '(find-file \"{file}\")'", path).unwrap(),
+            vec!["emacsclient", "--eval", "(find-file \"/tmp/hutt-compose.eml\")"]
+        );
+        assert!(editor_argv("", path).is_err());
+    }
 }
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 // ---------------------------------------------------------------------------
 // Top-level config
@@ -13,8 +13,47 @@ pub struct Config {
     pub accounts: Vec<AccountConfig>,
     pub editor: String,
     pub sync_command: Option<String>,
+    /// Sync command of the headers-only profile, for metered connections:
+    /// fetches new messages without their bodies. `{account}` and
+    /// `{maildir}` as in sync_command.
+    pub headers_sync_command: Option<String>,
+    /// Start with the headers-only sync profile on. Toggle with
+    /// "Headers-Only Sync".
+    pub headers_only: bool,
+    /// Shell command fetching the body of one message synced headers only,
+    /// run when it is previewed. `{message_id}` and `{path}` are replaced
+    /// with the quoted message-id and file path.
+    pub fetch_body_command: Option<String>,
     /// Directory to save attachments to. Default: ~/Downloads.
     pub download_dir: Option<String>,
+    /// Shell command "Apply Patch" runs on patch emails, e.g.
+    /// `git -C ~/src/project am -3`. `{file}` places the message files;
+    /// without it they are appended.
+    pub patch_command: Option<String>,
+    /// Shell command "Pipe Thread" feeds the selected thread to as an
+    /// mbox on stdin, e.g. `b4 am -m - -o ~/patches`. Its output is shown
+    /// in a popup.
+    pub pipe_thread_command: Option<String>,
+    /// Shell command "Summarize Thread" feeds the selected thread to as
+    /// plain text on stdin. Its output is shown in a popup and kept for
+    /// the session until the thread changes.
+    pub summarize_command: Option<String>,
+    /// Shell bindings show the command they would run, with its
+    /// environment, instead of running it. Toggle with "Shell Dry Run".
+    pub shell_dry_run: bool,
+    /// Directory for compose temp files. Default: $XDG_RUNTIME_DIR/hutt,
+    /// or a per-user directory under the system temp dir.
+    pub compose_dir: Option<String>,
+    /// Also read newline-delimited JSON IPC commands from this FIFO, or
+    /// from stdin if "-". For places where the Unix socket is awkward.
+    pub ipc_pipe: Option<String>,
+    /// Running on a remote machine over SSH: links and attachments are
+    /// copied to the local clipboard (via the terminal) rather than opened
+    /// on the server. Default: detected from SSH_CONNECTION.
+    pub remote: Option<bool>,
+    /// JSON file with unread counts and sync state for status bars to
+    /// poll. Default: ~/.local/state/hutt/status.json
+    pub status_file: Option<String>,
     /// Auto-sync interval in minutes (decimals accepted).
     /// When set, hutt will run sync_command periodically while idle.
     pub check_mail_every: Option<f64>,
@@ -32,30 +71,440 @@ pub struct Config {
     /// Default: true
     #[serde(default = "default_true")]
     pub background_servers: bool,
+    /// Check mu's version, the maildirs, the SMTP settings and the clock
+    /// at startup, and show any problems in a banner.
+    /// Default: true
+    #[serde(default = "default_true")]
+    pub health_check: bool,
     /// Use vi-style editing in the search bar and other input fields.
     /// Default: false
     #[serde(default)]
     pub vim_mode: bool,
+    /// Warn before sending a reply to a message older than this many days.
+    /// 0 disables the check. Default: 30
+    pub warn_reply_older_than_days: u32,
+    /// Warn before sending when To+Cc+Bcc exceed this many addresses.
+    /// 0 disables the check. Default: 15
+    pub warn_recipients_over: usize,
+    /// Before the editor opens, list a reply-all's recipients to check
+    /// when there are more than this many. 0 disables the count (a list
+    /// pointing Reply-To back at itself is still checked). Default: 10
+    pub reply_all_check_over: usize,
+    /// Warn before sending when the attached files add up to more than
+    /// this many MB. 0 disables the check. Default: 25
+    pub warn_attachments_over_mb: u64,
+    /// Shell command that uploads a file and prints a link to it, offered
+    /// when attachments are too large: the attachment is replaced by the
+    /// link in the body. `{path}` is the file; without it the path is
+    /// added to the end. Default: none
+    pub upload_command: Option<String>,
+    /// Wrap body lines longer than this many columns when sending.
+    /// 0 sends lines as written. Default: 0
+    pub compose_wrap: usize,
+    /// Extra mu query the needs-reply filter adds to "NOT flag:replied",
+    /// e.g. "to:{email} AND NOT flag:list". `{email}` is the account's
+    /// address. Default: none, so every unreplied message matches.
+    pub needs_reply_query: Option<String>,
+    /// Header holding a numeric score for each message, e.g.
+    /// "X-Spam-Score" or "X-Spam-Status" (read after "score="). Shown in a
+    /// score column and usable as a sort field. Default: none
+    pub score_header: Option<String>,
+    /// Shell command scoring a message for the score column: it gets the
+    /// raw message on stdin and prints a number. Used for messages without
+    /// `score_header`. Default: none
+    pub score_command: Option<String>,
+    /// Lowest score the score filter keeps. Default: 0
+    pub score_threshold: f64,
+    /// How many days back "Recently Deleted" looks in the operations log
+    /// for trashed and removed messages. Default: 30
+    pub recently_deleted_days: u32,
+    /// Most messages a folder or search lists. When more match, the top
+    /// bar shows how many were left out and offers to load them all.
+    /// Raised to 1000 if set lower. Default: 10000
+    pub max_results: u32,
+    /// Built-in smart folders to show in the picker and tab cycle.
+    /// Any of "Starred", "Unread", "Today", "Large", "Waiting", "Screened".
+    /// Set to [] to disable them all.
+    pub builtin_smart_folders: Vec<String>,
+    /// Smart folders shown for every account (`[[smart_folders]]`). An
+    /// account's own smart folder with the same name replaces the shared one.
+    pub smart_folders: Vec<crate::smart_folders::SmartFolder>,
+    /// Show the inbox in sections on launch. Toggle with "Inbox Sections".
+    pub sectioned_inbox: bool,
+    /// Sections of the sectioned inbox (`[[inbox_sections]]`), in order.
+    /// Default: Primary (everything else), Lists, Notifications.
+    pub inbox_sections: Vec<crate::inbox_sections::InboxSection>,
+    /// Envelope list row layout: "compact" or "relaxed". Toggle with D.
+    pub list_density: ListDensity,
+    /// Link syntax used by copy_message_link: "markdown" or "org".
+    pub link_format: LinkFormat,
+    /// Tint each sender's name with a stable color derived from their address.
+    pub color_senders: bool,
+    /// In the inbox, turn the date of an unanswered message amber once it
+    /// is more than this many days old. 0 disables it. Default: 3
+    pub age_amber_days: u32,
+    /// ...and red once it is more than this many days old. 0 disables it.
+    /// Default: 7
+    pub age_red_days: u32,
+    /// Names to show for addresses, keyed by address or by `@domain` for
+    /// everyone there, in place of the names their mail gives. A name in
+    /// the address book wins. Default: none
+    pub display_names: HashMap<String, String>,
+    /// Show a colored initials badge next to the sender in the preview
+    /// header and thread view.
+    pub sender_badges: bool,
+    /// Show a status message when follow-up reminders become overdue.
+    pub notify_followups: bool,
+    /// Time of day ("HH:MM") to show a summary of the day's triage in the
+    /// status bar, e.g. "Inbox zero! 84 archived, 12 replies".
+    pub triage_summary: Option<String>,
+    /// Reopen the last session's view tabs, folders, filters and selected
+    /// messages on launch (unless a folder is given on the command line).
+    pub restore_session: bool,
+    /// Save each folder's sort order and filters with the session, so they
+    /// survive restarts and not just folder switches.
+    pub persist_folder_settings: bool,
+    /// Save how far each long message and thread was scrolled with the
+    /// session, not just for the session.
+    pub persist_read_positions: bool,
+    /// What a second `hutt` does when one is already running: "allow"
+    /// another instance, "forward" its folder to the running one, or "ask".
+    pub single_instance: SingleInstance,
+    /// When to save battery by slowing the idle poll and stopping snippet
+    /// extraction, background counts and auto-sync: "auto" on battery,
+    /// "on" always, "off" never.
+    pub low_power: LowPower,
+    /// Encrypt state files (reminders, operations log, sessions, snippets)
+    /// at rest by piping them through these commands.
+    pub state_encryption: Option<StateEncryption>,
+    /// Localhost HTTP API mirroring the IPC commands (`[http_api]`).
+    pub http_api: Option<HttpApi>,
+    /// Glyphs and colors for the envelope list's flag column.
+    pub flag_glyphs: FlagGlyphs,
+    /// Folders visited by Tab/Shift+Tab, in order. Same wildcards as the
+    /// per-account `tabs`. Default: cycle through the tab bar.
+    pub cycle_folders: Option<Vec<String>>,
+    /// Folder, `#split`, `@smart` folder or search query to open on launch
+    /// and account switch. Default: the account's inbox.
+    #[serde(alias = "startup_query")]
+    pub startup_folder: Option<String>,
+    /// Folders pinned at the top of the folder and move-to pickers. Can
+    /// also be set per-account.
+    pub favorite_folders: Option<Vec<String>>,
+    /// How many recently visited or moved-to folders the pickers list
+    /// after the favorites. 0 disables the section. Default: 5
+    pub recent_folders: usize,
+    /// Maximum line length for message bodies in the preview and thread
+    /// view; wider panes leave the rest blank. Default: no limit.
+    pub preview_max_width: Option<u16>,
+    /// Messages over this many megabytes show a placeholder in the preview
+    /// and thread view until asked to render them. 0 renders everything.
+    /// Default: 10
+    pub preview_max_size_mb: u64,
+    /// Which headers each thread view message shows.
+    pub thread_view: ThreadViewConfig,
+    /// How long to wait on a silent mu server before restarting it.
+    pub mu_timeouts: MuTimeouts,
+    /// How often background counts (status file unread, smart folder
+    /// badges) may query mu.
+    pub counts: Counts,
+    /// Patterns hidden by "Copy Redacted Message" besides email addresses
+    /// (`[[redact]]`), e.g. ticket numbers and API tokens.
+    pub redact: Vec<crate::redact::RedactRule>,
+    /// Per-machine overrides (`[profiles.<name>]`), picked by
+    /// `HUTT_PROFILE` or the hostname.
+    pub profiles: BTreeMap<String, Profile>,
+    /// The profile applied when the config was loaded.
+    #[serde(skip)]
+    pub active_profile: Option<String>,
 }
 
-fn default_true() -> bool {
-    true
+/// `[profiles.<name>]` section: settings for one machine, so one config
+/// file works on all of them. Used when `HUTT_PROFILE` names it, or else
+/// when the hostname is in `hosts`.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Profile {
+    /// Hostnames (full or up to the first dot) the profile applies on.
+    pub hosts: Vec<String>,
+    pub editor: Option<String>,
+    pub sync_command: Option<String>,
+    pub headers_sync_command: Option<String>,
+    /// Overrides for accounts, by account name
+    /// (`[profiles.<name>.accounts.<account>]`).
+    pub accounts: HashMap<String, ProfileAccount>,
+}
+
+/// Per-account overrides in a profile.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ProfileAccount {
+    pub maildir: Option<String>,
+    pub muhome: Option<String>,
+    pub sync_command: Option<String>,
+}
+
+/// Commands that encrypt and decrypt state files: each reads stdin and
+/// writes stdout, e.g. `age -r <recipient>` and `age -d -i <identity>`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct StateEncryption {
+    pub encrypt_command: String,
+    pub decrypt_command: String,
+}
+
+/// `[http_api]` section: port and access token for the HTTP API.
+#[derive(Debug, Deserialize, Clone)]
+pub struct HttpApi {
+    #[serde(default = "default_http_api_port")]
+    pub port: u16,
+    pub token: String,
+}
+
+fn default_http_api_port() -> u16 {
+    7733
+}
+
+/// `[mu_timeouts]` section: seconds to wait for output from the mu server
+/// before giving up on the command and restarting the server. 0 waits
+/// forever.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct MuTimeouts {
+    /// Most commands (move, flag, add, ping).
+    pub command: u64,
+    /// Searches, between envelope batches.
+    pub find: u64,
+    /// Indexing, between progress updates.
+    pub index: u64,
+}
+
+impl Default for MuTimeouts {
+    fn default() -> Self {
+        Self {
+            command: 30,
+            find: 120,
+            index: 600,
+        }
+    }
+}
+
+/// `[counts]` section: how far apart batches of background count queries
+/// are kept.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct Counts {
+    /// Seconds from the start of one batch to the next.
+    pub min_interval: u64,
+    /// Space batches six times further apart while on battery.
+    pub battery_backoff: bool,
+}
+
+impl Default for Counts {
+    fn default() -> Self {
+        Self {
+            min_interval: 10,
+            battery_backoff: true,
+        }
+    }
+}
+
+/// `[thread_view]` section: header lines for collapsed and expanded
+/// messages in the thread view.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ThreadViewConfig {
+    pub collapsed: ThreadHeaders,
+    pub expanded: ThreadHeaders,
+}
+
+/// Headers for one state (collapsed or expanded) of a thread view message.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ThreadHeaders {
+    /// strftime-style date format, e.g. "%Y-%m-%d %H:%M". Default: the
+    /// relative form used in the message list ("14:05", "Tue 09:30", ...).
+    pub date_format: Option<String>,
+    /// Show the To recipients.
+    pub show_to: bool,
+    /// Show the Cc recipients, when there are any.
+    pub show_cc: bool,
+}
+
+/// `[flag_glyphs]` section: overrides for the flag column at the start
+/// of each envelope list row. Unset glyphs use the built-in Unicode set,
+/// or the ASCII set when `ascii = true`.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct FlagGlyphs {
+    /// Use plain ASCII glyphs, for fonts without the Unicode symbols.
+    pub ascii: bool,
+    pub unread: Option<String>,
+    pub starred: Option<String>,
+    pub replied: Option<String>,
+    pub attachment: Option<String>,
+    pub trashed: Option<String>,
+    /// Marks high-priority messages not yet replied to.
+    pub urgent: Option<String>,
+    /// Glyphs for keywords ("todo", "waiting"), shown after the flags.
+    /// Keywords with a `toggle_keyword` binding show by default as their
+    /// first letter.
+    pub keywords: BTreeMap<String, String>,
+    /// Colors keyed by flag name ("unread", "starred", ...) or keyword.
+    /// Accepts color names ("yellow"), 256-color indices ("75") or
+    /// "#rrggbb".
+    pub colors: HashMap<String, String>,
+}
+
+/// Markup for message links copied to the clipboard.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkFormat {
+    /// `[Subject — Sender](url)`
+    #[default]
+    Markdown,
+    /// `[[url][Subject — Sender]]`
+    Org,
+}
+
+/// What `hutt` does when another instance is already running.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SingleInstance {
+    /// Start another instance alongside it.
+    #[default]
+    Allow,
+    /// Send the folder and account to the running instance and exit.
+    Forward,
+    /// Ask whether to forward or start another instance.
+    Ask,
+}
+
+/// When low-power mode is on.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LowPower {
+    /// While running on battery.
+    #[default]
+    Auto,
+    /// Always.
+    On,
+    /// Never.
+    Off,
+}
+
+/// How much vertical space each row of the envelope list takes.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ListDensity {
+    /// One line per message: sender, subject and snippet, date.
+    #[default]
+    Compact,
+    /// Two lines per message: subject and date, then sender and snippet.
+    Relaxed,
+}
+
+impl ListDensity {
+    /// Terminal rows each list entry occupies.
+    pub fn rows_per_item(self) -> usize {
+        match self {
+            ListDensity::Compact => 1,
+            ListDensity::Relaxed => 2,
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            ListDensity::Compact => ListDensity::Relaxed,
+            ListDensity::Relaxed => ListDensity::Compact,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ListDensity::Compact => "compact",
+            ListDensity::Relaxed => "relaxed",
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             accounts: Vec::new(),
-            editor: "nvim".to_string(),
+            editor: String::new(),
             sync_command: None,
+            headers_sync_command: None,
+            headers_only: false,
+            fetch_body_command: None,
             download_dir: None,
+            patch_command: None,
+            pipe_thread_command: None,
+            summarize_command: None,
+            shell_dry_run: false,
+            compose_dir: None,
+            ipc_pipe: None,
+            remote: None,
+            status_file: None,
             check_mail_every: None,
             check_mail_after: None,
 
             bindings: BindingsSection::default(),
             conversations: false,
             background_servers: true,
+            health_check: true,
             vim_mode: false,
+            warn_reply_older_than_days: 30,
+            warn_recipients_over: 15,
+            reply_all_check_over: 10,
+            warn_attachments_over_mb: 25,
+            upload_command: None,
+            compose_wrap: 0,
+            needs_reply_query: None,
+            score_header: None,
+            score_command: None,
+            score_threshold: 0.0,
+            recently_deleted_days: 30,
+            max_results: 10000,
+            builtin_smart_folders: vec![
+                "Starred".to_string(),
+                "Unread".to_string(),
+                "Today".to_string(),
+                "Large".to_string(),
+                "Waiting".to_string(),
+                "Screened".to_string(),
+            ],
+            smart_folders: Vec::new(),
+            sectioned_inbox: false,
+            inbox_sections: crate::inbox_sections::default_sections(),
+            list_density: ListDensity::Compact,
+            link_format: LinkFormat::Markdown,
+            color_senders: true,
+            age_amber_days: 3,
+            age_red_days: 7,
+            display_names: HashMap::new(),
+            sender_badges: true,
+            notify_followups: true,
+            triage_summary: None,
+            restore_session: true,
+            persist_folder_settings: false,
+            persist_read_positions: false,
+            single_instance: SingleInstance::Allow,
+            low_power: LowPower::Auto,
+            state_encryption: None,
+            http_api: None,
+            flag_glyphs: FlagGlyphs::default(),
+            cycle_folders: None,
+            startup_folder: None,
+            favorite_folders: None,
+            recent_folders: 5,
+            preview_max_width: None,
+            preview_max_size_mb: 10,
+            thread_view: ThreadViewConfig::default(),
+            mu_timeouts: MuTimeouts::default(),
+            counts: Counts::default(),
+            profiles: BTreeMap::new(),
+            active_profile: None,
+            redact: Vec::new(),
         }
     }
 }
@@ -83,8 +532,28 @@ pub struct AccountConfig {
     /// Tab bar order. Wildcards: "/" = remaining maildirs, "#" = remaining splits, "@" = remaining smart folders.
     /// Default when omitted: ["/Inbox", "#", "/", "@"]
     pub tabs: Option<Vec<String>>,
+    /// Per-account Tab cycle order (overrides global cycle_folders).
+    pub cycle_folders: Option<Vec<String>>,
+    /// Per-account startup folder or query (overrides global startup_folder).
+    #[serde(alias = "startup_query")]
+    pub startup_folder: Option<String>,
+    /// Per-account picker favorites (overrides global favorite_folders).
+    pub favorite_folders: Option<Vec<String>>,
+    /// Per-account key bindings, merged over the global `[bindings]`.
+    #[serde(default)]
+    pub bindings: BindingsSection,
     /// Human-readable description of this account (for LLM context, docs, etc.).
     pub description: Option<String>,
+    /// Domains counted as internal. When set, sending to anyone outside
+    /// them (or subdomains) asks for confirmation first.
+    #[serde(default)]
+    pub internal_domains: Vec<String>,
+    /// Badge marking this account's rows when two accounts are shown
+    /// together. Default: the first letter of `name`.
+    pub badge: Option<String>,
+    /// Color of the badge (a ratatui color name or `#rrggbb`). Default:
+    /// one per account, in order.
+    pub color: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -104,6 +573,59 @@ pub struct SmtpConfig {
     pub password_command: Option<String>,
     /// OAuth2 access-token command, if used instead of password auth.
     pub oauth2_command: Option<String>,
+    /// Servers to try in order when this one can't be reached.
+    pub fallback: Vec<SmtpFallback>,
+}
+
+/// An alternate SMTP server. Unset fields are taken from the main server,
+/// so e.g. `{ port = 587, encryption = "starttls" }` retries the same host
+/// on the submission port.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SmtpFallback {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub encryption: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub password_command: Option<String>,
+}
+
+impl SmtpConfig {
+    /// The server as host:port.
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// This server followed by its fallbacks, in the order to try them.
+    pub fn servers(&self) -> Vec<SmtpConfig> {
+        let main = SmtpConfig {
+            fallback: Vec::new(),
+            ..self.clone()
+        };
+        let mut servers = vec![main.clone()];
+        for f in &self.fallback {
+            let mut server = main.clone();
+            if let Some(host) = &f.host {
+                server.host = host.clone();
+            }
+            if let Some(port) = f.port {
+                server.port = port;
+            }
+            if let Some(encryption) = &f.encryption {
+                server.encryption = encryption.clone();
+            }
+            if let Some(username) = &f.username {
+                server.username = username.clone();
+            }
+            if f.password.is_some() || f.password_command.is_some() {
+                server.password = f.password.clone();
+                server.password_command = f.password_command.clone();
+            }
+            servers.push(server);
+        }
+        servers
+    }
 }
 
 impl Default for SmtpConfig {
@@ -116,6 +638,7 @@ impl Default for SmtpConfig {
             password: None,
             password_command: None,
             oauth2_command: None,
+            fallback: Vec::new(),
         }
     }
 }
@@ -148,6 +671,54 @@ impl Default for FolderConfig {
     }
 }
 
+impl FolderConfig {
+    /// The folder for a role ("archive", "trash", ...), to change it.
+    pub fn role_mut(&mut self, role: &str) -> Option<&mut String> {
+        match role {
+            "inbox" => Some(&mut self.inbox),
+            "archive" => Some(&mut self.archive),
+            "drafts" => Some(&mut self.drafts),
+            "sent" => Some(&mut self.sent),
+            "trash" => Some(&mut self.trash),
+            "spam" => Some(&mut self.spam),
+            _ => None,
+        }
+    }
+}
+
+/// Folder names mail providers use for a standard role, for when the
+/// configured one doesn't exist. Compared without case.
+fn provider_folder_names(role: &str) -> &'static [&'static str] {
+    match role {
+        "archive" => &["[Gmail]/All Mail", "[Google Mail]/All Mail", "Archives", "All Mail"],
+        "trash" => &[
+            "[Gmail]/Bin",
+            "[Gmail]/Trash",
+            "[Google Mail]/Bin",
+            "[Google Mail]/Trash",
+            "Deleted Items",
+            "Deleted Messages",
+            "Deleted",
+            "Bin",
+        ],
+        "spam" => &["[Gmail]/Spam", "[Google Mail]/Spam", "Junk", "Junk E-mail", "Junk Email", "Bulk Mail"],
+        "sent" => &["[Gmail]/Sent Mail", "[Google Mail]/Sent Mail", "Sent Items", "Sent Messages", "Sent Mail"],
+        "drafts" => &["[Gmail]/Drafts", "[Google Mail]/Drafts", "Draft"],
+        _ => &[],
+    }
+}
+
+/// Of the existing `folders`, one a provider uses for `role`, e.g.
+/// `/[Gmail]/Bin` for trash.
+pub fn provider_folder(role: &str, folders: &[String]) -> Option<String> {
+    provider_folder_names(role).iter().find_map(|name| {
+        folders
+            .iter()
+            .find(|f| f.trim_start_matches('/').eq_ignore_ascii_case(name))
+            .cloned()
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Keybindings
 // ---------------------------------------------------------------------------
@@ -169,6 +740,9 @@ impl Default for FolderConfig {
 /// A table with `shell = "..."` runs a shell command.
 /// A table with `move = "..."` moves selected messages to a folder
 /// (alias like `"archive"` or literal path like `"/Projects"`).
+/// A table with `reply_template = "..."` replies using a template file.
+/// A table with `toggle_keyword = "..."` adds or removes a keyword, and
+/// one with `filter_keyword = "..."` lists only messages that have it.
 #[derive(Debug, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum BindingValue {
@@ -187,6 +761,24 @@ pub enum BindingValue {
         #[serde(rename = "move")]
         folder: String,
     },
+    /// `{ reply_template = "decline-meeting", send = true, confirm = false }`.
+    ReplyTemplate {
+        reply_template: String,
+        /// Send immediately instead of opening the editor.
+        #[serde(default)]
+        send: bool,
+        /// Ask before sending (only with `send = true`).
+        #[serde(default = "default_true")]
+        confirm: bool,
+    },
+    /// `{ toggle_keyword = "todo" }`.
+    ToggleKeyword { toggle_keyword: String },
+    /// `{ filter_keyword = "todo" }`.
+    FilterKeyword { filter_keyword: String },
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// The `[bindings]` config section.
@@ -207,6 +799,20 @@ pub struct BindingsSection {
     pub global: HashMap<String, BindingValue>,
 }
 
+impl BindingsSection {
+    /// The keywords bound to `toggle_keyword` keys.
+    fn toggled_keywords(&self) -> impl Iterator<Item = &str> {
+        self.global
+            .values()
+            .chain(self.normal.values())
+            .chain(self.thread.values())
+            .filter_map(|value| match value {
+                BindingValue::ToggleKeyword { toggle_keyword } => Some(toggle_keyword.as_str()),
+                _ => None,
+            })
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Loading
 // ---------------------------------------------------------------------------
@@ -220,6 +826,59 @@ impl Config {
             .unwrap_or(0)
     }
 
+    /// Keywords shown in the list's flag column: those with a glyph in
+    /// `[flag_glyphs]` and those any account's bindings toggle, sorted.
+    pub fn keywords(&self) -> Vec<String> {
+        let bound = self.bindings.toggled_keywords().chain(self.accounts.iter().flat_map(|a| a.bindings.toggled_keywords()));
+        let mut keywords: Vec<String> = self.flag_glyphs.keywords.keys().map(String::as_str).chain(bound).map(str::to_lowercase).collect();
+        keywords.sort();
+        keywords.dedup();
+        keywords
+    }
+
+    /// The Tab cycle order for an account: its own `cycle_folders` if set,
+    /// otherwise the global one. `None` means cycle through the tab bar.
+    pub fn effective_cycle_folders(&self, account_idx: usize) -> Option<&[String]> {
+        self.accounts
+            .get(account_idx)
+            .and_then(|a| a.cycle_folders.as_deref())
+            .or(self.cycle_folders.as_deref())
+    }
+
+    /// Folders pinned at the top of the pickers for an account: its own
+    /// `favorite_folders` if set, otherwise the global list.
+    pub fn effective_favorite_folders(&self, account_idx: usize) -> &[String] {
+        self.accounts
+            .get(account_idx)
+            .and_then(|a| a.favorite_folders.as_deref())
+            .or(self.favorite_folders.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// The folder to open first for an account: its own `startup_folder`,
+    /// the global one, or else its inbox.
+    pub fn effective_startup_folder(&self, account_idx: usize) -> String {
+        let account = self.accounts.get(account_idx);
+        account
+            .and_then(|a| a.startup_folder.clone())
+            .or_else(|| self.startup_folder.clone())
+            .or_else(|| account.map(|a| a.folders.inbox.clone()))
+            .unwrap_or_else(|| "/Inbox".to_string())
+    }
+
+    /// The key bindings for an account: the global `[bindings]` with the
+    /// account's `[accounts.bindings]` entries replacing same-key ones.
+    pub fn effective_bindings(&self, account_idx: usize) -> BindingsSection {
+        let mut merged = self.bindings.clone();
+        if let Some(acct) = self.accounts.get(account_idx) {
+            let own = &acct.bindings;
+            merged.global.extend(own.global.iter().map(|(k, v)| (k.clone(), v.clone())));
+            merged.normal.extend(own.normal.iter().map(|(k, v)| (k.clone(), v.clone())));
+            merged.thread.extend(own.thread.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        merged
+    }
+
     /// Return the effective sync command for an account index.
     /// Uses the account's sync_command if set, otherwise falls back to global.
     /// Replaces `{account}` with the account name and `{maildir}` with the
@@ -230,6 +889,31 @@ impl Config {
             .get(account_idx)
             .and_then(|a| a.sync_command.as_deref())
             .or(self.sync_command.as_deref())?;
+        self.account_command(cmd, account_idx)
+    }
+
+    /// The headers-only sync command for an account index, with the same
+    /// replacements as [`Self::effective_sync_command`].
+    pub fn effective_headers_sync_command(&self, account_idx: usize) -> Option<String> {
+        self.account_command(self.headers_sync_command.as_deref()?, account_idx)
+    }
+
+    /// The command fetching the body of the message `message_id` stored at
+    /// `path`, if one is configured.
+    pub fn effective_fetch_body_command(
+        &self,
+        account_idx: usize,
+        message_id: &str,
+        path: &Path,
+    ) -> Option<String> {
+        let cmd = self.fetch_body_command.as_deref()?
+            .replace("{message_id}", &shell_quote(message_id))
+            .replace("{path}", &shell_quote(&path.to_string_lossy()));
+        self.account_command(&cmd, account_idx)
+    }
+
+    /// `cmd` with `{account}` and `{maildir}` replaced for an account.
+    fn account_command(&self, cmd: &str, account_idx: usize) -> Option<String> {
         let acct = self.accounts.get(account_idx)?;
         let maildir = expand_tilde(&acct.maildir);
         Some(
@@ -256,6 +940,50 @@ impl Config {
         }
     }
 
+    /// Apply the profile named `requested`, or else the first (by name)
+    /// whose `hosts` include `hostname`. Naming a profile that isn't
+    /// defined is an error.
+    pub fn apply_profile(&mut self, requested: Option<&str>, hostname: &str) -> Result<()> {
+        let name = match requested {
+            Some(name) if self.profiles.contains_key(name) => name.to_string(),
+            Some(name) => anyhow::bail!("HUTT_PROFILE is {:?}, but there is no [profiles.{}]", name, name),
+            None => {
+                let short = hostname.split('.').next().unwrap_or(hostname);
+                let matches = |h: &String| h.eq_ignore_ascii_case(hostname) || h.eq_ignore_ascii_case(short);
+                match self.profiles.iter().find(|(_, p)| p.hosts.iter().any(matches)) {
+                    Some((name, _)) => name.clone(),
+                    None => return Ok(()),
+                }
+            }
+        };
+        let profile = self.profiles[&name].clone();
+        if let Some(editor) = profile.editor {
+            self.editor = editor;
+        }
+        if profile.sync_command.is_some() {
+            self.sync_command = profile.sync_command;
+        }
+        if profile.headers_sync_command.is_some() {
+            self.headers_sync_command = profile.headers_sync_command;
+        }
+        for (account_name, overrides) in profile.accounts {
+            let Some(account) = self.accounts.iter_mut().find(|a| a.name == account_name) else {
+                anyhow::bail!("[profiles.{}.accounts.{}]: no such account", name, account_name);
+            };
+            if let Some(maildir) = overrides.maildir {
+                account.maildir = maildir;
+            }
+            if overrides.muhome.is_some() {
+                account.muhome = overrides.muhome;
+            }
+            if overrides.sync_command.is_some() {
+                account.sync_command = overrides.sync_command;
+            }
+        }
+        self.active_profile = Some(name);
+        Ok(())
+    }
+
     /// Try to load the configuration file from, in order:
     ///
     /// 1. `$HUTT_CONFIG`
@@ -267,8 +995,10 @@ impl Config {
         if let Some(path) = Self::locate() {
             let contents = std::fs::read_to_string(&path)
                 .with_context(|| format!("failed to read config file {}", path.display()))?;
-            let config: Config = toml::from_str(&contents)
+            let mut config: Config = toml::from_str(&contents)
                 .with_context(|| format!("failed to parse config file {}", path.display()))?;
+            let requested = std::env::var("HUTT_PROFILE").ok().filter(|p| !p.is_empty());
+            config.apply_profile(requested.as_deref(), &crate::maildir::gethostname())?;
             Ok(config)
         } else {
             Ok(Config::default())
@@ -310,7 +1040,7 @@ impl Config {
 }
 
 /// Expand `~/` prefix in a path string.
-fn expand_tilde(path: &str) -> String {
+pub(crate) fn expand_tilde(path: &str) -> String {
     if let Some(rest) = path.strip_prefix("~/") {
         let home = std::env::var("HOME").unwrap_or_default();
         format!("{}/{}", home, rest)
@@ -319,6 +1049,11 @@ fn expand_tilde(path: &str) -> String {
     }
 }
 
+/// Quote `s` as one word for `sh -c`.
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -330,7 +1065,7 @@ mod tests {
     #[test]
     fn default_config_is_valid() {
         let cfg = Config::default();
-        assert_eq!(cfg.editor, "nvim");
+        assert_eq!(cfg.editor, "");
         assert!(cfg.accounts.is_empty());
         assert!(cfg.sync_command.is_none());
     }
@@ -347,6 +1082,71 @@ mod tests {
         assert!(cfg.accounts.is_empty());
     }
 
+    #[test]
+    fn parse_flag_glyphs() {
+        let cfg: Config = toml::from_str(
+            r#"
+[flag_glyphs]
+ascii = true
+starred = "S"
+colors = { unread = "green" }
+"#,
+        )
+        .unwrap();
+        assert!(cfg.flag_glyphs.ascii);
+        assert_eq!(cfg.flag_glyphs.starred.as_deref(), Some("S"));
+        assert!(cfg.flag_glyphs.unread.is_none());
+        assert_eq!(cfg.flag_glyphs.colors.get("unread").map(String::as_str), Some("green"));
+    }
+
+    #[test]
+    fn parse_list_density() {
+        let cfg: Config = toml::from_str(r#"list_density = "relaxed""#).unwrap();
+        assert_eq!(cfg.list_density, ListDensity::Relaxed);
+        assert_eq!(cfg.list_density.rows_per_item(), 2);
+        assert_eq!(Config::default().list_density, ListDensity::Compact);
+    }
+
+    #[test]
+    fn parse_single_instance() {
+        let cfg: Config = toml::from_str(r#"single_instance = "forward""#).unwrap();
+        assert_eq!(cfg.single_instance, SingleInstance::Forward);
+        assert_eq!(Config::default().single_instance, SingleInstance::Allow);
+
+        let cfg: Config = toml::from_str(r#"low_power = "off""#).unwrap();
+        assert_eq!(cfg.low_power, LowPower::Off);
+        assert_eq!(Config::default().low_power, LowPower::Auto);
+    }
+
+    #[test]
+    fn parse_mu_timeouts() {
+        let cfg: Config = toml::from_str("[mu_timeouts]\nfind = 300\nindex = 0").unwrap();
+        assert_eq!(cfg.mu_timeouts.command, 30);
+        assert_eq!(cfg.mu_timeouts.find, 300);
+        assert_eq!(cfg.mu_timeouts.index, 0);
+
+        let cfg: Config = toml::from_str("[counts]\nbattery_backoff = false").unwrap();
+        assert_eq!(cfg.counts.min_interval, 10);
+        assert!(!cfg.counts.battery_backoff);
+    }
+
+    #[test]
+    fn parse_thread_view_headers() {
+        let cfg: Config = toml::from_str(
+            r#"
+[thread_view.expanded]
+date_format = "%Y-%m-%d %H:%M"
+show_to = true
+"#,
+        )
+        .unwrap();
+        let expanded = &cfg.thread_view.expanded;
+        assert_eq!(expanded.date_format.as_deref(), Some("%Y-%m-%d %H:%M"));
+        assert!(expanded.show_to);
+        assert!(!expanded.show_cc);
+        assert!(cfg.thread_view.collapsed.date_format.is_none());
+    }
+
     #[test]
     fn parse_full_account() {
         let toml_str = r#"
@@ -386,7 +1186,6 @@ mod tests {
         assert_eq!(acct.folders.trash, "/Bin");
     }
 
-    #[test]
     #[test]
     fn parse_bindings_global() {
         let toml_str = r#"
@@ -412,6 +1211,48 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn parse_bindings_reply_template() {
+        let toml_str = r#"
+            [bindings]
+            T = { reply_template = "thanks", send = true }
+            "g r" = { reply_template = "decline-meeting" }
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+        assert!(matches!(
+            cfg.bindings.global.get("T"),
+            Some(BindingValue::ReplyTemplate { reply_template, send: true, confirm: true })
+                if reply_template == "thanks"
+        ));
+        assert!(matches!(
+            cfg.bindings.global.get("g r"),
+            Some(BindingValue::ReplyTemplate { send: false, .. })
+        ));
+    }
+
+    #[test]
+    fn parse_bindings_keywords() {
+        let toml_str = r#"
+            [bindings]
+            t = { toggle_keyword = "todo" }
+            "g t" = { filter_keyword = "todo" }
+
+            [flag_glyphs.keywords]
+            waiting = "W"
+            Todo = "T"
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+        assert!(matches!(
+            cfg.bindings.global.get("t"),
+            Some(BindingValue::ToggleKeyword { toggle_keyword }) if toggle_keyword == "todo"
+        ));
+        assert!(matches!(
+            cfg.bindings.global.get("g t"),
+            Some(BindingValue::FilterKeyword { filter_keyword }) if filter_keyword == "todo"
+        ));
+        assert_eq!(cfg.keywords(), ["todo", "waiting"]);
+    }
+
     #[test]
     fn parse_bindings_per_mode() {
         let toml_str = r#"
@@ -530,6 +1371,38 @@ mod tests {
         assert!(smtp.password.is_none());
         assert!(smtp.password_command.is_none());
         assert!(smtp.oauth2_command.is_none());
+        assert_eq!(smtp.servers().len(), 1);
+    }
+
+    #[test]
+    fn smtp_fallback_servers() {
+        let toml_str = r#"
+            host             = "smtp.example.com"
+            port             = 465
+            encryption       = "ssl"
+            username         = "user@example.com"
+            password_command = "pass email/work"
+
+            [[fallback]]
+            port       = 587
+            encryption = "starttls"
+
+            [[fallback]]
+            host     = "relay.example.net"
+            username = "backup"
+            password = "hunter2"
+        "#;
+        let smtp: SmtpConfig = toml::from_str(toml_str).unwrap();
+        let servers = smtp.servers();
+        let addresses: Vec<String> = servers.iter().map(|s| s.address()).collect();
+        assert_eq!(addresses, vec!["smtp.example.com:465", "smtp.example.com:587", "relay.example.net:465"]);
+        assert_eq!(servers[1].encryption, "starttls");
+        assert_eq!(servers[1].password_command.as_deref(), Some("pass email/work"));
+        assert_eq!(servers[2].encryption, "ssl");
+        assert_eq!(servers[2].username, "backup");
+        assert_eq!(servers[2].password.as_deref(), Some("hunter2"));
+        assert!(servers[2].password_command.is_none());
+        assert!(servers.iter().all(|s| s.fallback.is_empty()));
     }
 
     #[test]
@@ -617,6 +1490,150 @@ mod tests {
         assert_eq!(cfg.default_account_index(), 0);
     }
 
+    #[test]
+    fn effective_cycle_folders_account_overrides_global() {
+        let toml_str = r#"
+            cycle_folders = ["/Inbox", "@Today"]
+
+            [[accounts]]
+            name = "Work"
+            email = "w@w.com"
+            maildir = "~/w"
+            cycle_folders = ["/Inbox", "/Lists"]
+            [accounts.smtp]
+            host = "smtp.w.com"
+
+            [[accounts]]
+            name = "Home"
+            email = "h@h.com"
+            maildir = "~/h"
+            [accounts.smtp]
+            host = "smtp.h.com"
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(cfg.effective_cycle_folders(0), Some(&["/Inbox".to_string(), "/Lists".to_string()][..]));
+        assert_eq!(cfg.effective_cycle_folders(1), Some(&["/Inbox".to_string(), "@Today".to_string()][..]));
+        assert_eq!(Config::default().effective_cycle_folders(0), None);
+    }
+
+    #[test]
+    fn effective_startup_folder_falls_back_to_inbox() {
+        let toml_str = r##"
+            startup_query = "@Today"
+
+            [[accounts]]
+            name = "Work"
+            email = "w@w.com"
+            maildir = "~/w"
+            startup_folder = "#GitHub"
+            [accounts.smtp]
+            host = "smtp.w.com"
+
+            [[accounts]]
+            name = "Home"
+            email = "h@h.com"
+            maildir = "~/h"
+            [accounts.smtp]
+            host = "smtp.h.com"
+            [accounts.folders]
+            inbox = "/INBOX"
+        "##;
+        let mut cfg: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(cfg.effective_startup_folder(0), "#GitHub");
+        assert_eq!(cfg.effective_startup_folder(1), "@Today");
+        cfg.startup_folder = None;
+        assert_eq!(cfg.effective_startup_folder(1), "/INBOX");
+        assert_eq!(Config::default().effective_startup_folder(0), "/Inbox");
+    }
+
+    #[test]
+    fn parse_shared_smart_folders() {
+        let toml_str = r#"
+            [[smart_folders]]
+            name = "Invoices"
+            query = "subject:invoice"
+            refresh = 10
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(cfg.smart_folders.len(), 1);
+        assert_eq!(cfg.smart_folders[0].name, "Invoices");
+        assert_eq!(cfg.smart_folders[0].refresh, Some(10.0));
+        assert!(Config::default().smart_folders.is_empty());
+    }
+
+    #[test]
+    fn effective_favorite_folders_prefers_account() {
+        let toml_str = r#"
+            favorite_folders = ["/Archive", "@Today"]
+            recent_folders = 3
+
+            [[accounts]]
+            name = "Work"
+            email = "w@w.com"
+            maildir = "~/w"
+            favorite_folders = ["/Projects"]
+            [accounts.smtp]
+            host = "smtp.w.com"
+
+            [[accounts]]
+            name = "Home"
+            email = "h@h.com"
+            maildir = "~/h"
+            [accounts.smtp]
+            host = "smtp.h.com"
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(cfg.recent_folders, 3);
+        assert_eq!(cfg.effective_favorite_folders(0), ["/Projects".to_string()]);
+        assert_eq!(
+            cfg.effective_favorite_folders(1),
+            ["/Archive".to_string(), "@Today".to_string()]
+        );
+        assert!(Config::default().effective_favorite_folders(0).is_empty());
+        assert_eq!(Config::default().recent_folders, 5);
+    }
+
+    #[test]
+    fn effective_bindings_merge_account_over_global() {
+        let toml_str = r#"
+            [bindings]
+            G = { shell = "mbsync -a", reindex = true }
+            A = "archive"
+
+            [[accounts]]
+            name = "Work"
+            email = "w@w.com"
+            maildir = "~/w"
+            [accounts.smtp]
+            host = "smtp.w.com"
+            [accounts.bindings]
+            G = { shell = "mbsync work", reindex = true }
+            [accounts.bindings.thread]
+            o = "open"
+
+            [[accounts]]
+            name = "Home"
+            email = "h@h.com"
+            maildir = "~/h"
+            [accounts.smtp]
+            host = "smtp.h.com"
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+        let work = cfg.effective_bindings(0);
+        assert_eq!(work.global.len(), 2);
+        assert!(matches!(
+            work.global.get("G"),
+            Some(BindingValue::Shell { shell, .. }) if shell == "mbsync work"
+        ));
+        assert!(matches!(work.thread.get("o"), Some(BindingValue::Short(s)) if s == "open"));
+        let home = cfg.effective_bindings(1);
+        assert!(matches!(
+            home.global.get("G"),
+            Some(BindingValue::Shell { shell, .. }) if shell == "mbsync -a"
+        ));
+        assert!(home.thread.is_empty());
+    }
+
     #[test]
     fn effective_sync_command_account_overrides_global() {
         let toml_str = r#"
@@ -662,6 +1679,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn headers_only_sync_and_body_fetch_commands() {
+        let toml_str = r#"
+            sync_command = "mbsync {account}"
+            headers_sync_command = "mbsync {account}-headers"
+            fetch_body_command = "fetch-body --account {account} {message_id} {path}"
+
+            [[accounts]]
+            name = "spesh"
+            email = "s@s.com"
+            maildir = "~/spesh-mail"
+            [accounts.smtp]
+            host = "smtp.s.com"
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+        assert!(!cfg.headers_only);
+        assert_eq!(cfg.effective_headers_sync_command(0).as_deref(), Some("mbsync spesh-headers"));
+        assert_eq!(
+            cfg.effective_fetch_body_command(0, "it's@x", Path::new("/m/cur/1:2,S")).as_deref(),
+            Some("fetch-body --account spesh 'it'\\''s@x' '/m/cur/1:2,S'")
+        );
+        assert_eq!(Config::default().effective_fetch_body_command(0, "a@b", Path::new("/m")), None);
+    }
+
     #[test]
     fn effective_muhome_auto_derive() {
         let toml_str = r#"
@@ -701,4 +1742,68 @@ mod tests {
         // Single account, no explicit muhome → None (use system default)
         assert!(cfg.effective_muhome(0).is_none());
     }
+
+    #[test]
+    fn provider_folders_for_missing_roles() {
+        let folders: Vec<String> = ["/INBOX", "/[Gmail]/Bin", "/[Gmail]/Sent Mail", "/junk"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        assert_eq!(provider_folder("trash", &folders).as_deref(), Some("/[Gmail]/Bin"));
+        assert_eq!(provider_folder("sent", &folders).as_deref(), Some("/[Gmail]/Sent Mail"));
+        assert_eq!(provider_folder("spam", &folders).as_deref(), Some("/junk"));
+        assert_eq!(provider_folder("drafts", &folders), None);
+        assert_eq!(provider_folder("/Projects", &folders), None);
+
+        let mut mapped = FolderConfig::default();
+        *mapped.role_mut("trash").unwrap() = "/[Gmail]/Bin".to_string();
+        assert_eq!(mapped.trash, "/[Gmail]/Bin");
+        assert!(mapped.role_mut("projects").is_none());
+    }
+
+    #[test]
+    fn profiles_by_name_or_hostname() {
+        let toml_str = r#"
+            editor = "nvim"
+            sync_command = "mbsync -a"
+
+            [[accounts]]
+            name = "work"
+            email = "w@w.com"
+            maildir = "~/Mail/work"
+            [accounts.smtp]
+            host = "smtp.w.com"
+
+            [profiles.laptop]
+            hosts = ["lappy"]
+            editor = "vim"
+            [profiles.laptop.accounts.work]
+            maildir = "~/mail/work"
+            sync_command = "mbsync work-laptop"
+
+            [profiles.desktop]
+            hosts = ["tower.example.net"]
+            sync_command = "offlineimap"
+        "#;
+        let cfg: Config = toml::from_str(toml_str).unwrap();
+
+        let mut laptop = cfg.clone();
+        laptop.apply_profile(None, "lappy.local").unwrap();
+        assert_eq!(laptop.active_profile.as_deref(), Some("laptop"));
+        assert_eq!(laptop.editor, "vim");
+        assert_eq!(laptop.sync_command.as_deref(), Some("mbsync -a"));
+        assert_eq!(laptop.accounts[0].maildir, "~/mail/work");
+        assert_eq!(laptop.accounts[0].sync_command.as_deref(), Some("mbsync work-laptop"));
+
+        let mut desktop = cfg.clone();
+        desktop.apply_profile(Some("desktop"), "lappy").unwrap();
+        assert_eq!(desktop.sync_command.as_deref(), Some("offlineimap"));
+        assert_eq!(desktop.editor, "nvim");
+        assert_eq!(desktop.accounts[0].maildir, "~/Mail/work");
+
+        let mut other = cfg.clone();
+        other.apply_profile(None, "elsewhere").unwrap();
+        assert_eq!(other.active_profile, None);
+        assert!(other.clone().apply_profile(Some("travel"), "elsewhere").is_err());
+    }
 }
@@ -0,0 +1,201 @@
+//! The local address book: people added with "Add to Contacts", each with
+//! the name you know them by. Kept in `contacts.toml` in hutt's config
+//! directory, shared by every account and fine to edit by hand (unless
+//! `[state_encryption]` is set, which encrypts it like the state files). A
+//! contact's nickname (or name) is shown in place of the one their mail
+//! gives, as is a name set for their address or domain in config's
+//! `display_names`. Contacts also count as known addresses for the
+//! pre-send checks, ahead of mu's.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::envelope::Address;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub email: String,
+    /// Their name, as their mail gave it when they were added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// What to call them instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+}
+
+impl Contact {
+    /// The name shown for them: the nickname, else the name.
+    pub fn display(&self) -> Option<&str> {
+        self.nickname.as_deref().or(self.name.as_deref())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ContactsFile {
+    #[serde(default, rename = "contact")]
+    contacts: Vec<Contact>,
+}
+
+pub fn contacts_path() -> PathBuf {
+    crate::paths::config_dir().join("contacts.toml")
+}
+
+/// Load the address book. A missing or unparsable file yields no contacts.
+pub fn load_contacts() -> Vec<Contact> {
+    load_contacts_from(&contacts_path())
+}
+
+fn load_contacts_from(path: &Path) -> Vec<Contact> {
+    crate::state_crypt::read_to_string(path)
+        .ok()
+        .and_then(|contents| toml::from_str::<ContactsFile>(&contents).ok())
+        .map(|file| file.contacts)
+        .unwrap_or_default()
+}
+
+/// Save the address book. Creates parent directories if needed.
+pub fn save_contacts(contacts: &[Contact]) {
+    save_contacts_to(contacts, &contacts_path());
+}
+
+fn save_contacts_to(contacts: &[Contact], path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let file = ContactsFile { contacts: contacts.to_vec() };
+    if let Ok(contents) = toml::to_string_pretty(&file) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+/// The contact for `email`, if there is one.
+pub fn find<'a>(contacts: &'a [Contact], email: &str) -> Option<&'a Contact> {
+    contacts.iter().find(|c| c.email.eq_ignore_ascii_case(email))
+}
+
+/// Call `address` `name` from now on, adding them if they aren't a
+/// contact yet; an empty name removes them. Returns whether they are
+/// a contact afterwards.
+pub fn set_name(contacts: &mut Vec<Contact>, address: &Address, name: &str) -> bool {
+    let name = name.trim();
+    let existing = contacts.iter().position(|c| c.email.eq_ignore_ascii_case(&address.email));
+    if name.is_empty() {
+        if let Some(i) = existing {
+            contacts.remove(i);
+        }
+        return false;
+    }
+    let contact = match existing {
+        Some(i) => &mut contacts[i],
+        None => {
+            contacts.push(Contact { email: address.email.clone(), name: address.name.clone(), nickname: None });
+            contacts.last_mut().expect("just pushed")
+        }
+    };
+    contact.nickname = (contact.name.as_deref() != Some(name)).then(|| name.to_string());
+    true
+}
+
+/// Display names by lowercased address or `@domain`, for
+/// [`display_name`].
+static NAMES: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+/// The `display_names` from config, keyed by address or `@domain`, with
+/// contacts' names over them.
+fn names(contacts: &[Contact], overrides: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut names: HashMap<String, String> = overrides
+        .iter()
+        .filter(|(_, name)| !name.trim().is_empty())
+        .map(|(key, name)| (key.trim().to_lowercase(), name.trim().to_string()))
+        .collect();
+    names.extend(contacts.iter().filter_map(|c| Some((c.email.to_lowercase(), c.display()?.to_string()))));
+    names
+}
+
+/// Show these contacts' names, and the config's `display_names`, from
+/// now on.
+pub fn use_names(contacts: &[Contact], overrides: &HashMap<String, String>) {
+    if let Ok(mut guard) = NAMES.write() {
+        *guard = Some(names(contacts, overrides));
+    }
+}
+
+fn lookup(names: &HashMap<String, String>, email: &str) -> Option<String> {
+    let email = email.to_lowercase();
+    let domain = email.rfind('@').map(|at| &email[at..]);
+    names.get(&email).or_else(|| names.get(domain?)).cloned()
+}
+
+/// The name the address book or config gives `email`, if any: its own,
+/// else its domain's.
+pub fn display_name(email: &str) -> Option<String> {
+    let guard = NAMES.read().ok()?;
+    lookup(guard.as_ref()?, email)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn naming_and_renaming_contacts() {
+        let alice = Address { name: Some("Alice Liddell".into()), email: "alice@example.com".into() };
+        let mut contacts = Vec::new();
+
+        // Added under the name her mail gives: no nickname needed
+        assert!(set_name(&mut contacts, &alice, "Alice Liddell"));
+        assert_eq!(contacts[0].nickname, None);
+        assert!(set_name(&mut contacts, &Address { name: None, email: "Alice@Example.com".into() }, " Al "));
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].display(), Some("Al"));
+        assert_eq!(names(&contacts, &HashMap::new()).get("alice@example.com").map(String::as_str), Some("Al"));
+
+        let file = ContactsFile { contacts: contacts.clone() };
+        let text = toml::to_string_pretty(&file).unwrap();
+        let parsed: ContactsFile = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.contacts, contacts);
+
+        assert!(!set_name(&mut contacts, &alice, ""));
+        assert!(find(&contacts, "alice@example.com").is_none());
+    }
+
+    #[test]
+    fn display_overrides_by_address_and_domain() {
+        let overrides = HashMap::from([
+            ("no-reply@github.com".to_string(), "GitHub".to_string()),
+            ("@Example.org".to_string(), "Example".to_string()),
+            ("bob@example.org".to_string(), "Bob Old".to_string()),
+            ("blank@example.com".to_string(), " ".to_string()),
+        ]);
+        let bob = Address { name: Some("Bob Old".into()), email: "bob@example.org".into() };
+        let mut contacts = Vec::new();
+        set_name(&mut contacts, &bob, "Robert");
+        let names = names(&contacts, &overrides);
+
+        assert_eq!(lookup(&names, "No-Reply@GitHub.com").as_deref(), Some("GitHub"));
+        assert_eq!(lookup(&names, "noreply@github.com"), None);
+        assert_eq!(lookup(&names, "news@example.org").as_deref(), Some("Example"));
+        // The address book wins over config
+        assert_eq!(lookup(&names, "bob@example.org").as_deref(), Some("Robert"));
+        assert_eq!(lookup(&names, "blank@example.com"), None);
+    }
+
+    #[test]
+    fn contacts_file_goes_through_state_crypt() {
+        let dir = std::env::temp_dir().join(format!("hutt-contacts-{}", std::process::id()));
+        let path = dir.join("contacts.toml");
+        let alice = Contact { email: "alice@example.com".into(), name: Some("Alice".into()), nickname: None };
+        save_contacts_to(std::slice::from_ref(&alice), &path);
+        assert_eq!(load_contacts_from(&path), vec![alice.clone()]);
+
+        // An encrypted address book is never replaced with plain text
+        let cipher = "age-encryption.org/v1\n";
+        std::fs::write(&path, cipher).unwrap();
+        save_contacts_to(&[alice], &path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), cipher);
+        assert!(load_contacts_from(&path).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,92 @@
+//! When background counts run. The inbox unread counts for the status
+//! file and the smart folder badges in the folder picker are all mu
+//! queries; left alone they'd run after every triage and keep mu busy.
+//! Instead they start together in batches, no closer than the configured
+//! interval, never while mu is indexing, and further apart on battery.
+
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::Counts;
+
+/// How much further apart batches are while on battery.
+const BATTERY_FACTOR: u32 = 6;
+
+#[derive(Debug)]
+pub struct CountScheduler {
+    interval: Duration,
+    battery_backoff: bool,
+    /// When the last batch started.
+    last_batch: Option<Instant>,
+    /// Whether we were on battery when last checked, and when that was.
+    on_battery: Option<(bool, Instant)>,
+}
+
+impl CountScheduler {
+    pub fn new(config: &Counts) -> Self {
+        Self {
+            interval: Duration::from_secs(config.min_interval),
+            battery_backoff: config.battery_backoff,
+            last_batch: None,
+            on_battery: None,
+        }
+    }
+
+    fn on_battery(&mut self, now: Instant) -> bool {
+        match self.on_battery {
+            Some((on_battery, checked)) if now.duration_since(checked) < crate::power::RECHECK => on_battery,
+            _ => {
+                let on_battery = crate::power::on_battery();
+                self.on_battery = Some((on_battery, now));
+                on_battery
+            }
+        }
+    }
+
+    /// The time to leave between batches right now.
+    fn interval(&mut self, now: Instant) -> Duration {
+        if self.battery_backoff && self.on_battery(now) {
+            self.interval * BATTERY_FACTOR
+        } else {
+            self.interval
+        }
+    }
+
+    /// Whether a batch of counts may start: not while mu is indexing, and
+    /// not until the interval since the last one has passed.
+    pub fn due(&mut self, now: Instant, indexing: bool) -> bool {
+        if indexing {
+            return false;
+        }
+        match self.last_batch {
+            Some(last) => now.duration_since(last) >= self.interval(now),
+            None => true,
+        }
+    }
+
+    /// Record that a batch started at `now`.
+    pub fn started(&mut self, now: Instant) {
+        self.last_batch = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spaces_out_count_batches() {
+        let mut scheduler = CountScheduler::new(&Counts { min_interval: 10, battery_backoff: true });
+        let start = Instant::now();
+        scheduler.on_battery = Some((false, start));
+        assert!(!scheduler.due(start, true));
+        assert!(scheduler.due(start, false));
+        scheduler.started(start);
+        assert!(!scheduler.due(start + Duration::from_secs(9), false));
+        assert!(scheduler.due(start + Duration::from_secs(10), false));
+
+        scheduler.on_battery = Some((true, start + Duration::from_secs(30)));
+        assert!(!scheduler.due(start + Duration::from_secs(59), false));
+        assert!(scheduler.due(start + Duration::from_secs(60), false));
+    }
+}
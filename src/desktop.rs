@@ -0,0 +1,127 @@
+//! `hutt install-handler`: register hutt as the freedesktop URL handler
+//! for `hutt:`, `mid:` and `message:` links (and optionally `mailto:`), by
+//! writing a `.desktop` file that runs `hutt open %u` and making it the
+//! default with `xdg-mime`.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Name of the desktop file hutt installs.
+const DESKTOP_FILE: &str = "hutt.desktop";
+
+/// Schemes always registered.
+const SCHEMES: &[&str] = &["hutt", "mid", "message"];
+
+/// Return `$XDG_DATA_HOME/applications` (default `~/.local/share/applications`).
+fn applications_dir() -> PathBuf {
+    if let Ok(xdg) = std::env::var("XDG_DATA_HOME") {
+        PathBuf::from(xdg).join("applications")
+    } else if let Ok(home) = std::env::var("HOME") {
+        PathBuf::from(home).join(".local").join("share").join("applications")
+    } else {
+        PathBuf::from(".")
+    }
+}
+
+/// The schemes to register, with `mailto` when asked for.
+pub fn schemes(mailto: bool) -> Vec<&'static str> {
+    let mut schemes = SCHEMES.to_vec();
+    if mailto {
+        schemes.push("mailto");
+    }
+    schemes
+}
+
+/// Quote a program path for a desktop entry `Exec` key.
+fn exec_quote(arg: &str) -> String {
+    let plain = arg
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-+".contains(c));
+    let arg = arg.replace('%', "%%");
+    if plain {
+        return arg;
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Contents of the desktop file, running `<hutt> open %u`.
+pub fn desktop_entry(hutt: &Path, schemes: &[&str]) -> String {
+    let mime: String = schemes
+        .iter()
+        .map(|s| format!("x-scheme-handler/{};", s))
+        .collect();
+    format!(
+        "[Desktop Entry]
+Type=Application
+Name=hutt
+Comment=Open {} links in hutt
+Exec={} open %u
+MimeType={}
+NoDisplay=true
+Terminal=false
+Categories=Network;Email;
+",
+        schemes
+            .iter()
+            .map(|s| format!("{}:", s))
+            .collect::<Vec<_>>()
+            .join(", "),
+        exec_quote(&hutt.to_string_lossy()),
+        mime
+    )
+}
+
+/// Write the desktop file and make it the default handler for each scheme.
+/// Returns the path written.
+pub fn install(mailto: bool) -> Result<PathBuf> {
+    let hutt = std::env::current_exe().context("cannot find the hutt executable")?;
+    let schemes = schemes(mailto);
+    let dir = applications_dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(DESKTOP_FILE);
+    std::fs::write(&path, desktop_entry(&hutt, &schemes))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    // Not every desktop has update-desktop-database; xdg-mime is enough
+    let _ = Command::new("update-desktop-database").arg(&dir).status();
+    for scheme in &schemes {
+        let mime = format!("x-scheme-handler/{}", scheme);
+        let status = Command::new("xdg-mime")
+            .args(["default", DESKTOP_FILE, &mime])
+            .status()
+            .context("failed to run xdg-mime (is xdg-utils installed?)")?;
+        if !status.success() {
+            anyhow::bail!("xdg-mime default {} {} failed", DESKTOP_FILE, mime);
+        }
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_entry_registers_schemes() {
+        let entry = desktop_entry(Path::new("/usr/local/bin/hutt"), &schemes(true));
+        assert!(entry.contains("Exec=/usr/local/bin/hutt open %u\n"));
+        assert!(entry.contains(
+            "MimeType=x-scheme-handler/hutt;x-scheme-handler/mid;x-scheme-handler/message;x-scheme-handler/mailto;\n"
+        ));
+
+        let entry = desktop_entry(Path::new("/home/me/My Apps/hutt"), &schemes(false));
+        assert!(entry.contains("Exec=\"/home/me/My Apps/hutt\" open %u\n"));
+        assert!(!entry.contains("mailto"));
+        assert_eq!(exec_quote("/opt/100%/hutt"), "\"/opt/100%%/hutt\"");
+    }
+}
@@ -0,0 +1,296 @@
+//! Triage digest: a summary of recent mail for a Monday-morning overview.
+//! Counts messages per sender and per mailing list, reports the unread
+//! backlog, and lists direct messages that still look like they want a
+//! reply. Shown by `hutt digest` and the "Digest" palette entry.
+
+use anyhow::{bail, Result};
+use chrono::{Duration, Local, NaiveDate};
+use std::collections::{HashMap, HashSet};
+
+use crate::envelope::{Envelope, Flag};
+use crate::links::{self, IpcCommand, IpcResponse};
+use crate::mu_client::{FindOpts, MuClient};
+use crate::mu_sexp;
+
+/// Default period covered by a digest.
+pub const DEFAULT_SINCE: &str = "7d";
+
+/// Number of senders and lists shown.
+const TOP: usize = 10;
+
+/// Most messages a digest looks at.
+const MAX_MESSAGES: u32 = 100_000;
+
+/// Parse a period like `7d`, `2w` or `36h` (a bare number means days).
+pub fn parse_since(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let (num, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "d"),
+    };
+    let Ok(n) = num.parse::<i64>() else {
+        bail!("invalid period '{}' (expected e.g. 7d, 2w, 36h)", s);
+    };
+    match unit {
+        "h" => Ok(Duration::hours(n)),
+        "d" => Ok(Duration::days(n)),
+        "w" => Ok(Duration::weeks(n)),
+        _ => bail!("invalid period '{}' (expected e.g. 7d, 2w, 36h)", s),
+    }
+}
+
+/// First day covered by a digest over `period`.
+pub fn start_date(period: Duration) -> NaiveDate {
+    (Local::now() - period).date_naive()
+}
+
+/// mu query for the messages a digest starting on `since` covers.
+pub fn query(since: NaiveDate) -> String {
+    format!("date:{}.. AND NOT flag:trashed", since.format("%Y%m%d"))
+}
+
+/// mu query for the whole unread backlog.
+pub const BACKLOG_QUERY: &str = "flag:unread AND NOT flag:trashed";
+
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub since: NaiveDate,
+    pub total: usize,
+    pub unread: usize,
+    /// Unread messages of any age.
+    pub backlog: u32,
+    /// (sender, count), busiest first.
+    pub senders: Vec<(String, usize)>,
+    /// (list id, count), busiest first.
+    pub lists: Vec<(String, usize)>,
+    /// Unanswered messages addressed to us directly, oldest first.
+    pub needs_reply: Vec<Envelope>,
+}
+
+impl Digest {
+    /// Summarise `envelopes`; `own` are the account's addresses, used to
+    /// skip our own messages and spot mail sent to us directly.
+    pub fn build(envelopes: &[Envelope], own: &[String], since: NaiveDate, backlog: u32) -> Self {
+        let is_own = |email: &str| own.iter().any(|o| o.eq_ignore_ascii_case(email));
+        let mut seen = HashSet::new();
+        let mut senders: HashMap<String, (String, usize)> = HashMap::new();
+        let mut lists: HashMap<String, usize> = HashMap::new();
+        let mut digest = Digest {
+            since,
+            total: 0,
+            unread: 0,
+            backlog,
+            senders: Vec::new(),
+            lists: Vec::new(),
+            needs_reply: Vec::new(),
+        };
+
+        for env in envelopes {
+            if !env.message_id.is_empty() && !seen.insert(env.message_id.as_str()) {
+                continue;
+            }
+            let Some(from) = env.from.first() else {
+                continue;
+            };
+            if is_own(&from.email) || env.flags.contains(&Flag::Draft) {
+                continue;
+            }
+            digest.total += 1;
+            if env.is_unread() {
+                digest.unread += 1;
+            }
+            let entry = senders
+                .entry(from.email.to_lowercase())
+                .or_insert_with(|| (from.to_string(), 0));
+            entry.1 += 1;
+            match env.list.as_deref() {
+                Some(list) => *lists.entry(list.to_string()).or_default() += 1,
+                None => {
+                    let direct = env.to.iter().chain(&env.cc).any(|a| is_own(&a.email));
+                    if direct && !env.is_replied() {
+                        digest.needs_reply.push(env.clone());
+                    }
+                }
+            }
+        }
+
+        digest.senders = top(senders.into_values().collect());
+        digest.lists = top(lists.into_iter().collect());
+        digest.needs_reply.sort_by_key(|e| e.date);
+        digest
+    }
+
+    /// The digest as plain text lines. Section headings are unindented.
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "Since {}: {} messages, {} unread",
+                self.since.format("%a %-d %b %Y"),
+                self.total,
+                self.unread
+            ),
+            format!("Unread backlog: {} messages", self.backlog),
+        ];
+        let mut section = |title: String, rows: &[(String, usize)]| {
+            if rows.is_empty() {
+                return;
+            }
+            lines.push(String::new());
+            lines.push(title);
+            for (name, count) in rows {
+                lines.push(format!("  {:>4}  {}", count, name));
+            }
+        };
+        section("Top senders".to_string(), &self.senders);
+        section("Mailing lists".to_string(), &self.lists);
+
+        lines.push(String::new());
+        if self.needs_reply.is_empty() {
+            lines.push("Needs a reply: nothing".to_string());
+        } else {
+            lines.push(format!("Needs a reply ({})", self.needs_reply.len()));
+            for env in &self.needs_reply {
+                let sender: String = env.sender_display().chars().take(20).collect();
+                lines.push(format!(
+                    "  {}  {:<20}  {}",
+                    env.date.with_timezone(&Local).format("%b %e"),
+                    sender,
+                    env.subject
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// Every message matching a query, unthreaded.
+fn find_opts() -> FindOpts {
+    FindOpts {
+        threads: false,
+        max_num: MAX_MESSAGES,
+        ..FindOpts::default()
+    }
+}
+
+/// Build a digest from `since` using a mu server of our own.
+pub async fn collect(mu: &mut MuClient, own: &[String], since: NaiveDate) -> Result<Digest> {
+    let envelopes = mu.find(&query(since), &find_opts()).await?;
+    let backlog = mu.count(BACKLOG_QUERY, MAX_MESSAGES).await?;
+    Ok(Digest::build(&envelopes, own, since, backlog))
+}
+
+/// Build a digest through a running instance's mu server.
+pub async fn collect_remote(
+    instance: Option<&str>,
+    account: Option<&str>,
+    own: &[String],
+    since: NaiveDate,
+) -> Result<Digest> {
+    let (envelopes, _) = remote_find(instance, account, &query(since)).await?;
+    let (_, backlog) = remote_find(instance, account, BACKLOG_QUERY).await?;
+    Ok(Digest::build(&envelopes, own, since, backlog))
+}
+
+/// Run a find over IPC; returns the envelopes and mu's match count.
+async fn remote_find(
+    instance: Option<&str>,
+    account: Option<&str>,
+    query: &str,
+) -> Result<(Vec<Envelope>, u32)> {
+    let cmd = IpcCommand::MuCommand {
+        sexp: find_opts().to_sexp(query),
+        account: account.map(String::from),
+        muhome: None,
+    };
+    let frames = match links::send_ipc_command(instance, &cmd).await? {
+        IpcResponse::MuFrames { frames } => frames,
+        IpcResponse::Error { message } => bail!("{}", message),
+        IpcResponse::Ok => Vec::new(),
+    };
+    let mut envelopes = Vec::new();
+    let mut found = 0;
+    for frame in &frames {
+        let value = mu_sexp::parse_sexp(frame)?;
+        if let Some(err) = mu_sexp::is_error(&value) {
+            bail!("mu find error: {}", err);
+        }
+        match mu_sexp::is_found(&value) {
+            Some(n) => found = n,
+            None => envelopes.extend(mu_sexp::parse_find_response(&value)?),
+        }
+    }
+    Ok((envelopes, found))
+}
+
+/// Sort by count (then name) and keep the busiest.
+fn top(mut rows: Vec<(String, usize)>) -> Vec<(String, usize)> {
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    rows.truncate(TOP);
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::{flags_from_string, Address};
+    use chrono::{TimeZone, Utc};
+
+    fn addr(email: &str) -> Address {
+        Address {
+            name: None,
+            email: email.to_string(),
+        }
+    }
+
+    fn envelope(id: &str, from: &str, to: &str, list: Option<&str>, flags: &str) -> Envelope {
+        Envelope {
+            message_id: id.to_string(),
+            subject: format!("About {}", id),
+            from: vec![addr(from)],
+            to: vec![addr(to)],
+            list: list.map(String::from),
+            flags: flags_from_string(flags),
+            date: Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap(),
+            ..Envelope::default()
+        }
+    }
+
+    #[test]
+    fn parse_periods() {
+        assert_eq!(parse_since("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_since("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_since("36h").unwrap(), Duration::hours(36));
+        assert_eq!(parse_since("3").unwrap(), Duration::days(3));
+        assert!(parse_since("7x").is_err());
+        assert!(parse_since("d").is_err());
+    }
+
+    #[test]
+    fn build_counts_and_needs_reply() {
+        let me = "me@example.com";
+        let envelopes = vec![
+            envelope("a", "alice@example.com", me, None, ""),
+            envelope("b", "Alice@example.com", me, None, "SR"),
+            envelope("c", "bob@example.com", "dev@lists.example.org", Some("dev.lists.example.org"), "S"),
+            envelope("d", "carol@example.com", "dev@lists.example.org", Some("dev.lists.example.org"), ""),
+            envelope("e", me, "alice@example.com", None, "S"),
+            // Duplicate from another folder
+            envelope("a", "alice@example.com", me, None, ""),
+        ];
+        let since = NaiveDate::from_ymd_opt(2026, 10, 9).unwrap();
+        let digest = Digest::build(&envelopes, &[me.to_string()], since, 42);
+
+        assert_eq!(digest.total, 4);
+        assert_eq!(digest.unread, 2);
+        assert_eq!(digest.senders[0], ("alice@example.com".to_string(), 2));
+        assert_eq!(digest.lists, vec![("dev.lists.example.org".to_string(), 2)]);
+        let ids: Vec<&str> = digest.needs_reply.iter().map(|e| e.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["a"]);
+
+        let text = digest.render().join("\n");
+        assert!(text.starts_with("Since Fri 9 Oct 2026: 4 messages, 2 unread\n"));
+        assert!(text.contains("Unread backlog: 42 messages"));
+        assert!(text.contains("\nNeeds a reply (1)\n"));
+        assert_eq!(query(since), "date:20261009.. AND NOT flag:trashed");
+    }
+}
@@ -1,17 +1,62 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Address {
     pub name: Option<String>,
     pub email: String,
 }
 
 impl Address {
-    /// Name only (for compact list views), falls back to email.
+    /// Name only (for compact list views), falls back to email. A name
+    /// from the address book or config's `display_names` comes first.
     pub fn short_display(&self) -> String {
-        self.name.clone().unwrap_or_else(|| self.email.clone())
+        crate::contacts::display_name(&self.email)
+            .or_else(|| self.name.clone())
+            .unwrap_or_else(|| self.email.clone())
+    }
+
+    /// Name and address as shown in message headers, the name from the
+    /// address book or `display_names` when there is one.
+    pub fn header_display(&self) -> String {
+        match crate::contacts::display_name(&self.email) {
+            Some(name) => format!("{} <{}>", name, self.email),
+            None => self.to_string(),
+        }
+    }
+
+    /// Up to two uppercase initials for an avatar badge: first and last
+    /// word of the name shown for them, or the start of the address's
+    /// local part.
+    pub fn initials(&self) -> String {
+        let name = crate::contacts::display_name(&self.email).or_else(|| self.name.clone());
+        let words: Vec<&str> = name
+            .as_deref()
+            .unwrap_or("")
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|w| w.chars().next().is_some_and(char::is_alphanumeric))
+            .collect();
+        let letters: String = match words.as_slice() {
+            [] => {
+                let local = self.email.split('@').next().unwrap_or("");
+                let mut parts = local.split(['.', '_', '-', '+']).filter(|p| !p.is_empty());
+                match (parts.next(), parts.next()) {
+                    (Some(a), Some(b)) => a.chars().take(1).chain(b.chars().take(1)).collect(),
+                    (Some(a), None) => a.chars().take(2).collect(),
+                    _ => String::new(),
+                }
+            }
+            [only] => only.chars().take(1).collect(),
+            [first, .., last] => first.chars().take(1).chain(last.chars().take(1)).collect(),
+        };
+        if letters.is_empty() {
+            "?".to_string()
+        } else {
+            letters.to_uppercase()
+        }
     }
 }
 
@@ -24,7 +69,7 @@ impl fmt::Display for Address {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Flag {
     Seen,
     Replied,
@@ -34,6 +79,7 @@ pub enum Flag {
     Passed,
     List,
     Unread,
+    Attach,
 }
 
 impl Flag {
@@ -47,6 +93,7 @@ impl Flag {
             "passed" => Some(Flag::Passed),
             "list" => Some(Flag::List),
             "unread" => Some(Flag::Unread),
+            "attach" => Some(Flag::Attach),
             _ => None,
         }
     }
@@ -65,12 +112,44 @@ impl Flag {
     }
 }
 
+/// Message priority, from the `X-Priority` or `Importance` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    /// Parse mu's `:priority` symbol.
+    pub fn from_symbol(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(Priority::Low),
+            "normal" => Some(Priority::Normal),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+
+    /// Parse the value of a compose file's `Priority:` header: a name, or
+    /// an `X-Priority` number (1–2 high, 3 normal, 4–5 low).
+    pub fn from_header(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "high" | "urgent" | "1" | "2" => Some(Priority::High),
+            "normal" | "3" => Some(Priority::Normal),
+            "low" | "4" | "5" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+}
+
 /// Parse a mu flag string (e.g., "SFR") into a Vec<Flag>.
 pub fn flags_from_string(s: &str) -> Vec<Flag> {
     s.chars().filter_map(Flag::from_char).collect()
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct ThreadMeta {
     pub level: u32,
@@ -88,7 +167,7 @@ impl Default for ThreadMeta {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Envelope {
     pub docid: u32,
@@ -96,10 +175,22 @@ pub struct Envelope {
     pub subject: String,
     pub from: Vec<Address>,
     pub to: Vec<Address>,
+    pub cc: Vec<Address>,
+    pub bcc: Vec<Address>,
     pub date: DateTime<Utc>,
     pub flags: Vec<Flag>,
     pub maildir: String,
     pub path: PathBuf,
+    /// Size of the message file in bytes.
+    pub size: usize,
+    /// Mailing list id (List-Id), if the message came from a list.
+    pub list: Option<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Keywords ("todo", "waiting"), from the message's `X-Keywords`,
+    /// `X-Label` or `Keywords` header.
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub thread_meta: ThreadMeta,
 }
 
@@ -111,10 +202,16 @@ impl Default for Envelope {
             subject: String::new(),
             from: Vec::new(),
             to: Vec::new(),
+            cc: Vec::new(),
+            bcc: Vec::new(),
             date: Utc::now(),
             flags: Vec::new(),
             maildir: String::new(),
             path: PathBuf::new(),
+            size: 0,
+            list: None,
+            priority: Priority::Normal,
+            tags: Vec::new(),
             thread_meta: ThreadMeta::default(),
         }
     }
@@ -129,6 +226,22 @@ impl Envelope {
         self.flags.contains(&Flag::Flagged)
     }
 
+    pub fn is_replied(&self) -> bool {
+        self.flags.contains(&Flag::Replied)
+    }
+
+    pub fn is_trashed(&self) -> bool {
+        self.flags.contains(&Flag::Trashed)
+    }
+
+    pub fn has_attachment(&self) -> bool {
+        self.flags.contains(&Flag::Attach)
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        self.priority == Priority::High
+    }
+
     /// Convert flags to mu's single-character flag string format.
     /// D=Draft, F=Flagged, N=New, P=Passed, R=Replied, S=Seen, T=Trashed
     pub fn flags_string(&self) -> String {
@@ -141,7 +254,7 @@ impl Envelope {
                 Flag::Replied => s.push('R'),
                 Flag::Seen => s.push('S'),
                 Flag::Trashed => s.push('T'),
-                Flag::List | Flag::Unread => {} // not single-char mu flags
+                Flag::List | Flag::Unread | Flag::Attach => {} // not single-char mu flags
             }
         }
         s
@@ -155,6 +268,20 @@ impl Envelope {
             .unwrap_or_else(|| "(unknown)".to_string())
     }
 
+    /// The subject less its "Re:" and "Fwd:" prefixes, lowercased: the
+    /// same for a message and the replies and forwards quoting it.
+    pub fn thread_key(&self) -> String {
+        const PREFIXES: [&str; 7] = ["re", "fwd", "fw", "aw", "wg", "sv", "tr"];
+        let mut subject = self.subject.trim();
+        while let Some((prefix, rest)) = subject.split_once(':') {
+            if !PREFIXES.iter().any(|p| p.eq_ignore_ascii_case(prefix.trim())) {
+                break;
+            }
+            subject = rest.trim_start();
+        }
+        subject.to_lowercase()
+    }
+
     pub fn date_display(&self) -> String {
         let now = Utc::now();
         let date = self.date;
@@ -170,6 +297,39 @@ impl Envelope {
     }
 }
 
+/// Keep only the newest message of each thread (by [`Envelope::thread_key`])
+/// in `envelopes`, in their order, except for threads in `expanded`.
+/// Returns them with the hidden messages, keyed by the Message-ID of the
+/// one kept in their place.
+pub fn collapse_near_duplicates(
+    envelopes: Vec<Envelope>,
+    expanded: &HashSet<String>,
+) -> (Vec<Envelope>, HashMap<String, Vec<Envelope>>) {
+    let mut newest: HashMap<String, &Envelope> = HashMap::new();
+    for e in &envelopes {
+        let key = e.thread_key();
+        if key.is_empty() || expanded.contains(&key) {
+            continue;
+        }
+        let current = newest.entry(key).or_insert(e);
+        if e.date > current.date {
+            *current = e;
+        }
+    }
+    let newest: HashMap<String, String> =
+        newest.into_iter().map(|(key, e)| (key, e.message_id.clone())).collect();
+
+    let mut shown = Vec::new();
+    let mut hidden: HashMap<String, Vec<Envelope>> = HashMap::new();
+    for e in envelopes {
+        match newest.get(&e.thread_key()) {
+            Some(kept) if *kept != e.message_id => hidden.entry(kept.clone()).or_default().push(e),
+            _ => shown.push(e),
+        }
+    }
+    (shown, hidden)
+}
+
 // ---------------------------------------------------------------------------
 // Conversations (grouped threads)
 // ---------------------------------------------------------------------------
@@ -294,6 +454,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn address_initials() {
+        let addr = |name: Option<&str>, email: &str| Address {
+            name: name.map(str::to_string),
+            email: email.to_string(),
+        };
+        assert_eq!(addr(Some("Ada Lovelace"), "ada@x").initials(), "AL");
+        assert_eq!(addr(Some("Mary Ann Evans"), "m@x").initials(), "ME");
+        assert_eq!(addr(Some("Lovelace, Ada"), "ada@x").initials(), "LA");
+        assert_eq!(addr(Some("ada"), "ada@x").initials(), "A");
+        assert_eq!(addr(None, "john.smith@example.com").initials(), "JS");
+        assert_eq!(addr(None, "bob@example.com").initials(), "BO");
+        assert_eq!(addr(Some("\"\""), "").initials(), "?");
+    }
+
     #[test]
     fn group_empty() {
         let convos = group_into_conversations(&[]);
@@ -396,4 +571,33 @@ mod tests {
         let convos = group_into_conversations(&envelopes);
         assert_eq!(convos[0].all_docids(), vec![10, 20]);
     }
+
+    #[test]
+    fn near_duplicates_collapse_to_the_newest() {
+        let message = |id: &str, subject: &str, days_ago: i64| Envelope {
+            message_id: id.into(),
+            subject: subject.into(),
+            date: Utc::now() - chrono::Duration::days(days_ago),
+            ..Default::default()
+        };
+        assert_eq!(message("a", "Re: FWD: re:Budget", 0).thread_key(), "budget");
+        assert_eq!(message("a", "Note: budget", 0).thread_key(), "note: budget");
+
+        let envelopes = vec![
+            message("original", "Budget", 3),
+            message("other", "Lunch", 2),
+            message("reply", "Re: Budget", 1),
+            message("untitled", "", 1),
+            message("blank", "", 0),
+        ];
+        let (shown, hidden) = collapse_near_duplicates(envelopes.clone(), &HashSet::new());
+        let ids: Vec<&str> = shown.iter().map(|e| e.message_id.as_str()).collect();
+        assert_eq!(ids, ["other", "reply", "untitled", "blank"]);
+        assert_eq!(hidden["reply"][0].message_id, "original");
+
+        let expanded = HashSet::from(["budget".to_string()]);
+        let (shown, hidden) = collapse_near_duplicates(envelopes, &expanded);
+        assert_eq!(shown.len(), 5);
+        assert!(hidden.is_empty());
+    }
 }
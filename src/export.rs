@@ -0,0 +1,180 @@
+//! Exporting a set of messages for safekeeping elsewhere (legal holds,
+//! backups, moving a topic to another system): one `.eml` file per
+//! message in a directory, or a single mbox. Existing files are never
+//! overwritten.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::maildir;
+
+/// Where an export goes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// A directory of `.eml` files, created if missing.
+    Directory(PathBuf),
+    /// One new mbox file.
+    Mbox(PathBuf),
+}
+
+impl Target {
+    /// The target typed as `input`: a path ending in `.mbox` is an mbox
+    /// file, anything else a directory. `None` when empty.
+    pub fn parse(input: &str) -> Option<Target> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        let path = PathBuf::from(crate::config::expand_tilde(input));
+        if input.ends_with(".mbox") {
+            Some(Target::Mbox(path))
+        } else {
+            Some(Target::Directory(path))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Target::Directory(path) | Target::Mbox(path) => path,
+        }
+    }
+}
+
+/// File name for the `index`th (from 0) of `total` exported messages:
+/// a zero-padded number, so the files list in export order, then the
+/// subject lowercased with non-alphanumerics as dashes.
+pub fn eml_name(index: usize, total: usize, subject: &str) -> String {
+    let mut slug = String::new();
+    for c in subject.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_matches('-').chars().take(48).collect();
+    let slug = slug.trim_end_matches('-');
+    let digits = total.to_string().len().max(4);
+    if slug.is_empty() {
+        format!("{:0digits$}.eml", index + 1)
+    } else {
+        format!("{:0digits$}-{}.eml", index + 1, slug)
+    }
+}
+
+/// The progress line shown while exporting.
+pub fn progress_line(done: usize, total: usize) -> String {
+    const BAR: usize = 20;
+    let filled = (done * BAR).checked_div(total).unwrap_or(BAR);
+    format!(
+        " Exporting {}/{} \u{2595}{}{}\u{258f} {}% | Esc:cancel",
+        done,
+        total,
+        "\u{2588}".repeat(filled),
+        "\u{2591}".repeat(BAR - filled),
+        (done * 100).checked_div(total).unwrap_or(100)
+    )
+}
+
+/// An export in progress.
+pub struct Export {
+    target: Target,
+    total: usize,
+    written: usize,
+    mbox: Option<File>,
+}
+
+impl Export {
+    /// Start exporting `total` messages to `target`, creating its
+    /// directory or mbox file. An existing mbox is an error.
+    pub fn start(target: Target, total: usize) -> Result<Export> {
+        let mbox = match &target {
+            Target::Directory(dir) => {
+                std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+                None
+            }
+            Target::Mbox(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("creating {}", parent.display()))?;
+                }
+                let file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)
+                    .with_context(|| format!("creating {}", path.display()))?;
+                Some(file)
+            }
+        };
+        Ok(Export { target, total, written: 0, mbox })
+    }
+
+    /// Add the next message.
+    pub fn add(&mut self, raw: &[u8], subject: &str) -> Result<()> {
+        match (&self.target, self.mbox.as_mut()) {
+            (Target::Mbox(path), Some(file)) => {
+                file.write_all(&maildir::mbox(&[raw.to_vec()]))
+                    .with_context(|| format!("writing {}", path.display()))?;
+            }
+            (target, _) => {
+                let path = target.path().join(eml_name(self.written, self.total, subject));
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)
+                    .with_context(|| format!("creating {}", path.display()))?;
+                file.write_all(raw).with_context(|| format!("writing {}", path.display()))?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Flush the mbox to disk. Returns how many messages were written.
+    pub fn finish(self) -> Result<usize> {
+        if let Some(file) = self.mbox {
+            file.sync_all().with_context(|| format!("writing {}", self.target.path().display()))?;
+        }
+        Ok(self.written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_eml_files_and_mbox() {
+        assert_eq!(eml_name(0, 12, "Re: Q3 report (final)"), "0001-re-q3-report-final.eml");
+        assert_eq!(eml_name(41, 12000, "!!!"), "00042.eml");
+        assert_eq!(Target::parse("  "), None);
+        assert!(matches!(Target::parse("/tmp/hold.mbox"), Some(Target::Mbox(_))));
+        assert_eq!(progress_line(5, 20), format!(" Exporting 5/20 \u{2595}{}{}\u{258f} 25% | Esc:cancel", "\u{2588}".repeat(5), "\u{2591}".repeat(15)));
+
+        let dir = std::env::temp_dir().join(format!("hutt-export-{}", std::process::id()));
+        let messages: [(&[u8], &str); 2] = [(b"Subject: One\n\nFrom here on\n", "One"), (b"Subject: Two\n\nHi\n", "Two")];
+
+        let mut export = Export::start(Target::Directory(dir.join("eml")), 2).unwrap();
+        for (raw, subject) in messages {
+            export.add(raw, subject).unwrap();
+        }
+        assert_eq!(export.finish().unwrap(), 2);
+        assert_eq!(std::fs::read(dir.join("eml/0002-two.eml")).unwrap(), b"Subject: Two\n\nHi\n");
+
+        let mbox = dir.join("hold.mbox");
+        let mut export = Export::start(Target::Mbox(mbox.clone()), 2).unwrap();
+        for (raw, subject) in messages {
+            export.add(raw, subject).unwrap();
+        }
+        export.finish().unwrap();
+        let text = std::fs::read_to_string(&mbox).unwrap();
+        assert_eq!(text.matches("From MAILER-DAEMON").count(), 2);
+        assert!(text.contains("\n>From here on\n"));
+        // Never overwritten
+        assert!(Export::start(Target::Mbox(mbox), 1).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
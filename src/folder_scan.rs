@@ -0,0 +1,176 @@
+//! Finding every folder in a maildir tree, off the UI thread. Huge trees
+//! take seconds to walk, so the walk runs in the background, each
+//! top-level directory on its own thread, and what it finds is merged
+//! into the folder list when it's done.
+//!
+//! Each walk is cached with the mtime of every directory it listed. A
+//! folder created or removed anywhere changes its parent's mtime, so while
+//! they all still match, the cached folders are returned after a stat of
+//! each directory rather than a listing. Messages arriving only touch
+//! `cur`/`new`/`tmp`, which are never listed.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::maildir::{self, Responsiveness};
+
+/// A maildir to find the folders of.
+#[derive(Debug)]
+pub struct ScanRequest {
+    pub account_idx: usize,
+    pub root: PathBuf,
+}
+
+/// The folders found under an account's maildir, as "/Name".
+#[derive(Debug)]
+pub struct FolderScan {
+    pub account_idx: usize,
+    /// How the maildir answered first; slow or offline ones aren't walked.
+    pub storage: Responsiveness,
+    pub folders: Vec<String>,
+}
+
+/// One walk of a maildir tree.
+#[derive(Debug, Default)]
+struct FolderTree {
+    /// Every directory listed, with its mtime then.
+    dirs: Vec<(PathBuf, SystemTime)>,
+    folders: Vec<String>,
+}
+
+fn modified(dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+impl FolderTree {
+    /// Whether no directory has changed since the walk.
+    fn fresh(&self) -> bool {
+        !self.dirs.is_empty() && self.dirs.iter().all(|(dir, time)| modified(dir) == Some(*time))
+    }
+
+    /// Walk the tree under `root`, each top-level directory in parallel.
+    fn walk(root: &Path) -> Self {
+        let mut tree = FolderTree::default();
+        let top = tree.list(root, root);
+        let subtrees: Vec<FolderTree> = std::thread::scope(|scope| {
+            let walkers: Vec<_> = top
+                .into_iter()
+                .map(|dir| scope.spawn(move || FolderTree::walk_from(root, dir)))
+                .collect();
+            walkers.into_iter().filter_map(|w| w.join().ok()).collect()
+        });
+        for subtree in subtrees {
+            tree.dirs.extend(subtree.dirs);
+            tree.folders.extend(subtree.folders);
+        }
+        tree.folders.sort();
+        tree
+    }
+
+    fn walk_from(root: &Path, start: PathBuf) -> Self {
+        let mut tree = FolderTree::default();
+        let mut stack = vec![start];
+        while let Some(dir) = stack.pop() {
+            stack.extend(tree.list(root, &dir));
+        }
+        tree
+    }
+
+    /// Record `dir` (and the folder it is, if it's a maildir below the
+    /// root) and return its subdirectories, less a maildir's own
+    /// `cur`/`new`/`tmp`.
+    fn list(&mut self, root: &Path, dir: &Path) -> Vec<PathBuf> {
+        let Some(time) = modified(dir) else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        self.dirs.push((dir.to_path_buf(), time));
+        let is_maildir = dir.join("cur").is_dir();
+        if is_maildir && dir != root {
+            if let Ok(rel) = dir.strip_prefix(root) {
+                let name = rel.to_string_lossy();
+                self.folders.push(format!("/{}", name.strip_prefix('.').unwrap_or(&name)));
+            }
+        }
+        entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter(|e| !(is_maildir && matches!(e.file_name().to_str(), Some("cur" | "new" | "tmp"))))
+            .map(|e| e.path())
+            .collect()
+    }
+}
+
+/// The folders under `root`, from `cache` when nothing has changed.
+fn folders(cache: &mut HashMap<PathBuf, FolderTree>, root: &Path) -> Vec<String> {
+    match cache.get(root) {
+        Some(tree) if tree.fresh() => tree.folders.clone(),
+        _ => {
+            let tree = FolderTree::walk(root);
+            let folders = tree.folders.clone();
+            cache.insert(root.to_path_buf(), tree);
+            folders
+        }
+    }
+}
+
+/// Start the background folder scanner. Send it a maildir; its folders
+/// come back on the receiver.
+pub fn spawn_scanner() -> (UnboundedSender<ScanRequest>, UnboundedReceiver<FolderScan>) {
+    let (req_tx, mut req_rx) = unbounded_channel::<ScanRequest>();
+    let (res_tx, res_rx) = unbounded_channel();
+    tokio::spawn(async move {
+        let mut cache: HashMap<PathBuf, FolderTree> = HashMap::new();
+        while let Some(req) = req_rx.recv().await {
+            let scan = tokio::task::spawn_blocking(move || {
+                let storage = maildir::probe(&req.root);
+                let folders = match storage {
+                    Responsiveness::Fine => folders(&mut cache, &req.root),
+                    _ => Vec::new(),
+                };
+                (FolderScan { account_idx: req.account_idx, storage, folders }, cache)
+            })
+            .await;
+            let Ok((scan, kept)) = scan else {
+                break;
+            };
+            cache = kept;
+            if res_tx.send(scan).is_err() {
+                break;
+            }
+        }
+    });
+    (req_tx, res_rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_and_caches_folder_trees() {
+        let root = std::env::temp_dir().join(format!("hutt-folder-scan-{}", std::process::id()));
+        for folder in ["/", "Inbox", ".Sent", "Lists/rust", "Lists/rust/announce"] {
+            maildir::create_folder(&root, folder).unwrap();
+        }
+        std::fs::create_dir_all(root.join("Inbox/cur/not-a-folder/cur")).unwrap();
+
+        let mut cache = HashMap::new();
+        let expected = ["/Inbox", "/Lists/rust", "/Lists/rust/announce", "/Sent"];
+        assert_eq!(folders(&mut cache, &root), expected);
+        assert!(cache[&root].fresh());
+        assert!(!cache[&root].dirs.iter().any(|(d, _)| d.ends_with("cur")));
+        assert_eq!(folders(&mut cache, &root), expected);
+
+        // A new folder changes its parent's mtime
+        maildir::create_folder(&root, "Lists/go").unwrap();
+        assert!(!cache[&root].fresh());
+        assert!(folders(&mut cache, &root).contains(&"/Lists/go".to_string()));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
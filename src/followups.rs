@@ -0,0 +1,218 @@
+//! Follow-up reminders: messages marked as awaiting a reply by a date.
+//! Overdue ones are shown in the built-in `@Waiting` smart folder, and a
+//! reminder clears itself once a correspondent replies in the thread.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::envelope::Envelope;
+
+/// A mu query that matches nothing, for `@Waiting` with no overdue reminders.
+pub const NO_MATCH_QUERY: &str = "msgid:none@hutt.invalid";
+
+/// A message waiting for a reply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FollowUp {
+    pub message_id: String,
+    #[serde(default)]
+    pub subject: String,
+    /// Date of the message itself; only later replies count.
+    pub sent: DateTime<Utc>,
+    /// Reply expected by the end of this day.
+    pub due: NaiveDate,
+    /// Addresses whose reply clears the reminder (lowercase).
+    pub correspondents: Vec<String>,
+}
+
+impl FollowUp {
+    /// A reminder for `envelope`, clearing on a reply from anyone on it
+    /// other than `own_email`.
+    pub fn new(envelope: &Envelope, due: NaiveDate, own_email: &str) -> Self {
+        let mut correspondents: Vec<String> = envelope
+            .from
+            .iter()
+            .chain(&envelope.to)
+            .chain(&envelope.cc)
+            .map(|a| a.email.to_lowercase())
+            .filter(|e| !e.eq_ignore_ascii_case(own_email))
+            .collect();
+        correspondents.sort();
+        correspondents.dedup();
+        Self {
+            message_id: envelope.message_id.clone(),
+            subject: envelope.subject.clone(),
+            sent: envelope.date,
+            due,
+            correspondents,
+        }
+    }
+
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.due < today
+    }
+
+    /// Whether `thread` contains a reply to this message from one of its
+    /// correspondents.
+    pub fn has_reply(&self, thread: &[Envelope]) -> bool {
+        thread.iter().any(|e| {
+            e.message_id != self.message_id
+                && e.date > self.sent
+                && e.from
+                    .iter()
+                    .any(|a| self.correspondents.contains(&a.email.to_lowercase()))
+        })
+    }
+}
+
+/// Parse a due date typed by the user: `3d`, `2w`, `today`, `tomorrow`,
+/// or an ISO date like `2026-10-20`.
+pub fn parse_due(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let (count, unit_days) = if let Some(count) = input.strip_suffix('d') {
+        (count, 1)
+    } else if let Some(count) = input.strip_suffix('w') {
+        (count, 7)
+    } else {
+        return None;
+    };
+    let count: i64 = count.parse().ok()?;
+    Some(today + Duration::days(count * unit_days))
+}
+
+/// Local calendar date, for due-date comparisons.
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// The `@Waiting` query: the overdue reminders' messages.
+pub fn waiting_query(followups: &[FollowUp], today: NaiveDate) -> String {
+    let terms: Vec<String> = followups
+        .iter()
+        .filter(|f| f.is_overdue(today))
+        .map(|f| format!("msgid:\"{}\"", f.message_id))
+        .collect();
+    if terms.is_empty() {
+        NO_MATCH_QUERY.to_string()
+    } else {
+        terms.join(" OR ")
+    }
+}
+
+/// Return the path to the follow-up reminders for a given account name.
+///
+/// Per-account files: `followups.<account_name>.json`, or plain
+/// `followups.json` when `account_name` is empty.
+pub fn followups_path(account_name: &str) -> PathBuf {
+    let dir = crate::paths::state_dir();
+    if account_name.is_empty() {
+        dir.join("followups.json")
+    } else {
+        dir.join(format!("followups.{}.json", account_name))
+    }
+}
+
+/// Load an account's reminders. A missing or unreadable file yields none.
+pub fn load_followups(account_name: &str) -> Vec<FollowUp> {
+    load_followups_from(&followups_path(account_name))
+}
+
+fn load_followups_from(path: &Path) -> Vec<FollowUp> {
+    crate::state_crypt::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Save an account's reminders. Creates parent directories if needed.
+pub fn save_followups(followups: &[FollowUp], account_name: &str) {
+    save_followups_to(followups, &followups_path(account_name));
+}
+
+fn save_followups_to(followups: &[FollowUp], path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string_pretty(followups) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::Address;
+    use chrono::TimeZone;
+
+    fn addr(email: &str) -> Address {
+        Address { name: None, email: email.to_string() }
+    }
+
+    fn envelope(id: &str, from: &str, to: &str, day: u32) -> Envelope {
+        Envelope {
+            message_id: id.to_string(),
+            from: vec![addr(from)],
+            to: vec![addr(to)],
+            date: Utc.with_ymd_and_hms(2026, 10, day, 12, 0, 0).unwrap(),
+            ..Envelope::default()
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    #[test]
+    fn parse_due_dates() {
+        let today = date(16);
+        assert_eq!(parse_due("3d", today), Some(date(19)));
+        assert_eq!(parse_due(" 1W ", today), Some(date(23)));
+        assert_eq!(parse_due("tomorrow", today), Some(date(17)));
+        assert_eq!(parse_due("2026-10-30", today), Some(date(30)));
+        assert_eq!(parse_due("soon", today), None);
+        assert_eq!(parse_due("", today), None);
+        assert_eq!(parse_due("3é", today), None);
+        assert_eq!(parse_due("é", today), None);
+    }
+
+    #[test]
+    fn reply_from_correspondent_clears() {
+        let sent = envelope("ask@me", "me@example.com", "Bob@Example.com", 10);
+        let followup = FollowUp::new(&sent, date(13), "me@example.com");
+        assert_eq!(followup.correspondents, vec!["bob@example.com"]);
+
+        let mine = envelope("nudge@me", "me@example.com", "bob@example.com", 12);
+        let earlier = envelope("old@bob", "bob@example.com", "me@example.com", 9);
+        assert!(!followup.has_reply(&[sent.clone(), mine.clone(), earlier]));
+        let reply = envelope("re@bob", "bob@example.com", "me@example.com", 14);
+        assert!(followup.has_reply(&[sent, mine, reply]));
+    }
+
+    #[test]
+    fn waiting_query_lists_overdue() {
+        let a = FollowUp::new(&envelope("a@x", "me@x", "bob@x", 1), date(10), "me@x");
+        let b = FollowUp::new(&envelope("b@x", "me@x", "bob@x", 1), date(20), "me@x");
+        let list = vec![a, b];
+        assert_eq!(waiting_query(&list, date(16)), "msgid:\"a@x\"");
+        assert_eq!(waiting_query(&list, date(5)), NO_MATCH_QUERY);
+    }
+
+    #[test]
+    fn followups_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("hutt-followups-{}", std::process::id()));
+        let path = dir.join("followups.work.json");
+        let list = vec![FollowUp::new(&envelope("a@x", "me@x", "bob@x", 1), date(10), "me@x")];
+        save_followups_to(&list, &path);
+        assert_eq!(load_followups_from(&path), list);
+        assert!(load_followups_from(&dir.join("missing.json")).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,167 @@
+//! The startup health check: problems with the setup that would otherwise
+//! surface mid-session as cryptic errors — an old mu, a missing or
+//! read-only maildir, SMTP settings that can't work, a clock that's off —
+//! found once at launch and shown in a banner until dismissed.
+
+use chrono::{DateTime, Duration, Utc};
+use std::path::Path;
+
+use crate::config::{AccountConfig, Config, SmtpConfig};
+
+/// Oldest mu with the threading hutt relies on.
+const MIN_MU: (u32, u32) = (1, 8);
+
+/// How far ahead of the clock recent mail must be dated before the clock
+/// is blamed rather than the senders'.
+const SKEW_MARGIN_MINUTES: i64 = 10;
+
+/// Every problem found with `config`'s setup, one line each.
+pub fn check(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(problem) = mu_problem(mu_version().as_deref()) {
+        problems.push(problem);
+    }
+    for account in &config.accounts {
+        let label = account_label(config, account);
+        let maildir = crate::config::expand_tilde(&account.maildir);
+        if let Some(problem) = maildir_problem(Path::new(&maildir)) {
+            problems.push(format!("{}maildir {}: {}", label, account.maildir, problem));
+        }
+        if let Some(problem) = smtp_problem(&account.smtp) {
+            problems.push(format!("{}SMTP: {}", label, problem));
+        }
+    }
+    problems
+}
+
+/// "work: " when there are several accounts to tell apart.
+fn account_label(config: &Config, account: &AccountConfig) -> String {
+    if config.accounts.len() > 1 {
+        format!("{}: ", account.name)
+    } else {
+        String::new()
+    }
+}
+
+/// What `mu --version` prints, or `None` when mu can't be run.
+fn mu_version() -> Option<String> {
+    let output = std::process::Command::new("mu").arg("--version").output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The version in `mu --version` output, as (major, minor).
+fn parse_mu_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.split_whitespace().skip_while(|w| *w != "version").nth(1)?;
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??))
+}
+
+fn mu_problem(output: Option<&str>) -> Option<String> {
+    let Some(output) = output else {
+        return Some("mu not found; install mu 1.8+ and make sure it's on PATH".to_string());
+    };
+    match parse_mu_version(output) {
+        Some(version) if version < MIN_MU => Some(format!(
+            "mu {}.{} detected; {}.{}+ required for threads",
+            version.0, version.1, MIN_MU.0, MIN_MU.1
+        )),
+        _ => None,
+    }
+}
+
+fn maildir_problem(path: &Path) -> Option<String> {
+    if !path.exists() {
+        return Some("does not exist".to_string());
+    }
+    if !path.is_dir() {
+        return Some("is not a directory".to_string());
+    }
+    let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_encoded_bytes()) else {
+        return None;
+    };
+    let ret = unsafe { libc::access(c_path.as_ptr(), libc::R_OK | libc::W_OK | libc::X_OK) };
+    (ret != 0).then(|| "not readable and writable, so mail can't be moved or flagged".to_string())
+}
+
+fn smtp_problem(smtp: &SmtpConfig) -> Option<String> {
+    if smtp.host.trim().is_empty() {
+        return Some("no host set".to_string());
+    }
+    if smtp.port == 0 {
+        return Some("no port set".to_string());
+    }
+    if !["starttls", "ssl", "none"].contains(&smtp.encryption.as_str()) {
+        return Some(format!(
+            "encryption \"{}\" isn't one of starttls, ssl or none",
+            smtp.encryption
+        ));
+    }
+    if smtp.password.is_none() && smtp.password_command.is_none() {
+        return Some("no password or password_command, so mail can't be sent".to_string());
+    }
+    None
+}
+
+/// A warning when the newest of `dates` are mostly dated well after
+/// `now`: mail from everyone arriving from the future means the local
+/// clock is behind.
+pub fn clock_problem(dates: &[DateTime<Utc>], now: DateTime<Utc>) -> Option<String> {
+    let mut newest = dates.to_vec();
+    newest.sort_unstable_by(|a, b| b.cmp(a));
+    newest.truncate(10);
+    let margin = Duration::minutes(SKEW_MARGIN_MINUTES);
+    let ahead: Vec<Duration> = newest.iter().map(|d| *d - now).filter(|lead| *lead > margin).collect();
+    if ahead.len() < 3 || ahead.len() * 2 <= newest.len() {
+        return None;
+    }
+    let lead = ahead.iter().min()?;
+    let behind = if lead.num_hours() > 0 {
+        format!("{}h", lead.num_hours())
+    } else {
+        format!("{}m", lead.num_minutes())
+    };
+    Some(format!(
+        "System clock looks at least {} behind: recent mail is dated in the future",
+        behind
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_setup_problems() {
+        let old = "mu (mail indexer / searcher) version 1.6.11\nCopyright (C) 2008-2021";
+        assert_eq!(parse_mu_version(old), Some((1, 6)));
+        assert_eq!(mu_problem(Some(old)).as_deref(), Some("mu 1.6 detected; 1.8+ required for threads"));
+        assert_eq!(mu_problem(Some("mu (mail indexer / searcher) version 1.12.4")), None);
+        assert!(mu_problem(None).unwrap().starts_with("mu not found"));
+
+        let dir = std::env::temp_dir().join(format!("hutt-health-{}", std::process::id()));
+        assert_eq!(maildir_problem(&dir).as_deref(), Some("does not exist"));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(maildir_problem(&dir), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut smtp = SmtpConfig { password_command: Some("pass smtp".into()), ..SmtpConfig::default() };
+        assert_eq!(smtp_problem(&smtp), None);
+        smtp.encryption = "tls".into();
+        assert!(smtp_problem(&smtp).unwrap().contains("\"tls\""));
+        smtp.encryption = "ssl".into();
+        smtp.password_command = None;
+        assert!(smtp_problem(&smtp).unwrap().starts_with("no password"));
+
+        let now = Utc::now();
+        let mut dates: Vec<DateTime<Utc>> = (0..4).map(|i| now + Duration::hours(2) + Duration::minutes(i)).collect();
+        dates.push(now - Duration::hours(1));
+        assert_eq!(
+            clock_problem(&dates, now).as_deref(),
+            Some("System clock looks at least 2h behind: recent mail is dated in the future")
+        );
+        // One sender's clock being off isn't ours
+        dates.truncate(2);
+        dates.extend((1..5).map(|i| now - Duration::hours(i)));
+        assert_eq!(clock_problem(&dates, now), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! Triage done while mu is indexing. Docids shift under a running index,
+//! so the messages are kept by Message-ID (with the maildir they were in)
+//! and the change is made once the index completes. Changes still held
+//! when hutt quits before the index does are saved, and made on the next
+//! start; those held when switching to another account are saved too,
+//! and made when the account is next active.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::envelope::Envelope;
+
+/// One held-back triage action. `id` ties it to its undo entry, so undoing
+/// it before the index completes drops it instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DeferredTriage {
+    /// Move to `dest`, or with `remove`, drop from the index (Gmail archive).
+    Move {
+        id: u64,
+        dest: String,
+        remove: bool,
+        desc: String,
+        messages: Vec<(String, String)>,
+        /// The rows taken out of the list, put back if it's undone.
+        #[serde(skip)]
+        envelopes: Vec<Envelope>,
+    },
+    /// Set or clear `flag`, per message.
+    Flag {
+        id: u64,
+        flag: char,
+        desc: String,
+        messages: Vec<(String, String, bool)>,
+    },
+}
+
+impl DeferredTriage {
+    pub fn id(&self) -> u64 {
+        match self {
+            DeferredTriage::Move { id, .. } | DeferredTriage::Flag { id, .. } => *id,
+        }
+    }
+}
+
+/// Return the path to the held changes for a given account name.
+///
+/// Per-account files: `held_triage.<account_name>.json`, or plain
+/// `held_triage.json` when `account_name` is empty.
+pub fn held_triage_path(account_name: &str) -> PathBuf {
+    held_triage_file(&crate::paths::state_dir(), account_name)
+}
+
+fn held_triage_file(dir: &Path, account_name: &str) -> PathBuf {
+    if account_name.is_empty() {
+        dir.join("held_triage.json")
+    } else {
+        dir.join(format!("held_triage.{}.json", account_name))
+    }
+}
+
+/// Save the changes held for an account whose reindex is being abandoned
+/// (on switching away from it), adding to any saved already, and forget
+/// them here. They're made when the account is next active.
+pub fn set_aside(held: &mut Vec<DeferredTriage>, account_name: &str) {
+    set_aside_in(held, &crate::paths::state_dir(), account_name);
+}
+
+fn set_aside_in(held: &mut Vec<DeferredTriage>, dir: &Path, account_name: &str) {
+    if held.is_empty() {
+        return;
+    }
+    let path = held_triage_file(dir, account_name);
+    let mut saved = take_held_triage_from(&path);
+    saved.append(held);
+    save_held_triage_to(&saved, &path);
+}
+
+/// Take an account's saved changes, removing the file so they are made
+/// only once. A missing or unreadable file yields none.
+pub fn take_held_triage(account_name: &str) -> Vec<DeferredTriage> {
+    take_held_triage_from(&held_triage_path(account_name))
+}
+
+fn take_held_triage_from(path: &Path) -> Vec<DeferredTriage> {
+    let Ok(contents) = crate::state_crypt::read_to_string(path) else {
+        return Vec::new();
+    };
+    let _ = std::fs::remove_file(path);
+    serde_json::from_str(&contents).unwrap_or_default()
+}
+
+/// Save changes that couldn't be made before quitting. Creates parent
+/// directories if needed.
+pub fn save_held_triage(held: &[DeferredTriage], account_name: &str) {
+    save_held_triage_to(held, &held_triage_path(account_name));
+}
+
+fn save_held_triage_to(held: &[DeferredTriage], path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string(held) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_triage_is_taken_once() {
+        let dir = std::env::temp_dir().join(format!("hutt-held-{}", std::process::id()));
+        let path = dir.join("held_triage.work.json");
+        let held = vec![
+            DeferredTriage::Move {
+                id: 1,
+                dest: "/Archive".into(),
+                remove: false,
+                desc: "Archive".into(),
+                messages: vec![("a@x".into(), "/Inbox".into())],
+                envelopes: vec![Envelope::default()],
+            },
+            DeferredTriage::Flag { id: 2, flag: 'F', desc: "flag".into(), messages: vec![("b@x".into(), "/Inbox".into(), true)] },
+        ];
+        save_held_triage_to(&held, &path);
+        let taken = take_held_triage_from(&path);
+        assert_eq!(taken.iter().map(DeferredTriage::id).collect::<Vec<_>>(), [1, 2]);
+        assert!(matches!(&taken[0], DeferredTriage::Move { envelopes, .. } if envelopes.is_empty()));
+        assert!(!path.exists());
+        assert!(take_held_triage_from(&path).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn held_triage_stays_with_its_account() {
+        let dir = std::env::temp_dir().join(format!("hutt-held-switch-{}", std::process::id()));
+        let flag = |id: u64| DeferredTriage::Flag { id, flag: 'S', desc: "read".into(), messages: Vec::new() };
+        let mut held = vec![flag(1)];
+        set_aside_in(&mut held, &dir, "work");
+        assert!(held.is_empty());
+        // Switching away from it again adds to what was saved
+        let mut held = vec![flag(2)];
+        set_aside_in(&mut held, &dir, "work");
+        assert!(take_held_triage_from(&held_triage_file(&dir, "home")).is_empty());
+        let work = take_held_triage_from(&held_triage_file(&dir, "work"));
+        assert_eq!(work.iter().map(DeferredTriage::id).collect::<Vec<_>>(), [1, 2]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,315 @@
+//! Optional localhost HTTP API mirroring the IPC commands, for tools that
+//! can't speak Unix sockets (browser extensions, an "open in hutt"
+//! bookmarklet). Requests go through the same `IpcCommand` dispatch as the
+//! socket; responses are the JSON that `hutt r --json --wrapped` prints.
+//! `POST /compose` with a JSON body takes a full `ComposeRequest`, for
+//! extensions that intercept mailto: links.
+//!
+//! Any web page can make the browser send requests to localhost, so every
+//! request must carry the configured token, as `?token=` or an
+//! `Authorization: Bearer` header.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::compose::ComposeRequest;
+use crate::config::HttpApi;
+use crate::links::{self, HuttUrlSerde, IpcCommand, IpcReply, IpcResponse};
+use crate::mu_sexp;
+
+/// Largest request we read, head and body.
+const MAX_REQUEST: usize = 1024 * 1024;
+
+/// Bind the API's listener on 127.0.0.1.
+pub async fn bind(api: &HttpApi) -> Result<TcpListener> {
+    if api.token.trim().is_empty() {
+        bail!("http_api.token must be set");
+    }
+    TcpListener::bind(("127.0.0.1", api.port))
+        .await
+        .with_context(|| format!("binding 127.0.0.1:{}", api.port))
+}
+
+/// Accept connections forever, passing commands to the run loop over `tx`.
+pub async fn serve(
+    listener: TcpListener,
+    api: HttpApi,
+    tx: UnboundedSender<(IpcCommand, Option<IpcReply>)>,
+) {
+    loop {
+        let Ok((stream, _addr)) = listener.accept().await else {
+            continue;
+        };
+        let tx = tx.clone();
+        let token = api.token.clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, &token, tx).await;
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    token: &str,
+    tx: UnboundedSender<(IpcCommand, Option<IpcReply>)>,
+) -> Result<()> {
+    let request = tokio::time::timeout(Duration::from_secs(5), read_request(&mut stream))
+        .await
+        .context("timed out reading request")??;
+    let (status, body) = match respond(&request, token) {
+        Ok(cmd) => {
+            let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+            if tx.send((cmd, Some(IpcReply::Channel(reply_tx)))).is_err() {
+                (503, error_json("hutt is shutting down"))
+            } else {
+                match reply_rx.await {
+                    Ok(resp @ IpcResponse::Error { .. }) => (400, response_json(&resp)),
+                    Ok(resp) => (200, response_json(&resp)),
+                    Err(_) => (503, error_json("no response from hutt")),
+                }
+            }
+        }
+        Err((status, message)) => (status, error_json(&message)),
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Read the request line, headers and any body (by Content-Length).
+async fn read_request(stream: &mut TcpStream) -> Result<String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+    let mut body_start = None;
+    loop {
+        if body_start.is_none() {
+            body_start = buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4);
+        }
+        if let Some(start) = body_start {
+            let head = String::from_utf8_lossy(&buf[..start]);
+            let length = header(&head, "content-length")
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(0);
+            if buf.len() >= start + length {
+                break;
+            }
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.len() > MAX_REQUEST {
+            bail!("request too large");
+        }
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Value of a header in a request head, matched case-insensitively.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .skip(1)
+        .take_while(|l| !l.is_empty())
+        .filter_map(|l| l.split_once(':'))
+        .find(|(n, _)| n.trim().eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim())
+}
+
+/// Check a request and turn it into an IPC command, or an HTTP status and
+/// error message.
+fn respond(request: &str, token: &str) -> Result<IpcCommand, (u16, String)> {
+    let (head, body) = request.split_once("\r\n\r\n").unwrap_or((request, ""));
+    let request_line = head.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or("/"));
+    if method != "GET" && method != "POST" {
+        return Err((405, format!("unsupported method {}", method)));
+    }
+    let (path, qs) = links::split_query(target);
+    let params = links::parse_query_string(qs);
+
+    let bearer = header(head, "authorization")
+        .and_then(|value| value.strip_prefix("Bearer ").map(str::trim));
+    let given = bearer.or(params.get("token").map(String::as_str));
+    if given != Some(token) {
+        return Err((401, "missing or wrong token".to_string()));
+    }
+    // A JSON body on /compose is a full compose request
+    if path == "/compose" && method == "POST" && !body.trim().is_empty() {
+        let req: ComposeRequest = serde_json::from_str(body)
+            .map_err(|e| (400, format!("invalid compose request: {}", e)))?;
+        return Ok(IpcCommand::Compose(req));
+    }
+    route(path, &params)
+}
+
+/// Map an API path and its parameters to an IPC command.
+fn route(path: &str, params: &HashMap<String, String>) -> Result<IpcCommand, (u16, String)> {
+    let param = |name: &str| -> Result<String, (u16, String)> {
+        params
+            .get(name)
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .ok_or_else(|| (400, format!("{} requires {}=", path, name)))
+    };
+    let account = params.get("account").cloned();
+    let cmd = match path {
+        "/open" => {
+            let url = param("url")?;
+            if let Some((folder, account)) = links::parse_navigate_url(&url) {
+                IpcCommand::Navigate { folder, account }
+            } else if let Some(parsed) = links::parse_url(&url) {
+                IpcCommand::Open(parsed.into())
+            } else {
+                return Err((400, format!("unrecognized URI: {}", url)));
+            }
+        }
+        "/search" => IpcCommand::Open(HuttUrlSerde::Search { query: param("q")?, account }),
+        "/navigate" => IpcCommand::Navigate { folder: param("folder")?, account },
+        "/message" => IpcCommand::Open(HuttUrlSerde::Message { id: param("id")?, account }),
+        "/thread" => IpcCommand::Open(HuttUrlSerde::Thread { id: param("id")?, account }),
+        "/compose" => {
+            let fields = params.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            IpcCommand::Open(HuttUrlSerde::Compose(links::compose_request("", fields, true)))
+        }
+        _ => return Err((404, format!("unknown endpoint {}", path))),
+    };
+    Ok(cmd)
+}
+
+/// JSON body for a response, as printed by `hutt r --json --wrapped`.
+fn response_json(resp: &IpcResponse) -> String {
+    match resp {
+        IpcResponse::Ok => serde_json::json!({"found": 0}).to_string(),
+        IpcResponse::Error { message } => error_json(message),
+        IpcResponse::MuFrames { frames } => {
+            let headers: Vec<serde_json::Value> = frames
+                .iter()
+                .filter_map(|s| mu_sexp::sexp_to_json(s).ok())
+                .collect();
+            serde_json::json!({"headers": headers, "found": frames.len()}).to_string()
+        }
+    }
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({"error": message}).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_need_the_token() {
+        let head = |line: &str, extra: &str| format!("{}\r\nHost: localhost\r\n{}\r\n", line, extra);
+        assert!(matches!(
+            respond(&head("GET /search?q=from%3Aalice HTTP/1.1", ""), "s3cret"),
+            Err((401, _))
+        ));
+        assert!(matches!(
+            respond(&head("GET /search?q=from%3Aalice&token=s3cret HTTP/1.1", ""), "s3cret"),
+            Ok(IpcCommand::Open(HuttUrlSerde::Search { ref query, account: None })) if query == "from:alice"
+        ));
+        assert!(matches!(
+            respond(
+                &head("POST /navigate?folder=%2FSent&account=work HTTP/1.1", "authorization: Bearer s3cret\r\n"),
+                "s3cret"
+            ),
+            Ok(IpcCommand::Navigate { ref folder, account: Some(_) }) if folder == "/Sent"
+        ));
+        assert!(matches!(
+            respond(&head("DELETE /search?token=s3cret HTTP/1.1", ""), "s3cret"),
+            Err((405, _))
+        ));
+    }
+
+    #[test]
+    fn compose_json_body() {
+        let body = r#"{"to": ["bob@example.com"], "subject": "Hi", "attachments": ["/tmp/a.pdf"]}"#;
+        let request = format!(
+            "POST /compose HTTP/1.1\r\nAuthorization: Bearer t\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let Ok(IpcCommand::Compose(req)) = respond(&request, "t") else {
+            panic!("expected a compose request");
+        };
+        assert_eq!(req.to, vec!["bob@example.com"]);
+        assert_eq!(req.attachments, vec!["/tmp/a.pdf"]);
+        let bad = "POST /compose HTTP/1.1\r\nAuthorization: Bearer t\r\n\r\n{\"to\": 1}";
+        assert!(matches!(respond(bad, "t"), Err((400, _))));
+    }
+
+    #[test]
+    fn routes() {
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert!(matches!(
+            route("/open", &params(&[("url", "mid:abc@example.com?view=thread")])),
+            Ok(IpcCommand::Open(HuttUrlSerde::Thread { .. }))
+        ));
+        assert!(matches!(
+            route("/open", &params(&[("url", "hutt:navigate?folder=%2FInbox")])),
+            Ok(IpcCommand::Navigate { .. })
+        ));
+        assert!(matches!(route("/search", &params(&[])), Err((400, _))));
+        assert!(matches!(route("/quit", &params(&[])), Err((404, _))));
+        assert!(matches!(
+            route("/compose", &params(&[("to", "bob@example.com")])),
+            Ok(IpcCommand::Open(HuttUrlSerde::Compose(ref req))) if req.to == ["bob@example.com"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn serves_responses_from_the_run_loop() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let api = HttpApi { port, token: "t".to_string() };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(serve(listener, api, tx));
+        // Stand-in for the run loop
+        tokio::spawn(async move {
+            while let Some((_cmd, reply)) = rx.recv().await {
+                if let Some(reply) = reply {
+                    let resp = IpcResponse::MuFrames { frames: vec!["(:docid 7)".to_string()] };
+                    reply.send(&resp).await.unwrap();
+                }
+            }
+        });
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream
+            .write_all(b"GET /search?q=flag%3Aunread&token=t HTTP/1.1\r\nHost: x\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        let body: serde_json::Value =
+            serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["found"], 1);
+        assert_eq!(body["headers"][0]["docid"], 7);
+    }
+}
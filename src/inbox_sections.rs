@@ -0,0 +1,248 @@
+//! Sectioned inbox: inbox rows grouped under collapsible headers (Primary,
+//! Lists, Notifications, ...) by classifier queries, like mail tabs but in
+//! one list.
+
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::envelope::Envelope;
+
+/// Name of the catch-all section added when none is configured.
+const DEFAULT_REST: &str = "Other";
+
+/// One section, from `[[inbox_sections]]`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct InboxSection {
+    pub name: String,
+    /// mu query for the messages that belong here. The section without a
+    /// query takes everything the others don't.
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+/// Primary (everything else), then mailing lists, then automated mail.
+pub fn default_sections() -> Vec<InboxSection> {
+    let section = |name: &str, query: Option<&str>| InboxSection {
+        name: name.to_string(),
+        query: query.map(String::from),
+    };
+    vec![
+        section("Primary", None),
+        section("Lists", Some("flag:list")),
+        section(
+            "Notifications",
+            Some("from:noreply OR from:no-reply OR from:donotreply OR from:notifications OR from:notification"),
+        ),
+    ]
+}
+
+/// A section header line in the envelope list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionHeader {
+    pub name: String,
+    /// Index of the section's first row in the envelope list; the header
+    /// is drawn above it. Collapsed and empty sections have no rows.
+    pub start: usize,
+    pub count: usize,
+    pub unread: usize,
+    pub collapsed: bool,
+}
+
+/// Section membership and collapsed state for the inbox.
+#[derive(Debug, Clone, Default)]
+pub struct InboxSections {
+    names: Vec<String>,
+    /// (section, query) for each classifier, in match order.
+    queries: Vec<(usize, String)>,
+    /// The catch-all section.
+    rest: usize,
+    /// Section of each classified message, by message-id.
+    matched: HashMap<String, usize>,
+    collapsed: HashSet<String>,
+    /// Messages of collapsed sections, taken out of the envelope list.
+    hidden: Vec<Envelope>,
+}
+
+impl InboxSections {
+    pub fn new(sections: &[InboxSection]) -> Self {
+        let mut names: Vec<String> = sections.iter().map(|s| s.name.clone()).collect();
+        let rest = match sections.iter().position(|s| s.query.is_none()) {
+            Some(i) => i,
+            None => {
+                names.push(DEFAULT_REST.to_string());
+                names.len() - 1
+            }
+        };
+        let queries = sections
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| s.query.clone().map(|q| (i, q)))
+            .collect();
+        Self {
+            names,
+            queries,
+            rest,
+            ..Self::default()
+        }
+    }
+
+    /// The classifier queries, as (section, query), in match order.
+    pub fn queries(&self) -> &[(usize, String)] {
+        &self.queries
+    }
+
+    /// Forget all classified messages, before classifying afresh.
+    pub fn clear_matches(&mut self) {
+        self.matched.clear();
+    }
+
+    /// Put messages matching `section`'s query in it, unless an earlier
+    /// classifier already claimed them.
+    pub fn add_matches<'a>(&mut self, section: usize, message_ids: impl IntoIterator<Item = &'a str>) {
+        for id in message_ids {
+            self.matched.entry(id.to_string()).or_insert(section);
+        }
+    }
+
+    fn index_of(&self, envelope: &Envelope) -> usize {
+        self.matched.get(&envelope.message_id).copied().unwrap_or(self.rest)
+    }
+
+    /// Name of the section `envelope` is in.
+    pub fn name_of(&self, envelope: &Envelope) -> &str {
+        &self.names[self.index_of(envelope)]
+    }
+
+    /// Group a freshly loaded and sorted list by section, keeping the order
+    /// within each, and take out the rows of collapsed sections.
+    pub fn arrange(&mut self, envelopes: &mut Vec<Envelope>) {
+        self.hidden.clear();
+        envelopes.sort_by_key(|e| self.index_of(e));
+        let (hidden, shown): (Vec<Envelope>, Vec<Envelope>) = std::mem::take(envelopes)
+            .into_iter()
+            .partition(|e| self.collapsed.contains(self.name_of(e)));
+        self.hidden = hidden;
+        *envelopes = shown;
+    }
+
+    /// Drop the stashed rows of collapsed sections, once the list shows
+    /// something else.
+    pub fn forget_hidden(&mut self) {
+        self.hidden.clear();
+    }
+
+    /// Put the rows of collapsed sections back, e.g. before re-sorting.
+    pub fn unhide(&mut self, envelopes: &mut Vec<Envelope>) {
+        envelopes.append(&mut self.hidden);
+    }
+
+    /// Collapse the section called `name`; takes effect on the next arrange.
+    pub fn collapse(&mut self, name: &str) {
+        self.collapsed.insert(name.to_string());
+    }
+
+    pub fn expand_all(&mut self) {
+        self.collapsed.clear();
+    }
+
+    /// Headers for the arranged `envelopes`, one per section in order.
+    pub fn headers(&self, envelopes: &[Envelope]) -> Vec<SectionHeader> {
+        let mut headers: Vec<SectionHeader> = self
+            .names
+            .iter()
+            .map(|name| SectionHeader {
+                name: name.clone(),
+                start: 0,
+                count: 0,
+                unread: 0,
+                collapsed: self.collapsed.contains(name),
+            })
+            .collect();
+        let mut shown = vec![0; self.names.len()];
+        for e in envelopes.iter().chain(&self.hidden) {
+            let i = self.index_of(e);
+            headers[i].count += 1;
+            if e.is_unread() {
+                headers[i].unread += 1;
+            }
+        }
+        for e in envelopes {
+            shown[self.index_of(e)] += 1;
+        }
+        let mut start = 0;
+        for (header, n) in headers.iter_mut().zip(shown) {
+            header.start = start;
+            start += n;
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::flags_from_string;
+
+    fn envelope(id: &str, flags: &str) -> Envelope {
+        Envelope {
+            message_id: id.to_string(),
+            flags: flags_from_string(flags),
+            ..Envelope::default()
+        }
+    }
+
+    #[test]
+    fn arrange_and_collapse() {
+        let mut sections = InboxSections::new(&default_sections());
+        let classifiers: Vec<usize> = sections.queries().iter().map(|(i, _)| *i).collect();
+        assert_eq!(classifiers, vec![1, 2]);
+        sections.add_matches(1, ["list-a", "list-b", "both"]);
+        sections.add_matches(2, ["note", "both"]);
+
+        let mut envelopes = vec![
+            envelope("list-a", "S"),
+            envelope("me", ""),
+            envelope("note", ""),
+            envelope("both", "S"),
+            envelope("list-b", ""),
+        ];
+        sections.arrange(&mut envelopes);
+        let order: Vec<&str> = envelopes.iter().map(|e| e.message_id.as_str()).collect();
+        assert_eq!(order, vec!["me", "list-a", "both", "list-b", "note"]);
+        assert_eq!(sections.name_of(&envelopes[2]), "Lists");
+
+        let headers = sections.headers(&envelopes);
+        let summary: Vec<(&str, usize, usize, usize)> =
+            headers.iter().map(|h| (h.name.as_str(), h.start, h.count, h.unread)).collect();
+        assert_eq!(
+            summary,
+            vec![("Primary", 0, 1, 1), ("Lists", 1, 3, 1), ("Notifications", 4, 1, 1)]
+        );
+
+        // Collapsing Lists hides its rows but keeps its counts
+        sections.collapse("Lists");
+        sections.unhide(&mut envelopes);
+        sections.arrange(&mut envelopes);
+        let order: Vec<&str> = envelopes.iter().map(|e| e.message_id.as_str()).collect();
+        assert_eq!(order, vec!["me", "note"]);
+        let headers = sections.headers(&envelopes);
+        assert!(headers[1].collapsed);
+        assert_eq!((headers[1].start, headers[1].count), (1, 3));
+        assert_eq!(headers[2].start, 1);
+
+        sections.expand_all();
+        sections.unhide(&mut envelopes);
+        sections.arrange(&mut envelopes);
+        assert_eq!(envelopes.len(), 5);
+    }
+
+    #[test]
+    fn catch_all_added_when_missing() {
+        let config = vec![InboxSection { name: "GitHub".into(), query: Some("from:github.com".into()) }];
+        let sections = InboxSections::new(&config);
+        let headers = sections.headers(&[envelope("x", "")]);
+        let names: Vec<&str> = headers.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, vec!["GitHub", "Other"]);
+        assert_eq!(headers[1].count, 1);
+    }
+}
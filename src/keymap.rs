@@ -1,15 +1,18 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
 use crate::config::{BindingValue, BindingsSection};
 
 /// Available sort fields for the envelope list.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SortField {
     Date,
     From,
     Subject,
     To,
+    Score,
 }
 
 impl SortField {
@@ -19,6 +22,7 @@ impl SortField {
             SortField::From => "From",
             SortField::Subject => "Subject",
             SortField::To => "To",
+            SortField::Score => "Score",
         }
     }
 }
@@ -38,6 +42,21 @@ pub enum InputMode {
     MoveToFolder,
     AttachmentPopup,
     SortPicker,
+    History,
+    RecentlyDeleted,
+    Digest,
+    DeliveryInfo,
+    CommandOutput,
+    Summary,
+    AttachmentBrowser,
+    QuickReply,
+    FollowUpDate,
+    ExportPath,
+    ContactName,
+    NoteText,
+    HeaderMenu,
+    TriageLoop,
+    RecipientCheck,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -47,6 +66,8 @@ pub enum Action {
     MoveUp,
     JumpTop,
     JumpBottom,
+    PreviewTop,
+    PreviewBottom,
     ScrollPreviewDown,
     ScrollPreviewUp,
     HalfPageDown,
@@ -57,6 +78,9 @@ pub enum Action {
     // Triage — MoveToFolder(None) opens picker, Some("archive") resolves
     // from account folders config, Some("/Literal") uses path directly.
     MoveToFolder(Option<String>),
+    RepeatMove,
+    SweepSender,
+    TriageLoop,
     ToggleRead,
     ToggleStar,
     Undo,
@@ -81,11 +105,25 @@ pub enum Action {
     PrevAccount,
     SwitchAccount(usize),
 
+    // View tabs
+    NewView,
+    CloseView,
+    NextView,
+    PrevView,
+    SwitchView(usize),
+
+    // Two folders side by side
+    Compare,
+    CompareSwitch,
+
     // Search & Filters
     EnterSearch,
     FilterUnread,
     FilterStarred,
     FilterNeedsReply,
+    FilterScore,
+    CollapseDuplicates,
+    ExpandDuplicates,
 
     // Multi-select
     ToggleSelect,
@@ -108,26 +146,72 @@ pub enum Action {
     Reply,
     ReplyAll,
     Forward,
+    QuickReply,
+    AwaitReply,
+    EditNote,
 
     // Linkability (Phase 3)
     CopyMessageUrl,
     CopyThreadUrl,
+    CopyMessageLink,
+    CopyRedacted,
+
+    // Patch emails
+    SavePatch,
+    ApplyPatch,
+    PipeThread,
+    SummarizeThread,
+    ExportSearch,
+    CopyFolderUrl,
+    CopySearchUrl,
     OpenInBrowser,
+    /// Render a message over `preview_max_size_mb` after all.
+    RenderAnyway,
 
     // Command palette (Phase 4)
     OpenCommandPalette,
 
+    // Preview / list layout
+    ToggleHeaders,
+    HeaderActions,
+    /// Add the sender to the address book, or rename them there.
+    AddContact,
+    /// Let the sender through the screener: add them to the contacts.
+    ApproveSender,
+    /// Block the sender: their inbox mail goes to spam from now on.
+    BlockSender,
+    ToggleDirection,
+    ToggleDensity,
+
     // Conversations
     ToggleConversations,
 
+    // Sectioned inbox
+    ToggleSections,
+    CollapseSection,
+    ExpandSections,
+    SelectSection,
+
+    // Focus time
+    PauseInbox,
+
     // Help
     ShowHelp,
+    /// Say what the next key (or sequence) does, without doing it.
+    DescribeKey,
+    ShowHistory,
+    RecentlyDeleted,
+    ShowDigest,
+    DeliveryInfo,
+    BrowseAttachments,
 
     // Sync (Phase 4)
     SyncMail,
+    HeadersOnlySync,
 
     // Splits / Smart folders
     CreateSplit,
+    SplitPlusTag,
     EditFolder,
     DeleteFolder,
 
@@ -139,12 +223,23 @@ pub enum Action {
     OpenAccountPicker,
 
     // Custom bindings
+    ToggleShellDryRun,
     RunShell {
         command: String,
         reindex: bool,
         suspend: bool,
     },
     NavigateFolder(String),
+    /// Reply from a named template; `send` skips the editor.
+    ReplyTemplate {
+        name: String,
+        send: bool,
+        confirm: bool,
+    },
+    /// Add a keyword to the selection, or remove it if they all have it.
+    ToggleKeyword(String),
+    /// List only messages with a keyword (again: list them all).
+    FilterKeyword(String),
 
     // Text input (shared across input modes)
     InputChar(char),
@@ -288,6 +383,8 @@ pub fn parse_action_name(name: &str) -> Result<Action, String> {
         "move_up" => Ok(Action::MoveUp),
         "jump_top" => Ok(Action::JumpTop),
         "jump_bottom" => Ok(Action::JumpBottom),
+        "preview_top" => Ok(Action::PreviewTop),
+        "preview_bottom" => Ok(Action::PreviewBottom),
         "scroll_preview_down" => Ok(Action::ScrollPreviewDown),
         "scroll_preview_up" => Ok(Action::ScrollPreviewUp),
         "half_page_down" => Ok(Action::HalfPageDown),
@@ -298,6 +395,9 @@ pub fn parse_action_name(name: &str) -> Result<Action, String> {
         "trash" => Ok(Action::MoveToFolder(Some("trash".to_string()))),
         "spam" => Ok(Action::MoveToFolder(Some("spam".to_string()))),
         "move_to_folder" | "move" => Ok(Action::MoveToFolder(None)),
+        "repeat_move" => Ok(Action::RepeatMove),
+        "sweep_sender" => Ok(Action::SweepSender),
+        "triage_loop" => Ok(Action::TriageLoop),
         "toggle_read" => Ok(Action::ToggleRead),
         "toggle_star" => Ok(Action::ToggleStar),
         "undo" => Ok(Action::Undo),
@@ -314,10 +414,19 @@ pub fn parse_action_name(name: &str) -> Result<Action, String> {
         "prev_folder" => Ok(Action::PrevFolder),
         "next_account" => Ok(Action::NextAccount),
         "prev_account" => Ok(Action::PrevAccount),
+        "new_view" => Ok(Action::NewView),
+        "close_view" => Ok(Action::CloseView),
+        "next_view" => Ok(Action::NextView),
+        "prev_view" => Ok(Action::PrevView),
+        "compare" => Ok(Action::Compare),
+        "compare_switch" => Ok(Action::CompareSwitch),
         "enter_search" | "search" => Ok(Action::EnterSearch),
         "filter_unread" => Ok(Action::FilterUnread),
         "filter_starred" => Ok(Action::FilterStarred),
         "filter_needs_reply" => Ok(Action::FilterNeedsReply),
+        "filter_score" => Ok(Action::FilterScore),
+        "collapse_duplicates" => Ok(Action::CollapseDuplicates),
+        "expand_duplicates" => Ok(Action::ExpandDuplicates),
         "toggle_select" => Ok(Action::ToggleSelect),
         "select_all" => Ok(Action::SelectAll),
         "select_from_here" => Ok(Action::SelectFromHere),
@@ -334,14 +443,48 @@ pub fn parse_action_name(name: &str) -> Result<Action, String> {
         "reply" => Ok(Action::Reply),
         "reply_all" => Ok(Action::ReplyAll),
         "forward" => Ok(Action::Forward),
+        "quick_reply" => Ok(Action::QuickReply),
+        "await_reply" | "follow_up" => Ok(Action::AwaitReply),
+        "edit_note" | "note" => Ok(Action::EditNote),
         "copy_message_url" => Ok(Action::CopyMessageUrl),
         "copy_thread_url" => Ok(Action::CopyThreadUrl),
+        "copy_message_link" => Ok(Action::CopyMessageLink),
+        "copy_redacted" => Ok(Action::CopyRedacted),
+        "save_patch" => Ok(Action::SavePatch),
+        "apply_patch" => Ok(Action::ApplyPatch),
+        "pipe_thread" => Ok(Action::PipeThread),
+        "summarize_thread" | "summarize" => Ok(Action::SummarizeThread),
+        "export_search" | "export" => Ok(Action::ExportSearch),
+        "copy_folder_url" => Ok(Action::CopyFolderUrl),
+        "copy_search_url" => Ok(Action::CopySearchUrl),
         "open_in_browser" => Ok(Action::OpenInBrowser),
+        "render_anyway" => Ok(Action::RenderAnyway),
         "open_command_palette" | "command_palette" => Ok(Action::OpenCommandPalette),
+        "toggle_headers" => Ok(Action::ToggleHeaders),
+        "header_actions" => Ok(Action::HeaderActions),
+        "add_contact" => Ok(Action::AddContact),
+        "approve_sender" => Ok(Action::ApproveSender),
+        "block_sender" => Ok(Action::BlockSender),
+        "toggle_direction" => Ok(Action::ToggleDirection),
+        "toggle_density" | "density" => Ok(Action::ToggleDensity),
         "toggle_conversations" | "conversations" => Ok(Action::ToggleConversations),
+        "toggle_sections" => Ok(Action::ToggleSections),
+        "collapse_section" => Ok(Action::CollapseSection),
+        "expand_sections" => Ok(Action::ExpandSections),
+        "select_section" => Ok(Action::SelectSection),
+        "pause_inbox" | "pause" => Ok(Action::PauseInbox),
         "show_help" | "help" => Ok(Action::ShowHelp),
+        "describe_key" => Ok(Action::DescribeKey),
+        "show_history" | "history" => Ok(Action::ShowHistory),
+        "recently_deleted" | "deleted" => Ok(Action::RecentlyDeleted),
+        "show_digest" | "digest" => Ok(Action::ShowDigest),
+        "delivery_info" => Ok(Action::DeliveryInfo),
+        "browse_attachments" | "attachments" => Ok(Action::BrowseAttachments),
         "sync_mail" | "sync" => Ok(Action::SyncMail),
+        "headers_only_sync" => Ok(Action::HeadersOnlySync),
+        "toggle_shell_dry_run" | "shell_dry_run" => Ok(Action::ToggleShellDryRun),
         "create_split" => Ok(Action::CreateSplit),
+        "split_plus_tag" => Ok(Action::SplitPlusTag),
         "edit_folder" => Ok(Action::EditFolder),
         "delete_folder" => Ok(Action::DeleteFolder),
         "open_account_picker" | "account_picker" => Ok(Action::OpenAccountPicker),
@@ -374,16 +517,33 @@ fn resolve_binding_value(value: &BindingValue) -> Result<BindAction, String> {
         BindingValue::Move { folder } => {
             Ok(BindAction::Builtin(Action::MoveToFolder(Some(folder.clone()))))
         }
+        BindingValue::ReplyTemplate {
+            reply_template,
+            send,
+            confirm,
+        } => Ok(BindAction::Builtin(Action::ReplyTemplate {
+            name: reply_template.clone(),
+            send: *send,
+            confirm: *confirm,
+        })),
+        BindingValue::ToggleKeyword { toggle_keyword } => {
+            Ok(BindAction::Builtin(Action::ToggleKeyword(toggle_keyword.clone())))
+        }
+        BindingValue::FilterKeyword { filter_keyword } => {
+            Ok(BindAction::Builtin(Action::FilterKeyword(filter_keyword.clone())))
+        }
     }
 }
 
 /// Reverse-map an Action to its canonical config name (for help display).
-fn action_to_name(action: &Action) -> Option<String> {
+pub fn action_to_name(action: &Action) -> Option<String> {
     let name = match action {
         Action::MoveDown => "move_down",
         Action::MoveUp => "move_up",
         Action::JumpTop => "jump_top",
         Action::JumpBottom => "jump_bottom",
+        Action::PreviewTop => "preview_top",
+        Action::PreviewBottom => "preview_bottom",
         Action::ScrollPreviewDown => "scroll_preview_down",
         Action::ScrollPreviewUp => "scroll_preview_up",
         Action::HalfPageDown => "half_page_down",
@@ -397,6 +557,9 @@ fn action_to_name(action: &Action) -> Option<String> {
             _ => return None,
         },
         Action::MoveToFolder(None) => "move_to_folder",
+        Action::RepeatMove => "repeat_move",
+        Action::SweepSender => "sweep_sender",
+        Action::TriageLoop => "triage_loop",
         Action::ToggleRead => "toggle_read",
         Action::ToggleStar => "toggle_star",
         Action::Undo => "undo",
@@ -413,10 +576,19 @@ fn action_to_name(action: &Action) -> Option<String> {
         Action::PrevFolder => "prev_folder",
         Action::NextAccount => "next_account",
         Action::PrevAccount => "prev_account",
+        Action::NewView => "new_view",
+        Action::CloseView => "close_view",
+        Action::NextView => "next_view",
+        Action::PrevView => "prev_view",
+        Action::Compare => "compare",
+        Action::CompareSwitch => "compare_switch",
         Action::EnterSearch => "search",
         Action::FilterUnread => "filter_unread",
         Action::FilterStarred => "filter_starred",
         Action::FilterNeedsReply => "filter_needs_reply",
+        Action::FilterScore => "filter_score",
+        Action::CollapseDuplicates => "collapse_duplicates",
+        Action::ExpandDuplicates => "expand_duplicates",
         Action::ToggleSelect => "toggle_select",
         Action::SelectAll => "select_all",
         Action::SelectFromHere => "select_from_here",
@@ -433,14 +605,48 @@ fn action_to_name(action: &Action) -> Option<String> {
         Action::Reply => "reply",
         Action::ReplyAll => "reply_all",
         Action::Forward => "forward",
+        Action::QuickReply => "quick_reply",
+        Action::AwaitReply => "await_reply",
+        Action::EditNote => "edit_note",
         Action::CopyMessageUrl => "copy_message_url",
         Action::CopyThreadUrl => "copy_thread_url",
+        Action::CopyMessageLink => "copy_message_link",
+        Action::CopyRedacted => "copy_redacted",
+        Action::SavePatch => "save_patch",
+        Action::ApplyPatch => "apply_patch",
+        Action::PipeThread => "pipe_thread",
+        Action::SummarizeThread => "summarize_thread",
+        Action::ExportSearch => "export_search",
+        Action::CopyFolderUrl => "copy_folder_url",
+        Action::CopySearchUrl => "copy_search_url",
         Action::OpenInBrowser => "open_in_browser",
+        Action::RenderAnyway => "render_anyway",
         Action::OpenCommandPalette => "command_palette",
+        Action::ToggleHeaders => "toggle_headers",
+        Action::HeaderActions => "header_actions",
+        Action::AddContact => "add_contact",
+        Action::ApproveSender => "approve_sender",
+        Action::BlockSender => "block_sender",
+        Action::ToggleDirection => "toggle_direction",
+        Action::ToggleDensity => "toggle_density",
         Action::ToggleConversations => "conversations",
+        Action::ToggleSections => "toggle_sections",
+        Action::CollapseSection => "collapse_section",
+        Action::ExpandSections => "expand_sections",
+        Action::SelectSection => "select_section",
+        Action::PauseInbox => "pause_inbox",
         Action::ShowHelp => "help",
+        Action::DescribeKey => "describe_key",
+        Action::ShowHistory => "history",
+        Action::RecentlyDeleted => "recently_deleted",
+        Action::ShowDigest => "digest",
+        Action::DeliveryInfo => "delivery_info",
+        Action::BrowseAttachments => "attachments",
         Action::SyncMail => "sync_mail",
+        Action::HeadersOnlySync => "headers_only_sync",
+        Action::ToggleShellDryRun => "toggle_shell_dry_run",
         Action::CreateSplit => "create_split",
+        Action::SplitPlusTag => "split_plus_tag",
         Action::EditFolder => "edit_folder",
         Action::DeleteFolder => "delete_folder",
         Action::OpenAccountPicker => "account_picker",
@@ -503,6 +709,48 @@ pub fn format_trigger(trigger: &KeyTrigger) -> String {
 // KeyMapper
 // ---------------------------------------------------------------------------
 
+/// What a key does, from [`KeyMapper::describe`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyDescription {
+    /// The key or sequence pressed, as written in a binding.
+    pub keys: String,
+    /// `Noop` when it does nothing here.
+    pub action: Action,
+    /// Bound in the config rather than by default.
+    pub custom: bool,
+}
+
+impl KeyDescription {
+    /// One line saying what the key does; `label` names a built-in action
+    /// (e.g. its command palette entry).
+    pub fn line(&self, label: Option<&str>) -> String {
+        let what = match &self.action {
+            Action::Noop => return format!("{} is not bound here", self.keys),
+            Action::RunShell { command, reindex, suspend } => {
+                let mut what = format!("runs `{}`", command);
+                match (suspend, reindex) {
+                    (true, true) => what.push_str(" in the terminal, then reindexes"),
+                    (true, false) => what.push_str(" in the terminal"),
+                    (false, true) => what.push_str(", then reindexes"),
+                    (false, false) => {}
+                }
+                what
+            }
+            Action::NavigateFolder(folder) => format!("goes to {}", folder),
+            Action::ToggleKeyword(keyword) => format!("toggles the keyword \"{}\"", keyword),
+            Action::FilterKeyword(keyword) => format!("lists only messages with the keyword \"{}\"", keyword),
+            action => match (label, action_to_name(action)) {
+                (Some(label), Some(name)) => format!("{} ({})", label, name),
+                (Some(label), None) => label.to_string(),
+                (None, Some(name)) => name,
+                (None, None) => format!("{:?}", action),
+            },
+        };
+        let source = if self.custom { "custom binding" } else { "default" };
+        format!("{} \u{2192} {} [{}]", self.keys, what, source)
+    }
+}
+
 /// Tracks multi-key sequences (e.g., g then g for JumpTop, g then i for GoInbox)
 /// and custom keybindings from config.
 pub struct KeyMapper {
@@ -600,6 +848,36 @@ impl KeyMapper {
         None
     }
 
+    /// The key to show for `action` (a config action name) in a hint: its
+    /// custom binding in `mode`, else `default`. `None` when a custom
+    /// binding has taken the default key for something else.
+    pub fn hint_key(&self, action: &str, default: &str, mode: &InputMode) -> Option<String> {
+        let custom = self.custom_bindings.iter().find(|b| {
+            b.modes.contains(mode)
+                && matches!(&b.action, BindAction::Builtin(a) if action_to_name(a).as_deref() == Some(action))
+        });
+        if let Some(binding) = custom {
+            return Some(format_trigger(&binding.trigger));
+        }
+        let trigger = parse_key_string(default).ok()?;
+        if self.lookup_custom(&trigger, mode).is_some() {
+            return None;
+        }
+        Some(format_trigger(&trigger))
+    }
+
+    /// A hint line from (action, default key, label) entries, such as
+    /// "e archive · # trash", leaving out actions with no key.
+    pub fn hint_line(&self, entries: &[(&str, &str, &str)], mode: &InputMode) -> String {
+        entries
+            .iter()
+            .filter_map(|(action, default, label)| {
+                self.hint_key(action, default, mode).map(|key| format!("{} {}", key, label))
+            })
+            .collect::<Vec<_>>()
+            .join(" \u{b7} ")
+    }
+
     /// Return effective help data: sections of (key_string, description) pairs,
     /// plus any custom bindings not covered by the standard help sections.
     #[allow(clippy::type_complexity)]
@@ -615,6 +893,11 @@ impl KeyMapper {
                 ("jump_bottom", "G", "Jump to bottom"),
                 ("scroll_preview_down", "Space", "Scroll preview down"),
                 ("scroll_preview_up", "Shift+Space", "Scroll preview up"),
+                ("preview_top", "Home", "Preview start"),
+                ("preview_bottom", "End", "Preview end"),
+                ("toggle_headers", "H", "Show all recipients"),
+                ("header_actions", "h", "Act on a header address"),
+                ("add_contact", "A", "Add sender to contacts"),
                 ("half_page_down", "Ctrl+d", "Half page down"),
                 ("half_page_up", "Ctrl+u", "Half page up"),
             ]),
@@ -622,6 +905,9 @@ impl KeyMapper {
                 ("archive", "e", "Archive"),
                 ("trash", "#", "Trash"),
                 ("spam", "!", "Spam"),
+                ("approve_sender", "+", "Approve sender (screener)"),
+                ("block_sender", "-", "Block sender to spam"),
+                ("repeat_move", "M", "Repeat last move"),
                 ("toggle_read", "u", "Toggle read/unread"),
                 ("toggle_star", "s", "Toggle star"),
                 ("undo", "z", "Undo"),
@@ -639,11 +925,22 @@ impl KeyMapper {
                 ("edit_folder", "Ctrl+e", "Edit folder query"),
                 ("delete_folder", "Ctrl+d", "Delete folder"),
             ]),
+            ("Views", &[
+                ("new_view", "gn", "New view tab"),
+                ("close_view", "gw", "Close view tab"),
+                ("next_view", "g]", "Next view tab"),
+                ("prev_view", "g[", "Previous view tab"),
+                ("", "Alt+1-9", "Switch to view tab"),
+                ("compare", "gc", "Compare two folders"),
+                ("compare_switch", "go", "Switch compared pane"),
+            ]),
             ("Search & Filters", &[
                 ("search", "/", "Search"),
                 ("filter_unread", "U", "Filter unread"),
                 ("filter_starred", "S", "Filter starred"),
                 ("filter_needs_reply", "R", "Filter needs reply"),
+                ("collapse_duplicates", "C", "Collapse near-duplicates in searches"),
+                ("expand_duplicates", "E", "Show a thread's collapsed messages"),
             ]),
             ("Sort", &[
                 ("sort_picker", "o", "Sort by field"),
@@ -659,6 +956,7 @@ impl KeyMapper {
             ("Thread", &[
                 ("open_thread", "Enter", "Open thread"),
                 ("conversations", "V", "Toggle conversations"),
+                ("toggle_density", "D", "Compact/relaxed rows"),
                 ("thread_toggle_expand", "o", "Toggle expand"),
                 ("thread_expand_all", "O", "Expand/collapse all"),
                 ("close_thread", "q / Esc", "Close thread"),
@@ -668,16 +966,21 @@ impl KeyMapper {
                 ("reply", "r", "Reply"),
                 ("reply_all", "a", "Reply all"),
                 ("forward", "f", "Forward"),
+                ("quick_reply", "Q", "Quick one-line reply"),
+                ("await_reply", "W", "Remind if no reply by a date"),
+                ("edit_note", "N", "Private note on the message"),
             ]),
             ("Links & Clipboard", &[
                 ("copy_message_url", "y", "Copy message URL"),
                 ("copy_thread_url", "Y", "Copy thread URL"),
                 ("open_in_browser", "Ctrl+o", "Open in browser"),
+                ("render_anyway", "v", "Render a large message"),
             ]),
             ("Other", &[
                 ("command_palette", "Ctrl+k", "Command palette"),
                 ("sync_mail", "Ctrl+r", "Sync mail"),
                 ("help", "?", "This help"),
+                ("describe_key", "g?", "Describe a key"),
                 ("quit", "q", "Quit"),
             ]),
         ];
@@ -783,6 +1086,15 @@ impl KeyMapper {
             | InputMode::SmartFolderCreate
             | InputMode::SmartFolderName
             | InputMode::MaildirCreate
+            | InputMode::QuickReply
+            | InputMode::FollowUpDate
+            | InputMode::ExportPath
+            | InputMode::ContactName
+            | InputMode::NoteText
+            | InputMode::HeaderMenu
+            | InputMode::RecipientCheck
+            | InputMode::TriageLoop
+            | InputMode::RecentlyDeleted
             | InputMode::AccountPicker
             | InputMode::AttachmentPopup => {
                 return self.handle_input(key);
@@ -827,7 +1139,12 @@ impl KeyMapper {
         match mode {
             InputMode::Normal => self.handle_normal(key),
             InputMode::ThreadView => self.handle_thread(key),
-            InputMode::Help => self.handle_help(key),
+            InputMode::Help
+            | InputMode::History
+            | InputMode::Digest
+            | InputMode::DeliveryInfo
+            | InputMode::CommandOutput
+            | InputMode::Summary => self.handle_help(key),
             _ => Action::Noop,
         }
     }
@@ -849,6 +1166,8 @@ impl KeyMapper {
             (KeyCode::Char('G'), KeyModifiers::SHIFT) => Action::JumpBottom,
             (KeyCode::Char(' '), KeyModifiers::NONE) => Action::ScrollPreviewDown,
             (KeyCode::Char(' '), KeyModifiers::SHIFT) => Action::ScrollPreviewUp,
+            (KeyCode::Home, _) => Action::PreviewTop,
+            (KeyCode::End, _) => Action::PreviewBottom,
             (KeyCode::Char('d'), KeyModifiers::CONTROL) => Action::HalfPageDown,
             (KeyCode::Char('u'), KeyModifiers::CONTROL) => Action::HalfPageUp,
             (KeyCode::Char('f'), KeyModifiers::CONTROL) => Action::FullPageDown,
@@ -860,7 +1179,10 @@ impl KeyMapper {
             }
             (KeyCode::Char('#'), _) => Action::MoveToFolder(Some("trash".to_string())),
             (KeyCode::Char('!'), _) => Action::MoveToFolder(Some("spam".to_string())),
+            (KeyCode::Char('+'), _) => Action::ApproveSender,
+            (KeyCode::Char('-'), _) => Action::BlockSender,
             (KeyCode::Char('m'), KeyModifiers::NONE) => Action::MoveToFolder(None),
+            (KeyCode::Char('M'), KeyModifiers::SHIFT) => Action::RepeatMove,
             // Note: 'u' without Ctrl is ToggleRead
             (KeyCode::Char('u'), KeyModifiers::NONE) => Action::ToggleRead,
             (KeyCode::Char('s'), KeyModifiers::NONE) => Action::ToggleStar,
@@ -879,6 +1201,8 @@ impl KeyMapper {
             (KeyCode::Char('U'), KeyModifiers::SHIFT) => Action::FilterUnread,
             (KeyCode::Char('S'), KeyModifiers::SHIFT) => Action::FilterStarred,
             (KeyCode::Char('R'), KeyModifiers::SHIFT) => Action::FilterNeedsReply,
+            (KeyCode::Char('C'), KeyModifiers::SHIFT) => Action::CollapseDuplicates,
+            (KeyCode::Char('E'), KeyModifiers::SHIFT) => Action::ExpandDuplicates,
 
             // Thread view
             (KeyCode::Enter, _) => Action::OpenThread,
@@ -888,11 +1212,15 @@ impl KeyMapper {
             (KeyCode::Char('r'), KeyModifiers::NONE) => Action::Reply,
             (KeyCode::Char('a'), KeyModifiers::NONE) => Action::ReplyAll,
             (KeyCode::Char('f'), KeyModifiers::NONE) => Action::Forward,
+            (KeyCode::Char('Q'), KeyModifiers::SHIFT) => Action::QuickReply,
+            (KeyCode::Char('W'), KeyModifiers::SHIFT) => Action::AwaitReply,
+            (KeyCode::Char('N'), KeyModifiers::SHIFT) => Action::EditNote,
 
             // Linkability
             (KeyCode::Char('y'), KeyModifiers::NONE) => Action::CopyMessageUrl,
             (KeyCode::Char('Y'), KeyModifiers::SHIFT) => Action::CopyThreadUrl,
             (KeyCode::Char('o'), KeyModifiers::CONTROL) => Action::OpenInBrowser,
+            (KeyCode::Char('v'), KeyModifiers::NONE) => Action::RenderAnyway,
 
             // Command palette
             (KeyCode::Char('k'), KeyModifiers::CONTROL) => Action::OpenCommandPalette,
@@ -906,6 +1234,12 @@ impl KeyMapper {
             // Conversations
             (KeyCode::Char('V'), KeyModifiers::SHIFT) => Action::ToggleConversations,
 
+            // Preview / list layout
+            (KeyCode::Char('H'), KeyModifiers::SHIFT) => Action::ToggleHeaders,
+            (KeyCode::Char('h'), KeyModifiers::NONE) => Action::HeaderActions,
+            (KeyCode::Char('A'), KeyModifiers::SHIFT) => Action::AddContact,
+            (KeyCode::Char('D'), KeyModifiers::SHIFT) => Action::ToggleDensity,
+
             // Sort
             (KeyCode::Char('o'), KeyModifiers::NONE) => Action::SortPicker,
             (KeyCode::Char('O'), KeyModifiers::SHIFT) => Action::ReverseSort,
@@ -918,6 +1252,11 @@ impl KeyMapper {
                 Action::SwitchAccount((c as usize) - ('1' as usize))
             }
 
+            // View tabs by number (Alt+1-9)
+            (KeyCode::Char(c @ '1'..='9'), KeyModifiers::ALT) => {
+                Action::SwitchView((c as usize) - ('1' as usize))
+            }
+
             // Edit current folder query
             (KeyCode::Char('e'), KeyModifiers::CONTROL) => Action::EditFolder,
 
@@ -951,6 +1290,13 @@ impl KeyMapper {
             (KeyCode::Char('g'), KeyCode::Char('A')) => Action::OpenAccountPicker,
             (KeyCode::Char('g'), KeyCode::Tab) => Action::NextAccount,
             (KeyCode::Char('g'), KeyCode::BackTab) => Action::PrevAccount,
+            (KeyCode::Char('g'), KeyCode::Char('n')) => Action::NewView,
+            (KeyCode::Char('g'), KeyCode::Char('w')) => Action::CloseView,
+            (KeyCode::Char('g'), KeyCode::Char(']')) => Action::NextView,
+            (KeyCode::Char('g'), KeyCode::Char('[')) => Action::PrevView,
+            (KeyCode::Char('g'), KeyCode::Char('c')) => Action::Compare,
+            (KeyCode::Char('g'), KeyCode::Char('o')) => Action::CompareSwitch,
+            (KeyCode::Char('g'), KeyCode::Char('?')) => Action::DescribeKey,
             _ => Action::Noop,
         }
     }
@@ -978,6 +1324,7 @@ impl KeyMapper {
         if let Some(first) = self.pending.take() {
             return match (first, key.code) {
                 (KeyCode::Char('g'), KeyCode::Char('g')) => Action::JumpTop,
+                (KeyCode::Char('g'), KeyCode::Char('?')) => Action::DescribeKey,
                 _ => Action::Noop,
             };
         }
@@ -1000,22 +1347,30 @@ impl KeyMapper {
             (KeyCode::Char('O'), KeyModifiers::SHIFT) => Action::ThreadExpandAll,
             (KeyCode::Char(' '), KeyModifiers::NONE) => Action::ScrollPreviewDown,
             (KeyCode::Char(' '), KeyModifiers::SHIFT) => Action::ScrollPreviewUp,
+            (KeyCode::Home, _) => Action::PreviewTop,
+            (KeyCode::End, _) => Action::PreviewBottom,
             // Triage actions still work in thread view
             (KeyCode::Char('e'), KeyModifiers::NONE) => {
                 Action::MoveToFolder(Some("archive".to_string()))
             }
             (KeyCode::Char('#'), _) => Action::MoveToFolder(Some("trash".to_string())),
             (KeyCode::Char('!'), _) => Action::MoveToFolder(Some("spam".to_string())),
+            (KeyCode::Char('+'), _) => Action::ApproveSender,
+            (KeyCode::Char('-'), _) => Action::BlockSender,
             (KeyCode::Char('m'), KeyModifiers::NONE) => Action::MoveToFolder(None),
+            (KeyCode::Char('M'), KeyModifiers::SHIFT) => Action::RepeatMove,
             (KeyCode::Char('u'), KeyModifiers::NONE) => Action::ToggleRead,
             (KeyCode::Char('s'), KeyModifiers::NONE) => Action::ToggleStar,
             (KeyCode::Char('z'), KeyModifiers::NONE) => Action::Undo,
+            (KeyCode::Char('h'), KeyModifiers::NONE) => Action::HeaderActions,
+            (KeyCode::Char('A'), KeyModifiers::SHIFT) => Action::AddContact,
             // Compose from thread view
             (KeyCode::Char('r'), KeyModifiers::NONE) => Action::Reply,
             (KeyCode::Char('a'), KeyModifiers::NONE) => Action::ReplyAll,
             (KeyCode::Char('f'), KeyModifiers::NONE) => Action::Forward,
             // Open in browser
             (KeyCode::Char('o'), KeyModifiers::CONTROL) => Action::OpenInBrowser,
+            (KeyCode::Char('v'), KeyModifiers::NONE) => Action::RenderAnyway,
             // Folder cycling
             (KeyCode::Tab, _) => Action::NextFolder,
             (KeyCode::BackTab, _) => Action::PrevFolder,
@@ -1037,6 +1392,23 @@ impl KeyMapper {
         }
     }
 
+    /// Resolve a key as [`handle`](Self::handle) would in `mode`, for
+    /// "Describe Key". `None` while it is the first key of a sequence.
+    pub fn describe(&mut self, raw_key: KeyEvent, mode: &InputMode) -> Option<KeyDescription> {
+        let key = Self::normalize_key(raw_key);
+        let combo = KeyCombo { code: key.code, modifiers: key.modifiers };
+        let trigger = match self.pending {
+            Some(first) => KeyTrigger::Sequence(KeyCombo { code: first, modifiers: KeyModifiers::NONE }, combo.clone()),
+            None => KeyTrigger::Single(combo),
+        };
+        let custom = self.lookup_custom(&trigger, mode).is_some();
+        let action = self.handle(raw_key, mode);
+        if action == Action::Noop && self.has_pending() {
+            return None;
+        }
+        Some(KeyDescription { keys: format_trigger(&trigger), action, custom })
+    }
+
     /// Cancel any pending sequence (e.g., on timeout).
     pub fn cancel_pending(&mut self) {
         self.pending = None;
@@ -1179,15 +1551,51 @@ mod tests {
             "trash",
             "spam",
             "move_to_folder",
+            "repeat_move",
+            "sweep_sender",
+            "triage_loop",
             "move_down",
             "sync_mail",
+            "headers_only_sync",
+            "toggle_shell_dry_run",
+            "filter_score",
+            "collapse_duplicates",
+            "expand_duplicates",
+            "toggle_direction",
+            "render_anyway",
             "create_split",
+            "split_plus_tag",
+            "copy_redacted",
+            "save_patch",
+            "apply_patch",
+            "pipe_thread",
+            "summarize_thread",
+            "export_search",
+            "header_actions",
+            "add_contact",
+            "approve_sender",
+            "block_sender",
             "open_account_picker",
             "quit",
             "open_thread",
             "compose",
             "reply_all",
             "help",
+            "describe_key",
+            "edit_note",
+            "history",
+            "recently_deleted",
+            "digest",
+            "delivery_info",
+            "toggle_sections",
+            "collapse_section",
+            "expand_sections",
+            "select_section",
+            "pause_inbox",
+            "copy_folder_url",
+            "copy_search_url",
+            "compare",
+            "compare_switch",
         ];
         for name in &names {
             assert!(
@@ -1251,6 +1659,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn describe_key_resolves_bindings() {
+        let section = BindingsSection {
+            global: [(
+                "g m".to_string(),
+                BindingValue::Shell { shell: "mbsync -a".to_string(), reindex: true, suspend: true },
+            )]
+            .into_iter()
+            .collect(),
+            normal: [("X".to_string(), BindingValue::Short("/Sent".to_string()))].into_iter().collect(),
+            thread: Default::default(),
+        };
+        let mut mapper = KeyMapper::new();
+        mapper.load_bindings(&section);
+        let key = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        // A sequence waits for its second key
+        assert_eq!(mapper.describe(key('g'), &InputMode::Normal), None);
+        let shell = mapper.describe(key('m'), &InputMode::Normal).unwrap();
+        assert_eq!(shell.line(None), "g m \u{2192} runs `mbsync -a` in the terminal, then reindexes [custom binding]");
+
+        let folder = mapper.describe(KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT), &InputMode::Normal).unwrap();
+        assert_eq!(folder.line(None), "X \u{2192} goes to /Sent [custom binding]");
+
+        let archive = mapper.describe(key('e'), &InputMode::Normal).unwrap();
+        assert_eq!(archive.line(Some("Archive")), "e \u{2192} Archive (archive) [default]");
+        mapper.describe(key('g'), &InputMode::Normal);
+        let inbox = mapper.describe(key('i'), &InputMode::Normal).unwrap();
+        assert_eq!(inbox.action, Action::GoInbox);
+        assert_eq!(mapper.describe(key('%'), &InputMode::Normal).unwrap().line(None), "% is not bound here");
+    }
+
     #[test]
     fn custom_folder_binding() {
         let section = BindingsSection {
@@ -1305,4 +1745,68 @@ mod tests {
             Action::ThreadToggleExpand
         );
     }
+
+    #[test]
+    fn hints_follow_custom_bindings() {
+        let entries = [
+            ("archive", "e", "archive"),
+            ("trash", "#", "trash"),
+            ("clear_selection", "Esc", "clear"),
+        ];
+        let mut mapper = KeyMapper::new();
+        assert_eq!(mapper.hint_line(&entries, &InputMode::Normal), "e archive \u{b7} # trash \u{b7} Esc clear");
+
+        // Rebinding `e` moves the archive hint to its new key and drops
+        // the trash hint from `e`'s new meaning
+        let section = BindingsSection {
+            global: [
+                ("e".to_string(), BindingValue::Short("trash".to_string())),
+                ("ctrl+x".to_string(), BindingValue::Short("archive".to_string())),
+            ]
+            .into_iter()
+            .collect(),
+            normal: [("#".to_string(), BindingValue::Short("compose".to_string()))]
+                .into_iter()
+                .collect(),
+            thread: Default::default(),
+        };
+        mapper.load_bindings(&section);
+        assert_eq!(
+            mapper.hint_line(&entries, &InputMode::Normal),
+            "Ctrl+x archive \u{b7} e trash \u{b7} Esc clear"
+        );
+        assert_eq!(mapper.hint_key("go_inbox", "g i", &InputMode::Normal), Some("g i".to_string()));
+    }
+
+    #[test]
+    fn quick_reply_key_and_input() {
+        let mut km = KeyMapper::new();
+        let q = KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::SHIFT);
+        assert_eq!(km.handle(q, &InputMode::Normal), Action::QuickReply);
+        // Typed text goes to the prompt, not to bindings
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(km.handle(j, &InputMode::QuickReply), Action::InputChar('j'));
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(km.handle(enter, &InputMode::QuickReply), Action::InputSubmit);
+    }
+
+    #[test]
+    fn view_tab_keys() {
+        let mut km = KeyMapper::new();
+        let alt3 = KeyEvent::new(KeyCode::Char('3'), KeyModifiers::ALT);
+        assert_eq!(km.handle(alt3, &InputMode::Normal), Action::SwitchView(2));
+        let g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        let n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE);
+        assert_eq!(km.handle(g, &InputMode::Normal), Action::Noop);
+        assert_eq!(km.handle(n, &InputMode::Normal), Action::NewView);
+        assert_eq!(parse_action_name("close_view"), Ok(Action::CloseView));
+    }
+
+    #[test]
+    fn repeat_move_key_in_list_and_thread() {
+        let mut km = KeyMapper::new();
+        let m = KeyEvent::new(KeyCode::Char('M'), KeyModifiers::SHIFT);
+        assert_eq!(km.handle(m, &InputMode::Normal), Action::RepeatMove);
+        assert_eq!(km.handle(m, &InputMode::ThreadView), Action::RepeatMove);
+    }
 }
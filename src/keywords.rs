@@ -0,0 +1,156 @@
+//! Maildir keywords: personal labels like "todo" or "waiting", kept in
+//! the message's `X-Keywords` header where other mail clients (Dovecot,
+//! Thunderbird, notmuch's sync) look for them, and which mu indexes as
+//! `tag:`. Toggling one delivers the message again with the header
+//! rewritten, as a new file in the same folder with the same flags; the
+//! new file is then added to mu, and the old one dropped, so `tag:todo`
+//! finds it.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// The header keywords are added to.
+const HEADER: &str = "X-Keywords";
+
+/// Headers mu reads keywords from; a keyword removed is removed from all.
+const KEYWORD_HEADERS: [&str; 3] = ["X-Keywords", "X-Label", "Keywords"];
+
+/// The raw message with `keyword` added to (`on`) or removed from its
+/// keyword headers. Everything else is left byte for byte.
+pub fn with_keyword(raw: &[u8], keyword: &str, on: bool) -> Vec<u8> {
+    // Up to and including the line ending the header, before the blank one
+    let header_end = [find(raw, b"\n\n"), find(raw, b"\n\r\n")]
+        .into_iter()
+        .flatten()
+        .min()
+        .map_or(raw.len(), |i| i + 1);
+    let (head, body) = raw.split_at(header_end);
+    let newline: &[u8] = if find(head, b"\r\n").is_some() { b"\r\n" } else { b"\n" };
+
+    // Each header with its continuation lines
+    let mut fields: Vec<&[u8]> = Vec::new();
+    let mut start = 0;
+    for (i, _) in head.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+        let next = head.get(i + 1);
+        if !matches!(next, Some(b' ' | b'\t')) {
+            fields.push(&head[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < head.len() {
+        fields.push(&head[start..]);
+    }
+
+    let mut out = Vec::with_capacity(raw.len() + keyword.len() + 16);
+    let mut present = false;
+    let mut added = !on;
+    for field in fields {
+        let text = String::from_utf8_lossy(field);
+        let Some((name, value)) = text.split_once(':') else {
+            out.extend_from_slice(field);
+            continue;
+        };
+        let name = name.trim();
+        if !KEYWORD_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name)) {
+            out.extend_from_slice(field);
+            continue;
+        }
+        let mut words: Vec<String> = split(value);
+        present |= words.iter().any(|w| w.eq_ignore_ascii_case(keyword));
+        words.retain(|w| !w.eq_ignore_ascii_case(keyword));
+        if !added && name.eq_ignore_ascii_case(HEADER) {
+            words.push(keyword.to_string());
+            added = true;
+        }
+        if !words.is_empty() {
+            out.extend_from_slice(format!("{}: {}", name, words.join(", ")).as_bytes());
+            out.extend_from_slice(newline);
+        }
+    }
+    if !added {
+        out.extend_from_slice(format!("{}: {}", HEADER, keyword).as_bytes());
+        out.extend_from_slice(newline);
+    }
+    if !on && !present {
+        return raw.to_vec();
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// The keywords in a header value, which may be comma- or space-separated.
+fn split(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Add `keyword` to (or remove it from) the message at `path`. The
+/// changed message replaces the file through the maildir's `tmp`, so it's
+/// never seen half-written; returns its new path, or None if nothing
+/// changed.
+pub fn set_in_file(path: &Path, keyword: &str, on: bool) -> Result<Option<PathBuf>> {
+    let raw = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let updated = with_keyword(&raw, keyword, on);
+    if updated == raw {
+        return Ok(None);
+    }
+    crate::maildir::replace(path, &updated).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggles_keywords_in_headers() {
+        let raw = "From: a@x\r\nSubject: Plan\r\n\r\nBody: not a header\r\n";
+        let tagged = with_keyword(raw.as_bytes(), "todo", true);
+        assert_eq!(
+            String::from_utf8(tagged.clone()).unwrap(),
+            "From: a@x\r\nSubject: Plan\r\nX-Keywords: todo\r\n\r\nBody: not a header\r\n"
+        );
+        let both = with_keyword(&tagged, "waiting", true);
+        assert!(String::from_utf8_lossy(&both).contains("X-Keywords: todo, waiting\r\n\r\n"));
+        assert!(String::from_utf8_lossy(&with_keyword(&both, "TODO", true)).contains("X-Keywords: waiting, TODO\r\n"));
+        assert_eq!(with_keyword(&with_keyword(&tagged, "todo", false), "todo", false), raw.as_bytes());
+
+        // Folded and other keyword headers lose it too
+        let raw = "Subject: x\nX-Label: todo,\n urgent\nKeywords: todo\n\nbody\n";
+        assert_eq!(
+            String::from_utf8(with_keyword(raw.as_bytes(), "todo", false)).unwrap(),
+            "Subject: x\nX-Label: urgent\n\nbody\n"
+        );
+        assert_eq!(with_keyword(raw.as_bytes(), "other", false), raw.as_bytes());
+    }
+
+    #[test]
+    fn rewrites_through_tmp_keeping_mode_and_flags() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("hutt-keywords-{}", std::process::id()));
+        crate::maildir::create_folder(&dir, "/Inbox").unwrap();
+        let raw = b"Subject: Plan\n\nbody\n";
+        let path = dir.join("Inbox/cur/1.M1P1Q1.host,S=20:2,FS");
+        std::fs::write(&path, raw).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        let moved = set_in_file(&path, "todo", true).unwrap().unwrap();
+        let updated = std::fs::read(&moved).unwrap();
+        assert_eq!(updated, b"Subject: Plan\nX-Keywords: todo\n\nbody\n");
+        let name = moved.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.ends_with(&format!(",S={}:2,FS", updated.len())), "{}", name);
+        assert_eq!(moved.parent(), path.parent());
+        assert_eq!(std::fs::metadata(&moved).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_dir(dir.join("Inbox/tmp")).unwrap().count(), 0);
+        assert_eq!(set_in_file(&moved, "other", false).unwrap(), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -7,12 +7,18 @@
 //! - `mid:<message-id>` — open a message (RFC 2392)
 //! - `mid:<message-id>?view=thread` — open a message's thread
 //! - `message:<message-id>` — open a message (IANA provisional, Apple Mail)
-//! - `mailto:addr?subject=text` — compose (RFC 6068)
+//! - `mailto:addr?subject=text` — compose (RFC 6068), with `cc`, `bcc`
+//!   and `body` fields too; `attach` is ignored, so a link can't attach
+//!   local files
 //!
 //! For app-specific operations with no standard scheme:
 //!
+//! - `hutt:compose?to=<addr>&...` — compose, taking the same fields as `mailto:`
 //! - `hutt:search?q=<query>[&account=<name>]` — run a search
 //! - `hutt:navigate?folder=<path>[&account=<name>]` — switch to a folder
+//! - `hutt://folder/<path>` — switch to a folder in the active account
+//! - `hutt://account/<name>[/<path>]` — switch to a folder in a given account
+//!   (its startup folder when no path is given)
 //!
 //! The `account` parameter is optional; when omitted, the active account
 //! is used. For `mid:` URLs, hutt searches all accounts since Message-IDs
@@ -24,10 +30,12 @@
 use anyhow::{bail, Context, Result};
 use arboard::Clipboard;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{UnixListener, UnixStream};
 
+use crate::compose::ComposeRequest;
+
 // ---------------------------------------------------------------------------
 // URI scheme types
 // ---------------------------------------------------------------------------
@@ -44,7 +52,9 @@ pub enum HuttUrl {
     /// Run a search query.
     Search { query: String, account: Option<String> },
     /// Open a compose window.
-    Compose { to: String, subject: String, account: Option<String> },
+    Compose(ComposeRequest),
+    /// Switch to a folder. An empty folder means the account's startup folder.
+    Folder { folder: String, account: Option<String> },
 }
 
 // ---------------------------------------------------------------------------
@@ -61,6 +71,50 @@ pub fn format_thread_url(message_id: &str) -> String {
     format!("mid:{}?view=thread", message_id)
 }
 
+/// Format a link to a message in Markdown or org-mode syntax, described as
+/// "Subject — Sender".
+pub fn format_message_link(
+    format: crate::config::LinkFormat,
+    message_id: &str,
+    subject: &str,
+    sender: &str,
+) -> String {
+    let subject = if subject.trim().is_empty() { "(no subject)" } else { subject.trim() };
+    let text = format!("{} — {}", subject, sender);
+    let url = format_message_url(message_id);
+    match format {
+        crate::config::LinkFormat::Markdown => {
+            let text = text.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]");
+            // Angle brackets let the URL contain parentheses
+            format!("[{}](<{}>)", text, url)
+        }
+        // Org has no escape for brackets in a description
+        crate::config::LinkFormat::Org => {
+            let text = text.replace('[', "(").replace(']', ")");
+            format!("[[{}][{}]]", url.replace('[', "%5B").replace(']', "%5D"), text)
+        }
+    }
+}
+
+/// Format a `hutt:search?q=<query>[&account=<name>]` URI.
+pub fn format_search_url(query: &str, account: Option<&str>) -> String {
+    let mut url = format!("hutt:search?q={}", url_encode_path(query));
+    if let Some(name) = account {
+        url.push_str("&account=");
+        url.push_str(&url_encode_path(name));
+    }
+    url
+}
+
+/// Format a `hutt://folder/<path>` URI, or `hutt://account/<name>/<path>`
+/// when an account is given.
+pub fn format_folder_url(folder: &str, account: Option<&str>) -> String {
+    let path = url_encode_path(folder.trim_start_matches('/'));
+    match account {
+        Some(name) => format!("hutt://account/{}/{}", url_encode_path(name).replace('/', "%2F"), path),
+        None => format!("hutt://folder/{}", path),
+    }
+}
 
 // ---------------------------------------------------------------------------
 // URI parsing (input — IPC, URL handler, clipboard)
@@ -71,10 +125,11 @@ pub fn format_thread_url(message_id: &str) -> String {
 /// Accepts:
 /// - `mid:<message-id>[?view=thread][&account=name]`
 /// - `message:<message-id>` or `message://<message-id>`
-/// - `mailto:addr[?subject=text&account=name]`
+/// - `mailto:addr[,addr][?subject=text&cc=addr&body=text&account=name]`
+/// - `hutt:compose?to=addr[&...]`, with the same fields as `mailto:`
 /// - `hutt:search?q=query[&account=name]`
 /// - `hutt:navigate?folder=path[&account=name]`
-/// - Legacy: `hutt://message/id`, `hutt://thread/id`, `hutt://search/q`, `hutt://compose?...`
+/// - Legacy: `hutt://message/id`, `hutt://thread/id`, `hutt://search/q`
 pub fn parse_url(url: &str) -> Option<HuttUrl> {
     // mid:<message-id>[/<content-id>][?view=thread][&account=name]
     if let Some(rest) = url.strip_prefix("mid:") {
@@ -114,14 +169,10 @@ pub fn parse_url(url: &str) -> Option<HuttUrl> {
         return Some(HuttUrl::Message { id: id.to_string(), account: None });
     }
 
-    // mailto:addr[?subject=text]
+    // mailto:addr[?subject=text&...]
     if let Some(rest) = url.strip_prefix("mailto:") {
-        let (addr, qs) = split_query(rest);
-        let params = parse_query_string(qs);
-        let to = url_decode(addr);
-        let subject = params.get("subject").cloned().unwrap_or_default();
-        let account = params.get("account").cloned();
-        return Some(HuttUrl::Compose { to, subject, account });
+        let (addrs, qs) = split_query(rest);
+        return Some(HuttUrl::Compose(compose_request(&url_decode(addrs), parse_query_pairs(qs), false)));
     }
 
     // hutt:search?q=... and hutt:navigate?folder=...
@@ -157,6 +208,24 @@ fn parse_hutt_path(rest: &str) -> Option<HuttUrl> {
         return None;
     }
 
+    // hutt://folder/<path>
+    if let Some(folder) = path.strip_prefix("folder/") {
+        if folder.is_empty() { return None; }
+        return Some(HuttUrl::Folder { folder: format!("/{}", url_decode(folder)), account });
+    }
+
+    // hutt://account/<name>[/<path>]
+    if let Some(rest) = path.strip_prefix("account/") {
+        let (name, folder) = rest.split_once('/').unwrap_or((rest, ""));
+        if name.is_empty() { return None; }
+        let folder = if folder.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", url_decode(folder))
+        };
+        return Some(HuttUrl::Folder { folder, account: Some(url_decode(name)) });
+    }
+
     // Legacy: hutt://message/<id>
     if let Some(id) = path.strip_prefix("message/") {
         if id.is_empty() { return None; }
@@ -176,11 +245,9 @@ fn parse_hutt_path(rest: &str) -> Option<HuttUrl> {
         return Some(HuttUrl::Search { query, account });
     }
 
-    // Legacy: hutt://compose?to=...&subject=...
+    // hutt:compose?to=...&subject=...
     if path == "compose" {
-        let to = params.get("to").cloned().unwrap_or_default();
-        let subject = params.get("subject").cloned().unwrap_or_default();
-        return Some(HuttUrl::Compose { to, subject, account });
+        return Some(HuttUrl::Compose(compose_request("", parse_query_pairs(qs), false)));
     }
 
     None
@@ -221,13 +288,13 @@ pub fn copy_to_clipboard(text: &str) -> Result<()> {
 // Open in browser
 // ---------------------------------------------------------------------------
 
-/// Write HTML bytes to a temp file and open it in the default browser.
-pub fn open_html_in_browser(html: &[u8]) -> Result<()> {
+/// Write HTML bytes to a temp file for opening in a browser.
+pub fn write_temp_html(html: &[u8]) -> Result<PathBuf> {
     let dir = std::env::temp_dir();
     let path = dir.join(format!("hutt-{}.html", std::process::id()));
     std::fs::write(&path, html)
         .with_context(|| format!("writing temp HTML to {}", path.display()))?;
-    open_path(path.to_str().context("non-UTF-8 temp path")?)
+    Ok(path)
 }
 
 pub fn open_path(target: &str) -> Result<()> {
@@ -248,6 +315,60 @@ pub fn open_path(target: &str) -> Result<()> {
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Remote sessions
+// ---------------------------------------------------------------------------
+
+/// Whether hutt is running over SSH, where xdg-open would open things on
+/// the server and the system clipboard is the server's.
+pub fn detect_remote() -> bool {
+    ["SSH_CONNECTION", "SSH_TTY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+/// OSC 52 escape sequence asking the terminal to put `text` on the local
+/// clipboard. Works through SSH; tmux needs `set-clipboard on`.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Copy text to the clipboard of the terminal hutt is displayed on.
+pub fn copy_via_terminal(text: &str) -> Result<()> {
+    use std::io::Write;
+    let mut out = std::io::stdout();
+    out.write_all(osc52(text).as_bytes())
+        .and_then(|()| out.flush())
+        .context("writing clipboard escape to terminal")
+}
+
+/// `scp://` URL for fetching `path` from this machine, netrw style: an
+/// absolute path follows the host after a double slash.
+pub fn scp_url(path: &Path) -> String {
+    let host = crate::maildir::gethostname();
+    let path = path.to_string_lossy();
+    match std::env::var("USER") {
+        Ok(user) if !user.is_empty() => format!("scp://{}@{}/{}", user, host, path),
+        _ => format!("scp://{}/{}", host, path),
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
 // ---------------------------------------------------------------------------
 // IPC socket
 // ---------------------------------------------------------------------------
@@ -262,7 +383,21 @@ pub enum IpcCommand {
         #[serde(skip_serializing_if = "Option::is_none")]
         account: Option<String>,
     },
+    /// Open the message stored at a file path on disk.
+    OpenPath {
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        account: Option<String>,
+    },
+    /// Open a message by its mu docid.
+    OpenDocid {
+        docid: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        account: Option<String>,
+    },
     Quit,
+    /// Open compose with structured fields (`hutt compose --json`).
+    Compose(crate::compose::ComposeRequest),
     MuCommand {
         sexp: String,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -306,9 +441,9 @@ pub enum HuttUrlSerde {
         #[serde(skip_serializing_if = "Option::is_none")]
         account: Option<String>,
     },
-    Compose {
-        to: String,
-        subject: String,
+    Compose(ComposeRequest),
+    Folder {
+        folder: String,
         #[serde(skip_serializing_if = "Option::is_none")]
         account: Option<String>,
     },
@@ -321,7 +456,8 @@ impl From<HuttUrl> for HuttUrlSerde {
             HuttUrl::MessagePart { message_id, content_id, account } => HuttUrlSerde::MessagePart { message_id, content_id, account },
             HuttUrl::Thread { id, account } => HuttUrlSerde::Thread { id, account },
             HuttUrl::Search { query, account } => HuttUrlSerde::Search { query, account },
-            HuttUrl::Compose { to, subject, account } => HuttUrlSerde::Compose { to, subject, account },
+            HuttUrl::Compose(req) => HuttUrlSerde::Compose(req),
+            HuttUrl::Folder { folder, account } => HuttUrlSerde::Folder { folder, account },
         }
     }
 }
@@ -333,39 +469,104 @@ impl From<HuttUrlSerde> for HuttUrl {
             HuttUrlSerde::MessagePart { message_id, content_id, account } => HuttUrl::MessagePart { message_id, content_id, account },
             HuttUrlSerde::Thread { id, account } => HuttUrl::Thread { id, account },
             HuttUrlSerde::Search { query, account } => HuttUrl::Search { query, account },
-            HuttUrlSerde::Compose { to, subject, account } => HuttUrl::Compose { to, subject, account },
+            HuttUrlSerde::Compose(req) => HuttUrl::Compose(req),
+            HuttUrlSerde::Folder { folder, account } => HuttUrl::Folder { folder, account },
         }
     }
 }
 
-/// Determine the IPC socket path.
-pub fn socket_path() -> PathBuf {
+/// Directory holding the IPC sockets and the file name prefix they share:
+/// `$XDG_RUNTIME_DIR/hutt…`, or `/tmp/hutt-<uid>…` without a runtime dir.
+fn socket_base() -> (PathBuf, String) {
     if let Ok(dir) = std::env::var("XDG_RUNTIME_DIR") {
-        PathBuf::from(dir).join("hutt.sock")
+        (PathBuf::from(dir), "hutt".to_string())
     } else {
         let uid = unsafe { libc::getuid() };
-        PathBuf::from(format!("/tmp/hutt-{}.sock", uid))
+        (PathBuf::from("/tmp"), format!("hutt-{}", uid))
+    }
+}
+
+fn socket_file(dir: &Path, prefix: &str, instance: Option<&str>) -> PathBuf {
+    match instance {
+        Some(name) => dir.join(format!("{}-{}.sock", prefix, name)),
+        None => dir.join(format!("{}.sock", prefix)),
     }
 }
 
+/// IPC socket path of an instance, named by `--socket` or its pid. With
+/// `None`, the `hutt.sock` link to the most recently started instance.
+pub fn socket_path(instance: Option<&str>) -> PathBuf {
+    let (dir, prefix) = socket_base();
+    socket_file(&dir, &prefix, instance)
+}
+
+/// Names of the running instances, oldest first.
+pub fn live_instances() -> Vec<String> {
+    let (dir, prefix) = socket_base();
+    live_instances_in(&dir, &prefix)
+}
+
+fn live_instances_in(dir: &Path, prefix: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let lead = format!("{}-", prefix);
+    let mut found: Vec<(std::time::SystemTime, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.strip_prefix(&lead)?.strip_suffix(".sock")?.to_string();
+            // Only sockets something is still listening on
+            std::os::unix::net::UnixStream::connect(entry.path()).ok()?;
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, name))
+        })
+        .collect();
+    found.sort();
+    found.into_iter().map(|(_, name)| name).collect()
+}
+
 /// Server-side IPC listener wrapping a tokio `UnixListener`.
 pub struct IpcListener {
     listener: UnixListener,
     path: PathBuf,
+    /// The shared `hutt.sock` link, pointed at this instance while it runs
+    link: PathBuf,
+    prefix: String,
 }
 
 impl IpcListener {
-    /// Create and bind the Unix domain socket.  Removes a stale socket file
-    /// if one already exists.
-    pub fn bind() -> Result<Self> {
-        let path = socket_path();
+    /// Create and bind this instance's socket, `hutt-<name>.sock` (the pid
+    /// if no name is given), and point `hutt.sock` at it. Removes a stale
+    /// socket file, but refuses to take over one another instance is
+    /// listening on.
+    pub fn bind(name: Option<&str>) -> Result<Self> {
+        let (dir, prefix) = socket_base();
+        let name = name.map_or_else(|| std::process::id().to_string(), str::to_string);
+        Self::bind_in(&dir, &prefix, &name)
+    }
+
+    fn bind_in(dir: &Path, prefix: &str, name: &str) -> Result<Self> {
+        if name.is_empty() || name.contains('/') {
+            bail!("invalid socket name {:?}", name);
+        }
+        let path = socket_file(dir, prefix, Some(name));
         if path.exists() {
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                bail!("another hutt instance is already using socket {:?}", name);
+            }
             std::fs::remove_file(&path)
                 .with_context(|| format!("removing stale socket {}", path.display()))?;
         }
         let listener =
             UnixListener::bind(&path).with_context(|| format!("binding {}", path.display()))?;
-        Ok(Self { listener, path })
+
+        let link = socket_file(dir, prefix, None);
+        let _ = std::fs::remove_file(&link);
+        if let Err(e) = std::os::unix::fs::symlink(&path, &link) {
+            eprintln!("IPC socket: linking {}: {}", link.display(), e);
+        }
+        Ok(Self { listener, path, link, prefix: prefix.to_string() })
     }
 
     /// Accept a single connection, read a JSON-encoded `IpcCommand`,
@@ -397,6 +598,35 @@ impl IpcListener {
 impl Drop for IpcListener {
     fn drop(&mut self) {
         let _ = std::fs::remove_file(&self.path);
+        // Hand hutt.sock over to the newest instance still running
+        if std::fs::read_link(&self.link).is_ok_and(|target| target == self.path) {
+            let _ = std::fs::remove_file(&self.link);
+            let dir = self.link.parent().unwrap_or(Path::new("."));
+            if let Some(newest) = live_instances_in(dir, &self.prefix).pop() {
+                let target = socket_file(dir, &self.prefix, Some(&newest));
+                let _ = std::os::unix::fs::symlink(target, &self.link);
+            }
+        }
+    }
+}
+
+/// Where the response to an IPC command goes.
+pub enum IpcReply {
+    /// A Unix socket client waiting for the JSON response
+    Socket(UnixStream),
+    /// An in-process caller, such as the HTTP API
+    Channel(tokio::sync::oneshot::Sender<IpcResponse>),
+}
+
+impl IpcReply {
+    pub async fn send(self, resp: &IpcResponse) -> Result<()> {
+        match self {
+            IpcReply::Socket(mut stream) => send_response(&mut stream, resp).await,
+            IpcReply::Channel(tx) => {
+                let _ = tx.send(resp.clone());
+                Ok(())
+            }
+        }
     }
 }
 
@@ -408,11 +638,86 @@ pub async fn send_response(stream: &mut UnixStream, resp: &IpcResponse) -> Resul
     Ok(())
 }
 
-/// Client side: connect to the running hutt instance, send a command,
-/// and read back the response.
-pub async fn send_ipc_command(cmd: &IpcCommand) -> Result<IpcResponse> {
-    let path = socket_path();
+/// Parse one line of the pipe protocol: a JSON-encoded `IpcCommand`, the
+/// same encoding the socket uses. Blank lines and `#` comments give `None`.
+pub fn parse_command_line(line: &str) -> Option<Result<IpcCommand>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    Some(serde_json::from_str(line).context("deserializing IPC command"))
+}
+
+/// Read newline-delimited IPC commands from a FIFO, or from stdin when
+/// `path` is `-`, passing each to `on_command` until it returns `false`.
+/// Blocks, so run it on its own thread.
+///
+/// The FIFO is created (mode 0600) if it doesn't exist, and reopened
+/// whenever its last writer closes it, so a sequence of
+/// `echo '{...}' > fifo` calls all get through. Stdin is read until EOF.
+pub fn read_command_pipe(
+    path: &str,
+    mut on_command: impl FnMut(Result<IpcCommand>) -> bool,
+) -> Result<()> {
+    use std::io::BufRead;
+
+    let mut read_lines = |reader: &mut dyn BufRead| -> Result<bool> {
+        for line in reader.lines() {
+            let line = line.context("reading IPC pipe")?;
+            if let Some(cmd) = parse_command_line(&line) {
+                if !on_command(cmd) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    };
+
+    if path == "-" {
+        read_lines(&mut std::io::stdin().lock())?;
+        return Ok(());
+    }
+
+    let path = PathBuf::from(path);
     if !path.exists() {
+        let c_path = std::ffi::CString::new(path.as_os_str().as_encoded_bytes())
+            .context("invalid FIFO path")?;
+        if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("creating FIFO {}", path.display()));
+        }
+    } else {
+        use std::os::unix::fs::FileTypeExt;
+        // Reopening a regular file would replay its commands forever
+        if !std::fs::metadata(&path).is_ok_and(|m| m.file_type().is_fifo()) {
+            bail!("{} is not a FIFO", path.display());
+        }
+    }
+    loop {
+        // Opening blocks until a writer appears
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        if !read_lines(&mut std::io::BufReader::new(file))? {
+            return Ok(());
+        }
+    }
+}
+
+/// Client side: connect to a running hutt instance (the most recently
+/// started one unless `instance` names another), send a command, and read
+/// back the response.
+pub async fn send_ipc_command(instance: Option<&str>, cmd: &IpcCommand) -> Result<IpcResponse> {
+    let path = socket_path(instance);
+    if !path.exists() {
+        if instance.is_some() {
+            let running = live_instances();
+            bail!(
+                "no hutt instance {:?} running (socket {} not found). Running: {}",
+                instance.unwrap_or_default(),
+                path.display(),
+                if running.is_empty() { "none".to_string() } else { running.join(", ") }
+            );
+        }
         bail!(
             "no hutt instance running (socket {} not found)",
             path.display()
@@ -448,7 +753,7 @@ pub async fn send_ipc_command(cmd: &IpcCommand) -> Result<IpcResponse> {
 // Helpers: minimal percent-encoding / decoding
 // ---------------------------------------------------------------------------
 
-fn url_decode(s: &str) -> String {
+pub(crate) fn url_decode(s: &str) -> String {
     let mut out = Vec::with_capacity(s.len());
     let bytes = s.as_bytes();
     let mut i = 0;
@@ -466,6 +771,19 @@ fn url_decode(s: &str) -> String {
     String::from_utf8_lossy(&out).into_owned()
 }
 
+/// Percent-encode everything but unreserved characters and `/`.
+fn url_encode_path(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~/".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
 fn from_hex(b: u8) -> Option<u8> {
     match b {
         b'0'..=b'9' => Some(b - b'0'),
@@ -476,24 +794,55 @@ fn from_hex(b: u8) -> Option<u8> {
 }
 
 /// Split a URI string into path and query components at the first `?`.
-fn split_query(s: &str) -> (&str, &str) {
+pub(crate) fn split_query(s: &str) -> (&str, &str) {
     match s.split_once('?') {
         Some((path, qs)) => (path, qs),
         None => (s, ""),
     }
 }
 
-fn parse_query_string(qs: &str) -> std::collections::HashMap<String, String> {
-    let mut map = std::collections::HashMap::new();
-    if qs.is_empty() {
-        return map;
+pub(crate) fn parse_query_string(qs: &str) -> std::collections::HashMap<String, String> {
+    parse_query_pairs(qs).into_iter().collect()
+}
+
+/// Decoded query string fields in order, keeping repeated ones.
+pub(crate) fn parse_query_pairs(qs: &str) -> Vec<(String, String)> {
+    qs.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (url_decode(k), url_decode(v)))
+        .collect()
+}
+
+/// Build a compose request from a `mailto:` address list and decoded
+/// query fields (RFC 6068). Address lists are comma-separated and `to`,
+/// `cc`, `bcc` and `attach` may repeat; field names are case-insensitive
+/// and the body's CRLF line breaks become plain newlines.
+///
+/// `attach` fields are only taken with `attachments`, for requests made
+/// from the command line or the HTTP API. A URL could come from any web
+/// page or document, and mustn't be able to attach the user's files.
+pub(crate) fn compose_request(addrs: &str, fields: Vec<(String, String)>, attachments: bool) -> ComposeRequest {
+    fn push_addresses(list: &mut Vec<String>, value: &str) {
+        list.extend(value.split(',').map(str::trim).filter(|a| !a.is_empty()).map(String::from));
     }
-    for pair in qs.split('&') {
-        if let Some((k, v)) = pair.split_once('=') {
-            map.insert(url_decode(k), url_decode(v));
+    let mut req = ComposeRequest::default();
+    push_addresses(&mut req.to, addrs);
+    for (key, value) in fields {
+        match key.to_ascii_lowercase().as_str() {
+            "to" => push_addresses(&mut req.to, &value),
+            "cc" => push_addresses(&mut req.cc, &value),
+            "bcc" => push_addresses(&mut req.bcc, &value),
+            "subject" => req.subject = value,
+            "body" => req.body = value.replace("\r\n", "\n"),
+            "attach" | "attachment" if attachments => {
+                let path = value.strip_prefix("file://").map(url_decode).unwrap_or(value);
+                req.attachments.push(path);
+            }
+            "account" => req.account = Some(value),
+            _ => {}
         }
     }
-    map
+    req
 }
 
 // ---------------------------------------------------------------------------
@@ -504,6 +853,45 @@ fn parse_query_string(qs: &str) -> std::collections::HashMap<String, String> {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn instance_sockets_and_shared_link() {
+        let dir = std::env::temp_dir().join(format!("hutt-sockets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let link = socket_file(&dir, "hutt", None);
+
+        let work = IpcListener::bind_in(&dir, "hutt", "work").unwrap();
+        assert_eq!(std::fs::read_link(&link).unwrap(), dir.join("hutt-work.sock"));
+        // A name that's in use can't be taken over
+        assert!(IpcListener::bind_in(&dir, "hutt", "work").is_err());
+        assert!(IpcListener::bind_in(&dir, "hutt", "a/b").is_err());
+
+        let home = IpcListener::bind_in(&dir, "hutt", "home").unwrap();
+        assert_eq!(std::fs::read_link(&link).unwrap(), dir.join("hutt-home.sock"));
+        let mut running = live_instances_in(&dir, "hutt");
+        running.sort();
+        assert_eq!(running, vec!["home", "work"]);
+
+        // When the newest instance exits, the link moves to the other one
+        drop(home);
+        assert_eq!(std::fs::read_link(&link).unwrap(), dir.join("hutt-work.sock"));
+        drop(work);
+        assert!(std::fs::symlink_metadata(&link).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn pipe_command_lines() {
+        assert!(parse_command_line("").is_none());
+        assert!(parse_command_line("  # open the inbox").is_none());
+        let cmd = parse_command_line(r#"{"type":"Navigate","folder":"/Inbox"}"#)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(cmd, IpcCommand::Navigate { ref folder, account: None } if folder == "/Inbox"));
+        assert!(matches!(parse_command_line(r#"{"type":"Quit"}"#), Some(Ok(IpcCommand::Quit))));
+        assert!(parse_command_line("navigate /Inbox").unwrap().is_err());
+    }
+
     // ── mid: URLs ──────────────────────────────────────────────
 
     #[test]
@@ -596,11 +984,11 @@ mod tests {
     fn parse_mailto() {
         assert_eq!(
             parse_url("mailto:bob@example.com?subject=Hello%20World"),
-            Some(HuttUrl::Compose {
-                to: "bob@example.com".into(),
+            Some(HuttUrl::Compose(ComposeRequest {
+                to: vec!["bob@example.com".into()],
                 subject: "Hello World".into(),
-                account: None,
-            })
+                ..ComposeRequest::default()
+            }))
         );
     }
 
@@ -608,14 +996,41 @@ mod tests {
     fn parse_mailto_bare() {
         assert_eq!(
             parse_url("mailto:bob@example.com"),
-            Some(HuttUrl::Compose {
-                to: "bob@example.com".into(),
-                subject: String::new(),
-                account: None,
-            })
+            Some(HuttUrl::Compose(ComposeRequest {
+                to: vec!["bob@example.com".into()],
+                ..ComposeRequest::default()
+            }))
         );
     }
 
+    #[test]
+    fn parse_mailto_all_fields() {
+        let url = "mailto:bob@example.com,carol@example.com?To=dan%40example.com\
+                   &cc=eve@example.com,%20fay@example.com&bcc=gus@example.com\
+                   &subject=Caf%C3%A9%20plans&body=Hi%20both%2C%0D%0A%0D%0ASee%20attached.\
+                   &attach=%2Ftmp%2Fmenu.pdf&attach=file:///tmp/map%20v2.png&account=work";
+        assert_eq!(
+            parse_url(url),
+            Some(HuttUrl::Compose(ComposeRequest {
+                to: vec!["bob@example.com".into(), "carol@example.com".into(), "dan@example.com".into()],
+                cc: vec!["eve@example.com".into(), "fay@example.com".into()],
+                bcc: vec!["gus@example.com".into()],
+                subject: "Café plans".into(),
+                body: "Hi both,\n\nSee attached.".into(),
+                account: Some("work".into()),
+                ..ComposeRequest::default()
+            }))
+        );
+        // Attachments only come from the command line and the HTTP API
+        let fields = parse_query_pairs("attach=%2Ftmp%2Fmenu.pdf&attach=file:///tmp/map%20v2.png");
+        assert_eq!(compose_request("", fields, true).attachments, ["/tmp/menu.pdf", "/tmp/map v2.png"]);
+        // The same fields over IPC
+        let sent: HuttUrlSerde = parse_url(url).unwrap().into();
+        let json = serde_json::to_string(&sent).unwrap();
+        let received: HuttUrlSerde = serde_json::from_str(&json).unwrap();
+        assert_eq!(HuttUrl::from(received), parse_url(url).unwrap());
+    }
+
     // ── hutt: URLs (new format) ────────────────────────────────
 
     #[test]
@@ -646,6 +1061,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_hutt_folder_and_account() {
+        assert_eq!(
+            parse_url("hutt://folder/Archive/2024"),
+            Some(HuttUrl::Folder { folder: "/Archive/2024".into(), account: None })
+        );
+        assert_eq!(
+            parse_url("hutt://account/My%20Work/Projects%20%26%20Plans"),
+            Some(HuttUrl::Folder {
+                folder: "/Projects & Plans".into(),
+                account: Some("My Work".into()),
+            })
+        );
+        assert_eq!(
+            parse_url("hutt://account/work"),
+            Some(HuttUrl::Folder { folder: String::new(), account: Some("work".into()) })
+        );
+        assert_eq!(parse_url("hutt://folder/"), None);
+        assert_eq!(parse_url("hutt://account/"), None);
+    }
+
+    #[test]
+    fn message_links() {
+        use crate::config::LinkFormat;
+        assert_eq!(
+            format_message_link(LinkFormat::Markdown, "abc@example.com", "Invoice [#42]", "Alice"),
+            "[Invoice \\[#42\\] — Alice](<mid:abc@example.com>)"
+        );
+        assert_eq!(
+            format_message_link(LinkFormat::Org, "abc@example.com", "Invoice [#42]", "Alice"),
+            "[[mid:abc@example.com][Invoice (#42) — Alice]]"
+        );
+        assert_eq!(
+            format_message_link(LinkFormat::Org, "x@y", " ", "bob@example.com"),
+            "[[mid:x@y][(no subject) — bob@example.com]]"
+        );
+    }
+
+    #[test]
+    fn search_url_roundtrip() {
+        let query = "subject:invoice AND NOT flag:replied AND date:{last30days}..now";
+        let url = format_search_url(query, Some("My Work"));
+        assert_eq!(
+            parse_url(&url),
+            Some(HuttUrl::Search { query: query.into(), account: Some("My Work".into()) })
+        );
+        assert_eq!(format_search_url("from:a&b", None), "hutt:search?q=from%3Aa%26b");
+    }
+
+    #[test]
+    fn folder_url_roundtrip() {
+        for (folder, account) in [
+            ("/Inbox", None),
+            ("/Archive/2024", Some("work")),
+            ("/Projects & Plans", Some("My Work")),
+        ] {
+            let url = format_folder_url(folder, account);
+            assert_eq!(
+                parse_url(&url),
+                Some(HuttUrl::Folder {
+                    folder: folder.into(),
+                    account: account.map(str::to_string),
+                }),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(format_folder_url("/Inbox", Some("work")), "hutt://account/work/Inbox");
+    }
+
     // ── Legacy hutt:// URLs ────────────────────────────────────
 
     #[test]
@@ -673,15 +1158,14 @@ mod tests {
     }
 
     #[test]
-    fn parse_legacy_compose() {
-        assert_eq!(
-            parse_url("hutt://compose?to=bob%40example.com&subject=Hello"),
-            Some(HuttUrl::Compose {
-                to: "bob@example.com".into(),
-                subject: "Hello".into(),
-                account: None,
-            })
-        );
+    fn parse_hutt_compose() {
+        let expected = Some(HuttUrl::Compose(ComposeRequest {
+            to: vec!["bob@example.com".into()],
+            subject: "Hello".into(),
+            ..ComposeRequest::default()
+        }));
+        assert_eq!(parse_url("hutt:compose?to=bob%40example.com&subject=Hello"), expected);
+        assert_eq!(parse_url("hutt://compose?to=bob%40example.com&subject=Hello"), expected);
     }
 
     // ── Invalid URLs ───────────────────────────────────────────
@@ -759,6 +1243,14 @@ mod tests {
                 folder: "/Inbox".to_string(),
                 account: None,
             },
+            IpcCommand::OpenPath {
+                path: "/home/me/Maildir/INBOX/cur/123:2,S".to_string(),
+                account: Some("work".to_string()),
+            },
+            IpcCommand::OpenDocid {
+                docid: 42,
+                account: None,
+            },
             IpcCommand::Quit,
         ];
 
@@ -769,4 +1261,17 @@ mod tests {
             assert_eq!(json, json2);
         }
     }
+
+    #[test]
+    fn remote_clipboard_and_scp_urls() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(osc52("hi ✓"), "\x1b]52;c;aGkg4pyT\x07");
+
+        let url = scp_url(Path::new("/tmp/report.pdf"));
+        assert!(url.starts_with("scp://"));
+        assert!(url.ends_with(&format!("{}//tmp/report.pdf", crate::maildir::gethostname())));
+    }
 }
@@ -1,4 +1,12 @@
 use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A stat of the maildir root slower than this means a slow mount.
+const SLOW_STAT: Duration = Duration::from_millis(500);
+
+/// How long to wait for the stat before calling the mount offline.
+const STAT_TIMEOUT: Duration = Duration::from_secs(3);
 
 /// Expand `~/` prefix in a maildir root path.
 pub fn expand_maildir_root(maildir: &str) -> String {
@@ -10,35 +18,342 @@ pub fn expand_maildir_root(maildir: &str) -> String {
     }
 }
 
-/// Save a formatted message to the Sent maildir folder.
-pub fn save_to_sent(maildir_root: &str, sent_folder: &str, message: &[u8]) -> Result<()> {
-    let root = expand_maildir_root(maildir_root);
-    let sent_cur = format!("{}{}/cur", root, sent_folder);
+/// Save a formatted message to the Sent maildir folder, returning the path
+/// of the new file.
+pub fn save_to_sent(maildir_root: &str, sent_folder: &str, message: &[u8]) -> Result<PathBuf> {
+    let root = PathBuf::from(expand_maildir_root(maildir_root));
+    create_folder(&root, sent_folder)?;
+    deliver(&root.join(sent_folder.trim_start_matches('/')), message, "S")
+}
+
+/// Deliver a message into the maildir `dir` the way the maildir spec
+/// asks: written and synced under `tmp`, then renamed into place in one
+/// step, so a sync tool never sees a partial file. Messages with `flags`
+/// go to `cur` with them as the info suffix; without, to `new`.
+pub fn deliver(dir: &Path, message: &[u8], flags: &str) -> Result<PathBuf> {
+    let name = unique_name(message.len());
+    let tmp = dir.join("tmp").join(&name);
+    let dest = if flags.is_empty() {
+        dir.join("new").join(&name)
+    } else {
+        dir.join("cur").join(format!("{}:2,{}", name, flags))
+    };
+    write_via_tmp(&tmp, &dest, message, 0o666)?;
+    Ok(dest)
+}
+
+/// Replace the message file at `path` with `message`, delivered as
+/// [`deliver`] does: under the folder's `tmp` with the old file's
+/// permissions, then into the same `cur` or `new` under a fresh name, so
+/// its `,S=` size is right, keeping its `:2,` flags. The old file is then
+/// removed. Returns the new path.
+pub fn replace(path: &Path, message: &[u8]) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (Some(sub), Some(name)) = (path.parent(), path.file_name()) else {
+        anyhow::bail!("not a maildir message: {}", path.display());
+    };
+    let dir = sub.parent().with_context(|| format!("not a maildir message: {}", path.display()))?;
+    let mode = std::fs::metadata(path)
+        .with_context(|| format!("failed to read {}", path.display()))?
+        .permissions()
+        .mode();
+    let unique = unique_name(message.len());
+    let tmp = dir.join("tmp").join(&unique);
+    let dest = match name.to_string_lossy().split_once(":2,") {
+        Some((_, flags)) => sub.join(format!("{}:2,{}", unique, flags)),
+        None => sub.join(&unique),
+    };
+    write_via_tmp(&tmp, &dest, message, mode & 0o7777)?;
+    std::fs::remove_file(path).with_context(|| format!("failed to remove {}", path.display()))?;
+    Ok(dest)
+}
+
+/// Write and sync `message` to `tmp`, created with `mode` (less the
+/// umask), then rename it to `dest` and make the rename durable.
+fn write_via_tmp(tmp: &Path, dest: &Path, message: &[u8], mode: u32) -> Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
 
-    // Ensure the Sent/cur directory exists
-    std::fs::create_dir_all(&sent_cur)
-        .with_context(|| format!("failed to create {}", sent_cur))?;
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).mode(mode).open(tmp)?;
+        file.write_all(message)?;
+        file.sync_all()
+    };
+    if let Err(e) = write() {
+        let _ = std::fs::remove_file(tmp);
+        return Err(e).with_context(|| format!("failed to write {}", tmp.display()));
+    }
+    std::fs::rename(tmp, dest).with_context(|| format!("failed to move message to {}", dest.display()))?;
+    // Make the rename itself durable
+    if let Some(parent) = dest.parent() {
+        if let Ok(d) = std::fs::File::open(parent) {
+            let _ = d.sync_all();
+        }
+    }
+    Ok(())
+}
 
-    // Maildir filename: time.pid_seq.hostname:2,S (Seen flag)
-    let timestamp = std::time::SystemTime::now()
+/// A unique maildir filename (without the `:2,` info): the delivery time
+/// in seconds and microseconds, pid, per-process counter and hostname,
+/// plus the `,S=<size>` attribute that sync tools read the size from.
+pub fn unique_name(size: usize) -> String {
+    let now = SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    let hostname = gethostname();
-    let filename = format!(
-        "{}.{}_{}.{}:2,S",
-        timestamp,
+        .unwrap_or_default();
+    // `/` and `:` can't appear in the host part; the spec escapes them
+    let host = gethostname().replace('/', "\\057").replace(':', "\\072");
+    format!(
+        "{}.M{}P{}Q{}.{},S={}",
+        now.as_secs(),
+        now.subsec_micros(),
         std::process::id(),
         rand_seq(),
-        hostname,
-    );
-    let path = format!("{}/{}", sent_cur, filename);
+        host,
+        size,
+    )
+}
 
-    std::fs::write(&path, message).with_context(|| format!("failed to save to {}", path))?;
+/// Whether `folder` (e.g. "/Sent") is a maildir under `root`, either as
+/// a plain subdirectory or in Maildir++ form (`.Sent`).
+pub fn folder_exists(root: &Path, folder: &str) -> bool {
+    let name = folder.trim_start_matches('/');
+    !name.is_empty()
+        && (root.join(name).join("cur").is_dir()
+            || root.join(format!(".{}", name)).join("cur").is_dir())
+}
 
+/// Create the `cur`/`new`/`tmp` directories for a maildir folder.
+pub fn create_folder(root: &Path, folder: &str) -> Result<()> {
+    let dir = root.join(folder.trim_start_matches('/'));
+    for sub in ["cur", "new", "tmp"] {
+        let path = dir.join(sub);
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+    }
     Ok(())
 }
 
+/// How far before `since` a directory mtime still counts as changed, for
+/// filesystems that keep mtimes to the second (HFS+, FAT, some NFS).
+const MTIME_SLACK: Duration = Duration::from_secs(2);
+
+/// Maildir `cur`/`new` directories under `root` modified at or after
+/// `since`, less [`MTIME_SLACK`]. Delivering, renaming or deleting a
+/// message updates its directory's mtime, so this is the set of folders a
+/// sync touched.
+pub fn changed_dirs(root: &Path, since: SystemTime) -> Vec<PathBuf> {
+    let since = since.checked_sub(MTIME_SLACK).unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut changed = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            let path = entry.path();
+            match entry.file_name().to_str() {
+                Some("cur" | "new") => {
+                    let modified = entry.metadata().and_then(|m| m.modified());
+                    if modified.is_ok_and(|t| t >= since) {
+                        changed.push(path);
+                    }
+                }
+                Some("tmp") => {}
+                _ => stack.push(path),
+            }
+        }
+    }
+    changed.sort();
+    changed
+}
+
+/// How the filesystem holding a maildir is answering — on an NFS or
+/// sshfs mount, perhaps slowly or not at all.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Responsiveness {
+    Fine,
+    /// Answering, but a stat took this long.
+    Slow(Duration),
+    /// Not answering, or failing, e.g. with a stale file handle.
+    Offline(String),
+}
+
+impl Responsiveness {
+    /// What's wrong, e.g. "slow (1.2s to answer)"; `None` when fine.
+    pub fn problem(&self) -> Option<String> {
+        match self {
+            Responsiveness::Fine => None,
+            Responsiveness::Slow(took) => Some(format!("slow ({:.1}s to answer)", took.as_secs_f64())),
+            Responsiveness::Offline(reason) => Some(format!("offline ({})", reason)),
+        }
+    }
+}
+
+/// Time a stat and listing of `root`. The work runs on its own thread,
+/// so a hung network mount can't hang the caller.
+pub fn probe(root: &Path) -> Responsiveness {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let root = root.to_path_buf();
+    std::thread::spawn(move || {
+        let start = Instant::now();
+        let result = std::fs::metadata(&root).and_then(|_| std::fs::read_dir(&root)).map(|_| ());
+        let _ = tx.send((result, start.elapsed()));
+    });
+    match rx.recv_timeout(STAT_TIMEOUT) {
+        Ok((result, took)) => responsiveness(result, took),
+        Err(_) => Responsiveness::Offline(format!("no answer in {}s", STAT_TIMEOUT.as_secs())),
+    }
+}
+
+fn responsiveness(result: std::io::Result<()>, took: Duration) -> Responsiveness {
+    match result {
+        Err(e) if e.raw_os_error() == Some(libc::ESTALE) => Responsiveness::Offline("stale file handle".into()),
+        Err(e) => Responsiveness::Offline(e.to_string()),
+        Ok(()) if took > SLOW_STAT => Responsiveness::Slow(took),
+        Ok(()) => Responsiveness::Fine,
+    }
+}
+
+/// Whether a message file has headers but no body, as a headers-only sync
+/// leaves it.
+pub fn body_absent(raw: &[u8]) -> bool {
+    let mut lines = raw.split(|&b| b == b'\n');
+    if !lines.any(|line| line.is_empty() || line == b"\r") {
+        return true;
+    }
+    lines.all(|line| line.iter().all(u8::is_ascii_whitespace))
+}
+
+/// Concatenate raw messages into an mbox (mboxrd): each gets a `From `
+/// separator line, `From ` lines in the body are quoted with `>`, and
+/// line endings become `\n`.
+pub fn mbox(messages: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for message in messages {
+        out.extend_from_slice(b"From MAILER-DAEMON Thu Jan  1 00:00:00 1970\n");
+        for line in message.split(|&b| b == b'\n') {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.iter().skip_while(|&&b| b == b'>').take(5).eq(b"From ".iter()) {
+                out.push(b'>');
+            }
+            out.extend_from_slice(line);
+            out.push(b'\n');
+        }
+        // A blank line before the next separator
+        if !out.ends_with(b"\n\n") {
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+/// Split an mbox into its messages, handing each to `each` in turn. A
+/// message starts at a `From ` separator (one with a time in it, as in
+/// `From sender Mon Jan  1 09:00:00 2024`) at the top of the file or
+/// after a blank line; the `>From ` quoting of mboxrd is undone and the blank
+/// line before the next separator dropped. Returns how many there were.
+pub fn split_mbox(
+    mut reader: impl std::io::BufRead,
+    mut each: impl FnMut(Vec<u8>) -> Result<()>,
+) -> Result<usize> {
+    let finish = |mut message: Vec<u8>| {
+        if message.ends_with(b"\r\n\r\n") {
+            message.truncate(message.len() - 2);
+        } else if message.ends_with(b"\n\n") {
+            message.pop();
+        }
+        message
+    };
+    let mut count = 0;
+    let mut message: Option<Vec<u8>> = None;
+    let mut after_blank = true;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).context("failed to read mbox")? == 0 {
+            break;
+        }
+        if (after_blank || message.is_none()) && is_mbox_separator(&line) {
+            if let Some(done) = message.replace(Vec::new()) {
+                each(finish(done))?;
+                count += 1;
+            }
+            after_blank = false;
+            continue;
+        }
+        after_blank = line.iter().all(|&b| b == b'\r' || b == b'\n');
+        // Anything before the first separator isn't a message
+        let Some(message) = message.as_mut() else {
+            continue;
+        };
+        let quotes = line.iter().take_while(|&&b| b == b'>').count();
+        if quotes > 0 && line[quotes..].starts_with(b"From ") {
+            message.extend_from_slice(&line[1..]);
+        } else {
+            message.extend_from_slice(&line);
+        }
+    }
+    if let Some(done) = message {
+        each(finish(done))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Whether `line` is an mbox `From ` line: unquoted body lines starting
+/// "From " rarely have a time of day in them too.
+fn is_mbox_separator(line: &[u8]) -> bool {
+    let Some(rest) = line.strip_prefix(b"From ") else {
+        return false;
+    };
+    String::from_utf8_lossy(rest).split_whitespace().any(|word| {
+        let parts: Vec<&str> = word.split(':').collect();
+        (2..=3).contains(&parts.len())
+            && parts.iter().all(|p| (1..=2).contains(&p.len()) && p.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+/// Maildir flags for a message from an mbox, from the `Status` and
+/// `X-Status` headers mail clients keep there (`R` read, `A` answered,
+/// `F` flagged), or the labels of a Gmail Takeout export.
+pub fn mbox_flags(message: &[u8]) -> String {
+    let (mut flagged, mut replied, mut seen) = (false, false, false);
+    for line in message.split(|&b| b == b'\n') {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "status" => seen |= value.contains('R'),
+            "x-status" => {
+                replied |= value.contains('A');
+                flagged |= value.contains('F');
+            }
+            "x-gmail-labels" => {
+                let labels: Vec<&str> = value.split(',').map(str::trim).collect();
+                seen |= !labels.contains(&"Unread");
+                flagged |= labels.contains(&"Starred");
+            }
+            _ => {}
+        }
+    }
+    // Maildir wants the flags in ASCII order
+    [(flagged, 'F'), (replied, 'R'), (seen, 'S')]
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, flag)| flag)
+        .collect()
+}
+
 /// Simple counter for unique maildir filenames within a process.
 pub fn rand_seq() -> u64 {
     use std::sync::atomic::{AtomicU64, Ordering};
@@ -57,3 +372,143 @@ pub fn gethostname() -> String {
         "localhost".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probes_slow_and_stale_mounts() {
+        assert_eq!(probe(&std::env::temp_dir()), Responsiveness::Fine);
+        assert!(matches!(probe(Path::new("/nonexistent/hutt-maildir")), Responsiveness::Offline(_)));
+
+        let stale = std::io::Error::from_raw_os_error(libc::ESTALE);
+        assert_eq!(
+            responsiveness(Err(stale), Duration::ZERO).problem().as_deref(),
+            Some("offline (stale file handle)")
+        );
+        let slow = responsiveness(Ok(()), Duration::from_millis(1300));
+        assert_eq!(slow.problem().as_deref(), Some("slow (1.3s to answer)"));
+        assert_eq!(responsiveness(Ok(()), Duration::from_millis(20)).problem(), None);
+    }
+
+    #[test]
+    fn mbox_quotes_from_lines() {
+        let messages = vec![
+            b"Subject: one\r\n\r\nFrom here on\r\n>From there\r\n".to_vec(),
+            b"Subject: two\n\nFromage".to_vec(),
+        ];
+        let mbox = String::from_utf8(mbox(&messages)).unwrap();
+        assert_eq!(
+            mbox,
+            "From MAILER-DAEMON Thu Jan  1 00:00:00 1970\n\
+             Subject: one\n\n>From here on\n>>From there\n\n\
+             From MAILER-DAEMON Thu Jan  1 00:00:00 1970\n\
+             Subject: two\n\nFromage\n\n"
+        );
+    }
+
+    #[test]
+    fn split_mbox_undoes_mbox() {
+        let messages = vec![
+            b"Subject: one\nStatus: RO\nX-Status: F\n\nFrom here on\n>From there\n".to_vec(),
+            b"Subject: two\nX-Gmail-Labels: Inbox,Unread\n\nFromage\n".to_vec(),
+        ];
+        let data = [b"junk before the first separator\n".to_vec(), mbox(&messages)].concat();
+        let mut split = Vec::new();
+        let count = split_mbox(&data[..], |message| {
+            split.push(message);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(split, messages);
+        assert_eq!(mbox_flags(&split[0]), "FS");
+        assert_eq!(mbox_flags(&split[1]), "");
+        // Only the header block counts
+        assert_eq!(mbox_flags(b"Subject: x\n\nStatus: R\n"), "");
+
+        // Unquoted (mboxo) "From " lines only split after a blank line
+        let mboxo = b"From a@b Mon Jan  1 00:00:00 2024\nSubject: 1\n\nFrom me\n\nFrom c@d Tue Jan  2 00:00:00 2024\nSubject: 2\n\nbody\n";
+        let mut split = Vec::new();
+        split_mbox(&mboxo[..], |message| {
+            split.push(String::from_utf8(message).unwrap());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(split, ["Subject: 1\n\nFrom me\n", "Subject: 2\n\nbody\n"]);
+    }
+
+    #[test]
+    fn body_absent_for_header_only_files() {
+        assert!(body_absent(b"Subject: one\r\nFrom: a@b\r\n\r\n"));
+        assert!(body_absent(b"Subject: one\nFrom: a@b"));
+        assert!(body_absent(b"Subject: one\n\n \n\n"));
+        assert!(!body_absent(b"Subject: one\r\n\r\nHello\r\n"));
+    }
+
+    #[test]
+    fn changed_dirs_since() {
+        let root = std::env::temp_dir().join(format!("hutt-maildir-{}", std::process::id()));
+        for dir in ["Inbox/cur", "Inbox/new", "Inbox/tmp", "Lists/rust/cur", "Archive/cur"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let before = SystemTime::now() - std::time::Duration::from_secs(1);
+        let all = changed_dirs(&root, before);
+        assert_eq!(all.len(), 4);
+        assert!(all.contains(&root.join("Lists/rust/cur")));
+        assert!(!all.iter().any(|p| p.ends_with("tmp")));
+
+        let later = SystemTime::now() + std::time::Duration::from_secs(3600);
+        assert!(changed_dirs(&root, later).is_empty());
+
+        // An mtime kept to the second can read as earlier than the scan
+        let scan = SystemTime::now() + std::time::Duration::from_secs(60);
+        let set_mtime = |dir: &str, t: SystemTime| {
+            std::fs::File::open(root.join(dir)).unwrap().set_modified(t).unwrap();
+        };
+        set_mtime("Inbox/new", scan - std::time::Duration::from_millis(900));
+        set_mtime("Archive/cur", scan - std::time::Duration::from_secs(10));
+        assert_eq!(changed_dirs(&root, scan), [root.join("Inbox/new")]);
+        assert!(changed_dirs(&root.join("missing"), before).is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn sent_messages_are_delivered_via_tmp() {
+        let root = std::env::temp_dir().join(format!("hutt-deliver-{}", std::process::id()));
+        let message = b"Subject: hi\r\n\r\nHello\r\n";
+        let path = save_to_sent(root.to_str().unwrap(), "/Sent", message).unwrap();
+        assert_eq!(path.parent().unwrap(), root.join("Sent/cur"));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.ends_with(&format!(",S={}:2,S", message.len())), "{}", name);
+        assert_eq!(std::fs::read(&path).unwrap(), message);
+        assert!(root.join("Sent/new").is_dir());
+        assert_eq!(std::fs::read_dir(root.join("Sent/tmp")).unwrap().count(), 0);
+
+        let unflagged = deliver(&root.join("Sent"), b"x", "").unwrap();
+        assert_eq!(unflagged.parent().unwrap(), root.join("Sent/new"));
+        assert_ne!(unflagged.file_name(), path.file_name());
+        assert!(unflagged.to_str().unwrap().ends_with(",S=1"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn folder_exists_plain_and_dotted() {
+        let root = std::env::temp_dir().join(format!("hutt-folders-{}", std::process::id()));
+        create_folder(&root, "/INBOX").unwrap();
+        create_folder(&root, "/[Gmail]/All Mail").unwrap();
+        std::fs::create_dir_all(root.join(".Sent/cur")).unwrap();
+
+        assert!(root.join("INBOX/tmp").is_dir());
+        assert!(folder_exists(&root, "/INBOX"));
+        assert!(folder_exists(&root, "/[Gmail]/All Mail"));
+        assert!(folder_exists(&root, "/Sent"));
+        assert!(!folder_exists(&root, "/Archive"));
+        assert!(!folder_exists(&root, "/"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
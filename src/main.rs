@@ -1,17 +1,48 @@
+mod bidi;
 mod compose;
 mod config;
+mod contacts;
+mod counts;
+mod desktop;
+mod digest;
 mod envelope;
+mod export;
+mod folder_scan;
+mod followups;
+mod health;
+mod held_triage;
+mod http_api;
+mod inbox_sections;
 mod keymap;
+mod keywords;
 mod links;
 mod maildir;
 mod mime_render;
 mod mu_client;
 mod mu_sexp;
+mod notes;
+mod oplog;
+mod patch;
+mod paths;
+mod power;
+mod redact;
+mod registry;
+mod scores;
+mod screener;
 mod send;
+mod session;
 mod smart_folders;
+mod snippets;
 mod splits;
+mod state_bundle;
+mod state_crypt;
+mod status_file;
+mod templates;
+mod triage_stats;
 mod tui;
+mod tutorial;
 mod undo;
+mod warm_start;
 
 use anyhow::{bail, Context, Result};
 
@@ -36,12 +67,38 @@ USAGE:
     hutt r <COMMAND> [ARGS]          (shorthand for remote)
     hutt server [OPTIONS]            Run as mu server proxy (drop-in replacement)
     hutt config path                 Print config file path
+    hutt config profile              Print the profile used on this machine
+    hutt compose --json [FILE|-]     Open compose in the running instance from
+                                     a JSON request (to, cc, bcc, subject,
+                                     body, attachments, account)
+    hutt open <URI>                  Open a mid:, message:, mailto: or hutt: URI
+                                     in the running instance
+    hutt open-file <PATH>            Open the message stored at a maildir file
+                                     path in the running instance
+    hutt digest [--since 7d]         Summarise recent mail: top senders and
+                                     lists, unread backlog, needs-reply
+    hutt stats --triage [--days 7]   Messages archived, trashed and replied to
+                                     per day, from the operations log
+    hutt install-handler [--mailto]  Register as the desktop handler for those
+                                     URIs (--mailto: for mailto: links too)
+    hutt export-state [FILE]         Bundle smart folders, splits, templates
+                                     and state into FILE (default: stdout)
+    hutt import-state FILE [--force] Restore a bundle (--force overwrites)
+    hutt import-mbox FILE [--folder /Imported] [--read]
+                                     Copy an mbox's messages into a maildir
+                                     folder of the account and index them
 
 OPTIONS:
     -h, --help                  Show this help message
     -V, --version               Print version
     -a, --account <NAME>        Start with a specific account
     --log <PATH>                Write debug log to file (or set HUTT_LOG)
+    --socket <NAME>             Name this instance's IPC socket (default: pid)
+    --new-instance              Start even if single_instance would forward to
+                                a running hutt
+    --tutorial                  Learn the basics on a made-up mailbox, one
+                                step at a time (your mail isn't touched)
+    --ipc-pipe <PATH|->         Also read JSON IPC commands from a FIFO or stdin
     --conversations             Start in conversations (grouped threads) mode
     --no-conversations          Start in single-message mode
     --background-servers        Spawn background mu servers for prefetch (default)
@@ -54,9 +111,12 @@ OPTIONS:
 
 REMOTE COMMANDS:
     open <MESSAGE-ID>           Open a message by Message-ID
+    open-file <PATH>            Open the message stored at a maildir file path
+    open-docid <DOCID>          Open a message by its mu docid
     thread <MESSAGE-ID>         Open a thread by Message-ID
     search <QUERY>              Run a search query
-    compose [--to=ADDR] [--subject=TEXT]  Open compose window
+    compose [--to=ADDR] [--subject=TEXT]  Open compose window; also
+                                --cc, --bcc, --body and --attach=FILE
     navigate <FOLDER>           Switch to a folder
     open-url <URI>              Open any URI (mid:, message:, mailto:, hutt:)
     quit                        Quit the running instance
@@ -67,18 +127,22 @@ URI SCHEMES:
     mid:<message-id>                         Open message (RFC 2392)
     mid:<message-id>?view=thread             Open thread
     message:<message-id>                     Open message (Apple Mail)
-    mailto:addr?subject=text                 Compose (RFC 6068)
+    mailto:addr?subject=text                 Compose (RFC 6068); also cc,
+                                             bcc and body
+    hutt:compose?to=<addr>&...               Compose, same fields as mailto:
     hutt:search?q=<query>[&account=<name>]   Search
     hutt:navigate?folder=<path>[&account=<name>]  Navigate
 
 EXAMPLES:
-    hutt                        Open default account inbox
+    hutt                        Open default account (last session, or startup_folder)
     hutt /Sent                  Open the Sent folder
     hutt -a work /Drafts        Open Drafts on the 'work' account
     hutt r search from:alice    Search in the running instance
     hutt r search --account=work from:alice
     hutt r compose --to=bob@example.com --subject=\"Hello\"
     hutt r open-url 'mid:abc@example.com?view=thread'
+    hutt open-file ~/Maildir/INBOX/cur/1700000000.123.host:2,S
+    hutt r open-docid 4242
     hutt r --json search from:alice     Search and output ndjson
     hutt r --sexp thread abc@host.com   Thread envelopes as sexp
     hutt r --json search q | jq '.path' Extract file paths with jq
@@ -109,14 +173,23 @@ OUTPUT FLAGS:
     --json                  Print results as JSON (ndjson, one per line)
     --wrapped               Wrap output in a single object/list
 
+TARGET:
+    --instance NAME         Send to the instance started with --socket NAME
+                            (or with that pid). Default: the most recently
+                            started instance.
+
 COMMANDS:
     open <MESSAGE-ID>           Open a message by Message-ID
+    open-file <PATH>            Open the message stored at a maildir file path
+    open-docid <DOCID>          Open a message by its mu docid
     thread <MESSAGE-ID>         Open a thread by Message-ID
     search <QUERY>              Run a search query
-    compose [--to=ADDR] [--subject=TEXT]  Open compose window
+    compose [--to=ADDR] [--subject=TEXT]  Open compose window; also
+                                --cc, --bcc, --body and --attach=FILE
     navigate <FOLDER>           Switch to a folder
     open-url <URI>              Open any URI (mid:, message:, mailto:, hutt:)
     quit                        Quit the running instance
+    instances                   List running instances
 
     All commands accept --account=NAME / -a NAME to target a specific account."
     );
@@ -171,6 +244,30 @@ fn extract_output_flags(args: &[String]) -> Result<(OutputFormat, bool, Vec<Stri
     Ok((format, wrapped, rest))
 }
 
+/// Parse --instance=NAME or --instance NAME from args, returning the value
+/// and remaining args.
+fn extract_instance(args: &[String]) -> Result<(Option<String>, Vec<String>)> {
+    let mut instance = None;
+    let mut rest = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if let Some(v) = args[i].strip_prefix("--instance=") {
+            instance = Some(v.to_string());
+        } else if args[i] == "--instance" {
+            i += 1;
+            instance = Some(
+                args.get(i)
+                    .ok_or_else(|| anyhow::anyhow!("--instance requires a name"))?
+                    .clone(),
+            );
+        } else {
+            rest.push(args[i].clone());
+        }
+        i += 1;
+    }
+    Ok((instance, rest))
+}
+
 fn run_config(args: &[String]) -> Result<()> {
     let sub = args.first().map(|s| s.as_str()).unwrap_or("path");
     match sub {
@@ -183,12 +280,23 @@ fn run_config(args: &[String]) -> Result<()> {
                 }
             }
         }
+        "profile" => {
+            let config = config::Config::load()?;
+            match config.active_profile {
+                Some(name) => println!("{}", name),
+                None => {
+                    eprintln!("no profile applies on {}", maildir::gethostname());
+                    std::process::exit(1);
+                }
+            }
+        }
         "-h" | "--help" | "help" => {
             eprintln!(
                 "hutt config — config file utilities
 
 USAGE:
-    hutt config path            Print config file path"
+    hutt config path            Print config file path
+    hutt config profile         Print the profile used on this machine"
             );
         }
         other => bail!("unknown config command: '{}'\nRun 'hutt config --help' for usage", other),
@@ -196,6 +304,346 @@ USAGE:
     Ok(())
 }
 
+/// `hutt compose --json [FILE|-] [--instance NAME]`: hand a JSON compose
+/// request to the running instance.
+async fn run_compose(args: &[String]) -> Result<()> {
+    let (instance, args) = extract_instance(args)?;
+    if !args.iter().any(|a| a == "--json") {
+        bail!("usage: hutt compose --json [FILE|-] [--instance NAME]");
+    }
+    let source = args.iter().find(|a| a.as_str() != "--json").map(|s| s.as_str());
+    let contents = match source {
+        None | Some("-") => std::io::read_to_string(std::io::stdin())?,
+        Some(path) => {
+            std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path))?
+        }
+    };
+    let mut req: compose::ComposeRequest =
+        serde_json::from_str(&contents).context("invalid compose request")?;
+    // Resolve attachments relative to our cwd, not the running instance's
+    for path in &mut req.attachments {
+        let expanded = config::expand_tilde(path);
+        *path = std::fs::canonicalize(&expanded)
+            .with_context(|| format!("attachment {}", expanded))?
+            .to_string_lossy()
+            .into_owned();
+    }
+    match links::send_ipc_command(instance.as_deref(), &links::IpcCommand::Compose(req)).await? {
+        links::IpcResponse::Error { message } => bail!("hutt: {}", message),
+        _ => Ok(()),
+    }
+}
+
+/// `hutt digest [--since PERIOD] [--account NAME] [--instance NAME]`: print
+/// a triage summary of recent mail. Uses the running instance's mu server
+/// when there is one, since mu allows only one server per database.
+async fn run_digest(args: &[String], config: &config::Config) -> Result<()> {
+    let (instance, args) = extract_instance(args)?;
+    let (account_name, args) = extract_account(&args);
+    let mut since = digest::DEFAULT_SINCE.to_string();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--since" => {
+                i += 1;
+                since = args
+                    .get(i)
+                    .ok_or_else(|| anyhow::anyhow!("--since requires a period"))?
+                    .clone();
+            }
+            arg if arg.starts_with("--since=") => {
+                since = arg.strip_prefix("--since=").unwrap().to_string();
+            }
+            "-h" | "--help" => {
+                eprintln!(
+                    "USAGE: hutt digest [--since PERIOD] [--account NAME] [--instance NAME]
+
+Summarise mail since PERIOD ago (e.g. 7d, 2w, 36h; default {}): volume
+per sender and mailing list, unread backlog, and direct messages still
+waiting for a reply.",
+                    digest::DEFAULT_SINCE
+                );
+                return Ok(());
+            }
+            other => bail!("hutt digest: unknown argument '{}'", other),
+        }
+        i += 1;
+    }
+    let since = digest::start_date(digest::parse_since(&since)?);
+
+    let idx = match account_name {
+        Some(ref name) => config
+            .accounts
+            .iter()
+            .position(|a| a.name == *name)
+            .ok_or_else(|| anyhow::anyhow!("unknown account '{}'", name))?,
+        None => config.default_account_index(),
+    };
+    let account = config
+        .accounts
+        .get(idx)
+        .ok_or_else(|| anyhow::anyhow!("no accounts configured"))?;
+    let own = vec![account.email.clone()];
+
+    let digest = if instance.is_some() || !links::live_instances().is_empty() {
+        digest::collect_remote(instance.as_deref(), Some(&account.name), &own, since).await?
+    } else {
+        let mut mu = mu_client::MuClient::start(config.effective_muhome(idx).as_deref()).await?;
+        let digest = digest::collect(&mut mu, &own, since).await;
+        let _ = mu.quit().await;
+        digest?
+    };
+    for line in digest.render() {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// `hutt stats --triage [--days N] [--account NAME]`: print how many
+/// messages were archived, trashed, moved and replied to each day, from
+/// the account's operations log.
+fn run_stats(args: &[String], config: &config::Config) -> Result<()> {
+    let (account_name, args) = extract_account(args);
+    let mut triage = false;
+    let mut days = triage_stats::DEFAULT_DAYS;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--triage" => triage = true,
+            "--days" => {
+                i += 1;
+                days = args
+                    .get(i)
+                    .and_then(|d| d.parse().ok())
+                    .ok_or_else(|| anyhow::anyhow!("--days requires a number"))?;
+            }
+            arg if arg.starts_with("--days=") => {
+                days = arg
+                    .strip_prefix("--days=")
+                    .unwrap()
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("--days requires a number"))?;
+            }
+            "-h" | "--help" => {
+                eprintln!(
+                    "USAGE: hutt stats --triage [--days N] [--account NAME]
+
+Count the messages archived, trashed, marked as spam and moved, and the
+replies and other messages sent, on each of the last N days (default {}),
+from the account's operations log. Undone actions aren't counted.",
+                    triage_stats::DEFAULT_DAYS
+                );
+                return Ok(());
+            }
+            other => bail!("hutt stats: unknown argument '{}'", other),
+        }
+        i += 1;
+    }
+    if !triage {
+        bail!("hutt stats: say which stats, e.g. --triage");
+    }
+
+    let idx = match account_name {
+        Some(ref name) => config
+            .accounts
+            .iter()
+            .position(|a| a.name == *name)
+            .ok_or_else(|| anyhow::anyhow!("unknown account '{}'", name))?,
+        None => config.default_account_index(),
+    };
+    let account = config
+        .accounts
+        .get(idx)
+        .ok_or_else(|| anyhow::anyhow!("no accounts configured"))?;
+    let today = chrono::Local::now().date_naive();
+    let since = today - chrono::Duration::days(i64::from(days.max(1)) - 1);
+    let records = oplog::recent(&account.name, usize::MAX);
+    let daily = triage_stats::daily(&records, since);
+    for line in triage_stats::render(&daily, since, today) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// `hutt install-handler [--mailto]`: register hutt as the desktop URL handler.
+fn run_install_handler(args: &[String]) -> Result<()> {
+    let mailto = args.iter().any(|a| a == "--mailto");
+    if let Some(other) = args.iter().find(|a| a.as_str() != "--mailto") {
+        bail!("unknown install-handler option: {}", other);
+    }
+    let path = desktop::install(mailto)?;
+    eprintln!("Wrote {}", path.display());
+    eprintln!(
+        "hutt now handles {} links",
+        desktop::schemes(mailto)
+            .iter()
+            .map(|s| format!("{}:", s))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    Ok(())
+}
+
+/// `hutt export-state [FILE]`: write the state bundle to FILE or stdout.
+fn run_export_state(args: &[String]) -> Result<()> {
+    let bundle = state_bundle::export()?;
+    let json = serde_json::to_string_pretty(&bundle)?;
+    if state_crypt::enabled() {
+        eprintln!("Warning: state_encryption is on, but the exported bundle is plaintext");
+    }
+    match args.first().map(|s| s.as_str()) {
+        None | Some("-") => println!("{}", json),
+        Some(path) => {
+            use std::io::Write;
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            // The bundle holds decrypted state, so keep it private, even
+            // when overwriting a file that already existed.
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(0o600)
+                .open(path)
+                .with_context(|| format!("failed to write {}", path))?;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+            file.write_all((json + "\n").as_bytes())
+                .with_context(|| format!("failed to write {}", path))?;
+            eprintln!("Exported {} file(s) to {}", bundle.files.len(), path);
+        }
+    }
+    Ok(())
+}
+
+/// `hutt import-state FILE [--force]`: restore a state bundle.
+fn run_import_state(args: &[String]) -> Result<()> {
+    let force = args.iter().any(|a| a == "--force");
+    let path = args
+        .iter()
+        .find(|a| !a.starts_with("--"))
+        .ok_or_else(|| anyhow::anyhow!("import-state requires a bundle file"))?;
+    let contents = if path == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path))?
+    };
+    let bundle: state_bundle::Bundle =
+        serde_json::from_str(&contents).context("not a hutt state bundle")?;
+    let summary = state_bundle::import(&bundle, force)?;
+    for (file, reason) in &summary.skipped {
+        eprintln!("Skipped {}: {}", file, reason);
+    }
+    if !summary.kept.is_empty() {
+        eprintln!(
+            "Kept {} existing file(s) (use --force to overwrite): {}",
+            summary.kept.len(),
+            summary.kept.join(", ")
+        );
+    }
+    eprintln!("Imported {} file(s)", summary.written.len());
+    Ok(())
+}
+
+/// `hutt import-mbox FILE [--folder FOLDER] [--read] [--account NAME]`:
+/// deliver each message in an mbox into a maildir folder of the account,
+/// then have mu index them (through the running instance if there is one,
+/// since mu allows only one server per database).
+async fn run_import_mbox(args: &[String], config: &config::Config) -> Result<()> {
+    let (account_name, args) = extract_account(args);
+    let mut file: Option<String> = None;
+    let mut folder = "/Imported".to_string();
+    let mut read = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--folder" => {
+                i += 1;
+                folder = args
+                    .get(i)
+                    .ok_or_else(|| anyhow::anyhow!("--folder requires a maildir folder"))?
+                    .clone();
+            }
+            arg if arg.starts_with("--folder=") => {
+                folder = arg.strip_prefix("--folder=").unwrap().to_string();
+            }
+            "--read" => read = true,
+            "-h" | "--help" => {
+                eprintln!(
+                    "USAGE: hutt import-mbox FILE [--folder FOLDER] [--read] [--account NAME]
+
+Copy every message in the mbox FILE into the maildir FOLDER (default
+/Imported, created if missing) of the account, then index them. Flags
+kept in Status/X-Status headers (or Gmail Takeout labels) carry over;
+--read marks every imported message read."
+                );
+                return Ok(());
+            }
+            other if other.starts_with('-') => bail!("hutt import-mbox: unknown option '{}'", other),
+            other => file = Some(other.to_string()),
+        }
+        i += 1;
+    }
+    let file = file.ok_or_else(|| anyhow::anyhow!("import-mbox requires an mbox file"))?;
+    let folder = format!("/{}", folder.trim_matches('/'));
+    if folder == "/" {
+        bail!("--folder must name a maildir folder");
+    }
+
+    let idx = match account_name {
+        Some(ref name) => config
+            .accounts
+            .iter()
+            .position(|a| a.name == *name)
+            .ok_or_else(|| anyhow::anyhow!("unknown account '{}'", name))?,
+        None => config.default_account_index(),
+    };
+    let account = config
+        .accounts
+        .get(idx)
+        .ok_or_else(|| anyhow::anyhow!("no accounts configured"))?;
+
+    let input = std::fs::File::open(config::expand_tilde(&file))
+        .with_context(|| format!("failed to open {}", file))?;
+    let root = std::path::PathBuf::from(maildir::expand_maildir_root(&account.maildir));
+    maildir::create_folder(&root, &folder)?;
+    let dir = root.join(folder.trim_start_matches('/'));
+    let count = maildir::split_mbox(std::io::BufReader::new(input), |message| {
+        let mut flags = maildir::mbox_flags(&message);
+        if read && !flags.contains('S') {
+            flags.push('S');
+        }
+        maildir::deliver(&dir, &message, &flags)?;
+        Ok(())
+    })?;
+    eprintln!("Imported {} message(s) into {}", count, folder);
+    if count == 0 {
+        return Ok(());
+    }
+
+    const INDEX: &str = "(index :lazy-check t)";
+    if !links::live_instances().is_empty() {
+        let cmd = links::IpcCommand::MuCommand {
+            sexp: INDEX.to_string(),
+            account: Some(account.name.clone()),
+            muhome: None,
+        };
+        match links::send_ipc_command(None, &cmd).await? {
+            links::IpcResponse::Error { message } => {
+                eprintln!("Not indexed yet ({}); sync in hutt to pick them up", message)
+            }
+            _ => eprintln!("Indexed"),
+        }
+    } else {
+        let muhome = config.effective_muhome(idx);
+        mu_client::ensure_mu_database(muhome.as_deref(), &account.maildir).await?;
+        let mut mu = mu_client::MuClient::start(muhome.as_deref()).await?;
+        let indexed = mu.send_raw(INDEX).await;
+        let _ = mu.quit().await;
+        indexed?;
+        eprintln!("Indexed");
+    }
+    Ok(())
+}
+
 /// Format and print IPC response according to output flags.
 fn print_ipc_output(resp: &links::IpcResponse, format: OutputFormat, wrapped: bool) {
     match resp {
@@ -267,6 +715,7 @@ async fn run_remote(args: &[String]) -> Result<()> {
     }
 
     let (format, wrapped, args) = extract_output_flags(args)?;
+    let (instance, args) = extract_instance(&args)?;
 
     if args.is_empty() {
         print_remote_help();
@@ -291,6 +740,21 @@ async fn run_remote(args: &[String]) -> Result<()> {
             let id = rest.first().ok_or_else(|| anyhow::anyhow!("open requires a message-id"))?;
             links::IpcCommand::Open(links::HuttUrlSerde::Message { id: id.clone(), account })
         }
+        "open-file" | "file" => {
+            let (account, rest) = extract_account(&args[1..]);
+            let path = rest.first().ok_or_else(|| anyhow::anyhow!("open-file requires a path"))?;
+            // Resolve relative to our cwd, not the running instance's
+            let expanded = config::expand_tilde(path);
+            let path = std::fs::canonicalize(&expanded)
+                .with_context(|| format!("open-file: {}", expanded))?;
+            links::IpcCommand::OpenPath { path: path.to_string_lossy().into_owned(), account }
+        }
+        "open-docid" | "docid" => {
+            let (account, rest) = extract_account(&args[1..]);
+            let docid = rest.first().ok_or_else(|| anyhow::anyhow!("open-docid requires a docid"))?;
+            let docid = docid.parse().with_context(|| format!("open-docid: bad docid '{}'", docid))?;
+            links::IpcCommand::OpenDocid { docid, account }
+        }
         "thread" => {
             let (account, rest) = extract_account(&args[1..]);
             let id = rest.first().ok_or_else(|| anyhow::anyhow!("thread requires a message-id"))?;
@@ -305,21 +769,29 @@ async fn run_remote(args: &[String]) -> Result<()> {
             links::IpcCommand::Open(links::HuttUrlSerde::Search { query, account })
         }
         "compose" => {
-            let mut to = String::new();
-            let mut subject = String::new();
-            let mut account = None;
+            // The same fields as a mailto: URL; all but subject and body repeat
+            let mut fields = Vec::new();
             for arg in &args[1..] {
-                if let Some(v) = arg.strip_prefix("--to=") {
-                    to = v.to_string();
-                } else if let Some(v) = arg.strip_prefix("--subject=") {
-                    subject = v.to_string();
-                } else if let Some(v) = arg.strip_prefix("--account=") {
-                    account = Some(v.to_string());
-                } else {
+                let field = arg
+                    .strip_prefix("--")
+                    .and_then(|a| a.split_once('='))
+                    .filter(|(k, _)| ["to", "cc", "bcc", "subject", "body", "attach", "account"].contains(k));
+                let Some((key, value)) = field else {
                     bail!("compose: unknown argument '{}'", arg);
-                }
+                };
+                let value = if key == "attach" {
+                    // Relative to our cwd, not the running instance's
+                    let expanded = config::expand_tilde(value);
+                    std::fs::canonicalize(&expanded)
+                        .with_context(|| format!("attachment {}", expanded))?
+                        .to_string_lossy()
+                        .into_owned()
+                } else {
+                    value.to_string()
+                };
+                fields.push((key.to_string(), value));
             }
-            links::IpcCommand::Open(links::HuttUrlSerde::Compose { to, subject, account })
+            links::IpcCommand::Open(links::HuttUrlSerde::Compose(links::compose_request("", fields, true)))
         }
         "navigate" | "nav" => {
             let (account, rest) = extract_account(&args[1..]);
@@ -327,6 +799,12 @@ async fn run_remote(args: &[String]) -> Result<()> {
             links::IpcCommand::Navigate { folder: folder.clone(), account }
         }
         "quit" => links::IpcCommand::Quit,
+        "instances" => {
+            for name in links::live_instances() {
+                println!("{}", name);
+            }
+            return Ok(());
+        }
         "-h" | "--help" | "help" => {
             print_remote_help();
             return Ok(());
@@ -334,7 +812,7 @@ async fn run_remote(args: &[String]) -> Result<()> {
         other => bail!("unknown remote command: '{}'\nRun 'hutt remote --help' for usage", other),
     };
 
-    let resp = links::send_ipc_command(&cmd).await?;
+    let resp = links::send_ipc_command(instance.as_deref(), &cmd).await?;
 
     // Print structured output if requested
     print_ipc_output(&resp, format, wrapped);
@@ -422,7 +900,7 @@ async fn run_server(args: &[String]) -> Result<()> {
         i += 1;
     }
 
-    let hutt_available = links::socket_path().exists();
+    let hutt_available = links::socket_path(None).exists();
 
     if let Some(ref eval_sexp) = eval {
         if hutt_available {
@@ -448,7 +926,7 @@ async fn run_server_eval(
         account,
         muhome,
     };
-    let resp = links::send_ipc_command(&cmd).await?;
+    let resp = links::send_ipc_command(None, &cmd).await?;
     match resp {
         links::IpcResponse::MuFrames { frames } => {
             use std::io::Write;
@@ -505,7 +983,7 @@ async fn run_server_interactive(
             Ok(())
         };
 
-        match links::send_ipc_command(&cmd).await {
+        match links::send_ipc_command(None, &cmd).await {
             Ok(resp) => match resp {
                 links::IpcResponse::MuFrames { frames } => {
                     for frame in &frames {
@@ -687,31 +1165,110 @@ async fn run_send(args: &[String], config: &config::Config) -> Result<()> {
     };
 
     // Send via SMTP
-    let formatted = send::send_message(&message, &account.smtp)
+    let sent = send::send_message(&message, &account.smtp)
         .await
         .context("failed to send message")?;
+    oplog::append(&account.name, &[oplog::OpRecord::for_delivery(&sent)]);
 
     // Save to Sent folder
     if save_to_sent {
-        if let Err(e) = maildir::save_to_sent(&account.maildir, &account.folders.sent, &formatted) {
+        if let Err(e) = maildir::save_to_sent(&account.maildir, &account.folders.sent, &sent.formatted) {
             eprintln!("Warning: sent but failed to save to Sent folder: {}", e);
         }
     }
 
-    eprintln!("Message sent via {}", account_name);
+    if sent.unreachable.is_empty() {
+        eprintln!("Message sent via {}", account_name);
+    } else {
+        eprintln!(
+            "Message sent via {} using {} ({} unreachable)",
+            account_name,
+            sent.server,
+            sent.unreachable.join(", ")
+        );
+    }
     Ok(())
 }
 
+/// With `single_instance` set and another hutt already running, hand our
+/// folder and account to it instead of starting a second mu server.
+/// Returns true if there's nothing left for this invocation to do.
+async fn forward_to_running(
+    config: &config::Config,
+    folder: Option<&str>,
+    account: Option<&str>,
+) -> Result<bool> {
+    if config.single_instance == config::SingleInstance::Allow {
+        return Ok(false);
+    }
+    let Some(running) = links::live_instances().pop() else {
+        return Ok(false);
+    };
+    match config.single_instance {
+        config::SingleInstance::Allow | config::SingleInstance::Forward => {}
+        config::SingleInstance::Ask => {
+            eprint!("hutt is already running ({}). [f]orward to it, [n]ew instance, [q]uit? ", running);
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            match answer.trim() {
+                "" | "f" | "F" => {}
+                "n" | "N" => return Ok(false),
+                _ => return Ok(true),
+            }
+        }
+    }
+    let account_idx = match account {
+        Some(name) => Some(
+            config
+                .accounts
+                .iter()
+                .position(|a| a.name == name)
+                .ok_or_else(|| anyhow::anyhow!("unknown account '{}'", name))?,
+        ),
+        None => None,
+    };
+    let folder = match (folder, account_idx) {
+        (Some(folder), _) => folder.to_string(),
+        (None, Some(idx)) => config.effective_startup_folder(idx),
+        (None, None) => {
+            eprintln!("hutt is already running ({})", running);
+            return Ok(true);
+        }
+    };
+    let cmd = links::IpcCommand::Navigate {
+        folder: folder.clone(),
+        account: account.map(String::from),
+    };
+    match links::send_ipc_command(Some(&running), &cmd).await? {
+        links::IpcResponse::Error { message } => bail!("hutt: {}", message),
+        _ => eprintln!("Opened {} in the running hutt ({})", folder, running),
+    }
+    Ok(true)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
+    // The tutorial brings its own mail, account and state, so it's set up
+    // in place of the user's config, before anything reads their files
+    let tutorial_dir = args.iter().skip(1).any(|a| a == "--tutorial").then(tutorial::dir);
+
     // Load config
-    let mut config = config::Config::load()?;
+    let mut config = match &tutorial_dir {
+        Some(dir) => tutorial::prepare(dir)?,
+        None => config::Config::load()?,
+    };
+    state_crypt::init(config.state_encryption.clone());
+    mu_client::init_timeouts(config.mu_timeouts);
+    contacts::use_names(&contacts::load_contacts(), &config.display_names);
+    compose::sweep_stale_compose_files(&compose::compose_dir(config.compose_dir.as_deref()));
 
     // Parse CLI flags
     let mut initial_folder = None;
     let mut account_name: Option<String> = None;
+    let mut socket_name: Option<String> = None;
+    let mut new_instance = false;
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
@@ -723,6 +1280,45 @@ async fn main() -> Result<()> {
             "config" => {
                 return run_config(&args[i + 1..]);
             }
+            // State bundle export/import
+            "export-state" => {
+                return run_export_state(&args[i + 1..]);
+            }
+            "import-state" => {
+                return run_import_state(&args[i + 1..]);
+            }
+            // Copy an mbox into the maildir
+            "import-mbox" => {
+                return run_import_mbox(&args[i + 1..], &config).await;
+            }
+            // Open a URI in the running instance (URL handler entry point)
+            "open" => {
+                let mut remote = vec!["open-url".to_string()];
+                remote.extend_from_slice(&args[i + 1..]);
+                return run_remote(&remote).await;
+            }
+            // Open a message file in the running instance
+            "open-file" => {
+                let mut remote = vec!["open-file".to_string()];
+                remote.extend_from_slice(&args[i + 1..]);
+                return run_remote(&remote).await;
+            }
+            // Structured compose request for the running instance
+            "compose" => {
+                return run_compose(&args[i + 1..]).await;
+            }
+            // Triage summary of recent mail
+            "digest" => {
+                return run_digest(&args[i + 1..], &config).await;
+            }
+            // Triage metrics from the operations log
+            "stats" => {
+                return run_stats(&args[i + 1..], &config);
+            }
+            // Register as the desktop URL handler
+            "install-handler" => {
+                return run_install_handler(&args[i + 1..]);
+            }
             // Server subcommand (drop-in mu server replacement)
             "server" => {
                 return run_server(&args[i + 1..]).await;
@@ -757,6 +1353,28 @@ async fn main() -> Result<()> {
                     .ok_or_else(|| anyhow::anyhow!("--log requires a path"))?;
                 std::env::set_var("HUTT_LOG", path);
             }
+            // IPC socket name
+            "--socket" => {
+                i += 1;
+                socket_name = Some(
+                    args.get(i)
+                        .ok_or_else(|| anyhow::anyhow!("--socket requires a name"))?
+                        .clone(),
+                );
+            }
+            // Start even if another instance is running
+            "--new-instance" => new_instance = true,
+            // Guided first run on made-up mail, set up above
+            "--tutorial" => {}
+            // Extra IPC command input
+            "--ipc-pipe" => {
+                i += 1;
+                config.ipc_pipe = Some(
+                    args.get(i)
+                        .ok_or_else(|| anyhow::anyhow!("--ipc-pipe requires a path or -"))?
+                        .clone(),
+                );
+            }
             // Conversations mode
             "--conversations" => config.conversations = true,
             "--no-conversations" => config.conversations = false,
@@ -778,6 +1396,18 @@ async fn main() -> Result<()> {
         i += 1;
     }
 
+    if tutorial_dir.is_some() {
+        (initial_folder, account_name, new_instance) = (None, None, true);
+    }
+
+    // Hand off to a running instance if configured to
+    if socket_name.is_none()
+        && !new_instance
+        && forward_to_running(&config, initial_folder.as_deref(), account_name.as_deref()).await?
+    {
+        return Ok(());
+    }
+
     // Resolve account index
     let default_idx = if let Some(ref name) = account_name {
         config
@@ -798,24 +1428,61 @@ async fn main() -> Result<()> {
 
     let muhome = config.effective_muhome(default_idx);
 
-    // Determine initial folder: CLI arg > account's inbox > "/Inbox"
-    let initial_folder = initial_folder.unwrap_or_else(|| {
-        config
-            .accounts
-            .get(default_idx)
-            .map(|a| a.folders.inbox.clone())
-            .unwrap_or_else(|| "/Inbox".to_string())
+    // Determine initial folder: CLI arg > saved session > startup_folder >
+    // account's inbox > "/Inbox"
+    let explicit_folder = initial_folder.is_some();
+    let initial_folder =
+        initial_folder.unwrap_or_else(|| config.effective_startup_folder(default_idx));
+
+    // Start mu in the background, auto-initialising the database for new
+    // accounts, so the UI can be drawn while it comes up
+    let maildir = config.accounts.get(default_idx).map(|a| a.maildir.clone());
+    let starting = tokio::spawn(async move {
+        if let Some(maildir) = maildir {
+            mu_client::ensure_mu_database(muhome.as_deref(), &maildir).await?;
+        }
+        mu_client::MuClient::start(muhome.as_deref()).await
     });
 
-    // Ensure mu database exists (auto-init for new accounts)
-    if let Some(account) = config.accounts.get(default_idx) {
-        mu_client::ensure_mu_database(muhome.as_deref(), &account.maildir).await?;
-    }
+    // Meanwhile show the list saved at the last quit, if it's for the view
+    // we're about to open, or an empty list
+    let account = config.accounts.get(default_idx).map(|a| a.name.clone()).unwrap_or_default();
+    let opening = match session::load_session(&account) {
+        Some(saved) if !explicit_folder && config.restore_session => {
+            saved.views[saved.active_view].clone()
+        }
+        _ => session::ViewState {
+            folder: initial_folder.clone(),
+            ..session::ViewState::default()
+        },
+    };
+    let warm = warm_start::load_warm_start(&account)
+        .filter(|w| !w.envelopes.is_empty() && w.matches(&opening));
 
-    // Start mu server
-    let mu = mu_client::MuClient::start(muhome.as_deref()).await?;
-    let mut app = tui::App::new(mu, config).await?;
+    let started = async {
+        tui::draw_startup(&config, &account, &opening, warm.as_ref())?;
+        let mu = starting.await??;
+        tui::App::new(mu, config).await
+    };
+    let mut app = match started.await {
+        Ok(app) => app,
+        Err(e) => {
+            tui::leave_startup();
+            return Err(e);
+        }
+    };
     app.active_account = default_idx;
     app.current_folder = initial_folder;
-    tui::run(app).await
+    app.socket_name = socket_name;
+    if !explicit_folder && app.config.restore_session {
+        app.load_session();
+    }
+    if tutorial_dir.is_some() {
+        app.start_tutorial();
+    }
+    let result = tui::run(app).await;
+    if let Some(dir) = tutorial_dir {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    result
 }
@@ -1,9 +1,14 @@
 use anyhow::{Context, Result};
+use chrono::FixedOffset;
 use html2text::render::RichAnnotation;
 use mail_parser::MimeHeaders;
 use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+use crate::envelope::Address;
+use crate::patch::{self, DiffLine};
+
 // ---------------------------------------------------------------------------
 // Rich rendering types
 // ---------------------------------------------------------------------------
@@ -24,6 +29,10 @@ pub enum SpanKind {
     Emphasis,
     Strong,
     Code,
+    /// Lines of a diff in a patch email.
+    DiffAdded,
+    DiffRemoved,
+    DiffMeta,
 }
 
 /// A clickable link region for mouse hit-testing.
@@ -42,6 +51,10 @@ pub struct RenderedMessage {
     pub links: Vec<LinkRegion>,
     /// True when the body was rendered from HTML (no plaintext alternative).
     pub is_html: bool,
+    /// Reply-To addresses, when the message sets that header.
+    pub reply_to: Vec<Address>,
+    /// The sender's UTC offset, from the Date header.
+    pub sent_offset: Option<FixedOffset>,
 }
 
 impl RenderedMessage {
@@ -83,22 +96,42 @@ impl RenderCache {
     pub fn insert(&mut self, message_id: String, width: u16, msg: RenderedMessage) {
         self.cache.insert((message_id, width), msg);
     }
+
+    /// Drop a message's renderings at every width.
+    pub fn remove(&mut self, message_id: &str) {
+        self.cache.retain(|(id, _), _| id != message_id);
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Plain text rendering
 // ---------------------------------------------------------------------------
 
-/// Render plain text into a RenderedMessage, detecting URLs and quote lines.
-/// Lines are pre-wrapped to `width`.
+/// Render plain text into a RenderedMessage, detecting URLs, quote lines
+/// and diffs. Lines are pre-wrapped to `width`.
 pub fn render_plain_text(text: &str, width: u16) -> RenderedMessage {
     let mut lines = Vec::new();
     let mut links = Vec::new();
     let width = width as usize;
 
-    for raw_line in text.lines() {
+    let raw_lines: Vec<&str> = text.lines().collect();
+    let diff_from = patch::diff_start(&raw_lines);
+    let mut in_diff = false;
+    for (i, raw_line) in raw_lines.iter().copied().enumerate() {
+        in_diff = (in_diff || diff_from == Some(i)) && !patch::is_diff_end(raw_line);
         let is_quote = raw_line.starts_with('>');
-        let spans = if is_quote {
+        let spans = if in_diff {
+            let kind = match patch::classify(raw_line) {
+                DiffLine::Added => SpanKind::DiffAdded,
+                DiffLine::Removed => SpanKind::DiffRemoved,
+                DiffLine::Meta => SpanKind::DiffMeta,
+                DiffLine::Context => SpanKind::Normal,
+            };
+            vec![RichSpan {
+                text: raw_line.to_string(),
+                kind,
+            }]
+        } else if is_quote {
             vec![RichSpan {
                 text: raw_line.to_string(),
                 kind: SpanKind::Quote,
@@ -127,7 +160,7 @@ pub fn render_plain_text(text: &str, width: u16) -> RenderedMessage {
         }
     }
 
-    RenderedMessage { lines, links, is_html: false }
+    RenderedMessage { lines, links, is_html: false, reply_to: Vec::new(), sent_offset: None }
 }
 
 /// Scan a line for URLs and split into Normal / Link spans.
@@ -259,6 +292,8 @@ pub fn render_html(html: &[u8], width: u16) -> RenderedMessage {
                 }]],
                 links: Vec::new(),
                 is_html: true,
+                reply_to: Vec::new(),
+                sent_offset: None,
             };
         }
     };
@@ -299,7 +334,7 @@ pub fn render_html(html: &[u8], width: u16) -> RenderedMessage {
         lines.push(spans);
     }
 
-    RenderedMessage { lines, links, is_html: true }
+    RenderedMessage { lines, links, is_html: true, reply_to: Vec::new(), sent_offset: None }
 }
 
 /// Map html2text rich annotations to SpanKind.
@@ -325,6 +360,9 @@ fn same_kind(a: &SpanKind, b: &SpanKind) -> bool {
         (SpanKind::Emphasis, SpanKind::Emphasis) => true,
         (SpanKind::Strong, SpanKind::Strong) => true,
         (SpanKind::Code, SpanKind::Code) => true,
+        (SpanKind::DiffAdded, SpanKind::DiffAdded) => true,
+        (SpanKind::DiffRemoved, SpanKind::DiffRemoved) => true,
+        (SpanKind::DiffMeta, SpanKind::DiffMeta) => true,
         (SpanKind::Link(a), SpanKind::Link(b)) => a == b,
         _ => false,
     }
@@ -343,11 +381,183 @@ pub struct ExtractedAttachment {
 }
 
 /// Info about a discovered attachment (for rendering the attachment list).
-struct AttachmentInfo {
-    filename: String,
-    mime_type: String,
-    size: usize,
-    content_id: String,
+#[derive(Debug, Clone)]
+pub struct AttachmentInfo {
+    pub filename: String,
+    pub mime_type: String,
+    pub size: usize,
+    /// MIME Content-ID, or "part.N" when the part has none.
+    pub content_id: String,
+}
+
+/// List the attachments in a message file without extracting them. The
+/// file is read a line at a time and part bodies are only measured, so a
+/// message with huge attachments is never held in memory.
+pub fn list_attachments(message_path: &Path) -> Result<Vec<AttachmentInfo>> {
+    let file = std::fs::File::open(message_path)
+        .with_context(|| format!("reading message: {}", message_path.display()))?;
+    list_attachments_from(BufReader::new(file))
+        .with_context(|| format!("reading message: {}", message_path.display()))
+}
+
+/// List the attachments in a message read from `reader`.
+fn list_attachments_from(reader: impl BufRead) -> Result<Vec<AttachmentInfo>> {
+    let (skeleton, sizes) = mime_skeleton(reader)?;
+    let message = mail_parser::MessageParser::default()
+        .parse(&skeleton)
+        .context("failed to parse MIME message")?;
+    Ok(discover_attachments(&message, Some(&sizes)))
+}
+
+/// The message's MIME structure with every part body left out: headers
+/// and boundary lines as they are, so parsing it finds the same parts at
+/// the same indexes. Also returns each body's decoded size, keyed by
+/// where the body starts in the skeleton.
+fn mime_skeleton(mut reader: impl BufRead) -> Result<(Vec<u8>, HashMap<usize, usize>)> {
+    enum State {
+        Headers(Vec<u8>),
+        Body(BodySize),
+        /// A multipart preamble or epilogue.
+        Skip,
+    }
+
+    let mut skeleton = Vec::new();
+    let mut sizes = HashMap::new();
+    let mut boundaries: Vec<Vec<u8>> = Vec::new();
+    let mut state = State::Headers(Vec::new());
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let trimmed = line.trim_ascii_end();
+        let boundary = boundaries.last().and_then(|b| {
+            let rest = trimmed.strip_prefix(b"--")?.strip_prefix(b.as_slice())?;
+            match rest {
+                b"" => Some(false),
+                b"--" => Some(true),
+                _ => None,
+            }
+        });
+        if let Some(closing) = boundary {
+            if let State::Body(body) = &state {
+                sizes.insert(body.start, body.size());
+            }
+            skeleton.extend_from_slice(&line);
+            state = if closing {
+                boundaries.pop();
+                State::Skip
+            } else {
+                State::Headers(Vec::new())
+            };
+            continue;
+        }
+        match &mut state {
+            State::Headers(block) => {
+                block.extend_from_slice(&line);
+                if !trimmed.is_empty() {
+                    continue;
+                }
+                skeleton.extend_from_slice(block);
+                let headers = mail_parser::MessageParser::default().parse_headers(block.as_slice());
+                let part = headers.as_ref().and_then(|h| h.parts.first());
+                let content_type = part.and_then(|p| p.content_type());
+                let multipart = content_type
+                    .filter(|ct| ct.ctype().eq_ignore_ascii_case("multipart"))
+                    .and_then(|ct| ct.attribute("boundary"));
+                if let Some(boundary) = multipart {
+                    boundaries.push(boundary.as_bytes().to_vec());
+                    state = State::Skip;
+                    continue;
+                }
+                let encoding = part.and_then(|p| p.content_transfer_encoding()).unwrap_or("");
+                let body = BodySize::new(skeleton.len(), encoding);
+                let nested = content_type.is_some_and(|ct| {
+                    ct.ctype().eq_ignore_ascii_case("message")
+                        && ct.subtype().is_some_and(|s| s.eq_ignore_ascii_case("rfc822"))
+                });
+                if nested && body.encoding == Encoding::None {
+                    // The parser reads a nested message's headers straight
+                    // after the part's; give it some
+                    skeleton.extend_from_slice(b"X-Hutt-Omitted: yes\r\n\r\n");
+                }
+                state = State::Body(body);
+            }
+            State::Body(body) => body.add(&line),
+            State::Skip => {}
+        }
+    }
+    match state {
+        State::Body(body) => {
+            sizes.insert(body.start, body.size());
+        }
+        State::Headers(block) => skeleton.extend_from_slice(&block),
+        State::Skip => {}
+    }
+    Ok((skeleton, sizes))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    None,
+    Base64,
+    QuotedPrintable,
+}
+
+/// The decoded size of a part body, added up a line at a time.
+struct BodySize {
+    /// Where the body starts in the skeleton.
+    start: usize,
+    encoding: Encoding,
+    bytes: usize,
+    /// Line ending of the last line, which belongs to the boundary.
+    last_eol: usize,
+}
+
+impl BodySize {
+    fn new(start: usize, encoding: &str) -> Self {
+        let encoding = if encoding.eq_ignore_ascii_case("base64") {
+            Encoding::Base64
+        } else if encoding.eq_ignore_ascii_case("quoted-printable") {
+            Encoding::QuotedPrintable
+        } else {
+            Encoding::None
+        };
+        BodySize { start, encoding, bytes: 0, last_eol: 0 }
+    }
+
+    fn add(&mut self, line: &[u8]) {
+        match self.encoding {
+            // Six bits a character; padding and whitespace carry none
+            Encoding::Base64 => {
+                self.bytes += line
+                    .iter()
+                    .filter(|&&c| c.is_ascii_alphanumeric() || c == b'+' || c == b'/')
+                    .count()
+            }
+            Encoding::QuotedPrintable => {
+                let text = line.trim_ascii_end();
+                let escapes = text.windows(3).filter(|w| w[0] == b'=' && w[1].is_ascii_hexdigit()).count();
+                match text.strip_suffix(b"=") {
+                    Some(soft) => self.bytes += soft.len() - 2 * escapes,
+                    None => self.bytes += text.len() - 2 * escapes + 2,
+                }
+            }
+            Encoding::None => {
+                self.bytes += line.len();
+                self.last_eol = line.len() - line.trim_ascii_end().len();
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self.encoding {
+            Encoding::Base64 => self.bytes * 3 / 4,
+            Encoding::QuotedPrintable => self.bytes.saturating_sub(2),
+            Encoding::None => self.bytes - self.last_eol,
+        }
+    }
 }
 
 /// Extract an attachment from a message file by content-id.
@@ -358,6 +568,16 @@ pub fn extract_attachment(message_path: &Path, content_id: &str) -> Result<Extra
     extract_attachment_from_bytes(&raw, content_id)
 }
 
+/// The last path component of a sender-supplied attachment name, without
+/// control characters or leading dots, so it can be joined onto a
+/// directory without escaping it. None if nothing usable is left.
+fn safe_filename(name: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let cleaned: String = base.chars().filter(|c| !c.is_control()).collect();
+    let cleaned = cleaned.trim().trim_start_matches('.');
+    (!cleaned.is_empty()).then(|| cleaned.to_string())
+}
+
 /// Extract an attachment from raw message bytes by content-id.
 pub fn extract_attachment_from_bytes(raw: &[u8], content_id: &str) -> Result<ExtractedAttachment> {
     let message = mail_parser::MessageParser::default()
@@ -389,7 +609,7 @@ pub fn extract_attachment_from_bytes(raw: &[u8], content_id: &str) -> Result<Ext
 fn extract_part(part: &mail_parser::MessagePart, idx: usize) -> Result<ExtractedAttachment> {
     let filename = part
         .attachment_name()
-        .map(|s| s.to_string())
+        .and_then(safe_filename)
         .unwrap_or_else(|| format!("attachment-{}", idx));
 
     let mime_type = part
@@ -428,7 +648,12 @@ fn extract_part(part: &mail_parser::MessagePart, idx: usize) -> Result<Extracted
     })
 }
 
-fn discover_attachments(message: &mail_parser::Message) -> Vec<AttachmentInfo> {
+/// The attachments among a message's parts. `sizes` gives body sizes by
+/// where they start, for a message parsed from [`mime_skeleton`].
+fn discover_attachments(
+    message: &mail_parser::Message,
+    sizes: Option<&HashMap<usize, usize>>,
+) -> Vec<AttachmentInfo> {
     let mut attachments = Vec::new();
 
     for (idx, part) in message.parts.iter().enumerate() {
@@ -469,12 +694,13 @@ fn discover_attachments(message: &mail_parser::Message) -> Vec<AttachmentInfo> {
             })
             .unwrap_or_else(|| "application/octet-stream".to_string());
 
-        let size = match &part.body {
-            mail_parser::PartType::Binary(cow) | mail_parser::PartType::InlineBinary(cow) => {
+        let size = match (&part.body, sizes) {
+            (_, Some(sizes)) => sizes.get(&part.offset_body).copied().unwrap_or(0),
+            (mail_parser::PartType::Binary(cow) | mail_parser::PartType::InlineBinary(cow), None) => {
                 cow.len()
             }
-            mail_parser::PartType::Text(cow) => cow.len(),
-            mail_parser::PartType::Html(cow) => cow.len(),
+            (mail_parser::PartType::Text(cow), None) => cow.len(),
+            (mail_parser::PartType::Html(cow), None) => cow.len(),
             _ => 0,
         };
 
@@ -494,7 +720,7 @@ fn discover_attachments(message: &mail_parser::Message) -> Vec<AttachmentInfo> {
     attachments
 }
 
-fn format_size(bytes: usize) -> String {
+pub fn format_size(bytes: usize) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -543,6 +769,98 @@ fn append_attachment_list(
     }
 }
 
+/// Show text/x-patch and text/x-diff parts inline after the body, with
+/// their diffs highlighted. Also listed as attachments, for saving.
+fn append_patch_parts(rendered: &mut RenderedMessage, message: &mail_parser::Message, width: u16) {
+    let body_part = message.text_body.first().copied();
+    for (idx, part) in message.parts.iter().enumerate() {
+        let mail_parser::PartType::Text(text) = &part.body else {
+            continue;
+        };
+        if Some(idx) == body_part
+            || !(part.is_content_type("text", "x-patch") || part.is_content_type("text", "x-diff"))
+        {
+            continue;
+        }
+        let name = part
+            .attachment_name()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("part.{}", idx));
+        rendered.lines.push(Vec::new());
+        let sep_width = (width as usize).min(50);
+        let title = format!("── {} ", name);
+        let fill = sep_width.saturating_sub(title.chars().count());
+        rendered.lines.push(vec![RichSpan {
+            text: format!("{}{}", title, "─".repeat(fill)),
+            kind: SpanKind::Normal,
+        }]);
+        let offset = rendered.lines.len();
+        let part_rendered = render_plain_text(text, width);
+        rendered.links.extend(part_rendered.links.into_iter().map(|mut link| {
+            link.line += offset;
+            link
+        }));
+        rendered.lines.extend(part_rendered.lines);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Snippets
+// ---------------------------------------------------------------------------
+
+/// Extract a one-line body snippet for list views: the opening text of the
+/// message with quoted lines and blank lines skipped and whitespace
+/// collapsed, cut to at most `max_chars` characters.
+pub fn extract_snippet(raw: &[u8], max_chars: usize) -> String {
+    let Some(message) = mail_parser::MessageParser::default().parse(raw) else {
+        return String::new();
+    };
+    let Some(text) = message.body_text(0) else {
+        return String::new();
+    };
+    let mut snippet = String::new();
+    let mut count = 0;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('>') {
+            continue;
+        }
+        for word in line.split_whitespace() {
+            if count > 0 {
+                if count >= max_chars {
+                    return snippet;
+                }
+                snippet.push(' ');
+                count += 1;
+            }
+            for c in word.chars() {
+                if count >= max_chars {
+                    return snippet;
+                }
+                snippet.push(c);
+                count += 1;
+            }
+        }
+    }
+    snippet
+}
+
+/// Snippet of a message synced without its body.
+pub const BODY_ABSENT_SNIPPET: &str = "[body not downloaded]";
+
+/// Read a message file and extract its snippet. Unreadable files give "".
+pub fn snippet_for_path(path: &Path, max_chars: usize) -> String {
+    std::fs::read(path)
+        .map(|raw| {
+            if crate::maildir::body_absent(&raw) {
+                BODY_ABSENT_SNIPPET.to_string()
+            } else {
+                extract_snippet(&raw, max_chars)
+            }
+        })
+        .unwrap_or_default()
+}
+
 // ---------------------------------------------------------------------------
 // Top-level render entry points
 // ---------------------------------------------------------------------------
@@ -588,22 +906,86 @@ pub fn render_message_from_bytes(
             }]],
             links: Vec::new(),
             is_html: false,
+            reply_to: Vec::new(),
+            sent_offset: None,
         })
     };
 
-    let attachments = discover_attachments(&message);
+    append_patch_parts(&mut rendered, &message, width);
+
+    let attachments = discover_attachments(&message, None);
     if !attachments.is_empty() {
         append_attachment_list(&mut rendered, &attachments, message_id, width);
     }
 
+    if let Some(reply_to) = message.reply_to() {
+        rendered.reply_to = reply_to
+            .iter()
+            .filter_map(|a| {
+                Some(Address {
+                    name: a.name().map(|n| n.to_string()),
+                    email: a.address()?.to_string(),
+                })
+            })
+            .collect();
+    }
+    rendered.sent_offset = message.date().and_then(|date| {
+        let seconds = date.tz_hour as i32 * 3600 + date.tz_minute as i32 * 60;
+        FixedOffset::east_opt(if date.tz_before_gmt { -seconds } else { seconds })
+    });
+
     Ok(rendered)
 }
 
 /// Render a message file to a RenderedMessage for the preview/thread panes.
-pub fn render_message(path: &Path, message_id: &str, width: u16) -> Result<RenderedMessage> {
+///
+/// Text wraps at `width`, or at `max_width` if that is narrower (see
+/// [`text_measure`]); the rest of the pane is left as padding.
+pub fn render_message(
+    path: &Path,
+    message_id: &str,
+    width: u16,
+    max_width: Option<u16>,
+) -> Result<RenderedMessage> {
     let raw = std::fs::read(path)
         .with_context(|| format!("reading message file: {}", path.display()))?;
-    render_message_from_bytes(&raw, message_id, width)
+    render_message_from_bytes(&raw, message_id, text_measure(width, max_width))
+}
+
+/// The size of the message file at `path` when it is over `limit_mb`
+/// megabytes (0 for no limit), too large to render without asking.
+pub fn oversized(path: &Path, limit_mb: u64) -> Option<u64> {
+    if limit_mb == 0 {
+        return None;
+    }
+    let size = std::fs::metadata(path).ok()?.len();
+    (size > limit_mb * 1024 * 1024).then_some(size)
+}
+
+/// What the preview and thread view show for an [`oversized`] message.
+pub fn too_large(size: u64) -> RenderedMessage {
+    RenderedMessage {
+        lines: vec![vec![RichSpan {
+            text: format!(
+                "[Message is {} \u{2014} press v to render anyway, Ctrl+o to open externally]",
+                format_size(size as usize)
+            ),
+            kind: SpanKind::Normal,
+        }]],
+        links: Vec::new(),
+        is_html: false,
+        reply_to: Vec::new(),
+        sent_offset: None,
+    }
+}
+
+/// Width to wrap body text at: the available `width`, capped at
+/// `max_width` when set (0 means no cap).
+pub fn text_measure(width: u16, max_width: Option<u16>) -> u16 {
+    match max_width {
+        Some(max) if max > 0 => width.min(max),
+        _ => width,
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -614,6 +996,24 @@ pub fn render_message(path: &Path, message_id: &str, width: u16) -> Result<Rende
 mod tests {
     use super::*;
 
+    // ── Snippets ────────────────────────────────────────────────
+
+    #[test]
+    fn snippet_skips_quotes_and_collapses_whitespace() {
+        let raw = b"From: a@example.com\r\nSubject: hi\r\n\r\n\
+                    \r\n> quoted text\r\nHello   there,\r\n\r\nsee you  soon\r\n";
+        assert_eq!(extract_snippet(raw, 100), "Hello there, see you soon");
+        assert_eq!(extract_snippet(raw, 5), "Hello");
+    }
+
+    #[test]
+    fn text_measure_caps_width() {
+        assert_eq!(text_measure(200, Some(80)), 80);
+        assert_eq!(text_measure(60, Some(80)), 60);
+        assert_eq!(text_measure(200, None), 200);
+        assert_eq!(text_measure(200, Some(0)), 200);
+    }
+
     // ── Plain text ──────────────────────────────────────────────
 
     #[test]
@@ -709,6 +1109,74 @@ mod tests {
 
     // ── Attachments ─────────────────────────────────────────────
 
+    #[test]
+    fn attachments_listed_without_bodies() {
+        let msg = concat!(
+            "Subject: big\r\n",
+            "Content-Type: multipart/mixed; boundary=\"outer\"\r\n",
+            "\r\n",
+            "Preamble\r\n",
+            "--outer\r\n",
+            "Content-Type: multipart/related; boundary=\"inner\"\r\n",
+            "\r\n",
+            "--inner\r\n",
+            "Content-Type: text/html\r\n",
+            "\r\n",
+            "<p>Hi <img src=\"cid:logo\"></p>\r\n",
+            "--inner\r\n",
+            "Content-Type: image/png\r\n",
+            "Content-ID: <logo>\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAf\r\n",
+            "FcSJAAAADUlEQVR4nGNgYGD4DwABBAEAwS2OUAAA\r\n",
+            "AABJRU5ErkJggg==\r\n",
+            "--inner--\r\n",
+            "--outer\r\n",
+            "Content-Type: text/csv; name=\"=?utf-8?q?caf=C3=A9.csv?=\"\r\n",
+            "Content-Transfer-Encoding: quoted-printable\r\n",
+            "\r\n",
+            "a,b=3D1,caf=C3=A9\r\n",
+            "long line=\r\n",
+            " wrapped\r\n",
+            "--outer\r\n",
+            "Content-Type: message/rfc822\r\n",
+            "\r\n",
+            "Subject: forwarded\r\n",
+            "Content-Type: multipart/mixed; boundary=\"fwd\"\r\n",
+            "\r\n",
+            "--fwd\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Inside\r\n",
+            "--fwd--\r\n",
+            "--outer\r\n",
+            "Content-Type: application/pdf\r\n",
+            "Content-Disposition: attachment; filename=\"report.pdf\"\r\n",
+            "\r\n",
+            "%PDF-1.4\r\n",
+            "--outer--\r\n",
+            "Epilogue\r\n",
+        );
+        let parsed = mail_parser::MessageParser::default().parse(msg.as_bytes()).unwrap();
+        let summary = |list: Vec<AttachmentInfo>| -> Vec<(String, String, String, usize)> {
+            list.into_iter().map(|a| (a.filename, a.mime_type, a.content_id, a.size)).collect()
+        };
+        let expected = summary(discover_attachments(&parsed, None));
+        let streamed = summary(list_attachments_from(msg.as_bytes()).unwrap());
+        let names: Vec<&str> = streamed.iter().map(|a| a.0.as_str()).collect();
+        assert_eq!(names, ["attachment-3", "caf\u{e9}.csv", "attachment-5", "report.pdf"]);
+        for (streamed, expected) in streamed.iter().zip(&expected) {
+            assert_eq!(streamed.0, expected.0);
+            assert_eq!(streamed.1, expected.1);
+            assert_eq!(streamed.2, expected.2);
+        }
+        // Sizes are the decoded ones
+        assert_eq!(streamed[0].3, expected[0].3);
+        assert_eq!(streamed[1].3, "a,b=1,caf\u{e9}\r\nlong line wrapped".len());
+        assert_eq!(streamed[3].3, 8);
+    }
+
     #[test]
     fn extract_attachment_by_index() {
         let msg = concat!(
@@ -729,6 +1197,11 @@ mod tests {
             "fake pdf content\r\n",
             "--bound--\r\n",
         );
+        let listed = list_attachments_from(msg.as_bytes()).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].filename, "report.pdf");
+        assert_eq!(listed[0].content_id, "part.2");
+
         let result = extract_attachment_from_bytes(msg.as_bytes(), "part.2");
         assert!(result.is_ok());
         let att = result.unwrap();
@@ -736,6 +1209,17 @@ mod tests {
         assert!(att.mime_type.contains("pdf"));
     }
 
+    #[test]
+    fn attachment_names_stay_in_their_directory() {
+        assert_eq!(safe_filename("report.pdf").as_deref(), Some("report.pdf"));
+        assert_eq!(safe_filename("../../.bashrc").as_deref(), Some("bashrc"));
+        assert_eq!(safe_filename("C:\\Users\\x\\evil.exe").as_deref(), Some("evil.exe"));
+        assert_eq!(safe_filename("/etc/passwd").as_deref(), Some("passwd"));
+        assert_eq!(safe_filename("bad\nname.txt").as_deref(), Some("badname.txt"));
+        assert_eq!(safe_filename(".."), None);
+        assert_eq!(safe_filename("dir/"), None);
+    }
+
     #[test]
     fn render_message_with_attachments() {
         let msg = concat!(
@@ -765,6 +1249,52 @@ mod tests {
         assert!(!att_links.is_empty());
     }
 
+    #[test]
+    fn render_patch_part_with_diff_colors() {
+        let msg = concat!(
+            "From: dev@example.com\r\n",
+            "Subject: fix\r\n",
+            "Date: Wed, 1 May 2024 16:14:00 -0330\r\n",
+            "Message-ID: <fix@example.com>\r\n",
+            "MIME-Version: 1.0\r\n",
+            "Content-Type: multipart/mixed; boundary=\"bound\"\r\n",
+            "\r\n",
+            "--bound\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "- please review\r\n",
+            "--bound\r\n",
+            "Content-Type: text/x-patch\r\n",
+            "Content-Disposition: attachment; filename=\"fix.patch\"\r\n",
+            "\r\n",
+            "--- a/x.rs\r\n",
+            "+++ b/x.rs\r\n",
+            "@@ -1 +1 @@\r\n",
+            "-old\r\n",
+            "+new\r\n",
+            "--bound--\r\n",
+        );
+        let rm = render_message_from_bytes(msg.as_bytes(), "fix@example.com", 80).unwrap();
+        let kind_of = |text: &str| {
+            rm.lines
+                .iter()
+                .flatten()
+                .find(|s| s.text == text)
+                .map(|s| s.kind.clone())
+                .unwrap_or_else(|| panic!("no line {:?}", text))
+        };
+        assert_eq!(rm.sent_offset, FixedOffset::west_opt(3 * 3600 + 30 * 60));
+        // The body's "- " line isn't part of a diff
+        assert!(matches!(kind_of("- please review"), SpanKind::Normal));
+        assert!(rm.to_plain_text().contains("── fix.patch "));
+        assert!(matches!(kind_of("--- a/x.rs"), SpanKind::DiffMeta));
+        assert!(matches!(kind_of("@@ -1 +1 @@"), SpanKind::DiffMeta));
+        assert!(matches!(kind_of("-old"), SpanKind::DiffRemoved));
+        assert!(matches!(kind_of("+new"), SpanKind::DiffAdded));
+        // Still listed for saving
+        assert!(rm.to_plain_text().contains("📎 fix.patch"));
+    }
+
     #[test]
     fn format_size_units() {
         assert_eq!(format_size(500), "500 B");
@@ -1,8 +1,11 @@
 use anyhow::{bail, Context, Result};
 use lexpr::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
 use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::time::Instant as Deadline;
 
 fn debug_log_path() -> Option<&'static str> {
     static PATH: OnceLock<Option<String>> = OnceLock::new();
@@ -25,13 +28,87 @@ macro_rules! mu_log {
     };
 }
 
+use crate::config::MuTimeouts;
 use crate::envelope::Envelope;
 use crate::mu_sexp;
 
+static TIMEOUTS: OnceLock<MuTimeouts> = OnceLock::new();
+
+/// Set while the maildir is on a slow or unresponsive mount.
+static SLOW_STORAGE: AtomicBool = AtomicBool::new(false);
+
+/// How many times longer mu gets while the storage is slow.
+const SLOW_STORAGE_FACTOR: u32 = 4;
+
+/// Set the `[mu_timeouts]` used by every mu server. Call once at startup.
+pub fn init_timeouts(timeouts: MuTimeouts) {
+    let _ = TIMEOUTS.set(timeouts);
+}
+
+/// Give every mu command longer while the maildir answers slowly, so a
+/// sluggish network mount isn't taken for a hung server.
+pub fn set_slow_storage(slow: bool) {
+    SLOW_STORAGE.store(slow, Ordering::Relaxed);
+}
+
+/// Which `[mu_timeouts]` entry applies to a read.
+#[derive(Debug, Clone, Copy)]
+enum Wait {
+    Command,
+    Find,
+    Index,
+}
+
+impl Wait {
+    fn limit(self) -> Option<Duration> {
+        let timeouts = TIMEOUTS.get().copied().unwrap_or_default();
+        let secs = match self {
+            Wait::Command => timeouts.command,
+            Wait::Find => timeouts.find,
+            Wait::Index => timeouts.index,
+        };
+        let factor = if SLOW_STORAGE.load(Ordering::Relaxed) { SLOW_STORAGE_FACTOR } else { 1 };
+        (secs > 0).then(|| Duration::from_secs(secs) * factor)
+    }
+
+    /// The limit for a raw command passed through from elsewhere.
+    fn for_command(sexp: &str) -> Self {
+        let sexp = sexp.trim_start();
+        if sexp.starts_with("(index") {
+            Wait::Index
+        } else if sexp.starts_with("(find") {
+            Wait::Find
+        } else {
+            Wait::Command
+        }
+    }
+}
+
 pub struct MuClient {
     child: Child,
     stdin: BufWriter<ChildStdin>,
     reader: FrameReader,
+    /// A cancelled command's output is still coming; skip to the pong
+    /// of the ping sent after it before the next command.
+    resync: bool,
+    /// Database this server was started on, for restarting it.
+    muhome: Option<String>,
+    /// When we last sent a command or heard from the server; timeouts
+    /// count from here.
+    last_activity: Deadline,
+    /// Restart the server when it stops responding. Off until the
+    /// initial pong, so a server that never starts isn't retried forever.
+    restart_on_timeout: bool,
+}
+
+/// How often `find_with_progress` reports while waiting.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// State of a running find, reported by `find_with_progress`.
+pub struct FindProgress {
+    pub elapsed: Duration,
+    /// Envelopes received so far.
+    pub received: usize,
 }
 
 struct FrameReader {
@@ -104,6 +181,29 @@ impl Default for FindOpts {
     }
 }
 
+impl FindOpts {
+    /// The mu server `find` command for `query` with these options.
+    pub fn to_sexp(&self, query: &str) -> String {
+        let mut cmd = format!(
+            "(find :query \"{}\" :sortfield :{} :maxnum {}",
+            escape_string(query),
+            self.sort_field,
+            self.max_num,
+        );
+        if self.threads {
+            cmd.push_str(" :threads t");
+        }
+        if self.descending {
+            cmd.push_str(" :descending t");
+        }
+        if self.include_related {
+            cmd.push_str(" :include-related t");
+        }
+        cmd.push(')');
+        cmd
+    }
+}
+
 /// Check if a mu database exists at `muhome`, and if not, run `mu init` and `mu index`.
 /// Called before starting the mu server for an account.
 pub async fn ensure_mu_database(muhome: Option<&str>, maildir: &str) -> Result<()> {
@@ -181,15 +281,82 @@ impl MuClient {
             child,
             stdin: BufWriter::new(stdin),
             reader: FrameReader::new(BufReader::new(stdout)),
+            resync: false,
+            muhome: muhome.map(String::from),
+            last_activity: Deadline::now(),
+            restart_on_timeout: false,
         };
 
         // Wait for initial welcome, then ping
         client.ping().await?;
+        client.restart_on_timeout = true;
         Ok(client)
     }
 
+    /// Read the next frame, giving up once the server has been silent
+    /// for longer than the `wait` limit.
+    async fn read(&mut self, wait: Wait) -> Result<Value> {
+        let frame = match wait.limit() {
+            Some(limit) => {
+                let deadline = self.last_activity + limit;
+                match tokio::time::timeout_at(deadline, self.reader.next_frame()).await {
+                    Ok(frame) => frame?,
+                    Err(_) => return Err(self.timed_out(limit).await),
+                }
+            }
+            None => self.reader.next_frame().await?,
+        };
+        self.last_activity = Deadline::now();
+        Ok(frame)
+    }
+
+    /// Like `read`, but also returns the raw sexp string.
+    async fn read_raw(&mut self, wait: Wait) -> Result<(Value, String)> {
+        let frame = match wait.limit() {
+            Some(limit) => {
+                let deadline = self.last_activity + limit;
+                match tokio::time::timeout_at(deadline, self.reader.next_frame_raw()).await {
+                    Ok(frame) => frame?,
+                    Err(_) => return Err(self.timed_out(limit).await),
+                }
+            }
+            None => self.reader.next_frame_raw().await?,
+        };
+        self.last_activity = Deadline::now();
+        Ok(frame)
+    }
+
+    /// The server has been silent for `limit`: replace it with a fresh one
+    /// on the same database, and return the error for the stuck command.
+    async fn timed_out(&mut self, limit: Duration) -> anyhow::Error {
+        let secs = limit.as_secs();
+        mu_log!("mu server silent for {}s", secs);
+        if !self.restart_on_timeout {
+            return anyhow::anyhow!("mu server did not respond within {}s", secs);
+        }
+        match Box::pin(Self::start(self.muhome.as_deref())).await {
+            Ok(fresh) => {
+                // Dropping the old client kills its process
+                drop(std::mem::replace(self, fresh));
+                anyhow::anyhow!("mu server did not respond within {}s; restarted it", secs)
+            }
+            Err(e) => {
+                let _ = self.child.start_kill();
+                anyhow::anyhow!(
+                    "mu server did not respond within {}s, and restarting it failed: {}",
+                    secs,
+                    e
+                )
+            }
+        }
+    }
+
     /// Send a raw command string to mu server.
     async fn send(&mut self, cmd: &str) -> Result<()> {
+        if self.resync {
+            self.skip_to_pong().await?;
+        }
+        self.last_activity = Deadline::now();
         self.stdin
             .write_all(cmd.as_bytes())
             .await
@@ -205,7 +372,7 @@ impl MuClient {
     /// Read the next meaningful response (skipping :erase markers).
     async fn recv(&mut self) -> Result<Value> {
         loop {
-            let value = self.reader.next_frame().await?;
+            let value = self.read(Wait::Command).await?;
             if mu_sexp::is_erase(&value) {
                 continue;
             }
@@ -232,6 +399,75 @@ impl MuClient {
         Ok(envelopes)
     }
 
+    /// Like `find`, but calls `progress` every [`PROGRESS_INTERVAL`] while
+    /// waiting. Returning false abandons the query and gives `Ok(None)`;
+    /// its remaining output is skipped before the next command.
+    pub async fn find_with_progress(
+        &mut self,
+        query: &str,
+        opts: &FindOpts,
+        mut progress: impl FnMut(&FindProgress) -> bool,
+    ) -> Result<Option<Vec<Envelope>>> {
+        self.send(&opts.to_sexp(query)).await?;
+
+        let started = Instant::now();
+        let mut ticker = tokio::time::interval_at(
+            tokio::time::Instant::now() + PROGRESS_INTERVAL,
+            PROGRESS_INTERVAL,
+        );
+        let limit = Wait::Find.limit();
+        let mut envelopes = Vec::new();
+        loop {
+            // The timeout is checked on ticks rather than inside the read,
+            // so a restart isn't cut short by the next tick
+            let frame = tokio::select! {
+                frame = self.reader.next_frame() => frame?,
+                _ = ticker.tick() => {
+                    if let Some(limit) = limit {
+                        if self.last_activity.elapsed() >= limit {
+                            return Err(self.timed_out(limit).await);
+                        }
+                    }
+                    let state = FindProgress {
+                        elapsed: started.elapsed(),
+                        received: envelopes.len(),
+                    };
+                    if !progress(&state) {
+                        mu_log!("find_with_progress: cancelled after {:?}", state.elapsed);
+                        // mu answers in order, so the pong marks the end
+                        // of the abandoned find's output
+                        self.send("(ping)").await?;
+                        self.resync = true;
+                        return Ok(None);
+                    }
+                    continue;
+                }
+            };
+            self.last_activity = Deadline::now();
+            if mu_sexp::is_erase(&frame) {
+                continue;
+            }
+            if let Some(err) = mu_sexp::is_error(&frame) {
+                bail!("mu find error: {}", err);
+            }
+            if mu_sexp::is_found(&frame).is_some() {
+                return Ok(Some(envelopes));
+            }
+            envelopes.append(&mut mu_sexp::parse_find_response(&frame)?);
+        }
+    }
+
+    /// Discard output up to the pong that follows a cancelled command.
+    async fn skip_to_pong(&mut self) -> Result<()> {
+        loop {
+            let value = self.read(Wait::Find).await?;
+            if mu_sexp::is_pong(&value) {
+                self.resync = false;
+                return Ok(());
+            }
+        }
+    }
+
     /// Run a find query and collect envelopes plus individual raw sexp strings.
     /// Each string in the returned Vec is one envelope's sexp plist (re-serialized
     /// from the parsed Value — semantically identical to mu's output).
@@ -240,29 +476,12 @@ impl MuClient {
         query: &str,
         opts: &FindOpts,
     ) -> Result<(Vec<Envelope>, Vec<String>)> {
-        let mut cmd = format!(
-            "(find :query \"{}\" :sortfield :{} :maxnum {}",
-            escape_string(query),
-            opts.sort_field,
-            opts.max_num,
-        );
-        if opts.threads {
-            cmd.push_str(" :threads t");
-        }
-        if opts.descending {
-            cmd.push_str(" :descending t");
-        }
-        if opts.include_related {
-            cmd.push_str(" :include-related t");
-        }
-        cmd.push(')');
-
-        self.send(&cmd).await?;
+        self.send(&opts.to_sexp(query)).await?;
 
         let mut envelopes = Vec::new();
         let mut raw_sexps = Vec::new();
         loop {
-            let value = self.reader.next_frame().await?;
+            let value = self.read(Wait::Find).await?;
             if mu_sexp::is_erase(&value) {
                 continue;
             }
@@ -305,7 +524,7 @@ impl MuClient {
 
         let mut envelopes = Vec::new();
         loop {
-            let value = self.reader.next_frame().await?;
+            let value = self.read(Wait::Find).await?;
             if mu_sexp::is_erase(&value) {
                 continue;
             }
@@ -320,6 +539,43 @@ impl MuClient {
         }
     }
 
+    /// Count the messages matching a query (up to `max_num`), without
+    /// keeping the envelopes. Used for smart folder badges.
+    pub async fn count(&mut self, query: &str, max_num: u32) -> Result<u32> {
+        let cmd = format!(
+            "(find :query \"{}\" :sortfield :date :maxnum {} :threads nil :descending t)",
+            escape_string(query),
+            max_num,
+        );
+        self.send(&cmd).await?;
+
+        loop {
+            let value = self.read(Wait::Find).await?;
+            if let Some(err) = mu_sexp::is_error(&value) {
+                bail!("mu find error: {}", err);
+            }
+            if let Some(count) = mu_sexp::is_found(&value) {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// The addresses we've corresponded with, most frequently and recently
+    /// used first (mu's contacts ranking), up to `max_num`.
+    pub async fn contacts(&mut self, max_num: u32) -> Result<Vec<String>> {
+        self.send(&format!("(contacts :personal t :maxnum {})", max_num)).await?;
+
+        loop {
+            let value = self.read(Wait::Command).await?;
+            if let Some(err) = mu_sexp::is_error(&value) {
+                bail!("mu contacts error: {}", err);
+            }
+            if let Some(emails) = mu_sexp::parse_contacts(&value) {
+                return Ok(emails);
+            }
+        }
+    }
+
     /// Move a message to a different maildir and/or change flags.
     /// Returns the new docid assigned by mu after the move.
     pub async fn move_msg(
@@ -349,6 +605,16 @@ impl MuClient {
         Ok(docid)
     }
 
+    /// Look up a message by docid, without marking it read.
+    pub async fn view(&mut self, docid: u32) -> Result<Envelope> {
+        self.send(&format!("(view :docid {})", docid)).await?;
+        let resp = self.recv().await?;
+        // Response is (:view <msg-sexp>)
+        let msg = mu_sexp::plist_get(&resp, "view")
+            .with_context(|| format!("unexpected view response: {:?}", resp))?;
+        mu_sexp::parse_envelope(msg)
+    }
+
     /// Remove a message from the filesystem and database.
     /// Used for Gmail archiving: deleting from Inbox effectively archives
     /// the message (it remains in [Gmail]/All Mail).
@@ -363,12 +629,29 @@ impl MuClient {
         Ok(())
     }
 
+    /// Add a single message file to the database, without a full reindex.
+    /// Returns the docid mu assigned to it.
+    pub async fn add_msg(&mut self, path: &std::path::Path) -> Result<u32> {
+        let cmd = format!("(add :path \"{}\")", escape_string(&path.to_string_lossy()));
+        self.send(&cmd).await?;
+        let resp = self.recv().await?;
+        // Response is (:update <msg-sexp> :move nil)
+        mu_sexp::plist_get(&resp, "update")
+            .and_then(|update| mu_sexp::plist_get_u32(update, "docid"))
+            .with_context(|| format!("unexpected add response: {:?}", resp))
+    }
+
     /// Send the `(index)` command to mu server without waiting for the
     /// response.  Call `poll_index_frame()` to read responses one at a
     /// time from the event loop.
-    pub async fn start_index(&mut self) -> Result<()> {
-        mu_log!("index: sent (index)");
-        self.send("(index)").await
+    ///
+    /// With `lazy`, mu skips directories whose mtime predates its last
+    /// index (`:lazy-check t`), which is much faster on large maildirs
+    /// when only a few folders changed.
+    pub async fn start_index(&mut self, lazy: bool) -> Result<()> {
+        let cmd = if lazy { "(index :lazy-check t)" } else { "(index)" };
+        mu_log!("index: sent {}", cmd);
+        self.send(cmd).await
     }
 
     /// Read one frame from the mu server during an index operation.
@@ -378,7 +661,7 @@ impl MuClient {
     /// - `Ok(false)` — progress update, call again
     /// - `Err(_)`    — error (including from mu server)
     pub async fn poll_index_frame(&mut self) -> Result<bool> {
-        let value = self.reader.next_frame().await?;
+        let value = self.read(Wait::Index).await?;
         mu_log!("index: recv {:?}", value);
 
         if mu_sexp::is_erase(&value) {
@@ -416,9 +699,10 @@ impl MuClient {
     /// Skips :erase frames. Used for MuCommand proxying.
     pub async fn send_raw(&mut self, sexp: &str) -> Result<Vec<String>> {
         self.send(sexp).await?;
+        let wait = Wait::for_command(sexp);
         let mut frames = Vec::new();
         loop {
-            let (value, raw) = self.reader.next_frame_raw().await?;
+            let (value, raw) = self.read_raw(wait).await?;
             if mu_sexp::is_erase(&value) {
                 continue;
             }
@@ -453,7 +737,33 @@ impl Drop for MuClient {
     }
 }
 
+/// Whether mu rejected a command because its docid names no message (a
+/// sync moved or removed it since it was listed), rather than failing
+/// for some other reason.
+pub fn is_missing_docid(err: &anyhow::Error) -> bool {
+    let text = err.to_string().to_lowercase();
+    text.starts_with("mu server error:")
+        && (text.contains("docid") || text.contains("message"))
+        && ["no message", "not found", "cannot find", "could not find", "failed to get", "could not get"]
+            .iter()
+            .any(|missing| text.contains(missing))
+}
+
 /// Escape a string for inclusion in an s-expression.
 fn escape_string(s: &str) -> String {
     s.replace('\\', "\\\\").replace('"', "\\\"")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_docid_errors() {
+        let err = |text: &str| anyhow::anyhow!("{}", text);
+        assert!(is_missing_docid(&err("mu server error: failed to get message with docid 42")));
+        assert!(is_missing_docid(&err("mu server error: cannot find message for docid 42")));
+        assert!(!is_missing_docid(&err("mu server error: failed to move message: permission denied")));
+        assert!(!is_missing_docid(&err("mu server did not respond within 30s; restarted it")));
+    }
+}
@@ -4,7 +4,7 @@ use lexpr::parse::{KeywordSyntax, NilSymbol, Options};
 use lexpr::Value;
 use std::path::PathBuf;
 
-use crate::envelope::{Address, Envelope, Flag, ThreadMeta};
+use crate::envelope::{Address, Envelope, Flag, Priority, ThreadMeta};
 
 /// lexpr parse options configured for mu server's Emacs Lisp-style s-expressions.
 fn mu_parse_options() -> Options {
@@ -269,9 +269,28 @@ pub fn parse_envelope(value: &Value) -> Result<Envelope> {
     let to = plist_get(value, "to")
         .map(parse_addresses)
         .unwrap_or_default();
+    let cc = plist_get(value, "cc")
+        .map(parse_addresses)
+        .unwrap_or_default();
+    let bcc = plist_get(value, "bcc")
+        .map(parse_addresses)
+        .unwrap_or_default();
     let flags = plist_get(value, "flags")
         .map(parse_flags)
         .unwrap_or_default();
+    let size = plist_get_u32(value, "size").unwrap_or(0) as usize;
+    // mu sends `:list ""` for messages without a List-Id
+    let list = plist_get_str(value, "list")
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+    let priority = plist_get(value, "priority")
+        .and_then(|v| v.as_symbol())
+        .and_then(Priority::from_symbol)
+        .unwrap_or_default();
+    let tags = plist_get(value, "tags")
+        .and_then(|v| v.as_cons())
+        .map(|cons| cons.iter().filter_map(|pair| pair.car().as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
     let thread_meta = plist_get(value, "meta")
         .map(parse_thread_meta)
         .unwrap_or_default();
@@ -282,10 +301,16 @@ pub fn parse_envelope(value: &Value) -> Result<Envelope> {
         subject,
         from,
         to,
+        cc,
+        bcc,
         date,
         flags,
         maildir,
         path,
+        size,
+        list,
+        priority,
+        tags,
         thread_meta,
     })
 }
@@ -354,6 +379,32 @@ pub fn is_update(value: &Value) -> bool {
     plist_get(value, "update").is_some()
 }
 
+/// Parse a :contacts response into email addresses, in mu's order (most
+/// frequently and recently used first). Entries are "Name <email>" strings,
+/// or plists with an :address or :mail key in older mu versions.
+pub fn parse_contacts(value: &Value) -> Option<Vec<String>> {
+    let contacts = plist_get(value, "contacts")?;
+    let mut emails = Vec::new();
+    if let Some(list) = contacts.as_cons() {
+        for item in list.iter() {
+            let item = item.car();
+            let Some(text) = item
+                .as_str()
+                .or_else(|| plist_get_str(item, "address"))
+                .or_else(|| plist_get_str(item, "mail"))
+            else {
+                continue;
+            };
+            let email = match (text.rfind('<'), text.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &text[start + 1..end],
+                _ => text,
+            };
+            emails.push(email.trim().to_string());
+        }
+    }
+    Some(emails)
+}
+
 /// Convert a mu sexp plist string to a JSON value.
 ///
 /// Special handling:
@@ -502,7 +553,7 @@ mod tests {
 
     #[test]
     fn test_parse_envelope_from_real_sexp() {
-        let sexp = r#"(:path "/mail/Inbox/cur/123:2,S" :date (27028 6999 0) :flags (seen list) :from ((:email "alice@example.com" :name "Alice")) :to ((:email "bob@example.com")) :subject "Hello World" :message-id "abc@example.com" :maildir "/Inbox" :docid 42 :meta (:level 0 :root t :thread-subject t))"#;
+        let sexp = r#"(:path "/mail/Inbox/cur/123:2,S" :date (27028 6999 0) :flags (seen list attach) :from ((:email "alice@example.com" :name "Alice")) :to ((:email "bob@example.com")) :subject "Hello World" :message-id "abc@example.com" :maildir "/Inbox" :docid 42 :meta (:level 0 :root t :thread-subject t))"#;
 
         let value = parse_sexp(sexp).unwrap();
         let env = parse_envelope(&value).unwrap();
@@ -516,10 +567,38 @@ mod tests {
         assert_eq!(env.maildir, "/Inbox");
         assert!(env.flags.contains(&Flag::Seen));
         assert!(env.flags.contains(&Flag::List));
+        assert!(env.has_attachment());
         assert_eq!(env.thread_meta.level, 0);
         assert!(env.thread_meta.root);
     }
 
+    #[test]
+    fn test_parse_envelope_cc() {
+        let sexp = r#"(:docid 7 :from ((:email "alice@example.com")) :to ((:email "bob@example.com")) :cc ((:email "carol@example.com" :name "Carol") (:email "dave@example.com")) :subject "Hi")"#;
+
+        let env = parse_envelope(&parse_sexp(sexp).unwrap()).unwrap();
+        assert_eq!(env.cc.len(), 2);
+        assert_eq!(env.cc[0].name.as_deref(), Some("Carol"));
+        assert_eq!(env.cc[1].email, "dave@example.com");
+        assert!(env.bcc.is_empty());
+        assert_eq!(env.priority, Priority::Normal);
+    }
+
+    #[test]
+    fn test_parse_envelope_priority() {
+        let sexp = r#"(:docid 8 :subject "Server down" :priority high)"#;
+        let env = parse_envelope(&parse_sexp(sexp).unwrap()).unwrap();
+        assert_eq!(env.priority, Priority::High);
+        assert!(env.is_urgent());
+    }
+
+    #[test]
+    fn test_parse_envelope_tags() {
+        let sexp = r#"(:docid 9 :subject "Plan" :tags ("todo" "waiting"))"#;
+        let env = parse_envelope(&parse_sexp(sexp).unwrap()).unwrap();
+        assert_eq!(env.tags, ["todo", "waiting"]);
+    }
+
     #[test]
     fn test_parse_real_mu_headers_response() {
         // Actual sexp from mu server (captured from test run)
@@ -531,6 +610,8 @@ mod tests {
         assert_eq!(envelopes[0].docid, 14);
         assert_eq!(envelopes[0].subject, "Get better slow motion footage");
         assert_eq!(envelopes[0].from[0].name.as_deref(), Some("Example Sender"));
+        assert_eq!(envelopes[0].list, None);
+        assert_eq!(envelopes[0].size, 75490);
     }
 
     #[test]
@@ -563,6 +644,23 @@ mod tests {
         assert_eq!(is_found(&value), Some(3));
     }
 
+    #[test]
+    fn test_parse_contacts() {
+        let value = parse_sexp(
+            "(:contacts (\"Alice Example <alice@example.com>\" \"bob@example.com\") :tstamp \"1\")",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_contacts(&value),
+            Some(vec!["alice@example.com".to_string(), "bob@example.com".to_string()])
+        );
+        let value = parse_sexp("(:contacts ((:name \"Carol\" :address \"carol@example.com\")))").unwrap();
+        assert_eq!(parse_contacts(&value), Some(vec!["carol@example.com".to_string()]));
+        let value = parse_sexp("(:contacts nil)").unwrap();
+        assert_eq!(parse_contacts(&value), Some(Vec::new()));
+        assert_eq!(parse_contacts(&parse_sexp("(:found 3)").unwrap()), None);
+    }
+
     #[test]
     fn test_read_frame_raw() {
         let sexp = "(:found 3 :query \"test\")";
@@ -0,0 +1,168 @@
+//! Private notes on messages — "waiting on legal", "ask Sam about this" —
+//! kept in hutt's state directory by Message-ID and never sent anywhere.
+//! A message's note is shown in the preview header, and `note:` terms in
+//! a search are answered from the notes before the query reaches mu.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::envelope::Envelope;
+use crate::followups::NO_MATCH_QUERY;
+
+/// The search term answered from the notes.
+const TERM: &str = "note:";
+
+/// A note on one message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    pub message_id: String,
+    /// The message's subject, for reading the file by hand.
+    #[serde(default)]
+    pub subject: String,
+    pub text: String,
+    pub updated: DateTime<Utc>,
+}
+
+/// The note on `message_id`, if there is one.
+pub fn find<'a>(notes: &'a [Note], message_id: &str) -> Option<&'a Note> {
+    notes.iter().find(|n| n.message_id == message_id)
+}
+
+/// Note `text` on `envelope`, replacing any note it had; empty text
+/// removes the note. Returns whether it has a note afterwards.
+pub fn set(notes: &mut Vec<Note>, envelope: &Envelope, text: &str) -> bool {
+    notes.retain(|n| n.message_id != envelope.message_id);
+    let text = text.trim();
+    if text.is_empty() {
+        return false;
+    }
+    notes.push(Note {
+        message_id: envelope.message_id.clone(),
+        subject: envelope.subject.clone(),
+        text: text.to_string(),
+        updated: Utc::now(),
+    });
+    true
+}
+
+/// mu query for the messages whose note contains `text` (any case), or
+/// for every noted message when `text` is empty or `*`.
+fn matching(notes: &[Note], text: &str) -> String {
+    let text = text.to_lowercase();
+    let terms: Vec<String> = notes
+        .iter()
+        .filter(|n| text.is_empty() || text == "*" || n.text.to_lowercase().contains(&text))
+        .map(|n| format!("msgid:\"{}\"", n.message_id))
+        .collect();
+    if terms.is_empty() {
+        NO_MATCH_QUERY.to_string()
+    } else {
+        format!("({})", terms.join(" OR "))
+    }
+}
+
+/// Replace each `note:word` or `note:"some words"` term in `query` with
+/// the messages whose notes match, leaving the rest for mu.
+pub fn expand_terms(query: &str, notes: &[Note]) -> String {
+    let mut out = String::with_capacity(query.len());
+    let mut done = 0;
+    while let Some(found) = query[done..].find(TERM) {
+        let start = done + found;
+        let value_start = start + TERM.len();
+        // Only a whole term, not e.g. "footnote:"
+        if !query[..start].chars().last().is_none_or(|c| c.is_whitespace() || c == '(') {
+            out.push_str(&query[done..value_start]);
+            done = value_start;
+            continue;
+        }
+        out.push_str(&query[done..start]);
+        let rest = &query[value_start..];
+        let (value, len) = match rest.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(close) => (&quoted[..close], close + 2),
+                None => (quoted, rest.len()),
+            },
+            None => {
+                let len = rest.find(|c: char| c.is_whitespace() || c == ')').unwrap_or(rest.len());
+                (&rest[..len], len)
+            }
+        };
+        out.push_str(&matching(notes, value));
+        done = value_start + len;
+    }
+    out.push_str(&query[done..]);
+    out
+}
+
+/// Return the path to the notes for a given account name.
+///
+/// Per-account files: `notes.<account_name>.json`, or plain `notes.json`
+/// when `account_name` is empty.
+pub fn notes_path(account_name: &str) -> PathBuf {
+    let dir = crate::paths::state_dir();
+    if account_name.is_empty() {
+        dir.join("notes.json")
+    } else {
+        dir.join(format!("notes.{}.json", account_name))
+    }
+}
+
+/// Load an account's notes. A missing or unreadable file yields none.
+pub fn load_notes(account_name: &str) -> Vec<Note> {
+    load_notes_from(&notes_path(account_name))
+}
+
+fn load_notes_from(path: &Path) -> Vec<Note> {
+    crate::state_crypt::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Save an account's notes. Creates parent directories if needed.
+pub fn save_notes(notes: &[Note], account_name: &str) {
+    save_notes_to(notes, &notes_path(account_name));
+}
+
+fn save_notes_to(notes: &[Note], path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string_pretty(notes) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_answer_note_terms() {
+        let message = |id: &str| Envelope { message_id: id.into(), ..Envelope::default() };
+        let mut notes = Vec::new();
+        assert!(set(&mut notes, &message("a@x"), " Waiting on legal "));
+        assert!(set(&mut notes, &message("b@x"), "ask Sam"));
+        assert!(set(&mut notes, &message("b@x"), "ask Sam about legal"));
+        assert_eq!(notes.len(), 2);
+        assert_eq!(find(&notes, "a@x").map(|n| n.text.as_str()), Some("Waiting on legal"));
+
+        assert_eq!(
+            expand_terms("note:LEGAL AND date:1w..", &notes),
+            "(msgid:\"a@x\" OR msgid:\"b@x\") AND date:1w.."
+        );
+        assert_eq!(expand_terms("(note:\"ask sam\")", &notes), "((msgid:\"b@x\"))");
+        assert_eq!(expand_terms("note:nothing", &notes), NO_MATCH_QUERY);
+        assert_eq!(expand_terms("footnote:x from:bob", &notes), "footnote:x from:bob");
+
+        assert!(!set(&mut notes, &message("a@x"), ""));
+        assert_eq!(expand_terms("note:*", &notes), "(msgid:\"b@x\")");
+
+        let dir = std::env::temp_dir().join(format!("hutt-notes-{}", std::process::id()));
+        let path = dir.join("notes.work.json");
+        save_notes_to(&notes, &path);
+        assert_eq!(load_notes_from(&path), notes);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
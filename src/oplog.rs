@@ -0,0 +1,360 @@
+//! Append-only log of message operations (moves, flag changes, removals,
+//! sends, undos), kept per account for auditing.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::send::Sent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpKind {
+    Move,
+    Flag,
+    Remove,
+    Send,
+    Undo,
+}
+
+impl OpKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OpKind::Move => "move",
+            OpKind::Flag => "flag",
+            OpKind::Remove => "remove",
+            OpKind::Send => "send",
+            OpKind::Undo => "undo",
+        }
+    }
+}
+
+/// One logged operation on a message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpRecord {
+    pub time: DateTime<Local>,
+    pub op: OpKind,
+    #[serde(default)]
+    pub message_id: String,
+    #[serde(default)]
+    pub subject: String,
+    /// Human-readable description, e.g. "Archived: /Inbox → /Archive".
+    pub detail: String,
+    /// For sends: how the SMTP server took the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<Delivery>,
+}
+
+/// A sent message's delivery: who it went to, through which server, and
+/// what the server said.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Envelope recipients: To, Cc and Bcc.
+    pub recipients: Vec<String>,
+    /// SMTP server, as host:port.
+    pub server: String,
+    /// The server's reply, e.g. "250 2.0.0 Ok: queued as 4Bx9".
+    pub response: String,
+}
+
+impl OpRecord {
+    pub fn new(op: OpKind, message_id: &str, subject: &str, detail: String) -> Self {
+        Self {
+            time: Local::now(),
+            op,
+            message_id: message_id.to_string(),
+            subject: subject.to_string(),
+            detail,
+            delivery: None,
+        }
+    }
+
+    /// Record for a message an SMTP server accepted.
+    pub fn for_delivery(sent: &Sent) -> Self {
+        Self {
+            delivery: Some(Delivery {
+                recipients: sent.recipients.clone(),
+                server: sent.server.clone(),
+                response: sent.response.clone(),
+            }),
+            ..Self::for_sent(&sent.formatted)
+        }
+    }
+
+    /// The delivery record as popup lines: a heading, then the details.
+    pub fn delivery_lines(&self, account_name: &str) -> Vec<String> {
+        let mut lines = vec![
+            format!("Delivery of \"{}\"", self.subject),
+            format!("  Sent:        {}", self.time.format("%a %-d %b %Y %H:%M:%S %z")),
+        ];
+        if !account_name.is_empty() {
+            lines.push(format!("  Account:     {}", account_name));
+        }
+        match &self.delivery {
+            Some(delivery) => {
+                lines.push(format!("  Server:      {}", delivery.server));
+                lines.push(format!("  Recipients:  {}", delivery.recipients.join(", ")));
+                lines.push(format!("  Response:    {}", delivery.response));
+            }
+            None => lines.push(format!("  {}", self.detail)),
+        }
+        lines.push(format!("  Message-ID:  {}", self.message_id));
+        lines
+    }
+
+    /// For a move, the folder the message left and the one it went to, as
+    /// the detail gives them ("Trashed: /Inbox → /Trash").
+    pub fn moved(&self) -> Option<(&str, &str)> {
+        if self.op != OpKind::Move {
+            return None;
+        }
+        let (_, folders) = self.detail.split_once(": ")?;
+        folders.split_once(" \u{2192} ")
+    }
+
+    /// Record for a sent message, taking the id, subject and recipients
+    /// from its headers.
+    pub fn for_sent(raw: &[u8]) -> Self {
+        let parsed = mail_parser::MessageParser::default().parse(raw);
+        let message_id = parsed.as_ref().and_then(|m| m.message_id()).unwrap_or("");
+        let subject = parsed.as_ref().and_then(|m| m.subject()).unwrap_or("");
+        let to: Vec<String> = parsed
+            .as_ref()
+            .and_then(|m| m.to())
+            .map(|addrs| {
+                addrs
+                    .iter()
+                    .filter_map(|a| a.address().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self::new(OpKind::Send, message_id, subject, format!("Sent to {}", to.join(", ")))
+    }
+}
+
+/// Return the path to the operations log for a given account name.
+///
+/// Per-account files: `oplog.<account_name>.jsonl`, or plain
+/// `oplog.jsonl` when `account_name` is empty.
+pub fn oplog_path(account_name: &str) -> PathBuf {
+    let dir = crate::paths::state_dir();
+    if account_name.is_empty() {
+        dir.join("oplog.jsonl")
+    } else {
+        dir.join(format!("oplog.{}.jsonl", account_name))
+    }
+}
+
+/// Append records to an account's log, one JSON object per line.
+/// Failures are ignored: logging must never block triage.
+pub fn append(account_name: &str, records: &[OpRecord]) {
+    append_to(&oplog_path(account_name), records);
+}
+
+fn append_to(path: &Path, records: &[OpRecord]) {
+    if records.is_empty() {
+        return;
+    }
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let mut buf = String::new();
+    for record in records {
+        if let Ok(line) = serde_json::to_string(record) {
+            buf.push_str(&line);
+            buf.push('\n');
+        }
+    }
+    // An encrypted log can't be appended to; rewrite it whole
+    if crate::state_crypt::enabled() {
+        let existing = match crate::state_crypt::read_to_string(path) {
+            Ok(existing) => existing,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(_) => return,
+        };
+        let _ = crate::state_crypt::write(path, &(existing + &buf));
+        return;
+    }
+    if crate::state_crypt::is_encrypted_file(path) {
+        return;
+    }
+    let Ok(mut file) = std::fs::OpenOptions::new().create(true).append(true).open(path) else {
+        return;
+    };
+    let _ = file.write_all(buf.as_bytes());
+}
+
+/// The latest send record for `message_id` in an account's log.
+pub fn find_send(account_name: &str, message_id: &str) -> Option<OpRecord> {
+    find_send_in(&oplog_path(account_name), message_id)
+}
+
+fn find_send_in(path: &Path, message_id: &str) -> Option<OpRecord> {
+    let contents = crate::state_crypt::read_to_string(path).ok()?;
+    contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<OpRecord>(line).ok())
+        .find(|r| r.op == OpKind::Send && r.message_id == message_id)
+}
+
+/// A message the log shows trashed or removed, and not since moved back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deletion {
+    pub time: DateTime<Local>,
+    pub message_id: String,
+    pub subject: String,
+    /// The folder it was trashed from; `None` when it was removed outright.
+    pub from: Option<String>,
+}
+
+/// Messages moved to the `trash` folder or removed since `since`, newest
+/// first, each once. A later move or undo of a message means it is no
+/// longer deleted, so it is left out.
+pub fn deletions(account_name: &str, trash: &str, since: DateTime<Local>) -> Vec<Deletion> {
+    deletions_from(&oplog_path(account_name), trash, since)
+}
+
+fn deletions_from(path: &Path, trash: &str, since: DateTime<Local>) -> Vec<Deletion> {
+    let mut seen = std::collections::HashSet::new();
+    let mut deletions = Vec::new();
+    for record in recent_from(path, usize::MAX) {
+        if record.time < since {
+            break;
+        }
+        if record.message_id.is_empty()
+            || !matches!(record.op, OpKind::Move | OpKind::Remove | OpKind::Undo)
+            || !seen.insert(record.message_id.clone())
+        {
+            continue;
+        }
+        let from = match (record.op, record.moved()) {
+            (OpKind::Remove, _) => None,
+            (OpKind::Move, Some((from, to))) if to == trash => Some(from.to_string()),
+            _ => continue,
+        };
+        deletions.push(Deletion {
+            time: record.time,
+            message_id: record.message_id,
+            subject: record.subject,
+            from,
+        });
+    }
+    deletions
+}
+
+/// The most recent `limit` records for an account, newest first.
+/// Unparseable lines are skipped.
+pub fn recent(account_name: &str, limit: usize) -> Vec<OpRecord> {
+    recent_from(&oplog_path(account_name), limit)
+}
+
+fn recent_from(path: &Path, limit: usize) -> Vec<OpRecord> {
+    let Ok(contents) = crate::state_crypt::read_to_string(path) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_and_read_recent() {
+        let dir = std::env::temp_dir().join(format!("hutt-oplog-{}", std::process::id()));
+        let path = dir.join("oplog.work.jsonl");
+
+        let first = OpRecord::new(OpKind::Move, "a@x", "First", "Archived: /Inbox → /Archive".into());
+        let second = OpRecord::new(OpKind::Flag, "b@x", "Second", "starred: S → SF".into());
+        append_to(&path, std::slice::from_ref(&first));
+        append_to(&path, std::slice::from_ref(&second));
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        assert_eq!(recent_from(&path, 10), vec![second.clone(), first]);
+        assert_eq!(recent_from(&path, 1), vec![second]);
+        assert!(recent_from(&dir.join("missing.jsonl"), 10).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn deletions_skip_messages_moved_back() {
+        let dir = std::env::temp_dir().join(format!("hutt-oplog-deleted-{}", std::process::id()));
+        let path = dir.join("oplog.work.jsonl");
+        let record = |op, id: &str, detail: &str, days: i64| OpRecord {
+            time: Local::now() - chrono::Duration::days(days),
+            ..OpRecord::new(op, id, &format!("Subject {}", id), detail.into())
+        };
+        append_to(
+            &path,
+            &[
+                record(OpKind::Move, "old@x", "Trashed: /Inbox \u{2192} /Trash", 40),
+                record(OpKind::Move, "a@x", "Trashed: /Inbox \u{2192} /Trash", 3),
+                record(OpKind::Move, "b@x", "Trashed: /Lists \u{2192} /Trash", 3),
+                record(OpKind::Move, "c@x", "Archived: /Inbox \u{2192} /Archive", 2),
+                record(OpKind::Remove, "d@x", "Archived: removed from /INBOX", 2),
+                record(OpKind::Flag, "b@x", "read: \u{2192} S", 1),
+                record(OpKind::Move, "a@x", "Restored: /Trash \u{2192} /Inbox", 1),
+            ],
+        );
+
+        let since = Local::now() - chrono::Duration::days(30);
+        let deletions = deletions_from(&path, "/Trash", since);
+        let found: Vec<(&str, Option<&str>)> =
+            deletions.iter().map(|d| (d.message_id.as_str(), d.from.as_deref())).collect();
+        assert_eq!(found, [("d@x", None), ("b@x", Some("/Lists"))]);
+        assert_eq!(deletions[1].subject, "Subject b@x");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sent_record_from_headers() {
+        let msg = "From: me@example.com\r\nTo: Bob <bob@example.com>\r\nSubject: Lunch\r\nMessage-ID: <abc@example.com>\r\n\r\nHi\r\n";
+        let record = OpRecord::for_sent(msg.as_bytes());
+        assert_eq!(record.op, OpKind::Send);
+        assert_eq!(record.message_id, "abc@example.com");
+        assert_eq!(record.subject, "Lunch");
+        assert_eq!(record.detail, "Sent to bob@example.com");
+    }
+
+    #[test]
+    fn delivery_record_lookup() {
+        let dir = std::env::temp_dir().join(format!("hutt-oplog-sends-{}", std::process::id()));
+        let path = dir.join("oplog.work.jsonl");
+
+        let sent = Sent {
+            formatted: b"To: bob@example.com\r\nSubject: Lunch\r\nMessage-ID: <abc@example.com>\r\n\r\nHi\r\n".to_vec(),
+            recipients: vec!["bob@example.com".into(), "secret@example.com".into()],
+            response: "250 2.0.0 Ok: queued as 4Bx9".into(),
+            server: "smtp.example.com:465".into(),
+            unreachable: Vec::new(),
+        };
+        let delivery = OpRecord::for_delivery(&sent);
+        let moved = OpRecord::new(OpKind::Move, "abc@example.com", "Lunch", "Archived".into());
+        append_to(&path, &[delivery.clone(), moved]);
+
+        let found = find_send_in(&path, "abc@example.com").unwrap();
+        assert_eq!(found, delivery);
+        let lines = found.delivery_lines("Work").join("\n");
+        assert!(lines.starts_with("Delivery of \"Lunch\"\n"));
+        assert!(lines.contains("Server:      smtp.example.com:465"));
+        assert!(lines.contains("Recipients:  bob@example.com, secret@example.com"));
+        assert!(lines.contains("Response:    250 2.0.0 Ok: queued as 4Bx9"));
+        assert!(find_send_in(&path, "other@example.com").is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,133 @@
+//! Patch emails (git format-patch messages and text/x-patch parts): where
+//! the diff starts, how each of its lines is highlighted, and the file
+//! names and commands for saving and applying them.
+
+use std::path::PathBuf;
+
+use crate::config::shell_quote;
+
+/// How a diff line is shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffLine {
+    Added,
+    Removed,
+    /// File headers and hunk markers.
+    Meta,
+    Context,
+}
+
+/// Index of the line a diff starts on: a `diff ` line, or a `--- ` line
+/// directly followed by a `+++ ` one.
+pub fn diff_start(lines: &[&str]) -> Option<usize> {
+    lines.iter().enumerate().position(|(i, line)| {
+        line.starts_with("diff ")
+            || (line.starts_with("--- ") && lines.get(i + 1).is_some_and(|next| next.starts_with("+++ ")))
+    })
+}
+
+/// Whether `line` ends a diff: the `-- ` signature separator that
+/// format-patch puts before the git version.
+pub fn is_diff_end(line: &str) -> bool {
+    line == "-- "
+}
+
+pub fn classify(line: &str) -> DiffLine {
+    const META: [&str; 5] = ["diff ", "index ", "--- ", "+++ ", "@@"];
+    if META.iter().any(|prefix| line.starts_with(prefix)) {
+        DiffLine::Meta
+    } else if line.starts_with('+') {
+        DiffLine::Added
+    } else if line.starts_with('-') {
+        DiffLine::Removed
+    } else {
+        DiffLine::Context
+    }
+}
+
+/// File name for a saved patch, format-patch style: the subject without
+/// its `[PATCH ...]` prefix, lowercased, non-alphanumerics as dashes.
+pub fn file_name(subject: &str) -> String {
+    let mut subject = subject.trim();
+    if subject.starts_with('[') {
+        if let Some(end) = subject.find(']') {
+            subject = subject[end + 1..].trim_start();
+        }
+    }
+    let mut slug = String::new();
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_matches('-').chars().take(52).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "message.patch".to_string()
+    } else {
+        format!("{}.patch", slug)
+    }
+}
+
+/// The shell command applying `files` with the configured `patch_command`.
+/// `{file}` in the command is replaced with the files; without it they
+/// are appended.
+pub fn apply_command(command: &str, files: &[PathBuf]) -> String {
+    let files = files
+        .iter()
+        .map(|f| shell_quote(&f.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if command.contains("{file}") {
+        command.replace("{file}", &files)
+    } else {
+        format!("{} {}", command, files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_and_classifies_diff() {
+        let text = "Fix the frobnicator.\n\
+                    \n\
+                    - it crashed\n\
+                    ---\n\
+                    \x20src/frob.rs | 2 +-\n\
+                    \n\
+                    diff --git a/src/frob.rs b/src/frob.rs\n\
+                    index 1234567..89abcde 100644\n\
+                    --- a/src/frob.rs\n\
+                    +++ b/src/frob.rs\n\
+                    @@ -1,3 +1,3 @@\n\
+                    \x20fn frob() {\n\
+                    -    panic!();\n\
+                    +    ok();\n\
+                    -- \n\
+                    2.43.0";
+        let lines: Vec<&str> = text.lines().collect();
+        let start = diff_start(&lines).unwrap();
+        assert_eq!(lines[start], "diff --git a/src/frob.rs b/src/frob.rs");
+        let kinds: Vec<DiffLine> = lines[start..].iter().map(|l| classify(l)).collect();
+        use DiffLine::*;
+        assert_eq!(&kinds[..8], &[Meta, Meta, Meta, Meta, Meta, Context, Removed, Added]);
+        assert!(is_diff_end(lines[start + 8]));
+
+        // A plain unified diff, without git's header
+        let plain = ["Try this:", "--- old.txt", "+++ new.txt", "@@ -1 +1 @@"];
+        assert_eq!(diff_start(&plain), Some(1));
+        assert_eq!(diff_start(&["--- not a diff", "just text"]), None);
+    }
+
+    #[test]
+    fn file_names_and_commands() {
+        assert_eq!(file_name("[PATCH v2 3/7] mu: Fix the frobnicator's crash"), "mu-fix-the-frobnicator-s-crash.patch");
+        assert_eq!(file_name("[PATCH]"), "message.patch");
+        let files = [PathBuf::from("/tmp/a b"), PathBuf::from("/tmp/it's")];
+        assert_eq!(apply_command("git -C ~/src/hutt am -3", &files), "git -C ~/src/hutt am -3 '/tmp/a b' '/tmp/it'\\''s'");
+        assert_eq!(apply_command("cd ~/src && git am {file}", &files[..1]), "cd ~/src && git am '/tmp/a b'");
+    }
+}
@@ -0,0 +1,47 @@
+//! Where hutt keeps its files: settings it writes itself (smart folders,
+//! splits, contacts, ...) in the XDG config directory, saved state
+//! (sessions, the operations log, ...) in the state directory, and
+//! rebuildable data in the cache directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Set by [`use_root`]: all three directories live under it instead.
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keep config, state and cache in `config`, `state` and `cache` under
+/// `root` for the rest of the run, leaving the user's own untouched (the
+/// tutorial). Call before anything reads or writes them; later calls are
+/// ignored.
+pub fn use_root(root: &Path) {
+    let _ = ROOT.set(root.to_path_buf());
+}
+
+/// Return the config directory for hutt.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("config", "XDG_CONFIG_HOME", &[".config"])
+}
+
+/// Return the state directory for hutt.
+pub fn state_dir() -> PathBuf {
+    xdg_dir("state", "XDG_STATE_HOME", &[".local", "state"])
+}
+
+/// Return the cache directory for hutt.
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("cache", "XDG_CACHE_HOME", &[".cache"])
+}
+
+/// `<root>/<kind>` under [`use_root`], else `$<var>/hutt`, else
+/// `$HOME/<fallback>/hutt`, else the current directory.
+fn xdg_dir(kind: &str, var: &str, fallback: &[&str]) -> PathBuf {
+    if let Some(root) = ROOT.get() {
+        root.join(kind)
+    } else if let Ok(xdg) = std::env::var(var) {
+        PathBuf::from(xdg).join("hutt")
+    } else if let Ok(home) = std::env::var("HOME") {
+        fallback.iter().fold(PathBuf::from(home), |dir, part| dir.join(part)).join("hutt")
+    } else {
+        PathBuf::from(".")
+    }
+}
@@ -0,0 +1,61 @@
+//! Whether the machine is running on battery, from Linux's
+//! `/sys/class/power_supply`. Elsewhere, and when nothing there says so,
+//! it's assumed to be plugged in.
+
+use std::path::Path;
+use std::time::Duration;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// How long an answer is good for before looking again.
+pub const RECHECK: Duration = Duration::from_secs(60);
+
+/// Whether the machine is running on battery right now.
+pub fn on_battery() -> bool {
+    on_battery_in(Path::new(POWER_SUPPLY))
+}
+
+fn read(dir: &Path, name: &str) -> String {
+    std::fs::read_to_string(dir.join(name)).map(|s| s.trim().to_string()).unwrap_or_default()
+}
+
+/// Whether the supplies under `root` say we're on battery: no mains
+/// adapter is online and a battery is discharging.
+fn on_battery_in(root: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return false;
+    };
+    let mut discharging = false;
+    for dir in entries.flatten().map(|e| e.path()) {
+        match read(&dir, "type").as_str() {
+            "Mains" | "USB" if read(&dir, "online") == "1" => return false,
+            "Battery" if read(&dir, "status") == "Discharging" => discharging = true,
+            _ => {}
+        }
+    }
+    discharging
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_running_on_battery() {
+        let root = std::env::temp_dir().join(format!("hutt-power-{}", std::process::id()));
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let dir = root.join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            for (file, contents) in files {
+                std::fs::write(dir.join(file), format!("{}\n", contents)).unwrap();
+            }
+        };
+        assert!(!on_battery_in(&root));
+        supply("BAT0", &[("type", "Battery"), ("status", "Discharging")]);
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        assert!(on_battery_in(&root));
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert!(!on_battery_in(&root));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+//! Redacted copies of messages, for pasting into public bug reports and
+//! chat: email addresses and configured patterns (ticket numbers, tokens)
+//! are replaced by placeholders.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use regex::{Captures, NoExpand, Regex};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::envelope::{Address, Envelope};
+
+/// Good enough to catch addresses in running text; it need not validate.
+const EMAIL_PATTERN: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}";
+
+/// A pattern to hide, from `[[redact]]`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RedactRule {
+    /// Regular expression matching the text to hide.
+    pub pattern: String,
+    /// What to put in its place.
+    #[serde(default = "default_placeholder")]
+    pub placeholder: String,
+}
+
+fn default_placeholder() -> String {
+    "[REDACTED]".to_string()
+}
+
+pub struct Redactor {
+    email: Regex,
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    pub fn new(rules: &[RedactRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let re = Regex::new(&rule.pattern)
+                    .with_context(|| format!("invalid redact pattern '{}'", rule.pattern))?;
+                Ok((re, rule.placeholder.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            email: Regex::new(EMAIL_PATTERN).expect("valid email pattern"),
+            rules,
+        })
+    }
+
+    /// Redact `text`. Each distinct address gets its own numbered
+    /// placeholder, so who wrote to whom still reads through.
+    pub fn redact(&self, text: &str) -> String {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut text = self
+            .email
+            .replace_all(text, |caps: &Captures| {
+                let next = seen.len() + 1;
+                let n = *seen.entry(caps[0].to_lowercase()).or_insert(next);
+                format!("[email-{}]", n)
+            })
+            .into_owned();
+        for (re, placeholder) in &self.rules {
+            text = re.replace_all(&text, NoExpand(placeholder)).into_owned();
+        }
+        text
+    }
+}
+
+/// A message as plain text: the main headers, then the body. Addresses
+/// are written without display names, which redaction couldn't catch.
+pub fn message_text(envelope: &Envelope, body: &str) -> String {
+    let addresses = |list: &[Address]| {
+        list.iter().map(|a| a.email.as_str()).collect::<Vec<_>>().join(", ")
+    };
+    let mut out = format!("From: {}\n", addresses(&envelope.from));
+    out.push_str(&format!("To: {}\n", addresses(&envelope.to)));
+    if !envelope.cc.is_empty() {
+        out.push_str(&format!("Cc: {}\n", addresses(&envelope.cc)));
+    }
+    out.push_str(&format!(
+        "Date: {}\n",
+        envelope.date.with_timezone(&Local).format("%a, %-d %b %Y %H:%M")
+    ));
+    out.push_str(&format!("Subject: {}\n\n", envelope.subject));
+    out.push_str(body.trim_end());
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_addresses_and_rules() {
+        let rules = vec![
+            RedactRule { pattern: r"TICKET-\d+".into(), placeholder: "[ticket]".into() },
+            RedactRule { pattern: r"sk_live_[A-Za-z0-9]+".into(), placeholder: default_placeholder() },
+        ];
+        let redactor = Redactor::new(&rules).unwrap();
+        let text = "From: alice@example.com\nTo: bob+bugs@mail.example.org\n\n\
+                    Alice.Smith@Example.com wrote about TICKET-4411 and TICKET-12;\n\
+                    key sk_live_abc123, cc alice@example.com. Cost $1.";
+        assert_eq!(
+            redactor.redact(text),
+            "From: [email-1]\nTo: [email-2]\n\n\
+             [email-3] wrote about [ticket] and [ticket];\n\
+             key [REDACTED], cc [email-1]. Cost $1."
+        );
+
+        let bad = vec![RedactRule { pattern: "(".into(), placeholder: default_placeholder() }];
+        let err = Redactor::new(&bad).err().unwrap();
+        assert!(err.to_string().contains("invalid redact pattern '('"));
+    }
+
+    #[test]
+    fn message_text_drops_display_names() {
+        let envelope = Envelope {
+            subject: "Crash on start".to_string(),
+            from: vec![Address { name: Some("Alice Smith".into()), email: "alice@example.com".into() }],
+            to: vec![Address { name: None, email: "bugs@example.org".into() }],
+            ..Envelope::default()
+        };
+        let text = message_text(&envelope, "It crashes.\n\n");
+        assert!(text.starts_with("From: alice@example.com\nTo: bugs@example.org\nDate: "));
+        assert!(!text.contains("Alice Smith"));
+        assert!(text.ends_with("Subject: Crash on start\n\nIt crashes.\n"));
+    }
+}
@@ -0,0 +1,88 @@
+//! Which docid each message has now. mu gives a message a new docid
+//! whenever it moves it or changes its flags (renaming the file), so a
+//! docid kept for later — by the multi-selection, the split exclusions,
+//! the undo stack — can go stale after a change made somewhere else,
+//! such as a flag toggled from the thread view.
+//!
+//! Every renumbering is recorded here, and anything holding a docid asks
+//! for the current one before using it. Renumberings are followed docid
+//! to docid rather than by Message-ID, since copies of a message in two
+//! folders share a Message-ID but not a docid.
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Default)]
+pub struct MessageRegistry {
+    /// Each stale docid and the one mu replaced it with.
+    renumbered: HashMap<u32, u32>,
+}
+
+impl MessageRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that mu replaced `docid` with `new_docid`.
+    pub fn renumber(&mut self, docid: u32, new_docid: u32) {
+        if docid != new_docid {
+            self.renumbered.insert(docid, new_docid);
+        }
+    }
+
+    /// The message's docid now, following every renumbering since
+    /// `docid` was handed out.
+    pub fn current(&self, docid: u32) -> u32 {
+        let mut current = docid;
+        // Bounded, in case mu ever hands a docid out again
+        for _ in 0..=self.renumbered.len() {
+            match self.renumbered.get(&current) {
+                Some(&next) if next != docid => current = next,
+                _ => break,
+            }
+        }
+        current
+    }
+
+    /// Swap each stale docid in `docids` for the current one.
+    pub fn refresh(&self, docids: &mut HashSet<u32>) {
+        if docids.iter().any(|d| self.renumbered.contains_key(d)) {
+            *docids = docids.iter().map(|&d| self.current(d)).collect();
+        }
+    }
+
+    /// Forget everything, as when switching to another account's
+    /// database.
+    pub fn clear(&mut self) {
+        self.renumbered.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_renumberings() {
+        let mut registry = MessageRegistry::new();
+        // Starred in the thread view, then archived, then moved back
+        registry.renumber(10, 20);
+        registry.renumber(20, 30);
+        registry.renumber(30, 40);
+        registry.renumber(5, 5);
+        assert_eq!(registry.current(10), 40);
+        assert_eq!(registry.current(30), 40);
+        assert_eq!(registry.current(5), 5);
+        assert_eq!(registry.current(7), 7);
+
+        let mut selected = HashSet::from([10, 7]);
+        registry.refresh(&mut selected);
+        assert_eq!(selected, HashSet::from([40, 7]));
+
+        // A cycle can't hang the lookup
+        registry.renumber(40, 10);
+        assert_eq!(registry.current(10), 40);
+
+        registry.clear();
+        assert_eq!(registry.current(10), 10);
+    }
+}
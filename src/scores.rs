@@ -0,0 +1,179 @@
+//! Message scores from outside tools: a numeric header a spam filter or
+//! classifier adds (`score_header`, e.g. X-Spam-Score), or a command that
+//! reads the message and prints a number (`score_command`). Scores are
+//! taken in the background and cached per account, keyed by message-id.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// A message to score.
+pub struct ScoreRequest {
+    pub message_id: String,
+    pub path: PathBuf,
+}
+
+/// Where a score comes from.
+#[derive(Debug, Clone, Default)]
+pub struct Scorer {
+    /// Header holding the score; checked first.
+    pub header: Option<String>,
+    /// Shell command given the raw message on stdin.
+    pub command: Option<String>,
+}
+
+impl Scorer {
+    /// Score one message: its header if it has one, else the command's
+    /// output. `None` when neither gives a number.
+    pub fn score(&self, path: &Path) -> Option<f64> {
+        if let Some(header) = &self.header {
+            if let Some(score) = header_value(path, header).as_deref().and_then(parse_score) {
+                return Some(score);
+            }
+        }
+        let command = self.command.as_ref()?;
+        let message = std::fs::File::open(path).ok()?;
+        let output = Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::from(message))
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_score(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// The value of header `name` in the message at `path`, unfolded.
+fn header_value(path: &Path, name: &str) -> Option<String> {
+    let reader = BufReader::new(std::fs::File::open(path).ok()?);
+    let mut value: Option<String> = None;
+    for line in reader.split(b'\n') {
+        let line = line.ok()?;
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some(value) = value.as_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if value.is_some() {
+            break;
+        }
+        if let Some((field, rest)) = line.split_once(':') {
+            if field.trim().eq_ignore_ascii_case(name) {
+                value = Some(rest.trim().to_string());
+            }
+        }
+    }
+    value
+}
+
+/// The number in a header value or command output: after `score=` when
+/// there is one (as in X-Spam-Status), else the first word that parses.
+pub fn parse_score(text: &str) -> Option<f64> {
+    let text = match text.find("score=") {
+        Some(i) => &text[i + "score=".len()..],
+        None => text,
+    };
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .find_map(|word| word.parse::<f64>().ok())
+        .filter(|score| score.is_finite())
+}
+
+/// Return the path to the score cache for a given account name.
+pub fn scores_path(account_name: &str) -> PathBuf {
+    let dir = crate::paths::cache_dir();
+    if account_name.is_empty() {
+        dir.join("scores.json")
+    } else {
+        dir.join(format!("scores.{}.json", account_name))
+    }
+}
+
+/// Load cached scores (message-id → score, null when the message had
+/// none) for an account. A missing or unreadable cache yields an empty map.
+pub fn load_scores(account_name: &str) -> HashMap<String, Option<f64>> {
+    crate::state_crypt::read_to_string(&scores_path(account_name))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Save scores for an account. Creates parent directories if needed.
+pub fn save_scores(scores: &HashMap<String, Option<f64>>, account_name: &str) {
+    let path = scores_path(account_name);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string(scores) {
+        let _ = crate::state_crypt::write(&path, &contents);
+    }
+}
+
+/// Start the background scorer. Requests are scored in order on the
+/// blocking pool; each result is sent back as `(message_id, score)`.
+pub fn spawn_scorer(
+    scorer: Scorer,
+) -> (UnboundedSender<ScoreRequest>, UnboundedReceiver<(String, Option<f64>)>) {
+    let (req_tx, mut req_rx) = unbounded_channel::<ScoreRequest>();
+    let (res_tx, res_rx) = unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(req) = req_rx.recv().await {
+            let scorer = scorer.clone();
+            let path = req.path;
+            let score = tokio::task::spawn_blocking(move || scorer.score(&path))
+                .await
+                .unwrap_or_default();
+            if res_tx.send((req.message_id, score)).is_err() {
+                break;
+            }
+        }
+    });
+    (req_tx, res_rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_from_headers_and_commands() {
+        assert_eq!(parse_score("5.2"), Some(5.2));
+        assert_eq!(parse_score("Yes, score=7.1 required=5.0 tests=BAYES_99"), Some(7.1));
+        assert_eq!(parse_score("-0.4 (---)"), Some(-0.4));
+        assert_eq!(parse_score("***"), None);
+
+        let dir = std::env::temp_dir().join(format!("hutt-scores-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("msg");
+        std::fs::write(
+            &path,
+            "Subject: hi\r\nX-Spam-Status: No,\r\n\tscore=-1.5 required=5.0\r\n\r\nX-Spam-Score: 9\r\n",
+        )
+        .unwrap();
+
+        let header = |name: &str| Scorer { header: Some(name.into()), command: None };
+        assert_eq!(header("x-spam-status").score(&path), Some(-1.5));
+        // Only the header block counts
+        assert_eq!(header("X-Spam-Score").score(&path), None);
+
+        let both = Scorer {
+            header: Some("X-Spam-Score".into()),
+            command: Some("grep -c hi".into()),
+        };
+        assert_eq!(both.score(&path), Some(1.0));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
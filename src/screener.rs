@@ -0,0 +1,159 @@
+//! The screener: inbox mail from first-time senders — people you've never
+//! written to who aren't in the address book — gathered in the built-in
+//! `@Screened` smart folder until you approve or block them. Approving
+//! adds the sender to the contacts; blocking adds them to the blocked
+//! list in `screener.toml` (shared by every account, like the contacts),
+//! and their inbox mail is moved to spam, now and whenever new mail is
+//! indexed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::envelope::Envelope;
+
+/// Most of the newest sent messages read for the addresses you've
+/// written to.
+pub const SENT_LIMIT: u32 = 5000;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScreenerFile {
+    #[serde(default)]
+    blocked: Vec<String>,
+}
+
+pub fn screener_path() -> PathBuf {
+    crate::paths::config_dir().join("screener.toml")
+}
+
+/// Load the blocked addresses. A missing or unparsable file blocks no one.
+pub fn load_blocked() -> Vec<String> {
+    load_blocked_from(&screener_path())
+}
+
+fn load_blocked_from(path: &Path) -> Vec<String> {
+    crate::state_crypt::read_to_string(path)
+        .ok()
+        .and_then(|contents| toml::from_str::<ScreenerFile>(&contents).ok())
+        .map(|file| file.blocked)
+        .unwrap_or_default()
+}
+
+/// Save the blocked addresses. Creates parent directories if needed.
+pub fn save_blocked(blocked: &[String]) {
+    save_blocked_to(blocked, &screener_path());
+}
+
+fn save_blocked_to(blocked: &[String], path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let file = ScreenerFile { blocked: blocked.to_vec() };
+    if let Ok(contents) = toml::to_string_pretty(&file) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+pub fn is_blocked(blocked: &[String], email: &str) -> bool {
+    blocked.iter().any(|b| b.eq_ignore_ascii_case(email))
+}
+
+/// Block `email`. Returns whether they weren't blocked already.
+pub fn block(blocked: &mut Vec<String>, email: &str) -> bool {
+    if is_blocked(blocked, email) {
+        return false;
+    }
+    blocked.push(email.to_lowercase());
+    true
+}
+
+/// Stop blocking `email`. Returns whether they were blocked.
+pub fn unblock(blocked: &mut Vec<String>, email: &str) -> bool {
+    let before = blocked.len();
+    blocked.retain(|b| !b.eq_ignore_ascii_case(email));
+    blocked.len() != before
+}
+
+/// Everyone `sent` was addressed to, lowercased.
+pub fn recipients(sent: &[Envelope]) -> HashSet<String> {
+    sent.iter()
+        .flat_map(|e| e.to.iter().chain(&e.cc).chain(&e.bcc))
+        .map(|a| a.email.to_lowercase())
+        .collect()
+}
+
+/// Senders of `inbox` not in `known` (lowercased addresses), each once,
+/// in the order their mail appears.
+pub fn first_time_senders(inbox: &[Envelope], known: &HashSet<String>) -> Vec<String> {
+    let mut senders: Vec<String> = Vec::new();
+    for sender in inbox.iter().filter_map(|e| e.from.first()) {
+        let email = sender.email.to_lowercase();
+        if !email.is_empty() && !known.contains(&email) && !senders.contains(&email) {
+            senders.push(email);
+        }
+    }
+    senders
+}
+
+/// mu query for the mail in `folder` from any of `senders`, or `None`
+/// when there are none.
+pub fn senders_query(folder: &str, senders: &[String]) -> Option<String> {
+    if senders.is_empty() {
+        return None;
+    }
+    let terms: Vec<String> = senders.iter().map(|s| format!("from:\"{}\"", s)).collect();
+    Some(format!("maildir:\"{}\" AND ({})", folder, terms.join(" OR ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::Address;
+
+    #[test]
+    fn screens_first_time_senders() {
+        let address = |email: &str| Address { name: None, email: email.into() };
+        let message = |from: &str, to: &[&str]| Envelope {
+            from: vec![address(from)],
+            to: to.iter().map(|t| address(t)).collect(),
+            ..Envelope::default()
+        };
+        let sent = [message("me@example.com", &["Alice@Example.com"]), message("me@example.com", &["bob@example.com"])];
+        let mut known = recipients(&sent);
+        known.insert("carol@example.com".into());
+        let inbox = [
+            message("alice@example.com", &["me@example.com"]),
+            message("Stranger@Example.net", &["me@example.com"]),
+            message("carol@example.com", &["me@example.com"]),
+            message("stranger@example.net", &["me@example.com"]),
+        ];
+        let senders = first_time_senders(&inbox, &known);
+        assert_eq!(senders, ["stranger@example.net"]);
+        assert_eq!(
+            senders_query("/Inbox", &senders).as_deref(),
+            Some("maildir:\"/Inbox\" AND (from:\"stranger@example.net\")")
+        );
+        assert_eq!(senders_query("/Inbox", &[]), None);
+
+        let mut blocked = Vec::new();
+        assert!(block(&mut blocked, "Stranger@Example.net"));
+        assert!(!block(&mut blocked, "stranger@example.net"));
+        assert!(is_blocked(&blocked, "STRANGER@example.net"));
+        assert!(unblock(&mut blocked, "stranger@example.net"));
+        assert!(blocked.is_empty());
+    }
+
+    #[test]
+    fn blocked_list_goes_through_state_crypt() {
+        let dir = std::env::temp_dir().join(format!("hutt-screener-{}", std::process::id()));
+        let path = dir.join("screener.toml");
+        save_blocked_to(&["spam@example.net".to_string()], &path);
+        assert_eq!(load_blocked_from(&path), ["spam@example.net"]);
+
+        let cipher = "-----BEGIN PGP MESSAGE-----\n";
+        std::fs::write(&path, cipher).unwrap();
+        save_blocked_to(&[], &path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), cipher);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,10 +1,12 @@
 use anyhow::{Context, Result};
-use lettre::message::{Mailbox, MessageBuilder};
+use lettre::message::header::{ContentType, HeaderName, HeaderValue};
+use lettre::message::{Attachment, Mailbox, MessageBuilder, MultiPart, SinglePart};
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::config::SmtpConfig;
+use crate::envelope::Priority;
 
 /// Generate a unique Message-ID for outgoing messages.
 fn generate_message_id(from_domain: &str) -> String {
@@ -100,9 +102,39 @@ fn get_password(config: &SmtpConfig) -> Result<String> {
     }
 }
 
+/// A message the SMTP server accepted.
+pub struct Sent {
+    /// The formatted message bytes (for saving to the Sent folder).
+    pub formatted: Vec<u8>,
+    /// Envelope recipients: To, Cc and Bcc.
+    pub recipients: Vec<String>,
+    /// The server's reply, e.g. "250 2.0.0 Ok: queued as 4Bx9".
+    pub response: String,
+    /// The server that took the message, as host:port.
+    pub server: String,
+    /// Servers tried first that couldn't be reached.
+    pub unreachable: Vec<String>,
+}
+
+impl Sent {
+    /// Status line for the send, naming the fallback server if one was used.
+    pub fn status(&self) -> String {
+        if self.unreachable.is_empty() {
+            "Message sent".to_string()
+        } else {
+            format!(
+                "Message sent via {} ({} unreachable)",
+                self.server,
+                self.unreachable.join(", ")
+            )
+        }
+    }
+}
+
 /// SMTP sender wrapping a lettre async transport.
 pub struct SmtpSender {
     transport: AsyncSmtpTransport<Tokio1Executor>,
+    server: String,
 }
 
 impl SmtpSender {
@@ -137,23 +169,53 @@ impl SmtpSender {
             }
         };
 
-        Ok(Self { transport })
+        Ok(Self {
+            transport,
+            server: config.address(),
+        })
+    }
+
+    /// Connect, greet and log in, then hang up: a check that the server is
+    /// reachable before it is handed the message.  Times out after
+    /// [`CONNECT_TIMEOUT`].
+    async fn connect(&self) -> Result<()> {
+        let connected = tokio::time::timeout(CONNECT_TIMEOUT, self.transport.test_connection())
+            .await
+            .with_context(|| {
+                format!(
+                    "connecting to {} timed out after {}s",
+                    self.server,
+                    CONNECT_TIMEOUT.as_secs()
+                )
+            })?
+            .with_context(|| format!("cannot connect to {}", self.server))?;
+        if !connected {
+            anyhow::bail!("{} accepted the connection but stopped answering", self.server);
+        }
+        Ok(())
     }
 
     /// Parse a raw composed message string, build a proper RFC 2822 message,
-    /// send it via SMTP, and return the formatted message bytes (for saving
-    /// to the Sent folder).
-    pub async fn send(&self, raw_message: &str) -> Result<Vec<u8>> {
+    /// send it via SMTP, and return what was sent and the server's reply.
+    pub async fn send(&self, raw_message: &str) -> Result<Sent> {
         let message = build_message(raw_message)?;
 
         let formatted = message.formatted();
+        let recipients = message.envelope().to().iter().map(|a| a.to_string()).collect();
 
-        self.transport
+        let response = self
+            .transport
             .send(message)
             .await
             .context("SMTP send failed")?;
 
-        Ok(formatted)
+        Ok(Sent {
+            formatted,
+            recipients,
+            response: format!("{} {}", response.code(), response.message().collect::<Vec<_>>().join(" ")),
+            server: self.server.clone(),
+            unreachable: Vec::new(),
+        })
     }
 }
 
@@ -164,6 +226,7 @@ fn build_message(raw_message: &str) -> Result<Message> {
 
     let mut builder = MessageBuilder::new();
     let mut from_domain = "localhost".to_string();
+    let mut attachments = Vec::new();
 
     for (name, value) in &parsed.headers {
         match name.to_lowercase().as_str() {
@@ -200,6 +263,28 @@ fn build_message(raw_message: &str) -> Result<Message> {
                     }
                 }
             }
+            "bcc" => {
+                for addr in value.split(',') {
+                    let addr = addr.trim();
+                    if !addr.is_empty() {
+                        let mailbox: Mailbox = addr
+                            .parse()
+                            .with_context(|| format!("invalid Bcc address: {}", addr))?;
+                        builder = builder.bcc(mailbox);
+                    }
+                }
+            }
+            "attach" => {
+                attachments.push(attachment_part(value)?);
+            }
+            "priority" => {
+                let priority = Priority::from_header(value).with_context(|| {
+                    format!("invalid Priority: {} (expected high, normal or low)", value)
+                })?;
+                for header in priority_headers(priority) {
+                    builder = builder.raw_header(header);
+                }
+            }
             "subject" => {
                 builder = builder.subject(value.as_str());
             }
@@ -222,29 +307,174 @@ fn build_message(raw_message: &str) -> Result<Message> {
     let msg_id = generate_message_id(&from_domain);
     builder = builder.message_id(Some(msg_id));
 
+    if attachments.is_empty() {
+        return builder
+            .body(parsed.body)
+            .context("failed to build email message");
+    }
+    let mut multipart = MultiPart::mixed().singlepart(SinglePart::plain(parsed.body));
+    for part in attachments {
+        multipart = multipart.singlepart(part);
+    }
     builder
-        .body(parsed.body)
+        .multipart(multipart)
         .context("failed to build email message")
 }
 
-/// Send a message via SMTP and return the formatted message bytes
-/// (for saving to Sent folder).  Times out after 60 seconds.
-pub async fn send_message(raw_message: &str, config: &SmtpConfig) -> Result<Vec<u8>> {
-    use std::time::Duration;
-    let timeout = Duration::from_secs(60);
-    let fut = async {
-        let sender = SmtpSender::new(config).await?;
-        sender.send(raw_message).await
+/// The `X-Priority` and `Importance` headers for a `Priority:` pseudo-header.
+/// Normal priority is the default, so it adds none.
+fn priority_headers(priority: Priority) -> Vec<HeaderValue> {
+    let (x_priority, importance) = match priority {
+        Priority::High => ("1 (Highest)", "high"),
+        Priority::Low => ("5 (Lowest)", "low"),
+        Priority::Normal => return Vec::new(),
     };
-    tokio::time::timeout(timeout, fut)
-        .await
-        .map_err(|_| anyhow::anyhow!("SMTP send timed out after 60s"))?
+    vec![
+        HeaderValue::new(HeaderName::new_from_ascii_str("X-Priority"), x_priority.to_string()),
+        HeaderValue::new(HeaderName::new_from_ascii_str("Importance"), importance.to_string()),
+    ]
+}
+
+/// Read the file named in an `Attach:` header into an attachment part.
+fn attachment_part(path: &str) -> Result<SinglePart> {
+    let path = crate::config::expand_tilde(path);
+    let data = std::fs::read(&path).with_context(|| format!("cannot attach {}", path))?;
+    let filename = std::path::Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "attachment".to_string());
+    let content_type = ContentType::parse(guess_mime_type(&filename))
+        .unwrap_or_else(|_| ContentType::parse("application/octet-stream").expect("valid MIME type"));
+    Ok(Attachment::new(filename).body(data, content_type))
+}
+
+/// MIME type for a file name's extension, for common attachment types.
+fn guess_mime_type(filename: &str) -> &'static str {
+    let ext = filename.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("pdf") => "application/pdf",
+        Some("txt") | Some("md") => "text/plain",
+        Some("html") | Some("htm") => "text/html",
+        Some("csv") => "text/csv",
+        Some("ics") => "text/calendar",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("zip") => "application/zip",
+        Some("json") => "application/json",
+        Some("doc") => "application/msword",
+        Some("docx") => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Some("xlsx") => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        _ => "application/octet-stream",
+    }
+}
+
+/// How long a server has to accept a connection, greet us and log us in.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the send itself may take once the server has answered.
+const SEND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Send a message via SMTP and return what was sent (for saving to the
+/// Sent folder and the delivery record).  If a server can't be reached,
+/// the account's fallback servers are tried in turn.  Once a server has
+/// answered, any failure is final: the message may already have been
+/// handed over, and sending it again elsewhere could deliver it twice.
+pub async fn send_message(raw_message: &str, config: &SmtpConfig) -> Result<Sent> {
+    let servers = config.servers();
+    let mut unreachable = Vec::new();
+    for (i, server) in servers.iter().enumerate() {
+        let sender = SmtpSender::new(server).await?;
+        if let Err(e) = sender.connect().await {
+            unreachable.push(server.address());
+            if i + 1 < servers.len() && is_connection_error(&e) {
+                continue;
+            }
+            if unreachable.len() > 1 {
+                anyhow::bail!("{} (tried {})", e, unreachable.join(", "));
+            }
+            return Err(e);
+        }
+        let mut sent = tokio::time::timeout(SEND_TIMEOUT, sender.send(raw_message))
+            .await
+            .with_context(|| {
+                format!(
+                    "SMTP send to {} timed out after {}s; the message may have been delivered",
+                    server.address(),
+                    SEND_TIMEOUT.as_secs()
+                )
+            })??;
+        sent.unreachable = unreachable;
+        return Ok(sent);
+    }
+    anyhow::bail!("no SMTP server configured")
+}
+
+/// Whether connecting to a server failed before it could answer: no
+/// connection, a network or TLS error, or a timeout.  Worth trying another
+/// server.  Only meaningful for errors from [`SmtpSender::connect`].
+fn is_connection_error(e: &anyhow::Error) -> bool {
+    if e.is::<tokio::time::error::Elapsed>() {
+        return true;
+    }
+    match e.downcast_ref::<lettre::transport::smtp::Error>() {
+        Some(smtp) => {
+            !(smtp.is_response() || smtp.is_client() || smtp.is_transient() || smtp.is_permanent())
+        }
+        None => false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_build_message_with_bcc_and_attachment() {
+        let path = std::env::temp_dir().join(format!("hutt-attach-{}.pdf", std::process::id()));
+        std::fs::write(&path, b"%PDF-1.4 test").unwrap();
+        let input = format!(
+            "From: alice@example.com\n\
+             To: bob@example.com\n\
+             Bcc: carol@example.com\n\
+             Subject: Report\n\
+             Attach: {}\n\
+             \n\
+             Attached.",
+            path.display()
+        );
+        let message = build_message(&input).unwrap();
+        let recipients: Vec<String> =
+            message.envelope().to().iter().map(|a| a.to_string()).collect();
+        assert_eq!(recipients, vec!["bob@example.com", "carol@example.com"]);
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(!formatted.contains("Bcc:"));
+        assert!(formatted.contains("multipart/mixed"));
+        assert!(formatted.contains("application/pdf"));
+        assert!(formatted.contains(&format!(
+            "filename=\"{}\"",
+            path.file_name().unwrap().to_string_lossy()
+        )));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(build_message(&input).is_err());
+    }
+
+    #[test]
+    fn test_build_message_priority() {
+        let input = "From: alice@example.com\nTo: bob@example.com\nPriority: urgent\nSubject: Down\n\nHelp";
+        let formatted = String::from_utf8(build_message(input).unwrap().formatted()).unwrap();
+        assert!(formatted.contains("X-Priority: 1 (Highest)\r\n"), "{}", formatted);
+        assert!(formatted.contains("Importance: high\r\n"));
+        assert!(!formatted.contains("Priority: urgent"));
+
+        let normal = input.replace("urgent", "normal");
+        let formatted = String::from_utf8(build_message(&normal).unwrap().formatted()).unwrap();
+        assert!(!formatted.contains("Priority"));
+        assert!(build_message(&input.replace("urgent", "soonish")).is_err());
+    }
+
     #[test]
     fn test_parse_composed_message_basic() {
         let input = "From: alice@example.com\n\
@@ -288,4 +518,93 @@ mod tests {
         assert_eq!(parsed.headers.len(), 2);
         assert_eq!(parsed.body, "");
     }
+
+    #[tokio::test]
+    async fn test_fallback_only_on_connection_errors() {
+        use crate::config::SmtpFallback;
+
+        // Ports nothing is listening on
+        let closed = || {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let (first, second) = (closed(), closed());
+        let message = "From: alice@example.com\nTo: bob@example.com\nSubject: Hi\n\nHello";
+        let mut smtp = SmtpConfig {
+            host: "127.0.0.1".into(),
+            port: first,
+            encryption: "none".into(),
+            password: Some("secret".into()),
+            fallback: vec![SmtpFallback {
+                port: Some(second),
+                ..SmtpFallback::default()
+            }],
+            ..SmtpConfig::default()
+        };
+        let err = send_message(message, &smtp).await.err().unwrap().to_string();
+        assert!(
+            err.contains(&format!("(tried 127.0.0.1:{}, 127.0.0.1:{})", first, second)),
+            "{}",
+            err
+        );
+
+        // A failure that isn't about reaching the server doesn't fall back
+        smtp.password = None;
+        let err = send_message(message, &smtp).await.err().unwrap().to_string();
+        assert!(err.starts_with("no password"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_no_fallback_once_data_starts() {
+        use crate::config::SmtpFallback;
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::TcpListener;
+
+        // A server that answers everything, then hangs up partway through
+        // the message body
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = server.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = server.accept().await {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                write.write_all(b"220 test ESMTP\r\n").await.unwrap();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let reply: &[u8] = match line.to_ascii_uppercase().get(..4) {
+                        Some("EHLO") => b"250-test\r\n250 AUTH PLAIN\r\n",
+                        Some("AUTH") => b"235 ok\r\n",
+                        Some("DATA") => b"354 go ahead\r\n",
+                        Some("QUIT") => b"221 bye\r\n",
+                        _ => b"250 ok\r\n",
+                    };
+                    let data = reply.starts_with(b"354");
+                    let _ = write.write_all(reply).await;
+                    if data {
+                        let _ = lines.next_line().await;
+                        break;
+                    }
+                }
+            }
+        });
+        let fallback = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let fallback_port = fallback.local_addr().unwrap().port();
+
+        let smtp = SmtpConfig {
+            host: "127.0.0.1".into(),
+            port,
+            encryption: "none".into(),
+            password: Some("secret".into()),
+            fallback: vec![SmtpFallback {
+                port: Some(fallback_port),
+                ..SmtpFallback::default()
+            }],
+            ..SmtpConfig::default()
+        };
+        let message = "From: alice@example.com\nTo: bob@example.com\nSubject: Hi\n\nHello";
+        let err = send_message(message, &smtp).await.err().unwrap().to_string();
+        assert!(!err.contains("tried"), "{}", err);
+        let accepted =
+            tokio::time::timeout(Duration::from_millis(100), fallback.accept()).await;
+        assert!(accepted.is_err(), "fell back after DATA");
+    }
 }
@@ -0,0 +1,207 @@
+//! UI session state saved on quit and restored on the next launch: the
+//! open view tabs with their folders, filters and selected messages, the
+//! recently used folders shown in the pickers, each folder's sort and
+//! filters, and how far long messages were read.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::keymap::SortField;
+
+/// Folder/query, filters and list position of one view tab.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewState {
+    pub folder: String,
+    pub filter_unread: bool,
+    pub filter_starred: bool,
+    pub filter_needs_reply: bool,
+    /// Message-id of the selected row, to find it again after reloading
+    pub selected_id: Option<String>,
+    pub selected: usize,
+    pub scroll_offset: usize,
+}
+
+/// Sort order and filters last used in a folder, put back when it is
+/// opened again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderPrefs {
+    pub sort_field: SortField,
+    pub sort_descending: bool,
+    pub filter_unread: bool,
+    pub filter_starred: bool,
+    pub filter_needs_reply: bool,
+}
+
+impl Default for FolderPrefs {
+    fn default() -> Self {
+        FolderPrefs {
+            sort_field: SortField::Date,
+            sort_descending: true,
+            filter_unread: false,
+            filter_starred: false,
+            filter_needs_reply: false,
+        }
+    }
+}
+
+/// Most read positions kept; the least recently read go first.
+const READ_POSITION_LIMIT: usize = 500;
+
+/// How far each message (or thread) was scrolled, by message-id, most
+/// recently read first. Unscrolled messages aren't kept.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReadPositions(Vec<(String, u16)>);
+
+impl ReadPositions {
+    pub fn get(&self, message_id: &str) -> u16 {
+        self.0.iter().find(|(id, _)| id == message_id).map_or(0, |(_, offset)| *offset)
+    }
+
+    pub fn set(&mut self, message_id: &str, offset: u16) {
+        self.0.retain(|(id, _)| id != message_id);
+        if offset > 0 {
+            self.0.insert(0, (message_id.to_string(), offset));
+            self.0.truncate(READ_POSITION_LIMIT);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// An account's saved session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub views: Vec<ViewState>,
+    pub active_view: usize,
+    /// Recently visited or moved-to folders, most recent first
+    pub recent_folders: Vec<String>,
+    /// Folders whose sort or filters differ from the defaults; only saved
+    /// with `persist_folder_settings`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub folder_prefs: HashMap<String, FolderPrefs>,
+    /// Preview scroll offsets of messages; only saved with
+    /// `persist_read_positions`
+    #[serde(skip_serializing_if = "ReadPositions::is_empty")]
+    pub read_positions: ReadPositions,
+    /// Thread view scroll offsets, by the thread's first message-id
+    #[serde(skip_serializing_if = "ReadPositions::is_empty")]
+    pub thread_positions: ReadPositions,
+}
+
+/// Return the path to the saved session for a given account name.
+///
+/// Per-account files: `session.<account_name>.json`, or plain
+/// `session.json` when `account_name` is empty.
+pub fn session_path(account_name: &str) -> PathBuf {
+    let dir = crate::paths::state_dir();
+    if account_name.is_empty() {
+        dir.join("session.json")
+    } else {
+        dir.join(format!("session.{}.json", account_name))
+    }
+}
+
+/// Load an account's session. `None` if there is no usable saved session.
+pub fn load_session(account_name: &str) -> Option<Session> {
+    load_session_from(&session_path(account_name))
+}
+
+fn load_session_from(path: &Path) -> Option<Session> {
+    let contents = crate::state_crypt::read_to_string(path).ok()?;
+    let session: Session = serde_json::from_str(&contents).ok()?;
+    if session.views.is_empty() || session.active_view >= session.views.len() {
+        return None;
+    }
+    Some(session)
+}
+
+/// Save an account's session. Creates parent directories if needed.
+pub fn save_session(session: &Session, account_name: &str) {
+    save_session_to(session, &session_path(account_name));
+}
+
+fn save_session_to(session: &Session, path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string_pretty(session) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("hutt-session-{}", std::process::id()));
+        let path = dir.join("session.work.json");
+
+        let session = Session {
+            views: vec![
+                ViewState {
+                    folder: "/Inbox".to_string(),
+                    selected_id: Some("abc@example.com".to_string()),
+                    selected: 4,
+                    ..ViewState::default()
+                },
+                ViewState {
+                    folder: "from:alice".to_string(),
+                    filter_unread: true,
+                    ..ViewState::default()
+                },
+            ],
+            active_view: 1,
+            recent_folders: vec!["/Projects/Hutt".to_string()],
+            folder_prefs: HashMap::from([(
+                "/Sent".to_string(),
+                FolderPrefs {
+                    sort_field: SortField::To,
+                    filter_starred: true,
+                    ..FolderPrefs::default()
+                },
+            )]),
+            read_positions: ReadPositions(vec![("digest@lists.example.org".to_string(), 420)]),
+            thread_positions: ReadPositions::default(),
+        };
+        save_session_to(&session, &path);
+        assert_eq!(load_session_from(&path), Some(session));
+        assert_eq!(load_session_from(&dir.join("missing.json")), None);
+        let json = crate::state_crypt::read_to_string(&path).unwrap();
+        assert!(json.contains("\"sort_field\": \"to\""));
+
+        assert!(!json.contains("thread_positions"));
+
+        // A session pointing past its views is discarded
+        save_session_to(&Session::default(), &path);
+        assert_eq!(load_session_from(&path), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn read_positions_most_recent_first() {
+        let mut positions = ReadPositions::default();
+        positions.set("a@x", 10);
+        positions.set("b@x", 20);
+        positions.set("a@x", 30);
+        assert_eq!(positions.0, [("a@x".to_string(), 30), ("b@x".to_string(), 20)]);
+        assert_eq!(positions.get("b@x"), 20);
+        assert_eq!(positions.get("c@x"), 0);
+        positions.set("b@x", 0);
+        assert_eq!(positions.get("b@x"), 0);
+        for i in 0..READ_POSITION_LIMIT + 5 {
+            positions.set(&format!("{}@x", i), 1);
+        }
+        assert_eq!(positions.0.len(), READ_POSITION_LIMIT);
+        assert_eq!(positions.get("a@x"), 0);
+    }
+}
@@ -1,10 +1,79 @@
+use chrono::{Datelike, Duration, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmartFolder {
     pub name: String,
     pub query: String,
+    /// Keep this folder's match count current in the background, re-counting
+    /// every `refresh` minutes (decimals accepted). Counts show as badges in
+    /// the folder picker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh: Option<f64>,
+    /// Built-in folder from [`BUILTIN_SMART_FOLDERS`]: read-only, never saved.
+    #[serde(skip)]
+    pub builtin: bool,
+    /// Shared folder from the config's `[[smart_folders]]`, shown for every
+    /// account. Not saved to the account's file unless edited there.
+    #[serde(skip)]
+    pub shared: bool,
+}
+
+/// Built-in virtual folders, available without the user creating them.
+/// Enabled by name via the `builtin_smart_folders` config option.
+pub const BUILTIN_SMART_FOLDERS: &[(&str, &str)] = &[
+    ("Starred", "flag:flagged AND NOT flag:trashed"),
+    ("Unread", "flag:unread AND NOT flag:trashed"),
+    ("Today", "date:today..now"),
+    ("Large", "size:5M.. AND NOT flag:trashed"),
+    // Query filled in from the follow-up reminders (see crate::followups)
+    ("Waiting", crate::followups::NO_MATCH_QUERY),
+    // Query filled in from the first-time senders (see crate::screener)
+    ("Screened", crate::followups::NO_MATCH_QUERY),
+];
+
+/// Smart folders for an account: its own, then the shared ones it doesn't
+/// override by name, then the enabled built-ins.
+pub fn load_for_account(
+    account_name: &str,
+    shared: &[SmartFolder],
+    builtins: &[String],
+) -> Vec<SmartFolder> {
+    with_builtins(with_shared(load_smart_folders(account_name), shared), builtins)
+}
+
+/// Append the shared folders to an account's smart folders. The account's
+/// own folder with the same name takes precedence over the shared one.
+pub fn with_shared(mut folders: Vec<SmartFolder>, shared: &[SmartFolder]) -> Vec<SmartFolder> {
+    for sf in shared {
+        if !folders.iter().any(|f| f.name == sf.name) {
+            folders.push(SmartFolder {
+                shared: true,
+                ..sf.clone()
+            });
+        }
+    }
+    folders
+}
+
+/// Append the enabled built-in folders to the user's smart folders.
+/// A user folder with the same name takes precedence over the built-in one.
+pub fn with_builtins(mut folders: Vec<SmartFolder>, enabled: &[String]) -> Vec<SmartFolder> {
+    for (name, query) in BUILTIN_SMART_FOLDERS {
+        let wanted = enabled.iter().any(|e| e.eq_ignore_ascii_case(name));
+        if wanted && !folders.iter().any(|f| f.name == *name) {
+            folders.push(SmartFolder {
+                name: name.to_string(),
+                query: query.to_string(),
+                refresh: None,
+                builtin: true,
+                shared: false,
+            });
+        }
+    }
+    folders
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -13,17 +82,6 @@ struct SmartFoldersFile {
     folders: Vec<SmartFolder>,
 }
 
-/// Return the config directory for hutt.
-fn config_dir() -> PathBuf {
-    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
-        PathBuf::from(xdg).join("hutt")
-    } else if let Ok(home) = std::env::var("HOME") {
-        PathBuf::from(home).join(".config").join("hutt")
-    } else {
-        PathBuf::from(".")
-    }
-}
-
 /// Return the path to smart_folders file for a given account name.
 ///
 /// Per-account files: `smart_folders.<account_name>.toml`
@@ -32,7 +90,7 @@ fn config_dir() -> PathBuf {
 /// the plain file is used as fallback if the per-account one doesn't exist
 /// and the account is the first one.
 pub fn smart_folders_path(account_name: &str) -> PathBuf {
-    let dir = config_dir();
+    let dir = crate::paths::config_dir();
     if account_name.is_empty() {
         dir.join("smart_folders.toml")
     } else {
@@ -69,13 +127,79 @@ pub fn save_smart_folders(folders: &[SmartFolder], account_name: &str) {
         let _ = std::fs::create_dir_all(parent);
     }
     let file = SmartFoldersFile {
-        folders: folders.to_vec(),
+        folders: folders.iter().filter(|f| !f.builtin && !f.shared).cloned().collect(),
     };
     if let Ok(contents) = toml::to_string_pretty(&file) {
         let _ = std::fs::write(&path, contents);
     }
 }
 
+/// Group smart folders that are due for a count by their query, so folders
+/// sharing a query are counted with one mu search. Takes `(key, query)`
+/// pairs and returns `(query, keys)` in first-seen order.
+pub fn coalesce_counts(due: Vec<(String, String)>) -> Vec<(String, Vec<String>)> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (key, query) in due {
+        match index.get(&query) {
+            Some(&i) => groups[i].1.push(key),
+            None => {
+                index.insert(query.clone(), groups.len());
+                groups.push((query, vec![key]));
+            }
+        }
+    }
+    groups
+}
+
+/// Expand relative-date placeholders in a query, so saved searches like
+/// `date:{last7days}..now` stay current. Supported: `{today}`,
+/// `{yesterday}`, `{lastNdays}`, `{lastNweeks}`, `{thisweek}` (Monday),
+/// `{thismonth}` and `{thisyear}`. Dates are written as `YYYYMMDD`;
+/// unknown placeholders are left as-is.
+pub fn expand_date_macros(query: &str, today: NaiveDate) -> String {
+    let mut result = String::with_capacity(query.len());
+    let mut rest = query;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        match date_macro(&after[..end], today) {
+            Some(date) => result.push_str(&date.format("%Y%m%d").to_string()),
+            None => result.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The date a single placeholder name stands for.
+fn date_macro(name: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match name {
+        "today" => return Some(today),
+        "yesterday" => return today.pred_opt(),
+        "thisweek" => {
+            let days = today.weekday().num_days_from_monday();
+            return today.checked_sub_signed(Duration::days(days.into()));
+        }
+        "thismonth" => return today.with_day(1),
+        "thisyear" => return NaiveDate::from_ymd_opt(today.year(), 1, 1),
+        _ => {}
+    }
+    let n = name.strip_prefix("last")?;
+    let (n, unit_days) = if let Some(n) = n.strip_suffix("days") {
+        (n, 1)
+    } else {
+        (n.strip_suffix("weeks")?, 7)
+    };
+    let n: i64 = n.parse().ok()?;
+    today.checked_sub_signed(Duration::days(n * unit_days))
+}
+
 /// Known mu field prefixes for search throttling.
 const FIELD_PREFIXES: &[&str] = &[
     "from:", "to:", "cc:", "bcc:", "subject:", "body:", "date:", "flag:", "prio:",
@@ -157,10 +281,16 @@ mod tests {
             SmartFolder {
                 name: "Unread from Alice".into(),
                 query: "from:alice flag:unread".into(),
+                refresh: None,
+                builtin: false,
+                shared: false,
             },
             SmartFolder {
                 name: "Recent attachments".into(),
                 query: "mime:application/* date:1w..".into(),
+                refresh: None,
+                builtin: false,
+                shared: false,
             },
         ];
 
@@ -180,4 +310,102 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn builtins_respect_config_and_user_folders() {
+        let user = vec![SmartFolder {
+            name: "Today".into(),
+            query: "date:today.. AND maildir:/Inbox".into(),
+            refresh: None,
+            builtin: false,
+            shared: false,
+        }];
+        let enabled = vec!["starred".to_string(), "Today".to_string()];
+        let folders = with_builtins(user, &enabled);
+
+        let names: Vec<&str> = folders.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Today", "Starred"]);
+        // The user's own @Today shadows the built-in one
+        assert!(!folders[0].builtin);
+        assert!(folders[1].builtin);
+
+        assert!(with_builtins(Vec::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn shared_folders_yield_to_account_folders() {
+        let folder = |name: &str, query: &str| SmartFolder {
+            name: name.into(),
+            query: query.into(),
+            refresh: None,
+            builtin: false,
+            shared: false,
+        };
+        let account = vec![folder("Invoices", "subject:invoice AND from:billing@work.com")];
+        let shared = vec![
+            folder("Invoices", "subject:invoice"),
+            folder("Unread lists", "flag:unread AND NOT list:"),
+        ];
+        let folders = with_shared(account, &shared);
+
+        let names: Vec<&str> = folders.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Invoices", "Unread lists"]);
+        // The account's own @Invoices overrides the shared one
+        assert_eq!(folders[0].query, "subject:invoice AND from:billing@work.com");
+        assert!(!folders[0].shared);
+        assert!(folders[1].shared);
+    }
+
+    #[test]
+    fn date_macros_expand_relative_to_today() {
+        // A Friday
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        assert_eq!(expand_date_macros("date:{today}..now", today), "date:20261016..now");
+        assert_eq!(
+            expand_date_macros("date:{last7days}..{yesterday}", today),
+            "date:20261009..20261015"
+        );
+        assert_eq!(expand_date_macros("date:{last2weeks}..", today), "date:20261002..");
+        assert_eq!(expand_date_macros("date:{thisweek}..", today), "date:20261012..");
+        assert_eq!(expand_date_macros("date:{thismonth}..", today), "date:20261001..");
+        assert_eq!(expand_date_macros("date:{thisyear}..", today), "date:20260101..");
+        // Unknown or unterminated placeholders are left alone
+        assert_eq!(expand_date_macros("subject:{draft} {today", today), "subject:{draft} {today");
+        assert_eq!(expand_date_macros("flag:unread", today), "flag:unread");
+    }
+
+    #[test]
+    fn refresh_interval_parses_and_counts_coalesce() {
+        let file: SmartFoldersFile = toml::from_str(
+            r#"
+            [[folders]]
+            name = "Unread"
+            query = "flag:unread"
+            refresh = 2.5
+
+            [[folders]]
+            name = "Invoices"
+            query = "subject:invoice"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(file.folders[0].refresh, Some(2.5));
+        assert_eq!(file.folders[1].refresh, None);
+        // No refresh key is written for folders without one
+        let saved = toml::to_string_pretty(&file).unwrap();
+        assert_eq!(saved.matches("refresh").count(), 1);
+
+        let due = vec![
+            ("@Unread".to_string(), "flag:unread".to_string()),
+            ("@Invoices".to_string(), "subject:invoice".to_string()),
+            ("@New".to_string(), "flag:unread".to_string()),
+        ];
+        assert_eq!(
+            coalesce_counts(due),
+            vec![
+                ("flag:unread".to_string(), vec!["@Unread".to_string(), "@New".to_string()]),
+                ("subject:invoice".to_string(), vec!["@Invoices".to_string()]),
+            ]
+        );
+    }
 }
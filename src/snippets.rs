@@ -0,0 +1,214 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::mime_render;
+
+/// Messages read before the extractor pauses, so a full folder load
+/// doesn't saturate the disk.
+const BATCH_SIZE: usize = 16;
+
+/// Pause between batches.
+const BATCH_PAUSE: Duration = Duration::from_millis(50);
+
+/// Most snippets kept per account; the least recently extracted go first.
+const SNIPPET_LIMIT: usize = 5000;
+
+/// A message whose body snippet should be extracted.
+pub struct SnippetRequest {
+    pub message_id: String,
+    pub path: PathBuf,
+}
+
+/// Body snippets by message-id, remembering the order they were extracted
+/// in so the oldest can be dropped past [`SNIPPET_LIMIT`].
+#[derive(Debug, Default, PartialEq)]
+pub struct SnippetCache {
+    snippets: HashMap<String, String>,
+    /// Message-ids, oldest first.
+    order: VecDeque<String>,
+}
+
+impl SnippetCache {
+    pub fn get(&self, message_id: &str) -> Option<&String> {
+        self.snippets.get(message_id)
+    }
+
+    pub fn contains_key(&self, message_id: &str) -> bool {
+        self.snippets.contains_key(message_id)
+    }
+
+    pub fn insert(&mut self, message_id: String, snippet: String) {
+        if self.snippets.insert(message_id.clone(), snippet).is_some() {
+            self.order.retain(|id| *id != message_id);
+        }
+        self.order.push_back(message_id);
+        while self.order.len() > SNIPPET_LIMIT {
+            if let Some(oldest) = self.order.pop_front() {
+                self.snippets.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, message_id: &str) {
+        if self.snippets.remove(message_id).is_some() {
+            self.order.retain(|id| id != message_id);
+        }
+    }
+
+    /// The snippets, for the list widgets.
+    pub fn as_map(&self) -> &HashMap<String, String> {
+        &self.snippets
+    }
+}
+
+/// The cache file: `[message-id, snippet]` pairs, oldest first. Caches
+/// written before the limit are a plain map and load in any order.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SnippetsFile {
+    Ordered(Vec<(String, String)>),
+    Map(HashMap<String, String>),
+}
+
+/// Return the path to the snippet cache for a given account name.
+///
+/// Per-account files: `snippets.<account_name>.json`, or plain
+/// `snippets.json` when `account_name` is empty.
+pub fn snippets_path(account_name: &str) -> PathBuf {
+    let dir = crate::paths::cache_dir();
+    if account_name.is_empty() {
+        dir.join("snippets.json")
+    } else {
+        dir.join(format!("snippets.{}.json", account_name))
+    }
+}
+
+/// Load cached snippets for an account.
+/// A missing or unreadable cache yields an empty cache.
+pub fn load_snippets(account_name: &str) -> SnippetCache {
+    load_snippets_from(&snippets_path(account_name))
+}
+
+fn load_snippets_from(path: &Path) -> SnippetCache {
+    let pairs = match crate::state_crypt::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+    {
+        Some(SnippetsFile::Ordered(pairs)) => pairs,
+        Some(SnippetsFile::Map(map)) => map.into_iter().collect(),
+        None => Vec::new(),
+    };
+    let mut cache = SnippetCache::default();
+    for (message_id, snippet) in pairs {
+        cache.insert(message_id, snippet);
+    }
+    cache
+}
+
+/// Save snippets for an account. Creates parent directories if needed.
+pub fn save_snippets(snippets: &SnippetCache, account_name: &str) {
+    save_snippets_to(snippets, &snippets_path(account_name));
+}
+
+fn save_snippets_to(snippets: &SnippetCache, path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let pairs = snippets
+        .order
+        .iter()
+        .filter_map(|id| Some((id.clone(), snippets.snippets.get(id)?.clone())))
+        .collect();
+    if let Ok(contents) = serde_json::to_string(&SnippetsFile::Ordered(pairs)) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+/// Start the background snippet extractor.
+///
+/// Requests are processed in order; each result is sent back as
+/// `(message_id, snippet)`. Reads happen on the blocking pool and the
+/// task pauses every [`BATCH_SIZE`] messages.
+pub fn spawn_extractor(
+    max_chars: usize,
+) -> (UnboundedSender<SnippetRequest>, UnboundedReceiver<(String, String)>) {
+    let (req_tx, mut req_rx) = unbounded_channel::<SnippetRequest>();
+    let (res_tx, res_rx) = unbounded_channel();
+    tokio::spawn(async move {
+        let mut processed = 0usize;
+        while let Some(req) = req_rx.recv().await {
+            let path = req.path;
+            let snippet = tokio::task::spawn_blocking(move || {
+                mime_render::snippet_for_path(&path, max_chars)
+            })
+            .await
+            .unwrap_or_default();
+            if res_tx.send((req.message_id, snippet)).is_err() {
+                break;
+            }
+            processed += 1;
+            if processed.is_multiple_of(BATCH_SIZE) {
+                tokio::time::sleep(BATCH_PAUSE).await;
+            }
+        }
+    });
+    (req_tx, res_rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("hutt-snippets-{}", std::process::id()));
+        let path = dir.join("snippets.work.json");
+
+        let mut cache = SnippetCache::default();
+        cache.insert("abc@example.com".to_string(), "Hello there".to_string());
+        cache.insert("def@example.com".to_string(), "Bye".to_string());
+        save_snippets_to(&cache, &path);
+        assert_eq!(load_snippets_from(&path), cache);
+        assert_eq!(load_snippets_from(&dir.join("missing.json")), SnippetCache::default());
+        assert!(snippets_path("work").ends_with("hutt/snippets.work.json"));
+
+        // Caches saved as a plain map still load
+        std::fs::write(&path, r#"{"abc@example.com":"Hello there"}"#).unwrap();
+        assert_eq!(load_snippets_from(&path).get("abc@example.com").unwrap(), "Hello there");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn oldest_snippets_are_dropped_past_the_limit() {
+        let mut cache = SnippetCache::default();
+        for i in 0..SNIPPET_LIMIT + 5 {
+            cache.insert(format!("{}@x", i), "snippet".to_string());
+        }
+        assert_eq!(cache.as_map().len(), SNIPPET_LIMIT);
+        assert!(!cache.contains_key("4@x"));
+        assert!(cache.contains_key("5@x"));
+        cache.remove("5@x");
+        assert_eq!(cache.order.len(), SNIPPET_LIMIT - 1);
+    }
+
+    #[tokio::test]
+    async fn extractor_reads_bodies() {
+        let dir = std::env::temp_dir().join(format!("hutt-extract-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("msg");
+        std::fs::write(&path, "Subject: hi\r\n\r\nFirst line of the body\r\n").unwrap();
+
+        let (tx, mut rx) = spawn_extractor(100);
+        tx.send(SnippetRequest { message_id: "m1".into(), path }).unwrap();
+        let (id, snippet) = rx.recv().await.unwrap();
+        assert_eq!(id, "m1");
+        assert_eq!(snippet, "First line of the body");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -13,18 +13,8 @@ struct SplitsFile {
     splits: Vec<Split>,
 }
 
-fn config_dir() -> PathBuf {
-    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
-        PathBuf::from(xdg).join("hutt")
-    } else if let Ok(home) = std::env::var("HOME") {
-        PathBuf::from(home).join(".config").join("hutt")
-    } else {
-        PathBuf::from(".")
-    }
-}
-
 pub fn splits_path(account_name: &str) -> PathBuf {
-    let dir = config_dir();
+    let dir = crate::paths::config_dir();
     if account_name.is_empty() {
         dir.join("splits.toml")
     } else {
@@ -0,0 +1,332 @@
+//! Export and import of hutt's per-account config and state files (smart
+//! folders, splits, contacts, blocked senders, reply templates, follow-up
+//! reminders, the operations log and saved sessions) as one JSON bundle,
+//! for moving between machines.
+//!
+//! Each bundled file records the format version of its kind, so a newer
+//! hutt can tell which files it may need to convert, and an older one can
+//! skip files it doesn't understand.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+/// Version of the bundle layout itself.
+const BUNDLE_VERSION: u32 = 1;
+
+/// Which base directory a kind of file lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Base {
+    Config,
+    State,
+}
+
+/// A kind of file carried in the bundle.
+struct Kind {
+    name: &'static str,
+    base: Base,
+    /// Matches file names relative to the base directory
+    matches: fn(&str) -> bool,
+    /// Current format version of this kind of file
+    format: u32,
+    /// Read and written through `state_crypt`, so encrypted at rest when
+    /// `[state_encryption]` is set
+    private: bool,
+}
+
+/// `<stem>.toml` / `<stem>.<account>.toml` style names.
+fn per_account(name: &str, stem: &str, ext: &str) -> bool {
+    name.strip_prefix(stem)
+        .and_then(|rest| rest.strip_suffix(ext))
+        .is_some_and(|mid| mid == "." || (mid.starts_with('.') && !mid[1..].contains('/')))
+}
+
+const KINDS: &[Kind] = &[
+    Kind {
+        name: "smart_folders",
+        base: Base::Config,
+        matches: |n| per_account(n, "smart_folders", "toml"),
+        format: 1,
+        private: false,
+    },
+    Kind {
+        name: "splits",
+        base: Base::Config,
+        matches: |n| per_account(n, "splits", "toml"),
+        format: 1,
+        private: false,
+    },
+    Kind {
+        name: "contacts",
+        base: Base::Config,
+        matches: |n| n == "contacts.toml",
+        format: 1,
+        private: true,
+    },
+    Kind {
+        name: "screener",
+        base: Base::Config,
+        matches: |n| n == "screener.toml",
+        format: 1,
+        private: true,
+    },
+    Kind {
+        name: "templates",
+        base: Base::Config,
+        matches: |n| n.strip_prefix("templates/").is_some_and(|t| !t.contains('/')),
+        format: 1,
+        private: false,
+    },
+    Kind {
+        name: "followups",
+        base: Base::State,
+        matches: |n| per_account(n, "followups", "json"),
+        format: 1,
+        private: true,
+    },
+    Kind {
+        name: "notes",
+        base: Base::State,
+        matches: |n| per_account(n, "notes", "json"),
+        format: 1,
+        private: true,
+    },
+    Kind {
+        name: "oplog",
+        base: Base::State,
+        matches: |n| per_account(n, "oplog", "jsonl"),
+        format: 1,
+        private: true,
+    },
+    Kind {
+        name: "session",
+        base: Base::State,
+        matches: |n| per_account(n, "session", "json"),
+        format: 1,
+        private: true,
+    },
+];
+
+/// A bundle of hutt files.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub hutt_bundle: u32,
+    pub created: DateTime<Local>,
+    pub files: Vec<BundleFile>,
+}
+
+/// One file in a bundle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleFile {
+    pub kind: String,
+    /// Format version of this kind of file when it was exported
+    pub format: u32,
+    /// Path relative to hutt's config or state directory
+    pub path: String,
+    pub contents: String,
+}
+
+/// What an import did.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub written: Vec<String>,
+    /// Files that already exist and were left alone (without --force)
+    pub kept: Vec<String>,
+    /// Files of an unknown kind or newer format, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Collect the bundle from hutt's config and state directories.
+pub fn export() -> Result<Bundle> {
+    export_from(&crate::paths::config_dir(), &crate::paths::state_dir())
+}
+
+fn export_from(config_dir: &Path, state_dir: &Path) -> Result<Bundle> {
+    let mut files = Vec::new();
+    for (base, dir) in [(Base::Config, config_dir), (Base::State, state_dir)] {
+        for name in relative_files(dir) {
+            let Some(kind) = KINDS.iter().find(|k| k.base == base && (k.matches)(&name)) else {
+                continue;
+            };
+            // Encrypted state files go into the bundle decrypted
+            let contents = crate::state_crypt::read_to_string(&dir.join(&name))
+                .with_context(|| format!("failed to read {}", dir.join(&name).display()))?;
+            files.push(BundleFile {
+                kind: kind.name.to_string(),
+                format: kind.format,
+                path: name,
+                contents,
+            });
+        }
+    }
+    Ok(Bundle {
+        hutt_bundle: BUNDLE_VERSION,
+        created: Local::now(),
+        files,
+    })
+}
+
+/// File names under `dir`, one level of subdirectory deep, sorted.
+fn relative_files(dir: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return names;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_file() {
+            names.push(name);
+        } else if path.is_dir() {
+            if let Ok(sub) = std::fs::read_dir(&path) {
+                for e in sub.flatten() {
+                    if e.path().is_file() {
+                        names.push(format!("{}/{}", name, e.file_name().to_string_lossy()));
+                    }
+                }
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+/// Write a bundle's files into hutt's config and state directories.
+/// Existing files are kept unless `force` is set.
+pub fn import(bundle: &Bundle, force: bool) -> Result<ImportSummary> {
+    import_to(bundle, &crate::paths::config_dir(), &crate::paths::state_dir(), force)
+}
+
+fn import_to(
+    bundle: &Bundle,
+    config_dir: &Path,
+    state_dir: &Path,
+    force: bool,
+) -> Result<ImportSummary> {
+    if bundle.hutt_bundle > BUNDLE_VERSION {
+        bail!(
+            "bundle version {} is newer than this hutt supports ({}); upgrade hutt first",
+            bundle.hutt_bundle,
+            BUNDLE_VERSION
+        );
+    }
+    let mut summary = ImportSummary::default();
+    for file in &bundle.files {
+        let Some(kind) = KINDS.iter().find(|k| k.name == file.kind) else {
+            summary.skipped.push((file.path.clone(), format!("unknown kind {:?}", file.kind)));
+            continue;
+        };
+        if file.format > kind.format {
+            summary.skipped.push((
+                file.path.clone(),
+                format!("{} format {} is newer than {}", kind.name, file.format, kind.format),
+            ));
+            continue;
+        }
+        if !(kind.matches)(&file.path) || !is_plain_relative(&file.path) {
+            summary.skipped.push((file.path.clone(), "unexpected file name".to_string()));
+            continue;
+        }
+        let dir = match kind.base {
+            Base::Config => config_dir,
+            Base::State => state_dir,
+        };
+        let dest = dir.join(&file.path);
+        if dest.exists() && !force {
+            summary.kept.push(file.path.clone());
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let written = if kind.private {
+            crate::state_crypt::write(&dest, &file.contents)
+        } else {
+            std::fs::write(&dest, &file.contents)
+        };
+        written.with_context(|| format!("failed to write {}", dest.display()))?;
+        summary.written.push(file.path.clone());
+    }
+    Ok(summary)
+}
+
+/// Whether a bundled path stays inside its base directory.
+fn is_plain_relative(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_import_roundtrip() {
+        let root = std::env::temp_dir().join(format!("hutt-bundle-{}", std::process::id()));
+        let (config, state) = (root.join("config"), root.join("state"));
+        std::fs::create_dir_all(config.join("templates")).unwrap();
+        std::fs::create_dir_all(&state).unwrap();
+        std::fs::write(config.join("smart_folders.Work.toml"), "[[folders]]\n").unwrap();
+        std::fs::write(config.join("templates/thanks"), "Thanks!\n").unwrap();
+        std::fs::write(config.join("config.toml"), "secret = 1\n").unwrap();
+        std::fs::write(state.join("followups.Work.json"), "[]").unwrap();
+        std::fs::write(state.join("session.json"), "{}").unwrap();
+
+        let bundle = export_from(&config, &state).unwrap();
+        let paths: Vec<&str> = bundle.files.iter().map(|f| f.path.as_str()).collect();
+        // config.toml holds credentials and is not bundled
+        assert_eq!(
+            paths,
+            vec!["smart_folders.Work.toml", "templates/thanks", "followups.Work.json", "session.json"]
+        );
+
+        let (config2, state2) = (root.join("config2"), root.join("state2"));
+        std::fs::create_dir_all(&state2).unwrap();
+        std::fs::write(state2.join("session.json"), "mine").unwrap();
+        let summary = import_to(&bundle, &config2, &state2, false).unwrap();
+        assert_eq!(summary.kept, vec!["session.json"]);
+        assert_eq!(summary.written.len(), 3);
+        assert_eq!(
+            std::fs::read_to_string(config2.join("templates/thanks")).unwrap(),
+            "Thanks!\n"
+        );
+        assert_eq!(std::fs::read_to_string(state2.join("session.json")).unwrap(), "mine");
+
+        import_to(&bundle, &config2, &state2, true).unwrap();
+        assert_eq!(std::fs::read_to_string(state2.join("session.json")).unwrap(), "{}");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn import_skips_unknown_newer_and_escaping_files() {
+        let root = std::env::temp_dir().join(format!("hutt-bundle-skip-{}", std::process::id()));
+        let file = |kind: &str, format: u32, path: &str| BundleFile {
+            kind: kind.to_string(),
+            format,
+            path: path.to_string(),
+            contents: String::new(),
+        };
+        let bundle = Bundle {
+            hutt_bundle: BUNDLE_VERSION,
+            created: Local::now(),
+            files: vec![
+                file("tags", 1, "tags.json"),
+                file("splits", 9, "splits.toml"),
+                file("templates", 1, "templates/../../evil"),
+                file("oplog", 1, "oplog.Work.jsonl"),
+            ],
+        };
+        let summary = import_to(&bundle, &root.join("c"), &root.join("s"), false).unwrap();
+        assert_eq!(summary.written, vec!["oplog.Work.jsonl"]);
+        assert_eq!(summary.skipped.len(), 3);
+
+        let newer = Bundle { hutt_bundle: BUNDLE_VERSION + 1, ..bundle };
+        assert!(import_to(&newer, &root.join("c"), &root.join("s"), false).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
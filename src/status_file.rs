@@ -0,0 +1,80 @@
+//! A small JSON status file for tmux status lines and desktop widgets to
+//! poll: unread counts per account, whether a sync is running, and when
+//! the last one finished. Written whenever those change, never encrypted.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub accounts: Vec<AccountStatus>,
+    /// A sync command is running.
+    pub syncing: bool,
+    /// When the last sync finished successfully.
+    pub last_sync: Option<DateTime<Local>>,
+    /// The inbox is paused; unread counts are left out until it resumes.
+    pub paused: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountStatus {
+    pub name: String,
+    /// Unread messages in the inbox; null until first counted.
+    pub unread: Option<u32>,
+}
+
+/// Where the status file goes unless `status_file` is configured.
+pub fn default_path() -> PathBuf {
+    crate::paths::state_dir().join("status.json")
+}
+
+/// Read a status file; missing or unreadable ones give `None`.
+pub fn read(path: &Path) -> Option<Status> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Write the status file. It is replaced in one step, so a poller never
+/// reads it half-written.
+pub fn write(path: &Path, status: &Status) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let mut json = serde_json::to_string_pretty(status).context("encoding status")?;
+    json.push('\n');
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&tmp, json).with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("hutt-status-{}", std::process::id()));
+        let path = dir.join("status.json");
+        assert_eq!(read(&path), None);
+
+        let status = Status {
+            accounts: vec![
+                AccountStatus { name: "work".into(), unread: Some(12) },
+                AccountStatus { name: "home".into(), unread: None },
+            ],
+            syncing: true,
+            last_sync: Some(Local::now()),
+            paused: false,
+        };
+        write(&path, &status).unwrap();
+        assert_eq!(read(&path), Some(status));
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"unread\": 12"));
+        assert!(json.contains("\"unread\": null"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,82 @@
+//! Reply templates ("canned responses") stored as plain-text files in
+//! `~/.config/hutt/templates/`.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::envelope::Address;
+
+/// Return the directory holding reply templates.
+pub fn templates_dir() -> PathBuf {
+    crate::paths::config_dir().join("templates")
+}
+
+/// Load the template called `name`: `templates/<name>`, or
+/// `templates/<name>.txt` if that doesn't exist.
+pub fn load_template(name: &str) -> Result<String> {
+    load_template_from(&templates_dir(), name)
+}
+
+fn load_template_from(dir: &Path, name: &str) -> Result<String> {
+    let exact = dir.join(name);
+    let path = if exact.is_file() {
+        exact
+    } else {
+        dir.join(format!("{}.txt", name))
+    };
+    std::fs::read_to_string(&path)
+        .with_context(|| format!("no reply template {:?} in {}", name, dir.display()))
+}
+
+/// Fill in placeholders for the person being replied to: `{name}` (display
+/// name, or email), `{first_name}` and `{email}`.
+pub fn expand_template(template: &str, to: Option<&Address>) -> String {
+    let email = to.map(|a| a.email.as_str()).unwrap_or("");
+    let name = to.map(|a| a.short_display()).unwrap_or_default();
+    let first_name = name
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .to_string();
+    template
+        .replace("{first_name}", &first_name)
+        .replace("{name}", &name)
+        .replace("{email}", email)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_with_and_without_extension() {
+        let dir = std::env::temp_dir().join(format!("hutt-templates-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("thanks"), "Thanks!\n").unwrap();
+        std::fs::write(dir.join("decline-meeting.txt"), "Sorry, can't make it.\n").unwrap();
+
+        assert_eq!(load_template_from(&dir, "thanks").unwrap(), "Thanks!\n");
+        assert_eq!(
+            load_template_from(&dir, "decline-meeting").unwrap(),
+            "Sorry, can't make it.\n"
+        );
+        assert!(load_template_from(&dir, "missing").is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn placeholders() {
+        let to = Address {
+            name: Some("Ada Lovelace".into()),
+            email: "ada@example.com".into(),
+        };
+        assert_eq!(
+            expand_template("Hi {first_name} ({name}, {email})", Some(&to)),
+            "Hi Ada (Ada Lovelace, ada@example.com)"
+        );
+        let bare = Address { name: None, email: "bob@example.com".into() };
+        assert_eq!(expand_template("Hi {first_name}", Some(&bare)), "Hi bob@example.com");
+        assert_eq!(expand_template("Hi {name}", None), "Hi ");
+    }
+}
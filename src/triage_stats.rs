@@ -0,0 +1,179 @@
+//! Triage metrics from the operations log: how many messages were
+//! archived, trashed, marked as spam or moved elsewhere each day, and how
+//! many replies and new messages went out. Undoing a triage action takes
+//! it back off the day's count. Shown by `hutt stats --triage` and in the
+//! end-of-day summary (`triage_summary`).
+
+use chrono::{Local, NaiveDate};
+use std::collections::BTreeMap;
+
+use crate::oplog::{OpKind, OpRecord};
+
+/// Days `hutt stats --triage` covers by default.
+pub const DEFAULT_DAYS: u32 = 7;
+
+/// One day's triage.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DayStats {
+    pub archived: u32,
+    pub trashed: u32,
+    pub spam: u32,
+    /// Moved to any other folder.
+    pub moved: u32,
+    pub replies: u32,
+    /// Sent messages that weren't replies.
+    pub sent: u32,
+}
+
+impl DayStats {
+    /// The counter a move or removal described by `detail` (as the log
+    /// gives it, "Archived: /Inbox → /Archive") adds to, if it's triage.
+    fn triage_count(&mut self, detail: &str) -> Option<&mut u32> {
+        let desc = detail.split_once(':').map_or(detail, |(desc, _)| desc);
+        match desc {
+            "Archived" => Some(&mut self.archived),
+            "Trashed" => Some(&mut self.trashed),
+            "Marked as spam" => Some(&mut self.spam),
+            _ if desc.starts_with("Blocked ") => Some(&mut self.spam),
+            "Moved to inbox" => None,
+            _ if desc.starts_with("Moved to ") => Some(&mut self.moved),
+            _ => None,
+        }
+    }
+
+    fn add(&mut self, record: &OpRecord) {
+        match record.op {
+            OpKind::Move | OpKind::Remove => {
+                if let Some(count) = self.triage_count(&record.detail) {
+                    *count += 1;
+                }
+            }
+            OpKind::Undo => {
+                if let Some(count) = self.triage_count(&record.detail) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+            OpKind::Send if is_reply(&record.subject) => self.replies += 1,
+            OpKind::Send => self.sent += 1,
+            OpKind::Flag => {}
+        }
+    }
+
+    /// "84 archived, 3 trashed, 12 replies": the counts that aren't zero.
+    pub fn summary(&self) -> String {
+        let parts: Vec<String> = [
+            (self.archived, "archived"),
+            (self.trashed, "trashed"),
+            (self.spam, "marked as spam"),
+            (self.moved, "moved"),
+            (self.replies, if self.replies == 1 { "reply" } else { "replies" }),
+            (self.sent, "sent"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+        if parts.is_empty() {
+            "nothing triaged".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+fn is_reply(subject: &str) -> bool {
+    subject.trim_start().get(..3).is_some_and(|p| p.eq_ignore_ascii_case("re:"))
+}
+
+/// Triage per day from `records` (in any order), for the days from
+/// `since` on.
+pub fn daily(records: &[OpRecord], since: NaiveDate) -> BTreeMap<NaiveDate, DayStats> {
+    let mut days: BTreeMap<NaiveDate, DayStats> = BTreeMap::new();
+    let mut records: Vec<&OpRecord> = records.iter().filter(|r| r.time.date_naive() >= since).collect();
+    // Undos only take back what came before them
+    records.sort_by_key(|r| r.time);
+    for record in records {
+        days.entry(record.time.date_naive()).or_default().add(record);
+    }
+    days
+}
+
+/// Today's triage from an account's log.
+pub fn today(account_name: &str) -> DayStats {
+    let today = Local::now().date_naive();
+    let records: Vec<OpRecord> = crate::oplog::recent(account_name, usize::MAX)
+        .into_iter()
+        .take_while(|r| r.time.date_naive() >= today)
+        .collect();
+    daily(&records, today).remove(&today).unwrap_or_default()
+}
+
+/// A table of `days` (every day from `since` to `until`, quiet ones
+/// too), with totals.
+pub fn render(days: &BTreeMap<NaiveDate, DayStats>, since: NaiveDate, until: NaiveDate) -> Vec<String> {
+    let row = |label: &str, d: &DayStats| {
+        format!(
+            "{:<12} {:>8} {:>8} {:>5} {:>6} {:>8} {:>5}",
+            label, d.archived, d.trashed, d.spam, d.moved, d.replies, d.sent
+        )
+    };
+    let mut lines = vec![format!(
+        "{:<12} {:>8} {:>8} {:>5} {:>6} {:>8} {:>5}",
+        "Day", "Archived", "Trashed", "Spam", "Moved", "Replies", "Sent"
+    )];
+    let mut total = DayStats::default();
+    for date in since.iter_days().take_while(|d| *d <= until) {
+        let day = days.get(&date).cloned().unwrap_or_default();
+        lines.push(row(&date.format("%a %-d %b").to_string(), &day));
+        total.archived += day.archived;
+        total.trashed += day.trashed;
+        total.spam += day.spam;
+        total.moved += day.moved;
+        total.replies += day.replies;
+        total.sent += day.sent;
+    }
+    lines.push(row("Total", &total));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn counts_triage_per_day() {
+        let monday = Local.with_ymd_and_hms(2026, 3, 9, 9, 0, 0).unwrap();
+        let at = |minutes: i64, op, subject: &str, detail: &str| OpRecord {
+            time: monday + Duration::minutes(minutes),
+            ..OpRecord::new(op, "id@x", subject, detail.into())
+        };
+        let records = [
+            at(0, OpKind::Move, "Hi", "Archived: /Inbox \u{2192} /Archive"),
+            at(1, OpKind::Remove, "Hi", "Archived: removed from /INBOX"),
+            at(2, OpKind::Move, "Hi", "Trashed: /Inbox \u{2192} /Trash"),
+            at(3, OpKind::Undo, "Hi", "Trashed: \u{2192} /Inbox"),
+            at(4, OpKind::Move, "Hi", "Blocked spam@x: /Inbox \u{2192} /Spam"),
+            at(5, OpKind::Move, "Hi", "Moved to /Lists: /Inbox \u{2192} /Lists"),
+            at(6, OpKind::Move, "Hi", "Restored: /Trash \u{2192} /Inbox"),
+            at(7, OpKind::Flag, "Hi", "starred: S \u{2192} SF"),
+            at(8, OpKind::Send, "RE: Hi", "Sent to bob@x"),
+            at(9, OpKind::Send, "Lunch?", "Sent to bob@x"),
+            at(60 * 24, OpKind::Move, "Hi", "Archived: /Inbox \u{2192} /Archive"),
+        ];
+        let days = daily(&records, monday.date_naive());
+        let expected = DayStats { archived: 2, trashed: 0, spam: 1, moved: 1, replies: 1, sent: 1 };
+        assert_eq!(days[&monday.date_naive()], expected);
+        assert_eq!(expected.summary(), "2 archived, 1 marked as spam, 1 moved, 1 reply, 1 sent");
+        assert_eq!(DayStats::default().summary(), "nothing triaged");
+
+        let tuesday = monday.date_naive().succ_opt().unwrap();
+        assert_eq!(days[&tuesday].archived, 1);
+        assert_eq!(daily(&records, tuesday).len(), 1);
+
+        let table = render(&days, monday.date_naive(), tuesday + Duration::days(1));
+        assert_eq!(table.len(), 5);
+        assert!(table[1].starts_with("Mon 9 Mar"));
+        assert!(table[4].starts_with("Total               3"));
+    }
+}
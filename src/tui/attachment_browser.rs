@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::folder_picker::centered_rect;
+use crate::mime_render::{format_size, AttachmentInfo};
+
+/// One attachment found in the current folder or search.
+pub struct AttachmentItem {
+    pub path: PathBuf,
+    pub sender: String,
+    pub date: String,
+    pub attachment: AttachmentInfo,
+}
+
+/// Popup listing every attachment across the current folder/search.
+pub struct AttachmentBrowser<'a> {
+    pub items: &'a [AttachmentItem],
+    pub selected: usize,
+}
+
+impl<'a> Widget for AttachmentBrowser<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width: u16 = area.width.saturating_sub(4).clamp(40, 110);
+        let popup_height: u16 = area.height.saturating_sub(4).clamp(8, 40);
+        let popup = centered_rect(popup_width, popup_height, area);
+
+        Clear.render(popup, buf);
+
+        let title = format!(" Attachments ({}) ", self.items.len());
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(title)
+            .title_style(
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+        block.render(popup, buf);
+
+        let inner = Rect::new(
+            popup.x + 1,
+            popup.y + 1,
+            popup.width.saturating_sub(2),
+            popup.height.saturating_sub(2),
+        );
+        if inner.width < 20 || inner.height < 2 {
+            return;
+        }
+
+        // Body height leaves room for the footer line
+        let body_height = inner.height.saturating_sub(1) as usize;
+        let offset = self.selected.saturating_sub(body_height.saturating_sub(1));
+
+        // Columns: filename | sender | date | size (right-aligned)
+        let size_w = 9usize;
+        let date_w = 10usize;
+        let sender_w = 20usize.min(inner.width as usize / 4);
+        let name_w = (inner.width as usize).saturating_sub(size_w + date_w + sender_w + 5);
+
+        for (i, item) in self.items.iter().skip(offset).take(body_height).enumerate() {
+            let idx = offset + i;
+            let y = inner.y + i as u16;
+            let is_selected = idx == self.selected;
+            let base = if is_selected {
+                Style::default().bg(Color::Blue).fg(Color::White)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            buf.set_style(Rect::new(inner.x, y, inner.width, 1), base);
+
+            let mut x = inner.x + 1;
+            for (text, width, style) in [
+                (item.attachment.filename.as_str(), name_w, base.add_modifier(Modifier::BOLD)),
+                (item.sender.as_str(), sender_w, base.fg(Color::Cyan)),
+                (item.date.as_str(), date_w, base.fg(Color::Gray)),
+            ] {
+                buf.set_stringn(x, y, text, width, style);
+                x += width as u16 + 1;
+            }
+            let size = format!("{:>width$}", format_size(item.attachment.size), width = size_w);
+            buf.set_stringn(x, y, &size, size_w, base.fg(Color::DarkGray));
+        }
+
+        buf.set_string(
+            inner.x,
+            inner.y + inner.height - 1,
+            " j/k:nav  Enter/o:open  s:save  q/Esc:close",
+            Style::default().fg(Color::DarkGray),
+        );
+    }
+}
@@ -8,6 +8,7 @@ use ratatui::{
 use crate::keymap::Action;
 
 use super::folder_picker::centered_rect;
+use super::text;
 
 #[derive(Clone)]
 pub struct PaletteEntry {
@@ -58,6 +59,18 @@ impl PaletteEntry {
                 shortcut: Some("Shift+Space".into()),
                 action: Action::ScrollPreviewUp,
             },
+            PaletteEntry {
+                name: "Preview Start".into(),
+                description: "Jump to the start of the previewed message".into(),
+                shortcut: Some("Home".into()),
+                action: Action::PreviewTop,
+            },
+            PaletteEntry {
+                name: "Preview End".into(),
+                description: "Jump to the end of the previewed message".into(),
+                shortcut: Some("End".into()),
+                action: Action::PreviewBottom,
+            },
             PaletteEntry {
                 name: "Half Page Down".into(),
                 description: "Move half a page down".into(),
@@ -101,6 +114,24 @@ impl PaletteEntry {
                 shortcut: Some("!".into()),
                 action: Action::MoveToFolder(Some("spam".to_string())),
             },
+            PaletteEntry {
+                name: "Repeat Move".into(),
+                description: "Move to the last folder picked with m".into(),
+                shortcut: Some("M".into()),
+                action: Action::RepeatMove,
+            },
+            PaletteEntry {
+                name: "Sweep Sender".into(),
+                description: "Archive, trash or move everything here from this sender".into(),
+                shortcut: None,
+                action: Action::SweepSender,
+            },
+            PaletteEntry {
+                name: "Triage Loop".into(),
+                description: "Step through the list one message at a time: archive, keep, trash or skip".into(),
+                shortcut: None,
+                action: Action::TriageLoop,
+            },
             PaletteEntry {
                 name: "Toggle Read".into(),
                 description: "Toggle read/unread status".into(),
@@ -175,6 +206,43 @@ impl PaletteEntry {
                 shortcut: Some("g S+Tab".into()),
                 action: Action::PrevAccount,
             },
+            // View tabs
+            PaletteEntry {
+                name: "New View".into(),
+                description: "Open a view tab on the current folder".into(),
+                shortcut: Some("gn".into()),
+                action: Action::NewView,
+            },
+            PaletteEntry {
+                name: "Close View".into(),
+                description: "Close the current view tab".into(),
+                shortcut: Some("gw".into()),
+                action: Action::CloseView,
+            },
+            PaletteEntry {
+                name: "Next View".into(),
+                description: "Switch to the next view tab".into(),
+                shortcut: Some("g]".into()),
+                action: Action::NextView,
+            },
+            PaletteEntry {
+                name: "Previous View".into(),
+                description: "Switch to the previous view tab".into(),
+                shortcut: Some("g[".into()),
+                action: Action::PrevView,
+            },
+            PaletteEntry {
+                name: "Compare Folders".into(),
+                description: "Show two folders side by side, from any accounts".into(),
+                shortcut: Some("gc".into()),
+                action: Action::Compare,
+            },
+            PaletteEntry {
+                name: "Switch Compared Pane".into(),
+                description: "Focus the other folder being compared".into(),
+                shortcut: Some("go".into()),
+                action: Action::CompareSwitch,
+            },
             // Search & Filters
             PaletteEntry {
                 name: "Search".into(),
@@ -200,6 +268,24 @@ impl PaletteEntry {
                 shortcut: Some("R".into()),
                 action: Action::FilterNeedsReply,
             },
+            PaletteEntry {
+                name: "Collapse Near-Duplicates".into(),
+                description: "In searches, show only the newest message of each thread".into(),
+                shortcut: Some("C".into()),
+                action: Action::CollapseDuplicates,
+            },
+            PaletteEntry {
+                name: "Expand Near-Duplicates".into(),
+                description: "Show or hide the collapsed messages of the selected thread".into(),
+                shortcut: Some("E".into()),
+                action: Action::ExpandDuplicates,
+            },
+            PaletteEntry {
+                name: "Filter Score".into(),
+                description: "Show only messages scoring at least score_threshold".into(),
+                shortcut: None,
+                action: Action::FilterScore,
+            },
             // Multi-select
             PaletteEntry {
                 name: "Toggle Select".into(),
@@ -226,12 +312,90 @@ impl PaletteEntry {
                 shortcut: Some("Enter".into()),
                 action: Action::OpenThread,
             },
+            PaletteEntry {
+                name: "Toggle Headers".into(),
+                description: "Show or truncate long recipient lists in the preview".into(),
+                shortcut: Some("H".into()),
+                action: Action::ToggleHeaders,
+            },
+            PaletteEntry {
+                name: "Header Actions".into(),
+                description: "Search, write to, make a split for or copy a header address or list".into(),
+                shortcut: Some("h".into()),
+                action: Action::HeaderActions,
+            },
+            PaletteEntry {
+                name: "Add to Contacts".into(),
+                description: "Add the sender to the address book, or change their name there".into(),
+                shortcut: Some("A".into()),
+                action: Action::AddContact,
+            },
+            PaletteEntry {
+                name: "Approve Sender".into(),
+                description: "Let a first-time sender through the screener by adding them to contacts".into(),
+                shortcut: Some("+".into()),
+                action: Action::ApproveSender,
+            },
+            PaletteEntry {
+                name: "Block Sender".into(),
+                description: "Send the sender's inbox mail to spam, now and from now on".into(),
+                shortcut: Some("-".into()),
+                action: Action::BlockSender,
+            },
+            PaletteEntry {
+                name: "Toggle Text Direction".into(),
+                description: "Show this message right-to-left, left-to-right, or detect it".into(),
+                shortcut: None,
+                action: Action::ToggleDirection,
+            },
+            PaletteEntry {
+                name: "Render Large Message".into(),
+                description: "Show a message over preview_max_size_mb after all".into(),
+                shortcut: Some("v".into()),
+                action: Action::RenderAnyway,
+            },
+            PaletteEntry {
+                name: "Toggle Density".into(),
+                description: "Switch between compact and relaxed list rows".into(),
+                shortcut: Some("D".into()),
+                action: Action::ToggleDensity,
+            },
             PaletteEntry {
                 name: "Toggle Conversations".into(),
                 description: "Switch between message and conversation view".into(),
                 shortcut: Some("V".into()),
                 action: Action::ToggleConversations,
             },
+            PaletteEntry {
+                name: "Inbox Sections".into(),
+                description: "Group the inbox into Primary, Lists and Notifications".into(),
+                shortcut: None,
+                action: Action::ToggleSections,
+            },
+            PaletteEntry {
+                name: "Collapse Section".into(),
+                description: "Fold away the selected message's inbox section".into(),
+                shortcut: None,
+                action: Action::CollapseSection,
+            },
+            PaletteEntry {
+                name: "Expand Sections".into(),
+                description: "Unfold all collapsed inbox sections".into(),
+                shortcut: None,
+                action: Action::ExpandSections,
+            },
+            PaletteEntry {
+                name: "Select Section".into(),
+                description: "Select every message in the selected message's inbox section".into(),
+                shortcut: None,
+                action: Action::SelectSection,
+            },
+            PaletteEntry {
+                name: "Pause Inbox".into(),
+                description: "Hold back new mail, reminders and unread counts until resumed".into(),
+                shortcut: None,
+                action: Action::PauseInbox,
+            },
             PaletteEntry {
                 name: "Sort Messages".into(),
                 description: "Change sort field (date, from, subject, to)".into(),
@@ -269,6 +433,24 @@ impl PaletteEntry {
                 shortcut: Some("f".into()),
                 action: Action::Forward,
             },
+            PaletteEntry {
+                name: "Quick Reply".into(),
+                description: "Send a one-line reply without the editor".into(),
+                shortcut: Some("Q".into()),
+                action: Action::QuickReply,
+            },
+            PaletteEntry {
+                name: "Await Reply".into(),
+                description: "Remind me if there's no reply by a date (@Waiting)".into(),
+                shortcut: Some("W".into()),
+                action: Action::AwaitReply,
+            },
+            PaletteEntry {
+                name: "Edit Note".into(),
+                description: "Write a private note on the message (search with note:)".into(),
+                shortcut: Some("N".into()),
+                action: Action::EditNote,
+            },
             // Linkability
             PaletteEntry {
                 name: "Copy Message URL".into(),
@@ -282,6 +464,60 @@ impl PaletteEntry {
                 shortcut: Some("Y".into()),
                 action: Action::CopyThreadUrl,
             },
+            PaletteEntry {
+                name: "Copy Message Link".into(),
+                description: "Copy a Markdown or org-mode link to the message".into(),
+                shortcut: None,
+                action: Action::CopyMessageLink,
+            },
+            PaletteEntry {
+                name: "Copy Redacted Message".into(),
+                description: "Copy the message as text with addresses and redact patterns hidden".into(),
+                shortcut: None,
+                action: Action::CopyRedacted,
+            },
+            PaletteEntry {
+                name: "Save Patch".into(),
+                description: "Save the patch email(s) to download_dir as .patch files".into(),
+                shortcut: None,
+                action: Action::SavePatch,
+            },
+            PaletteEntry {
+                name: "Apply Patch".into(),
+                description: "Run patch_command (e.g. git am) on the patch email(s)".into(),
+                shortcut: None,
+                action: Action::ApplyPatch,
+            },
+            PaletteEntry {
+                name: "Pipe Thread".into(),
+                description: "Pipe the thread as an mbox to pipe_thread_command (e.g. b4 am)".into(),
+                shortcut: None,
+                action: Action::PipeThread,
+            },
+            PaletteEntry {
+                name: "Summarize Thread".into(),
+                description: "Show summarize_command's summary of the thread's text".into(),
+                shortcut: None,
+                action: Action::SummarizeThread,
+            },
+            PaletteEntry {
+                name: "Export Search".into(),
+                description: "Save every message in this folder or search as .eml files or an mbox".into(),
+                shortcut: None,
+                action: Action::ExportSearch,
+            },
+            PaletteEntry {
+                name: "Copy Folder URL".into(),
+                description: "Copy a hutt://account/ URL for this folder".into(),
+                shortcut: None,
+                action: Action::CopyFolderUrl,
+            },
+            PaletteEntry {
+                name: "Copy Search URL".into(),
+                description: "Copy a hutt:search URL that reruns this view's query".into(),
+                shortcut: None,
+                action: Action::CopySearchUrl,
+            },
             PaletteEntry {
                 name: "Open in Browser".into(),
                 description: "Open message in browser".into(),
@@ -295,6 +531,18 @@ impl PaletteEntry {
                 shortcut: Some("Ctrl+r".into()),
                 action: Action::SyncMail,
             },
+            PaletteEntry {
+                name: "Headers-Only Sync".into(),
+                description: "Toggle syncing with headers_sync_command (bodies fetched on preview)".into(),
+                shortcut: None,
+                action: Action::HeadersOnlySync,
+            },
+            PaletteEntry {
+                name: "Shell Dry Run".into(),
+                description: "Toggle showing what shell bindings would run instead of running them".into(),
+                shortcut: None,
+                action: Action::ToggleShellDryRun,
+            },
             // Splits
             PaletteEntry {
                 name: "Create Split".into(),
@@ -302,6 +550,12 @@ impl PaletteEntry {
                 shortcut: None,
                 action: Action::CreateSplit,
             },
+            PaletteEntry {
+                name: "Split by Plus Tag".into(),
+                description: "Create a split for mail sent to the selected message's plus address".into(),
+                shortcut: None,
+                action: Action::SplitPlusTag,
+            },
             PaletteEntry {
                 name: "Switch Account".into(),
                 description: "Open account picker".into(),
@@ -315,6 +569,42 @@ impl PaletteEntry {
                 shortcut: Some("?".into()),
                 action: Action::ShowHelp,
             },
+            PaletteEntry {
+                name: "Describe Key".into(),
+                description: "Show what the next key or sequence does here, custom bindings included".into(),
+                shortcut: Some("g?".into()),
+                action: Action::DescribeKey,
+            },
+            PaletteEntry {
+                name: "Operations History".into(),
+                description: "Show recent moves, flag changes and sends".into(),
+                shortcut: None,
+                action: Action::ShowHistory,
+            },
+            PaletteEntry {
+                name: "Recently Deleted".into(),
+                description: "Messages trashed or removed lately, to put back where they were".into(),
+                shortcut: None,
+                action: Action::RecentlyDeleted,
+            },
+            PaletteEntry {
+                name: "Digest".into(),
+                description: "Summarise the last week: top senders and lists, unread, needs reply".into(),
+                shortcut: None,
+                action: Action::ShowDigest,
+            },
+            PaletteEntry {
+                name: "Delivery Info".into(),
+                description: "Show when and how the selected sent message was delivered".into(),
+                shortcut: None,
+                action: Action::DeliveryInfo,
+            },
+            PaletteEntry {
+                name: "Browse Attachments".into(),
+                description: "List every attachment in the current folder or search".into(),
+                shortcut: None,
+                action: Action::BrowseAttachments,
+            },
             // System
             PaletteEntry {
                 name: "Quit".into(),
@@ -409,7 +699,7 @@ impl<'a> Widget for CommandPalette<'a> {
         let prompt = "> ";
         buf.set_string(inner.x, inner.y, prompt, filter_style);
         buf.set_string(inner.x + 2, inner.y, self.filter, filter_style);
-        let cursor_x = inner.x + 2 + self.filter.len() as u16;
+        let cursor_x = inner.x + 2 + text::width(self.filter) as u16;
         if cursor_x < inner.x + inner.width {
             buf.set_string(cursor_x, inner.y, " ", cursor_style);
         }
@@ -474,7 +764,7 @@ impl<'a> Widget for CommandPalette<'a> {
             }
 
             // Name
-            let name_display = truncate_str(&entry.name, inner.width as usize);
+            let name_display = text::truncate(&entry.name, inner.width as usize);
             buf.set_string(inner.x + 1, y, &name_display, name_style);
 
             // Shortcut (right-aligned)
@@ -486,9 +776,9 @@ impl<'a> Widget for CommandPalette<'a> {
                 } else {
                     Style::default().fg(Color::DarkGray)
                 };
-                let sc_len = shortcut.len() as u16;
+                let sc_len = text::width(shortcut) as u16;
                 let sc_x = (inner.x + inner.width).saturating_sub(sc_len + 1);
-                if sc_x > inner.x + 1 + entry.name.len() as u16 {
+                if sc_x > inner.x + 1 + text::width(&entry.name) as u16 {
                     buf.set_string(sc_x, y, shortcut, shortcut_style);
                 }
             }
@@ -506,7 +796,7 @@ impl<'a> Widget for CommandPalette<'a> {
             } else {
                 Style::default().fg(Color::Gray)
             };
-            let desc_display = truncate_str(&entry.description, (inner.width as usize).saturating_sub(2));
+            let desc_display = text::truncate(&entry.description, (inner.width as usize).saturating_sub(2));
             buf.set_string(inner.x + 2, y, &desc_display, desc_style);
 
             y += 1;
@@ -524,19 +814,3 @@ impl<'a> Widget for CommandPalette<'a> {
     }
 }
 
-/// Truncate a string to fit within `max_width` characters, adding "\u{2026}" if needed.
-fn truncate_str(s: &str, max_width: usize) -> String {
-    if max_width == 0 {
-        return String::new();
-    }
-    let chars: Vec<char> = s.chars().collect();
-    if chars.len() <= max_width {
-        s.to_string()
-    } else if max_width <= 1 {
-        "\u{2026}".to_string()
-    } else {
-        let mut result: String = chars[..max_width - 1].iter().collect();
-        result.push('\u{2026}');
-        result
-    }
-}
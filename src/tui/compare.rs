@@ -0,0 +1,135 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::Widget,
+};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use super::envelope_list::AccountBadge;
+use super::text;
+use crate::config::Config;
+use crate::envelope::Envelope;
+use crate::session::ViewState;
+
+/// Badge colors of accounts without their own `color`, by account order.
+const BADGE_COLORS: [Color; 6] =
+    [Color::Cyan, Color::Magenta, Color::Yellow, Color::Green, Color::LightBlue, Color::LightRed];
+
+/// The pane of the comparison that isn't focused: where it was left and
+/// what it listed then. Focusing it loads it again, swapping the other
+/// pane in here.
+#[derive(Debug, Clone)]
+pub struct ComparePane {
+    /// Index of the account its folder belongs to.
+    pub account: usize,
+    pub view: ViewState,
+    pub envelopes: Vec<Envelope>,
+    pub selected_set: HashSet<u32>,
+}
+
+/// Two folders side by side, perhaps from different accounts. The focused
+/// pane is the app's own list, so every key acts on it as usual.
+#[derive(Debug, Clone)]
+pub struct Compare {
+    pub other: ComparePane,
+    /// Whether the focused pane is the right-hand one.
+    pub focus_right: bool,
+}
+
+/// How many of `envelopes` have no message with the same Message-ID in
+/// `other`: what a migration would still have to copy.
+pub fn only_here(envelopes: &[Envelope], other: &[Envelope]) -> usize {
+    let ids: HashSet<&str> = other.iter().map(|e| e.message_id.as_str()).collect();
+    envelopes.iter().filter(|e| !ids.contains(e.message_id.as_str())).count()
+}
+
+/// "Work /Archive · 1234 · 12 only here", the line above each pane.
+pub fn title(account: &str, folder: &str, envelopes: &[Envelope], other: &[Envelope]) -> String {
+    let mut title = if account.is_empty() { folder.to_string() } else { format!("{} {}", account, folder) };
+    title.push_str(&format!(" \u{b7} {}", envelopes.len()));
+    let missing = only_here(envelopes, other);
+    if missing > 0 {
+        title.push_str(&format!(" \u{b7} {} only here", missing));
+    }
+    title
+}
+
+/// The badge marking account `index`'s rows when the panes are on two
+/// accounts: its `badge` or the first letter of its name, in its `color`
+/// or the next of [`BADGE_COLORS`].
+pub fn account_badge(config: &Config, index: usize) -> AccountBadge {
+    let account = config.accounts.get(index);
+    let letter = account
+        .and_then(|a| a.badge.clone())
+        .or_else(|| account.map(|a| a.name.chars().take(1).flat_map(char::to_uppercase).collect()))
+        .unwrap_or_default();
+    let color = account
+        .and_then(|a| a.color.as_deref())
+        .and_then(|c| Color::from_str(c).ok())
+        .unwrap_or(BADGE_COLORS[index % BADGE_COLORS.len()]);
+    AccountBadge { text: format!("[{}]", letter), color }
+}
+
+/// The title line of one pane, highlighted when focused.
+pub struct PaneTitle<'a> {
+    pub text: &'a str,
+    pub focused: bool,
+}
+
+impl<'a> Widget for PaneTitle<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let style = if self.focused {
+            Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().bg(Color::DarkGray).fg(Color::White)
+        };
+        let width = area.width as usize;
+        let line = text::pad(&text::truncate(&format!(" {}", self.text), width), width);
+        buf.set_string(area.x, area.y, line, style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_messages_only_in_one_pane() {
+        let message = |id: &str| Envelope { message_id: id.into(), ..Envelope::default() };
+        let old = vec![message("a@x"), message("b@x"), message("c@x")];
+        let new = vec![message("b@x")];
+        assert_eq!(only_here(&old, &new), 2);
+        assert_eq!(only_here(&new, &old), 0);
+        assert_eq!(title("Old", "/Archive", &old, &new), "Old /Archive \u{b7} 3 \u{b7} 2 only here");
+        assert_eq!(title("", "/Archive", &new, &old), "/Archive \u{b7} 1");
+    }
+
+    #[test]
+    fn account_badges_from_config() {
+        let config: Config = toml::from_str(
+            r#"
+            [[accounts]]
+            name = "work"
+            email = "me@work.example"
+            maildir = "/tmp/work"
+            smtp = {}
+
+            [[accounts]]
+            name = "Personal"
+            email = "me@home.example"
+            maildir = "/tmp/home"
+            smtp = {}
+            badge = "H"
+            color = "green"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(account_badge(&config, 0), AccountBadge { text: "[W]".into(), color: Color::Cyan });
+        assert_eq!(account_badge(&config, 1), AccountBadge { text: "[H]".into(), color: Color::Green });
+    }
+}
@@ -0,0 +1,105 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::folder_picker::centered_rect;
+use super::text;
+use crate::envelope::Envelope;
+use crate::oplog::Deletion;
+
+/// A recently deleted message, with its copy in the trash if it is
+/// still there.
+#[derive(Debug, Clone)]
+pub struct DeletedMessage {
+    pub deletion: Deletion,
+    pub copy: Option<Envelope>,
+}
+
+impl DeletedMessage {
+    /// Where it can be put back to: only a trashed message still in the
+    /// trash can be.
+    pub fn restore_to(&self) -> Option<&str> {
+        self.copy.as_ref().and(self.deletion.from.as_deref())
+    }
+
+    /// The folder column: where it came from, or why it can't go back.
+    fn origin(&self) -> &str {
+        match (&self.deletion.from, &self.copy) {
+            (Some(from), Some(_)) => from,
+            (Some(_), None) => "(trash emptied)",
+            (None, _) => "(removed)",
+        }
+    }
+}
+
+/// Popup listing messages trashed or removed in the last `days` days,
+/// newest first, the selected one highlighted.
+pub struct DeletedOverlay<'a> {
+    pub messages: &'a [DeletedMessage],
+    pub selected: usize,
+    pub days: u32,
+}
+
+impl<'a> Widget for DeletedOverlay<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width: u16 = area.width.saturating_sub(4).clamp(40, 100);
+        let popup_height: u16 = area.height.clamp(10, 30);
+        let popup = centered_rect(popup_width, popup_height, area);
+
+        Clear.render(popup, buf);
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(format!(" Recently Deleted (last {} days) ", self.days))
+            .title_style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            .render(popup, buf);
+
+        let inner = Rect::new(
+            popup.x + 1,
+            popup.y + 1,
+            popup.width.saturating_sub(2),
+            popup.height.saturating_sub(2),
+        );
+        if inner.width < 2 || inner.height == 0 {
+            return;
+        }
+
+        let dim = Style::default().fg(Color::DarkGray);
+        if self.messages.is_empty() {
+            buf.set_string(inner.x + 1, inner.y, "Nothing trashed or removed", dim);
+            return;
+        }
+
+        // Keep the selection in view; the last line is the footer
+        let rows = inner.height.saturating_sub(1) as usize;
+        let skip = (self.selected + 1).saturating_sub(rows);
+        let width = (inner.width - 1) as usize;
+        let folder_width = self.messages.iter().map(|m| text::width(m.origin())).max().unwrap_or(0).min(24);
+        for (i, message) in self.messages.iter().enumerate().skip(skip).take(rows) {
+            let y = inner.y + (i - skip) as u16;
+            let style = if i == self.selected {
+                Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD)
+            } else if message.restore_to().is_some() {
+                Style::default().fg(Color::White)
+            } else {
+                dim
+            };
+            let line = format!(
+                "{}  {}  {}",
+                message.deletion.time.format("%Y-%m-%d %H:%M"),
+                text::pad(&text::truncate(message.origin(), folder_width), folder_width),
+                message.deletion.subject
+            );
+            buf.set_string(inner.x + 1, y, text::pad(&text::truncate(&line, width), width), style);
+        }
+        buf.set_string(
+            inner.x + 1,
+            inner.y + inner.height - 1,
+            text::truncate("j/k:nav  Enter/r:restore to folder  q/Esc:close", width),
+            dim,
+        );
+    }
+}
@@ -1,21 +1,157 @@
+use chrono::{DateTime, Utc};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
     style::{Color, Modifier, Style},
     widgets::Widget,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use unicode_width::UnicodeWidthStr;
 
-use crate::envelope::{Conversation, Envelope};
+use super::text;
+use crate::config::{FlagGlyphs, ListDensity};
+use crate::envelope::{Address, Conversation, Envelope};
+use crate::inbox_sections::SectionHeader;
+use crate::mime_render::format_size;
 
 pub struct EnvelopeList<'a> {
     pub envelopes: &'a [Envelope],
     pub selected: usize,
     pub offset: usize,
     pub multi_selected: &'a HashSet<u32>,
+    /// Docids that just arrived, drawn on a tinted background.
+    pub new_arrivals: &'a HashSet<u32>,
+    pub density: ListDensity,
+    /// Body snippets keyed by message-id (missing entries render blank).
+    pub snippets: &'a HashMap<String, String>,
+    /// Tint sender names with [`sender_color`].
+    pub color_senders: bool,
+    pub flag_column: &'a FlagColumn,
+    /// Show each message's size before the date (for `size:` queries).
+    pub show_size: bool,
+    /// Scores keyed by message-id, shown before the date; `None` hides
+    /// the column.
+    pub scores: Option<&'a HashMap<String, Option<f64>>>,
+    /// Section headers of the sectioned inbox, drawn above their rows.
+    pub sections: &'a [SectionHeader],
+    /// Tint the dates of unanswered messages by age; `None` leaves them.
+    pub aging: Option<Aging>,
+    /// Near-duplicates collapsed into each listed message, by its
+    /// Message-ID; counted after its subject.
+    pub collapsed: &'a HashMap<String, Vec<Envelope>>,
+    /// Account badge drawn at the start of every row, when accounts are
+    /// shown together.
+    pub badge: Option<&'a AccountBadge>,
+}
+
+/// A short colored marker (`[W]`) for the account a row belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountBadge {
+    pub text: String,
+    pub color: Color,
+}
+
+impl AccountBadge {
+    /// Draw the badge at `x` and return the columns it took, with a gap.
+    fn render(badge: Option<&Self>, buf: &mut Buffer, x: u16, y: u16, base: Style) -> u16 {
+        let Some(badge) = badge else {
+            return 0;
+        };
+        buf.set_string(x, y, &badge.text, base.fg(badge.color).add_modifier(Modifier::BOLD));
+        badge.text.width() as u16 + 1
+    }
+}
+
+/// How many days an unanswered message waits before its date turns amber,
+/// then red. 0 turns that color off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aging {
+    pub amber_days: u32,
+    pub red_days: u32,
+}
+
+impl Aging {
+    /// Date color for an unanswered message from `date`, once it has
+    /// waited more than `amber_days` or `red_days`.
+    pub fn color(&self, date: DateTime<Utc>, now: DateTime<Utc>) -> Option<Color> {
+        let hours = now.signed_duration_since(date).num_hours();
+        let past = |days: u32| days > 0 && hours > days as i64 * 24;
+        if past(self.red_days) {
+            Some(Color::Red)
+        } else if past(self.amber_days) {
+            Some(Color::Yellow)
+        } else {
+            None
+        }
+    }
+}
+
+/// Date column style: gray, or the aging color of an unanswered message.
+fn date_style(base: Style, aging: Option<Aging>, newest: Option<&Envelope>) -> Style {
+    let color = aging
+        .zip(newest.filter(|e| !e.is_replied()))
+        .and_then(|(aging, e)| aging.color(e.date, Utc::now()));
+    match color {
+        Some(color) => base.fg(color),
+        None => base.fg(Color::DarkGray),
+    }
+}
+
+/// Date column text, with the size in front when `show_size` is set.
+fn date_text(date: String, size: usize, show_size: bool) -> String {
+    if show_size {
+        format!("{:>8}  {}", format_size(size), date)
+    } else {
+        date
+    }
+}
+
+/// Date column text with the highest score of `message_ids` in front,
+/// when scores are shown. Unscored messages leave the column blank.
+fn scored_text<'s>(
+    date: String,
+    scores: Option<&HashMap<String, Option<f64>>>,
+    message_ids: impl Iterator<Item = &'s str>,
+) -> String {
+    let Some(scores) = scores else {
+        return date;
+    };
+    match message_ids.filter_map(|id| scores.get(id).copied().flatten()).reduce(f64::max) {
+        Some(score) => format!("{:>6.1}  {}", score, date),
+        None => format!("{:6}  {}", "", date),
+    }
+}
+
+/// Row style: highlighted when selected, tinted when newly arrived.
+fn row_style(is_selected: bool, is_new: bool) -> Style {
+    if is_selected {
+        Style::default().bg(Color::Indexed(236)).fg(Color::White)
+    } else if is_new {
+        Style::default().bg(Color::Indexed(22))
+    } else {
+        Style::default()
+    }
+}
+
+/// Draw a section header line: ▾ Lists  12 · 3 unread
+fn render_section_header(buf: &mut Buffer, area: Rect, y: u16, header: &SectionHeader) {
+    let arrow = if header.collapsed { "\u{25b8}" } else { "\u{25be}" };
+    let mut text = format!("{} {}  {}", arrow, header.name, header.count);
+    if header.unread > 0 {
+        text.push_str(&format!(" \u{00b7} {} unread", header.unread));
+    }
+    let style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    buf.set_string(area.x + 1, y, text::truncate_with(&text, area.width.saturating_sub(1) as usize, "~"), style);
 }
 
 impl<'a> EnvelopeList<'a> {
+    /// Number of items that fit in `area_height` rows, leaving room for
+    /// `headers` section header lines.
+    pub fn capacity(area_height: u16, density: ListDensity, headers: usize) -> usize {
+        ((area_height as usize).saturating_sub(headers) / density.rows_per_item()).max(1)
+    }
+
     /// Calculate the visible range for scrolling.
     pub fn visible_range(
         selected: usize,
@@ -37,7 +173,7 @@ impl<'a> EnvelopeList<'a> {
 
 impl<'a> Widget for EnvelopeList<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        if self.envelopes.is_empty() {
+        if self.envelopes.is_empty() && self.sections.is_empty() {
             let style = Style::default().fg(Color::DarkGray);
             buf.set_string(
                 area.x + 2,
@@ -48,86 +184,369 @@ impl<'a> Widget for EnvelopeList<'a> {
             return;
         }
 
-        let height = area.height as usize;
+        let rows = self.density.rows_per_item();
+        let height = Self::capacity(area.height, self.density, self.sections.len());
         let (start, end) =
             Self::visible_range(self.selected, self.offset, height, self.envelopes.len());
 
+        // Headers of sections starting at `idx`, drawn from `y` down
+        let headers_at = |buf: &mut Buffer, idx: usize, y: &mut u16| {
+            for header in self.sections.iter().filter(|h| h.start == idx) {
+                if *y >= area.bottom() {
+                    return;
+                }
+                render_section_header(buf, area, *y, header);
+                *y += 1;
+            }
+        };
+
+        let mut row_y = area.y;
         for (i, envelope) in self.envelopes[start..end].iter().enumerate() {
-            let y = area.y + i as u16;
             let idx = start + i;
+            headers_at(buf, idx, &mut row_y);
+            if row_y + rows as u16 > area.bottom() {
+                return;
+            }
+            let y = row_y;
+            row_y += rows as u16;
             let is_selected = idx == self.selected;
             let is_multi = self.multi_selected.contains(&envelope.docid);
             let is_unread = envelope.is_unread();
-            let is_flagged = envelope.is_flagged();
 
-            let base_style = if is_selected {
-                Style::default().bg(Color::Indexed(236)).fg(Color::White)
-            } else {
-                Style::default()
-            };
+            let base_style = row_style(is_selected, self.new_arrivals.contains(&envelope.docid));
 
-            // Fill the line with background
-            buf.set_style(Rect::new(area.x, y, area.width, 1), base_style);
+            // Fill the row with background
+            buf.set_style(Rect::new(area.x, y, area.width, rows as u16), base_style);
 
             let w = area.width as usize;
+            let snippet = self.snippets
+                .get(&envelope.message_id)
+                .map(|s| s.as_str())
+                .unwrap_or("");
 
-            // Multi-select / unread / flag indicator (2 chars)
-            let indicator = if is_multi {
-                "x "
-            } else if is_flagged {
-                "* "
-            } else if is_unread {
-                "> "
-            } else {
-                "  "
-            };
-            let ind_style = if is_multi {
-                base_style.fg(Color::Green).add_modifier(Modifier::BOLD)
-            } else if is_flagged {
-                base_style.fg(Color::Yellow)
-            } else if is_unread {
-                base_style.fg(Color::Cyan).add_modifier(Modifier::BOLD)
-            } else {
-                base_style.fg(Color::DarkGray)
-            };
-            buf.set_string(area.x, y, indicator, ind_style);
+            // Multi-select marker, the account badge, then the flag glyph column
+            if is_multi {
+                let style = base_style.fg(Color::Green).add_modifier(Modifier::BOLD);
+                buf.set_string(area.x, y, "x", style);
+            }
+            let badged = AccountBadge::render(self.badge, buf, area.x + 1, y, base_style);
+            self.flag_column.render(buf, area.x + 1 + badged, y, base_style, &FlagState::of_envelope(envelope));
+            let lead = badged + self.flag_column.width() as u16 + 2;
 
-            // From field (up to 20 chars)
             let from = envelope.sender_display();
-            let from_width = 20.min(w.saturating_sub(2));
-            let from_truncated = truncate_str(&from, from_width);
-            let from_style = if is_unread {
-                base_style.add_modifier(Modifier::BOLD)
-            } else {
+            let from_style = sender_style(
+                base_style,
+                envelope.from.first().map(|a| a.email.as_str()),
+                self.color_senders,
+                is_unread,
+            );
+            let subj_style = if is_unread {
                 base_style
-            };
-            buf.set_string(area.x + 2, y, &from_truncated, from_style);
-
-            // Date (right-aligned, ~10 chars)
-            let date = envelope.date_display();
-            let date_width = date.len();
-            let date_x = if w > date_width + 1 {
-                area.x + area.width - date_width as u16 - 1
             } else {
-                area.x + area.width - 1
+                base_style.fg(Color::Gray)
             };
-            let date_style = base_style.fg(Color::DarkGray);
-            buf.set_string(date_x, y, &date, date_style);
-
-            // Subject (fills the middle)
-            let subject_start = area.x + 2 + from_width as u16 + 1;
-            let subject_end = date_x.saturating_sub(1);
-            if subject_start < subject_end {
-                let subject_width = (subject_end - subject_start) as usize;
-                let subject = truncate_str(&envelope.subject, subject_width);
-                let subj_style = if is_unread {
-                    base_style
-                } else {
-                    base_style.fg(Color::Gray)
-                };
-                buf.set_string(subject_start, y, &subject, subj_style);
+            let subject = match self.collapsed.get(&envelope.message_id) {
+                Some(hidden) => format!("{} (+{})", envelope.subject, hidden.len()),
+                None => envelope.subject.clone(),
+            };
+            let date = date_text(envelope.date_display(), envelope.size, self.show_size);
+            let date = scored_text(date, self.scores, std::iter::once(envelope.message_id.as_str()));
+            let date_x = right_align_x(area, text::width(&date));
+            buf.set_string(date_x, y, &date, date_style(base_style, self.aging, Some(envelope)));
+
+            match self.density {
+                ListDensity::Compact => {
+                    // From field (up to 20 chars)
+                    let from_width = 20.min(w.saturating_sub(lead as usize));
+                    let from_truncated = text::truncate_with(&from, from_width, "~");
+                    buf.set_string(area.x + lead, y, &from_truncated, from_style);
+
+                    // Subject then snippet (fills the middle)
+                    let subject_start = area.x + lead + from_width as u16 + 1;
+                    let subject_end = date_x.saturating_sub(1);
+                    render_subject_snippet(
+                        buf, subject_start, subject_end, y,
+                        &subject, subj_style, snippet, base_style,
+                    );
+                }
+                ListDensity::Relaxed => {
+                    // Line one: subject and date
+                    let subject_end = date_x.saturating_sub(1);
+                    let subject_style = if is_unread {
+                        base_style.add_modifier(Modifier::BOLD)
+                    } else {
+                        subj_style
+                    };
+                    render_subject_snippet(
+                        buf, area.x + lead, subject_end, y,
+                        &subject, subject_style, "", base_style,
+                    );
+
+                    // Line two: sender then snippet
+                    render_subject_snippet(
+                        buf, area.x + lead, area.x + area.width.saturating_sub(1), y + 1,
+                        &from, from_style, snippet, base_style,
+                    );
+                }
             }
         }
+        // Collapsed or empty sections after the last row
+        if end == self.envelopes.len() {
+            headers_at(buf, end, &mut row_y);
+        }
+    }
+}
+
+/// Which flag glyphs a list row shows.
+#[derive(Debug, Default, PartialEq)]
+pub struct FlagState {
+    pub unread: bool,
+    pub starred: bool,
+    pub replied: bool,
+    pub attachment: bool,
+    pub trashed: bool,
+    pub urgent: bool,
+    /// Keywords ("todo"), for the keyword glyphs.
+    pub tags: Vec<String>,
+}
+
+impl FlagState {
+    pub fn of_envelope(envelope: &Envelope) -> Self {
+        Self {
+            unread: envelope.is_unread(),
+            starred: envelope.is_flagged(),
+            replied: envelope.is_replied(),
+            attachment: envelope.has_attachment(),
+            trashed: envelope.is_trashed(),
+            urgent: envelope.is_urgent(),
+            tags: envelope.tags.clone(),
+        }
+    }
+
+    /// A conversation shows a flag if any of its messages has it.
+    pub fn of_conversation(convo: &Conversation) -> Self {
+        let any = |f: fn(&Envelope) -> bool| convo.messages.iter().any(f);
+        Self {
+            unread: convo.has_unread(),
+            starred: convo.has_flagged(),
+            replied: any(Envelope::is_replied),
+            attachment: any(Envelope::has_attachment),
+            trashed: any(Envelope::is_trashed),
+            urgent: any(Envelope::is_urgent),
+            tags: convo.messages.iter().flat_map(|m| m.tags.iter().cloned()).collect(),
+        }
+    }
+}
+
+/// A glyph and its color in the flag column.
+#[derive(Debug, Clone)]
+struct Glyph {
+    text: String,
+    color: Color,
+}
+
+/// The fixed-width flag column drawn at the start of each list row.
+///
+/// Four slots, left to right: unread (or trashed), starred, replied (or
+/// urgent, until it's replied to), attachment, then one per keyword shown.
+/// Each slot is as wide as its widest glyph so columns line up whichever
+/// flags a row has.
+#[derive(Debug, Clone)]
+pub struct FlagColumn {
+    unread: Glyph,
+    starred: Glyph,
+    replied: Glyph,
+    attachment: Glyph,
+    trashed: Glyph,
+    urgent: Glyph,
+    /// Keyword glyphs, by keyword.
+    keywords: Vec<(String, Glyph)>,
+}
+
+impl FlagColumn {
+    /// Resolve glyphs and colors from the `[flag_glyphs]` config section.
+    pub fn from_config(cfg: &FlagGlyphs) -> Self {
+        let glyph = |name: &str, set: &Option<String>, unicode: &str, ascii: &str, color: Color| {
+            let text = set.clone().unwrap_or_else(|| {
+                if cfg.ascii { ascii } else { unicode }.to_string()
+            });
+            let color = cfg
+                .colors
+                .get(name)
+                .and_then(|c| Color::from_str(c).ok())
+                .unwrap_or(color);
+            Glyph { text, color }
+        };
+        Self {
+            unread: glyph("unread", &cfg.unread, "\u{25cf}", "N", Color::Cyan),
+            starred: glyph("starred", &cfg.starred, "\u{2605}", "*", Color::Yellow),
+            replied: glyph("replied", &cfg.replied, "\u{21a9}", "r", Color::DarkGray),
+            attachment: glyph("attachment", &cfg.attachment, "\u{1f4ce}", "@", Color::Magenta),
+            trashed: glyph("trashed", &cfg.trashed, "\u{2691}", "D", Color::Red),
+            urgent: glyph("urgent", &cfg.urgent, "!", "!", Color::LightRed),
+            keywords: Vec::new(),
+        }
+    }
+
+    /// Add a slot for each of `keywords`, drawn with its glyph from
+    /// `[flag_glyphs.keywords]`, else its first letter.
+    pub fn with_keywords(mut self, cfg: &FlagGlyphs, keywords: &[String]) -> Self {
+        fn find<'a>(
+            map: impl IntoIterator<Item = (&'a String, &'a String)>,
+            keyword: &str,
+        ) -> Option<&'a String> {
+            map.into_iter().find(|(k, _)| k.eq_ignore_ascii_case(keyword)).map(|(_, v)| v)
+        }
+        self.keywords = keywords
+            .iter()
+            .map(|keyword| {
+                let text = find(&cfg.keywords, keyword)
+                    .cloned()
+                    .unwrap_or_else(|| keyword.chars().take(1).flat_map(char::to_uppercase).collect());
+                let color = find(&cfg.colors, keyword)
+                    .and_then(|c| Color::from_str(c).ok())
+                    .unwrap_or(Color::LightBlue);
+                (keyword.clone(), Glyph { text, color })
+            })
+            .collect();
+        self
+    }
+
+    fn slot_widths(&self) -> [usize; 4] {
+        [
+            self.unread.text.width().max(self.trashed.text.width()),
+            self.starred.text.width(),
+            self.replied.text.width().max(self.urgent.text.width()),
+            self.attachment.text.width(),
+        ]
+    }
+
+    /// Total display width of the column.
+    pub fn width(&self) -> usize {
+        let keywords: usize = self.keywords.iter().map(|(_, g)| g.text.width()).sum();
+        self.slot_widths().iter().sum::<usize>() + keywords
+    }
+
+    /// Draw the glyphs for `state` starting at `x`.
+    pub fn render(&self, buf: &mut Buffer, x: u16, y: u16, base: Style, state: &FlagState) {
+        let first = if state.trashed {
+            Some(&self.trashed)
+        } else if state.unread {
+            Some(&self.unread)
+        } else {
+            None
+        };
+        let third = if state.replied {
+            Some(&self.replied)
+        } else if state.urgent {
+            Some(&self.urgent)
+        } else {
+            None
+        };
+        let slots = [
+            first,
+            state.starred.then_some(&self.starred),
+            third,
+            state.attachment.then_some(&self.attachment),
+        ];
+        let mut x = x;
+        for (glyph, width) in slots.iter().zip(self.slot_widths()) {
+            if let Some(glyph) = glyph {
+                buf.set_string(x, y, &glyph.text, base.fg(glyph.color));
+            }
+            x += width as u16;
+        }
+        for (keyword, glyph) in &self.keywords {
+            if state.tags.iter().any(|t| t.eq_ignore_ascii_case(keyword)) {
+                buf.set_string(x, y, &glyph.text, base.fg(glyph.color));
+            }
+            x += glyph.text.width() as u16;
+        }
+    }
+}
+
+/// Colors assigned to senders, chosen to stay readable on a dark background.
+const SENDER_PALETTE: &[Color] = &[
+    Color::Indexed(75),
+    Color::Indexed(114),
+    Color::Indexed(180),
+    Color::Indexed(176),
+    Color::Indexed(81),
+    Color::Indexed(215),
+    Color::Indexed(147),
+    Color::Indexed(150),
+    Color::Indexed(210),
+    Color::Indexed(116),
+];
+
+/// Stable color for a sender address (case-insensitive FNV-1a hash into
+/// [`SENDER_PALETTE`]), so the same correspondent always looks the same.
+pub fn sender_color(email: &str) -> Color {
+    let mut hash: u32 = 0x811c_9dc5;
+    for b in email.trim().to_lowercase().bytes() {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    SENDER_PALETTE[hash as usize % SENDER_PALETTE.len()]
+}
+
+/// Initials badge for a sender, e.g. " AL ", drawn in their
+/// [`sender_color`] like a small avatar.
+pub fn sender_badge(addr: &Address) -> (String, Style) {
+    let style = Style::default()
+        .fg(Color::Black)
+        .bg(sender_color(&addr.email))
+        .add_modifier(Modifier::BOLD);
+    (format!(" {} ", text::pad(&addr.initials(), 2)), style)
+}
+
+/// Style for a sender name: bold when unread, tinted when colors are enabled.
+fn sender_style(base: Style, email: Option<&str>, colored: bool, unread: bool) -> Style {
+    let mut style = base;
+    if colored {
+        if let Some(email) = email {
+            style = style.fg(sender_color(email));
+        }
+    }
+    if unread {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    style
+}
+
+/// X coordinate at which to draw right-aligned text `width` cells wide.
+fn right_align_x(area: Rect, width: usize) -> u16 {
+    if (area.width as usize) > width + 1 {
+        area.x + area.width - width as u16 - 1
+    } else {
+        area.x + area.width - 1
+    }
+}
+
+/// Draw `text` between `start` and `end`, followed by a dimmed ` — snippet`
+/// in whatever room is left.
+#[allow(clippy::too_many_arguments)]
+fn render_subject_snippet(
+    buf: &mut Buffer,
+    start: u16,
+    end: u16,
+    y: u16,
+    text: &str,
+    text_style: Style,
+    snippet: &str,
+    base_style: Style,
+) {
+    if start >= end {
+        return;
+    }
+    let width = (end - start) as usize;
+    let shown = text::truncate_with(text, width, "~");
+    let used = text::width(&shown);
+    buf.set_string(start, y, &shown, text_style);
+    let room = width.saturating_sub(used + 3);
+    if !snippet.is_empty() && room > 0 {
+        let tail = format!(" \u{2014} {}", text::truncate_with(snippet, room, "~"));
+        buf.set_string(start + used as u16, y, &tail, base_style.fg(Color::DarkGray));
     }
 }
 
@@ -136,6 +555,23 @@ pub struct ConversationList<'a> {
     pub selected: usize,
     pub offset: usize,
     pub multi_selected: &'a HashSet<u32>,
+    /// Docids that just arrived; conversations containing one are tinted.
+    pub new_arrivals: &'a HashSet<u32>,
+    pub density: ListDensity,
+    /// Body snippets keyed by message-id (missing entries render blank).
+    pub snippets: &'a HashMap<String, String>,
+    /// Tint sender names with [`sender_color`].
+    pub color_senders: bool,
+    pub flag_column: &'a FlagColumn,
+    /// Show each conversation's total size before the date.
+    pub show_size: bool,
+    /// Scores keyed by message-id; a conversation shows its highest.
+    pub scores: Option<&'a HashMap<String, Option<f64>>>,
+    /// Tint the date of a conversation whose newest message is unanswered.
+    pub aging: Option<Aging>,
+    /// Account badge drawn at the start of every row, when accounts are
+    /// shown together.
+    pub badge: Option<&'a AccountBadge>,
 }
 
 impl<'a> Widget for ConversationList<'a> {
@@ -151,7 +587,8 @@ impl<'a> Widget for ConversationList<'a> {
             return;
         }
 
-        let height = area.height as usize;
+        let rows = self.density.rows_per_item();
+        let height = (area.height as usize / rows).max(1);
         let (start, end) = EnvelopeList::visible_range(
             self.selected,
             self.offset,
@@ -160,114 +597,288 @@ impl<'a> Widget for ConversationList<'a> {
         );
 
         for (i, convo) in self.conversations[start..end].iter().enumerate() {
-            let y = area.y + i as u16;
+            let y = area.y + (i * rows) as u16;
             let idx = start + i;
             let is_selected = idx == self.selected;
             let is_unread = convo.has_unread();
-            let is_flagged = convo.has_flagged();
             // Check if any docid in this conversation is multi-selected
             let is_multi = convo
-                .all_docids()
+                .messages
+                .iter()
+                .any(|e| self.multi_selected.contains(&e.docid));
+            let is_new = convo
+                .messages
                 .iter()
-                .any(|d| self.multi_selected.contains(d));
+                .any(|e| self.new_arrivals.contains(&e.docid));
 
-            let base_style = if is_selected {
-                Style::default().bg(Color::Indexed(236)).fg(Color::White)
-            } else {
-                Style::default()
-            };
+            let base_style = row_style(is_selected, is_new);
 
-            // Fill the line with background
-            buf.set_style(Rect::new(area.x, y, area.width, 1), base_style);
+            // Fill the row with background
+            buf.set_style(Rect::new(area.x, y, area.width, rows as u16), base_style);
 
             let w = area.width as usize;
+            let snippet = self.snippets
+                .get(&convo.representative().message_id)
+                .map(|s| s.as_str())
+                .unwrap_or("");
 
-            // Multi-select / unread / flag indicator (2 chars)
-            // Conversations use » instead of > to signal grouped messages
+            // Multi-select marker (» for grouped threads), the account badge,
+            // then the flag glyph column
             let is_thread = convo.messages.len() > 1;
-            let indicator = if is_multi {
-                "x "
-            } else if is_flagged {
-                "* "
-            } else if is_unread {
-                if is_thread { "\u{00bb} " } else { "> " }
+            if is_multi {
+                let style = base_style.fg(Color::Green).add_modifier(Modifier::BOLD);
+                buf.set_string(area.x, y, "x", style);
             } else if is_thread {
-                "\u{00bb} "
-            } else {
-                "  "
-            };
-            let ind_style = if is_multi {
-                base_style.fg(Color::Green).add_modifier(Modifier::BOLD)
-            } else if is_flagged {
-                base_style.fg(Color::Yellow)
-            } else if is_unread {
-                base_style.fg(Color::Cyan).add_modifier(Modifier::BOLD)
-            } else {
-                base_style.fg(Color::DarkGray)
-            };
-            buf.set_string(area.x, y, indicator, ind_style);
+                buf.set_string(area.x, y, "\u{00bb}", base_style.fg(Color::DarkGray));
+            }
+            let badged = AccountBadge::render(self.badge, buf, area.x + 1, y, base_style);
+            self.flag_column.render(buf, area.x + 1 + badged, y, base_style, &FlagState::of_conversation(convo));
+            let lead = badged + self.flag_column.width() as u16 + 2;
 
-            // Senders (up to 20 chars)
             let senders = convo.senders();
-            let senders_width = 20.min(w.saturating_sub(2));
-            let senders_truncated = truncate_str(&senders, senders_width);
-            let senders_style = if is_unread {
-                base_style.add_modifier(Modifier::BOLD)
-            } else {
+            let senders_style = sender_style(
+                base_style,
+                convo.messages.first().and_then(|m| m.from.first()).map(|a| a.email.as_str()),
+                self.color_senders,
+                is_unread,
+            );
+            let subj_style = if is_unread {
                 base_style
+            } else {
+                base_style.fg(Color::Gray)
             };
-            buf.set_string(area.x + 2, y, &senders_truncated, senders_style);
+            let size = convo.messages.iter().map(|e| e.size).sum();
+            let date = date_text(convo.date_display(), size, self.show_size);
+            let date = scored_text(date, self.scores, convo.messages.iter().map(|e| e.message_id.as_str()));
+            let date_x = right_align_x(area, text::width(&date));
+            buf.set_string(date_x, y, &date, date_style(base_style, self.aging, convo.messages.last()));
 
-            // Date (right-aligned, ~10 chars)
-            let date = convo.date_display();
-            let date_width = date.len();
-            let date_x = if w > date_width + 1 {
-                area.x + area.width - date_width as u16 - 1
+            // Subject + count badge
+            let count = convo.message_count();
+            let badge = if count > 1 {
+                format!(" ({})", count)
             } else {
-                area.x + area.width - 1
+                String::new()
             };
-            let date_style = base_style.fg(Color::DarkGray);
-            buf.set_string(date_x, y, &date, date_style);
-
-            // Subject + count badge (fills the middle)
-            let subject_start = area.x + 2 + senders_width as u16 + 1;
-            let subject_end = date_x.saturating_sub(1);
-            if subject_start < subject_end {
-                let subject_width = (subject_end - subject_start) as usize;
-                let count = convo.message_count();
-                let badge = if count > 1 {
-                    format!(" ({})", count)
-                } else {
-                    String::new()
-                };
-                let subj_text = convo.subject();
-                let avail = subject_width.saturating_sub(badge.len());
-                let mut display = truncate_str(subj_text, avail);
+            let subject_line = |width: usize| -> String {
+                let avail = width.saturating_sub(badge.len());
+                let mut display = text::truncate_with(convo.subject(), avail, "~");
                 display.push_str(&badge);
-                let subj_style = if is_unread {
-                    base_style
-                } else {
-                    base_style.fg(Color::Gray)
-                };
-                buf.set_string(subject_start, y, &display, subj_style);
+                display
+            };
+
+            match self.density {
+                ListDensity::Compact => {
+                    // Senders (up to 20 chars)
+                    let senders_width = 20.min(w.saturating_sub(lead as usize));
+                    let senders_truncated = text::truncate_with(&senders, senders_width, "~");
+                    buf.set_string(area.x + lead, y, &senders_truncated, senders_style);
+
+                    // Subject, badge, then snippet (fills the middle)
+                    let subject_start = area.x + lead + senders_width as u16 + 1;
+                    let subject_end = date_x.saturating_sub(1);
+                    if subject_start < subject_end {
+                        let display = subject_line((subject_end - subject_start) as usize);
+                        render_subject_snippet(
+                            buf, subject_start, subject_end, y,
+                            &display, subj_style, snippet, base_style,
+                        );
+                    }
+                }
+                ListDensity::Relaxed => {
+                    // Line one: subject + badge and date
+                    let subject_end = date_x.saturating_sub(1);
+                    if area.x + lead < subject_end {
+                        let display = subject_line((subject_end - area.x - lead) as usize);
+                        let subject_style = if is_unread {
+                            base_style.add_modifier(Modifier::BOLD)
+                        } else {
+                            subj_style
+                        };
+                        buf.set_string(area.x + lead, y, &display, subject_style);
+                    }
+
+                    // Line two: senders then snippet
+                    render_subject_snippet(
+                        buf, area.x + lead, area.x + area.width.saturating_sub(1), y + 1,
+                        &senders, senders_style, snippet, base_style,
+                    );
+                }
             }
         }
     }
 }
 
-/// Truncate a string to fit within `max_width` characters, adding "..." if needed.
-fn truncate_str(s: &str, max_width: usize) -> String {
-    if max_width == 0 {
-        return String::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_column_width_is_fixed() {
+        let unicode = FlagColumn::from_config(&FlagGlyphs::default());
+        // ● ★ ↩ are one cell each, 📎 is two
+        assert_eq!(unicode.width(), 5);
+
+        let ascii = FlagColumn::from_config(&FlagGlyphs { ascii: true, ..Default::default() });
+        assert_eq!(ascii.width(), 4);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 1));
+        let state = FlagState { unread: true, replied: true, ..Default::default() };
+        ascii.render(&mut buf, 0, 0, Style::default(), &state);
+        let row: String = (0..4).map(|x| buf[(x, 0)].symbol().to_string()).collect();
+        assert_eq!(row, "N r ");
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 1));
+        let state = FlagState { urgent: true, ..Default::default() };
+        ascii.render(&mut buf, 0, 0, Style::default(), &state);
+        let row: String = (0..4).map(|x| buf[(x, 0)].symbol().to_string()).collect();
+        assert_eq!(row, "  ! ");
+        assert_eq!(buf[(2, 0)].fg, Color::LightRed);
     }
-    let chars: Vec<char> = s.chars().collect();
-    if chars.len() <= max_width {
-        s.to_string()
-    } else if max_width <= 1 {
-        "~".to_string()
-    } else {
-        let mut result: String = chars[..max_width - 1].iter().collect();
-        result.push('~');
-        result
+
+    #[test]
+    fn flag_column_config_overrides() {
+        let cfg = FlagGlyphs {
+            starred: Some("!".into()),
+            colors: [("starred".to_string(), "green".to_string())].into(),
+            ..Default::default()
+        };
+        let column = FlagColumn::from_config(&cfg);
+        assert_eq!(column.starred.text, "!");
+        assert_eq!(column.starred.color, Color::Green);
+        assert_eq!(column.unread.text, "\u{25cf}");
+
+        let cfg = FlagGlyphs {
+            ascii: true,
+            keywords: [("waiting".to_string(), "W".to_string())].into(),
+            ..Default::default()
+        };
+        let column = FlagColumn::from_config(&cfg).with_keywords(&cfg, &["todo".into(), "waiting".into()]);
+        assert_eq!(column.width(), 6);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 6, 1));
+        let state = FlagState { tags: vec!["Waiting".into()], ..Default::default() };
+        column.render(&mut buf, 0, 0, Style::default(), &state);
+        let row: String = (0..6).map(|x| buf[(x, 0)].symbol().to_string()).collect();
+        assert_eq!(row, "     W");
+        assert_eq!(buf[(5, 0)].fg, Color::LightBlue);
+    }
+
+    #[test]
+    fn score_column_shows_highest() {
+        let scores: HashMap<String, Option<f64>> =
+            [("a".to_string(), Some(2.5)), ("b".to_string(), Some(7.3)), ("c".to_string(), None)].into();
+        assert_eq!(scored_text("Mar 3".into(), None, ["a"].into_iter()), "Mar 3");
+        assert_eq!(scored_text("Mar 3".into(), Some(&scores), ["a", "b", "c"].into_iter()), "   7.3  Mar 3");
+        assert_eq!(scored_text("Mar 3".into(), Some(&scores), ["c", "d"].into_iter()), "        Mar 3");
+    }
+
+    #[test]
+    fn section_headers_above_rows() {
+        let envelopes: Vec<Envelope> = ["Lunch?", "Weekly digest"]
+            .iter()
+            .map(|s| Envelope { subject: s.to_string(), ..Envelope::default() })
+            .collect();
+        let header = |name: &str, start, count, collapsed| SectionHeader {
+            name: name.to_string(),
+            start,
+            count,
+            unread: count,
+            collapsed,
+        };
+        let sections = vec![
+            header("Primary", 0, 1, false),
+            header("Lists", 1, 1, false),
+            header("Notifications", 2, 4, true),
+        ];
+        let (none, snippets) = (HashSet::new(), HashMap::new());
+        let flag_column = FlagColumn::from_config(&FlagGlyphs::default());
+        let list = EnvelopeList {
+            envelopes: &envelopes,
+            selected: 0,
+            offset: 0,
+            multi_selected: &none,
+            new_arrivals: &none,
+            density: ListDensity::Compact,
+            snippets: &snippets,
+            color_senders: false,
+            flag_column: &flag_column,
+            show_size: false,
+            scores: None,
+            sections: &sections,
+            aging: None,
+            collapsed: &HashMap::new(),
+            badge: None,
+        };
+        let area = Rect::new(0, 0, 60, 6);
+        let mut buf = Buffer::empty(area);
+        list.render(area, &mut buf);
+        let line = |y: u16| (0..60).map(|x| buf[(x, y)].symbol().to_string()).collect::<String>();
+        assert!(line(0).starts_with(" \u{25be} Primary  1 \u{00b7} 1 unread"), "{}", line(0));
+        assert!(line(1).contains("Lunch?"));
+        assert!(line(2).starts_with(" \u{25be} Lists  1"));
+        assert!(line(3).contains("Weekly digest"));
+        assert!(line(4).starts_with(" \u{25b8} Notifications  4"));
+        assert_eq!(EnvelopeList::capacity(6, ListDensity::Compact, sections.len()), 3);
+    }
+
+    #[test]
+    fn account_badge_leads_each_row() {
+        let envelopes = vec![Envelope { subject: "Lunch?".into(), ..Envelope::default() }];
+        let (none, snippets) = (HashSet::new(), HashMap::new());
+        let flag_column = FlagColumn::from_config(&FlagGlyphs { ascii: true, ..Default::default() });
+        let badge = AccountBadge { text: "[W]".into(), color: Color::Cyan };
+        let list = EnvelopeList {
+            envelopes: &envelopes,
+            selected: 0,
+            offset: 0,
+            multi_selected: &none,
+            new_arrivals: &none,
+            density: ListDensity::Compact,
+            snippets: &snippets,
+            color_senders: false,
+            flag_column: &flag_column,
+            show_size: false,
+            scores: None,
+            sections: &[],
+            aging: None,
+            collapsed: &HashMap::new(),
+            badge: Some(&badge),
+        };
+        let area = Rect::new(0, 0, 60, 1);
+        let mut buf = Buffer::empty(area);
+        list.render(area, &mut buf);
+        let line: String = (0..20).map(|x| buf[(x, 0)].symbol().to_string()).collect();
+        assert_eq!(line, " [W] N    (unknown) ");
+        assert_eq!(buf[(2, 0)].fg, Color::Cyan);
+    }
+
+    #[test]
+    fn aging_colors_by_days_waiting() {
+        let aging = Aging { amber_days: 3, red_days: 7 };
+        let now = Utc::now();
+        let ago = |hours: i64| now - chrono::Duration::hours(hours);
+        assert_eq!(aging.color(ago(72), now), None);
+        assert_eq!(aging.color(ago(73), now), Some(Color::Yellow));
+        assert_eq!(aging.color(ago(24 * 7 + 1), now), Some(Color::Red));
+        let red_only = Aging { amber_days: 0, ..aging };
+        assert_eq!(red_only.color(ago(100), now), None);
+
+        let mut envelope = Envelope { date: ago(200), ..Envelope::default() };
+        let base = Style::default();
+        assert_eq!(date_style(base, Some(aging), Some(&envelope)).fg, Some(Color::Red));
+        assert_eq!(date_style(base, None, Some(&envelope)).fg, Some(Color::DarkGray));
+        envelope.flags.push(crate::envelope::Flag::Replied);
+        assert_eq!(date_style(base, Some(aging), Some(&envelope)).fg, Some(Color::DarkGray));
+    }
+
+    #[test]
+    fn sender_color_is_stable() {
+        assert_eq!(sender_color("alice@example.com"), sender_color("Alice@Example.com"));
+        assert!(SENDER_PALETTE.contains(&sender_color("bob@example.com")));
+        let distinct: HashSet<_> = ["a@x.org", "b@x.org", "c@x.org", "d@x.org", "e@x.org"]
+            .iter()
+            .map(|e| format!("{:?}", sender_color(e)))
+            .collect();
+        assert!(distinct.len() > 1);
     }
 }
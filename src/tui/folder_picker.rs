@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -5,11 +7,19 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Widget},
 };
 
+use super::text;
+
 pub struct FolderPicker<'a> {
     pub folders: &'a [String],
     pub selected: usize,
     pub filter: &'a str,
     pub title: &'a str,
+    /// The first `favorites` entries are pinned favorites, and the next
+    /// `recents` are recently used folders; both are tagged on the right.
+    pub favorites: usize,
+    pub recents: usize,
+    /// Background match counts for smart folders, shown right-aligned
+    pub counts: &'a HashMap<String, u32>,
 }
 
 /// Compute a centered rectangle of the given width and height within `area`.
@@ -91,7 +101,7 @@ impl<'a> Widget for FolderPicker<'a> {
         buf.set_string(inner.x, inner.y, prompt, filter_style);
         buf.set_string(inner.x + 2, inner.y, self.filter, filter_style);
         // Cursor block after the filter text
-        let cursor_x = inner.x + 2 + self.filter.len() as u16;
+        let cursor_x = inner.x + 2 + text::width(self.filter) as u16;
         if cursor_x < inner.x + inner.width {
             buf.set_string(cursor_x, inner.y, " ", cursor_style);
         }
@@ -168,10 +178,35 @@ impl<'a> Widget for FolderPicker<'a> {
                 buf.set_style(Rect::new(inner.x, y, inner.width, 1), style);
             }
 
-            // Truncate folder name to fit
-            let max_w = inner.width as usize;
-            let display = truncate_str(&display, max_w);
+            let tag = if display_idx < self.favorites {
+                Some("favorite")
+            } else if display_idx < self.favorites + self.recents {
+                Some("recent")
+            } else {
+                None
+            };
+            let count = self.counts.get(folder.as_str());
+            let right = match (count, tag) {
+                (Some(n), Some(tag)) => format!("{} {}", n, tag),
+                (Some(n), None) => n.to_string(),
+                (None, Some(tag)) => tag.to_string(),
+                (None, None) => String::new(),
+            };
+
+            // Truncate folder name to fit (leaving room for the count/tag)
+            let right_w = if right.is_empty() { 0 } else { text::width(&right) + 2 };
+            let max_w = (inner.width as usize).saturating_sub(1 + right_w);
+            let display = text::truncate(&display, max_w);
             buf.set_string(inner.x + 1, y, &display, style);
+            if !right.is_empty() {
+                let right_x = inner.x + inner.width - text::width(&right) as u16 - 1;
+                let right_style = if is_selected {
+                    style.remove_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                buf.set_string(right_x, y, &right, right_style);
+            }
         }
 
         // If no matches, show hint
@@ -346,7 +381,7 @@ impl<'a> Widget for SmartFolderPopup<'a> {
             // Not editing name — show it as static text if we have one
             if !self.name.is_empty() {
                 buf.set_string(inner.x, y, "Name:  ", label_style);
-                let name_display = truncate_str(self.name, query_field_width);
+                let name_display = text::truncate(self.name, query_field_width);
                 buf.set_string(inner.x + label_len, y, &name_display, text_style);
             }
             y += 1;
@@ -376,7 +411,7 @@ impl<'a> Widget for SmartFolderPopup<'a> {
                 if y >= inner.y + inner.height {
                     break;
                 }
-                let display = truncate_str(subject, inner.width as usize);
+                let display = text::truncate(subject, inner.width as usize);
                 buf.set_string(inner.x + 1, y, &display, Style::default().fg(Color::DarkGray));
                 y += 1;
             }
@@ -472,9 +507,9 @@ impl<'a> Widget for MaildirCreatePopup<'a> {
         let cursor_style = Style::default().fg(Color::White).bg(Color::Gray);
 
         buf.set_string(inner.x, inner.y, "Path: ", label_style);
-        let display = truncate_str(self.input, (inner.width as usize).saturating_sub(7));
+        let display = text::truncate(self.input, (inner.width as usize).saturating_sub(7));
         buf.set_string(inner.x + 6, inner.y, &display, text_style);
-        let cx = inner.x + 6 + self.input.len().min(inner.width as usize - 7) as u16;
+        let cx = inner.x + 6 + text::width(self.input).min(inner.width as usize - 7) as u16;
         if cx < inner.x + inner.width {
             buf.set_string(cx, inner.y, " ", cursor_style);
         }
@@ -493,23 +528,6 @@ impl<'a> Widget for MaildirCreatePopup<'a> {
     }
 }
 
-/// Truncate a string to fit within `max_width` characters, adding "\u{2026}" if needed.
-fn truncate_str(s: &str, max_width: usize) -> String {
-    if max_width == 0 {
-        return String::new();
-    }
-    let chars: Vec<char> = s.chars().collect();
-    if chars.len() <= max_width {
-        s.to_string()
-    } else if max_width <= 1 {
-        "\u{2026}".to_string()
-    } else {
-        let mut result: String = chars[..max_width - 1].iter().collect();
-        result.push('\u{2026}');
-        result
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
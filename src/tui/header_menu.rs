@@ -0,0 +1,153 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::folder_picker::centered_rect;
+use super::text;
+use crate::envelope::{Address, Envelope};
+
+/// Keys offered under the list of header targets.
+const KEYS: &str = "Enter:search c:compose a:contact r:rule y:copy Esc:close";
+
+/// Something in the preview header the menu can act on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderTarget {
+    /// Header it came from: "From", "To", "Cc" or "List".
+    pub field: &'static str,
+    /// As shown in the menu.
+    pub text: String,
+    /// Bare address, or the List-Id.
+    pub value: String,
+    /// The person's name, when the header gives one.
+    pub name: Option<String>,
+}
+
+impl HeaderTarget {
+    pub fn is_list(&self) -> bool {
+        self.field == "List"
+    }
+
+    /// mu query for mail from this address, or sent through this list.
+    pub fn query(&self) -> String {
+        if self.is_list() {
+            format!("list:{}", self.value)
+        } else {
+            format!("from:{}", self.value)
+        }
+    }
+
+    /// Name for a split built from this target: the person's name, or the
+    /// address or list without its domain.
+    pub fn rule_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.value.split(['@', '.']).next().unwrap_or(&self.value).to_string(),
+        }
+    }
+
+    /// The address to write to; lists have none.
+    pub fn address(&self) -> Option<Address> {
+        (!self.is_list()).then(|| Address { name: self.name.clone(), email: self.value.clone() })
+    }
+}
+
+/// The addresses in a message's From, To and Cc, each once, then its list.
+pub fn targets(envelope: &Envelope) -> Vec<HeaderTarget> {
+    let mut targets: Vec<HeaderTarget> = Vec::new();
+    let fields = [("From", &envelope.from), ("To", &envelope.to), ("Cc", &envelope.cc)];
+    for (field, addresses) in fields {
+        for address in addresses.iter() {
+            if targets.iter().any(|t| t.value.eq_ignore_ascii_case(&address.email)) {
+                continue;
+            }
+            targets.push(HeaderTarget {
+                field,
+                text: address.to_string(),
+                value: address.email.clone(),
+                name: address.name.clone(),
+            });
+        }
+    }
+    if let Some(list) = &envelope.list {
+        targets.push(HeaderTarget { field: "List", text: list.clone(), value: list.clone(), name: None });
+    }
+    targets
+}
+
+/// Popup listing the header targets, the selected one highlighted.
+pub struct HeaderMenu<'a> {
+    pub targets: &'a [HeaderTarget],
+    pub selected: usize,
+}
+
+impl<'a> Widget for HeaderMenu<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let widest = self.targets.iter().map(|t| text::width(&t.text) + 7).max().unwrap_or(0);
+        let popup_width = (widest.max(text::width(KEYS)) as u16 + 4).min(area.width);
+        let popup_height = (self.targets.len() as u16 + 3).min(area.height);
+        let popup = centered_rect(popup_width, popup_height, area);
+
+        Clear.render(popup, buf);
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(" Header Actions ")
+            .title_style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            .render(popup, buf);
+
+        let inner = Rect::new(
+            popup.x + 1,
+            popup.y + 1,
+            popup.width.saturating_sub(2),
+            popup.height.saturating_sub(2),
+        );
+        if inner.width < 2 || inner.height == 0 {
+            return;
+        }
+
+        // Keep the selection in view when the terminal is short
+        let rows = inner.height.saturating_sub(1) as usize;
+        let skip = (self.selected + 1).saturating_sub(rows);
+        let width = (inner.width - 1) as usize;
+        for (i, target) in self.targets.iter().enumerate().skip(skip).take(rows) {
+            let y = inner.y + (i - skip) as u16;
+            let style = if i == self.selected {
+                Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let line = format!("{:<5}  {}", target.field, target.text);
+            buf.set_string(inner.x + 1, y, text::pad(&text::truncate(&line, width), width), style);
+        }
+        let keys = text::truncate(KEYS, width);
+        buf.set_string(inner.x + 1, inner.y + inner.height - 1, keys, Style::default().fg(Color::DarkGray));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_targets_and_queries() {
+        let address = |name: Option<&str>, email: &str| Address { name: name.map(String::from), email: email.into() };
+        let envelope = Envelope {
+            from: vec![address(Some("Alice Liddell"), "alice@example.com")],
+            to: vec![address(None, "dev@lists.example.org"), address(None, "Alice@Example.com")],
+            list: Some("dev.lists.example.org".into()),
+            ..Envelope::default()
+        };
+        let targets = targets(&envelope);
+        let fields: Vec<&str> = targets.iter().map(|t| t.field).collect();
+        assert_eq!(fields, ["From", "To", "List"]);
+        assert_eq!(targets[0].query(), "from:alice@example.com");
+        assert_eq!(targets[0].rule_name(), "Alice Liddell");
+        assert_eq!(targets[1].rule_name(), "dev");
+        assert_eq!(targets[2].query(), "list:dev.lists.example.org");
+        assert_eq!(targets[2].rule_name(), "dev");
+        assert!(targets[2].address().is_none());
+    }
+}
@@ -0,0 +1,93 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::folder_picker::centered_rect;
+use crate::oplog::OpRecord;
+
+/// Popup listing recent operations from the per-account log, newest first.
+pub struct HistoryOverlay<'a> {
+    pub scroll: u16,
+    pub records: &'a [OpRecord],
+}
+
+impl<'a> Widget for HistoryOverlay<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width: u16 = area.width.saturating_sub(4).clamp(40, 100);
+        let popup_height: u16 = area.height.clamp(10, 30);
+        let popup = centered_rect(popup_width, popup_height, area);
+
+        Clear.render(popup, buf);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(" Operations History ")
+            .title_style(
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+        block.render(popup, buf);
+
+        let inner = Rect::new(
+            popup.x + 1,
+            popup.y + 1,
+            popup.width.saturating_sub(2),
+            popup.height.saturating_sub(2),
+        );
+
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        let time_style = Style::default().fg(Color::DarkGray);
+        let op_style = Style::default().fg(Color::Cyan);
+        let detail_style = Style::default().fg(Color::White);
+        let subject_style = Style::default().fg(Color::Gray);
+
+        if self.records.is_empty() {
+            buf.set_string(inner.x + 1, inner.y, "No operations logged yet", time_style);
+            return;
+        }
+
+        // Body height leaves room for the footer line
+        let body_height = inner.height.saturating_sub(1) as usize;
+        let max_scroll = self.records.len().saturating_sub(body_height);
+        let scroll = (self.scroll as usize).min(max_scroll);
+
+        for (i, record) in self.records.iter().skip(scroll).take(body_height).enumerate() {
+            let y = inner.y + i as u16;
+            let mut x = inner.x + 1;
+            let right = inner.x + inner.width;
+
+            let time = record.time.format("%Y-%m-%d %H:%M ").to_string();
+            let op = format!("{:7}", record.op.label());
+            for (text, style) in [
+                (time, time_style),
+                (op, op_style),
+                (record.detail.clone(), detail_style),
+            ] {
+                if x >= right {
+                    break;
+                }
+                let (nx, _) = buf.set_stringn(x, y, &text, (right - x) as usize, style);
+                x = nx;
+            }
+            if !record.subject.is_empty() && x + 3 < right {
+                let subject = format!("  {}", record.subject);
+                buf.set_stringn(x, y, &subject, (right - x) as usize, subject_style);
+            }
+        }
+
+        buf.set_string(
+            inner.x,
+            inner.y + inner.height - 1,
+            " j/k:scroll  q/Esc:close",
+            Style::default().fg(Color::DarkGray),
+        );
+    }
+}
@@ -1,9 +1,17 @@
+pub mod attachment_browser;
 pub mod command_palette;
+pub mod compare;
+pub mod deleted_overlay;
 pub mod envelope_list;
 pub mod folder_picker;
+pub mod header_menu;
 pub mod help_overlay;
+pub mod history_overlay;
 pub mod preview;
+pub mod recipient_check;
 pub mod status_bar;
+pub mod text;
+pub mod text_overlay;
 pub mod thread_view;
 
 use std::collections::HashSet;
@@ -17,34 +25,70 @@ use crossterm::{
 };
 use futures::StreamExt;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     Terminal,
 };
-use std::io;
+use std::io::{self, IsTerminal};
 use std::time::Duration;
 use tokio::time::Instant;
 
 use std::collections::HashMap;
 
+use crate::bidi;
 use crate::compose;
-use crate::config::Config;
-use crate::envelope::{flags_from_string, group_into_conversations, Conversation, Envelope};
+use crate::config::{shell_quote, Config, ListDensity, LowPower};
+use crate::contacts::{self, Contact};
+use crate::counts::CountScheduler;
+use crate::digest;
+use crate::envelope::{
+    collapse_near_duplicates, flags_from_string, group_into_conversations, Address, Conversation, Envelope, Flag,
+};
+use crate::export;
+use crate::folder_scan;
+use crate::followups::{self, FollowUp};
+use crate::health;
+use crate::held_triage::{self, DeferredTriage};
+use crate::http_api;
+use crate::inbox_sections::{InboxSections, SectionHeader};
 use crate::keymap::{Action, InputMode, KeyMapper, SortField};
+use crate::keywords;
 use crate::links::{self, HuttUrl, IpcCommand, IpcListener, IpcResponse};
-use crate::maildir::{expand_maildir_root, save_to_sent};
+use crate::maildir::{self, expand_maildir_root, save_to_sent};
 use crate::mime_render::{self, RenderCache};
-use crate::mu_client::{FindOpts, MuClient};
+use crate::mu_client::{self, FindOpts, FindProgress, MuClient};
+use crate::notes::{self, Note};
+use crate::oplog::{self, OpKind, OpRecord};
+use crate::patch;
+use crate::power;
+use crate::redact;
+use crate::registry::MessageRegistry;
 use crate::send;
+use crate::session::{self, FolderPrefs, ReadPositions, Session, ViewState};
 use crate::smart_folders::{self, SmartFolder};
+use crate::scores;
+use crate::screener;
+use crate::snippets;
+use crate::status_file;
 use crate::splits::{self, Split};
+use crate::templates;
+use crate::triage_stats;
+use crate::tutorial::{self, Tutorial};
 use crate::undo::{UndoAction, UndoEntry, UndoStack};
+use crate::warm_start::{self, WarmStart};
 
+use self::attachment_browser::{AttachmentBrowser, AttachmentItem};
 use self::command_palette::{CommandPalette, PaletteEntry};
-use self::envelope_list::{ConversationList, EnvelopeList};
+use self::deleted_overlay::{DeletedMessage, DeletedOverlay};
+use self::envelope_list::{Aging, ConversationList, EnvelopeList, FlagColumn};
 use self::folder_picker::FolderPicker;
+use self::compare::{Compare, ComparePane, PaneTitle};
+use self::header_menu::{HeaderMenu, HeaderTarget};
+use self::recipient_check::{RecipientCheck, RecipientCheckPopup};
 use self::help_overlay::HelpOverlay;
+use self::history_overlay::HistoryOverlay;
 use self::preview::PreviewPane;
-use self::status_bar::{BottomBar, TopBar};
+use self::status_bar::{thousands, BottomBar, FrameStats, HealthBanner, TopBar};
+use self::text_overlay::TextOverlay;
 use self::thread_view::{ThreadMessage, ThreadView};
 use tui_textarea::{TextArea, Input, Key, CursorMove};
 
@@ -109,7 +153,7 @@ pub struct PrefetchItem {
 }
 
 /// Partial caches hold the first ~100 envelopes for instant display;
-/// full caches hold the complete result set (up to 10,000).
+/// full caches hold the complete result set (up to `max_results`).
 #[derive(Clone)]
 pub enum CacheEntry {
     Partial(Vec<Envelope>),
@@ -131,9 +175,86 @@ pub struct TabRegion {
 pub enum TabRegionKind {
     Account,
     Tab(usize),   // index into app.tabs
+    View(usize),  // index into app.views
     Overflow,     // the "…" button
 }
 
+/// Whether a picker entry matches the (lowercased) filter. Smart folders
+/// (`@Name`) and splits (`#Name`) also match on the bare name.
+fn folder_matches(folder: &str, filter: &str) -> bool {
+    filter.is_empty()
+        || folder.to_lowercase().contains(filter)
+        || folder
+            .strip_prefix(['@', '#'])
+            .is_some_and(|name| name.to_lowercase().contains(filter))
+}
+
+/// Starting folder list for an account: the folders its `[accounts.folders]`
+/// maps to, skipping any (other than the inbox) that don't exist on disk.
+fn seed_known_folders(account: Option<&crate::config::AccountConfig>) -> Vec<String> {
+    let Some(account) = account else {
+        return vec![crate::config::FolderConfig::default().inbox];
+    };
+    let root = std::path::PathBuf::from(expand_maildir_root(&account.maildir));
+    let f = &account.folders;
+    let mut folders = vec![f.inbox.clone()];
+    for folder in [&f.archive, &f.drafts, &f.sent, &f.trash, &f.spam] {
+        if !folders.contains(folder) && maildir::folder_exists(&root, folder) {
+            folders.push(folder.clone());
+        }
+    }
+    folders
+}
+
+/// Most messages scanned when browsing attachments.
+const ATTACHMENT_SEARCH_LIMIT: u32 = 500;
+
+/// Number of operations shown in the history overlay.
+const HISTORY_LIMIT: usize = 200;
+
+/// Most deletions "Recently Deleted" looks up.
+const DELETED_LIMIT: usize = 500;
+
+/// Length of the body snippets shown in the envelope list.
+const SNIPPET_CHARS: usize = 100;
+
+/// How long a folder load runs before its progress is shown.
+const FIND_PROGRESS_DELAY: Duration = Duration::from_millis(500);
+
+/// How long messages that arrived with a background reload stay tinted.
+const NEW_ARRIVAL_HIGHLIGHT: Duration = Duration::from_secs(10);
+
+/// How long a search view waits after a flag change before re-running,
+/// so a run of toggles doesn't reload the list under each keypress.
+const VIEW_REFRESH_DELAY: Duration = Duration::from_millis(1500);
+
+/// How long the main loop waits for input before its idle work in
+/// low-power mode, rather than a tenth of a second.
+const LOW_POWER_POLL: Duration = Duration::from_secs(1);
+
+/// Most messages one export takes; well past any real search.
+const EXPORT_LIMIT: u32 = 1_000_000;
+
+/// How long quitting waits for a running sync or reindex to finish.
+const QUIT_GRACE: Duration = Duration::from_secs(10);
+
+/// How often the screen is redrawn while nothing changes.
+const IDLE_REDRAW: Duration = Duration::from_secs(1);
+
+/// Initial width of the envelope list, as a percentage of the screen.
+const DEFAULT_LIST_PCT: u16 = 35;
+
+/// Most contacts fetched for the misspelled-recipient check.
+const CONTACTS_LIMIT: u32 = 5000;
+
+/// Lowest `max_results` honoured, so a full load is always more than the
+/// partial one.
+const MIN_MAX_RESULTS: u32 = 1000;
+
+/// Most messages counted for "showing N of M", or loaded when asked to
+/// load them all.
+const LOAD_ALL_LIMIT: u32 = 1_000_000;
+
 /// Format a `maildir:` query term with proper quoting.
 /// Paths containing special characters (brackets, spaces) must be quoted
 /// for mu's Xapian query parser to handle them correctly.
@@ -141,6 +262,21 @@ fn maildir_term(folder: &str) -> String {
     format!("maildir:\"{}\"" , folder)
 }
 
+/// The query terms the needs-reply filter adds: unreplied, plus the
+/// configured `needs_reply_query` with `{email}` filled in.
+fn needs_reply_terms(extra: Option<&str>, email: &str) -> String {
+    match extra.map(str::trim).filter(|q| !q.is_empty()) {
+        Some(q) => format!("NOT flag:replied AND ({})", q.replace("{email}", email)),
+        None => "NOT flag:replied".to_string(),
+    }
+}
+
+/// Build a mu `path:` query term for a message file, quoted so paths with
+/// spaces or maildir flag suffixes (`:2,S`) survive query parsing.
+fn path_term(path: &str) -> String {
+    format!("path:\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
 /// Resolve the tab list from config + runtime folder data.
 ///
 /// `config_tabs` is the user's `tabs` list (or None for default).
@@ -197,13 +333,101 @@ fn resolve_tabs(
     result
 }
 
+/// The folder `delta` steps from `current` in a cycle list, wrapping at
+/// either end. From a folder not in the list, forward goes to the first
+/// entry and backward to the last.
+fn cycle_step(list: &[String], current: &str, delta: i32) -> Option<String> {
+    if list.is_empty() {
+        return None;
+    }
+    let len = list.len() as i32;
+    let next = match list.iter().position(|f| f == current) {
+        Some(cur) => ((cur as i32 + delta) % len + len) % len,
+        None if delta > 0 => 0,
+        None => len - 1,
+    };
+    Some(list[next as usize].clone())
+}
+
+/// Flags whose local changes are protected from being reverted by a sync.
+const TRACKED_FLAGS: [char; 2] = ['S', 'F'];
+
+/// Messages whose read/starred state no longer matches what was set
+/// locally this session. Returns `(docid, flags)` pairs, where `flags` is
+/// the message's current flag string with the local state reapplied.
+fn flag_conflicts(local: &HashMap<String, String>, envelopes: &[Envelope]) -> Vec<(u32, String)> {
+    envelopes
+        .iter()
+        .filter_map(|e| {
+            let wanted = local.get(&e.message_id)?;
+            let current = e.flags_string();
+            let differs = TRACKED_FLAGS
+                .iter()
+                .any(|c| current.contains(*c) != wanted.contains(*c));
+            if !differs {
+                return None;
+            }
+            let mut flags: String = current.chars().filter(|c| !TRACKED_FLAGS.contains(c)).collect();
+            flags.extend(wanted.chars().filter(|c| TRACKED_FLAGS.contains(c)));
+            Some((e.docid, flags))
+        })
+        .collect()
+}
+
+fn triage_target(e: &Envelope) -> (u32, String, String, String) {
+    (e.docid, e.maildir.clone(), e.flags_string(), e.message_id.clone())
+}
+
+/// `flags` with `flag` added or removed.
+fn with_flag(flags: &str, flag: char, set: bool) -> String {
+    let mut out: String = flags.chars().filter(|c| *c != flag).collect();
+    if set {
+        out.push(flag);
+    }
+    out
+}
+
+/// Set an envelope's flags from a maildir flag string, keeping the ones
+/// mu derives from content rather than the filename.
+fn set_envelope_flags(e: &mut Envelope, flags: &str) {
+    let derived: Vec<Flag> = e.flags
+        .iter()
+        .filter(|f| matches!(f, Flag::List | Flag::Attach))
+        .cloned()
+        .collect();
+    e.flags = flags_from_string(flags);
+    e.flags.extend(derived);
+}
+
+/// Of the copies of a message mu found, the one in `maildir`, or else any.
+fn pick_copy(copies: Vec<Envelope>, maildir: &str) -> Option<Envelope> {
+    let i = copies.iter().position(|e| e.maildir == maildir).unwrap_or(0);
+    copies.into_iter().nth(i)
+}
+
 /// Pending confirmation action.
 #[derive(Debug, Clone)]
 pub enum ConfirmAction {
     /// Delete the current folder (smart folder, split, or empty maildir).
     DeleteFolder(String),
+    /// Reapply local flag changes that a sync reverted, as `(docid, flags)`.
+    ReapplyFlags(Vec<(u32, String)>),
+    /// Send a composed message despite pre-send warnings.
+    SendMessage(String),
+    /// Archive, trash or move every message from one sender (docids).
+    SweepSender(HashSet<u32>),
+    /// The account's folder for a role is missing but the provider's is
+    /// there: use it, or create the configured one.
+    MapFolder { role: String, folder: String },
+    /// Quit while a sync or reindex is still running.
+    Quit,
+    /// A query matched more than `max_results`: load every match.
+    LoadAll(String),
 }
 
+/// Most view tabs open at once (switched with Alt+1-9).
+const MAX_VIEWS: usize = 9;
+
 /// Sub-mode for vi-style editing within input fields (search bar, etc.).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VimSubMode {
@@ -211,6 +435,37 @@ pub enum VimSubMode {
     Normal,
 }
 
+/// Progress through "Triage Loop", which steps through the list from the
+/// selection one message at a time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TriageLoop {
+    /// Messages (or conversations) from the start to the end of the list.
+    pub total: usize,
+    pub archived: usize,
+    pub trashed: usize,
+    pub kept: usize,
+    pub skipped: usize,
+}
+
+impl TriageLoop {
+    pub fn done(&self) -> usize {
+        self.archived + self.trashed + self.kept + self.skipped
+    }
+
+    /// Shown in the top bar while the loop runs.
+    pub fn title(&self) -> String {
+        format!("Triage {} of {}", (self.done() + 1).min(self.total), self.total)
+    }
+
+    /// What the loop did, for the status line when it ends.
+    pub fn summary(&self) -> String {
+        format!(
+            "Triage: {} archived, {} trashed, {} kept, {} skipped",
+            self.archived, self.trashed, self.kept, self.skipped
+        )
+    }
+}
+
 /// State for the attachment open/save popup.
 pub struct AttachmentPopup {
     pub message_id: String,
@@ -225,6 +480,9 @@ pub struct App {
     // Active account (index into config.accounts)
     pub active_account: usize,
 
+    /// IPC socket name from `--socket`; the pid when unset
+    pub socket_name: Option<String>,
+
     // Core state
     pub current_folder: String,
     pub current_query: String,
@@ -233,6 +491,31 @@ pub struct App {
     pub scroll_offset: usize,
     pub preview_scroll: u16,
     pub preview_cache: RenderCache,
+    /// Show full recipient lists in the preview header
+    pub preview_headers_expanded: bool,
+    /// Envelope list row layout
+    pub list_density: ListDensity,
+    /// Flag glyphs for list rows, resolved from `[flag_glyphs]`
+    flag_column: FlagColumn,
+    /// Body snippets for list rows, keyed by message-id (persisted per account)
+    pub snippets: snippets::SnippetCache,
+    /// Message-ids queued with the snippet extractor but not yet returned
+    snippets_pending: HashSet<String>,
+    /// True when `snippets` has entries not yet written to the cache
+    snippets_dirty: bool,
+    /// Message scores keyed by message-id (persisted per account); `None`
+    /// for messages that had no score
+    pub scores: HashMap<String, Option<f64>>,
+    /// Message-ids queued with the scorer but not yet returned
+    scores_pending: HashSet<String>,
+    /// True when `scores` has entries not yet written to the cache
+    scores_dirty: bool,
+    /// Flags set locally this session, keyed by message-id, so a reindex
+    /// that reverts them can be detected
+    local_flags: HashMap<String, String>,
+    /// When to re-run the current view's query because flags it filters
+    /// on have changed
+    view_refresh_at: Option<Instant>,
     pub mu: MuClient,
     pub keymap: KeyMapper,
     pub should_quit: bool,
@@ -245,6 +528,8 @@ pub struct App {
 
     // Undo
     pub undo_stack: UndoStack,
+    /// Docids mu has replaced, so those kept for later can be refreshed
+    pub registry: MessageRegistry,
 
     // Multi-select
     pub selected_set: HashSet<u32>,
@@ -259,8 +544,19 @@ pub struct App {
 
     // Filters
     pub filter_unread: bool,
+    /// Hide messages scoring under `score_threshold`
+    pub filter_score: bool,
+    /// List only messages with this keyword
+    pub filter_keyword: Option<String>,
     pub filter_starred: bool,
     pub filter_needs_reply: bool,
+    /// Show only the newest message of each thread in search results
+    collapse_duplicates: bool,
+    /// Messages hidden by `collapse_duplicates`, by the Message-ID of the
+    /// one listed in their place
+    collapsed: HashMap<String, Vec<Envelope>>,
+    /// Threads (by [`Envelope::thread_key`]) shown in full anyway
+    expanded_duplicates: HashSet<String>,
 
     // Thread view
     pub thread_messages: Vec<ThreadMessage>,
@@ -275,6 +571,15 @@ pub struct App {
     // Smart folders
     pub smart_folders: Vec<SmartFolder>,
     pub smart_folder_queries: HashMap<String, String>, // "@name" -> query
+    /// Background match counts for smart folders with a `refresh` interval,
+    /// keyed by "@name" and shown as badges in the folder picker
+    pub smart_counts: HashMap<String, u32>,
+    // When each refreshing smart folder is next due for a recount
+    smart_count_due: HashMap<String, Instant>,
+    // Coalesced count queries waiting to run: (query, "@name" keys)
+    smart_count_queue: Vec<(String, Vec<String>)>,
+    // Spaces out batches of background counts
+    count_scheduler: CountScheduler,
 
     // Splits (inbox partitions)
     pub splits: Vec<Split>,
@@ -282,6 +587,8 @@ pub struct App {
     pub split_excluded: HashSet<u32>,              // union of all split caches
     pub creating_split: bool,                      // true = create-flow saves as split
     pub editing_folder: Option<String>,            // Some("#name") or Some("@name") when editing
+    /// Sectioned inbox state, when the inbox is shown in sections
+    pub sections: Option<InboxSections>,
 
     // Smart folder / split creation & editing
     pub smart_create_query: String,
@@ -294,10 +601,50 @@ pub struct App {
     // Maildir creation
     pub maildir_create_input: String,
 
+    // Quick reply text typed in the bottom bar
+    pub quick_reply_input: String,
+
+    // Follow-up reminders for the active account, and the due date being typed
+    followups: Vec<FollowUp>,
+    /// Overdue reminders already announced in the status bar
+    followups_notified: HashSet<String>,
+    pub followup_input: String,
+    // When to sum up the day's triage (`triage_summary`), and the day it
+    // was last done
+    triage_summary_at: Option<chrono::NaiveTime>,
+    triage_summary_shown: Option<chrono::NaiveDate>,
+    // Private notes for the active account, and the note being typed
+    notes: Vec<Note>,
+    pub note_input: String,
+    /// Bottom-bar input for "Export Search": where the messages go
+    pub export_input: String,
+    /// The local address book
+    address_book: Vec<Contact>,
+    /// The address being named in the address book, the name typed for
+    /// it and the mode to go back to
+    contact_target: Option<Address>,
+    pub contact_input: String,
+    contact_return: InputMode,
+    /// Senders blocked from the screener, whose inbox mail goes to spam
+    blocked: Vec<String>,
+    /// Addresses and list of the message the header actions menu is for
+    pub header_menu: Vec<HeaderTarget>,
+    pub header_menu_selected: usize,
+    /// Mode to go back to when the header actions menu closes
+    header_menu_from: InputMode,
+    /// A reply-all waiting for its recipients to be checked
+    pub recipient_check: Option<RecipientCheck>,
+    /// Set while "Triage Loop" runs
+    pub triage_loop: Option<TriageLoop>,
+
     // Command palette
     pub palette_filter: String,
     pub palette_selected: usize,
     pub palette_entries: Vec<PaletteEntry>,
+    /// "Describe Key": the next key is described instead of acted on
+    describing_key: bool,
+    /// Setup problems found at startup, shown until Esc dismisses them
+    health_banner: Vec<String>,
 
     // Conversations (grouped threads) mode
     pub conversations_mode: bool,
@@ -305,14 +652,50 @@ pub struct App {
     pub sort_descending: bool,
     pub conversations: Vec<Conversation>,
 
+    // View tabs: saved state per view (the active one lives in the
+    // fields above), and which one is on screen
+    pub views: Vec<ViewState>,
+    /// Set while two folders are compared side by side
+    pub compare: Option<Compare>,
+    /// Set while `hutt --tutorial` runs
+    tutorial: Option<Tutorial>,
+    pub active_view: usize,
+    /// View restored from the saved session, loaded on startup
+    restored_view: Option<ViewState>,
+    /// Recently visited or moved-to folders, most recent first, listed
+    /// in the pickers after the favorites
+    recent_folders: Vec<String>,
+    /// Sort and filters last used in each folder, where not the defaults
+    folder_prefs: HashMap<String, FolderPrefs>,
+    /// How far messages in the preview were scrolled, to resume reading
+    read_positions: ReadPositions,
+    /// Likewise for threads, by their first message-id
+    thread_positions: ReadPositions,
+    /// The message and thread on screen whose scroll is being tracked
+    read_anchor: Option<String>,
+    thread_anchor: Option<String>,
+    /// Destination of the last move-to-folder picker move, for RepeatMove
+    last_move: Option<String>,
+    /// The next triage move is a sender sweep, undone as one entry
+    sweep_undo: bool,
+    /// Triage held back until the running reindex completes
+    deferred_triage: Vec<DeferredTriage>,
+    /// Id for the next held-back triage action, to match it to its undo entry
+    next_held_id: u64,
+
     // Tab bar
     pub tabs: Vec<String>,
+    /// Tab/Shift+Tab order from `cycle_folders`; empty = cycle the tabs
+    pub cycle_folders: Vec<String>,
     pub tab_scroll: usize,
     pub tab_regions: Vec<TabRegion>,
     pub account_picker_selected: usize,
 
     // Attachment popup state
     pub attachment_popup: Option<AttachmentPopup>,
+    /// Attachments across the current folder, for the attachment browser
+    pub attachment_items: Vec<AttachmentItem>,
+    pub attachment_selected: usize,
 
     // Folder query cache: (account_index, query_string) → CacheEntry.
     // Partial entries hold first ~100 results for instant display;
@@ -320,9 +703,21 @@ pub struct App {
     // on triage/reindex. Survives account switches so prefetched
     // results for other accounts are ready immediately.
     pub folder_cache: HashMap<(usize, String), CacheEntry>,
-    // When true, collect_known_folders() will rescan the maildir tree.
-    // Set on reindex and account switch; cleared after scan.
+    // How many messages match each query whose full load stopped at
+    // max_results, keyed like folder_cache.
+    pub result_totals: HashMap<(usize, String), u32>,
+    // The answer to "load them all anyway?" for each query asked about.
+    pub load_all: HashMap<(usize, String), bool>,
+    // When true, collect_known_folders() will have the maildir tree
+    // rescanned. Set on reindex and account switch; cleared after.
     pub known_folders_dirty: bool,
+    // The maildir answered slowly or not at all when last probed (as an
+    // NFS or sshfs mount can), so walks of its tree are skipped.
+    maildir_slow: bool,
+    // Low-power mode is on (see `low_power` in config), and when the power
+    // supply was last looked at for it.
+    low_power: bool,
+    power_checked: Option<Instant>,
     // Queue of prefetch items to run during idle time.
     pub prefetch_queue: Vec<PrefetchItem>,
     // Background mu servers for non-active accounts (read-only prefetch).
@@ -335,26 +730,101 @@ pub struct App {
 
     // Help overlay
     pub help_scroll: u16,
+    /// Recent operations-log records shown in the history overlay
+    pub history: Vec<OpRecord>,
+    /// Messages listed in the "Recently Deleted" overlay
+    pub deleted: Vec<DeletedMessage>,
+    pub deleted_selected: usize,
+    /// Rendered lines of the digest or delivery info overlay
+    pub overlay_lines: Vec<String>,
 
     // Status message (temporary feedback)
     pub status_message: Option<String>,
     pub status_time: Option<Instant>,
 
+    /// Docids that arrived with the last reindex reload, tinted in the list
+    pub new_arrivals: HashSet<u32>,
+    pub new_arrivals_time: Option<Instant>,
+
+    /// Paused inbox: Message-IDs of the new mail held out of the list
+    /// until the pause is lifted
+    pub paused: Option<HashSet<String>>,
+
+    /// Running over SSH: copy links and file paths to the local clipboard
+    /// instead of opening them on this machine
+    remote: bool,
+
+    /// Sync with headers_sync_command, leaving bodies to be fetched when
+    /// a message is previewed
+    headers_only: bool,
+    /// Shell bindings show what they would run instead of running
+    shell_dry_run: bool,
+    /// Summaries from summarize_command, by the thread's first message-id,
+    /// with the number of messages summarized
+    summaries: HashMap<String, (usize, Vec<String>)>,
+    /// Body fetches still running: command -> message-id
+    body_fetches: HashMap<String, String>,
+    /// Message-ids whose body was fetched (or tried) this session
+    bodies_fetched: HashSet<String>,
+    /// Text direction forced on messages, by message-id; the others are
+    /// detected per paragraph
+    text_direction: HashMap<String, bidi::Direction>,
+    /// Message-ids over `preview_max_size_mb` to render all the same
+    render_anyway: HashSet<String>,
+
+    /// Where the status file for status bars is written
+    status_path: std::path::PathBuf,
+    /// The status file needs rewriting: counts or sync state changed
+    status_dirty: bool,
+    /// Inbox unread counts by account index, as last counted
+    account_unread: HashMap<usize, u32>,
+    /// When the last sync finished successfully
+    last_sync: Option<chrono::DateTime<chrono::Local>>,
+
+    /// Find progress was written straight to the terminal; repaint it all
+    needs_clear: bool,
+    /// Something on screen changed since the last frame was drawn
+    redraw: bool,
+
     // Compose pending (set by action handler, processed by run loop)
     pub compose_pending: Option<compose::ComposePending>,
+    /// The account's known addresses, fetched from mu on the first send
+    contacts: Option<Vec<String>>,
 
     // Shell command pending (suspend=true, processed by run loop like compose)
     pub shell_pending: Option<ShellPending>,
 
     // Set when a background shell command finishes with reindex=true
     pub needs_reindex: bool,
+    /// Use mu's lazy check for the next reindex (only changed folders)
+    lazy_reindex: bool,
+    /// Whether the running reindex is lazy, so a failure can fall back
+    /// to a full one
+    indexing_lazy: bool,
 
     // True while mu server is processing an (index) command
     pub indexing: bool,
 
+    // Background shell commands (syncs included) that haven't finished
+    running_commands: Vec<String>,
+
     // Channel sender for background shell command results (receiver lives in run loop)
     shell_tx: tokio::sync::mpsc::UnboundedSender<Result<ShellResult, ShellError>>,
 
+    // Channel sender for the background snippet extractor (results handled in run loop)
+    snippet_tx: tokio::sync::mpsc::UnboundedSender<snippets::SnippetRequest>,
+    // Channel sender for the background scorer (results handled in run loop)
+    score_tx: tokio::sync::mpsc::UnboundedSender<scores::ScoreRequest>,
+
+    // Channel sender for the background thread body renderer (results handled in run loop)
+    thread_render_tx: tokio::sync::mpsc::UnboundedSender<thread_view::RenderRequest>,
+    /// Sends maildirs to the background folder scanner
+    folder_scan_tx: tokio::sync::mpsc::UnboundedSender<folder_scan::ScanRequest>,
+    /// Carries attachments listed off the UI thread back to the run loop
+    attachment_list_tx: tokio::sync::mpsc::UnboundedSender<Vec<AttachmentItem>>,
+    /// Thread message bodies queued with the renderer, by message-id
+    thread_rendering: HashSet<String>,
+
     // Config
     pub config: Config,
 }
@@ -368,9 +838,15 @@ pub struct ShellPending {
 struct ShellResult {
     command: String,
     reindex: bool,
+    /// When the command was started, for finding the folders it changed
+    started: std::time::SystemTime,
     stdout: String,
     stderr: String,
     status: std::process::ExitStatus,
+    /// Show the output in a popup rather than its last line in the status bar
+    show_output: bool,
+    /// The thread summarized, as its first message-id and message count
+    summary_of: Option<(String, usize)>,
 }
 
 /// Error from a background shell command.
@@ -393,7 +869,7 @@ impl App {
     /// Build the mu query string for a given folder name (without filters).
     /// Used for prefetch — we prefetch the base query without filter flags.
     fn query_for_folder(&self, folder: &str) -> String {
-        if let Some(q) = self.smart_folder_queries.get(folder) {
+        let query = if let Some(q) = self.smart_folder_queries.get(folder) {
             q.clone()
         } else if let Some(q) = self.split_queries.get(folder) {
             let inbox_folder = self.account()
@@ -404,7 +880,8 @@ impl App {
             maildir_term(folder)
         } else {
             folder.to_string()
-        }
+        };
+        expand_dates(&notes::expand_terms(&query, &self.notes))
     }
 
     /// Build an ordered tab list for an account: neighbors of `center` expanding outward.
@@ -554,7 +1031,7 @@ impl App {
                     account_idx: *acct,
                     folder: folder.clone(),
                     query: query.clone(),
-                    max_num: FindOpts::default().max_num,
+                    max_num: self.result_limit(*acct, query),
                 });
             }
         }
@@ -567,6 +1044,35 @@ impl App {
         }
     }
 
+    /// Queue counts for smart folders whose refresh interval has passed.
+    /// Folders sharing a query are counted once; nothing is queued while
+    /// earlier counts are still waiting to run.
+    fn queue_smart_counts(&mut self) {
+        if !self.smart_count_queue.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let refreshing: Vec<(String, f64)> = self
+            .smart_folders
+            .iter()
+            .filter_map(|sf| {
+                let mins = sf.refresh.filter(|m| *m > 0.0)?;
+                Some((format!("@{}", sf.name), mins))
+            })
+            .collect();
+        let mut due = Vec::new();
+        for (key, mins) in refreshing {
+            if self.smart_count_due.get(&key).is_some_and(|t| *t > now) {
+                continue;
+            }
+            self.smart_count_due
+                .insert(key.clone(), now + Duration::from_secs_f64(mins * 60.0));
+            let query = self.query_for_folder(&key);
+            due.push((key, query));
+        }
+        self.smart_count_queue = smart_folders::coalesce_counts(due);
+    }
+
     /// Invalidate the folder query cache. Called after triage, reindex,
     /// and account switch — any event that changes mu's data.
     fn invalidate_folder_cache(&mut self) {
@@ -578,7 +1084,10 @@ impl App {
             debug_log!("invalidate_folder_cache: cleared {} entries for account {}", removed, acct);
         }
         self.prefetch_queue.clear();
+        // Smart folder counts may have changed too; recount them now
+        self.smart_count_due.clear();
         self.known_folders_dirty = true;
+        self.status_dirty = true;
     }
 
     /// Adjust tab_scroll to keep the selected folder visible in the tab bar.
@@ -624,6 +1133,10 @@ impl App {
             &split_names,
             &smart_folder_names,
         );
+        self.cycle_folders = self.config
+            .effective_cycle_folders(self.active_account)
+            .map(|list| resolve_tabs(Some(list), &account_folder_list, &split_names, &smart_folder_names))
+            .unwrap_or_default();
         self.tab_scroll = 0;
     }
 
@@ -634,28 +1147,38 @@ impl App {
         if let Some(acct) = config.accounts.first() {
             debug_log!("App::new: account[0] email={:?} maildir={:?}", acct.email, acct.maildir);
         }
+        if let Some(profile) = &config.active_profile {
+            debug_log!("App::new: profile {:?}", profile);
+        }
         let mut keymap = KeyMapper::new();
-        keymap.load_bindings(&config.bindings);
+        keymap.load_bindings(&config.effective_bindings(config.default_account_index()));
 
         let (shell_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let (snippet_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let (score_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let (thread_render_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let (folder_scan_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let (attachment_list_tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let status_path = config
+            .status_file
+            .as_deref()
+            .map(|p| std::path::PathBuf::from(crate::config::expand_tilde(p)))
+            .unwrap_or_else(status_file::default_path);
 
         let active_account = config.default_account_index();
 
         // Load smart folders from disk for the default account
         let acct_name = config.accounts.get(active_account).map(|a| a.name.as_str()).unwrap_or("");
-        let smart_folders = smart_folders::load_smart_folders(acct_name);
+        let smart_folders = smart_folders::load_for_account(
+            acct_name,
+            &config.smart_folders,
+            &config.builtin_smart_folders,
+        );
         let smart_folder_queries: HashMap<String, String> = smart_folders
             .iter()
             .map(|sf| (format!("@{}", sf.name), sf.query.clone()))
             .collect();
-        let mut known_folders = vec![
-            "/Inbox".into(),
-            "/Archive".into(),
-            "/Drafts".into(),
-            "/Sent".into(),
-            "/Trash".into(),
-            "/Junk".into(),
-        ];
+        let mut known_folders = seed_known_folders(config.accounts.get(active_account));
         for sf in &smart_folders {
             known_folders.push(format!("@{}", sf.name));
         }
@@ -670,6 +1193,18 @@ impl App {
             known_folders.push(format!("#{}", s.name));
         }
 
+        let saved_session = session::load_session(acct_name).unwrap_or_default();
+        let folder_prefs = if config.persist_folder_settings {
+            saved_session.folder_prefs
+        } else {
+            HashMap::new()
+        };
+        let (read_positions, thread_positions) = if config.persist_read_positions {
+            (saved_session.read_positions, saved_session.thread_positions)
+        } else {
+            Default::default()
+        };
+
         // Resolve tab bar
         let account = config.accounts.get(active_account);
         let account_folder_list: Vec<String> = account
@@ -690,15 +1225,32 @@ impl App {
             &split_names,
             &smart_folder_names,
         );
+        let cycle_folders = config
+            .effective_cycle_folders(active_account)
+            .map(|list| resolve_tabs(Some(list), &account_folder_list, &split_names, &smart_folder_names))
+            .unwrap_or_default();
 
-        Ok(Self {
+        let mut app = Self {
             active_account,
+            socket_name: None,
             current_folder: "/Inbox".to_string(),
             current_query: String::new(),
             envelopes: Vec::new(),
             selected: 0,
             scroll_offset: 0,
             preview_scroll: 0,
+            preview_headers_expanded: false,
+            list_density: config.list_density,
+            flag_column: FlagColumn::from_config(&config.flag_glyphs)
+                .with_keywords(&config.flag_glyphs, &config.keywords()),
+            snippets: snippets::load_snippets(acct_name),
+            snippets_pending: HashSet::new(),
+            snippets_dirty: false,
+            scores: scores::load_scores(acct_name),
+            scores_pending: HashSet::new(),
+            scores_dirty: false,
+            local_flags: HashMap::new(),
+            view_refresh_at: None,
             preview_cache: RenderCache::new(),
             mu,
             keymap,
@@ -706,6 +1258,7 @@ impl App {
             mode: InputMode::Normal,
             pending_confirm: None,
             undo_stack: UndoStack::new(),
+            registry: MessageRegistry::new(),
             selected_set: HashSet::new(),
             search_input: String::new(),
             search_textarea: new_search_textarea(""),
@@ -714,6 +1267,11 @@ impl App {
             search_history: Vec::new(),
             search_history_index: None,
             filter_unread: false,
+            filter_score: false,
+            filter_keyword: None,
+            collapse_duplicates: false,
+            collapsed: HashMap::new(),
+            expanded_duplicates: HashSet::new(),
             filter_starred: false,
             filter_needs_reply: false,
             thread_messages: Vec::new(),
@@ -724,9 +1282,16 @@ impl App {
             folder_selected: 0,
             smart_folders,
             smart_folder_queries,
+            smart_counts: HashMap::new(),
+            smart_count_due: HashMap::new(),
+            smart_count_queue: Vec::new(),
+            count_scheduler: CountScheduler::new(&config.counts),
             splits,
             split_queries,
             split_excluded: HashSet::new(),
+            sections: config
+                .sectioned_inbox
+                .then(|| InboxSections::new(&config.inbox_sections)),
             creating_split: false,
             editing_folder: None,
             smart_create_query: String::new(),
@@ -736,6 +1301,31 @@ impl App {
             smart_create_preview: Vec::new(),
             smart_create_count: None,
             maildir_create_input: String::new(),
+            quick_reply_input: String::new(),
+            followups: followups::load_followups(acct_name),
+            followups_notified: HashSet::new(),
+            triage_summary_at: config.triage_summary.as_deref().and_then(|t| {
+                let time = chrono::NaiveTime::parse_from_str(t.trim(), "%H:%M").ok();
+                if time.is_none() {
+                    debug_log!("triage_summary: {:?} isn't HH:MM", t);
+                }
+                time
+            }),
+            triage_summary_shown: None,
+            followup_input: String::new(),
+            notes: notes::load_notes(acct_name),
+            note_input: String::new(),
+            export_input: String::new(),
+            address_book: contacts::load_contacts(),
+            blocked: screener::load_blocked(),
+            contact_target: None,
+            contact_input: String::new(),
+            contact_return: InputMode::Normal,
+            header_menu: Vec::new(),
+            header_menu_selected: 0,
+            header_menu_from: InputMode::Normal,
+            recipient_check: None,
+            triage_loop: None,
             conversations_mode: config.conversations,
             sort_field: SortField::Date,
             sort_descending: true,
@@ -743,33 +1333,94 @@ impl App {
             palette_filter: String::new(),
             palette_selected: 0,
             palette_entries: PaletteEntry::all_actions(),
+            describing_key: false,
+            health_banner: Vec::new(),
+            views: vec![ViewState::default()],
+            compare: None,
+            tutorial: None,
+            active_view: 0,
+            restored_view: None,
+            recent_folders: saved_session.recent_folders,
+            folder_prefs,
+            read_positions,
+            thread_positions,
+            read_anchor: None,
+            thread_anchor: None,
+            last_move: None,
+            sweep_undo: false,
+            deferred_triage: Vec::new(),
+            next_held_id: 0,
             tabs,
+            cycle_folders,
             tab_scroll: 0,
             tab_regions: Vec::new(),
             account_picker_selected: 0,
             attachment_popup: None,
+            attachment_items: Vec::new(),
+            attachment_selected: 0,
             folder_cache: HashMap::new(),
+            result_totals: HashMap::new(),
+            load_all: HashMap::new(),
             known_folders_dirty: true,
+            maildir_slow: false,
+            low_power: false,
+            power_checked: None,
             prefetch_queue: Vec::new(),
             background_mu: HashMap::new(),
-            list_pct: 35,
+            list_pct: DEFAULT_LIST_PCT,
             dragging_border: false,
             help_scroll: 0,
+            history: Vec::new(),
+            deleted: Vec::new(),
+            deleted_selected: 0,
+            overlay_lines: Vec::new(),
             status_message: None,
             status_time: None,
+            new_arrivals: HashSet::new(),
+            new_arrivals_time: None,
+            paused: None,
+            remote: config.remote.unwrap_or_else(links::detect_remote),
+            headers_only: config.headers_only,
+            shell_dry_run: config.shell_dry_run,
+            summaries: HashMap::new(),
+            body_fetches: HashMap::new(),
+            bodies_fetched: HashSet::new(),
+            text_direction: HashMap::new(),
+            render_anyway: HashSet::new(),
+            status_path: status_path.clone(),
+            status_dirty: true,
+            account_unread: HashMap::new(),
+            last_sync: status_file::read(&status_path).and_then(|s| s.last_sync),
+            needs_clear: false,
+            redraw: true,
             compose_pending: None,
+            contacts: None,
             shell_pending: None,
             needs_reindex: false,
+            lazy_reindex: false,
+            indexing_lazy: false,
             indexing: false,
+            running_commands: Vec::new(),
             shell_tx,
+            snippet_tx,
+            score_tx,
+            thread_render_tx,
+            folder_scan_tx,
+            attachment_list_tx,
+            thread_rendering: HashSet::new(),
             config,
-        })
+        };
+        app.refresh_waiting_query();
+        Ok(app)
     }
 
     /// Number of envelopes to fetch for a fast partial load.
     const PARTIAL_MAX_NUM: u32 = 100;
 
     pub async fn load_folder(&mut self) -> Result<()> {
+        self.new_arrivals.clear();
+        self.collapsed.clear();
+        self.view_refresh_at = None;
         let query = self.build_query();
         debug_log!("load_folder: query={:?} folder={:?}", query, self.current_folder);
         self.current_query = query.clone();
@@ -789,7 +1440,28 @@ impl App {
             None => {
                 // Fast partial load: fetch first ~100 envelopes for instant display
                 let partial_opts = FindOpts { max_num: Self::PARTIAL_MAX_NUM, ..FindOpts::default() };
-                self.envelopes = self.mu.find(&query, &partial_opts).await?;
+                let mut shown = false;
+                let found = self
+                    .mu
+                    .find_with_progress(&query, &partial_opts, |progress| {
+                        if progress.elapsed < FIND_PROGRESS_DELAY {
+                            return true;
+                        }
+                        shown = true;
+                        show_find_progress(progress)
+                    })
+                    .await?;
+                self.needs_clear |= shown;
+                let Some(found) = found else {
+                    debug_log!("load_folder: cancelled");
+                    self.envelopes.clear();
+                    self.rebuild_conversations();
+                    self.selected = 0;
+                    self.scroll_offset = 0;
+                    self.set_status("Search cancelled");
+                    return Ok(());
+                };
+                self.envelopes = found;
                 let mu_returned = self.envelopes.len() as u32;
                 if self.is_inbox_folder() && !self.split_excluded.is_empty() {
                     let before = self.envelopes.len();
@@ -809,6 +1481,10 @@ impl App {
             }
         };
 
+        if let Some(held) = &self.paused {
+            self.envelopes.retain(|e| !held.contains(&e.message_id));
+        }
+        self.score_envelopes();
         self.selected = 0;
         self.scroll_offset = 0;
         self.preview_scroll = 0;
@@ -825,12 +1501,62 @@ impl App {
                 account_idx: self.active_account,
                 folder: self.current_folder.clone(),
                 query: self.current_query.clone(),
-                max_num: FindOpts::default().max_num,
+                max_num: self.result_limit(self.active_account, &self.current_query),
             });
         }
+        self.offer_load_all();
         Ok(())
     }
 
+    /// Most messages a full load of `query` fetches: `max_results`, or
+    /// everything once asked to load them all.
+    fn result_limit(&self, account_idx: usize, query: &str) -> u32 {
+        if self.load_all.get(&(account_idx, query.to_string())) == Some(&true) {
+            LOAD_ALL_LIMIT
+        } else {
+            self.config.max_results.max(MIN_MAX_RESULTS)
+        }
+    }
+
+    /// `(loaded, matching)` when the current list stopped at `max_results`.
+    fn truncation(&self) -> Option<(usize, usize)> {
+        let total = *self.result_totals.get(&(self.active_account, self.current_query.clone()))?;
+        Some((self.envelopes.len(), total as usize))
+    }
+
+    /// The current list stopped at `max_results`: ask, once per query,
+    /// whether to load every match anyway.
+    fn offer_load_all(&mut self) {
+        let key = (self.active_account, self.current_query.clone());
+        let Some(&total) = self.result_totals.get(&key) else {
+            return;
+        };
+        if self.load_all.contains_key(&key) || self.pending_confirm.is_some() || self.mode != InputMode::Normal {
+            return;
+        }
+        self.load_all.insert(key, false);
+        self.pending_confirm = Some(ConfirmAction::LoadAll(self.current_query.clone()));
+        self.set_status(format!(
+            "{} messages match, showing the first {}. Load them all anyway? (y/n)",
+            thousands(total as usize),
+            thousands(self.result_limit(self.active_account, &self.current_query) as usize)
+        ));
+    }
+
+    /// Reload `query` with every match rather than the first `max_results`.
+    fn load_all_results(&mut self, query: String) {
+        let key = (self.active_account, query.clone());
+        self.load_all.insert(key.clone(), true);
+        self.folder_cache.remove(&key);
+        self.prefetch_queue.insert(0, PrefetchItem {
+            account_idx: self.active_account,
+            folder: self.current_folder.clone(),
+            query,
+            max_num: LOAD_ALL_LIMIT,
+        });
+        self.set_status("Loading every match\u{2026}");
+    }
+
     /// Check if the current folder is the account's inbox.
     fn is_inbox_folder(&self) -> bool {
         let inbox = self.account()
@@ -841,7 +1567,9 @@ impl App {
 
     /// Run each split query against the inbox and cache the resulting docids.
     /// Builds the combined `split_excluded` set used to filter the inbox view.
+    /// Also reclassifies the inbox sections, which change with the inbox.
     async fn refresh_split_caches(&mut self) {
+        self.refresh_section_caches().await;
         self.split_excluded.clear();
         if self.splits.is_empty() {
             return;
@@ -856,7 +1584,7 @@ impl App {
             ..Default::default()
         };
         for split in &self.splits {
-            let query = format!("{} AND ({})", maildir_term(&inbox_folder), split.query);
+            let query = expand_dates(&format!("{} AND ({})", maildir_term(&inbox_folder), split.query));
             match self.mu.find(&query, &opts).await {
                 Ok(envelopes) => {
                     debug_log!("split cache {:?}: {} docids", split.name, envelopes.len());
@@ -877,11 +1605,82 @@ impl App {
         let inbox_query = maildir_term(&inbox_folder);
         self.folder_cache.remove(&(acct, inbox_query));
         for split in &self.splits {
-            let q = format!("{} AND ({})", maildir_term(&inbox_folder), split.query);
+            let q = expand_dates(&format!("{} AND ({})", maildir_term(&inbox_folder), split.query));
             self.folder_cache.remove(&(acct, q));
         }
     }
 
+    /// Run each inbox section's query and record which section its
+    /// messages belong in, then regroup the list if it's the inbox.
+    async fn refresh_section_caches(&mut self) {
+        let Some(queries) = self.sections.as_ref().map(|s| s.queries().to_vec()) else {
+            return;
+        };
+        let inbox_folder = self.account()
+            .map(|a| a.folders.inbox.clone())
+            .unwrap_or_else(|| "/Inbox".to_string());
+        let opts = FindOpts {
+            max_num: 10000,
+            threads: false,
+            descending: false,
+            ..Default::default()
+        };
+        let mut matches = Vec::new();
+        for (section, query) in queries {
+            let query = expand_dates(&format!("{} AND ({})", maildir_term(&inbox_folder), query));
+            match self.mu.find(&query, &opts).await {
+                Ok(envelopes) => matches.push((section, envelopes)),
+                Err(e) => debug_log!("section cache error for {:?}: {}", query, e),
+            }
+        }
+        if let Some(sections) = self.sections.as_mut() {
+            sections.clear_matches();
+            for (section, envelopes) in &matches {
+                sections.add_matches(*section, envelopes.iter().map(|e| e.message_id.as_str()));
+            }
+        }
+        if self.sections_active() {
+            self.unhide_sections();
+            self.apply_sort();
+        }
+    }
+
+    /// Whether the list is showing the sectioned inbox.
+    fn sections_active(&self) -> bool {
+        self.sections.is_some() && !self.conversations_mode && self.is_inbox_folder()
+    }
+
+    /// Group the envelope list by inbox section, if it's showing the
+    /// sectioned inbox. Assumes the list holds every message again.
+    fn arrange_sections(&mut self) {
+        let active = self.sections_active();
+        if let Some(sections) = self.sections.as_mut() {
+            if active {
+                sections.arrange(&mut self.envelopes);
+            } else {
+                sections.forget_hidden();
+            }
+        }
+    }
+
+    /// Put collapsed sections' messages back in the envelope list, before
+    /// re-sorting or leaving the sectioned view.
+    fn unhide_sections(&mut self) {
+        if self.sections_active() {
+            if let Some(sections) = self.sections.as_mut() {
+                sections.unhide(&mut self.envelopes);
+            }
+        }
+    }
+
+    /// Section headers for the envelope list (none outside the sectioned inbox).
+    fn section_headers(&self) -> Vec<SectionHeader> {
+        match &self.sections {
+            Some(sections) if self.sections_active() => sections.headers(&self.envelopes),
+            _ => Vec::new(),
+        }
+    }
+
     fn build_query(&self) -> String {
         let mut query = if let Some(q) = self.smart_folder_queries.get(&self.current_folder) {
             q.clone()
@@ -903,9 +1702,14 @@ impl App {
             query.push_str(" AND flag:flagged");
         }
         if self.filter_needs_reply {
-            query.push_str(" AND NOT flag:replied");
+            let email = self.account().map_or("", |a| a.email.as_str());
+            query.push_str(" AND ");
+            query.push_str(&needs_reply_terms(self.config.needs_reply_query.as_deref(), email));
         }
-        query
+        if let Some(keyword) = &self.filter_keyword {
+            query.push_str(&format!(" AND tag:{}", keyword));
+        }
+        expand_dates(&notes::expand_terms(&query, &self.notes))
     }
 
     /// Expand `#split` and `@smart` references in a search query to their
@@ -931,44 +1735,176 @@ impl App {
                 folders.insert(e.maildir.clone());
             }
         }
-        // Scan maildir root recursively for all real folders
-        if let Some(account) = self.account() {
-            let root = expand_maildir_root(&account.maildir);
-            let root_path = std::path::PathBuf::from(&root);
-            let mut stack = vec![root_path.clone()];
-            while let Some(dir) = stack.pop() {
-                if let Ok(entries) = std::fs::read_dir(&dir) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        if path.is_dir() {
-                            if path.join("cur").is_dir() {
-                                if let Ok(rel) = path.strip_prefix(&root_path) {
-                                    let name = rel.to_string_lossy();
-                                    let name = name.strip_prefix('.').unwrap_or(&name);
-                                    folders.insert(format!("/{}", name));
-                                }
-                                // Also recurse into it — there may be sub-maildirs
-                                stack.push(path);
-                            } else {
-                                stack.push(path);
-                            }
-                        }
-                    }
-                }
-            }
-        }
         // Re-add smart folder entries so they persist across reloads
         for sf in &self.smart_folders {
             folders.insert(format!("@{}", sf.name));
         }
         self.known_folders = folders.into_iter().collect();
         self.known_folders.sort();
+        self.scan_folders();
+    }
+
+    /// Have the maildir tree walked in the background for folders no
+    /// message has been seen in; see [`Self::folders_scanned`].
+    fn scan_folders(&mut self) {
+        if let Some(account) = self.account() {
+            let request = folder_scan::ScanRequest {
+                account_idx: self.active_account,
+                root: std::path::PathBuf::from(expand_maildir_root(&account.maildir)),
+            };
+            let _ = self.folder_scan_tx.send(request);
+        }
+    }
+
+    /// Merge a background walk's folders into the known folders, unless
+    /// it's of an account since switched away from. A maildir on a mount
+    /// too slow to walk is reported instead.
+    fn folders_scanned(&mut self, scan: folder_scan::FolderScan) {
+        if scan.account_idx != self.active_account {
+            return;
+        }
+        self.maildir_slow = scan.storage != maildir::Responsiveness::Fine;
+        mu_client::set_slow_storage(self.maildir_slow);
+        if let Some(problem) = scan.storage.problem() {
+            debug_log!("maildir: {}", problem);
+            self.set_status(format!("Maildir is {}: folder scan skipped, mu given longer", problem));
+        }
+        let before = self.known_folders.len();
+        for folder in scan.folders {
+            if !self.known_folders.contains(&folder) {
+                self.known_folders.push(folder);
+            }
+        }
+        if self.known_folders.len() != before {
+            self.known_folders.sort();
+        }
     }
 
     fn selected_envelope(&self) -> Option<&Envelope> {
         self.preview_envelope()
     }
 
+    /// Whether the thread view is on screen, perhaps under the header menu
+    /// or the contact name prompt.
+    fn showing_thread(&self) -> bool {
+        match self.mode {
+            InputMode::ThreadView => true,
+            InputMode::HeaderMenu => self.header_menu_from == InputMode::ThreadView,
+            InputMode::RecipientCheck => {
+                self.recipient_check.as_ref().is_some_and(|c| c.from == InputMode::ThreadView)
+            }
+            InputMode::ContactName => self.contact_return == InputMode::ThreadView,
+            _ => false,
+        }
+    }
+
+    /// Queue body snippets for the list rows currently on screen with the
+    /// background extractor. Low-power mode shows only those already cached.
+    fn ensure_snippets_loaded(&mut self, list_height: usize) {
+        if self.low_power {
+            return;
+        }
+        let height = (list_height / self.list_density.rows_per_item()).max(1);
+        let total = self.visible_count();
+        let (start, end) =
+            EnvelopeList::visible_range(self.selected, self.scroll_offset, height, total);
+        for idx in start..end {
+            let envelope = if self.conversations_mode {
+                self.conversations.get(idx).map(|c| c.representative())
+            } else {
+                self.envelopes.get(idx)
+            };
+            let Some(envelope) = envelope else { continue };
+            let request = snippets::SnippetRequest {
+                message_id: envelope.message_id.clone(),
+                path: envelope.path.clone(),
+            };
+            self.request_snippet(request);
+        }
+    }
+
+    /// Queue one message with the snippet extractor unless it is already
+    /// cached or pending.
+    fn request_snippet(&mut self, request: snippets::SnippetRequest) {
+        if self.snippets.contains_key(&request.message_id)
+            || self.snippets_pending.contains(&request.message_id)
+        {
+            return;
+        }
+        let message_id = request.message_id.clone();
+        if self.snippet_tx.send(request).is_ok() {
+            self.snippets_pending.insert(message_id);
+        }
+    }
+
+    /// Age tints for the list: only in the inbox and its splits, where
+    /// unanswered mail is waiting on you.
+    fn aging(&self) -> Option<Aging> {
+        let aging = Aging {
+            amber_days: self.config.age_amber_days,
+            red_days: self.config.age_red_days,
+        };
+        (self.is_inbox_derived() && (aging.amber_days > 0 || aging.red_days > 0)).then_some(aging)
+    }
+
+    /// Whether `score_header` or `score_command` is set.
+    fn scoring(&self) -> bool {
+        self.config.score_header.is_some() || self.config.score_command.is_some()
+    }
+
+    /// Queue the listed messages that have no score yet with the scorer,
+    /// then, with the score filter on, drop those scoring under the
+    /// threshold. Messages still being scored stay until their score is in.
+    fn score_envelopes(&mut self) {
+        if !self.scoring() {
+            return;
+        }
+        for e in &self.envelopes {
+            if self.scores.contains_key(&e.message_id) || self.scores_pending.contains(&e.message_id) {
+                continue;
+            }
+            let request = scores::ScoreRequest { message_id: e.message_id.clone(), path: e.path.clone() };
+            if self.score_tx.send(request).is_ok() {
+                self.scores_pending.insert(e.message_id.clone());
+            }
+        }
+        if self.filter_score {
+            let (scores, threshold) = (&self.scores, self.config.score_threshold);
+            self.envelopes.retain(|e| match scores.get(&e.message_id) {
+                Some(score) => score.is_some_and(|s| s >= threshold),
+                None => true,
+            });
+        }
+    }
+
+    /// Record a score from the scorer. Once the queue is empty the list is
+    /// filtered and sorted again if either depends on scores.
+    fn score_arrived(&mut self, message_id: String, score: Option<f64>) {
+        self.scores_pending.remove(&message_id);
+        self.scores.insert(message_id, score);
+        self.scores_dirty = true;
+        if self.scores_pending.is_empty() && (self.filter_score || self.sort_field == SortField::Score) {
+            self.score_envelopes();
+            self.apply_sort();
+        }
+    }
+
+    /// Write new scores to the active account's cache.
+    fn save_scores(&mut self) {
+        if self.scores_dirty {
+            scores::save_scores(&self.scores, self.account_name());
+            self.scores_dirty = false;
+        }
+    }
+
+    /// Write newly extracted snippets to the active account's cache.
+    fn save_snippets(&mut self) {
+        if self.snippets_dirty {
+            snippets::save_snippets(&self.snippets, self.account_name());
+            self.snippets_dirty = false;
+        }
+    }
+
     fn ensure_preview_loaded(&mut self, width: u16) {
         let envelope = match self.preview_envelope() {
             Some(e) => e,
@@ -978,8 +1914,28 @@ impl App {
         if self.preview_cache.get(msg_id, width).is_some() {
             return;
         }
-        match mime_render::render_message(&envelope.path, msg_id, width) {
-            Ok(rendered) => self.preview_cache.insert(msg_id.clone(), width, rendered),
+        if let Some(size) = mime_render::oversized(&envelope.path, self.max_size_mb(msg_id)) {
+            self.preview_cache.insert(msg_id.clone(), width, mime_render::too_large(size));
+            return;
+        }
+        if self.body_may_be_absent() {
+            let absent = match std::fs::read(&envelope.path) {
+                Ok(raw) => maildir::body_absent(&raw),
+                // Moved by a finished fetch; shown properly after the reload
+                Err(_) => self.bodies_fetched.contains(msg_id),
+            };
+            if absent {
+                let (message_id, path) = (msg_id.clone(), envelope.path.clone());
+                self.preview_body_absent(&message_id, &path, width);
+                return;
+            }
+        }
+        match mime_render::render_message(&envelope.path, msg_id, width, self.config.preview_max_width) {
+            Ok(mut rendered) => {
+                let measure = mime_render::text_measure(width, self.config.preview_max_width);
+                bidi::apply(&mut rendered, measure, self.text_direction.get(msg_id).copied());
+                self.preview_cache.insert(msg_id.clone(), width, rendered)
+            }
             Err(e) => self.preview_cache.insert(
                 msg_id.clone(),
                 width,
@@ -990,11 +1946,62 @@ impl App {
                     }]],
                     links: Vec::new(),
                     is_html: false,
+                    reply_to: Vec::new(),
+                    sent_offset: None,
                 },
             ),
         }
     }
 
+    /// Size over which a message shows a placeholder rather than being
+    /// rendered: `preview_max_size_mb`, unless asked to render it anyway.
+    fn max_size_mb(&self, message_id: &str) -> u64 {
+        if self.render_anyway.contains(message_id) {
+            0
+        } else {
+            self.config.preview_max_size_mb
+        }
+    }
+
+    /// Forget previews and snippets of messages whose bodies were fetched,
+    /// which the reindex may have moved to new files.
+    fn refresh_fetched_bodies(&mut self) {
+        for message_id in &self.bodies_fetched {
+            self.preview_cache.remove(message_id);
+            if self.snippets.get(message_id).is_some_and(|s| s == mime_render::BODY_ABSENT_SNIPPET) {
+                self.snippets.remove(message_id);
+            }
+        }
+    }
+
+    /// Whether messages may have been synced without their bodies.
+    fn body_may_be_absent(&self) -> bool {
+        self.config.headers_sync_command.is_some() || self.config.fetch_body_command.is_some()
+    }
+
+    /// Preview a message synced without its body, starting a fetch of the
+    /// body the first time it is shown.
+    fn preview_body_absent(&mut self, message_id: &str, path: &std::path::Path, width: u16) {
+        let fetch = self
+            .config
+            .effective_fetch_body_command(self.active_account, message_id, path);
+        let notice = match fetch {
+            _ if self.body_fetches.values().any(|id| id == message_id) => {
+                "[Body not downloaded \u{2014} fetching\u{2026}]"
+            }
+            Some(command) if self.bodies_fetched.insert(message_id.to_string()) => {
+                self.set_status("Fetching message body...");
+                self.body_fetches.insert(command.clone(), message_id.to_string());
+                self.spawn_command(command, true);
+                "[Body not downloaded \u{2014} fetching\u{2026}]"
+            }
+            Some(_) => "[Body not downloaded]",
+            None => "[Body not downloaded \u{2014} set fetch_body_command to fetch it]",
+        };
+        let rendered = mime_render::render_plain_text(notice, width);
+        self.preview_cache.insert(message_id.to_string(), width, rendered);
+    }
+
     /// Find the filesystem path for a message by Message-ID.
     fn find_message_path(&self, message_id: &str) -> Option<std::path::PathBuf> {
         for e in &self.envelopes {
@@ -1013,8 +2020,7 @@ impl App {
     /// Dispatch a URL from a clicked link in the preview or thread view.
     async fn dispatch_link_url(&mut self, url: &str, mouse_pos: Option<(u16, u16)>) {
         if url.starts_with("http://") || url.starts_with("https://") {
-            let _ = links::open_path(url);
-            self.set_status(format!("Opened: {}", url));
+            self.open_external(url, format!("Opened: {}", url));
         } else if url.starts_with("mid:") {
             if let Some(parsed) = links::parse_url(url) {
                 match parsed {
@@ -1052,7 +2058,7 @@ impl App {
                     }
                 }
             }
-        } else if url.starts_with("mailto:") {
+        } else if url.starts_with("mailto:") || url.starts_with("hutt:") {
             if let Some(parsed) = links::parse_url(url) {
                 match self
                     .handle_ipc_command(links::IpcCommand::Open(parsed.into()))
@@ -1065,63 +2071,225 @@ impl App {
                 }
             }
         } else {
-            let _ = links::open_path(url);
-            self.set_status(format!("Opened: {}", url));
+            self.open_external(url, format!("Opened: {}", url));
+        }
+    }
+
+    /// Copy text to the clipboard: the system one locally, the terminal's
+    /// in a remote session.
+    fn copy_text(&self, text: &str) -> Result<()> {
+        if self.remote {
+            links::copy_via_terminal(text)
+        } else {
+            links::copy_to_clipboard(text)
+        }
+    }
+
+    /// Open a URL or file and show `opened`. In a remote session, copy the
+    /// URL (a file as an scp:// URL) to the local clipboard instead.
+    fn open_external(&mut self, target: &str, opened: String) {
+        if !self.remote {
+            let _ = links::open_path(target);
+            self.set_status(opened);
+            return;
+        }
+        let url = if target.contains("://") {
+            target.to_string()
+        } else {
+            links::scp_url(std::path::Path::new(target))
+        };
+        match links::copy_via_terminal(&url) {
+            Ok(()) => self.set_status(format!("Copied: {}", url)),
+            Err(e) => self.set_status(format!("Clipboard error: {}", e)),
         }
     }
 
     async fn open_attachment(&mut self, message_id: &str, content_id: &str) {
         if let Some(path) = self.find_message_path(message_id) {
-            match mime_render::extract_attachment(&path, content_id) {
-                Ok(att) => {
-                    let tmp_path = std::env::temp_dir().join(&att.filename);
-                    if let Err(e) = std::fs::write(&tmp_path, &att.data) {
-                        self.set_status(format!("Write error: {}", e));
-                        return;
-                    }
-                    let _ = links::open_path(tmp_path.to_str().unwrap_or(""));
-                    self.set_status(format!("Opened: {}", att.filename));
-                }
-                Err(e) => self.set_status(format!("Extract error: {}", e)),
-            }
+            self.open_attachment_at(&path, content_id);
         } else {
             self.set_status("Message not found");
         }
     }
 
-    async fn save_attachment(&mut self, message_id: &str, content_id: &str) {
-        if let Some(path) = self.find_message_path(message_id) {
-            match mime_render::extract_attachment(&path, content_id) {
-                Ok(att) => {
-                    let download_dir = self.config.download_dir.as_deref()
-                        .unwrap_or("~/Downloads");
-                    let expanded = if let Some(rest) = download_dir.strip_prefix("~/") {
-                        let home = std::env::var("HOME").unwrap_or_default();
-                        format!("{}/{}", home, rest)
-                    } else {
-                        download_dir.to_string()
-                    };
-                    let dir = std::path::Path::new(&expanded);
-                    if let Err(e) = std::fs::create_dir_all(dir) {
-                        self.set_status(format!("Create dir error: {}", e));
-                        return;
-                    }
-                    let save_path = dir.join(&att.filename);
-                    match std::fs::write(&save_path, &att.data) {
-                        Ok(_) => self.set_status(format!("Saved: {}", save_path.display())),
-                        Err(e) => self.set_status(format!("Save error: {}", e)),
-                    }
+    /// Extract an attachment from the message file at `path` and open it.
+    fn open_attachment_at(&mut self, path: &std::path::Path, content_id: &str) {
+        match mime_render::extract_attachment(path, content_id) {
+            Ok(att) => {
+                let tmp_path = std::env::temp_dir().join(&att.filename);
+                if let Err(e) = std::fs::write(&tmp_path, &att.data) {
+                    self.set_status(format!("Write error: {}", e));
+                    return;
                 }
-                Err(e) => self.set_status(format!("Extract error: {}", e)),
+                self.open_external(&tmp_path.to_string_lossy(), format!("Opened: {}", att.filename));
             }
+            Err(e) => self.set_status(format!("Extract error: {}", e)),
+        }
+    }
+
+    async fn save_attachment(&mut self, message_id: &str, content_id: &str) {
+        if let Some(path) = self.find_message_path(message_id) {
+            self.save_attachment_at(&path, content_id);
         } else {
             self.set_status("Message not found");
         }
     }
 
-    fn set_status(&mut self, msg: impl Into<String>) {
-        self.status_message = Some(msg.into());
-        self.status_time = Some(Instant::now());
+    /// The configured download_dir, with `~/` expanded.
+    fn download_dir(&self) -> std::path::PathBuf {
+        let download_dir = self.config.download_dir.as_deref()
+            .unwrap_or("~/Downloads");
+        if let Some(rest) = download_dir.strip_prefix("~/") {
+            let home = std::env::var("HOME").unwrap_or_default();
+            std::path::Path::new(&home).join(rest)
+        } else {
+            std::path::PathBuf::from(download_dir)
+        }
+    }
+
+    /// Extract an attachment from the message file at `path` into download_dir.
+    fn save_attachment_at(&mut self, path: &std::path::Path, content_id: &str) {
+        match mime_render::extract_attachment(path, content_id) {
+            Ok(att) => {
+                let dir = self.download_dir();
+                if let Err(e) = std::fs::create_dir_all(&dir) {
+                    self.set_status(format!("Create dir error: {}", e));
+                    return;
+                }
+                let save_path = dir.join(&att.filename);
+                match std::fs::write(&save_path, &att.data) {
+                    Ok(_) => self.set_status(format!("Saved: {}", save_path.display())),
+                    Err(e) => self.set_status(format!("Save error: {}", e)),
+                }
+            }
+            Err(e) => self.set_status(format!("Extract error: {}", e)),
+        }
+    }
+
+    /// Find messages with attachments in the current folder/search and list
+    /// every attachment part, newest message first. The message files are
+    /// read on the blocking pool; the list arrives in [`Self::attachments_listed`].
+    async fn load_attachment_items(&mut self) -> Result<()> {
+        let query = if self.current_query.is_empty() {
+            "flag:attach".to_string()
+        } else {
+            format!("({}) AND flag:attach", self.current_query)
+        };
+        let opts = FindOpts {
+            threads: false,
+            max_num: ATTACHMENT_SEARCH_LIMIT,
+            ..FindOpts::default()
+        };
+        let envelopes = self.mu.find(&query, &opts).await?;
+        if envelopes.is_empty() {
+            self.attachments_listed(Vec::new());
+            return Ok(());
+        }
+        self.set_status(format!("Listing attachments of {} message(s)\u{2026}", envelopes.len()));
+        let tx = self.attachment_list_tx.clone();
+        tokio::spawn(async move {
+            let items = tokio::task::spawn_blocking(move || {
+                envelopes
+                    .iter()
+                    .flat_map(|e| {
+                        let attachments = mime_render::list_attachments(&e.path).unwrap_or_default();
+                        attachments.into_iter().map(move |attachment| AttachmentItem {
+                            path: e.path.clone(),
+                            sender: e.sender_display(),
+                            date: e.date.format("%Y-%m-%d").to_string(),
+                            attachment,
+                        })
+                    })
+                    .collect()
+            })
+            .await
+            .unwrap_or_default();
+            let _ = tx.send(items);
+        });
+        Ok(())
+    }
+
+    /// Open the attachment browser on a finished listing, unless the user
+    /// has moved on to another mode meanwhile.
+    fn attachments_listed(&mut self, items: Vec<AttachmentItem>) {
+        if self.mode != InputMode::Normal {
+            return;
+        }
+        if items.is_empty() {
+            self.set_status("No attachments in this folder");
+        } else {
+            self.status_message = None;
+            self.attachment_items = items;
+            self.attachment_selected = 0;
+            self.mode = InputMode::AttachmentBrowser;
+        }
+        self.redraw = true;
+    }
+
+    fn set_status(&mut self, msg: impl Into<String>) {
+        self.status_message = Some(msg.into());
+        self.status_time = Some(Instant::now());
+        self.redraw = true;
+    }
+
+    /// Turn low-power mode on or off as config says, looking at the power
+    /// supply once a minute for "auto".
+    fn update_low_power(&mut self) {
+        let low_power = match self.config.low_power {
+            LowPower::On => true,
+            LowPower::Off => false,
+            LowPower::Auto => {
+                if self.power_checked.is_some_and(|t| t.elapsed() < power::RECHECK) {
+                    return;
+                }
+                self.power_checked = Some(Instant::now());
+                power::on_battery()
+            }
+        };
+        if low_power == self.low_power {
+            return;
+        }
+        self.low_power = low_power;
+        if low_power {
+            self.set_status("Low-power mode on".to_string());
+        } else {
+            // Counts weren't taken meanwhile
+            self.status_dirty = true;
+            self.set_status("Low-power mode off".to_string());
+        }
+    }
+
+    /// Walk the user through `hutt --tutorial`, one step at a time.
+    pub fn start_tutorial(&mut self) {
+        self.tutorial = Some(Tutorial::new(self.tutorial_snapshot()));
+    }
+
+    fn tutorial_snapshot(&self) -> tutorial::Snapshot {
+        tutorial::Snapshot {
+            folder: self.current_folder.clone(),
+            selected: self.selected,
+            listed: self.visible_count(),
+            undo_depth: self.undo_stack.len(),
+        }
+    }
+
+    /// Move the tutorial on once its step is done, and keep the current
+    /// step's prompt in the status bar when nothing else is there.
+    fn update_tutorial(&mut self) {
+        let Some(mut tutorial) = self.tutorial.take() else {
+            return;
+        };
+        let advanced = tutorial.advance(&self.tutorial_snapshot());
+        match tutorial.prompt() {
+            Some(prompt) if advanced || self.status_message.is_none() => self.set_status(prompt),
+            None if advanced => {
+                self.set_status("Tutorial done! Press q to quit; the tutorial mail is then deleted")
+            }
+            _ => {}
+        }
+        if !tutorial.finished() {
+            self.tutorial = Some(tutorial);
+        }
     }
 
     fn clear_stale_status(&mut self) {
@@ -1129,10 +2297,88 @@ impl App {
             if t.elapsed() > Duration::from_secs(3) {
                 self.status_message = None;
                 self.status_time = None;
+                self.redraw = true;
             }
         }
     }
 
+    /// Mark the loaded messages whose Message-IDs weren't in `before`
+    /// (the list as it was before a background reload).
+    fn mark_new_arrivals(&mut self, before: &HashSet<String>) {
+        self.new_arrivals = new_docids(&self.envelopes, before);
+        self.new_arrivals_time = Some(Instant::now());
+    }
+
+    /// While paused, take the messages a background reload brought in
+    /// (those not in `before`) back out of the list and hold them.
+    fn hold_arrivals(&mut self, before: &HashSet<String>) {
+        if self.paused.is_none() {
+            return;
+        }
+        self.unhide_sections();
+        if let Some(held) = self.paused.as_mut() {
+            self.envelopes.retain(|e| {
+                if before.contains(&e.message_id) {
+                    return true;
+                }
+                held.insert(e.message_id.clone());
+                false
+            });
+        }
+        self.apply_sort();
+    }
+
+    fn clear_stale_arrivals(&mut self) {
+        if self.new_arrivals_time.is_some_and(|t| t.elapsed() > NEW_ARRIVAL_HIGHLIGHT) {
+            self.new_arrivals.clear();
+            self.new_arrivals_time = None;
+            self.redraw = true;
+        }
+    }
+
+    /// Put a message's new flags, and the docid mu gave it when renaming
+    /// its file, into every loaded copy of it: the list, its conversation
+    /// and an open thread.
+    fn patch_flags(&mut self, docid: u32, new_docid: u32, flags: &str) {
+        self.renumber(docid, new_docid);
+        let copies = self
+            .envelopes
+            .iter_mut()
+            .chain(self.conversations.iter_mut().flat_map(|c| c.messages.iter_mut()))
+            .chain(self.thread_messages.iter_mut().map(|m| &mut m.envelope));
+        for e in copies.filter(|e| e.docid == docid) {
+            e.docid = new_docid;
+            set_envelope_flags(e, flags);
+        }
+    }
+
+    /// Note that mu replaced `docid` with `new_docid`, and refresh the
+    /// docids kept for later: the multi-selection, the split exclusions
+    /// and the new-mail highlight. Undo asks the registry when it runs.
+    fn renumber(&mut self, docid: u32, new_docid: u32) {
+        if docid == new_docid {
+            return;
+        }
+        self.registry.renumber(docid, new_docid);
+        for docids in [&mut self.selected_set, &mut self.split_excluded, &mut self.new_arrivals] {
+            self.registry.refresh(docids);
+        }
+    }
+
+    /// Whether which messages the current view lists depends on their
+    /// flags: searches, splits and smart folders, and any list filter.
+    fn view_filters_on_flags(&self) -> bool {
+        !self.current_folder.starts_with('/') || self.filter_description().is_some()
+    }
+
+    /// Re-run the current view's query once flags have settled, keeping
+    /// the selection on the same message if it still matches.
+    async fn refresh_view(&mut self) -> Result<()> {
+        self.view_refresh_at = None;
+        let view = self.capture_view();
+        self.restore_view(view).await
+    }
+
     fn rebuild_conversations(&mut self) {
         self.conversations = group_into_conversations(&self.envelopes);
     }
@@ -1142,6 +2388,9 @@ impl App {
     fn apply_sort(&mut self) {
         let selected_msgid = self.preview_envelope()
             .map(|e| e.message_id.clone());
+        // Near-duplicates collapsed last time are sorted back in first
+        let hidden = std::mem::take(&mut self.collapsed);
+        self.envelopes.extend(hidden.into_values().flatten());
 
         let desc = self.sort_descending;
         self.envelopes.sort_by(|a, b| {
@@ -1162,9 +2411,20 @@ impl App {
                     let bt = b.to.first().map(|addr| addr.email.to_lowercase()).unwrap_or_default();
                     at.cmp(&bt)
                 }
+                SortField::Score => {
+                    // Unscored messages sort below every score
+                    let score = |e: &Envelope| self.scores.get(&e.message_id).copied().flatten();
+                    score(a).partial_cmp(&score(b)).unwrap_or(std::cmp::Ordering::Equal)
+                }
             };
             if desc { cmp.reverse() } else { cmp }
         });
+        if self.collapse_duplicates && self.is_search() {
+            let envelopes = std::mem::take(&mut self.envelopes);
+            (self.envelopes, self.collapsed) =
+                collapse_near_duplicates(envelopes, &self.expanded_duplicates);
+        }
+        self.arrange_sections();
 
         self.rebuild_conversations();
 
@@ -1182,6 +2442,14 @@ impl App {
         }
     }
 
+    /// Whether the list is a free-form search, not a folder, split or
+    /// smart folder.
+    fn is_search(&self) -> bool {
+        !self.current_folder.starts_with('/')
+            && !self.smart_folder_queries.contains_key(&self.current_folder)
+            && !self.split_queries.contains_key(&self.current_folder)
+    }
+
     /// Human-readable label for the current sort (shown in status bar).
     fn sort_label(&self) -> String {
         let arrow = if self.sort_descending { "\u{25bc}" } else { "\u{25b2}" };
@@ -1211,13 +2479,19 @@ impl App {
     fn filter_description(&self) -> Option<String> {
         let mut parts = Vec::new();
         if self.filter_unread {
-            parts.push("unread");
+            parts.push("unread".to_string());
         }
         if self.filter_starred {
-            parts.push("starred");
+            parts.push("starred".to_string());
         }
         if self.filter_needs_reply {
-            parts.push("needs-reply");
+            parts.push("needs-reply".to_string());
+        }
+        if self.filter_score {
+            parts.push(format!("score\u{2265}{}", self.config.score_threshold));
+        }
+        if let Some(keyword) = &self.filter_keyword {
+            parts.push(keyword.clone());
         }
         if parts.is_empty() {
             None
@@ -1248,7 +2522,8 @@ impl App {
     ///
     /// If `target` starts with `/`, it's a literal maildir path.
     /// Otherwise it's an alias (archive, trash, spam, inbox, sent, drafts)
-    /// resolved from the active account's folder config.
+    /// resolved from the active account's folder config. When comparing,
+    /// that's the focused pane's account: focusing a pane switches to it.
     fn resolve_move_target(&self, target: &str) -> (String, String) {
         if target.starts_with('/') {
             let desc = format!("Moved to {}", target);
@@ -1306,6 +2581,12 @@ impl App {
 
     /// Check if the current folder contains inbox messages.
     /// True for the inbox itself and for split inbox folders.
+    /// Rows the startup banner takes at the bottom of a terminal this
+    /// tall.
+    fn banner_height(&self, height: u16) -> u16 {
+        (self.health_banner.len() as u16).min(height / 4)
+    }
+
     fn is_inbox_derived(&self) -> bool {
         self.is_inbox_folder() || self.split_queries.contains_key(&self.current_folder)
     }
@@ -1317,32 +2598,100 @@ impl App {
         }
         let count = targets.len();
         let gmail_archive = self.is_gmail_archive(dest_maildir);
+        let grouped = std::mem::take(&mut self.sweep_undo);
+        // A configured folder that doesn't exist yet is created on first use
+        let created = if !gmail_archive && self.ensure_folder(dest_maildir)? {
+            format!(" (created {})", dest_maildir)
+        } else {
+            String::new()
+        };
+        if self.indexing {
+            let messages = targets
+                .iter()
+                .map(|(_, maildir, _, message_id)| (message_id.clone(), maildir.clone()))
+                .collect();
+            let docids: HashSet<u32> = targets.iter().map(|t| t.0).collect();
+            let (envelopes, kept) = std::mem::take(&mut self.envelopes)
+                .into_iter()
+                .partition(|e| docids.contains(&e.docid));
+            self.envelopes = kept;
+            let id = self.hold_triage(desc);
+            self.deferred_triage.push(DeferredTriage::Move {
+                id,
+                dest: dest_maildir.to_string(),
+                remove: gmail_archive,
+                desc: desc.to_string(),
+                messages,
+                envelopes,
+            });
+            self.rebuild_conversations();
+            self.selected_set.clear();
+            self.clamp_selection();
+            self.preview_scroll = 0;
+            self.set_status(format!("{} {} message(s) (once the reindex finishes){}", desc, count, created));
+            return Ok(());
+        }
         let mut succeeded: HashSet<u32> = HashSet::new();
         let mut errors = 0u32;
-        for (docid, maildir, flags) in &targets {
+        let mut records = Vec::new();
+        let mut moves = Vec::new();
+        for (docid, maildir, flags, message_id) in &targets {
+            // The envelope's docid, or the one it's moved to if mu says it's stale
+            let mut docid = *docid;
             if gmail_archive {
                 // Gmail: just remove from Inbox; message stays in All Mail.
                 // Undo not supported for Gmail archive (message removed from
                 // mu database; would need to re-sync to recover).
-                match self.mu.remove_msg(*docid).await {
-                    Ok(()) => { succeeded.insert(*docid); }
+                let mut result = self.mu.remove_msg(docid).await;
+                if let Err(e) = &result {
+                    if let Some(fresh) = self.resolve_stale_docid(e, docid, message_id, maildir).await {
+                        docid = fresh;
+                        result = self.mu.remove_msg(fresh).await;
+                    }
+                }
+                match result {
+                    Ok(()) => {
+                        succeeded.insert(docid);
+                        records.push(self.op_record(
+                            docid,
+                            OpKind::Remove,
+                            format!("{}: removed from {}", desc, maildir),
+                        ));
+                    }
                     Err(e) => {
                         debug_log!("triage_move: remove docid {} failed: {}", docid, e);
                         errors += 1;
                     }
                 }
             } else {
-                match self.mu.move_msg(*docid, Some(dest_maildir), None).await {
+                let mut result = self.mu.move_msg(docid, Some(dest_maildir), None).await;
+                if let Err(e) = &result {
+                    if let Some(fresh) = self.resolve_stale_docid(e, docid, message_id, maildir).await {
+                        docid = fresh;
+                        result = self.mu.move_msg(fresh, Some(dest_maildir), None).await;
+                    }
+                }
+                match result {
                     Ok(new_docid) => {
-                        succeeded.insert(*docid);
-                        self.undo_stack.push(UndoEntry {
-                            action: UndoAction::MoveMessage {
-                                docid: new_docid,
-                                original_maildir: maildir.clone(),
-                                original_flags: flags.clone(),
-                            },
-                            description: desc.to_string(),
-                        });
+                        succeeded.insert(docid);
+                        self.renumber(docid, new_docid);
+                        records.push(self.op_record(
+                            docid,
+                            OpKind::Move,
+                            format!("{}: {} \u{2192} {}", desc, maildir, dest_maildir),
+                        ));
+                        if grouped {
+                            moves.push((new_docid, maildir.clone(), flags.clone()));
+                        } else {
+                            self.undo_stack.push(UndoEntry {
+                                action: UndoAction::MoveMessage {
+                                    docid: new_docid,
+                                    original_maildir: maildir.clone(),
+                                    original_flags: flags.clone(),
+                                },
+                                description: desc.to_string(),
+                            });
+                        }
                     }
                     Err(e) => {
                         debug_log!("triage_move: move docid {} failed: {}", docid, e);
@@ -1351,6 +2700,13 @@ impl App {
                 }
             }
         }
+        if !moves.is_empty() {
+            self.undo_stack.push(UndoEntry {
+                action: UndoAction::MoveMessages { moves },
+                description: desc.to_string(),
+            });
+        }
+        oplog::append(self.account_name(), &records);
         self.envelopes.retain(|e| !succeeded.contains(&e.docid));
         self.invalidate_folder_cache();
         self.rebuild_conversations();
@@ -1358,29 +2714,396 @@ impl App {
         self.clamp_selection();
         self.preview_scroll = 0;
         if errors > 0 {
-            self.set_status(format!("{} {} message(s) ({} failed)", desc, succeeded.len(), errors));
+            self.set_status(format!("{} {} message(s) ({} failed){}", desc, succeeded.len(), errors, created));
         } else {
-            self.set_status(format!("{} {} message(s)", desc, count));
+            self.set_status(format!("{} {} message(s){}", desc, count, created));
         }
         Ok(())
     }
 
+    /// Note a triage action held back until the reindex completes, with an
+    /// undo entry that drops it until then. Returns the action's id.
+    fn hold_triage(&mut self, description: &str) -> u64 {
+        let id = self.next_held_id;
+        self.next_held_id += 1;
+        self.undo_stack.push(UndoEntry {
+            action: UndoAction::HeldTriage { id },
+            description: description.to_string(),
+        });
+        id
+    }
+
+    /// Drop the held-back triage action `id` before it is made, putting
+    /// its messages back in the list as they were.
+    fn drop_held_triage(&mut self, id: u64) {
+        let Some(i) = self.deferred_triage.iter().position(|op| op.id() == id) else {
+            return;
+        };
+        match self.deferred_triage.remove(i) {
+            DeferredTriage::Move { envelopes, .. } => {
+                self.envelopes.extend(envelopes);
+                self.apply_sort();
+                self.rebuild_conversations();
+                self.clamp_selection();
+            }
+            DeferredTriage::Flag { flag, messages, .. } => {
+                for (message_id, _, set) in messages {
+                    self.local_flags.remove(&message_id);
+                    let copies: Vec<(u32, String)> = self
+                        .envelopes
+                        .iter()
+                        .filter(|e| e.message_id == message_id)
+                        .map(|e| (e.docid, with_flag(&e.flags_string(), flag, !set)))
+                        .collect();
+                    for (docid, flags) in copies {
+                        self.patch_flags(docid, docid, &flags);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Make the triage changes held back while mu was indexing, finding
+    /// each message again by Message-ID. Each action's undo entry becomes
+    /// one that reverts the change made. Returns a notice for the status
+    /// bar (to follow a prefix), or `None` if nothing was held back.
+    async fn replay_deferred_triage(&mut self) -> Option<String> {
+        let deferred = std::mem::take(&mut self.deferred_triage);
+        if deferred.is_empty() {
+            return None;
+        }
+        let mut applied = 0u32;
+        let mut failed = 0u32;
+        let mut records = Vec::new();
+        for op in deferred {
+            let id = op.id();
+            let mut moves = Vec::new();
+            let description = match op {
+                DeferredTriage::Move { dest, remove, desc, messages, .. } => {
+                    for (message_id, maildir) in messages {
+                        let Some(e) = self.find_copy(&message_id, &maildir).await else {
+                            failed += 1;
+                            continue;
+                        };
+                        let result = if remove {
+                            self.mu.remove_msg(e.docid).await.map(|()| None)
+                        } else {
+                            self.mu.move_msg(e.docid, Some(&dest), None).await.map(Some)
+                        };
+                        match result {
+                            Ok(new_docid) => {
+                                applied += 1;
+                                let (kind, detail) = match new_docid {
+                                    None => (OpKind::Remove, format!("{}: removed from {}", desc, e.maildir)),
+                                    Some(_) => (OpKind::Move, format!("{}: {} \u{2192} {}", desc, e.maildir, dest)),
+                                };
+                                records.push(OpRecord::new(kind, &message_id, &e.subject, detail));
+                                if let Some(docid) = new_docid {
+                                    self.renumber(e.docid, docid);
+                                    moves.push((docid, e.maildir.clone(), e.flags_string()));
+                                }
+                            }
+                            Err(err) => {
+                                debug_log!("deferred triage: move {} failed: {}", message_id, err);
+                                failed += 1;
+                            }
+                        }
+                    }
+                    desc
+                }
+                DeferredTriage::Flag { flag, desc, messages, .. } => {
+                    for (message_id, maildir, set) in messages {
+                        let Some(e) = self.find_copy(&message_id, &maildir).await else {
+                            failed += 1;
+                            continue;
+                        };
+                        let flags = e.flags_string();
+                        let new_flags = with_flag(&flags, flag, set);
+                        if new_flags == flags {
+                            applied += 1;
+                            continue;
+                        }
+                        match self.mu.move_msg(e.docid, None, Some(&new_flags)).await {
+                            Ok(docid) => {
+                                applied += 1;
+                                self.renumber(e.docid, docid);
+                                let detail = format!("{}: {} \u{2192} {}", desc, flags, new_flags);
+                                records.push(OpRecord::new(OpKind::Flag, &message_id, &e.subject, detail));
+                                moves.push((docid, e.maildir.clone(), flags));
+                            }
+                            Err(err) => {
+                                debug_log!("deferred triage: flag {} failed: {}", message_id, err);
+                                failed += 1;
+                            }
+                        }
+                    }
+                    format!("toggle {}", desc)
+                }
+            };
+            let entry = match moves.len() {
+                0 => None,
+                1 => {
+                    let (docid, original_maildir, original_flags) = moves.remove(0);
+                    Some(UndoEntry {
+                        action: UndoAction::MoveMessage { docid, original_maildir, original_flags },
+                        description,
+                    })
+                }
+                _ => Some(UndoEntry { action: UndoAction::MoveMessages { moves }, description }),
+            };
+            self.undo_stack.resolve_held(id, entry);
+        }
+        oplog::append(self.account_name(), &records);
+        self.invalidate_folder_cache();
+        Some(if failed > 0 {
+            format!("applied {} held change(s) ({} no longer found)", applied, failed)
+        } else {
+            format!("applied {} held change(s)", applied)
+        })
+    }
+
+    /// Save triage still held back at quit, to be made on the next start.
+    fn save_held_triage(&self) {
+        if !self.deferred_triage.is_empty() {
+            held_triage::save_held_triage(&self.deferred_triage, self.account_name());
+        }
+    }
+
+    /// Make the triage changes saved when hutt last quit mid-reindex, or
+    /// hold them for the reindex running now. Returns a notice for the
+    /// status bar, or `None` if there were none.
+    async fn resume_held_triage(&mut self) -> Option<String> {
+        for mut op in held_triage::take_held_triage(self.account_name()) {
+            // Fresh ids, so they can't be mistaken for this session's
+            let id = self.next_held_id;
+            self.next_held_id += 1;
+            match &mut op {
+                DeferredTriage::Move { id: old, .. } | DeferredTriage::Flag { id: old, .. } => *old = id,
+            }
+            self.deferred_triage.push(op);
+        }
+        if self.indexing {
+            return None;
+        }
+        self.replay_deferred_triage().await
+    }
+
+    /// After mu rejects `docid` as naming no message (a background sync
+    /// moved or renamed it), find the message again by Message-ID. Returns
+    /// its fresh docid, with the list's envelope, selection and other
+    /// docid sets moved over to it and where it now lives, or `None` if
+    /// it is gone or the error was something else.
+    async fn resolve_stale_docid(
+        &mut self,
+        err: &anyhow::Error,
+        docid: u32,
+        message_id: &str,
+        maildir: &str,
+    ) -> Option<u32> {
+        if message_id.is_empty() || !crate::mu_client::is_missing_docid(err) {
+            return None;
+        }
+        let copy = self.find_copy(message_id, maildir).await?;
+        if copy.docid == docid {
+            return None;
+        }
+        debug_log!("triage: docid {} of {} is now {}", docid, message_id, copy.docid);
+        self.renumber(docid, copy.docid);
+        if let Some(e) = self.envelopes.iter_mut().find(|e| e.docid == docid) {
+            e.docid = copy.docid;
+            e.maildir = copy.maildir;
+            e.path = copy.path;
+        }
+        Some(copy.docid)
+    }
+
+    /// Look a message up by Message-ID, preferring its copy in `maildir`.
+    async fn find_copy(&mut self, message_id: &str, maildir: &str) -> Option<Envelope> {
+        let query = format!("msgid:\"{}\"", message_id);
+        match self.mu.find(&query, &FindOpts::default()).await {
+            Ok(copies) => pick_copy(copies, maildir),
+            Err(e) => {
+                debug_log!("find_copy: {} failed: {}", message_id, e);
+                None
+            }
+        }
+    }
+
+    /// Ask what to do with every message in the current list from the
+    /// selected message's sender.
+    fn start_sweep(&mut self) {
+        let Some(sender) = self.selected_envelope().and_then(|e| e.from.first()).cloned() else {
+            return;
+        };
+        let docids = sweep_docids(&self.envelopes, &sender.email);
+        self.set_status(format!(
+            "Sweep {} message(s) from {}? e:archive #:trash m:move (other keys cancel)",
+            docids.len(),
+            sender.email
+        ));
+        self.pending_confirm = Some(ConfirmAction::SweepSender(docids));
+    }
+
+    /// Carry out a sweep with the key pressed at the prompt.
+    async fn finish_sweep(&mut self, key: crossterm::event::KeyCode, docids: HashSet<u32>) -> Result<()> {
+        use crossterm::event::KeyCode;
+        let target = match key {
+            KeyCode::Char('e') => "archive",
+            KeyCode::Char('#') => "trash",
+            KeyCode::Char('m') => {
+                self.selected_set = docids;
+                self.sweep_undo = true;
+                self.folder_filter.clear();
+                self.folder_selected = 0;
+                self.mode = InputMode::MoveToFolder;
+                return Ok(());
+            }
+            _ => {
+                self.set_status("Cancelled");
+                return Ok(());
+            }
+        };
+        self.selected_set = docids;
+        self.sweep_undo = true;
+        self.move_to_target(target).await
+    }
+
+    /// Move the selection to a folder alias or path. An alias whose folder
+    /// doesn't exist yet, in a maildir holding the provider's own folder
+    /// for it (e.g. `/[Gmail]/Bin` for trash), asks which to use first.
+    async fn move_to_target(&mut self, target: &str) -> Result<()> {
+        let (maildir, desc) = self.resolve_move_target(target);
+        if let Some(folder) = self.unmapped_provider_folder(target, &maildir) {
+            self.set_status(format!(
+                "No {} folder \u{2014} y:use {} for {} n:create {} (other keys cancel)",
+                maildir, folder, target, maildir
+            ));
+            self.pending_confirm = Some(ConfirmAction::MapFolder {
+                role: target.to_string(),
+                folder,
+            });
+            return Ok(());
+        }
+        self.triage_move(&maildir, &desc).await
+    }
+
+    /// The provider's folder for `role` when the account's own, `maildir`,
+    /// doesn't exist on disk.
+    fn unmapped_provider_folder(&self, role: &str, maildir: &str) -> Option<String> {
+        let account = self.account()?;
+        if role.starts_with('/') || role == "inbox" {
+            return None;
+        }
+        let root = std::path::PathBuf::from(expand_maildir_root(&account.maildir));
+        if maildir::folder_exists(&root, maildir) {
+            return None;
+        }
+        crate::config::provider_folder(role, &self.known_folders)
+    }
+
+    /// Carry out a move held for a folder mapping prompt: `y` uses the
+    /// provider's folder for the rest of the session, `n` creates the
+    /// configured one.
+    async fn finish_folder_mapping(&mut self, key: crossterm::event::KeyCode, role: &str, folder: String) -> Result<()> {
+        use crossterm::event::KeyCode;
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let idx = self.active_account;
+                if let Some(slot) = self.config.accounts.get_mut(idx).and_then(|a| a.folders.role_mut(role)) {
+                    *slot = folder.clone();
+                }
+                let (maildir, desc) = self.resolve_move_target(role);
+                self.triage_move(&maildir, &desc).await?;
+                let moved = self.status_message.clone().unwrap_or_default();
+                self.set_status(format!(
+                    "{} \u{2014} using {} for {} this session; set {} = \"{}\" in [accounts.folders] to keep it",
+                    moved, folder, role, role, folder
+                ));
+                Ok(())
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                let (maildir, desc) = self.resolve_move_target(role);
+                self.triage_move(&maildir, &desc).await
+            }
+            _ => {
+                self.sweep_undo = false;
+                self.set_status("Cancelled");
+                Ok(())
+            }
+        }
+    }
+
+    /// Create the maildir folder `folder` if it doesn't exist yet, so a
+    /// first move into it works. Returns whether it was created.
+    fn ensure_folder(&mut self, folder: &str) -> Result<bool> {
+        let Some(account) = self.account() else {
+            return Ok(false);
+        };
+        let root = std::path::PathBuf::from(expand_maildir_root(&account.maildir));
+        if maildir::folder_exists(&root, folder) {
+            return Ok(false);
+        }
+        maildir::create_folder(&root, folder)?;
+        debug_log!("created maildir folder {}", folder);
+        if !self.known_folders.iter().any(|f| f == folder) {
+            self.known_folders.push(folder.to_string());
+            self.known_folders.sort();
+        }
+        Ok(true)
+    }
+
     async fn triage_toggle_flag(&mut self, flag_char: char, desc: &str) -> Result<()> {
         let targets = self.triage_targets();
         if targets.is_empty() {
             return Ok(());
         }
+        if self.indexing {
+            let mut messages = Vec::new();
+            for (docid, maildir, flags, message_id) in &targets {
+                let set = !flags.contains(flag_char);
+                let new_flags = with_flag(flags, flag_char, set);
+                self.local_flags.insert(message_id.clone(), new_flags.clone());
+                self.patch_flags(*docid, *docid, &new_flags);
+                messages.push((message_id.clone(), maildir.clone(), set));
+            }
+            let id = self.hold_triage(&format!("toggle {}", desc));
+            self.deferred_triage.push(DeferredTriage::Flag {
+                id,
+                flag: flag_char,
+                desc: desc.to_string(),
+                messages,
+            });
+            self.selected_set.clear();
+            self.set_status(format!(
+                "Toggled {} on {} message(s) (saved once the reindex finishes)",
+                desc,
+                targets.len()
+            ));
+            return Ok(());
+        }
         let mut succeeded = 0u32;
         let mut errors = 0u32;
-        for (docid, maildir, flags) in &targets {
-            let new_flags = if flags.contains(flag_char) {
-                flags.replace(flag_char, "")
-            } else {
-                format!("{}{}", flags, flag_char)
-            };
-            match self.mu.move_msg(*docid, None, Some(&new_flags)).await {
+        let mut records = Vec::new();
+        for (docid, maildir, flags, message_id) in &targets {
+            // The envelope's docid, or the one it's moved to if mu says it's stale
+            let mut docid = *docid;
+            let new_flags = with_flag(flags, flag_char, !flags.contains(flag_char));
+            let mut result = self.mu.move_msg(docid, None, Some(&new_flags)).await;
+            if let Err(e) = &result {
+                if let Some(fresh) = self.resolve_stale_docid(e, docid, message_id, maildir).await {
+                    docid = fresh;
+                    result = self.mu.move_msg(fresh, None, Some(&new_flags)).await;
+                }
+            }
+            match result {
                 Ok(new_docid) => {
                     succeeded += 1;
+                    records.push(self.op_record(
+                        docid,
+                        OpKind::Flag,
+                        format!("{}: {} \u{2192} {}", desc, flags, new_flags),
+                    ));
+                    self.local_flags.insert(message_id.clone(), new_flags.clone());
                     self.undo_stack.push(UndoEntry {
                         action: UndoAction::MoveMessage {
                             docid: new_docid,
@@ -1389,10 +3112,7 @@ impl App {
                         },
                         description: format!("toggle {}", desc),
                     });
-                    if let Some(e) = self.envelopes.iter_mut().find(|e| e.docid == *docid) {
-                        e.docid = new_docid;
-                        e.flags = flags_from_string(&new_flags);
-                    }
+                    self.patch_flags(docid, new_docid, &new_flags);
                 }
                 Err(e) => {
                     debug_log!("triage_toggle_flag: move docid {} failed: {}", docid, e);
@@ -1400,8 +3120,12 @@ impl App {
                 }
             }
         }
+        oplog::append(self.account_name(), &records);
         self.invalidate_folder_cache();
         self.selected_set.clear();
+        if succeeded > 0 && self.view_filters_on_flags() {
+            self.view_refresh_at = Some(Instant::now() + VIEW_REFRESH_DELAY);
+        }
         if errors > 0 {
             self.set_status(format!("Toggled {} on {} message(s) ({} failed)", desc, succeeded, errors));
         } else {
@@ -1410,42 +3134,1099 @@ impl App {
         Ok(())
     }
 
-    fn triage_targets(&self) -> Vec<(u32, String, String)> {
-        if !self.selected_set.is_empty() {
-            self.envelopes
-                .iter()
-                .filter(|e| self.selected_set.contains(&e.docid))
-                .map(|e| (e.docid, e.maildir.clone(), e.flags_string()))
-                .collect()
-        } else if self.conversations_mode {
-            // In conversations mode, act on all messages in the selected conversation
-            if let Some(convo) = self.conversations.get(self.selected) {
-                convo
-                    .messages
-                    .iter()
-                    .map(|e| (e.docid, e.maildir.clone(), e.flags_string()))
-                    .collect()
-            } else {
-                vec![]
-            }
-        } else if let Some(e) = self.envelopes.get(self.selected) {
-            vec![(e.docid, e.maildir.clone(), e.flags_string())]
+    /// Set (or, with empty input, clear) the follow-up reminder on the
+    /// selected message from the due date typed in the bottom bar.
+    fn set_followup(&mut self) {
+        let Some(envelope) = self.selected_envelope() else {
+            self.mode = InputMode::Normal;
+            return;
+        };
+        let message_id = envelope.message_id.clone();
+        let input = self.followup_input.trim().to_string();
+        if input.is_empty() {
+            self.followups.retain(|f| f.message_id != message_id);
+            self.set_status("Follow-up reminder cleared");
         } else {
-            vec![]
+            let Some(due) = followups::parse_due(&input, followups::today()) else {
+                self.set_status(format!("Can't parse date \"{}\"", input));
+                return;
+            };
+            let own_email = self.account().map(|a| a.email.as_str()).unwrap_or("");
+            let followup = FollowUp::new(envelope, due, own_email);
+            self.followups.retain(|f| f.message_id != message_id);
+            self.followups.push(followup);
+            self.followups_notified.remove(&message_id);
+            self.set_status(format!("Waiting for a reply until {}", due.format("%a %b %-d")));
         }
+        self.mode = InputMode::Normal;
+        followups::save_followups(&self.followups, self.account_name());
+        self.refresh_waiting_query();
     }
 
-    fn clamp_selection(&mut self) {
-        let count = self.visible_count();
-        if count > 0 && self.selected >= count {
-            self.selected = count - 1;
+    /// The note on `envelope`, if it has one.
+    fn note(&self, envelope: &Envelope) -> Option<&str> {
+        notes::find(&self.notes, &envelope.message_id).map(|n| n.text.as_str())
+    }
+
+    /// Set (or, with empty input, remove) the note on the selected message
+    /// from the text typed in the bottom bar.
+    fn save_note(&mut self) {
+        self.mode = InputMode::Normal;
+        let Some(envelope) = self.selected_envelope().cloned() else {
+            return;
+        };
+        if notes::set(&mut self.notes, &envelope, &self.note_input) {
+            self.set_status("Note saved");
+        } else {
+            self.set_status("Note removed");
         }
+        notes::save_notes(&self.notes, self.account_name());
+        // Searches for note: terms now match differently
+        self.invalidate_folder_cache();
     }
 
-    async fn undo(&mut self) -> Result<()> {
-        if let Some(entry) = self.undo_stack.pop() {
-            match entry.action {
-                UndoAction::MoveMessage {
+    /// Open split creation with its name and query filled in, so the rule
+    /// can still be adjusted before saving.
+    async fn prefill_split(&mut self, name: String, query: String) {
+        self.smart_create_name = name;
+        self.smart_create_query = query;
+        self.smart_create_phase = 0;
+        self.smart_create_preview.clear();
+        self.smart_create_count = None;
+        self.creating_split = true;
+        self.init_smart_create_textarea();
+        self.update_smart_create_preview().await;
+        self.mode = InputMode::SmartFolderCreate;
+    }
+
+    /// Act on the header actions menu's selected entry: Enter or `/`
+    /// searches for mail from it, `c` writes to it, `r` starts a split
+    /// for it and `y` copies it.
+    async fn header_menu_action(&mut self, key: char) -> Result<()> {
+        let Some(target) = self.header_menu.get(self.header_menu_selected).cloned() else {
+            return Ok(());
+        };
+        self.mode = self.header_menu_from.clone();
+        match key {
+            '/' => {
+                self.mode = InputMode::Normal;
+                self.thread_messages.clear();
+                self.navigate_folder(&target.query()).await?;
+            }
+            'c' => match target.address() {
+                Some(address) => {
+                    let ctx = compose::ComposeContext { to: vec![address], ..compose::ComposeContext::new_message() };
+                    self.compose_pending = Some(compose::ComposePending::Ready(Box::new(ctx)));
+                }
+                None => self.set_status("Write to a list by replying to one of its messages"),
+            },
+            'r' => {
+                self.thread_messages.clear();
+                self.prefill_split(target.rule_name(), target.query()).await;
+            }
+            'y' => match self.copy_text(&target.value) {
+                Ok(()) => self.set_status(format!("Copied {}", target.value)),
+                Err(e) => self.set_status(format!("Clipboard error: {}", e)),
+            },
+            'a' => match target.address() {
+                Some(address) => self.edit_contact(address),
+                None => self.set_status("Only addresses can be contacts"),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Messages the operations log shows trashed or removed within
+    /// `recently_deleted_days`, each with its copy in the trash if it is
+    /// still there. Those since moved out of the trash are left out.
+    async fn recently_deleted(&mut self) -> Result<Vec<DeletedMessage>> {
+        let (trash, _) = self.resolve_move_target("trash");
+        let days = self.config.recently_deleted_days as i64;
+        let since = chrono::Local::now() - chrono::Duration::days(days);
+        let mut deletions = oplog::deletions(self.account_name(), &trash, since);
+        deletions.truncate(DELETED_LIMIT);
+
+        // Where each trashed message is now, a batch of Message-IDs at a time
+        let ids: Vec<String> = deletions
+            .iter()
+            .filter(|d| d.from.is_some())
+            .map(|d| format!("msgid:\"{}\"", d.message_id))
+            .collect();
+        let opts = FindOpts { threads: false, ..FindOpts::default() };
+        let mut copies = Vec::new();
+        for batch in ids.chunks(50) {
+            copies.extend(self.mu.find(&batch.join(" OR "), &opts).await?);
+        }
+
+        Ok(deletions
+            .into_iter()
+            .filter_map(|deletion| {
+                if deletion.from.is_none() {
+                    return Some(DeletedMessage { deletion, copy: None });
+                }
+                let mut found = copies.iter().filter(|e| e.message_id == deletion.message_id).peekable();
+                let elsewhere = found.peek().is_some();
+                let copy = found.find(|e| e.maildir == trash).cloned();
+                if copy.is_none() && elsewhere {
+                    return None;
+                }
+                Some(DeletedMessage { deletion, copy })
+            })
+            .collect())
+    }
+
+    /// Move the message selected in "Recently Deleted" from the trash back
+    /// to the folder it was trashed from. `z` trashes it again.
+    async fn restore_deleted(&mut self) -> Result<()> {
+        let Some(message) = self.deleted.get(self.deleted_selected) else {
+            return Ok(());
+        };
+        let (Some(copy), Some(dest)) = (message.copy.clone(), message.deletion.from.clone()) else {
+            self.set_status(match message.deletion.from {
+                Some(_) => "No longer in the trash, so it can't be restored",
+                None => "Removed outright, so it can't be restored",
+            });
+            return Ok(());
+        };
+        self.ensure_folder(&dest)?;
+        let docid = self.mu.move_msg(copy.docid, Some(&dest), None).await?;
+        self.renumber(copy.docid, docid);
+        let record = OpRecord::new(
+            OpKind::Move,
+            &copy.message_id,
+            &copy.subject,
+            format!("Restored: {} \u{2192} {}", copy.maildir, dest),
+        );
+        oplog::append(self.account_name(), &[record]);
+        self.undo_stack.push(UndoEntry {
+            action: UndoAction::MoveMessage {
+                docid,
+                original_maildir: copy.maildir.clone(),
+                original_flags: copy.flags_string(),
+            },
+            description: "restore".to_string(),
+        });
+        self.deleted.remove(self.deleted_selected);
+        self.deleted_selected = self.deleted_selected.min(self.deleted.len().saturating_sub(1));
+        self.invalidate_folder_cache();
+        self.load_folder().await?;
+        self.set_status(format!("Restored \"{}\" to {}", copy.subject, dest));
+        Ok(())
+    }
+
+    /// Act on the message "Triage Loop" is showing: `a` archives it, `d`
+    /// trashes it, `k` keeps it (marking it read) and `s` skips it. The
+    /// loop moves on to the next message and ends after the last one.
+    async fn triage_loop_step(&mut self, step: char) -> Result<()> {
+        let before = self.visible_count();
+        let result = match step {
+            'a' => self.move_to_target("archive").await,
+            'd' => self.move_to_target("trash").await,
+            'k' => {
+                let unread: HashSet<u32> = self
+                    .target_envelopes()
+                    .iter()
+                    .filter(|e| !e.flags.contains(&Flag::Seen))
+                    .map(|e| e.docid)
+                    .collect();
+                if unread.is_empty() {
+                    Ok(())
+                } else {
+                    self.selected_set = unread;
+                    self.triage_toggle_flag('S', "read").await
+                }
+            }
+            _ => Ok(()),
+        };
+        let moved = self.visible_count() < before;
+        if let Some(state) = self.triage_loop.as_mut() {
+            match step {
+                'a' if moved => state.archived += 1,
+                'd' if moved => state.trashed += 1,
+                'k' => state.kept += 1,
+                's' => state.skipped += 1,
+                _ => {}
+            }
+        }
+        if matches!(step, 'k' | 's') {
+            self.selected += 1;
+        }
+        self.preview_scroll = 0;
+        if self.pending_confirm.is_some() {
+            // Answered from the list: the loop can be started again after
+            self.triage_loop = None;
+            self.mode = InputMode::Normal;
+        } else if self.selected >= self.visible_count() || step == 'q' {
+            self.end_triage_loop();
+        }
+        result
+    }
+
+    /// Leave "Triage Loop", reporting what it did.
+    fn end_triage_loop(&mut self) {
+        if let Some(state) = self.triage_loop.take() {
+            self.set_status(state.summary());
+        }
+        self.mode = InputMode::Normal;
+        self.clamp_selection();
+    }
+
+    /// Write every message matching the current query to the directory
+    /// or mbox typed in the bottom bar, oldest first. Progress is drawn on
+    /// the bottom line; `Esc` stops the export, keeping what was written.
+    async fn export_search(&mut self) {
+        let Some(target) = export::Target::parse(&self.export_input) else {
+            self.set_status("Export cancelled");
+            return;
+        };
+        let opts = FindOpts {
+            threads: false,
+            descending: false,
+            max_num: EXPORT_LIMIT,
+            ..FindOpts::default()
+        };
+        let query = self.current_query.clone();
+        let found = self
+            .mu
+            .find_with_progress(&query, &opts, |progress| {
+                progress.elapsed < FIND_PROGRESS_DELAY || show_find_progress(progress)
+            })
+            .await;
+        self.needs_clear = true;
+        let envelopes = match found {
+            Ok(Some(envelopes)) => envelopes,
+            Ok(None) => {
+                self.set_status("Export cancelled");
+                return;
+            }
+            Err(e) => {
+                self.set_status(format!("Export error: {}", e));
+                return;
+            }
+        };
+        let total = envelopes.len();
+        let mut out = match export::Export::start(target.clone(), total) {
+            Ok(out) => out,
+            Err(e) => {
+                self.set_status(format!("Export error: {:#}", e));
+                return;
+            }
+        };
+        let mut unreadable = 0;
+        let mut stopped = false;
+        for (i, e) in envelopes.iter().enumerate() {
+            if i % 20 == 0 {
+                if esc_pressed() {
+                    stopped = true;
+                    break;
+                }
+                write_bottom_line(&export::progress_line(i, total));
+            }
+            let Ok(raw) = std::fs::read(&e.path) else {
+                debug_log!("export: can't read {}", e.path.display());
+                unreadable += 1;
+                continue;
+            };
+            if let Err(err) = out.add(&raw, &e.subject) {
+                self.set_status(format!("Export error: {:#}", err));
+                return;
+            }
+        }
+        let written = match out.finish() {
+            Ok(written) => written,
+            Err(e) => {
+                self.set_status(format!("Export error: {:#}", e));
+                return;
+            }
+        };
+        let place = target.path().display();
+        let mut status = if stopped {
+            format!("Export stopped: {} of {} message(s) written to {}", written, total, place)
+        } else {
+            format!("Exported {} message(s) to {}", written, place)
+        };
+        if unreadable > 0 {
+            status.push_str(&format!(" ({} unreadable)", unreadable));
+        }
+        self.set_status(status);
+    }
+
+    /// Point the built-in @Waiting folder at the overdue reminders.
+    /// A user folder named Waiting is left alone.
+    fn refresh_waiting_query(&mut self) {
+        let builtin = self.smart_folders.iter().any(|sf| sf.builtin && sf.name == "Waiting");
+        if builtin {
+            self.smart_folder_queries.insert(
+                "@Waiting".to_string(),
+                followups::waiting_query(&self.followups, followups::today()),
+            );
+        }
+    }
+
+    /// Move inbox mail from blocked senders to spam, then point the
+    /// built-in @Screened folder at the inbox mail from first-time
+    /// senders. Returns a notice when blocked mail was moved.
+    async fn screen_inbox(&mut self) -> Option<String> {
+        let inbox = self.account()?.folders.inbox.clone();
+        let mut notice = None;
+        if let Some(query) = screener::senders_query(&inbox, &self.blocked) {
+            let moved = self.move_to_spam(&query).await.len();
+            if moved > 0 {
+                let (spam, _) = self.resolve_move_target("spam");
+                notice = Some(format!("Moved {} message(s) from blocked senders to {}", moved, spam));
+            }
+        }
+        self.refresh_screened_query().await;
+        notice
+    }
+
+    /// Point the built-in @Screened folder at the inbox mail from senders
+    /// you've never written to and who aren't contacts. A user folder
+    /// named Screened is left alone.
+    async fn refresh_screened_query(&mut self) {
+        let builtin = self.smart_folders.iter().any(|sf| sf.builtin && sf.name == "Screened");
+        let Some(account) = self.account().filter(|_| builtin) else {
+            return;
+        };
+        let inbox = account.folders.inbox.clone();
+        let sent = account.folders.sent.clone();
+        let mut known = HashSet::from([account.email.to_lowercase()]);
+        let opts = FindOpts {
+            threads: false,
+            max_num: screener::SENT_LIMIT,
+            ..FindOpts::default()
+        };
+        match self.mu.find(&maildir_term(&sent), &opts).await {
+            Ok(sent) => known.extend(screener::recipients(&sent)),
+            Err(e) => {
+                debug_log!("screener: find {} failed: {}", sent, e);
+                return;
+            }
+        }
+        known.extend(self.address_book.iter().map(|c| c.email.to_lowercase()));
+        let opts = FindOpts {
+            threads: false,
+            max_num: self.config.max_results.max(MIN_MAX_RESULTS),
+            ..FindOpts::default()
+        };
+        let senders = match self.mu.find(&maildir_term(&inbox), &opts).await {
+            Ok(messages) => screener::first_time_senders(&messages, &known),
+            Err(e) => {
+                debug_log!("screener: find {} failed: {}", inbox, e);
+                return;
+            }
+        };
+        self.smart_folder_queries.insert(
+            "@Screened".to_string(),
+            screener::senders_query(&inbox, &senders).unwrap_or_else(|| followups::NO_MATCH_QUERY.to_string()),
+        );
+    }
+
+    /// Move everything `query` matches to the spam folder. Returns the
+    /// moves made, as `(new docid, maildir, flags)` for undo.
+    async fn move_to_spam(&mut self, query: &str) -> Vec<(u32, String, String)> {
+        let (spam, desc) = self.resolve_move_target("spam");
+        let opts = FindOpts {
+            threads: false,
+            max_num: self.config.max_results.max(MIN_MAX_RESULTS),
+            ..FindOpts::default()
+        };
+        let found = match self.mu.find(query, &opts).await {
+            Ok(found) => found,
+            Err(e) => {
+                debug_log!("move_to_spam: find failed: {}", e);
+                return Vec::new();
+            }
+        };
+        if found.is_empty() {
+            return Vec::new();
+        }
+        if let Err(e) = self.ensure_folder(&spam) {
+            debug_log!("move_to_spam: creating {} failed: {}", spam, e);
+            return Vec::new();
+        }
+        let mut moves = Vec::new();
+        let mut records = Vec::new();
+        for e in &found {
+            let (docid, maildir, flags, message_id) = triage_target(e);
+            match self.mu.move_msg(docid, Some(&spam), None).await {
+                Ok(new_docid) => {
+                    self.renumber(docid, new_docid);
+                    records.push(OpRecord::new(
+                        OpKind::Move,
+                        &message_id,
+                        &e.subject,
+                        format!("{}: {} \u{2192} {}", desc, maildir, spam),
+                    ));
+                    moves.push((new_docid, maildir, flags));
+                }
+                Err(err) => debug_log!("move_to_spam: move docid {} failed: {}", docid, err),
+            }
+        }
+        oplog::append(self.account_name(), &records);
+        self.invalidate_folder_cache();
+        moves
+    }
+
+    /// Let `sender` through the screener: add them to the contacts under
+    /// the name their mail gives, and unblock them.
+    async fn approve_sender(&mut self, sender: Address) -> Result<()> {
+        let added = contacts::find(&self.address_book, &sender.email).is_none();
+        if added {
+            let name = sender.name.clone().unwrap_or_else(|| sender.email.clone());
+            contacts::set_name(&mut self.address_book, &sender, &name);
+            contacts::save_contacts(&self.address_book);
+            contacts::use_names(&self.address_book, &self.config.display_names);
+            self.contacts = None;
+        }
+        if screener::unblock(&mut self.blocked, &sender.email) {
+            screener::save_blocked(&self.blocked);
+        }
+        self.refresh_screened_query().await;
+        if self.current_folder == "@Screened" {
+            self.load_folder().await?;
+        }
+        self.set_status(if added {
+            format!("Approved {} \u{2014} added to contacts", sender.email)
+        } else {
+            format!("{} is already a contact", sender.email)
+        });
+        Ok(())
+    }
+
+    /// Block `sender`: their inbox mail goes to spam, now and after every
+    /// reindex, until they're approved.
+    async fn block_sender(&mut self, sender: Address) -> Result<()> {
+        if screener::block(&mut self.blocked, &sender.email) {
+            screener::save_blocked(&self.blocked);
+        }
+        if self.indexing {
+            self.set_status(format!(
+                "Blocked {} \u{2014} their mail moves to spam once the reindex finishes",
+                sender.email
+            ));
+            return Ok(());
+        }
+        let Some(inbox) = self.account().map(|a| a.folders.inbox.clone()) else {
+            return Ok(());
+        };
+        let query = screener::senders_query(&inbox, &[sender.email.to_lowercase()]).unwrap_or_default();
+        let moves = self.move_to_spam(&query).await;
+        let count = moves.len();
+        let desc = format!("Blocked {}", sender.email);
+        if !moves.is_empty() {
+            self.undo_stack.push(UndoEntry {
+                action: UndoAction::MoveMessages { moves },
+                description: desc.clone(),
+            });
+        }
+        self.refresh_screened_query().await;
+        self.load_folder().await?;
+        let (spam, _) = self.resolve_move_target("spam");
+        self.set_status(format!("{}: moved {} message(s) to {}", desc, count, spam));
+        Ok(())
+    }
+
+    /// Drop reminders whose thread now has a reply from a correspondent,
+    /// and return a notice for reminders that have newly become overdue.
+    async fn check_followups(&mut self) -> Option<String> {
+        if self.followups.is_empty() {
+            return None;
+        }
+        let opts = FindOpts {
+            max_num: 200,
+            threads: true,
+            include_related: true,
+            ..FindOpts::default()
+        };
+        let mut answered = Vec::new();
+        for followup in &self.followups {
+            let query = format!("msgid:\"{}\"", followup.message_id);
+            match self.mu.find(&query, &opts).await {
+                Ok(thread) if followup.has_reply(&thread) => {
+                    answered.push(followup.message_id.clone())
+                }
+                Ok(_) => {}
+                Err(e) => debug_log!("followups: find {} failed: {}", followup.message_id, e),
+            }
+        }
+        if !answered.is_empty() {
+            debug_log!("followups: {} answered", answered.len());
+            self.followups.retain(|f| !answered.contains(&f.message_id));
+            followups::save_followups(&self.followups, self.account_name());
+        }
+        self.refresh_waiting_query();
+
+        if !self.config.notify_followups {
+            return None;
+        }
+        let today = followups::today();
+        let newly_overdue: Vec<String> = self.followups
+            .iter()
+            .filter(|f| f.is_overdue(today) && !self.followups_notified.contains(&f.message_id))
+            .map(|f| f.message_id.clone())
+            .collect();
+        if newly_overdue.is_empty() {
+            return None;
+        }
+        let count = newly_overdue.len();
+        self.followups_notified.extend(newly_overdue);
+        Some(format!("{} follow-up(s) overdue \u{2014} see @Waiting", count))
+    }
+
+    /// Sum up the day's triage in the status bar, once a day from the
+    /// `triage_summary` time on, celebrating an empty inbox.
+    async fn show_triage_summary(&mut self) {
+        let Some(at) = self.triage_summary_at else {
+            return;
+        };
+        let now = chrono::Local::now();
+        if now.time() < at || self.triage_summary_shown == Some(now.date_naive()) {
+            return;
+        }
+        self.triage_summary_shown = Some(now.date_naive());
+        let stats = triage_stats::today(self.account_name());
+        let inbox = self.account().map_or("/Inbox".to_string(), |a| a.folders.inbox.clone());
+        let left = self.mu.count(&maildir_term(&inbox), FindOpts::default().max_num).await;
+        let message = match left {
+            Ok(0) => format!("Inbox zero! {}", stats.summary()),
+            Ok(n) => format!("Today: {} \u{00b7} {} left in the inbox", stats.summary(), n),
+            Err(e) => {
+                debug_log!("triage summary: counting the inbox: {}", e);
+                format!("Today: {}", stats.summary())
+            }
+        };
+        self.set_status(message);
+    }
+
+    /// Send the one-line reply typed in the bottom bar to the selected
+    /// message's sender. Pre-send warnings turn into a y/n confirmation.
+    async fn send_quick_reply(&mut self) {
+        let text = self.quick_reply_input.trim().to_string();
+        if text.is_empty() {
+            self.set_status("Empty reply not sent");
+            return;
+        }
+        let Some(envelope) = self.selected_envelope() else {
+            return;
+        };
+        let mut ctx = self.reply_context(envelope, "", false);
+        ctx.quoted_body = text;
+        self.send_without_editor(&ctx, None).await;
+    }
+
+    /// Reply to the selected message with a named template, either
+    /// opening the editor pre-filled or sending directly.
+    async fn reply_from_template(&mut self, name: &str, send: bool, confirm: bool) {
+        let template = match templates::load_template(name) {
+            Ok(t) => t,
+            Err(e) => {
+                self.set_status(format!("Template error: {}", e));
+                return;
+            }
+        };
+        let Some(mut ctx) = self.build_compose_context(&compose::ComposeKind::Reply) else {
+            return;
+        };
+        let body = templates::expand_template(&template, ctx.to.first());
+        ctx.quoted_body = if ctx.quoted_body.is_empty() {
+            body
+        } else {
+            format!("{}\n{}", body.trim_end(), ctx.quoted_body)
+        };
+        if !send {
+            self.compose_pending = Some(compose::ComposePending::Ready(Box::new(ctx)));
+            return;
+        }
+        let prompt = confirm.then(|| {
+            let to: Vec<String> = ctx.to.iter().map(|a| a.short_display()).collect();
+            format!("Send \"{}\" reply to {}?", name, to.join(", "))
+        });
+        self.send_without_editor(&ctx, prompt).await;
+    }
+
+    /// Build a message from `ctx` and send it without the editor. Pre-send
+    /// warnings, or a `confirm` prompt, turn into a y/n confirmation.
+    async fn send_without_editor(&mut self, ctx: &compose::ComposeContext, confirm: Option<String>) {
+        let from_email = self
+            .account()
+            .map(|a| a.email.as_str())
+            .unwrap_or("user@example.com");
+        let content = match compose::build_compose_file(ctx, from_email) {
+            Ok(content) => content,
+            Err(e) => {
+                self.set_status(format!("Compose error: {}", e));
+                return;
+            }
+        };
+        self.load_contacts().await;
+        let checks = compose::SendChecks {
+            old_reply_days: self.config.warn_reply_older_than_days,
+            max_recipients: self.config.warn_recipients_over,
+            internal_domains: self.account().map_or(&[], |a| a.internal_domains.as_slice()),
+            known_addresses: self.contacts.as_deref().unwrap_or_default(),
+            max_attachments_mb: self.config.warn_attachments_over_mb,
+        };
+        let warnings = compose::reply_warnings(ctx, &content, chrono::Utc::now(), checks);
+        let prompt = if warnings.is_empty() {
+            confirm
+        } else {
+            Some(format!("{} \u{2014} send anyway?", warnings.join("; ")))
+        };
+        match prompt {
+            Some(prompt) => {
+                self.set_status(format!("{} (y/n)", prompt));
+                self.pending_confirm = Some(ConfirmAction::SendMessage(content));
+            }
+            None => self.send_composed(&content).await,
+        }
+    }
+
+    /// Fetch the account's contacts from mu for the pre-send checks, once,
+    /// after those in the address book. A failure from mu leaves just the
+    /// address book, or an empty list, which turns the check off.
+    async fn load_contacts(&mut self) {
+        if self.contacts.is_none() {
+            let mut known: Vec<String> = self.address_book.iter().map(|c| c.email.clone()).collect();
+            known.extend(self.mu.contacts(CONTACTS_LIMIT).await.unwrap_or_default());
+            self.contacts = Some(known);
+        }
+    }
+
+    /// Sender of the message in focus: the one open in the thread view,
+    /// else the selected one.
+    fn focused_sender(&self) -> Option<Address> {
+        let focused = match self.mode {
+            InputMode::ThreadView => self.thread_messages.get(self.thread_selected).map(|m| &m.envelope),
+            _ => self.selected_envelope(),
+        };
+        focused.and_then(|e| e.from.first()).cloned()
+    }
+
+    /// Ask what to call `address` in the address book, starting from the
+    /// name it has now.
+    fn edit_contact(&mut self, address: Address) {
+        self.contact_input = contacts::find(&self.address_book, &address.email)
+            .and_then(|c| c.display().map(String::from))
+            .or_else(|| address.name.clone())
+            .unwrap_or_default();
+        self.contact_target = Some(address);
+        self.contact_return = self.mode.clone();
+        self.mode = InputMode::ContactName;
+    }
+
+    /// Save the name typed for the contact being added or edited; no name
+    /// removes them from the address book.
+    fn save_contact(&mut self) {
+        self.mode = self.contact_return.clone();
+        let Some(address) = self.contact_target.take() else {
+            return;
+        };
+        let existed = contacts::find(&self.address_book, &address.email).is_some();
+        let kept = contacts::set_name(&mut self.address_book, &address, &self.contact_input);
+        if !kept && !existed {
+            self.set_status("Not added: no name given");
+            return;
+        }
+        contacts::save_contacts(&self.address_book);
+        contacts::use_names(&self.address_book, &self.config.display_names);
+        self.contacts = None;
+        self.set_status(if kept {
+            format!("Contact saved: {} <{}>", self.contact_input.trim(), address.email)
+        } else {
+            format!("Removed {} from contacts", address.email)
+        });
+    }
+
+    /// Send a composed message from inside the TUI, save it to Sent and
+    /// index the saved copy. The terminal is in normal mode during the send,
+    /// as when sending from the editor, so password_command (e.g. pass/gpg
+    /// pinentry) can use the tty.
+    async fn send_composed(&mut self, content: &str) {
+        let Some(acct) = self.account().cloned() else {
+            self.set_status("No SMTP account configured");
+            return;
+        };
+        let content = compose::wrap_body(content, self.config.compose_wrap);
+        suspend_terminal();
+        {
+            use std::io::Write;
+            print!("Sending...");
+            let _ = io::stdout().flush();
+        }
+        let result = send::send_message(&content, &acct.smtp).await;
+        resume_terminal();
+        self.needs_clear = true;
+        match result {
+            Ok(sent) => {
+                oplog::append(&acct.name, &[OpRecord::for_delivery(&sent)]);
+                match save_to_sent(&acct.maildir, &acct.folders.sent, &sent.formatted) {
+                    Ok(path) => {
+                        self.set_status(sent.status());
+                        self.index_sent_copy(Some(path)).await;
+                    }
+                    Err(e) => {
+                        self.set_status(format!("Sent but failed to save to Sent folder: {}", e));
+                        self.index_sent_copy(None).await;
+                    }
+                }
+            }
+            Err(e) => self.set_status(format!("Send error: {}", e)),
+        }
+    }
+
+    /// Add the Sent copy of a message to the index. A full reindex is
+    /// only the fallback, when there is no saved copy or mu rejects it.
+    async fn index_sent_copy(&mut self, path: Option<std::path::PathBuf>) {
+        let Some(path) = path else {
+            self.needs_reindex = true;
+            return;
+        };
+        match self.mu.add_msg(&path).await {
+            Ok(docid) => {
+                debug_log!("sent: indexed {:?} as docid {}", path, docid);
+                self.invalidate_folder_cache();
+                if let Err(e) = self.load_folder().await {
+                    debug_log!("sent: reload error: {}", e);
+                }
+            }
+            Err(e) => {
+                debug_log!("sent: add failed, reindexing: {}", e);
+                self.needs_reindex = true;
+            }
+        }
+    }
+
+    /// Schedule a reindex after a command that may have changed the
+    /// maildir. Only folders modified since `since` matter: if there are
+    /// none the reindex is skipped, otherwise mu's lazy check limits the
+    /// work to them.
+    fn request_reindex_since(&mut self, since: std::time::SystemTime) {
+        let Some(account) = self.account() else {
+            self.needs_reindex = true;
+            return;
+        };
+        // Let mu look for itself rather than walk a slow mount
+        if self.maildir_slow {
+            self.needs_reindex = true;
+            self.lazy_reindex = true;
+            return;
+        }
+        let root = expand_maildir_root(&account.maildir);
+        let changed = maildir::changed_dirs(std::path::Path::new(&root), since);
+        debug_log!("reindex: {} maildir folder(s) changed", changed.len());
+        if !changed.is_empty() {
+            self.needs_reindex = true;
+            self.lazy_reindex = true;
+        }
+    }
+
+    /// Reapply flag changes that a sync reverted (see [`flag_conflicts`]).
+    async fn reapply_flags(&mut self, changes: &[(u32, String)]) {
+        let mut succeeded = 0u32;
+        let mut errors = 0u32;
+        let mut records = Vec::new();
+        for (docid, flags) in changes {
+            match self.mu.move_msg(*docid, None, Some(flags)).await {
+                Ok(new_docid) => {
+                    succeeded += 1;
+                    records.push(self.op_record(
+                        *docid,
+                        OpKind::Flag,
+                        format!("reapplied after sync: {}", flags),
+                    ));
+                    if let Some(e) = self.envelopes.iter_mut().find(|e| e.docid == *docid) {
+                        e.docid = new_docid;
+                        let derived: Vec<Flag> = e.flags
+                            .iter()
+                            .filter(|f| matches!(f, Flag::List | Flag::Attach))
+                            .cloned()
+                            .collect();
+                        e.flags = flags_from_string(flags);
+                        e.flags.extend(derived);
+                    }
+                }
+                Err(e) => {
+                    debug_log!("reapply_flags: move docid {} failed: {}", docid, e);
+                    errors += 1;
+                }
+            }
+        }
+        oplog::append(self.account_name(), &records);
+        self.invalidate_folder_cache();
+        if errors > 0 {
+            self.set_status(format!("Reapplied flags on {} message(s) ({} failed)", succeeded, errors));
+        } else {
+            self.set_status(format!("Reapplied flags on {} message(s)", succeeded));
+        }
+    }
+
+    /// Stop tracking local flag changes for the given messages.
+    fn forget_local_flags(&mut self, changes: &[(u32, String)]) {
+        for (docid, _) in changes {
+            if let Some(e) = self.envelopes.iter().find(|e| e.docid == *docid) {
+                self.local_flags.remove(&e.message_id);
+            }
+        }
+    }
+
+    /// Build an operations-log record for a message in the current list.
+    fn op_record(&self, docid: u32, op: OpKind, detail: String) -> OpRecord {
+        let envelope = self.envelopes.iter().find(|e| e.docid == docid);
+        OpRecord::new(
+            op,
+            envelope.map_or("", |e| e.message_id.as_str()),
+            envelope.map_or("", |e| e.subject.as_str()),
+            detail,
+        )
+    }
+
+    /// Add `keyword` to the targeted messages, or remove it if they all
+    /// have it. Each message is delivered again with its keyword header
+    /// rewritten; the new file is added to mu and the old one dropped, so
+    /// `tag:` searches see the change.
+    async fn toggle_keyword(&mut self, keyword: &str) -> Result<()> {
+        let targets: Vec<(u32, std::path::PathBuf, bool)> = self
+            .target_envelopes()
+            .into_iter()
+            .map(|e| (e.docid, e.path.clone(), e.tags.iter().any(|t| t.eq_ignore_ascii_case(keyword))))
+            .collect();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        if self.indexing {
+            self.set_status("Keywords need mu: try again when the reindex finishes");
+            return Ok(());
+        }
+        let on = !targets.iter().all(|(_, _, has)| *has);
+        let mut succeeded = 0u32;
+        let mut errors = 0u32;
+        let mut records = Vec::new();
+        for (docid, path, _) in &targets {
+            let result = match keywords::set_in_file(path, keyword, on) {
+                Ok(Some(moved)) => match self.mu.add_msg(&moved).await {
+                    Ok(new_docid) => {
+                        // mu still has the old file under the old docid
+                        if let Err(e) = self.mu.remove_msg(*docid).await {
+                            debug_log!("toggle_keyword: dropping {} failed: {}", docid, e);
+                        }
+                        Ok((new_docid, moved))
+                    }
+                    Err(e) => Err(e),
+                },
+                Ok(None) => Ok((*docid, path.clone())),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok((new_docid, moved)) => {
+                    succeeded += 1;
+                    let change = if on { "added" } else { "removed" };
+                    records.push(self.op_record(*docid, OpKind::Flag, format!("keyword {}: {}", keyword, change)));
+                    self.renumber(*docid, new_docid);
+                    let copies = self
+                        .envelopes
+                        .iter_mut()
+                        .chain(self.conversations.iter_mut().flat_map(|c| c.messages.iter_mut()))
+                        .chain(self.thread_messages.iter_mut().map(|m| &mut m.envelope));
+                    for e in copies.filter(|e| e.docid == *docid) {
+                        e.docid = new_docid;
+                        e.path = moved.clone();
+                        e.tags.retain(|t| !t.eq_ignore_ascii_case(keyword));
+                        if on {
+                            e.tags.push(keyword.to_string());
+                        }
+                    }
+                }
+                Err(e) => {
+                    debug_log!("toggle_keyword: {:?} failed: {}", path, e);
+                    errors += 1;
+                }
+            }
+        }
+        oplog::append(self.account_name(), &records);
+        self.invalidate_folder_cache();
+        self.selected_set.clear();
+        if succeeded > 0 && self.view_filters_on_flags() {
+            self.view_refresh_at = Some(Instant::now() + VIEW_REFRESH_DELAY);
+        }
+        let verb = if on { "Added" } else { "Removed" };
+        let prep = if on { "to" } else { "from" };
+        if errors > 0 {
+            self.set_status(format!("{} {} {} {} message(s) ({} failed)", verb, keyword, prep, succeeded, errors));
+        } else {
+            self.set_status(format!("{} {} {} {} message(s)", verb, keyword, prep, succeeded));
+        }
+        Ok(())
+    }
+
+    /// The messages triage acts on, as `(docid, maildir, flags, message_id)`.
+    fn triage_targets(&self) -> Vec<(u32, String, String, String)> {
+        self.target_envelopes().into_iter().map(triage_target).collect()
+    }
+
+    /// The messages an action on "the selection" covers: the multi-selected
+    /// ones, else the selected conversation or message.
+    fn target_envelopes(&self) -> Vec<&Envelope> {
+        if !self.selected_set.is_empty() {
+            self.envelopes
+                .iter()
+                .filter(|e| self.selected_set.contains(&e.docid))
+                .collect()
+        } else if self.conversations_mode {
+            // In conversations mode, act on all messages in the selected conversation
+            if let Some(convo) = self.conversations.get(self.selected) {
+                convo.messages.iter().collect()
+            } else {
+                vec![]
+            }
+        } else if let Some(e) = self.envelopes.get(self.selected) {
+            vec![e]
+        } else {
+            vec![]
+        }
+    }
+
+    /// A shell binding's command with its placeholders filled in, each
+    /// value quoted for the shell: `{path}` and `{msgid}` of the message
+    /// being read, `{folder}`, `{account}`, `{maildir}` (the account's
+    /// maildir root) and `{selection_paths}`, the files of the selected
+    /// messages.
+    fn shell_binding_command(&self, command: &str) -> Result<String> {
+        let focused = match self.mode {
+            InputMode::ThreadView => self.thread_messages.get(self.thread_selected).map(|m| &m.envelope),
+            _ => self.selected_envelope(),
+        };
+        let path = focused.map(|e| shell_quote(&e.path.to_string_lossy()));
+        let msgid = focused.map(|e| shell_quote(&e.message_id));
+        let targets = self.target_envelopes();
+        let selection = (!targets.is_empty()).then(|| {
+            targets
+                .iter()
+                .map(|e| shell_quote(&e.path.to_string_lossy()))
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        let account = self.account();
+        let values = [
+            ("path", path),
+            ("msgid", msgid),
+            ("folder", Some(shell_quote(&self.current_folder))),
+            ("account", account.map(|a| shell_quote(&a.name))),
+            ("maildir", account.map(|a| shell_quote(&expand_maildir_root(&a.maildir)))),
+            ("selection_paths", selection),
+        ];
+        fill_placeholders(command, &values)
+            .map_err(|name| anyhow::anyhow!("{{{}}} needs a message, and none is selected", name))
+    }
+
+    /// Patch emails to save or apply: the multi-selected messages, oldest
+    /// first so a series applies in order, or else the selected one.
+    fn patch_targets(&self) -> Vec<&Envelope> {
+        let mut targets: Vec<&Envelope> = self
+            .envelopes
+            .iter()
+            .filter(|e| self.selected_set.contains(&e.docid))
+            .collect();
+        if targets.is_empty() {
+            targets.extend(self.selected_envelope());
+        }
+        targets.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.subject.cmp(&b.subject)));
+        targets
+    }
+
+    /// The bottom bar's hint line: the keys most useful right now, with
+    /// the user's bindings. `None` leaves the mode's fixed hints.
+    fn context_hints(&self) -> Option<String> {
+        let mode = &self.mode;
+        let entries: &[(&str, &str, &str)] = match mode {
+            InputMode::Normal if self.keymap.pending_display().as_deref() == Some("g") => {
+                let entries = [
+                    ("go_inbox", "g i", "inbox"),
+                    ("go_archive", "g a", "archive"),
+                    ("go_sent", "g t", "sent"),
+                    ("go_drafts", "g d", "drafts"),
+                    ("go_starred", "g s", "starred"),
+                    ("go_folder_picker", "g l", "folders"),
+                    ("new_view", "g n", "new view"),
+                    ("compare", "g c", "compare"),
+                    ("jump_top", "g g", "top"),
+                ];
+                // The g is already shown as pending; list what may follow it
+                let hints: Vec<String> = entries
+                    .iter()
+                    .filter_map(|(action, default, label)| {
+                        let key = self.keymap.hint_key(action, default, mode)?;
+                        Some(format!("{} {}", key.strip_prefix("g ")?, label))
+                    })
+                    .collect();
+                return Some(hints.join(" \u{b7} "));
+            }
+            InputMode::Normal if !self.selected_set.is_empty() => &[
+                ("archive", "e", "archive"),
+                ("trash", "#", "trash"),
+                ("move_to_folder", "m", "move"),
+                ("toggle_read", "u", "read"),
+                ("toggle_star", "s", "star"),
+                ("clear_selection", "Esc", "clear"),
+            ],
+            InputMode::Normal if self.visible_count() == 0 => &[
+                ("search", "/", "search"),
+                ("go_folder_picker", "g l", "folders"),
+                ("sync_mail", "Ctrl+r", "sync"),
+                ("help", "?", "help"),
+            ],
+            InputMode::Normal => &[
+                ("open_thread", "Enter", "open"),
+                ("archive", "e", "archive"),
+                ("trash", "#", "trash"),
+                ("reply", "r", "reply"),
+                ("toggle_select", "x", "select"),
+                ("search", "/", "search"),
+                ("command_palette", "Ctrl+k", "commands"),
+                ("help", "?", "help"),
+            ],
+            InputMode::ThreadView => &[
+                ("thread_toggle_expand", "o", "expand"),
+                ("reply", "r", "reply"),
+                ("reply_all", "a", "reply all"),
+                ("forward", "f", "forward"),
+                ("archive", "e", "archive"),
+                ("close_thread", "q", "back"),
+                ("help", "?", "help"),
+            ],
+            _ => return None,
+        };
+        Some(self.keymap.hint_line(entries, mode))
+    }
+
+    /// Total size of the multi-selected messages, in bytes.
+    fn selection_size(&self) -> usize {
+        self.envelopes
+            .iter()
+            .filter(|e| self.selected_set.contains(&e.docid))
+            .map(|e| e.size)
+            .sum()
+    }
+
+    fn clamp_selection(&mut self) {
+        let count = self.visible_count();
+        if count > 0 && self.selected >= count {
+            self.selected = count - 1;
+        }
+    }
+
+    async fn undo(&mut self) -> Result<()> {
+        if let Some(entry) = self.undo_stack.pop() {
+            // Moving messages mid-index would race the docids it hands out
+            let moves = matches!(entry.action, UndoAction::MoveMessage { .. } | UndoAction::MoveMessages { .. });
+            if self.indexing && moves {
+                self.undo_stack.push(entry);
+                self.set_status("Undo waits for the reindex: try again when it finishes");
+                return Ok(());
+            }
+            match entry.action {
+                UndoAction::MoveMessage {
                     docid,
                     original_maildir,
                     original_flags,
@@ -1455,12 +4236,47 @@ impl App {
                     } else {
                         Some(original_flags.as_str())
                     };
-                    self.mu
+                    // mu may have renumbered it since, e.g. a flag toggled
+                    let docid = self.registry.current(docid);
+                    let new_docid = self.mu
                         .move_msg(docid, Some(&original_maildir), flags)
                         .await?;
+                    let record = self.op_record(
+                        docid,
+                        OpKind::Undo,
+                        format!("{}: \u{2192} {}", entry.description, original_maildir),
+                    );
+                    self.renumber(docid, new_docid);
+                    self.local_flags.remove(&record.message_id);
+                    oplog::append(self.account_name(), &[record]);
                     self.invalidate_folder_cache();
                     self.load_folder().await?;
                 }
+                UndoAction::MoveMessages { moves } => {
+                    let mut records = Vec::new();
+                    for (docid, original_maildir, original_flags) in &moves {
+                        let flags = (!original_flags.is_empty()).then_some(original_flags.as_str());
+                        let docid = self.registry.current(*docid);
+                        match self.mu.move_msg(docid, Some(original_maildir), flags).await {
+                            Ok(new_docid) => {
+                                records.push(self.op_record(
+                                    docid,
+                                    OpKind::Undo,
+                                    format!("{}: \u{2192} {}", entry.description, original_maildir),
+                                ));
+                                self.renumber(docid, new_docid);
+                            }
+                            Err(e) => debug_log!("undo: move docid {} failed: {}", docid, e),
+                        }
+                    }
+                    for record in &records {
+                        self.local_flags.remove(&record.message_id);
+                    }
+                    oplog::append(self.account_name(), &records);
+                    self.invalidate_folder_cache();
+                    self.load_folder().await?;
+                }
+                UndoAction::HeldTriage { id } => self.drop_held_triage(id),
                 UndoAction::DeleteSmartFolder { folder } => {
                     self.smart_folders.push(folder.clone());
                     smart_folders::save_smart_folders(&self.smart_folders, self.account_name());
@@ -1487,11 +4303,8 @@ impl App {
                 UndoAction::DeleteMaildirFolder { path } => {
                     // Re-create the maildir directory structure
                     if let Some(account) = self.account() {
-                        let root = expand_maildir_root(&account.maildir);
-                        let full = format!("{}{}", root, path);
-                        let _ = std::fs::create_dir_all(format!("{}/cur", full));
-                        let _ = std::fs::create_dir_all(format!("{}/new", full));
-                        let _ = std::fs::create_dir_all(format!("{}/tmp", full));
+                        let root = std::path::PathBuf::from(expand_maildir_root(&account.maildir));
+                        let _ = maildir::create_folder(&root, &path);
                         self.known_folders.push(path);
                         self.known_folders.sort();
                     }
@@ -1516,15 +4329,28 @@ impl App {
 
         let old_active = self.active_account;
 
+        // A reindex still running is abandoned along with its server. The
+        // triage held for it is saved for this account, to be made when
+        // it's next active, so it can't be replayed on the new one.
+        let abandoned_index = std::mem::take(&mut self.indexing);
+        let old_name = self.account_name().to_string();
+        held_triage::set_aside(&mut self.deferred_triage, &old_name);
+
         // Swap mu servers: current primary → background, background → primary
         if let Some(new_mu) = self.background_mu.remove(&index) {
             debug_log!("switch_account: swapping mu servers (bg for account {})", index);
             let old_mu = std::mem::replace(&mut self.mu, new_mu);
-            self.background_mu.insert(old_active, old_mu);
+            // One mid-index would answer later commands with index output
+            if !abandoned_index {
+                self.background_mu.insert(old_active, old_mu);
+            }
         } else {
-            // No background server for this account — fall back to quit/restart
+            // No background server for this account — fall back to quit/restart.
+            // One mid-index is killed when it's replaced instead.
             debug_log!("switch_account: no background server, doing quit/restart");
-            self.mu.quit().await?;
+            if !abandoned_index {
+                self.mu.quit().await?;
+            }
             let muhome = self.config.effective_muhome(index);
             if let Some(account) = self.config.accounts.get(index) {
                 let account_name = account.name.clone();
@@ -1538,37 +4364,72 @@ impl App {
             self.mu = MuClient::start(muhome.as_deref()).await?;
         }
 
+        // Persist the old account's snippets and session before switching
+        self.save_snippets();
+        self.save_scores();
+        self.save_session();
+        self.save_warm_start();
+
         // Update active account
         self.active_account = index;
+        self.keymap.load_bindings(&self.config.effective_bindings(index));
 
         // Clear state
         self.envelopes.clear();
         self.thread_messages.clear();
         self.selected_set.clear();
         self.undo_stack = UndoStack::new();
+        self.registry.clear();
+        self.contacts = None;
         self.thread_selected = 0;
         self.thread_scroll = 0;
         self.selected = 0;
         self.scroll_offset = 0;
         self.preview_scroll = 0;
+        // Views hold folders of the old account
+        self.views = vec![ViewState::default()];
+        self.active_view = 0;
 
         // Reload smart folders for new account
         let acct_name = self.account_name().to_string();
-        self.smart_folders = smart_folders::load_smart_folders(&acct_name);
+        self.snippets = snippets::load_snippets(&acct_name);
+        self.snippets_pending.clear();
+        self.scores = scores::load_scores(&acct_name);
+        self.scores_pending.clear();
+        self.smart_folders = smart_folders::load_for_account(
+            &acct_name,
+            &self.config.smart_folders,
+            &self.config.builtin_smart_folders,
+        );
         self.smart_folder_queries = self.smart_folders
             .iter()
             .map(|sf| (format!("@{}", sf.name), sf.query.clone()))
             .collect();
+        self.smart_counts.clear();
+        self.smart_count_due.clear();
+        self.smart_count_queue.clear();
+        self.followups = followups::load_followups(&acct_name);
+        self.followups_notified.clear();
+        self.notes = notes::load_notes(&acct_name);
+        self.refresh_waiting_query();
+        let saved_session = session::load_session(&acct_name).unwrap_or_default();
+        self.recent_folders = saved_session.recent_folders;
+        self.folder_prefs = if self.config.persist_folder_settings {
+            saved_session.folder_prefs
+        } else {
+            HashMap::new()
+        };
+        (self.read_positions, self.thread_positions) = if self.config.persist_read_positions {
+            (saved_session.read_positions, saved_session.thread_positions)
+        } else {
+            Default::default()
+        };
+        self.read_anchor = None;
+        self.thread_anchor = None;
+        self.last_move = None;
 
         // Rebuild known_folders
-        self.known_folders = vec![
-            "/Inbox".into(),
-            "/Archive".into(),
-            "/Drafts".into(),
-            "/Sent".into(),
-            "/Trash".into(),
-            "/Junk".into(),
-        ];
+        self.known_folders = seed_known_folders(self.account());
         for sf in &self.smart_folders {
             self.known_folders.push(format!("@{}", sf.name));
         }
@@ -1587,11 +4448,12 @@ impl App {
         // Rebuild tabs for new account
         self.rebuild_tabs();
 
-        // Navigate to new account's inbox
-        let inbox = self.account()
-            .map(|a| a.folders.inbox.clone())
-            .unwrap_or_else(|| "/Inbox".to_string());
-        self.current_folder = inbox;
+        // Triage held for this account when it was last switched away from
+        let held_notice = self.resume_held_triage().await;
+
+        // Navigate to new account's startup folder (its inbox by default)
+        self.current_folder = self.config.effective_startup_folder(index);
+        self.apply_folder_prefs(true);
         self.load_folder().await?;
 
         // Refresh split caches for the new account
@@ -1600,39 +4462,373 @@ impl App {
             self.load_folder().await?;
         }
 
+        if self.screen_inbox().await.is_some() {
+            self.load_folder().await?;
+        }
+        let notice = self.check_followups().await;
         let name = self.account().map(|a| a.name.as_str()).unwrap_or("?");
-        self.set_status(format!("Switched to {}", name));
+        let mut status = format!("Switched to {}", name);
+        for notice in [held_notice, notice].into_iter().flatten() {
+            status.push_str(&format!(" | {}", notice));
+        }
+        self.set_status(status);
         Ok(())
     }
 
     // ── Folder switching ────────────────────────────────────────────
 
-    async fn navigate_folder(&mut self, folder: &str) -> Result<()> {
-        self.previous_folder = Some(self.current_folder.clone());
-        self.current_folder = folder.to_string();
-        self.filter_unread = false;
-        self.filter_starred = false;
-        self.filter_needs_reply = false;
-        self.adjust_tab_scroll();
-        self.load_folder().await?;
-        self.set_status(format!("Switched to {}", folder));
-        Ok(())
+    async fn navigate_folder(&mut self, folder: &str) -> Result<()> {
+        self.remember_folder_prefs();
+        self.previous_folder = Some(self.current_folder.clone());
+        self.current_folder = folder.to_string();
+        self.apply_folder_prefs(true);
+        self.adjust_tab_scroll();
+        self.load_folder().await?;
+        self.remember_folder(folder);
+        self.set_status(format!("Switched to {}", folder));
+        Ok(())
+    }
+
+    /// Keep the current folder's sort and filters for when it is next
+    /// opened. Folders left on the defaults aren't stored.
+    fn remember_folder_prefs(&mut self) {
+        let prefs = FolderPrefs {
+            sort_field: self.sort_field,
+            sort_descending: self.sort_descending,
+            filter_unread: self.filter_unread,
+            filter_starred: self.filter_starred,
+            filter_needs_reply: self.filter_needs_reply,
+        };
+        if prefs == FolderPrefs::default() {
+            self.folder_prefs.remove(&self.current_folder);
+        } else {
+            self.folder_prefs.insert(self.current_folder.clone(), prefs);
+        }
+    }
+
+    /// Switch to the sort last used in the current folder, and with
+    /// `filters` its filters too (view tabs keep their own).
+    fn apply_folder_prefs(&mut self, filters: bool) {
+        let prefs = self.folder_prefs.get(&self.current_folder).cloned().unwrap_or_default();
+        self.sort_field = prefs.sort_field;
+        self.sort_descending = prefs.sort_descending;
+        if filters {
+            self.filter_unread = prefs.filter_unread;
+            self.filter_starred = prefs.filter_starred;
+            self.filter_needs_reply = prefs.filter_needs_reply;
+        }
+    }
+
+    /// Put `folder` at the front of the recent folders list.
+    fn remember_folder(&mut self, folder: &str) {
+        self.recent_folders.retain(|f| f != folder);
+        self.recent_folders.insert(0, folder.to_string());
+        self.recent_folders.truncate(self.config.recent_folders);
+    }
+
+    // ── View tabs ───────────────────────────────────────────────────
+
+    /// Snapshot of the on-screen view.
+    fn capture_view(&self) -> ViewState {
+        ViewState {
+            folder: self.current_folder.clone(),
+            filter_unread: self.filter_unread,
+            filter_starred: self.filter_starred,
+            filter_needs_reply: self.filter_needs_reply,
+            selected_id: self.preview_envelope().map(|e| e.message_id.clone()),
+            selected: self.selected,
+            scroll_offset: self.scroll_offset,
+        }
+    }
+
+    /// Load a saved view's folder and filters, then put the selection back
+    /// on the same message (or the same row if it has gone).
+    async fn restore_view(&mut self, view: ViewState) -> Result<()> {
+        self.current_folder = view.folder;
+        self.apply_folder_prefs(false);
+        self.filter_unread = view.filter_unread;
+        self.filter_starred = view.filter_starred;
+        self.filter_needs_reply = view.filter_needs_reply;
+        self.selected_set.clear();
+        self.adjust_tab_scroll();
+        self.load_folder().await?;
+        let count = self.visible_count();
+        let found = view.selected_id.as_ref().and_then(|id| {
+            (0..count).find(|&i| {
+                let envelope = if self.conversations_mode {
+                    self.conversations.get(i).map(|c| c.representative())
+                } else {
+                    self.envelopes.get(i)
+                };
+                envelope.is_some_and(|e| &e.message_id == id)
+            })
+        });
+        self.selected = found.unwrap_or(view.selected.min(count.saturating_sub(1)));
+        self.scroll_offset = view.scroll_offset.min(self.selected);
+        Ok(())
+    }
+
+    /// Compare the current folder with another side by side, or stop.
+    /// Both panes start on the current folder; the focused one is then
+    /// moved like the list always is, to any folder or account.
+    fn toggle_compare(&mut self) {
+        if self.compare.take().is_some() {
+            self.set_status("Stopped comparing");
+            return;
+        }
+        self.compare = Some(Compare {
+            other: ComparePane {
+                account: self.active_account,
+                view: self.capture_view(),
+                envelopes: self.envelopes.clone(),
+                selected_set: HashSet::new(),
+            },
+            focus_right: true,
+        });
+        self.set_status("Comparing: go to another folder or account here; g o switches panes");
+    }
+
+    /// Focus the other pane of the comparison, switching account if it's
+    /// another account's, and keep this one as it was.
+    async fn switch_compare_pane(&mut self) -> Result<()> {
+        let Some(compare) = self.compare.take() else {
+            self.set_status("Not comparing (g c compares two folders)");
+            return Ok(());
+        };
+        let here = ComparePane {
+            account: self.active_account,
+            view: self.capture_view(),
+            envelopes: self.envelopes.clone(),
+            selected_set: std::mem::take(&mut self.selected_set),
+        };
+        let there = compare.other;
+        self.remember_folder_prefs();
+        if there.account != self.active_account {
+            if let Err(e) = self.switch_account(there.account).await {
+                self.selected_set = here.selected_set;
+                self.compare = Some(Compare { other: there, focus_right: compare.focus_right });
+                return Err(e);
+            }
+        }
+        self.compare = Some(Compare { other: here, focus_right: !compare.focus_right });
+        self.restore_view(there.view).await?;
+        self.selected_set = there.selected_set;
+        Ok(())
+    }
+
+    /// Open a new view tab showing the current folder.
+    fn new_view(&mut self) {
+        if self.views.len() >= MAX_VIEWS {
+            self.set_status(format!("At most {} views can be open", MAX_VIEWS));
+            return;
+        }
+        self.views[self.active_view] = self.capture_view();
+        self.views.push(self.capture_view());
+        self.active_view = self.views.len() - 1;
+        self.set_status(format!("Opened view {}", self.active_view + 1));
+    }
+
+    /// Close the current view tab and show its neighbour.
+    async fn close_view(&mut self) -> Result<()> {
+        if self.views.len() <= 1 {
+            self.set_status("Only one view is open");
+            return Ok(());
+        }
+        self.remember_folder_prefs();
+        self.views.remove(self.active_view);
+        self.active_view = self.active_view.min(self.views.len() - 1);
+        self.restore_view(self.views[self.active_view].clone()).await?;
+        self.set_status(format!("Closed view; now on view {}", self.active_view + 1));
+        Ok(())
+    }
+
+    /// Switch to view tab `index` (0-based), saving the current one.
+    async fn switch_view(&mut self, index: usize) -> Result<()> {
+        if index >= self.views.len() {
+            self.set_status(format!("No view {}", index + 1));
+            return Ok(());
+        }
+        if index == self.active_view {
+            return Ok(());
+        }
+        self.remember_folder_prefs();
+        self.views[self.active_view] = self.capture_view();
+        self.active_view = index;
+        self.restore_view(self.views[index].clone()).await?;
+        self.set_status(format!("View {}: {}", index + 1, self.current_folder));
+        Ok(())
+    }
+
+    /// Reopen the active account's saved view tabs. The active view is
+    /// loaded by `run` in place of the initial folder.
+    pub fn load_session(&mut self) {
+        let Some(saved) = session::load_session(self.account_name()) else {
+            return;
+        };
+        self.restored_view = Some(saved.views[saved.active_view].clone());
+        self.views = saved.views;
+        self.active_view = saved.active_view;
+    }
+
+    /// Save the view tabs, recent folders and (if configured) folder
+    /// settings for the active account.
+    fn save_session(&mut self) {
+        self.views[self.active_view] = self.capture_view();
+        self.remember_folder_prefs();
+        let saved = Session {
+            views: self.views.clone(),
+            active_view: self.active_view,
+            recent_folders: self.recent_folders.clone(),
+            folder_prefs: if self.config.persist_folder_settings {
+                self.folder_prefs.clone()
+            } else {
+                HashMap::new()
+            },
+            read_positions: if self.config.persist_read_positions {
+                self.read_positions.clone()
+            } else {
+                ReadPositions::default()
+            },
+            thread_positions: if self.config.persist_read_positions {
+                self.thread_positions.clone()
+            } else {
+                ReadPositions::default()
+            },
+        };
+        session::save_session(&saved, self.account_name());
+    }
+
+    /// The sync, reindex or command still running in the background, as
+    /// named in the quit prompt.
+    fn background_work(&self) -> Option<String> {
+        if self.indexing {
+            return Some("Reindex".to_string());
+        }
+        let cmd = self.running_commands.first()?;
+        if self.sync_command().as_ref() == Some(cmd) {
+            Some("Sync".to_string())
+        } else {
+            Some(format!("`{}`", cmd))
+        }
+    }
+
+    /// Recount inbox unread mail and rewrite the status file. Accounts
+    /// without a running mu server keep their last count.
+    async fn write_status_file(&mut self) {
+        self.status_dirty = false;
+        let paused = self.paused.is_some();
+        if !paused && !self.low_power {
+            for idx in 0..self.config.accounts.len() {
+                let inbox = &self.config.accounts[idx].folders.inbox;
+                let query = format!("{} AND flag:unread", maildir_term(inbox));
+                let mu = if idx == self.active_account {
+                    &mut self.mu
+                } else if let Some(mu) = self.background_mu.get_mut(&idx) {
+                    mu
+                } else {
+                    continue;
+                };
+                match mu.count(&query, FindOpts::default().max_num).await {
+                    Ok(n) => {
+                        self.account_unread.insert(idx, n);
+                    }
+                    Err(e) => debug_log!("status file: counting {:?}: {}", query, e),
+                }
+            }
+        }
+        let status = status_file::Status {
+            accounts: self
+                .config
+                .accounts
+                .iter()
+                .enumerate()
+                .map(|(idx, a)| status_file::AccountStatus {
+                    name: a.name.clone(),
+                    unread: self.account_unread.get(&idx).copied().filter(|_| !paused),
+                })
+                .collect(),
+            syncing: self.sync_command().is_some_and(|cmd| self.running_commands.contains(&cmd)),
+            last_sync: self.last_sync,
+            paused,
+        };
+        if let Err(e) = status_file::write(&self.status_path, &status) {
+            debug_log!("status file: {}", e);
+        }
+    }
+
+    /// The sync command of the current profile: headers-only or full.
+    fn sync_command(&self) -> Option<String> {
+        if self.headers_only {
+            self.config.effective_headers_sync_command(self.active_account)
+        } else {
+            self.config.effective_sync_command(self.active_account)
+        }
+    }
+
+    /// Run a shell command in the background; its [`ShellResult`] comes
+    /// back through `shell_tx`.
+    fn spawn_command(&mut self, cmd: String, reindex: bool) {
+        self.running_commands.push(cmd.clone());
+        self.status_dirty = true;
+        let tx = self.shell_tx.clone();
+        tokio::spawn(async move {
+            let started = std::time::SystemTime::now();
+            let output = tokio::process::Command::new("sh")
+                .args(["-c", &cmd])
+                .output()
+                .await;
+            match output {
+                Ok(o) => {
+                    let _ = tx.send(Ok(ShellResult {
+                        command: cmd,
+                        reindex,
+                        started,
+                        stdout: String::from_utf8_lossy(&o.stdout).into_owned(),
+                        stderr: String::from_utf8_lossy(&o.stderr).into_owned(),
+                        status: o.status,
+                        show_output: false,
+                        summary_of: None,
+                    }));
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(ShellError {
+                        command: cmd,
+                        error: e.to_string(),
+                    }));
+                }
+            }
+        });
+    }
+
+    /// A shell command sent its result; it's no longer running.
+    fn command_finished(&mut self, command: &str) {
+        if let Some(i) = self.running_commands.iter().position(|c| c == command) {
+            self.running_commands.remove(i);
+            self.status_dirty = true;
+        }
+        // A fetched body shows in place of the "fetching" notice
+        if let Some(message_id) = self.body_fetches.remove(command) {
+            self.preview_cache.remove(&message_id);
+        }
+    }
+
+    /// Save the envelope list for `draw_startup` to show next launch.
+    fn save_warm_start(&self) {
+        let warm = WarmStart::new(self.capture_view(), self.conversations_mode, &self.envelopes);
+        warm_start::save_warm_start(&warm, self.account_name());
     }
 
     /// Return the folder `delta` positions from the current one in the
     /// sorted known_folders list, wrapping around.
     fn next_folder(&self, delta: i32) -> Option<String> {
-        let list = if self.tabs.is_empty() { &self.known_folders } else { &self.tabs };
-        if list.is_empty() {
-            return None;
-        }
-        let cur = list
-            .iter()
-            .position(|f| f == &self.current_folder)
-            .unwrap_or(0);
-        let len = list.len() as i32;
-        let next = ((cur as i32 + delta) % len + len) % len;
-        Some(list[next as usize].clone())
+        let list = if !self.cycle_folders.is_empty() {
+            &self.cycle_folders
+        } else if !self.tabs.is_empty() {
+            &self.tabs
+        } else {
+            &self.known_folders
+        };
+        cycle_step(list, &self.current_folder, delta)
     }
 
     // ── Search ──────────────────────────────────────────────────────
@@ -1668,7 +4864,7 @@ impl App {
             } else {
                 self.smart_create_query.clone()
             };
-            match self.mu.find_preview(&preview_query, 5).await {
+            match self.mu.find_preview(&expand_dates(&preview_query), 5).await {
                 Ok((envelopes, count)) => {
                     self.smart_create_count = Some(count);
                     self.smart_create_preview = envelopes
@@ -1698,8 +4894,19 @@ impl App {
         self.vim_sub_mode = VimSubMode::Insert;
     }
 
+    /// Whether `folder` is a read-only built-in smart folder (e.g. `@Starred`).
+    fn is_builtin_folder(&self, folder: &str) -> bool {
+        folder.strip_prefix('@').is_some_and(|name| {
+            self.smart_folders.iter().any(|sf| sf.builtin && sf.name == name)
+        })
+    }
+
     /// Open the query editor for a @smart or #split folder.
     async fn edit_folder(&mut self, folder: &str) {
+        if self.is_builtin_folder(folder) {
+            self.set_status(format!("{} is built in and read-only", folder));
+            return;
+        }
         if let Some(name) = folder.strip_prefix('@') {
             if let Some(sf) = self.smart_folders.iter().find(|sf| sf.name == name) {
                 self.smart_create_query = sf.query.clone();
@@ -1747,11 +4954,29 @@ impl App {
     /// Delete a folder by name and navigate to inbox. Used by both
     /// the folder picker (Ctrl+D) and normal mode (Ctrl+D with confirm).
     async fn delete_folder(&mut self, folder: &str) -> Result<()> {
+        if self.is_builtin_folder(folder) {
+            self.set_status(format!(
+                "{} is built in; disable it with builtin_smart_folders in config",
+                folder
+            ));
+            return Ok(());
+        }
+        let shared = folder.strip_prefix('@').is_some_and(|name| {
+            self.smart_folders.iter().any(|sf| sf.shared && sf.name == name)
+        });
+        if shared {
+            self.set_status(format!(
+                "{} is shared by all accounts; remove it from smart_folders in config",
+                folder
+            ));
+            return Ok(());
+        }
         if let Some(name) = folder.strip_prefix('@') {
             if let Some(pos) = self.smart_folders.iter().position(|sf| sf.name == name) {
                 let removed = self.smart_folders.remove(pos);
                 smart_folders::save_smart_folders(&self.smart_folders, self.account_name());
                 self.smart_folder_queries.remove(folder);
+                self.smart_counts.remove(folder);
                 self.known_folders.retain(|f| f != folder);
                 self.rebuild_tabs();
                 self.undo_stack.push(UndoEntry {
@@ -1871,21 +5096,13 @@ impl App {
         };
         let thread_envelopes = self.mu.find(&query, &opts).await.unwrap_or_default();
         if thread_envelopes.is_empty() {
-            self.thread_messages = vec![ThreadMessage {
-                envelope: envelope.clone(),
-                body: None,
-                expanded: true,
-            }];
+            self.thread_messages = vec![ThreadMessage::new(envelope.clone(), true)];
         } else {
             self.thread_messages = thread_envelopes
                 .into_iter()
                 .map(|e| {
                     let is_selected = e.message_id == envelope.message_id;
-                    ThreadMessage {
-                        envelope: e,
-                        body: None,
-                        expanded: is_selected,
-                    }
+                    ThreadMessage::new(e, is_selected)
                 })
                 .collect();
         }
@@ -1896,27 +5113,131 @@ impl App {
             .unwrap_or(0);
         self.thread_scroll = 0;
         self.mode = InputMode::ThreadView;
+        // Collapsed messages show a snippet on their summary line
+        let requests: Vec<_> = self
+            .thread_messages
+            .iter()
+            .filter(|m| !m.expanded)
+            .map(|m| snippets::SnippetRequest {
+                message_id: m.envelope.message_id.clone(),
+                path: m.envelope.path.clone(),
+            })
+            .collect();
+        for request in requests {
+            self.request_snippet(request);
+        }
         Ok(())
     }
 
+    /// Paths of the selected thread's messages, in thread order: the open
+    /// thread view's, or else looked up for the selected message.
+    async fn thread_paths(&mut self) -> Vec<std::path::PathBuf> {
+        self.thread_envelopes().await.into_iter().map(|e| e.path).collect()
+    }
+
+    /// The messages of the selected thread, oldest first: the open thread
+    /// in thread view, else the selected message's whole thread.
+    async fn thread_envelopes(&mut self) -> Vec<Envelope> {
+        if self.mode == InputMode::ThreadView {
+            return self.thread_messages.iter().map(|m| m.envelope.clone()).collect();
+        }
+        let Some(envelope) = self.preview_envelope().cloned() else {
+            return Vec::new();
+        };
+        let opts = FindOpts {
+            threads: true,
+            include_related: true,
+            descending: false,
+            ..Default::default()
+        };
+        let query = format!("msgid:{}", envelope.message_id);
+        match self.mu.find(&query, &opts).await {
+            Ok(found) if !found.is_empty() => {
+                // The same message can turn up in more than one folder
+                let mut seen = HashSet::new();
+                found
+                    .into_iter()
+                    .filter(|e| seen.insert(e.message_id.clone()))
+                    .collect()
+            }
+            _ => vec![envelope],
+        }
+    }
+
+    /// Queue the expanded thread messages that have no body yet with the
+    /// renderer; they show a placeholder until it sends them back.
+    /// Keep read positions in step: note how far the message (and thread)
+    /// on screen is scrolled, and when a different one comes up, scroll it
+    /// to where it was left.
+    fn track_read_positions(&mut self) {
+        let message = self.selected_envelope().map(|e| e.message_id.clone());
+        if message != self.read_anchor {
+            if let Some(id) = &message {
+                self.preview_scroll = self.read_positions.get(id);
+            }
+            self.read_anchor = message;
+            self.redraw = true;
+        } else if let Some(id) = &self.read_anchor {
+            if self.read_positions.get(id) != self.preview_scroll {
+                self.read_positions.set(id, self.preview_scroll);
+            }
+        }
+
+        let thread = match self.mode {
+            InputMode::ThreadView => self.thread_messages.first().map(|m| m.envelope.message_id.clone()),
+            _ => None,
+        };
+        if thread != self.thread_anchor {
+            if let Some(id) = &thread {
+                self.thread_scroll = self.thread_positions.get(id);
+            }
+            self.thread_anchor = thread;
+            self.redraw = true;
+        } else if let Some(id) = &self.thread_anchor {
+            if self.thread_positions.get(id) != self.thread_scroll {
+                self.thread_positions.set(id, self.thread_scroll);
+            }
+        }
+    }
+
     fn ensure_thread_body_loaded(&mut self, width: u16) {
-        for msg in &mut self.thread_messages {
-            if msg.expanded && msg.body.is_none() {
-                match mime_render::render_message(&msg.envelope.path, &msg.envelope.message_id, width) {
-                    Ok(rendered) => msg.body = Some(rendered),
-                    Err(e) => msg.body = Some(mime_render::RenderedMessage {
-                        lines: vec![vec![mime_render::RichSpan {
-                            text: format!("[Error: {}]", e),
-                            kind: mime_render::SpanKind::Normal,
-                        }]],
-                        links: Vec::new(),
-                        is_html: false,
-                    }),
-                }
+        let max_width = self.config.preview_max_width;
+        for msg in &self.thread_messages {
+            let message_id = &msg.envelope.message_id;
+            if !msg.expanded || msg.body.is_some() || self.thread_rendering.contains(message_id) {
+                continue;
+            }
+            let request = thread_view::RenderRequest {
+                message_id: message_id.clone(),
+                path: msg.envelope.path.clone(),
+                width,
+                max_width,
+                max_size_mb: self.max_size_mb(message_id),
+                direction: self.text_direction.get(message_id).copied(),
+            };
+            if self.thread_render_tx.send(request).is_ok() {
+                self.thread_rendering.insert(message_id.clone());
             }
         }
     }
 
+    /// Put a body from the renderer into the open thread, unless the
+    /// message has gone or its text direction changed meanwhile.
+    fn thread_body_rendered(&mut self, rendered: thread_view::RenderedBody) {
+        self.thread_rendering.remove(&rendered.message_id);
+        if self.text_direction.get(&rendered.message_id).copied() != rendered.direction {
+            return;
+        }
+        if let Some(msg) = self
+            .thread_messages
+            .iter_mut()
+            .find(|m| m.envelope.message_id == rendered.message_id && m.body.is_none())
+        {
+            msg.body = Some(rendered.body);
+            self.redraw = true;
+        }
+    }
+
     // ── Multi-select ────────────────────────────────────────────────
 
     fn toggle_select(&mut self) {
@@ -1993,21 +5314,21 @@ impl App {
             compose::ComposeKind::NewMessage => Some(compose::ComposeContext::new_message()),
             compose::ComposeKind::Reply => {
                 let envelope = self.selected_envelope()?;
-                let body_text = mime_render::render_message(&envelope.path, &envelope.message_id, 80)
+                let body_text = mime_render::render_message(&envelope.path, &envelope.message_id, 80, None)
                     .map(|rm| rm.to_plain_text())
                     .unwrap_or_default();
-                Some(compose::ComposeContext::reply(envelope, &body_text, false))
+                Some(self.reply_context(envelope, &body_text, false))
             }
             compose::ComposeKind::ReplyAll => {
                 let envelope = self.selected_envelope()?;
-                let body_text = mime_render::render_message(&envelope.path, &envelope.message_id, 80)
+                let body_text = mime_render::render_message(&envelope.path, &envelope.message_id, 80, None)
                     .map(|rm| rm.to_plain_text())
                     .unwrap_or_default();
-                Some(compose::ComposeContext::reply(envelope, &body_text, true))
+                Some(self.reply_context(envelope, &body_text, true))
             }
             compose::ComposeKind::Forward => {
                 let envelope = self.selected_envelope()?;
-                let body_text = mime_render::render_message(&envelope.path, &envelope.message_id, 80)
+                let body_text = mime_render::render_message(&envelope.path, &envelope.message_id, 80, None)
                     .map(|rm| rm.to_plain_text())
                     .unwrap_or_default();
                 Some(compose::ComposeContext::forward(envelope, &body_text))
@@ -2015,31 +5336,85 @@ impl App {
         }
     }
 
+    /// Hold a reply-all back for its recipients to be checked when it
+    /// would go to many people or the list munges Reply-To; otherwise hand
+    /// it straight back for the editor.
+    fn check_recipients(&mut self, ctx: compose::ComposeContext) -> Option<compose::ComposeContext> {
+        let own = self.account().map_or("", |a| a.email.as_str());
+        let from = self.mode.clone();
+        match RecipientCheck::for_reply_all(&ctx, own, self.config.reply_all_check_over, from) {
+            Some(check) => {
+                self.recipient_check = Some(check);
+                self.mode = InputMode::RecipientCheck;
+                None
+            }
+            None => Some(ctx),
+        }
+    }
+
+    /// A reply to `envelope`, sent from the plus address it was delivered
+    /// to when there is one.
+    fn reply_context(&self, envelope: &Envelope, body_text: &str, reply_all: bool) -> compose::ComposeContext {
+        let mut ctx = compose::ComposeContext::reply(envelope, body_text, reply_all);
+        let own = self.account().map_or("", |a| a.email.as_str());
+        ctx.from = compose::plus_address(envelope, own);
+        ctx
+    }
+
     // ── Filtered list helpers ───────────────────────────────────────
 
+    /// Favorites and recent folders matching the picker filter, shown at
+    /// the top of the folder pickers. Splits are left out of move targets.
+    fn pinned_folders(&self, move_targets: bool) -> (Vec<String>, Vec<String>) {
+        let filter = self.folder_filter.to_lowercase();
+        let wanted = |f: &String| {
+            folder_matches(f, &filter)
+                && *f != self.current_folder
+                && !(move_targets && f.starts_with('#'))
+        };
+        let favorites: Vec<String> = self
+            .config
+            .effective_favorite_folders(self.active_account)
+            .iter()
+            .filter(|f| wanted(f))
+            .cloned()
+            .collect();
+        let recents = self
+            .recent_folders
+            .iter()
+            .filter(|f| wanted(f) && !favorites.contains(f))
+            .cloned()
+            .collect();
+        (favorites, recents)
+    }
+
+    /// Index in filtered_folders() to select after the filter changes: the
+    /// first pinned match, or else skip past the special entries.
+    fn first_folder_match(&self) -> usize {
+        let (favorites, recents) = self.pinned_folders(false);
+        if favorites.is_empty() && recents.is_empty() {
+            2
+        } else {
+            0
+        }
+    }
+
     fn filtered_folders(&self) -> Vec<String> {
         let filter = self.folder_filter.to_lowercase();
-        let mut result = Vec::new();
-        // Special entries always at top (not affected by filter)
+        let (favorites, recents) = self.pinned_folders(false);
+        let mut result = favorites;
+        result.extend(recents);
+        // Special entries (not affected by filter)
         result.push("+ New smart folder".to_string());
         result.push("+ New split".to_string());
         result.push("+ New maildir folder".to_string());
         // Then filtered known folders
-        for f in &self.known_folders {
-            if filter.is_empty() {
-                result.push(f.clone());
-            } else {
-                // For smart folders (@Name) and splits (#Name), also match against just the name
-                let matches = f.to_lowercase().contains(&filter)
-                    || f.strip_prefix('@')
-                        .is_some_and(|name| name.to_lowercase().contains(&filter))
-                    || f.strip_prefix('#')
-                        .is_some_and(|name| name.to_lowercase().contains(&filter));
-                if matches {
-                    result.push(f.clone());
-                }
-            }
-        }
+        result.extend(
+            self.known_folders
+                .iter()
+                .filter(|f| folder_matches(f, &filter))
+                .cloned(),
+        );
         result
     }
 
@@ -2047,22 +5422,17 @@ impl App {
     /// Used for MoveToFolder where those entries don't apply.
     fn filtered_folders_plain(&self) -> Vec<String> {
         let filter = self.folder_filter.to_lowercase();
-        self.known_folders
-            .iter()
-            .filter(|f| {
+        let (favorites, recents) = self.pinned_folders(true);
+        let mut result = favorites;
+        result.extend(recents);
+        result.extend(
+            self.known_folders
+                .iter()
                 // Exclude splits from move targets
-                if f.starts_with('#') {
-                    return false;
-                }
-                if filter.is_empty() {
-                    return true;
-                }
-                f.to_lowercase().contains(&filter)
-                    || f.strip_prefix('@')
-                        .is_some_and(|name| name.to_lowercase().contains(&filter))
-            })
-            .cloned()
-            .collect()
+                .filter(|f| !f.starts_with('#') && folder_matches(f, &filter))
+                .cloned(),
+        );
+        result
     }
 
     fn filtered_palette(&self) -> Vec<PaletteEntry> {
@@ -2123,6 +5493,53 @@ impl App {
         Some(self.active_account)
     }
 
+    /// Show a message found by path or docid. It's loaded by Message-ID,
+    /// so the view survives the file being renamed by a later flag change.
+    async fn ipc_open_envelope(&mut self, envelope: &Envelope, status: String) -> IpcResponse {
+        let query = format!("msgid:{}", envelope.message_id);
+        self.mode = InputMode::Normal;
+        self.thread_messages.clear();
+        self.current_folder = query.clone();
+        match self.load_folder().await {
+            Ok(()) => debug_log!("IPC open: loaded {} envelopes", self.envelopes.len()),
+            Err(e) => debug_log!("IPC open: load error: {}", e),
+        }
+        self.set_status(status);
+        self.capture_envelopes(&query, &FindOpts::default()).await
+    }
+
+    /// Switch to a folder for an IPC command and return its envelopes.
+    async fn ipc_navigate(&mut self, folder: &str) -> IpcResponse {
+        debug_log!("IPC Navigate: folder={}", folder);
+        self.mode = InputMode::Normal;
+        self.thread_messages.clear();
+        match self.navigate_folder(folder).await {
+            Ok(()) => debug_log!("IPC Navigate: loaded {} envelopes", self.envelopes.len()),
+            Err(e) => debug_log!("IPC Navigate: error: {}", e),
+        }
+        // Capture using the expanded query (after navigate set current_folder)
+        let expanded = self.build_query();
+        self.capture_envelopes(&expanded, &FindOpts::default()).await
+    }
+
+    /// Open the editor on a compose request from IPC or a URL, once its
+    /// attachments are checked.
+    async fn compose_from_request(&mut self, req: compose::ComposeRequest, status: &str) -> Result<IpcResponse> {
+        self.switch_to_account_if_needed(&req.account).await?;
+        if let Some(missing) = req.attachments.iter().find(|p| {
+            let path = std::path::Path::new(p.as_str());
+            !path.is_absolute() || !path.is_file()
+        }) {
+            return Ok(IpcResponse::Error {
+                message: format!("attachment is not an absolute path to a file: {}", missing),
+            });
+        }
+        let ctx = compose::ComposeContext::from_request(&req);
+        self.compose_pending = Some(compose::ComposePending::Ready(Box::new(ctx)));
+        self.set_status(status.to_string());
+        Ok(IpcResponse::Ok)
+    }
+
     async fn handle_ipc_command(&mut self, cmd: IpcCommand) -> Result<IpcResponse> {
         debug_log!("handle_ipc_command: {:?}", cmd);
         match cmd {
@@ -2157,6 +5574,7 @@ impl App {
                         let envelopes = result.unwrap_or_default();
                         if let Some(envelope) = envelopes.into_iter().next() {
                             self.envelopes = vec![envelope];
+                            self.collapsed.clear();
                             self.selected = 0;
                             match self.open_thread().await {
                                 Ok(()) => debug_log!("IPC Thread: opened, {} messages", self.thread_messages.len()),
@@ -2204,8 +5622,7 @@ impl App {
                                             message: format!("write error: {}", e),
                                         });
                                     }
-                                    let _ = links::open_path(tmp_path.to_str().unwrap_or(""));
-                                    self.set_status(format!("Opened: {}", att.filename));
+                                    self.open_external(&tmp_path.to_string_lossy(), format!("Opened: {}", att.filename));
                                     Ok(IpcResponse::Ok)
                                 }
                                 Err(e) => Ok(IpcResponse::Error {
@@ -2218,38 +5635,51 @@ impl App {
                             })
                         }
                     }
-                    HuttUrl::Compose { to, subject, account } => {
+                    HuttUrl::Compose(req) => self.compose_from_request(req, "Compose from URL").await,
+                    HuttUrl::Folder { folder, account } => {
                         self.switch_to_account_if_needed(&account).await?;
-                        let mut ctx = compose::ComposeContext::new_message();
-                        ctx.to = vec![crate::envelope::Address {
-                            name: None,
-                            email: to,
-                        }];
-                        ctx.subject = subject;
-                        self.compose_pending =
-                            Some(compose::ComposePending::Ready(ctx));
-                        self.set_status("Compose from URL");
-                        Ok(IpcResponse::Ok)
+                        let folder = if folder.is_empty() {
+                            self.config.effective_startup_folder(self.active_account)
+                        } else {
+                            folder
+                        };
+                        Ok(self.ipc_navigate(&folder).await)
                     }
                 }
             }
             IpcCommand::Navigate { folder, account } => {
                 self.switch_to_account_if_needed(&account).await?;
-                debug_log!("IPC Navigate: folder={}", folder);
-                self.mode = InputMode::Normal;
-                self.thread_messages.clear();
-                match self.navigate_folder(&folder).await {
-                    Ok(()) => debug_log!("IPC Navigate: loaded {} envelopes", self.envelopes.len()),
-                    Err(e) => debug_log!("IPC Navigate: error: {}", e),
-                }
-                // Capture using the expanded query (after navigate set current_folder)
-                let expanded = self.build_query();
-                Ok(self.capture_envelopes(&expanded, &FindOpts::default()).await)
+                Ok(self.ipc_navigate(&folder).await)
+            }
+            IpcCommand::OpenPath { path, account } => {
+                self.switch_to_account_if_needed(&account).await?;
+                let path_query = path_term(&path);
+                debug_log!("IPC OpenPath: query={}", path_query);
+                let found = self.mu.find(&path_query, &FindOpts::default()).await;
+                let Some(envelope) = found.ok().and_then(|v| v.into_iter().next()) else {
+                    self.set_status(format!("No indexed message at {}", path));
+                    return Ok(IpcResponse::Error {
+                        message: format!("no indexed message at path: {}", path),
+                    });
+                };
+                Ok(self.ipc_open_envelope(&envelope, format!("Opened {}", path)).await)
+            }
+            IpcCommand::OpenDocid { docid, account } => {
+                self.switch_to_account_if_needed(&account).await?;
+                debug_log!("IPC OpenDocid: docid={}", docid);
+                let Ok(envelope) = self.mu.view(docid).await else {
+                    self.set_status(format!("No message with docid {}", docid));
+                    return Ok(IpcResponse::Error {
+                        message: format!("no message with docid: {}", docid),
+                    });
+                };
+                Ok(self.ipc_open_envelope(&envelope, format!("Opened docid {}", docid)).await)
             }
             IpcCommand::Quit => {
                 self.should_quit = true;
                 Ok(IpcResponse::Ok)
             }
+            IpcCommand::Compose(req) => self.compose_from_request(req, "Compose request received").await,
             IpcCommand::MuCommand { sexp, account, muhome } => {
                 let target_idx = self.resolve_mu_target(account.as_deref(), muhome.as_deref());
                 match target_idx {
@@ -2315,6 +5745,15 @@ impl App {
                     }
                 }
             }
+            Action::PreviewTop => match self.mode {
+                InputMode::ThreadView => self.thread_scroll = 0,
+                _ => self.preview_scroll = 0,
+            },
+            // Clamped to the last screenful when drawn
+            Action::PreviewBottom => match self.mode {
+                InputMode::ThreadView => self.thread_scroll = u16::MAX,
+                _ => self.preview_scroll = u16::MAX,
+            },
             Action::JumpBottom => {
                 match self.mode {
                     InputMode::ThreadView => {
@@ -2335,7 +5774,12 @@ impl App {
                 InputMode::ThreadView => {
                     self.thread_scroll = self.thread_scroll.saturating_add(5);
                 }
-                InputMode::Help => {
+                InputMode::Help
+                | InputMode::History
+                | InputMode::Digest
+                | InputMode::DeliveryInfo
+                | InputMode::CommandOutput
+                | InputMode::Summary => {
                     self.help_scroll = self.help_scroll.saturating_add(3);
                 }
                 _ => {
@@ -2346,7 +5790,12 @@ impl App {
                 InputMode::ThreadView => {
                     self.thread_scroll = self.thread_scroll.saturating_sub(5);
                 }
-                InputMode::Help => {
+                InputMode::Help
+                | InputMode::History
+                | InputMode::Digest
+                | InputMode::DeliveryInfo
+                | InputMode::CommandOutput
+                | InputMode::Summary => {
                     self.help_scroll = self.help_scroll.saturating_sub(3);
                 }
                 _ => {
@@ -2377,14 +5826,34 @@ impl App {
             // Triage — move to folder (alias, literal path, or picker)
             Action::MoveToFolder(ref target) => {
                 if let Some(dest) = target {
-                    let (maildir, desc) = self.resolve_move_target(dest);
-                    self.triage_move(&maildir, &desc).await?;
+                    self.move_to_target(dest).await?;
                 } else if !self.triage_targets().is_empty() {
                     self.folder_filter.clear();
                     self.folder_selected = 0;
                     self.mode = InputMode::MoveToFolder;
                 }
             }
+            Action::RepeatMove => match self.last_move.clone() {
+                Some(folder) => {
+                    self.triage_move(&folder, &format!("Moved to {}", folder))
+                        .await?
+                }
+                None => self.set_status("No move to repeat yet (pick a folder with m)"),
+            },
+            Action::SweepSender => self.start_sweep(),
+            Action::TriageLoop => {
+                let total = self.visible_count().saturating_sub(self.selected);
+                if self.mode != InputMode::Normal {
+                    self.set_status("Triage Loop runs from the message list");
+                } else if total == 0 {
+                    self.set_status("Nothing to triage");
+                } else {
+                    self.selected_set.clear();
+                    self.preview_scroll = 0;
+                    self.triage_loop = Some(TriageLoop { total, ..TriageLoop::default() });
+                    self.mode = InputMode::TriageLoop;
+                }
+            }
             Action::ToggleRead => self.triage_toggle_flag('S', "read/unread").await?,
             Action::ToggleStar => self.triage_toggle_flag('F', "star").await?,
             Action::Undo => self.undo().await?,
@@ -2465,6 +5934,22 @@ impl App {
                 }
             }
 
+            // View tabs
+            Action::NewView => self.new_view(),
+            Action::Compare => self.toggle_compare(),
+            Action::CompareSwitch => self.switch_compare_pane().await?,
+            Action::CloseView => self.close_view().await?,
+            Action::NextView | Action::PrevView => {
+                let len = self.views.len();
+                let index = if action == Action::NextView {
+                    (self.active_view + 1) % len
+                } else {
+                    (self.active_view + len - 1) % len
+                };
+                self.switch_view(index).await?;
+            }
+            Action::SwitchView(idx) => self.switch_view(idx).await?,
+
             // Search
             Action::EnterSearch => {
                 // Pre-fill with the current folder's short name.
@@ -2499,6 +5984,50 @@ impl App {
                 self.filter_needs_reply = !self.filter_needs_reply;
                 self.load_folder().await?;
             }
+            Action::CollapseDuplicates => {
+                self.collapse_duplicates = !self.collapse_duplicates;
+                self.expanded_duplicates.clear();
+                self.apply_sort();
+                let state = if self.collapse_duplicates { "on" } else { "off" };
+                let shown = if self.is_search() { "" } else { " (takes effect in searches)" };
+                self.set_status(format!("Collapse near-duplicates: {}{}", state, shown));
+            }
+            Action::ExpandDuplicates => {
+                let Some(envelope) = self.selected_envelope() else {
+                    return Ok(());
+                };
+                let key = envelope.thread_key();
+                let hidden = self.collapsed.get(&envelope.message_id).map_or(0, Vec::len);
+                if self.expanded_duplicates.remove(&key) {
+                    self.apply_sort();
+                    self.set_status("Collapsed the thread again");
+                } else if hidden > 0 {
+                    self.expanded_duplicates.insert(key);
+                    self.apply_sort();
+                    self.set_status(format!("Showing {} more in the thread", hidden));
+                } else {
+                    self.set_status("Nothing collapsed here");
+                }
+            }
+            Action::ToggleKeyword(keyword) => {
+                self.toggle_keyword(&keyword).await?;
+            }
+            Action::FilterKeyword(keyword) => {
+                if self.filter_keyword.as_deref() == Some(keyword.as_str()) {
+                    self.filter_keyword = None;
+                } else {
+                    self.filter_keyword = Some(keyword);
+                }
+                self.load_folder().await?;
+            }
+            Action::FilterScore => {
+                if self.scoring() {
+                    self.filter_score = !self.filter_score;
+                    self.load_folder().await?;
+                } else {
+                    self.set_status("Set score_header or score_command to score messages");
+                }
+            }
 
             // Multi-select
             Action::ToggleSelect => {
@@ -2512,6 +6041,10 @@ impl App {
                 self.select_from_here();
             }
             Action::ClearSelection => {
+                // A second Esc dismisses the startup banner
+                if self.selected_set.is_empty() {
+                    self.health_banner.clear();
+                }
                 self.selected_set.clear();
             }
             Action::SelectDown => {
@@ -2561,13 +6094,40 @@ impl App {
             Action::Compose => self.compose_pending = Some(compose::ComposePending::Kind(compose::ComposeKind::NewMessage)),
             Action::Reply => self.compose_pending = Some(compose::ComposePending::Kind(compose::ComposeKind::Reply)),
             Action::ReplyAll => self.compose_pending = Some(compose::ComposePending::Kind(compose::ComposeKind::ReplyAll)),
+            Action::AwaitReply => {
+                if self.mode != InputMode::Normal {
+                    self.set_status("Follow-up reminders are set from the message list");
+                } else if let Some(envelope) = self.selected_envelope() {
+                    let existing = self.followups.iter().find(|f| f.message_id == envelope.message_id);
+                    self.followup_input = existing
+                        .map(|f| f.due.format("%Y-%m-%d").to_string())
+                        .unwrap_or_else(|| "3d".to_string());
+                    self.mode = InputMode::FollowUpDate;
+                }
+            }
+            Action::EditNote => {
+                if self.mode != InputMode::Normal {
+                    self.set_status("Notes are written from the message list");
+                } else if let Some(envelope) = self.selected_envelope() {
+                    self.note_input = self.note(envelope).unwrap_or_default().to_string();
+                    self.mode = InputMode::NoteText;
+                }
+            }
+            Action::QuickReply => {
+                if self.mode != InputMode::Normal {
+                    self.set_status("Quick reply works from the message list");
+                } else if self.selected_envelope().is_some() {
+                    self.quick_reply_input.clear();
+                    self.mode = InputMode::QuickReply;
+                }
+            }
             Action::Forward => self.compose_pending = Some(compose::ComposePending::Kind(compose::ComposeKind::Forward)),
 
             // Linkability
             Action::CopyMessageUrl => {
                 if let Some(e) = self.selected_envelope() {
                     let url = links::format_message_url(&e.message_id);
-                    match links::copy_to_clipboard(&url) {
+                    match self.copy_text(&url) {
                         Ok(()) => self.set_status("Message URL copied"),
                         Err(e) => self.set_status(format!("Clipboard error: {}", e)),
                     }
@@ -2576,21 +6136,226 @@ impl App {
             Action::CopyThreadUrl => {
                 if let Some(e) = self.selected_envelope() {
                     let url = links::format_thread_url(&e.message_id);
-                    match links::copy_to_clipboard(&url) {
+                    match self.copy_text(&url) {
                         Ok(()) => self.set_status("Thread URL copied"),
                         Err(e) => self.set_status(format!("Clipboard error: {}", e)),
                     }
                 }
             }
+            Action::CopyMessageLink => {
+                if let Some(e) = self.selected_envelope() {
+                    let sender = e.from.first().map(|a| a.short_display()).unwrap_or_default();
+                    let link = links::format_message_link(
+                        self.config.link_format,
+                        &e.message_id,
+                        &e.subject,
+                        &sender,
+                    );
+                    match self.copy_text(&link) {
+                        Ok(()) => self.set_status("Message link copied"),
+                        Err(e) => self.set_status(format!("Clipboard error: {}", e)),
+                    }
+                }
+            }
+            Action::CopySearchUrl => {
+                // The unexpanded query, so date placeholders stay relative
+                let account = self.account().map(|a| a.name.as_str());
+                let url = links::format_search_url(&self.current_folder, account);
+                match self.copy_text(&url) {
+                    Ok(()) => self.set_status("Search URL copied"),
+                    Err(e) => self.set_status(format!("Clipboard error: {}", e)),
+                }
+            }
+            Action::CopyRedacted => {
+                let Some(e) = self.selected_envelope() else {
+                    return Ok(());
+                };
+                let redactor = match redact::Redactor::new(&self.config.redact) {
+                    Ok(redactor) => redactor,
+                    Err(err) => {
+                        self.set_status(format!("{:#}", err));
+                        return Ok(());
+                    }
+                };
+                let body = mime_render::render_message(&e.path, &e.message_id, 80, None)
+                    .map(|rm| rm.to_plain_text())
+                    .unwrap_or_default();
+                let text = redactor.redact(&redact::message_text(e, &body));
+                match self.copy_text(&text) {
+                    Ok(()) => self.set_status("Redacted message copied"),
+                    Err(e) => self.set_status(format!("Clipboard error: {}", e)),
+                }
+            }
+            Action::ExportSearch => {
+                if self.mode != InputMode::Normal {
+                    self.set_status("Searches are exported from the message list");
+                    return Ok(());
+                }
+                let stamp = chrono::Local::now().format("%Y%m%d-%H%M");
+                let dir = self.download_dir().join(format!("hutt-export-{}", stamp));
+                self.export_input = dir.to_string_lossy().into_owned();
+                self.mode = InputMode::ExportPath;
+            }
+            Action::PipeThread => {
+                let Some(command) = self.config.pipe_thread_command.clone() else {
+                    self.set_status("Set pipe_thread_command in config.toml to pipe threads");
+                    return Ok(());
+                };
+                let paths = self.thread_paths().await;
+                let messages: Vec<Vec<u8>> = paths.iter().filter_map(|p| std::fs::read(p).ok()).collect();
+                if messages.is_empty() {
+                    return Ok(());
+                }
+                let mbox = maildir::mbox(&messages);
+                self.set_status(format!("Piping {} message(s) to: {}...", messages.len(), command));
+                self.running_commands.push(command.clone());
+                let tx = self.shell_tx.clone();
+                tokio::spawn(async move {
+                    let started = std::time::SystemTime::now();
+                    match pipe_to_command(&command, mbox).await {
+                        Ok(o) => {
+                            let _ = tx.send(Ok(ShellResult {
+                                command,
+                                reindex: false,
+                                started,
+                                stdout: String::from_utf8_lossy(&o.stdout).into_owned(),
+                                stderr: String::from_utf8_lossy(&o.stderr).into_owned(),
+                                status: o.status,
+                                show_output: true,
+                                summary_of: None,
+                            }));
+                        }
+                        Err(e) => {
+                            let _ = tx.send(Err(ShellError {
+                                command,
+                                error: e.to_string(),
+                            }));
+                        }
+                    }
+                });
+            }
+            Action::SavePatch => {
+                let dir = self.download_dir();
+                let targets: Vec<(std::path::PathBuf, String)> = self
+                    .patch_targets()
+                    .iter()
+                    .map(|e| (e.path.clone(), patch::file_name(&e.subject)))
+                    .collect();
+                let Some((_, last_name)) = targets.last().cloned() else {
+                    return Ok(());
+                };
+                let saved = std::fs::create_dir_all(&dir).and_then(|()| {
+                    targets
+                        .iter()
+                        .try_for_each(|(path, name)| std::fs::copy(path, dir.join(name)).map(|_| ()))
+                });
+                match saved {
+                    Ok(()) if targets.len() == 1 => {
+                        self.set_status(format!("Saved: {}", dir.join(last_name).display()))
+                    }
+                    Ok(()) => self.set_status(format!("Saved {} patches to {}", targets.len(), dir.display())),
+                    Err(e) => self.set_status(format!("Save error: {}", e)),
+                }
+            }
+            Action::ApplyPatch => {
+                let Some(command) = self.config.patch_command.clone() else {
+                    self.set_status("Set patch_command in config.toml to apply patches");
+                    return Ok(());
+                };
+                let files: Vec<std::path::PathBuf> =
+                    self.patch_targets().iter().map(|e| e.path.clone()).collect();
+                if files.is_empty() {
+                    return Ok(());
+                }
+                // In the background, like an unsuspended shell binding; the
+                // status bar shows the last line of output, e.g. git am's
+                let command = patch::apply_command(&command, &files);
+                Box::pin(self.handle_action(Action::RunShell { command, reindex: false, suspend: false })).await?;
+            }
+            Action::SummarizeThread => {
+                let Some(command) = self.config.summarize_command.clone() else {
+                    self.set_status("Set summarize_command in config.toml to summarize threads");
+                    return Ok(());
+                };
+                let envelopes = self.thread_envelopes().await;
+                let Some(first) = envelopes.first() else {
+                    return Ok(());
+                };
+                let key = first.message_id.clone();
+                let count = envelopes.len();
+                if let Some((summarized, lines)) = self.summaries.get(&key) {
+                    if *summarized == count {
+                        self.overlay_lines = lines.clone();
+                        self.help_scroll = 0;
+                        self.mode = InputMode::Summary;
+                        return Ok(());
+                    }
+                }
+                if self.running_commands.contains(&command) {
+                    self.set_status("Already summarizing\u{2026}");
+                    return Ok(());
+                }
+                self.set_status(format!("Summarizing {} message(s)\u{2026}", count));
+                self.running_commands.push(command.clone());
+                let tx = self.shell_tx.clone();
+                tokio::spawn(async move {
+                    let started = std::time::SystemTime::now();
+                    let text = tokio::task::spawn_blocking(move || thread_text(&envelopes))
+                        .await
+                        .unwrap_or_default();
+                    match pipe_to_command(&command, text.into_bytes()).await {
+                        Ok(o) => {
+                            let _ = tx.send(Ok(ShellResult {
+                                command,
+                                reindex: false,
+                                started,
+                                stdout: String::from_utf8_lossy(&o.stdout).into_owned(),
+                                stderr: String::from_utf8_lossy(&o.stderr).into_owned(),
+                                status: o.status,
+                                show_output: true,
+                                summary_of: Some((key, count)),
+                            }));
+                        }
+                        Err(e) => {
+                            let _ = tx.send(Err(ShellError {
+                                command,
+                                error: e.to_string(),
+                            }));
+                        }
+                    }
+                });
+            }
+            Action::CopyFolderUrl => {
+                if !self.current_folder.starts_with('/') {
+                    self.set_status("Only maildir folders have folder URLs");
+                    return Ok(());
+                }
+                let account = self.account().map(|a| a.name.as_str());
+                let url = links::format_folder_url(&self.current_folder, account);
+                match self.copy_text(&url) {
+                    Ok(()) => self.set_status("Folder URL copied"),
+                    Err(e) => self.set_status(format!("Clipboard error: {}", e)),
+                }
+            }
             Action::OpenInBrowser => {
                 if let Some(e) = self.selected_envelope() {
                     let path = e.path.clone();
+                    // Too large to parse here: hand the whole file over
+                    if mime_render::oversized(&path, self.config.preview_max_size_mb).is_some() {
+                        self.open_external(&path.to_string_lossy(), "Opened message externally".to_string());
+                        return Ok(());
+                    }
                     match std::fs::read(&path) {
                         Ok(raw) => {
                             if let Some(msg) = mail_parser::MessageParser::default().parse(&raw) {
                                 if let Some(html) = msg.body_html(0) {
-                                    let _ = links::open_html_in_browser(html.as_bytes());
-                                    self.set_status("Opened in browser");
+                                    match links::write_temp_html(html.as_bytes()) {
+                                        Ok(path) => self.open_external(
+                                            &path.to_string_lossy(),
+                                            "Opened in browser".to_string(),
+                                        ),
+                                        Err(e) => self.set_status(format!("Write error: {}", e)),
+                                    }
                                 } else {
                                     self.set_status("No HTML content");
                                 }
@@ -2600,8 +6365,172 @@ impl App {
                     }
                 }
             }
-
-            // Conversations
+
+            Action::ToggleDirection => {
+                let message_id = if self.mode == InputMode::ThreadView {
+                    self.thread_messages.get(self.thread_selected).map(|m| m.envelope.message_id.clone())
+                } else {
+                    self.preview_envelope().map(|e| e.message_id.clone())
+                };
+                let Some(message_id) = message_id else {
+                    return Ok(());
+                };
+                // Detected, then forced right-to-left, then left-to-right
+                let next = match self.text_direction.get(&message_id) {
+                    None => Some(bidi::Direction::Rtl),
+                    Some(bidi::Direction::Rtl) => Some(bidi::Direction::Ltr),
+                    Some(bidi::Direction::Ltr) => None,
+                };
+                match next {
+                    Some(direction) => {
+                        self.text_direction.insert(message_id.clone(), direction);
+                        self.set_status(format!("Text direction: {}", direction.label()));
+                    }
+                    None => {
+                        self.text_direction.remove(&message_id);
+                        self.set_status("Text direction: detected");
+                    }
+                }
+                self.preview_cache.remove(&message_id);
+                for msg in &mut self.thread_messages {
+                    if msg.envelope.message_id == message_id {
+                        msg.body = None;
+                    }
+                }
+            }
+            Action::RenderAnyway => {
+                let envelope = if self.mode == InputMode::ThreadView {
+                    self.thread_messages.get(self.thread_selected).map(|m| &m.envelope)
+                } else {
+                    self.preview_envelope()
+                };
+                let Some(envelope) = envelope else {
+                    return Ok(());
+                };
+                let message_id = envelope.message_id.clone();
+                if mime_render::oversized(&envelope.path, self.max_size_mb(&message_id)).is_none() {
+                    self.set_status("Message is already shown in full");
+                    return Ok(());
+                }
+                self.render_anyway.insert(message_id.clone());
+                self.preview_cache.remove(&message_id);
+                for msg in &mut self.thread_messages {
+                    if msg.envelope.message_id == message_id {
+                        msg.body = None;
+                    }
+                }
+            }
+            Action::AddContact => match self.focused_sender() {
+                Some(sender) => self.edit_contact(sender),
+                None => self.set_status("No sender to add"),
+            },
+            Action::ApproveSender => match self.focused_sender() {
+                Some(sender) => self.approve_sender(sender).await?,
+                None => self.set_status("No sender to approve"),
+            },
+            Action::BlockSender => match self.focused_sender() {
+                Some(sender) => self.block_sender(sender).await?,
+                None => self.set_status("No sender to block"),
+            },
+            Action::ToggleHeaders => {
+                self.preview_headers_expanded = !self.preview_headers_expanded;
+            }
+            Action::HeaderActions => {
+                let focused = match self.mode {
+                    InputMode::ThreadView => self.thread_messages.get(self.thread_selected).map(|m| &m.envelope),
+                    _ => self.selected_envelope(),
+                };
+                let targets = focused.map(header_menu::targets).unwrap_or_default();
+                if targets.is_empty() {
+                    self.set_status("No addresses in this message's header");
+                } else {
+                    self.header_menu = targets;
+                    self.header_menu_selected = 0;
+                    self.header_menu_from = self.mode.clone();
+                    self.mode = InputMode::HeaderMenu;
+                }
+            }
+            Action::ToggleDensity => {
+                self.list_density = self.list_density.toggled();
+                self.set_status(format!("List density: {}", self.list_density.label()));
+            }
+
+            // Conversations
+            Action::ToggleSections => {
+                if self.sections.is_some() {
+                    self.unhide_sections();
+                    self.sections = None;
+                    self.apply_sort();
+                    self.set_status("Inbox sections off");
+                } else {
+                    self.sections = Some(InboxSections::new(&self.config.inbox_sections));
+                    self.refresh_section_caches().await;
+                    self.set_status("Inbox sections on");
+                }
+            }
+            Action::CollapseSection => {
+                let name = match (&self.sections, self.envelopes.get(self.selected)) {
+                    (Some(sections), Some(envelope)) if self.sections_active() => {
+                        sections.name_of(envelope).to_string()
+                    }
+                    _ => return Ok(()),
+                };
+                self.unhide_sections();
+                if let Some(sections) = self.sections.as_mut() {
+                    sections.collapse(&name);
+                }
+                self.apply_sort();
+                // Land on whatever follows the folded section
+                let start = self.section_headers().iter().find(|h| h.name == name).map_or(0, |h| h.start);
+                self.selected = start.min(self.envelopes.len().saturating_sub(1));
+                self.preview_scroll = 0;
+                self.set_status(format!("Collapsed {}", name));
+            }
+            Action::ExpandSections => {
+                if !self.sections_active() {
+                    return Ok(());
+                }
+                self.unhide_sections();
+                if let Some(sections) = self.sections.as_mut() {
+                    sections.expand_all();
+                }
+                self.apply_sort();
+            }
+            Action::SelectSection => {
+                let Some(header) = self
+                    .section_headers()
+                    .into_iter()
+                    .rfind(|h| !h.collapsed && h.start <= self.selected)
+                else {
+                    return Ok(());
+                };
+                let rows = &self.envelopes[header.start..(header.start + header.count).min(self.envelopes.len())];
+                self.selected_set.extend(rows.iter().map(|e| e.docid));
+                self.set_status(format!("Selected {} in {}", rows.len(), header.name));
+            }
+            Action::PauseInbox => {
+                self.status_dirty = true;
+                let Some(held) = self.paused.take() else {
+                    self.paused = Some(HashSet::new());
+                    self.set_status("Inbox paused \u{2014} new mail is held until you resume");
+                    return Ok(());
+                };
+                // Bring the held mail in, tinted like any other arrivals
+                let before: HashSet<String> =
+                    self.envelopes.iter().map(|e| e.message_id.clone()).collect();
+                self.load_folder().await?;
+                self.mark_new_arrivals(&before);
+                let followup_notice = self.check_followups().await;
+                let summary = match held.len() {
+                    0 => "Inbox resumed \u{2014} no new mail while paused".to_string(),
+                    1 => "1 message arrived while paused".to_string(),
+                    n => format!("{} messages arrived while paused", n),
+                };
+                match followup_notice {
+                    Some(notice) => self.set_status(format!("{}; {}", summary, notice)),
+                    None => self.set_status(summary),
+                }
+            }
             Action::ToggleConversations => {
                 // Capture the currently selected message before switching
                 let anchor_msgid = if self.conversations_mode {
@@ -2615,9 +6544,11 @@ impl App {
                         .map(|e| e.message_id.clone())
                 };
 
+                self.unhide_sections();
                 self.conversations_mode = !self.conversations_mode;
                 self.preview_scroll = 0;
                 self.selected_set.clear();
+                self.arrange_sections();
                 self.rebuild_conversations();
 
                 // Restore position to the anchored message
@@ -2645,10 +6576,11 @@ impl App {
             // Sort
             Action::SortPicker => {
                 self.mode = InputMode::SortPicker;
-                self.set_status("Sort by: (d)ate  (f)rom  (s)ubject  (t)o".to_string());
+                self.set_status("Sort by: (d)ate  (f)rom  (s)ubject  (t)o  s(c)ore".to_string());
             }
             Action::ReverseSort => {
                 self.sort_descending = !self.sort_descending;
+                self.unhide_sections();
                 self.apply_sort();
                 self.set_status(format!("Sort: {}", self.sort_label()));
             }
@@ -2658,6 +6590,55 @@ impl App {
                 self.help_scroll = 0;
                 self.mode = InputMode::Help;
             }
+            Action::DescribeKey => {
+                self.describing_key = true;
+                self.set_status("Describe key: press a key or sequence");
+            }
+            Action::BrowseAttachments => {
+                self.load_attachment_items().await?;
+            }
+            Action::ShowHistory => {
+                self.history = oplog::recent(self.account_name(), HISTORY_LIMIT);
+                self.help_scroll = 0;
+                self.mode = InputMode::History;
+            }
+            Action::RecentlyDeleted => {
+                if self.indexing {
+                    self.set_status("Recently Deleted needs mu: try again when the reindex finishes");
+                    return Ok(());
+                }
+                self.deleted = self.recently_deleted().await?;
+                self.deleted_selected = 0;
+                self.mode = InputMode::RecentlyDeleted;
+            }
+            Action::ShowDigest => {
+                let since = digest::start_date(
+                    digest::parse_since(digest::DEFAULT_SINCE).expect("default period parses"),
+                );
+                let own = self.account().map(|a| vec![a.email.clone()]).unwrap_or_default();
+                match digest::collect(&mut self.mu, &own, since).await {
+                    Ok(d) => {
+                        self.overlay_lines = d.render();
+                        self.help_scroll = 0;
+                        self.mode = InputMode::Digest;
+                    }
+                    Err(e) => self.set_status(format!("Digest failed: {}", e)),
+                }
+            }
+            Action::DeliveryInfo => {
+                let Some(message_id) = self.preview_envelope().map(|e| e.message_id.clone()) else {
+                    return Ok(());
+                };
+                let account_name = self.account_name().to_string();
+                match oplog::find_send(&account_name, &message_id) {
+                    Some(record) => {
+                        self.overlay_lines = record.delivery_lines(&account_name);
+                        self.help_scroll = 0;
+                        self.mode = InputMode::DeliveryInfo;
+                    }
+                    None => self.set_status("No delivery record for this message"),
+                }
+            }
 
             // Command palette
             Action::OpenCommandPalette => {
@@ -2669,36 +6650,25 @@ impl App {
 
             // Sync — runs sync_command in background, then reindexes
             Action::SyncMail => {
-                if let Some(cmd) = self.config.effective_sync_command(self.active_account) {
+                if let Some(cmd) = self.sync_command() {
                     self.set_status(format!("Syncing: {}...", cmd));
-                    let tx = self.shell_tx.clone();
-                    tokio::spawn(async move {
-                        let output = tokio::process::Command::new("sh")
-                            .args(["-c", &cmd])
-                            .output()
-                            .await;
-                        match output {
-                            Ok(o) => {
-                                let _ = tx.send(Ok(ShellResult {
-                                    command: cmd,
-                                    reindex: true,
-                                    stdout: String::from_utf8_lossy(&o.stdout).into_owned(),
-                                    stderr: String::from_utf8_lossy(&o.stderr).into_owned(),
-                                    status: o.status,
-                                }));
-                            }
-                            Err(e) => {
-                                let _ = tx.send(Err(ShellError {
-                                    command: cmd,
-                                    error: e.to_string(),
-                                }));
-                            }
-                        }
-                    });
+                    self.spawn_command(cmd, true);
                 } else {
                     self.set_status("No sync_command configured");
                 }
             }
+            Action::HeadersOnlySync => {
+                if self.config.headers_sync_command.is_none() {
+                    self.set_status("Set headers_sync_command in config.toml for headers-only sync");
+                } else {
+                    self.headers_only = !self.headers_only;
+                    self.set_status(if self.headers_only {
+                        "Headers-only sync on"
+                    } else {
+                        "Headers-only sync off"
+                    });
+                }
+            }
 
             Action::OpenAccountPicker => {
                 if self.config.accounts.len() > 1 {
@@ -2718,6 +6688,16 @@ impl App {
                 self.mode = InputMode::SmartFolderCreate;
             }
 
+            Action::SplitPlusTag => {
+                let own = self.account().map_or("", |a| a.email.as_str());
+                let Some(address) = self.selected_envelope().and_then(|e| compose::plus_address(e, own)) else {
+                    self.set_status("Not sent to a plus address");
+                    return Ok(());
+                };
+                let name = format!("+{}", compose::plus_tag(&address).unwrap_or_default());
+                self.prefill_split(name, format!("to:\"{}\"", address)).await;
+            }
+
             Action::EditFolder => {
                 let folder = self.current_folder.clone();
                 self.edit_folder(&folder).await;
@@ -2725,7 +6705,9 @@ impl App {
 
             Action::DeleteFolder => {
                 let folder = self.current_folder.clone();
-                if folder.starts_with('@') || folder.starts_with('#') {
+                if self.is_builtin_folder(&folder) {
+                    self.delete_folder(&folder).await?;
+                } else if folder.starts_with('@') || folder.starts_with('#') {
                     let kind = if folder.starts_with('@') { "smart folder" } else { "split" };
                     self.set_status(format!("Delete {} \"{}\"? (y/n)", kind, folder));
                     self.pending_confirm = Some(ConfirmAction::DeleteFolder(folder));
@@ -2763,8 +6745,7 @@ impl App {
                 InputMode::Search => {} // handled by textarea in event loop
                 InputMode::FolderPicker => {
                     self.folder_filter.push(c);
-                    // Skip past the two special entries to first real folder
-                    self.folder_selected = 2;
+                    self.folder_selected = self.first_folder_match();
                 }
                 InputMode::MoveToFolder => {
                     self.folder_filter.push(c);
@@ -2779,13 +6760,28 @@ impl App {
                 InputMode::MaildirCreate => {
                     self.maildir_create_input.push(c);
                 }
+                InputMode::QuickReply => {
+                    self.quick_reply_input.push(c);
+                }
+                InputMode::FollowUpDate => {
+                    self.followup_input.push(c);
+                }
+                InputMode::ExportPath => {
+                    self.export_input.push(c);
+                }
+                InputMode::ContactName => {
+                    self.contact_input.push(c);
+                }
+                InputMode::NoteText => {
+                    self.note_input.push(c);
+                }
                 _ => {}
             },
             Action::InputBackspace => match self.mode {
                 InputMode::Search => {} // handled by textarea in event loop
                 InputMode::FolderPicker => {
                     self.folder_filter.pop();
-                    self.folder_selected = 2;
+                    self.folder_selected = self.first_folder_match();
                 }
                 InputMode::MoveToFolder => {
                     self.folder_filter.pop();
@@ -2800,6 +6796,21 @@ impl App {
                 InputMode::MaildirCreate => {
                     self.maildir_create_input.pop();
                 }
+                InputMode::QuickReply => {
+                    self.quick_reply_input.pop();
+                }
+                InputMode::FollowUpDate => {
+                    self.followup_input.pop();
+                }
+                InputMode::ExportPath => {
+                    self.export_input.pop();
+                }
+                InputMode::ContactName => {
+                    self.contact_input.pop();
+                }
+                InputMode::NoteText => {
+                    self.note_input.pop();
+                }
                 _ => {}
             },
             Action::InputHistoryPrev => {
@@ -2848,10 +6859,13 @@ impl App {
                         Box::pin(self.handle_action(action)).await?;
                     }
                 }
+                // An empty query stays in this arm, so Enter does nothing
+                #[allow(clippy::collapsible_match)]
                 InputMode::SmartFolderCreate => {
                     if !self.smart_create_query.trim().is_empty() {
-                        // When editing, keep the existing name; when creating, default to query
-                        if self.editing_folder.is_none() {
+                        // When editing, keep the existing name; when creating, default
+                        // to the query unless one was suggested
+                        if self.editing_folder.is_none() && self.smart_create_name.is_empty() {
                             self.smart_create_name = self.smart_create_query.clone();
                         }
                         self.smart_create_phase = 1;
@@ -2900,15 +6914,26 @@ impl App {
                                 if let Some(sf) = self.smart_folders.iter_mut().find(|sf| sf.name == old_name) {
                                     sf.name = name.clone();
                                     sf.query = query.clone();
+                                    // Editing a shared folder saves it as
+                                    // this account's own override
+                                    sf.shared = false;
                                 }
                                 self.smart_folder_queries.remove(old_key);
+                                // Recount under the new query on the next idle tick
+                                self.smart_counts.remove(old_key);
+                                self.smart_count_due.remove(old_key);
                                 self.known_folders.retain(|f| f != old_key);
                             } else {
                                 // Creating new smart folder
                                 let sf = SmartFolder {
                                     name: name.clone(),
                                     query: query.clone(),
+                                    refresh: None,
+                                    builtin: false,
+                                    shared: false,
                                 };
+                                // A user folder replaces a built-in one of the same name
+                                self.smart_folders.retain(|f| f.name != name);
                                 self.smart_folders.push(sf);
                             }
                             smart_folders::save_smart_folders(&self.smart_folders, self.account_name());
@@ -2933,12 +6958,23 @@ impl App {
                         } else {
                             format!("/{}", path)
                         };
-                        if let Some(account) = self.account() {
-                            let root = expand_maildir_root(&account.maildir);
-                            let full = format!("{}{}", root, folder_path);
-                            let _ = std::fs::create_dir_all(format!("{}/cur", full));
-                            let _ = std::fs::create_dir_all(format!("{}/new", full));
-                            let _ = std::fs::create_dir_all(format!("{}/tmp", full));
+                        // An existing folder differing only in case (e.g. "/inbox"
+                        // for an account's "/INBOX") is opened rather than duplicated
+                        let existing = self
+                            .known_folders
+                            .iter()
+                            .find(|f| f.eq_ignore_ascii_case(&folder_path))
+                            .cloned();
+                        if let Some(folder) = existing {
+                            self.mode = InputMode::Normal;
+                            self.navigate_folder(&folder).await?;
+                            self.set_status(format!("{} already exists", folder));
+                        } else if let Some(account) = self.account() {
+                            let root = std::path::PathBuf::from(expand_maildir_root(&account.maildir));
+                            if let Err(e) = maildir::create_folder(&root, &folder_path) {
+                                self.set_status(format!("Error: {}", e));
+                                return Ok(());
+                            }
                             self.known_folders.push(folder_path.clone());
                             self.known_folders.sort();
                             self.mode = InputMode::Normal;
@@ -2949,12 +6985,25 @@ impl App {
                         }
                     }
                 }
+                InputMode::QuickReply => {
+                    self.mode = InputMode::Normal;
+                    self.send_quick_reply().await;
+                }
+                InputMode::FollowUpDate => self.set_followup(),
+                InputMode::ExportPath => {
+                    self.mode = InputMode::Normal;
+                    self.export_search().await;
+                }
+                InputMode::ContactName => self.save_contact(),
+                InputMode::NoteText => self.save_note(),
                 InputMode::MoveToFolder => {
                     let filtered = self.filtered_folders_plain();
                     if let Some(folder) = filtered.get(self.folder_selected).cloned() {
                         // Only move to real maildir folders (starting with /)
                         if folder.starts_with('/') {
                             self.mode = InputMode::Normal;
+                            self.remember_folder(&folder);
+                            self.last_move = Some(folder.clone());
                             self.triage_move(&folder, &format!("Moved to {}", folder))
                                 .await?;
                         } else {
@@ -2973,9 +7022,15 @@ impl App {
                     }
                 }
                 InputMode::FolderPicker | InputMode::CommandPalette | InputMode::MoveToFolder => {
+                    self.sweep_undo = false;
                     self.mode = InputMode::Normal;
                 }
-                InputMode::Help => {
+                InputMode::Help
+                | InputMode::History
+                | InputMode::Digest
+                | InputMode::DeliveryInfo
+                | InputMode::CommandOutput
+                | InputMode::Summary => {
                     self.mode = InputMode::Normal;
                 }
                 InputMode::SmartFolderCreate => {
@@ -2993,49 +7048,62 @@ impl App {
                 InputMode::MaildirCreate => {
                     self.mode = InputMode::FolderPicker;
                 }
+                InputMode::QuickReply => {
+                    self.mode = InputMode::Normal;
+                    self.set_status("Quick reply cancelled");
+                }
+                InputMode::FollowUpDate | InputMode::ExportPath | InputMode::NoteText => {
+                    self.mode = InputMode::Normal;
+                }
+                InputMode::ContactName => {
+                    self.contact_target = None;
+                    self.mode = self.contact_return.clone();
+                }
                 _ => {}
             },
 
             // Custom bindings: shell commands
+            Action::ToggleShellDryRun => {
+                self.shell_dry_run = !self.shell_dry_run;
+                self.set_status(if self.shell_dry_run {
+                    "Shell dry run on: bindings show their command instead of running it"
+                } else {
+                    "Shell dry run off"
+                });
+            }
             Action::RunShell {
                 command,
                 reindex,
                 suspend,
             } => {
-                if suspend {
+                let command = match self.shell_binding_command(&command) {
+                    Ok(command) => command,
+                    Err(e) => {
+                        self.set_status(format!("Not run: {}", e));
+                        return Ok(());
+                    }
+                };
+                if self.shell_dry_run {
+                    let cwd = std::env::current_dir().map_or_else(|_| "?".to_string(), |d| d.display().to_string());
+                    self.overlay_lines =
+                        shell_dry_run_lines(&command, reindex, suspend, &cwd, |name| std::env::var(name).ok());
+                    self.help_scroll = 0;
+                    self.mode = InputMode::CommandOutput;
+                } else if suspend {
                     // Deferred to run loop (needs terminal suspend/resume)
                     self.shell_pending = Some(ShellPending { command, reindex });
                 } else {
                     // Spawn in background so the TUI stays responsive
                     self.set_status(format!("Running: {}...", command));
-                    let tx = self.shell_tx.clone();
-                    let cmd = command.clone();
-                    tokio::spawn(async move {
-                        let output = tokio::process::Command::new("sh")
-                            .args(["-c", &cmd])
-                            .output()
-                            .await;
-                        match output {
-                            Ok(o) => {
-                                let _ = tx.send(Ok(ShellResult {
-                                    command: cmd,
-                                    reindex,
-                                    stdout: String::from_utf8_lossy(&o.stdout).into_owned(),
-                                    stderr: String::from_utf8_lossy(&o.stderr).into_owned(),
-                                    status: o.status,
-                                }));
-                            }
-                            Err(e) => {
-                                let _ = tx.send(Err(ShellError {
-                                    command: cmd,
-                                    error: e.to_string(),
-                                }));
-                            }
-                        }
-                    });
+                    self.spawn_command(command, reindex);
                 }
             }
 
+            // Custom bindings: reply templates
+            Action::ReplyTemplate { name, send, confirm } => {
+                self.reply_from_template(&name, send, confirm).await;
+            }
+
             // Custom bindings: folder navigation
             Action::NavigateFolder(folder) => {
                 self.navigate_folder(&folder).await?;
@@ -3043,7 +7111,13 @@ impl App {
 
             // System
             Action::Redraw => {} // handled in run loop
-            Action::Quit => self.should_quit = true,
+            Action::Quit => match self.background_work() {
+                Some(work) => {
+                    self.set_status(format!("{} in progress \u{2014} quit anyway? (y/n)", work));
+                    self.pending_confirm = Some(ConfirmAction::Quit);
+                }
+                None => self.should_quit = true,
+            },
             Action::Noop => {}
         }
         Ok(())
@@ -3052,18 +7126,106 @@ impl App {
 
 // expand_maildir_root, save_to_sent, rand_seq, gethostname moved to crate::maildir
 
+/// Run an IPC command from the socket, the HTTP API or the command pipe,
+/// replying if the sender is waiting for a response.
+async fn dispatch_ipc(app: &mut App, cmd: IpcCommand, reply: Option<links::IpcReply>) {
+    let resp = match app.handle_ipc_command(cmd).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            app.set_status(format!("IPC error: {}", e));
+            IpcResponse::Error { message: e.to_string() }
+        }
+    };
+    if let Some(reply) = reply {
+        if let Err(e) = reply.send(&resp).await {
+            debug_log!("IPC response error: {}", e);
+        }
+    }
+}
+
+/// Draw a frame with `render` when `needed`, and report whether it did.
+fn draw_if<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    needed: bool,
+    render: impl FnOnce(&mut ratatui::Frame),
+) -> io::Result<bool> {
+    if needed {
+        terminal.draw(render)?;
+    }
+    Ok(needed)
+}
+
 pub async fn run(mut app: App) -> Result<()> {
     // Fast partial load — renders immediately with first ~100 envelopes.
     // Split caches and background servers are deferred to after first render.
-    app.load_folder().await?;
+    match app.restored_view.take() {
+        Some(view) => app.restore_view(view).await?,
+        None => {
+            app.apply_folder_prefs(true);
+            app.load_folder().await?
+        }
+    }
 
     // Start IPC listener as a background task, sending commands through a channel
     // Create shell result channel — replace the dummy one from App::new
     let (shell_tx, mut shell_rx) = tokio::sync::mpsc::unbounded_channel();
     app.shell_tx = shell_tx;
 
-    let (ipc_tx, mut ipc_rx) = tokio::sync::mpsc::unbounded_channel::<(IpcCommand, tokio::net::UnixStream)>();
-    let _ipc_guard = match IpcListener::bind() {
+    // Start the snippet extractor — replaces the dummy sender from App::new
+    let (snippet_tx, mut snippet_rx) = snippets::spawn_extractor(SNIPPET_CHARS);
+    app.snippet_tx = snippet_tx;
+    let scorer = scores::Scorer {
+        header: app.config.score_header.clone(),
+        command: app.config.score_command.clone(),
+    };
+    let (score_tx, mut score_rx) = scores::spawn_scorer(scorer);
+    app.score_tx = score_tx;
+    // The first folder was listed before the scorer started
+    app.score_envelopes();
+    let (thread_render_tx, mut thread_render_rx) = thread_view::spawn_renderer();
+    app.thread_render_tx = thread_render_tx;
+    let (folder_scan_tx, mut folder_scan_rx) = folder_scan::spawn_scanner();
+    app.folder_scan_tx = folder_scan_tx;
+    let (attachment_list_tx, mut attachment_list_rx) = tokio::sync::mpsc::unbounded_channel();
+    app.attachment_list_tx = attachment_list_tx;
+    // The first folder was listed before the scanner started
+    app.scan_folders();
+
+    // Socket and HTTP commands carry a way to reply; pipe commands don't.
+    let (ipc_tx, mut ipc_rx) =
+        tokio::sync::mpsc::unbounded_channel::<(IpcCommand, Option<links::IpcReply>)>();
+    if let Some(api) = app.config.http_api.clone() {
+        match http_api::bind(&api).await {
+            Ok(listener) => {
+                tokio::spawn(http_api::serve(listener, api, ipc_tx.clone()));
+            }
+            Err(e) => eprintln!("HTTP API: {}", e),
+        }
+    }
+    if let Some(pipe) = app.config.ipc_pipe.as_deref().map(crate::config::expand_tilde) {
+        if pipe == "-" && io::stdin().is_terminal() {
+            eprintln!("IPC pipe: stdin is a terminal, not reading commands from it");
+        } else {
+            let tx = ipc_tx.clone();
+            std::thread::spawn(move || {
+                debug_log!("IPC pipe reader started on {}", pipe);
+                let result = links::read_command_pipe(&pipe, |cmd| match cmd {
+                    Ok(cmd) => {
+                        debug_log!("IPC pipe: {:?}", cmd);
+                        tx.send((cmd, None)).is_ok()
+                    }
+                    Err(e) => {
+                        debug_log!("IPC pipe: bad command: {}", e);
+                        true
+                    }
+                });
+                if let Err(e) = result {
+                    debug_log!("IPC pipe error: {}", e);
+                }
+            });
+        }
+    }
+    let _ipc_guard = match IpcListener::bind(app.socket_name.as_deref()) {
         Ok(listener) => {
             let tx = ipc_tx;
             Some(tokio::spawn(async move {
@@ -3072,7 +7234,7 @@ pub async fn run(mut app: App) -> Result<()> {
                     match listener.accept().await {
                         Ok((cmd, stream)) => {
                             debug_log!("IPC accepted: {:?}", cmd);
-                            if tx.send((cmd, stream)).is_err() {
+                            if tx.send((cmd, Some(links::IpcReply::Socket(stream)))).is_err() {
                                 debug_log!("IPC channel closed, exiting");
                                 break;
                             }
@@ -3112,16 +7274,28 @@ pub async fn run(mut app: App) -> Result<()> {
     let mut last_auto_sync_time: Option<Instant> = None;
     let mut event_stream = EventStream::new();
     let mut startup_deferred = true;
+    // Smart folder badges shown while the inbox is paused
+    let paused_counts = HashMap::new();
+    let mut last_draw = Instant::now();
+    let mut frame_stats = std::env::var_os("HUTT_FRAME_TIME").map(|_| FrameStats::default());
 
     loop {
+        app.update_low_power();
         app.clear_stale_status();
+        app.update_tutorial();
+        app.clear_stale_arrivals();
 
         let preview_width = {
             let size = terminal.size()?;
-            (size.width * (100 - app.list_pct) / 100).saturating_sub(4)
+            if app.mode == InputMode::TriageLoop {
+                // Triage Loop shows the preview alone, full width
+                size.width.saturating_sub(4)
+            } else {
+                (size.width * (100 - app.list_pct) / 100).saturating_sub(4)
+            }
         };
 
-        if app.mode == InputMode::ThreadView {
+        if app.showing_thread() {
             // Thread view uses full terminal width (not split preview width)
             let thread_width = {
                 let size = terminal.size()?;
@@ -3130,10 +7304,33 @@ pub async fn run(mut app: App) -> Result<()> {
             app.ensure_thread_body_loaded(thread_width);
         } else {
             app.ensure_preview_loaded(preview_width);
+            let list_height = terminal.size()?.height.saturating_sub(2) as usize;
+            app.ensure_snippets_loaded(list_height);
         }
 
-        terminal.draw(|frame| {
+        app.track_read_positions();
+
+        if std::mem::take(&mut app.needs_clear) {
+            terminal.clear()?;
+            app.redraw = true;
+        }
+        // Draw when something has changed, and now and then while idle
+        // for the clocks and elapsed times on screen
+        let needed = std::mem::take(&mut app.redraw) || last_draw.elapsed() >= IDLE_REDRAW;
+        let frame_start = Instant::now();
+        let drawn = draw_if(&mut terminal, needed, |frame| {
             let size = frame.area();
+            // The startup banner goes under everything else
+            let banner_height = app.banner_height(size.height);
+            if banner_height > 0 {
+                let banner_area = Rect {
+                    y: size.y + size.height - banner_height,
+                    height: banner_height,
+                    ..size
+                };
+                frame.render_widget(HealthBanner { problems: &app.health_banner }, banner_area);
+            }
+            let size = Rect { height: size.height - banner_height, ..size };
             let outer = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
@@ -3144,12 +7341,13 @@ pub async fn run(mut app: App) -> Result<()> {
                 .split(size);
 
             // Top bar
-            let thread_subject = if app.mode == InputMode::ThreadView {
+            let triage_title = app.triage_loop.as_ref().map(TriageLoop::title);
+            let thread_subject = if app.showing_thread() {
                 app.thread_messages
                     .first()
                     .map(|m| m.envelope.subject.as_str())
             } else {
-                None
+                triage_title.as_deref()
             };
             let unread = app.envelopes.iter().filter(|e| e.is_unread()).count();
             let account_name = if app.config.accounts.len() > 1 {
@@ -3168,25 +7366,145 @@ pub async fn run(mut app: App) -> Result<()> {
                 tabs: &app.tabs,
                 tab_scroll: app.tab_scroll,
                 multi_account: app.config.accounts.len() > 1,
+                view_count: app.views.len(),
+                active_view: app.active_view,
+                paused: app.paused.is_some(),
+                truncated: app.truncation(),
+                low_power: app.low_power,
             };
             let tab_bar_result = top.render_with_regions(outer[0], frame.buffer_mut());
             app.tab_regions = tab_bar_result.regions;
 
             // Content
             match app.mode {
-                InputMode::ThreadView => {
+                InputMode::TriageLoop => {
+                    let envelope = app.preview_envelope();
+                    let body = envelope
+                        .and_then(|e| app.preview_cache.get(&e.message_id, preview_width));
+                    let loaded = body.is_some();
+                    let preview = PreviewPane {
+                        envelope,
+                        body,
+                        scroll: app.preview_scroll,
+                        headers_expanded: app.preview_headers_expanded,
+                        sender_badge: app.config.sender_badges,
+                        note: envelope.and_then(|e| app.note(e)),
+                    };
+                    let max_scroll = preview.line_count()
+                        .saturating_sub(outer[1].height as usize) as u16;
+                    let scroll = app.preview_scroll.min(max_scroll);
+                    frame.render_widget(PreviewPane { scroll, ..preview }, outer[1]);
+                    if loaded {
+                        app.preview_scroll = scroll;
+                    }
+                }
+                _ if app.showing_thread() => {
                     let tv = ThreadView {
                         messages: &app.thread_messages,
                         selected: app.thread_selected,
                         scroll: app.thread_scroll,
+                        color_senders: app.config.color_senders,
+                        sender_badges: app.config.sender_badges,
+                        headers: &app.config.thread_view,
+                        snippets: app.snippets.as_map(),
                     };
-                    frame.render_widget(tv, outer[1]);
+                    // Clamp so End / over-scrolling stops at the last screenful
+                    let max_scroll = tv.line_count(outer[1].width)
+                        .saturating_sub(outer[1].height as usize) as u16;
+                    let scroll = app.thread_scroll.min(max_scroll);
+                    frame.render_widget(ThreadView { scroll, ..tv }, outer[1]);
+                    // Bodies still rendering would clamp a resumed position
+                    if app.thread_rendering.is_empty() {
+                        app.thread_scroll = scroll;
+                    }
                 }
                 _ => {
                     let content = Layout::default()
                         .direction(Direction::Horizontal)
                         .constraints([Constraint::Percentage(app.list_pct), Constraint::Percentage(100 - app.list_pct)])
                         .split(outer[1]);
+                    // Panes on two accounts: every row gets its account's badge
+                    let badges = app
+                        .compare
+                        .as_ref()
+                        .filter(|compare| compare.other.account != app.active_account)
+                        .map(|compare| {
+                            (
+                                compare::account_badge(&app.config, app.active_account),
+                                compare::account_badge(&app.config, compare.other.account),
+                            )
+                        });
+                    let (live_badge, other_badge) = match &badges {
+                        Some((live, other)) => (Some(live), Some(other)),
+                        None => (None, None),
+                    };
+                    // Comparing: the two panes side by side, with no preview
+                    let (list_area, preview_area) = match &app.compare {
+                        Some(compare) => {
+                            let halves = Layout::default()
+                                .direction(Direction::Horizontal)
+                                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                                .split(outer[1]);
+                            let (live, other) = if compare.focus_right {
+                                (halves[1], halves[0])
+                            } else {
+                                (halves[0], halves[1])
+                            };
+                            let multi_account = app.config.accounts.len() > 1;
+                            let account_label = |index: usize| {
+                                app.config.accounts.get(index).filter(|_| multi_account).map_or("", |a| a.name.as_str())
+                            };
+                            let pane = &compare.other;
+                            let live_title = compare::title(
+                                account_label(app.active_account),
+                                &app.current_folder,
+                                &app.envelopes,
+                                &pane.envelopes,
+                            );
+                            let other_title =
+                                compare::title(account_label(pane.account), &pane.view.folder, &pane.envelopes, &app.envelopes);
+                            let title_row = |area: Rect| Rect { height: area.height.min(1), ..area };
+                            let below = |area: Rect| Rect {
+                                y: area.y + area.height.min(1),
+                                height: area.height.saturating_sub(1),
+                                ..area
+                            };
+                            frame.render_widget(PaneTitle { text: &live_title, focused: true }, title_row(live));
+                            frame.render_widget(PaneTitle { text: &other_title, focused: false }, title_row(other));
+
+                            let height = EnvelopeList::capacity(below(other).height, app.list_density, 0);
+                            let (offset, _) = EnvelopeList::visible_range(
+                                pane.view.selected,
+                                pane.view.scroll_offset,
+                                height,
+                                pane.envelopes.len(),
+                            );
+                            let other_list = EnvelopeList {
+                                envelopes: &pane.envelopes,
+                                selected: pane.view.selected,
+                                offset,
+                                multi_selected: &pane.selected_set,
+                                new_arrivals: &HashSet::new(),
+                                density: app.list_density,
+                                snippets: app.snippets.as_map(),
+                                color_senders: app.config.color_senders,
+                                flag_column: &app.flag_column,
+                                show_size: false,
+                                scores: None,
+                                sections: &[],
+                                aging: None,
+                                collapsed: &HashMap::new(),
+                                badge: other_badge,
+                            };
+                            frame.render_widget(other_list, below(other));
+                            (below(live), None)
+                        }
+                        None => (content[0], Some(content[1])),
+                    };
+                    // Size-based views (e.g. @Large) show message sizes
+                    let show_size = app.current_query.contains("size:");
+                    let scores = app.scoring().then_some(&app.scores);
+                    let aging = app.aging();
 
                     if app.conversations_mode {
                         let conv_list = ConversationList {
@@ -3194,10 +7512,19 @@ pub async fn run(mut app: App) -> Result<()> {
                             selected: app.selected,
                             offset: app.scroll_offset,
                             multi_selected: &app.selected_set,
+                            new_arrivals: &app.new_arrivals,
+                            density: app.list_density,
+                            snippets: app.snippets.as_map(),
+                            color_senders: app.config.color_senders,
+                            flag_column: &app.flag_column,
+                            show_size,
+                            scores,
+                            aging,
+                            badge: live_badge,
                         };
-                        frame.render_widget(conv_list, content[0]);
+                        frame.render_widget(conv_list, list_area);
 
-                        let height = content[0].height as usize;
+                        let height = (list_area.height as usize / app.list_density.rows_per_item()).max(1);
                         let (new_offset, _) = EnvelopeList::visible_range(
                             app.selected,
                             app.scroll_offset,
@@ -3206,15 +7533,27 @@ pub async fn run(mut app: App) -> Result<()> {
                         );
                         app.scroll_offset = new_offset;
                     } else {
+                        let sections = app.section_headers();
                         let env_list = EnvelopeList {
                             envelopes: &app.envelopes,
                             selected: app.selected,
                             offset: app.scroll_offset,
                             multi_selected: &app.selected_set,
+                            new_arrivals: &app.new_arrivals,
+                            density: app.list_density,
+                            snippets: app.snippets.as_map(),
+                            color_senders: app.config.color_senders,
+                            flag_column: &app.flag_column,
+                            show_size,
+                            scores,
+                            sections: &sections,
+                            aging,
+                            collapsed: &app.collapsed,
+                            badge: live_badge,
                         };
-                        frame.render_widget(env_list, content[0]);
+                        frame.render_widget(env_list, list_area);
 
-                        let height = content[0].height as usize;
+                        let height = EnvelopeList::capacity(list_area.height, app.list_density, sections.len());
                         let (new_offset, _) = EnvelopeList::visible_range(
                             app.selected,
                             app.scroll_offset,
@@ -3224,20 +7563,103 @@ pub async fn run(mut app: App) -> Result<()> {
                         app.scroll_offset = new_offset;
                     }
 
-                    let envelope = app.preview_envelope();
-                    let body = envelope
-                        .and_then(|e| app.preview_cache.get(&e.message_id, preview_width));
-                    let preview = PreviewPane {
-                        envelope,
-                        body,
-                        scroll: app.preview_scroll,
-                    };
-                    frame.render_widget(preview, content[1]);
+                    if let Some(preview_area) = preview_area {
+                        let envelope = app.preview_envelope();
+                        let body = envelope
+                            .and_then(|e| app.preview_cache.get(&e.message_id, preview_width));
+                        let loaded = body.is_some();
+                        let preview = PreviewPane {
+                            envelope,
+                            body,
+                            scroll: app.preview_scroll,
+                            headers_expanded: app.preview_headers_expanded,
+                            sender_badge: app.config.sender_badges,
+                            note: envelope.and_then(|e| app.note(e)),
+                        };
+                        // Clamp so End / over-scrolling stops at the last screenful
+                        let max_scroll = preview.line_count()
+                            .saturating_sub(preview_area.height as usize) as u16;
+                        let scroll = app.preview_scroll.min(max_scroll);
+                        frame.render_widget(PreviewPane { scroll, ..preview }, preview_area);
+                        if loaded {
+                            app.preview_scroll = scroll;
+                        }
+                    }
                 }
             }
 
             // Bottom bar
-            if app.mode == InputMode::Search {
+            let contact_label = app
+                .contact_target
+                .as_ref()
+                .map(|a| format!(" Name for {}: ", a.email))
+                .unwrap_or_default();
+            let prompt = match app.mode {
+                InputMode::FollowUpDate => Some((" Await reply by: ", &app.followup_input)),
+                InputMode::ExportPath => Some((" Export to: ", &app.export_input)),
+                InputMode::ContactName => Some((contact_label.as_str(), &app.contact_input)),
+                InputMode::NoteText => Some((" Note: ", &app.note_input)),
+                _ => None,
+            };
+            if let Some((label, input)) = prompt {
+                use ratatui::style::{Color, Modifier, Style};
+                let bar_area = outer[2];
+                buf_set_style_area(frame.buffer_mut(), bar_area,
+                    Style::default().bg(Color::DarkGray));
+                let prompt_style = Style::default()
+                    .bg(Color::DarkGray)
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD);
+                let buf = frame.buffer_mut();
+                let (x, _) = buf.set_stringn(bar_area.x, bar_area.y, label,
+                    bar_area.width as usize, prompt_style);
+                let right = bar_area.x + bar_area.width;
+                let text = format!("{}\u{2588}  ", input);
+                let (x, _) = buf.set_stringn(x, bar_area.y, &text,
+                    right.saturating_sub(x) as usize,
+                    Style::default().bg(Color::DarkGray).fg(Color::White));
+                let hint = BottomBar {
+                    mode: &app.mode,
+                    pending_key: None,
+                    status_message: None,
+                    filter_desc: None,
+                    selection_count: 0,
+                    selection_size: 0,
+                    conversations_mode: false,
+                    sort_label: None,
+                    hints: None,
+                };
+                if x < right {
+                    use ratatui::widgets::Widget;
+                    hint.render(ratatui::layout::Rect::new(x, bar_area.y, right - x, 1), buf);
+                }
+            } else if app.mode == InputMode::QuickReply {
+                use ratatui::style::{Color, Modifier, Style};
+                let bar_area = outer[2];
+                buf_set_style_area(frame.buffer_mut(), bar_area,
+                    Style::default().bg(Color::DarkGray));
+                let prompt = format!(
+                    " Reply to {}: ",
+                    app.selected_envelope().map(|e| e.sender_display()).unwrap_or_default()
+                );
+                let prompt_style = Style::default()
+                    .bg(Color::DarkGray)
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD);
+                let input = format!("{}\u{2588}", app.quick_reply_input);
+                let buf = frame.buffer_mut();
+                let (x, _) = buf.set_stringn(bar_area.x, bar_area.y, &prompt,
+                    bar_area.width as usize, prompt_style);
+                let right = bar_area.x + bar_area.width;
+                if x < right {
+                    // Keep the end of long input (and the cursor) visible
+                    let avail = (right - x) as usize;
+                    let skip = input.chars().count().saturating_sub(avail);
+                    let visible: String = input.chars().skip(skip).collect();
+                    buf.set_stringn(x, bar_area.y, &visible, avail,
+                        Style::default().bg(Color::DarkGray).fg(Color::White));
+                }
+            } else if app.mode == InputMode::Search {
                 // Render search textarea with "/" prompt and optional vim mode indicator
                 use ratatui::style::{Color, Modifier, Style};
                 let bar_area = outer[2];
@@ -3296,29 +7718,39 @@ pub async fn run(mut app: App) -> Result<()> {
                     status_message: app.status_message.as_deref(),
                     filter_desc: filter_desc.as_deref(),
                     selection_count: app.selected_set.len(),
+                    selection_size: app.selection_size(),
                     conversations_mode: app.conversations_mode,
                     sort_label: sort_label_str.as_deref(),
+                    hints: app.context_hints(),
                 };
                 frame.render_widget(bottom, outer[2]);
             }
 
             if app.mode == InputMode::FolderPicker {
                 let filtered = app.filtered_folders();
+                let (favorites, recents) = app.pinned_folders(false);
                 let picker = FolderPicker {
                     folders: &filtered,
                     selected: app.folder_selected,
                     filter: &app.folder_filter,
                     title: "Folders",
+                    favorites: favorites.len(),
+                    recents: recents.len(),
+                    counts: if app.paused.is_some() { &paused_counts } else { &app.smart_counts },
                 };
                 frame.render_widget(picker, size);
             }
             if app.mode == InputMode::MoveToFolder {
                 let filtered = app.filtered_folders_plain();
+                let (favorites, recents) = app.pinned_folders(true);
                 let picker = FolderPicker {
                     folders: &filtered,
                     selected: app.folder_selected,
                     filter: &app.folder_filter,
                     title: "Move to folder",
+                    favorites: favorites.len(),
+                    recents: recents.len(),
+                    counts: if app.paused.is_some() { &paused_counts } else { &app.smart_counts },
                 };
                 frame.render_widget(picker, size);
             }
@@ -3382,6 +7814,70 @@ pub async fn run(mut app: App) -> Result<()> {
                 };
                 frame.render_widget(help, size);
             }
+            if app.mode == InputMode::AttachmentBrowser {
+                let browser = AttachmentBrowser {
+                    items: &app.attachment_items,
+                    selected: app.attachment_selected,
+                };
+                frame.render_widget(browser, size);
+            }
+            if app.mode == InputMode::Digest {
+                let digest = TextOverlay {
+                    title: "Digest",
+                    scroll: app.help_scroll,
+                    lines: &app.overlay_lines,
+                };
+                frame.render_widget(digest, size);
+            }
+            if app.mode == InputMode::DeliveryInfo {
+                let delivery = TextOverlay {
+                    title: "Delivery Info",
+                    scroll: app.help_scroll,
+                    lines: &app.overlay_lines,
+                };
+                frame.render_widget(delivery, size);
+            }
+            if app.mode == InputMode::CommandOutput {
+                let output = TextOverlay {
+                    title: "Command Output",
+                    scroll: app.help_scroll,
+                    lines: &app.overlay_lines,
+                };
+                frame.render_widget(output, size);
+            }
+            if app.mode == InputMode::Summary {
+                let summary = TextOverlay {
+                    title: "Thread Summary",
+                    scroll: app.help_scroll,
+                    lines: &app.overlay_lines,
+                };
+                frame.render_widget(summary, size);
+            }
+            if app.mode == InputMode::HeaderMenu {
+                let menu = HeaderMenu {
+                    targets: &app.header_menu,
+                    selected: app.header_menu_selected,
+                };
+                frame.render_widget(menu, size);
+            }
+            if let (InputMode::RecipientCheck, Some(check)) = (&app.mode, &app.recipient_check) {
+                frame.render_widget(RecipientCheckPopup { check }, size);
+            }
+            if app.mode == InputMode::History {
+                let history = HistoryOverlay {
+                    scroll: app.help_scroll,
+                    records: &app.history,
+                };
+                frame.render_widget(history, size);
+            }
+            if app.mode == InputMode::RecentlyDeleted {
+                let deleted = DeletedOverlay {
+                    messages: &app.deleted,
+                    selected: app.deleted_selected,
+                    days: app.config.recently_deleted_days,
+                };
+                frame.render_widget(deleted, size);
+            }
 
             // Account picker dropdown
             if app.mode == InputMode::AccountPicker {
@@ -3471,12 +7967,24 @@ pub async fn run(mut app: App) -> Result<()> {
                     }
                 }
             }
+
+            if let Some(stats) = &frame_stats {
+                let area = frame.area();
+                stats.render(area, frame.buffer_mut());
+            }
         })?;
+        if drawn {
+            if let Some(stats) = frame_stats.as_mut() {
+                stats.record(frame_start.elapsed());
+            }
+            last_draw = Instant::now();
+        }
 
         // Deferred startup: after the first render, do expensive initialization.
         // This lets the TUI appear instantly with the partial inbox load.
         if startup_deferred {
             startup_deferred = false;
+            app.redraw = true;
 
             // Populate split caches and re-filter inbox if needed
             app.refresh_split_caches().await;
@@ -3484,6 +7992,30 @@ pub async fn run(mut app: App) -> Result<()> {
                 let _ = app.load_folder().await;
             }
 
+            // Clear answered follow-ups and announce overdue ones
+            if let Some(notice) = app.check_followups().await {
+                app.set_status(notice);
+            }
+
+            // Triage held back when hutt last quit before a reindex finished
+            if let Some(notice) = app.resume_held_triage().await {
+                let _ = app.load_folder().await;
+                app.set_status(format!("From the last session: {}", notice));
+            }
+
+            // Send blocked senders' mail to spam and find first-time senders
+            if let Some(notice) = app.screen_inbox().await {
+                let _ = app.load_folder().await;
+                app.set_status(notice);
+            }
+
+            // Report setup problems now rather than as errors mid-session
+            if app.config.health_check {
+                app.health_banner = health::check(&app.config);
+                let dates: Vec<_> = app.envelopes.iter().map(|e| e.date).collect();
+                app.health_banner.extend(health::clock_problem(&dates, chrono::Utc::now()));
+            }
+
             // Spawn background mu servers for non-active accounts (for prefetch)
             if app.config.background_servers {
                 for idx in 0..app.config.accounts.len() {
@@ -3507,13 +8039,22 @@ pub async fn run(mut app: App) -> Result<()> {
         }
 
         if app.should_quit {
+            finish_background_work(&mut app, &mut shell_rx).await;
+            app.save_held_triage();
+            app.save_snippets();
+            app.save_scores();
+            app.save_session();
+            app.save_warm_start();
             break;
         }
 
         // Handle compose (requires terminal suspend/resume)
         if let Some(pending) = app.compose_pending.take() {
             let ctx = match pending {
-                compose::ComposePending::Ready(ctx) => Some(ctx),
+                compose::ComposePending::Ready(ctx) => Some(*ctx),
+                compose::ComposePending::Kind(compose::ComposeKind::ReplyAll) => app
+                    .build_compose_context(&compose::ComposeKind::ReplyAll)
+                    .and_then(|ctx| app.check_recipients(ctx)),
                 compose::ComposePending::Kind(kind) => app.build_compose_context(&kind),
             };
             if let Some(ctx) = ctx {
@@ -3524,9 +8065,8 @@ pub async fn run(mut app: App) -> Result<()> {
 
                 match compose::build_compose_file(&ctx, from_email) {
                     Ok(content) => {
-                        let tmp_path = std::env::temp_dir()
-                            .join(format!("hutt-compose-{}.eml", std::process::id()));
-                        if std::fs::write(&tmp_path, &content).is_ok() {
+                        let compose_dir = compose::compose_dir(app.config.compose_dir.as_deref());
+                        if let Ok(tmp_path) = compose::create_compose_file(&compose_dir, &content) {
                             // Build env vars for the editor (child-only, not process-global).
                             let muhome = app.config.effective_muhome(app.active_account);
                             let account_name = app.account().map(|a| a.name.clone());
@@ -3547,29 +8087,102 @@ pub async fn run(mut app: App) -> Result<()> {
                             terminal::disable_raw_mode()?;
                             io::stdout().execute(LeaveAlternateScreen)?;
 
-                            let modified =
+                            let mut modified =
                                 compose::launch_editor(&tmp_path, &app.config.editor, &env_refs)
                                     .unwrap_or(false);
 
+                            // Pre-send checks: show warnings and offer a way
+                            // back into the editor before anything goes out.
+                            if modified {
+                                app.load_contacts().await;
+                            }
+                            let checks = compose::SendChecks {
+                                old_reply_days: app.config.warn_reply_older_than_days,
+                                max_recipients: app.config.warn_recipients_over,
+                                internal_domains: app.account().map_or(&[], |a| a.internal_domains.as_slice()),
+                                known_addresses: app.contacts.as_deref().unwrap_or_default(),
+                                max_attachments_mb: app.config.warn_attachments_over_mb,
+                            };
+                            while modified {
+                                let Ok(msg_content) = std::fs::read_to_string(&tmp_path) else {
+                                    break;
+                                };
+                                let warnings = compose::reply_warnings(
+                                    &ctx, &msg_content, chrono::Utc::now(), checks,
+                                );
+                                if warnings.is_empty() {
+                                    break;
+                                }
+                                use std::io::Write;
+                                println!();
+                                for w in &warnings {
+                                    println!("Warning: {}", w);
+                                }
+                                // Offer to upload only while there's something to upload
+                                let upload_command = app.config.upload_command.clone()
+                                    .filter(|_| !compose::attachment_paths(&msg_content).is_empty());
+                                if upload_command.is_some() {
+                                    print!("Send anyway? [y]es / [e]dit / [u]pload attachments / [n]o: ");
+                                } else {
+                                    print!("Send anyway? [y]es / [e]dit / [n]o: ");
+                                }
+                                let _ = io::stdout().flush();
+                                let mut answer = String::new();
+                                let _ = io::stdin().read_line(&mut answer);
+                                match answer.trim() {
+                                    "y" | "Y" | "yes" => break,
+                                    "e" | "E" | "edit" => {
+                                        let _ = compose::launch_editor(
+                                            &tmp_path, &app.config.editor, &env_refs,
+                                        );
+                                    }
+                                    "u" | "U" | "upload" if upload_command.is_some() => {
+                                        let command = upload_command.unwrap_or_default();
+                                        let mut links = Vec::new();
+                                        for path in compose::attachment_paths(&msg_content) {
+                                            print!("Uploading {}...", path);
+                                            let _ = io::stdout().flush();
+                                            match compose::upload_attachment(&command, &path) {
+                                                Ok(link) => {
+                                                    println!(" {}", link);
+                                                    links.push((path, link));
+                                                }
+                                                Err(e) => println!(" {}", e),
+                                            }
+                                        }
+                                        let linked = compose::link_attachments(&msg_content, &links);
+                                        if let Err(e) = std::fs::write(&tmp_path, linked) {
+                                            println!("Could not update the message: {}", e);
+                                        }
+                                    }
+                                    _ => modified = false,
+                                }
+                            }
+
                             // Send while terminal is still in normal mode so that
                             // password_command (e.g. pass/gpg pinentry) can use the tty.
                             let send_result = if modified {
                                 if let Ok(msg_content) = std::fs::read_to_string(&tmp_path) {
+                                    let msg_content = compose::wrap_body(&msg_content, app.config.compose_wrap);
                                     if let Some(acct) = app.account() {
                                         use std::io::Write;
                                         print!("Sending...");
                                         let _ = io::stdout().flush();
                                         match send::send_message(&msg_content, &acct.smtp).await {
-                                            Ok(formatted) => {
+                                            Ok(sent) => {
+                                                oplog::append(&acct.name, &[OpRecord::for_delivery(&sent)]);
                                                 // Save to Sent maildir
-                                                if let Err(e) = save_to_sent(
+                                                match save_to_sent(
                                                     &acct.maildir,
                                                     &acct.folders.sent,
-                                                    &formatted,
+                                                    &sent.formatted,
                                                 ) {
-                                                    println!("\nWarning: sent but failed to save to Sent folder: {}", e);
+                                                    Ok(path) => Some(Ok((sent.status(), Some(path)))),
+                                                    Err(e) => {
+                                                        println!("\nWarning: sent but failed to save to Sent folder: {}", e);
+                                                        Some(Ok((sent.status(), None)))
+                                                    }
                                                 }
-                                                Some(Ok(()))
                                             }
                                             Err(e) => Some(Err(e)),
                                         }
@@ -3592,9 +8205,9 @@ pub async fn run(mut app: App) -> Result<()> {
                             terminal.clear()?;
 
                             match send_result {
-                                Some(Ok(())) => {
-                                    app.set_status("Message sent");
-                                    app.needs_reindex = true;
+                                Some(Ok((status, sent_path))) => {
+                                    app.set_status(status);
+                                    app.index_sent_copy(sent_path).await;
                                 }
                                 Some(Err(e)) => {
                                     app.set_status(format!("Send error: {}", e))
@@ -3617,6 +8230,7 @@ pub async fn run(mut app: App) -> Result<()> {
             terminal::disable_raw_mode()?;
             io::stdout().execute(LeaveAlternateScreen)?;
 
+            let started = std::time::SystemTime::now();
             let status = std::process::Command::new("sh")
                 .args(["-c", &pending.command])
                 .status();
@@ -3645,7 +8259,7 @@ pub async fn run(mut app: App) -> Result<()> {
             }
 
             if pending.reindex {
-                app.needs_reindex = true;
+                app.request_reindex_since(started);
             }
             continue;
         }
@@ -3653,10 +8267,13 @@ pub async fn run(mut app: App) -> Result<()> {
         // Handle key sequence timeout
         if app.keymap.has_pending() && last_key_time.elapsed() > sequence_timeout {
             app.keymap.cancel_pending();
+            app.redraw = true;
         }
 
         let timeout = if app.keymap.has_pending() {
             sequence_timeout
+        } else if app.low_power {
+            LOW_POWER_POLL
         } else {
             Duration::from_millis(100)
         };
@@ -3664,10 +8281,14 @@ pub async fn run(mut app: App) -> Result<()> {
         // Start server-side reindex if requested (non-blocking: we poll in the select loop)
         if app.needs_reindex && !app.indexing {
             app.needs_reindex = false;
-            debug_log!("reindex: sending (index) to mu server");
+            let lazy = std::mem::take(&mut app.lazy_reindex);
+            debug_log!("reindex: sending (index) to mu server (lazy={})", lazy);
             app.set_status("Reindexing...".to_string());
-            match app.mu.start_index().await {
-                Ok(()) => app.indexing = true,
+            match app.mu.start_index(lazy).await {
+                Ok(()) => {
+                    app.indexing = true;
+                    app.indexing_lazy = lazy;
+                }
                 Err(e) => {
                     debug_log!("reindex: start_index failed: {}", e);
                     app.set_status(format!("Reindex error: {}", e));
@@ -3676,18 +8297,10 @@ pub async fn run(mut app: App) -> Result<()> {
         }
 
         // Drain any pending IPC commands before blocking on input
-        while let Ok((cmd, mut stream)) = ipc_rx.try_recv() {
+        while let Ok((cmd, reply)) = ipc_rx.try_recv() {
             debug_log!("IPC drain: {:?}", cmd);
-            let resp = match app.handle_ipc_command(cmd).await {
-                Ok(resp) => resp,
-                Err(e) => {
-                    app.set_status(format!("IPC error: {}", e));
-                    IpcResponse::Error { message: e.to_string() }
-                }
-            };
-            if let Err(e) = links::send_response(&mut stream, &resp).await {
-                debug_log!("IPC response error: {}", e);
-            }
+            dispatch_ipc(&mut app, cmd, reply).await;
+            app.redraw = true;
         }
 
         // Background prefetch: run one queued query per loop iteration
@@ -3719,6 +8332,13 @@ pub async fn run(mut app: App) -> Result<()> {
                     match mu.find(&item.query, &opts).await {
                         Ok(mut envelopes) => {
                             let mu_returned = envelopes.len() as u32;
+                            // A full load stopped at max_results: count
+                            // every match for the top bar
+                            let total = if !is_partial && mu_returned >= item.max_num {
+                                Some(mu.count(&item.query, LOAD_ALL_LIMIT).await.unwrap_or(mu_returned))
+                            } else {
+                                None
+                            };
                             // Apply split exclusion for active account inbox
                             if item.account_idx == app.active_account {
                                 let is_inbox = app.account()
@@ -3740,6 +8360,12 @@ pub async fn run(mut app: App) -> Result<()> {
                                 app.config.accounts.get(item.account_idx).map(|a| a.name.as_str()).unwrap_or("?"),
                                 item.folder, envelopes.len(),
                                 if entry.is_full() { "full" } else { "partial" });
+                            if !is_partial {
+                                match total {
+                                    Some(total) => app.result_totals.insert(cache_key.clone(), total),
+                                    None => app.result_totals.remove(&cache_key),
+                                };
+                            }
                             app.folder_cache.insert(cache_key, entry);
 
                             // If this was a full load for the folder the user is
@@ -3751,7 +8377,12 @@ pub async fn run(mut app: App) -> Result<()> {
                                 // Preserve selection by message-id
                                 let selected_msgid = app.preview_envelope()
                                     .map(|e| e.message_id.clone());
+                                if let Some(held) = &app.paused {
+                                    envelopes.retain(|e| !held.contains(&e.message_id));
+                                }
                                 app.envelopes = envelopes;
+                                app.collapsed.clear();
+                                app.score_envelopes();
                                 app.apply_sort();
                                 // Restore selection
                                 if let Some(ref mid) = selected_msgid {
@@ -3767,6 +8398,8 @@ pub async fn run(mut app: App) -> Result<()> {
                                     }
                                     // else: message no longer in results, keep selected=0
                                 }
+                                app.offer_load_all();
+                                app.redraw = true;
                                 debug_log!("prefetch: live-swapped current folder {:?} ({} envelopes)",
                                     item.folder, app.envelopes.len());
                             }
@@ -3779,44 +8412,131 @@ pub async fn run(mut app: App) -> Result<()> {
             }
         }
 
+        // Background counts: once prefetching is done, the status file's
+        // unread counts and the smart folders due a recount start together
+        // as a batch, when the count scheduler allows one. A paused inbox
+        // isn't counted, and neither is anything in low-power mode, so
+        // the status is written straight away.
+        let now = Instant::now();
+        if app.paused.is_some() || app.low_power {
+            if app.status_dirty && !app.indexing {
+                app.write_status_file().await;
+            }
+        } else if app.prefetch_queue.is_empty() && app.count_scheduler.due(now, app.indexing) {
+            app.queue_smart_counts();
+            if app.status_dirty || !app.smart_count_queue.is_empty() {
+                app.count_scheduler.started(now);
+            }
+            if app.status_dirty {
+                app.write_status_file().await;
+            }
+        }
+
+        // The batch's smart folder counts: one coalesced count query per
+        // idle iteration.
+        if !app.smart_count_queue.is_empty()
+            && app.prefetch_queue.is_empty()
+            && !app.indexing
+            && app.paused.is_none()
+            && !app.low_power
+        {
+            let (query, keys) = app.smart_count_queue.remove(0);
+            match app.mu.count(&query, FindOpts::default().max_num).await {
+                Ok(count) => {
+                    for key in keys {
+                        app.smart_counts.insert(key, count);
+                    }
+                    app.redraw = true;
+                }
+                Err(e) => {
+                    debug_log!("smart counts: error for {:?}: {}", keys, e);
+                }
+            }
+        }
+
+        // The day's triage summary, when someone's there to see it
+        if !app.indexing
+            && app.mode == InputMode::Normal
+            && last_interaction_time.elapsed() < Duration::from_secs(60)
+        {
+            app.show_triage_summary().await;
+        }
+
+        // Searches and filtered lists drop messages a flag change took
+        // out of them, once the toggling stops
+        if app.view_refresh_at.is_some_and(|t| Instant::now() >= t)
+            && !app.indexing
+            && app.mode == InputMode::Normal
+        {
+            if let Err(e) = app.refresh_view().await {
+                app.set_status(format!("Error: {}", e));
+            }
+            app.redraw = true;
+        }
+
         // Multiplex keyboard events and IPC commands
         let event = tokio::select! {
             ev = event_stream.next() => ev.and_then(|r| r.ok()),
             cmd = ipc_rx.recv() => {
-                if let Some((cmd, mut stream)) = cmd {
+                if let Some((cmd, reply)) = cmd {
                     debug_log!("IPC select: {:?}", cmd);
-                    let resp = match app.handle_ipc_command(cmd).await {
-                        Ok(resp) => resp,
-                        Err(e) => {
-                            app.set_status(format!("IPC error: {}", e));
-                            IpcResponse::Error { message: e.to_string() }
-                        }
-                    };
-                    if let Err(e) = links::send_response(&mut stream, &resp).await {
-                        debug_log!("IPC response error: {}", e);
-                    }
+                    dispatch_ipc(&mut app, cmd, reply).await;
                 }
+                app.redraw = true;
                 continue;
             }
             index_frame = app.mu.poll_index_frame(), if app.indexing => {
+                app.redraw = true;
                 match index_frame {
                     Ok(true) => {
                         // Index complete — reload folder
                         app.indexing = false;
                         debug_log!("reindex: complete, reloading folder");
+                        let triage_notice = app
+                            .replay_deferred_triage()
+                            .await
+                            .map(|notice| format!("Reindex complete; {}", notice));
+                        let screen_notice = app.screen_inbox().await;
                         app.invalidate_folder_cache();
+                        app.refresh_fetched_bodies();
                         // Refresh split caches before reloading so inbox
                         // exclusions are up to date.
                         app.refresh_split_caches().await;
+                        // Paused: no reminders, and new mail is held back
+                        let followup_notice = match app.paused {
+                            Some(_) => None,
+                            None => app.check_followups().await,
+                        };
+                        let query = app.current_query.clone();
+                        let before: HashSet<String> =
+                            app.envelopes.iter().map(|e| e.message_id.clone()).collect();
                         if let Err(e) = app.load_folder().await {
                             debug_log!("reindex: reload error: {}", e);
                         }
-                        app.set_status("Reindex complete".to_string());
+                        if app.current_query == query {
+                            app.hold_arrivals(&before);
+                            app.mark_new_arrivals(&before);
+                        }
+                        let conflicts = flag_conflicts(&app.local_flags, &app.envelopes);
+                        if conflicts.is_empty() {
+                            app.set_status(
+                                triage_notice
+                                    .or(screen_notice)
+                                    .or(followup_notice)
+                                    .unwrap_or_else(|| "Reindex complete".to_string()),
+                            );
+                        } else {
+                            app.set_status(format!(
+                                "Sync reverted {} local flag change(s) \u{2014} reapply? (y/n)",
+                                conflicts.len()
+                            ));
+                            app.pending_confirm = Some(ConfirmAction::ReapplyFlags(conflicts));
+                        }
 
                         // Reindex background accounts' mu databases.
                         // These run out-of-process (not via mu server protocol)
                         // so they don't interfere with the running mu servers.
-                        for (idx, _) in &app.background_mu {
+                        for idx in app.background_mu.keys() {
                             if let Some(muhome) = app.config.effective_muhome(*idx) {
                                 let muhome = muhome.clone();
                                 tokio::spawn(async move {
@@ -3837,18 +8557,28 @@ pub async fn run(mut app: App) -> Result<()> {
                         }
                     }
                     Ok(false) => {} // progress update, keep polling
+                    Err(e) if app.indexing_lazy => {
+                        // Older mu without :lazy-check — fall back to a full index
+                        app.indexing = false;
+                        app.indexing_lazy = false;
+                        debug_log!("reindex: lazy index failed, retrying full: {}", e);
+                        app.needs_reindex = true;
+                    }
                     Err(e) => {
                         app.indexing = false;
                         debug_log!("reindex: error: {}", e);
+                        app.replay_deferred_triage().await;
                         app.set_status(format!("Reindex error: {}", e));
                     }
                 }
                 continue;
             }
             result = shell_rx.recv() => {
+                app.redraw = true;
                 if let Some(result) = result {
                     match result {
                         Ok(r) => {
+                            app.command_finished(&r.command);
                             debug_log!("shell[{}]: exit={}", r.command, r.status);
                             for line in r.stdout.lines() {
                                 debug_log!("shell[{}] stdout: {}", r.command, line);
@@ -3859,9 +8589,26 @@ pub async fn run(mut app: App) -> Result<()> {
                             let last_line = r.stderr.lines().last()
                                 .or_else(|| r.stdout.lines().last())
                                 .unwrap_or("");
-                            if r.status.success() {
+                            if r.status.success() && app.sync_command().as_ref() == Some(&r.command) {
+                                app.last_sync = Some(chrono::Local::now());
+                            }
+                            if let (Some((key, count)), true) = (&r.summary_of, r.status.success()) {
+                                let lines: Vec<String> = r.stdout.lines().map(str::to_string).collect();
+                                app.summaries.insert(key.clone(), (*count, lines.clone()));
+                                if matches!(app.mode, InputMode::Normal | InputMode::ThreadView) {
+                                    app.overlay_lines = lines;
+                                    app.help_scroll = 0;
+                                    app.mode = InputMode::Summary;
+                                } else {
+                                    app.set_status("Summary ready \u{2014} Summarize Thread shows it");
+                                }
+                            } else if r.show_output && matches!(app.mode, InputMode::Normal | InputMode::ThreadView) {
+                                app.overlay_lines = command_output_lines(&r);
+                                app.help_scroll = 0;
+                                app.mode = InputMode::CommandOutput;
+                            } else if r.status.success() {
                                 if r.reindex {
-                                    app.needs_reindex = true;
+                                    app.request_reindex_since(r.started);
                                 }
                                 if last_line.is_empty() {
                                     app.set_status(format!("Done: {}", r.command));
@@ -3875,6 +8622,7 @@ pub async fn run(mut app: App) -> Result<()> {
                             }
                         }
                         Err(e) => {
+                            app.command_finished(&e.command);
                             debug_log!("shell[{}]: error={}", e.command, e.error);
                             app.set_status(format!("Failed: {}", e.error));
                         }
@@ -3882,11 +8630,47 @@ pub async fn run(mut app: App) -> Result<()> {
                 }
                 continue;
             }
+            extracted = snippet_rx.recv() => {
+                if let Some((message_id, snippet)) = extracted {
+                    app.snippets_pending.remove(&message_id);
+                    app.snippets.insert(message_id, snippet);
+                    app.snippets_dirty = true;
+                    app.redraw = true;
+                }
+                continue;
+            }
+            scored = score_rx.recv() => {
+                if let Some((message_id, score)) = scored {
+                    app.score_arrived(message_id, score);
+                    app.redraw = true;
+                }
+                continue;
+            }
+            rendered = thread_render_rx.recv() => {
+                if let Some(rendered) = rendered {
+                    app.thread_body_rendered(rendered);
+                }
+                continue;
+            }
+            scanned = folder_scan_rx.recv() => {
+                if let Some(scan) = scanned {
+                    app.folders_scanned(scan);
+                    app.redraw = true;
+                }
+                continue;
+            }
+            listed = attachment_list_rx.recv() => {
+                if let Some(items) = listed {
+                    app.attachments_listed(items);
+                }
+                continue;
+            }
             _ = tokio::time::sleep(timeout) => None,
         };
 
         // Reset idle timer on any user interaction
         if event.is_some() {
+            app.redraw = true;
             last_interaction_time = Instant::now();
             last_auto_sync_time = None; // reset cycle on interaction
         }
@@ -3900,9 +8684,10 @@ pub async fn run(mut app: App) -> Result<()> {
             let idle_for = last_interaction_time.elapsed();
 
             if idle_for >= idle_threshold
+                && !app.low_power
                 && !app.indexing
                 && app.shell_pending.is_none()
-                && app.config.effective_sync_command(app.active_account).is_some()
+                && app.sync_command().is_some()
             {
                 let should_sync = match last_auto_sync_time {
                     None => true,
@@ -3930,6 +8715,11 @@ pub async fn run(mut app: App) -> Result<()> {
                 continue;
             }
 
+            // Attachment browser is keyboard-only
+            if app.mode == InputMode::AttachmentBrowser {
+                continue;
+            }
+
             // Attachment popup: click on Open/Save or dismiss
             if app.mode == InputMode::AttachmentPopup {
                 if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
@@ -3971,7 +8761,8 @@ pub async fn run(mut app: App) -> Result<()> {
             if app.mode == InputMode::Normal || app.mode == InputMode::Search {
                 let size = terminal.size()?;
                 let border_col = (size.width as u32 * app.list_pct as u32 / 100) as u16;
-                let in_content = mouse.row > 0 && mouse.row < size.height.saturating_sub(1);
+                let bottom_bar = size.height.saturating_sub(1 + app.banner_height(size.height));
+                let in_content = mouse.row > 0 && mouse.row < bottom_bar;
                 let on_tab_bar = mouse.row == 0;
 
                 match mouse.kind {
@@ -3995,6 +8786,11 @@ pub async fn run(mut app: App) -> Result<()> {
                                             }
                                         }
                                     }
+                                    TabRegionKind::View(i) => {
+                                        if let Err(e) = app.switch_view(*i).await {
+                                            app.set_status(format!("Error: {}", e));
+                                        }
+                                    }
                                     TabRegionKind::Overflow => {
                                         app.folder_filter.clear();
                                         app.folder_selected = 0;
@@ -4012,10 +8808,12 @@ pub async fn run(mut app: App) -> Result<()> {
                         } else if mouse.column > border_col + 1 {
                             // Click in preview pane — check for links
                             let preview_x = border_col + 2; // left border + padding
-                            let header_lines = 5u16; // Subject, From, To, Date, separator
                             let msg_id = app.preview_envelope().map(|e| e.message_id.clone());
                             if let Some(msg_id) = msg_id {
                                 if let Some(rendered) = app.preview_cache.get(&msg_id, preview_width) {
+                                    let header_lines = app.preview_envelope()
+                                        .map(|e| preview::header_lines(e, Some(rendered), app.preview_headers_expanded, app.config.sender_badges, app.note(e)).len())
+                                        .unwrap_or(0) as u16;
                                     let content_row = (mouse.row.saturating_sub(1)) + app.preview_scroll;
                                     if content_row >= header_lines {
                                         let body_line = (content_row - header_lines) as usize;
@@ -4056,9 +8854,14 @@ pub async fn run(mut app: App) -> Result<()> {
                     let mut row_counter = 2usize; // header + blank line
                     'thread_click: for (idx, msg) in app.thread_messages.iter().enumerate() {
                         if idx > 0 { row_counter += 1; } // separator
-                        row_counter += 1; // message header line
 
-                        if msg.expanded {
+                        if !msg.expanded {
+                            row_counter += 1; // summary line
+                        } else {
+                            row_counter += thread_view::expanded_header_rows(
+                                &msg.envelope,
+                                &app.config.thread_view.expanded,
+                            );
                             if let Some(ref body) = msg.body {
                                 let body_start = row_counter;
                                 let body_end = body_start + body.lines.len();
@@ -4092,6 +8895,18 @@ pub async fn run(mut app: App) -> Result<()> {
 
             // Confirmation prompt: y confirms, anything else cancels
             if let Some(confirm) = app.pending_confirm.take() {
+                if let ConfirmAction::SweepSender(docids) = confirm {
+                    if let Err(e) = app.finish_sweep(key.code, docids).await {
+                        app.set_status(format!("Error: {}", e));
+                    }
+                    continue;
+                }
+                if let ConfirmAction::MapFolder { role, folder } = confirm {
+                    if let Err(e) = app.finish_folder_mapping(key.code, &role, folder).await {
+                        app.set_status(format!("Error: {}", e));
+                    }
+                    continue;
+                }
                 match key.code {
                     crossterm::event::KeyCode::Char('y') | crossterm::event::KeyCode::Char('Y') => {
                         match confirm {
@@ -4100,10 +8915,25 @@ pub async fn run(mut app: App) -> Result<()> {
                                     app.set_status(format!("Error: {}", e));
                                 }
                             }
+                            ConfirmAction::ReapplyFlags(changes) => {
+                                app.reapply_flags(&changes).await;
+                            }
+                            ConfirmAction::SendMessage(content) => {
+                                app.send_composed(&content).await;
+                            }
+                            ConfirmAction::SweepSender(_) | ConfirmAction::MapFolder { .. } => {}
+                            ConfirmAction::Quit => app.should_quit = true,
+                            ConfirmAction::LoadAll(query) => app.load_all_results(query),
                         }
                     }
                     _ => {
-                        app.set_status("Cancelled".to_string());
+                        if let ConfirmAction::ReapplyFlags(changes) = confirm {
+                            // Accept the synced state: stop tracking these
+                            app.forget_local_flags(&changes);
+                            app.set_status("Kept synced flags".to_string());
+                        } else {
+                            app.set_status("Cancelled".to_string());
+                        }
                     }
                 }
                 continue;
@@ -4582,6 +9412,7 @@ pub async fn run(mut app: App) -> Result<()> {
                         crossterm::event::KeyCode::Char('f') => Some(SortField::From),
                         crossterm::event::KeyCode::Char('s') => Some(SortField::Subject),
                         crossterm::event::KeyCode::Char('t') => Some(SortField::To),
+                        crossterm::event::KeyCode::Char('c') => Some(SortField::Score),
                         crossterm::event::KeyCode::Esc => {
                             app.mode = InputMode::Normal;
                             app.set_status("".to_string());
@@ -4595,10 +9426,11 @@ pub async fn run(mut app: App) -> Result<()> {
                             app.sort_descending = !app.sort_descending;
                         } else {
                             app.sort_field = field;
-                            // Default direction: date descending, others ascending
-                            app.sort_descending = field == SortField::Date;
+                            // Default direction: date and score descending, others ascending
+                            app.sort_descending = matches!(field, SortField::Date | SortField::Score);
                         }
                         app.mode = InputMode::Normal;
+                        app.unhide_sections();
                         app.apply_sort();
                         app.set_status(format!("Sort: {}", app.sort_label()));
                     }
@@ -4633,6 +9465,152 @@ pub async fn run(mut app: App) -> Result<()> {
                         _ => { continue; }
                     }
                 }
+                InputMode::AttachmentBrowser => {
+                    use crossterm::event::KeyCode;
+                    let count = app.attachment_items.len();
+                    match key.code {
+                        KeyCode::Down | KeyCode::Char('j') if app.attachment_selected + 1 < count => {
+                            app.attachment_selected += 1;
+                        }
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            app.attachment_selected = app.attachment_selected.saturating_sub(1);
+                        }
+                        KeyCode::Enter | KeyCode::Char('o') | KeyCode::Char('s') => {
+                            if let Some(item) = app.attachment_items.get(app.attachment_selected) {
+                                let path = item.path.clone();
+                                let content_id = item.attachment.content_id.clone();
+                                if key.code == KeyCode::Char('s') {
+                                    app.save_attachment_at(&path, &content_id);
+                                } else {
+                                    app.open_attachment_at(&path, &content_id);
+                                }
+                            }
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => {
+                            app.attachment_items.clear();
+                            app.mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                InputMode::RecipientCheck => {
+                    use crossterm::event::KeyCode;
+                    let Some(check) = app.recipient_check.as_mut() else {
+                        app.mode = InputMode::Normal;
+                        continue;
+                    };
+                    match key.code {
+                        KeyCode::Down | KeyCode::Char('j') if check.selected + 1 < check.recipients.len() => {
+                            check.selected += 1;
+                        }
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            check.selected = check.selected.saturating_sub(1);
+                        }
+                        KeyCode::Char(' ') | KeyCode::Char('x') => check.toggle(),
+                        KeyCode::Enter if check.kept() == 0 => {
+                            app.set_status("Keep at least one recipient");
+                        }
+                        KeyCode::Enter => {
+                            if let Some(check) = app.recipient_check.take() {
+                                app.mode = check.from.clone();
+                                app.compose_pending =
+                                    Some(compose::ComposePending::Ready(Box::new(check.confirmed())));
+                            }
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => {
+                            if let Some(check) = app.recipient_check.take() {
+                                app.mode = check.from;
+                            }
+                            app.set_status("Reply all cancelled");
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                InputMode::HeaderMenu => {
+                    use crossterm::event::KeyCode;
+                    let action = match key.code {
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            if app.header_menu_selected + 1 < app.header_menu.len() {
+                                app.header_menu_selected += 1;
+                            }
+                            None
+                        }
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            app.header_menu_selected = app.header_menu_selected.saturating_sub(1);
+                            None
+                        }
+                        KeyCode::Enter | KeyCode::Char('/') => Some('/'),
+                        KeyCode::Char(c @ ('a' | 'c' | 'r' | 'y')) => Some(c),
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('h') => {
+                            app.mode = app.header_menu_from.clone();
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(action) = action {
+                        if let Err(e) = app.header_menu_action(action).await {
+                            app.set_status(format!("Error: {}", e));
+                        }
+                    }
+                    continue;
+                }
+                InputMode::RecentlyDeleted => {
+                    use crossterm::event::KeyCode;
+                    match key.code {
+                        KeyCode::Down | KeyCode::Char('j') if app.deleted_selected + 1 < app.deleted.len() => {
+                            app.deleted_selected += 1;
+                        }
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            app.deleted_selected = app.deleted_selected.saturating_sub(1);
+                        }
+                        KeyCode::Enter | KeyCode::Char('r') => {
+                            if let Err(e) = app.restore_deleted().await {
+                                app.set_status(format!("Error: {}", e));
+                            }
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => {
+                            app.deleted.clear();
+                            app.mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                InputMode::TriageLoop => {
+                    use crossterm::event::KeyCode;
+                    let step = match key.code {
+                        KeyCode::Char('a' | 'e') => Some('a'),
+                        KeyCode::Char('d' | '#') => Some('d'),
+                        KeyCode::Char('k') => Some('k'),
+                        KeyCode::Char('s' | 'n') => Some('s'),
+                        KeyCode::Esc | KeyCode::Char('q') => Some('q'),
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            app.preview_scroll = app.preview_scroll.saturating_add(5);
+                            None
+                        }
+                        KeyCode::Up => {
+                            app.preview_scroll = app.preview_scroll.saturating_sub(5);
+                            None
+                        }
+                        KeyCode::Char(' ') | KeyCode::PageDown => {
+                            app.preview_scroll = app.preview_scroll.saturating_add(20);
+                            None
+                        }
+                        KeyCode::PageUp | KeyCode::Backspace => {
+                            app.preview_scroll = app.preview_scroll.saturating_sub(20);
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(step) = step {
+                        if let Err(e) = app.triage_loop_step(step).await {
+                            app.set_status(format!("Error: {}", e));
+                        }
+                    }
+                    continue;
+                }
                 InputMode::AttachmentPopup => {
                     match key.code {
                         crossterm::event::KeyCode::Down | crossterm::event::KeyCode::Char('j') => {
@@ -4671,6 +9649,20 @@ pub async fn run(mut app: App) -> Result<()> {
                 _ => {}
             }
 
+            // Describe Key: say what the key does instead of doing it
+            if app.describing_key {
+                if let Some(description) = app.keymap.describe(key, &app.mode) {
+                    app.describing_key = false;
+                    let label = app
+                        .palette_entries
+                        .iter()
+                        .find(|e| e.action == description.action)
+                        .map(|e| format!("{}: {}", e.name, e.description));
+                    app.set_status(description.line(label.as_deref()));
+                }
+                continue;
+            }
+
             let action = app.keymap.handle(key, &app.mode);
             if action == Action::Redraw {
                 terminal.clear()?;
@@ -4693,6 +9685,407 @@ pub async fn run(mut app: App) -> Result<()> {
     Ok(())
 }
 
+/// Draw the UI before the mu server is up, so startup doesn't sit on a
+/// blank screen: the list saved by a previous run if there is one for
+/// `view`, else an empty list. `run` takes the screen over once the real
+/// folder has loaded.
+pub fn draw_startup(
+    config: &Config,
+    account_name: &str,
+    view: &ViewState,
+    warm: Option<&WarmStart>,
+) -> Result<()> {
+    terminal::enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    let mut terminal = Terminal::new(backend)?;
+    terminal.clear()?;
+
+    let envelopes = warm.map(|w| w.envelopes.as_slice()).unwrap_or_default();
+    let conversations_mode = warm.map_or(config.conversations, |w| w.conversations);
+    let conversations = if conversations_mode {
+        group_into_conversations(envelopes)
+    } else {
+        Vec::new()
+    };
+    let count = if conversations_mode { conversations.len() } else { envelopes.len() };
+    let selected = view.selected.min(count.saturating_sub(1));
+    let offset = view.scroll_offset.min(selected);
+    let unread = envelopes.iter().filter(|e| e.is_unread()).count();
+    let flag_column = FlagColumn::from_config(&config.flag_glyphs)
+        .with_keywords(&config.flag_glyphs, &config.keywords());
+    let snippets = snippets::load_snippets(account_name);
+    let none = HashSet::new();
+
+    terminal.draw(|frame| {
+        let outer = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .split(frame.area());
+
+        let top = TopBar {
+            folder: &view.folder,
+            unread_count: unread,
+            total_count: count,
+            mode: &InputMode::Normal,
+            thread_subject: None,
+            account_name: (config.accounts.len() > 1).then_some(account_name),
+            conversations_mode,
+            tabs: &[],
+            tab_scroll: 0,
+            multi_account: config.accounts.len() > 1,
+            view_count: 1,
+            active_view: 0,
+            paused: false,
+            truncated: None,
+            low_power: false,
+        };
+        top.render_with_regions(outer[0], frame.buffer_mut());
+
+        let content = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(DEFAULT_LIST_PCT),
+                Constraint::Percentage(100 - DEFAULT_LIST_PCT),
+            ])
+            .split(outer[1]);
+        let list_area = content[0];
+        if envelopes.is_empty() {
+            use ratatui::style::{Color, Style};
+            frame.buffer_mut().set_string(
+                list_area.x + 2,
+                list_area.y + list_area.height / 2,
+                "Connecting to mu\u{2026}",
+                Style::default().fg(Color::DarkGray),
+            );
+        } else if conversations_mode {
+            let list = ConversationList {
+                conversations: &conversations,
+                selected,
+                offset,
+                multi_selected: &none,
+                new_arrivals: &none,
+                density: config.list_density,
+                snippets: snippets.as_map(),
+                color_senders: config.color_senders,
+                flag_column: &flag_column,
+                show_size: false,
+                scores: None,
+                aging: None,
+                badge: None,
+            };
+            frame.render_widget(list, list_area);
+        } else {
+            let list = EnvelopeList {
+                envelopes,
+                selected,
+                offset,
+                multi_selected: &none,
+                new_arrivals: &none,
+                density: config.list_density,
+                snippets: snippets.as_map(),
+                color_senders: config.color_senders,
+                flag_column: &flag_column,
+                show_size: false,
+                scores: None,
+                sections: &[],
+                aging: None,
+                collapsed: &HashMap::new(),
+                badge: None,
+            };
+            frame.render_widget(list, list_area);
+        }
+
+        let bottom = BottomBar {
+            mode: &InputMode::Normal,
+            pending_key: None,
+            status_message: Some("Connecting to mu\u{2026}"),
+            filter_desc: None,
+            selection_count: 0,
+            selection_size: 0,
+            conversations_mode,
+            sort_label: None,
+            hints: None,
+        };
+        frame.render_widget(bottom, outer[2]);
+    })?;
+    Ok(())
+}
+
+/// Hand the terminal back to the shell for a moment, e.g. for a send whose
+/// password_command may prompt on the tty.
+fn suspend_terminal() {
+    let _ = io::stdout().execute(crossterm::event::PopKeyboardEnhancementFlags);
+    let _ = io::stdout().execute(crossterm::event::DisableMouseCapture);
+    let _ = terminal::disable_raw_mode();
+    let _ = io::stdout().execute(LeaveAlternateScreen);
+}
+
+/// Take the terminal back after [`suspend_terminal`]. The caller sets
+/// `needs_clear` so the next draw repaints everything.
+fn resume_terminal() {
+    let _ = terminal::enable_raw_mode();
+    let _ = io::stdout().execute(EnterAlternateScreen);
+    let _ = io::stdout().execute(crossterm::event::EnableMouseCapture);
+    let _ = io::stdout().execute(crossterm::event::PushKeyboardEnhancementFlags(
+        crossterm::event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+    ));
+}
+
+/// Give the terminal back after `draw_startup` if startup fails.
+pub fn leave_startup() {
+    let _ = terminal::disable_raw_mode();
+    let _ = io::stdout().execute(LeaveAlternateScreen);
+}
+
+/// Show a running find's progress on the bottom line, drawn directly since
+/// the main loop is waiting on the find. Returns false if Esc was pressed.
+fn show_find_progress(progress: &FindProgress) -> bool {
+    if esc_pressed() {
+        return false;
+    }
+    write_bottom_line(&format!(
+        " Searching\u{2026} {:.1}s, {} received | Esc:cancel",
+        progress.elapsed.as_secs_f32(),
+        progress.received
+    ));
+    true
+}
+
+/// Whether `Esc` was pressed, for loops that block the main loop. Other
+/// keys typed meanwhile are dropped.
+fn esc_pressed() -> bool {
+    use crossterm::event::{self, KeyCode};
+
+    while event::poll(Duration::ZERO).unwrap_or(false) {
+        if let Ok(Event::Key(key)) = event::read() {
+            if key.kind == KeyEventKind::Press && key.code == KeyCode::Esc {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Overwrite the terminal's bottom line, for progress shown while the main
+/// loop is blocked.
+fn write_bottom_line(text: &str) {
+    use crossterm::{cursor::MoveTo, style::Print, terminal::Clear, terminal::ClearType, QueueableCommand};
+    use std::io::Write;
+
+    let (_, rows) = terminal::size().unwrap_or((80, 24));
+    let mut out = io::stdout();
+    let _ = out
+        .queue(MoveTo(0, rows.saturating_sub(1)))
+        .and_then(|o| o.queue(Clear(ClearType::CurrentLine)))
+        .and_then(|o| o.queue(Print(text)))
+        .and_then(|o| o.flush());
+}
+
+/// On quit, give a running reindex and background commands up to
+/// [`QUIT_GRACE`] to finish, so mu's index isn't left half-updated.
+/// Triage still held back after that is saved by the caller.
+async fn finish_background_work(
+    app: &mut App,
+    shell_rx: &mut tokio::sync::mpsc::UnboundedReceiver<Result<ShellResult, ShellError>>,
+) {
+    let deadline = Instant::now() + QUIT_GRACE;
+    while let Some(work) = app.background_work() {
+        write_bottom_line(&format!(" Waiting for {} to finish\u{2026}", work));
+        tokio::select! {
+            frame = app.mu.poll_index_frame(), if app.indexing => {
+                if !matches!(frame, Ok(false)) {
+                    app.indexing = false;
+                    app.replay_deferred_triage().await;
+                }
+            }
+            result = shell_rx.recv(), if !app.running_commands.is_empty() => match result {
+                Some(Ok(r)) => app.command_finished(&r.command),
+                Some(Err(e)) => app.command_finished(&e.command),
+                None => app.running_commands.clear(),
+            },
+            _ = tokio::time::sleep_until(deadline) => {
+                debug_log!("quit: gave up waiting for {}", work);
+                break;
+            }
+        }
+    }
+}
+
+/// Run `command` with `input` on its stdin, collecting its output.
+async fn pipe_to_command(command: &str, input: Vec<u8>) -> std::io::Result<std::process::Output> {
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = tokio::process::Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // Write while the output is read, so a chatty command can't block us
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+    });
+    let output = child.wait_with_output().await?;
+    let _ = writer.await;
+    Ok(output)
+}
+
+/// A finished command's output as popup lines: the command as a heading,
+/// its stdout, its stderr, then its exit status if it failed.
+fn command_output_lines(result: &ShellResult) -> Vec<String> {
+    let mut lines = vec![format!("$ {}", result.command)];
+    let indented = |text: &str| text.lines().map(|l| format!("  {}", l)).collect::<Vec<_>>();
+    lines.extend(indented(&result.stdout));
+    if !result.stderr.trim().is_empty() {
+        lines.push(String::new());
+        lines.push("stderr".to_string());
+        lines.extend(indented(&result.stderr));
+    }
+    if !result.status.success() {
+        lines.push(String::new());
+        lines.push(format!("Exited with {}", result.status));
+    }
+    lines
+}
+
+/// What a shell binding would do, for dry-run mode: the command, how it
+/// would run, and the environment variables it refers to, looked up with
+/// `env`.
+fn shell_dry_run_lines(
+    command: &str,
+    reindex: bool,
+    suspend: bool,
+    cwd: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    let how = if suspend { "suspending hutt for the terminal" } else { "in the background" };
+    let mut lines = vec![
+        format!("$ {}", command),
+        String::new(),
+        format!("Runs with sh -c, {}", how),
+        format!("Reindexes afterwards: {}", if reindex { "yes" } else { "no" }),
+        format!("Working directory: {}", cwd),
+    ];
+    let vars = shell_variables(command);
+    if !vars.is_empty() {
+        lines.push(String::new());
+        lines.push("Environment".to_string());
+        for name in vars {
+            match env(&name) {
+                Some(value) => lines.push(format!("  {}={}", name, value)),
+                None => lines.push(format!("  {} is unset", name)),
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.push("Dry run: nothing was run (toggle with \"Shell Dry Run\")".to_string());
+    lines
+}
+
+/// `command` with each `{name}` in `values` replaced by its value. Other
+/// braces are left alone. A placeholder whose value is `None` is an error
+/// naming it.
+fn fill_placeholders(command: &str, values: &[(&str, Option<String>)]) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let known = rest.find('}').and_then(|close| {
+            let name = &rest[1..close];
+            values.iter().find(|(n, _)| *n == name).map(|(_, value)| (close, name, value))
+        });
+        match known {
+            Some((close, _, Some(value))) => {
+                out.push_str(value);
+                rest = &rest[close + 1..];
+            }
+            Some((_, name, None)) => return Err(name.to_string()),
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Names of the `$NAME` and `${NAME}` variables in a shell command, in
+/// order of first use.
+fn shell_variables(command: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = command;
+    while let Some(i) = rest.find('$') {
+        rest = &rest[i + 1..];
+        let inner = rest.strip_prefix('{').unwrap_or(rest);
+        let len = inner
+            .char_indices()
+            .find(|&(j, c)| !(c == '_' || c.is_ascii_alphabetic() || (j > 0 && c.is_ascii_digit())))
+            .map_or(inner.len(), |(j, _)| j);
+        let name = &inner[..len];
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// A thread as plain text for summarize_command: its subject, then each
+/// message's sender, date and rendered body, oldest first.
+fn thread_text(envelopes: &[Envelope]) -> String {
+    let mut out = String::new();
+    if let Some(first) = envelopes.first() {
+        out.push_str(&format!("Subject: {}\n", first.subject));
+    }
+    for (i, e) in envelopes.iter().enumerate() {
+        let from = e.from.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ");
+        let body = mime_render::render_message(&e.path, &e.message_id, 80, None)
+            .map(|rm| rm.to_plain_text())
+            .unwrap_or_default();
+        out.push_str(&format!("\n--- Message {} of {} ---\n", i + 1, envelopes.len()));
+        out.push_str(&format!("From: {}\n", from));
+        out.push_str(&format!(
+            "Date: {}\n\n",
+            e.date.with_timezone(&chrono::Local).format("%a, %-d %b %Y %H:%M")
+        ));
+        out.push_str(body.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Docids of the messages in `envelopes` whose Message-IDs aren't in `before`.
+fn new_docids(envelopes: &[Envelope], before: &HashSet<String>) -> HashSet<u32> {
+    envelopes
+        .iter()
+        .filter(|e| !before.contains(&e.message_id))
+        .map(|e| e.docid)
+        .collect()
+}
+
+/// Docids of the messages in `envelopes` sent by `email`.
+fn sweep_docids(envelopes: &[Envelope], email: &str) -> HashSet<u32> {
+    envelopes
+        .iter()
+        .filter(|e| e.from.first().is_some_and(|a| a.email.eq_ignore_ascii_case(email)))
+        .map(|e| e.docid)
+        .collect()
+}
+
+/// Expand relative-date placeholders (`{today}`, `{last7days}`, ...) in a
+/// query against the current local date.
+fn expand_dates(query: &str) -> String {
+    smart_folders::expand_date_macros(query, chrono::Local::now().date_naive())
+}
+
 /// Expand `#split` and `@smart` folder references in a query string.
 ///
 /// - `#name` → `(maildir:<inbox> AND (<split_query>))`
@@ -4742,6 +10135,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn needs_reply_terms_add_configured_query() {
+        assert_eq!(needs_reply_terms(None, "me@example.com"), "NOT flag:replied");
+        assert_eq!(needs_reply_terms(Some(" "), "me@example.com"), "NOT flag:replied");
+        assert_eq!(
+            needs_reply_terms(Some("to:{email} AND NOT flag:list"), "me@example.com"),
+            "NOT flag:replied AND (to:me@example.com AND NOT flag:list)"
+        );
+    }
+
+    #[test]
+    fn path_term_quotes_flag_suffix() {
+        assert_eq!(
+            path_term("/home/me/Maildir/INBOX/cur/1700000000.1_2.host:2,S"),
+            "path:\"/home/me/Maildir/INBOX/cur/1700000000.1_2.host:2,S\""
+        );
+        assert_eq!(
+            path_term("/home/me/Maildir/say \"hi\"/cur/1:2,S"),
+            "path:\"/home/me/Maildir/say \\\"hi\\\"/cur/1:2,S\""
+        );
+    }
+
     #[test]
     fn expand_split_reference() {
         let mut splits = HashMap::new();
@@ -4811,4 +10226,198 @@ mod tests {
             "(maildir:/INBOX AND (subject:alert)) OR (flag:flagged)"
         );
     }
+
+    #[test]
+    fn cycle_step_wraps_and_enters_list() {
+        let list: Vec<String> = ["/Inbox", "@Today", "/Lists"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(cycle_step(&list, "/Inbox", 1).as_deref(), Some("@Today"));
+        assert_eq!(cycle_step(&list, "/Lists", 1).as_deref(), Some("/Inbox"));
+        assert_eq!(cycle_step(&list, "/Inbox", -1).as_deref(), Some("/Lists"));
+        assert_eq!(cycle_step(&list, "/Archive", 1).as_deref(), Some("/Inbox"));
+        assert_eq!(cycle_step(&list, "/Archive", -1).as_deref(), Some("/Lists"));
+        assert_eq!(cycle_step(&[], "/Inbox", 1), None);
+    }
+
+    #[test]
+    fn folder_filter_matches_bare_names() {
+        assert!(folder_matches("/Archive", ""));
+        assert!(folder_matches("/Archive", "arch"));
+        assert!(folder_matches("@Today", "tod"));
+        assert!(folder_matches("#GitHub", "github"));
+        assert!(!folder_matches("/Archive", "inbox"));
+    }
+
+    #[test]
+    fn seed_folders_follow_account_config() {
+        let root = std::env::temp_dir().join(format!("hutt-seed-{}", std::process::id()));
+        for folder in ["/INBOX", "/[Gmail]/All Mail", "/[Gmail]/Sent Mail"] {
+            maildir::create_folder(&root, folder).unwrap();
+        }
+        let toml_str = format!(
+            r#"
+            name = "Gmail"
+            email = "me@gmail.com"
+            maildir = "{}"
+            [smtp]
+            host = "smtp.gmail.com"
+            [folders]
+            inbox = "/INBOX"
+            archive = "/[Gmail]/All Mail"
+            sent = "/[Gmail]/Sent Mail"
+            trash = "/[Gmail]/Trash"
+            "#,
+            root.display()
+        );
+        let account: crate::config::AccountConfig = toml::from_str(&toml_str).unwrap();
+        assert_eq!(
+            seed_known_folders(Some(&account)),
+            vec!["/INBOX", "/[Gmail]/All Mail", "/[Gmail]/Sent Mail"]
+        );
+        assert_eq!(seed_known_folders(None), vec!["/Inbox"]);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn flag_conflicts_reapply_local_state() {
+        let env = |docid: u32, id: &str, flags: &str| Envelope {
+            docid,
+            message_id: id.to_string(),
+            flags: flags_from_string(flags),
+            ..Envelope::default()
+        };
+        let mut local = HashMap::new();
+        local.insert("read@x".to_string(), "S".to_string());
+        local.insert("same@x".to_string(), "SF".to_string());
+        local.insert("unstarred@x".to_string(), "R".to_string());
+        let envelopes = vec![
+            env(1, "read@x", "R"),
+            env(2, "same@x", "FS"),
+            env(3, "unstarred@x", "RF"),
+            env(4, "other@x", ""),
+        ];
+        assert_eq!(
+            flag_conflicts(&local, &envelopes),
+            vec![(1, "RS".to_string()), (3, "R".to_string())]
+        );
+    }
+
+    #[test]
+    fn deferred_triage_helpers() {
+        assert_eq!(with_flag("RS", 'F', true), "RSF");
+        assert_eq!(with_flag("RSF", 'F', true), "RSF");
+        assert_eq!(with_flag("RFS", 'F', false), "RS");
+
+        let copy = |docid: u32, maildir: &str| Envelope {
+            docid,
+            maildir: maildir.to_string(),
+            ..Envelope::default()
+        };
+        let copies = vec![copy(1, "/Sent"), copy(2, "/Inbox")];
+        assert_eq!(pick_copy(copies.clone(), "/Inbox").map(|e| e.docid), Some(2));
+        assert_eq!(pick_copy(copies, "/Archive").map(|e| e.docid), Some(1));
+        assert!(pick_copy(Vec::new(), "/Inbox").is_none());
+    }
+
+    #[test]
+    fn shell_dry_run_shows_command_and_environment() {
+        assert_eq!(shell_variables("mbsync $ACCOUNT && notify ${HOME}/x $1 $ACCOUNT"), ["ACCOUNT", "HOME"]);
+        let env = |name: &str| (name == "ACCOUNT").then(|| "work".to_string());
+        let lines = shell_dry_run_lines("mbsync $ACCOUNT; echo $NOPE", true, false, "/home/me", env);
+        assert_eq!(lines[0], "$ mbsync $ACCOUNT; echo $NOPE");
+        assert!(lines.contains(&"Runs with sh -c, in the background".to_string()));
+        assert!(lines.contains(&"Reindexes afterwards: yes".to_string()));
+        assert!(lines.contains(&"  ACCOUNT=work".to_string()));
+        assert!(lines.contains(&"  NOPE is unset".to_string()));
+    }
+
+    #[test]
+    fn shell_placeholders_are_filled() {
+        let values = [
+            ("path", Some("'/mail/cur/1:2,S'".to_string())),
+            ("folder", Some("'/Inbox'".to_string())),
+            ("msgid", None),
+        ];
+        assert_eq!(
+            fill_placeholders("mu verify {path} && awk '{print}' {folder}{path}", &values).unwrap(),
+            "mu verify '/mail/cur/1:2,S' && awk '{print}' '/Inbox''/mail/cur/1:2,S'"
+        );
+        assert_eq!(fill_placeholders("echo ${HOME} {unknown", &values).unwrap(), "echo ${HOME} {unknown");
+        assert_eq!(fill_placeholders("echo {msgid}", &values), Err("msgid".to_string()));
+    }
+
+    #[test]
+    fn thread_text_lists_messages_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("hutt-summary-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let message = |n: u32, from: &str, body: &str| {
+            let path = dir.join(format!("{}.eml", n));
+            std::fs::write(&path, format!("From: {}\r\nSubject: Plans\r\nContent-Type: text/plain\r\n\r\n{}\r\n", from, body)).unwrap();
+            Envelope {
+                docid: n,
+                message_id: format!("{}@x", n),
+                subject: "Plans".to_string(),
+                from: vec![crate::envelope::Address {
+                    name: Some(from.to_string()),
+                    email: format!("{}@example.com", from.to_lowercase()),
+                }],
+                path,
+                ..Envelope::default()
+            }
+        };
+        let text = thread_text(&[message(1, "Ana", "Lunch on Friday?"), message(2, "Bo", "Yes, noon.")]);
+        assert!(text.starts_with("Subject: Plans\n\n--- Message 1 of 2 ---\nFrom: Ana <ana@example.com>\n"));
+        let first = text.find("Lunch on Friday?").unwrap();
+        let second = text.find("--- Message 2 of 2 ---\nFrom: Bo <bo@example.com>").unwrap();
+        assert!(first < second);
+        assert!(text.ends_with("Yes, noon.\n"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn new_arrivals_are_unseen_message_ids() {
+        let env = |docid: u32, id: &str| Envelope {
+            docid,
+            message_id: id.to_string(),
+            ..Envelope::default()
+        };
+        let before = HashSet::from(["old@x".to_string()]);
+        let envelopes = vec![env(7, "old@x"), env(8, "new@x")];
+        assert_eq!(new_docids(&envelopes, &before), HashSet::from([8]));
+    }
+
+    #[test]
+    fn sweep_matches_sender_case_insensitively() {
+        let env = |docid: u32, from: &str| Envelope {
+            docid,
+            from: vec![crate::envelope::Address {
+                name: None,
+                email: from.to_string(),
+            }],
+            ..Envelope::default()
+        };
+        let envelopes = vec![
+            env(1, "noreply@ci.example.com"),
+            env(2, "alice@example.com"),
+            env(3, "NoReply@CI.example.com"),
+            Envelope::default(),
+        ];
+        assert_eq!(
+            sweep_docids(&envelopes, "noreply@ci.example.com"),
+            HashSet::from([1, 3])
+        );
+    }
+
+    #[test]
+    fn triage_loop_counts_through_the_list() {
+        let mut state = TriageLoop { total: 3, ..TriageLoop::default() };
+        assert_eq!(state.title(), "Triage 1 of 3");
+        state.archived += 1;
+        state.skipped += 1;
+        assert_eq!(state.title(), "Triage 3 of 3");
+        state.kept += 1;
+        assert_eq!(state.title(), "Triage 3 of 3");
+        assert_eq!(state.summary(), "Triage: 1 archived, 0 trashed, 1 kept, 1 skipped");
+    }
 }
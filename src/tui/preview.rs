@@ -6,15 +6,26 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
-use crate::envelope::Envelope;
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone, Utc};
+use super::envelope_list::sender_badge;
+use crate::envelope::{Address, Envelope};
 use crate::mime_render::{RenderedMessage, SpanKind};
 
 pub struct PreviewPane<'a> {
     pub envelope: Option<&'a Envelope>,
     pub body: Option<&'a RenderedMessage>,
     pub scroll: u16,
+    /// Show every recipient instead of truncating long lists.
+    pub headers_expanded: bool,
+    /// Draw an initials badge before the From address.
+    pub sender_badge: bool,
+    /// Your private note on the message, shown under its headers.
+    pub note: Option<&'a str>,
 }
 
+/// Address lists longer than this are cut short unless headers are expanded.
+const COLLAPSED_ADDRESSES: usize = 3;
+
 impl<'a> Widget for PreviewPane<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let envelope = match self.envelope {
@@ -31,51 +42,7 @@ impl<'a> Widget for PreviewPane<'a> {
             }
         };
 
-        // Build header lines
-        let header_style = Style::default().fg(Color::DarkGray);
-        let value_style = Style::default().fg(Color::White);
-        let subject_style = Style::default()
-            .fg(Color::White)
-            .add_modifier(Modifier::BOLD);
-
-        let mut lines = vec![
-            Line::from(vec![
-                Span::styled("Subject: ", header_style),
-                Span::styled(&envelope.subject, subject_style),
-            ]),
-            Line::from(vec![
-                Span::styled("From:    ", header_style),
-                Span::styled(
-                    envelope
-                        .from
-                        .iter()
-                        .map(|a| a.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                    value_style,
-                ),
-            ]),
-            Line::from(vec![
-                Span::styled("To:      ", header_style),
-                Span::styled(
-                    envelope
-                        .to
-                        .iter()
-                        .map(|a| a.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                    value_style,
-                ),
-            ]),
-            Line::from(vec![
-                Span::styled("Date:    ", header_style),
-                Span::styled(
-                    envelope.date.format("%Y-%m-%d %H:%M %Z").to_string(),
-                    value_style,
-                ),
-            ]),
-            Line::from(""), // separator
-        ];
+        let mut lines = header_lines(envelope, self.body, self.headers_expanded, self.sender_badge, self.note);
 
         // Add body lines from RenderedMessage
         if let Some(body) = self.body {
@@ -104,12 +71,56 @@ impl<'a> Widget for PreviewPane<'a> {
             .borders(Borders::LEFT)
             .border_style(Style::default().fg(Color::DarkGray));
 
+        let total = lines.len();
         let paragraph = Paragraph::new(lines)
             .block(block)
             .scroll((self.scroll, 0));
 
         paragraph.render(area, buf);
+        draw_scroll_indicator(buf, area, self.scroll as usize, total);
+    }
+}
+
+impl<'a> PreviewPane<'a> {
+    /// Total number of lines (headers and body), for scroll clamping.
+    pub fn line_count(&self) -> usize {
+        let Some(envelope) = self.envelope else {
+            return 0;
+        };
+        let headers = header_lines(envelope, self.body, self.headers_expanded, self.sender_badge, self.note).len();
+        let body = match self.body {
+            Some(body) if body.is_html => body.lines.len() + 2,
+            Some(body) => body.lines.len(),
+            None => 1,
+        };
+        headers + body
+    }
+}
+
+/// Position text for a scrollable pane, e.g. "120/480 25%": the last
+/// visible line, the total, and how far through the content that is.
+/// `None` when everything fits.
+pub fn scroll_indicator(scroll: usize, height: usize, total: usize) -> Option<String> {
+    if total <= height || height == 0 {
+        return None;
     }
+    let last = (scroll + height).min(total);
+    let pct = last * 100 / total;
+    Some(format!("{}/{} {}%", last, total, pct))
+}
+
+/// Draw [`scroll_indicator`] in the bottom-right corner of `area`.
+pub fn draw_scroll_indicator(buf: &mut Buffer, area: Rect, scroll: usize, total: usize) {
+    let Some(text) = scroll_indicator(scroll, area.height as usize, total) else {
+        return;
+    };
+    let text = format!(" {} ", text);
+    let width = text.chars().count() as u16;
+    if width >= area.width {
+        return;
+    }
+    let style = Style::default().fg(Color::Black).bg(Color::DarkGray);
+    buf.set_string(area.x + area.width - width, area.y + area.height - 1, &text, style);
 }
 
 /// Map SpanKind to ratatui Style.
@@ -127,5 +138,140 @@ pub fn span_style(kind: &SpanKind) -> Style {
             .fg(Color::White)
             .add_modifier(Modifier::BOLD),
         SpanKind::Code => Style::default().fg(Color::Green),
+        // git's diff colors
+        SpanKind::DiffAdded => Style::default().fg(Color::Green),
+        SpanKind::DiffRemoved => Style::default().fg(Color::Red),
+        SpanKind::DiffMeta => Style::default().fg(Color::Cyan),
+    }
+}
+
+/// Build the header block shown above the body, including the trailing
+/// blank separator line. Cc, Bcc and Reply-To appear only when present.
+pub fn header_lines<'a>(
+    envelope: &'a Envelope,
+    body: Option<&RenderedMessage>,
+    expanded: bool,
+    badge: bool,
+    note: Option<&'a str>,
+) -> Vec<Line<'a>> {
+    let header_style = Style::default().fg(Color::DarkGray);
+    let value_style = Style::default().fg(Color::White);
+    let subject_style = Style::default()
+        .fg(Color::White)
+        .add_modifier(Modifier::BOLD);
+
+    let address_line = |label: &'a str, addrs: &[Address]| -> Line<'a> {
+        let (text, hidden) = format_addresses(addrs, expanded);
+        let mut spans = vec![
+            Span::styled(label, header_style),
+            Span::styled(text, value_style),
+        ];
+        if hidden > 0 {
+            spans.push(Span::styled(format!(" +{} more (H)", hidden), header_style));
+        }
+        Line::from(spans)
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Subject: ", header_style),
+            Span::styled(&envelope.subject, subject_style),
+        ]),
+        address_line("From:    ", &envelope.from),
+        address_line("To:      ", &envelope.to),
+    ];
+    if let (true, Some(addr)) = (badge, envelope.from.first()) {
+        let (text, style) = sender_badge(addr);
+        lines[1].spans.insert(1, Span::styled(text, style));
+        lines[1].spans.insert(2, Span::raw(" "));
+    }
+    if !envelope.cc.is_empty() {
+        lines.push(address_line("Cc:      ", &envelope.cc));
+    }
+    if !envelope.bcc.is_empty() {
+        lines.push(address_line("Bcc:     ", &envelope.bcc));
+    }
+    if let Some(body) = body {
+        // Only worth showing when it sends replies somewhere other than From
+        let differs = body
+            .reply_to
+            .iter()
+            .any(|r| !envelope.from.iter().any(|f| f.email.eq_ignore_ascii_case(&r.email)));
+        if differs {
+            lines.push(address_line("Reply-To: ", &body.reply_to));
+        }
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Date:    ", header_style),
+        Span::styled(
+            date_text(envelope.date, local_offset(envelope.date), body.and_then(|b| b.sent_offset)),
+            value_style,
+        ),
+    ]));
+    if let Some(note) = note {
+        lines.push(Line::from(vec![
+            Span::styled("Note:    ", header_style),
+            Span::styled(note, Style::default().fg(Color::Yellow)),
+        ]));
+    }
+    lines.push(Line::from("")); // separator
+    lines
+}
+
+/// The local timezone's offset at `date`.
+fn local_offset(date: DateTime<Utc>) -> FixedOffset {
+    Local.offset_from_utc_datetime(&date.naive_utc()).fix()
+}
+
+/// `date` in local time and, when the sender's Date header was written in
+/// another timezone, the time it was for them: "2024-05-01 09:14 (16:14
+/// sender time, +09:00)". Their weekday is added when it isn't ours.
+fn date_text(date: DateTime<Utc>, local: FixedOffset, sent: Option<FixedOffset>) -> String {
+    let here = date.with_timezone(&local);
+    let mut text = here.format("%Y-%m-%d %H:%M").to_string();
+    if let Some(sent) = sent.filter(|s| *s != local) {
+        let there = date.with_timezone(&sent);
+        let time = if there.date_naive() == here.date_naive() { "%H:%M" } else { "%a %H:%M" };
+        text.push_str(&format!(" ({} sender time, {})", there.format(time), there.format("%:z")));
+    }
+    text
+}
+
+/// Join addresses for display. Unless `expanded`, only the first
+/// `COLLAPSED_ADDRESSES` are shown; returns the text and how many were cut.
+fn format_addresses(addrs: &[Address], expanded: bool) -> (String, usize) {
+    let shown = if expanded {
+        addrs.len()
+    } else {
+        addrs.len().min(COLLAPSED_ADDRESSES)
+    };
+    let text = addrs[..shown]
+        .iter()
+        .map(Address::header_display)
+        .collect::<Vec<_>>()
+        .join(", ");
+    (text, addrs.len() - shown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_indicator_reports_position() {
+        assert_eq!(scroll_indicator(0, 20, 10), None);
+        assert_eq!(scroll_indicator(0, 20, 80).as_deref(), Some("20/80 25%"));
+        assert_eq!(scroll_indicator(60, 20, 80).as_deref(), Some("80/80 100%"));
+        assert_eq!(scroll_indicator(500, 20, 80).as_deref(), Some("80/80 100%"));
+    }
+
+    #[test]
+    fn date_shows_sender_time_when_zones_differ() {
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 7, 14, 0).unwrap();
+        let hour = |h: i32| FixedOffset::east_opt(h * 3600).unwrap();
+        assert_eq!(date_text(date, hour(2), None), "2024-05-01 09:14");
+        assert_eq!(date_text(date, hour(2), Some(hour(2))), "2024-05-01 09:14");
+        assert_eq!(date_text(date, hour(2), Some(hour(9))), "2024-05-01 09:14 (16:14 sender time, +09:00)");
+        assert_eq!(date_text(date, hour(2), Some(hour(-8))), "2024-05-01 09:14 (Tue 23:14 sender time, -08:00)");
     }
 }
@@ -0,0 +1,169 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::folder_picker::centered_rect;
+use super::text;
+use crate::compose::{self, ComposeContext};
+use crate::envelope::Address;
+use crate::keymap::InputMode;
+
+/// Keys offered under the list of recipients.
+const KEYS: &str = "Space:toggle Enter:compose Esc:cancel";
+
+/// A reply-all held back before the editor opens, so its recipients can
+/// be checked: it would go to a lot of people, or the list's Reply-To
+/// would send it to everyone on the list.
+#[derive(Debug, Clone)]
+pub struct RecipientCheck {
+    pub ctx: ComposeContext,
+    /// Everyone the reply would go to, and whether to keep them.
+    pub recipients: Vec<(Address, bool)>,
+    pub selected: usize,
+    /// Why we're asking, e.g. "Reply all would go to 14 people".
+    pub reasons: Vec<String>,
+    /// Mode to go back to once it's answered.
+    pub from: InputMode,
+}
+
+impl RecipientCheck {
+    /// The check for a reply-all, or None when it can go straight to the
+    /// editor. `max` is the most recipients that pass without asking
+    /// (0 never counts them).
+    pub fn for_reply_all(ctx: &ComposeContext, own: &str, max: usize, from: InputMode) -> Option<Self> {
+        let recipients = compose::reply_all_recipients(ctx, ctx.from.as_deref().unwrap_or(own));
+        let mut reasons = Vec::new();
+        if max > 0 && recipients.len() > max {
+            reasons.push(format!("Reply all would go to {} people", recipients.len()));
+        }
+        if compose::reply_to_munged(ctx, &recipients) {
+            let list = ctx.list_address.as_deref().unwrap_or_default();
+            reasons.push(format!("Reply-To points back to the list {}", list));
+        }
+        if reasons.is_empty() {
+            return None;
+        }
+        Some(Self {
+            ctx: ctx.clone(),
+            recipients: recipients.into_iter().map(|a| (a, true)).collect(),
+            selected: 0,
+            reasons,
+            from,
+        })
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some((_, keep)) = self.recipients.get_mut(self.selected) {
+            *keep = !*keep;
+        }
+    }
+
+    pub fn kept(&self) -> usize {
+        self.recipients.iter().filter(|(_, keep)| *keep).count()
+    }
+
+    /// The reply, addressed to the recipients kept.
+    pub fn confirmed(self) -> ComposeContext {
+        let to = self.recipients.into_iter().filter(|(_, keep)| *keep).map(|(a, _)| a).collect();
+        ComposeContext { to, cc: Vec::new(), ..self.ctx }
+    }
+}
+
+/// Popup listing a reply-all's recipients with a checkbox each.
+pub struct RecipientCheckPopup<'a> {
+    pub check: &'a RecipientCheck,
+}
+
+impl<'a> Widget for RecipientCheckPopup<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let check = self.check;
+        let list = check.ctx.list_address.as_deref();
+        let label = |address: &Address| {
+            if list.is_some_and(|l| l.eq_ignore_ascii_case(&address.email)) {
+                format!("{} (list)", address)
+            } else {
+                address.to_string()
+            }
+        };
+        let widest = check
+            .recipients
+            .iter()
+            .map(|(a, _)| text::width(&label(a)) + 4)
+            .chain(check.reasons.iter().map(|r| text::width(r)))
+            .max()
+            .unwrap_or(0);
+        let popup_width = (widest.max(text::width(KEYS)) as u16 + 4).min(area.width);
+        let popup_height = ((check.reasons.len() + check.recipients.len()) as u16 + 4).min(area.height);
+        let popup = centered_rect(popup_width, popup_height, area);
+
+        Clear.render(popup, buf);
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(format!(" Reply All: {} of {} ", check.kept(), check.recipients.len()))
+            .title_style(Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            .render(popup, buf);
+
+        let inner = Rect::new(
+            popup.x + 1,
+            popup.y + 1,
+            popup.width.saturating_sub(2),
+            popup.height.saturating_sub(2),
+        );
+        if inner.width < 2 || inner.height == 0 {
+            return;
+        }
+        let width = (inner.width - 1) as usize;
+        let mut y = inner.y;
+        for reason in &check.reasons {
+            buf.set_string(inner.x + 1, y, text::truncate(reason, width), Style::default().fg(Color::Yellow));
+            y += 1;
+        }
+
+        // Keep the selection in view when the terminal is short
+        let rows = inner.height.saturating_sub(check.reasons.len() as u16 + 2) as usize;
+        let skip = (check.selected + 1).saturating_sub(rows);
+        y += 1;
+        for (i, (address, keep)) in check.recipients.iter().enumerate().skip(skip).take(rows) {
+            let style = if i == check.selected {
+                Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD)
+            } else if *keep {
+                Style::default().fg(Color::White)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            let line = format!("[{}] {}", if *keep { "x" } else { " " }, label(address));
+            buf.set_string(inner.x + 1, y, text::pad(&text::truncate(&line, width), width), style);
+            y += 1;
+        }
+        let keys = text::truncate(KEYS, width);
+        buf.set_string(inner.x + 1, inner.y + inner.height - 1, keys, Style::default().fg(Color::DarkGray));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_large_reply_alls() {
+        let addr = |email: &str| Address { name: None, email: email.to_string() };
+        let mut ctx = ComposeContext::new_message();
+        ctx.kind = compose::ComposeKind::ReplyAll;
+        ctx.to = vec![addr("alice@x.org")];
+        ctx.cc = vec![addr("me@example.com"), addr("bob@x.org"), addr("carol@x.org")];
+        assert!(RecipientCheck::for_reply_all(&ctx, "me@example.com", 3, InputMode::Normal).is_none());
+        assert!(RecipientCheck::for_reply_all(&ctx, "me@example.com", 0, InputMode::Normal).is_none());
+
+        let mut check = RecipientCheck::for_reply_all(&ctx, "me@example.com", 2, InputMode::Normal).unwrap();
+        assert_eq!(check.reasons, ["Reply all would go to 3 people"]);
+        check.selected = 1;
+        check.toggle();
+        assert_eq!(check.kept(), 2);
+        let content = compose::build_compose_file(&check.confirmed(), "me@example.com").unwrap();
+        assert!(content.contains("To: alice@x.org, carol@x.org\n"), "{}", content);
+    }
+}
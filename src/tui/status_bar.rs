@@ -4,8 +4,11 @@ use ratatui::{
     style::{Color, Modifier, Style},
     widgets::Widget,
 };
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 use crate::keymap::InputMode;
+use crate::mime_render::format_size;
 use crate::tui::{TabRegion, TabRegionKind};
 
 pub struct TopBar<'a> {
@@ -19,6 +22,15 @@ pub struct TopBar<'a> {
     pub tabs: &'a [String],
     pub tab_scroll: usize,
     pub multi_account: bool,
+    /// Number of open view tabs; badges are drawn only when there are several.
+    pub view_count: usize,
+    pub active_view: usize,
+    /// The inbox is paused: show no unread count.
+    pub paused: bool,
+    /// `(loaded, matching)` when the list stopped at `max_results`.
+    pub truncated: Option<(usize, usize)>,
+    /// Low-power mode is on.
+    pub low_power: bool,
 }
 
 /// Result of rendering the tab bar — the hit regions for mouse clicks.
@@ -34,8 +46,9 @@ impl<'a> TopBar<'a> {
 
         let mut regions: Vec<TabRegion> = Vec::new();
 
-        // In thread view, show the thread subject (old behavior)
-        if *self.mode == InputMode::ThreadView {
+        // In thread view, show the thread subject (old behavior); Triage
+        // Loop shows its progress the same way
+        if matches!(self.mode, InputMode::ThreadView | InputMode::TriageLoop) {
             let subj = self.thread_subject.unwrap_or("Thread");
             let text = format!(" {} ", subj);
             buf.set_string(
@@ -73,13 +86,51 @@ impl<'a> TopBar<'a> {
             x += 1;
         }
 
+        // ── View tabs ──────────────────────────────────────────────
+        if self.view_count > 1 {
+            for i in 0..self.view_count {
+                let label = format!(" {} ", i + 1);
+                let style = if i == self.active_view {
+                    Style::default()
+                        .bg(Color::Magenta)
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().bg(Color::Indexed(236)).fg(Color::Magenta)
+                };
+                buf.set_string(x, area.y, &label, style);
+                regions.push(TabRegion {
+                    x_start: x,
+                    x_end: x + 3,
+                    kind: TabRegionKind::View(i),
+                });
+                x += 3;
+            }
+            buf.set_string(x, area.y, " ", bar_style);
+            x += 1;
+        }
+
         // ── Right-aligned counts ───────────────────────────────────
         let unit = if self.conversations_mode { "threads" } else { "messages" };
-        let right = if self.unread_count > 0 {
+        let right = if let Some((loaded, matching)) = self.truncated {
+            let unread = if self.unread_count > 0 && !self.paused {
+                format!("{} unread \u{00b7} ", self.unread_count)
+            } else {
+                String::new()
+            };
+            format!(" {}showing {} of {} ", unread, thousands(loaded), thousands(matching))
+        } else if self.paused {
+            format!(" Paused \u{00b7} {} {} ", self.total_count, unit)
+        } else if self.unread_count > 0 {
             format!(" {}/{} unread ", self.unread_count, self.total_count)
         } else {
             format!(" {} {} ", self.total_count, unit)
         };
+        let right = if self.low_power {
+            format!(" Low power \u{00b7}{}", right)
+        } else {
+            right
+        };
         let right_len = right.len() as u16;
         let right_x = area.x + area.width - right_len;
         // We'll render the right count later, but reserve the space now
@@ -191,14 +242,32 @@ fn tab_style(folder: &str) -> Style {
     Style::default().bg(Color::DarkGray).fg(fg)
 }
 
+/// A count with commas between each group of three digits: 18,214.
+pub fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
 pub struct BottomBar<'a> {
     pub mode: &'a InputMode,
     pub pending_key: Option<String>,
     pub status_message: Option<&'a str>,
     pub filter_desc: Option<&'a str>,
     pub selection_count: usize,
+    /// Total size in bytes of the selected messages.
+    pub selection_size: usize,
     pub conversations_mode: bool,
     pub sort_label: Option<&'a str>,
+    /// Keys for the current state, built from the keymap; the mode's
+    /// fixed hints are shown without them.
+    pub hints: Option<String>,
 }
 
 impl<'a> BottomBar<'a> {
@@ -219,12 +288,29 @@ impl<'a> BottomBar<'a> {
             }
             InputMode::CommandPalette => "j/k:nav Enter:select Esc:cancel | type to filter",
             InputMode::Help => "j/k:scroll ?/q/Esc:close",
+            InputMode::History
+            | InputMode::Digest
+            | InputMode::DeliveryInfo
+            | InputMode::CommandOutput
+            | InputMode::Summary => {
+                "j/k:scroll q/Esc:close"
+            }
+            InputMode::AttachmentBrowser => "j/k:nav Enter:open s:save Esc:close",
             InputMode::SmartFolderCreate => "Type query | Enter:confirm Esc:cancel",
             InputMode::SmartFolderName => "Type name | Enter:save Esc:back",
             InputMode::MaildirCreate => "Type path | Enter:create Esc:cancel",
+            InputMode::QuickReply => "Enter:send Esc:cancel",
+            InputMode::FollowUpDate => "3d, 1w, tomorrow or YYYY-MM-DD; empty clears | Enter:set Esc:cancel",
+            InputMode::HeaderMenu => "j/k:nav Enter:search c:compose r:rule y:copy Esc:close",
+            InputMode::RecentlyDeleted => "j/k:nav Enter/r:restore q/Esc:close",
+            InputMode::RecipientCheck => "j/k:nav Space:toggle Enter:compose Esc:cancel",
+            InputMode::TriageLoop => "a:archive d:trash k:keep s:skip j/Space:scroll q:stop",
+            InputMode::ExportPath => "a directory for .eml files, or a file ending .mbox | Enter:export Esc:cancel",
+            InputMode::ContactName => "the name to show for them; empty removes them | Enter:save Esc:cancel",
+            InputMode::NoteText => "never sent; find it with note: | empty removes it | Enter:save Esc:cancel",
             InputMode::MoveToFolder => "Enter:move Esc:cancel | type to filter",
             InputMode::AccountPicker => "j/k:nav Enter:select Esc:cancel",
-            InputMode::SortPicker => "(d)ate (f)rom (s)ubject (t)o | Esc:cancel",
+            InputMode::SortPicker => "(d)ate (f)rom (s)ubject (t)o s(c)ore | Esc:cancel",
             InputMode::AttachmentPopup => "j/k:nav Enter:select Esc:cancel",
         }
     }
@@ -242,7 +328,11 @@ impl<'a> Widget for BottomBar<'a> {
         }
 
         if self.selection_count > 0 {
-            text.push_str(&format!(" [{} selected] ", self.selection_count));
+            text.push_str(&format!(
+                " [{} selected, {}] ",
+                self.selection_count,
+                format_size(self.selection_size)
+            ));
         }
 
         if let Some(filter) = self.filter_desc {
@@ -257,8 +347,79 @@ impl<'a> Widget for BottomBar<'a> {
             text.push_str(&format!(" {}... | ", pending));
         }
 
-        text.push_str(&format!(" {}", self.hints_for_mode()));
+        match self.hints {
+            Some(ref hints) => text.push_str(&format!(" {}", hints)),
+            None => text.push_str(&format!(" {}", self.hints_for_mode())),
+        }
 
         buf.set_string(area.x, area.y, &text, style);
     }
 }
+
+/// Setup problems found at startup, one per row under the bottom bar.
+pub struct HealthBanner<'a> {
+    pub problems: &'a [String],
+}
+
+impl<'a> Widget for HealthBanner<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default().bg(Color::Yellow).fg(Color::Black);
+        buf.set_style(area, style);
+        for (i, problem) in self.problems.iter().take(area.height as usize).enumerate() {
+            buf.set_string(area.x, area.y + i as u16, format!(" ⚠ {}", problem), style);
+        }
+        let hint = " Esc:dismiss ";
+        let hint_width = hint.chars().count() as u16;
+        if area.height > 0 && area.width > hint_width {
+            let x = area.x + area.width - hint_width;
+            buf.set_string(x, area.y, hint, style.add_modifier(Modifier::BOLD));
+        }
+    }
+}
+
+/// Draw timings for the `HUTT_FRAME_TIME` overlay: how long the last frame
+/// took and how many were drawn in the last second.
+#[derive(Default)]
+pub struct FrameStats {
+    last: Duration,
+    recent: VecDeque<Instant>,
+}
+
+impl FrameStats {
+    pub fn record(&mut self, took: Duration) {
+        let now = Instant::now();
+        self.last = took;
+        self.recent.push_back(now);
+        while self.recent.front().is_some_and(|t| now - *t > Duration::from_secs(1)) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Draw the timings in the bottom-right corner of `area`.
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let text = format!(
+            " {:.1}ms \u{b7} {}/s ",
+            self.last.as_secs_f64() * 1000.0,
+            self.recent.len()
+        );
+        let width = text.chars().count() as u16;
+        if area.width < width || area.height == 0 {
+            return;
+        }
+        let style = Style::default().bg(Color::Black).fg(Color::Yellow);
+        buf.set_string(area.right() - width, area.bottom() - 1, &text, style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thousands_groups_digits() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(500), "500");
+        assert_eq!(thousands(10000), "10,000");
+        assert_eq!(thousands(1_234_567), "1,234,567");
+    }
+}
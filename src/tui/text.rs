@@ -0,0 +1,74 @@
+//! Fitting text into terminal columns. Emoji and CJK characters take two
+//! cells and combining marks none, so everything here measures display
+//! width rather than counting chars.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Width of `s` in terminal cells.
+pub fn width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Cut `s` to at most `max_width` cells, ending it with "…" if cut.
+pub fn truncate(s: &str, max_width: usize) -> String {
+    truncate_with(s, max_width, "\u{2026}")
+}
+
+/// Cut `s` to at most `max_width` cells, ending it with `marker` if cut.
+/// A cut string is padded to exactly `max_width` when a wide character
+/// would have straddled the edge, so columns after it still line up.
+pub fn truncate_with(s: &str, max_width: usize, marker: &str) -> String {
+    if width(s) <= max_width {
+        return s.to_string();
+    }
+    let marker_width = width(marker);
+    if max_width < marker_width {
+        return " ".repeat(max_width);
+    }
+    let room = max_width - marker_width;
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > room {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.push_str(&" ".repeat(room - used));
+    out.push_str(marker);
+    out
+}
+
+/// `s` padded with spaces on the right to `min_width` cells.
+pub fn pad(s: &str, min_width: usize) -> String {
+    let w = width(s);
+    if w >= min_width {
+        s.to_string()
+    } else {
+        format!("{}{}", s, " ".repeat(min_width - w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_by_display_width() {
+        assert_eq!(truncate("Hello", 5), "Hello");
+        assert_eq!(truncate("Hello world", 6), "Hello\u{2026}");
+        // Each CJK character and emoji is two cells
+        assert_eq!(truncate("会议纪要：第三季度", 7), "会议纪\u{2026}");
+        assert_eq!(width(&truncate("会议纪要：第三季度", 8)), 8);
+        assert_eq!(truncate("会议纪要：第三季度", 8), "会议纪 \u{2026}");
+        assert_eq!(truncate("🎉 Launch day", 4), "🎉 \u{2026}");
+        assert_eq!(truncate_with("abcdef", 3, "~"), "ab~");
+        assert_eq!(truncate("abc", 0), "");
+
+        assert_eq!(pad("李", 2), "李");
+        assert_eq!(pad("A", 2), "A ");
+        assert_eq!(width("café"), 4);
+    }
+}
@@ -0,0 +1,73 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::folder_picker::centered_rect;
+
+/// Scrollable popup of plain text lines: the triage digest rendered by
+/// `digest::Digest::render`, or a sent message's delivery record.
+/// Unindented lines are drawn as headings.
+pub struct TextOverlay<'a> {
+    pub title: &'a str,
+    pub scroll: u16,
+    pub lines: &'a [String],
+}
+
+impl<'a> Widget for TextOverlay<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let popup_width: u16 = area.width.saturating_sub(4).clamp(40, 100);
+        let popup_height: u16 = area.height.clamp(10, 40);
+        let popup = centered_rect(popup_width, popup_height, area);
+
+        Clear.render(popup, buf);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(format!(" {} ", self.title))
+            .title_style(
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+        block.render(popup, buf);
+
+        let inner = Rect::new(
+            popup.x + 1,
+            popup.y + 1,
+            popup.width.saturating_sub(2),
+            popup.height.saturating_sub(2),
+        );
+
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        let heading_style = Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD);
+        let row_style = Style::default().fg(Color::White);
+
+        // Body height leaves room for the footer line
+        let body_height = inner.height.saturating_sub(1) as usize;
+        let max_scroll = self.lines.len().saturating_sub(body_height);
+        let scroll = (self.scroll as usize).min(max_scroll);
+        let width = inner.width.saturating_sub(1) as usize;
+
+        for (i, line) in self.lines.iter().skip(scroll).take(body_height).enumerate() {
+            // Section headings are the unindented lines
+            let style = if line.starts_with(' ') { row_style } else { heading_style };
+            buf.set_stringn(inner.x + 1, inner.y + i as u16, line, width, style);
+        }
+
+        buf.set_string(
+            inner.x,
+            inner.y + inner.height - 1,
+            " j/k:scroll  q/Esc:close",
+            Style::default().fg(Color::DarkGray),
+        );
+    }
+}
@@ -5,34 +5,99 @@ use ratatui::{
     widgets::Widget,
 };
 
-use crate::envelope::Envelope;
-use crate::mime_render::{RenderedMessage, SpanKind};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use unicode_width::UnicodeWidthStr;
+
+use super::envelope_list::{sender_badge, sender_color};
+use super::preview::draw_scroll_indicator;
+use super::text;
+use crate::bidi;
+use crate::config::{ThreadHeaders, ThreadViewConfig};
+use crate::envelope::{Address, Envelope};
+use crate::mime_render::{self, RenderedMessage, SpanKind};
 
 pub struct ThreadMessage {
     pub envelope: Envelope,
     pub body: Option<RenderedMessage>,
     pub expanded: bool,
+    /// Number of attachments, counted when the thread is opened.
+    pub attachments: usize,
+}
+
+impl ThreadMessage {
+    pub fn new(envelope: Envelope, expanded: bool) -> Self {
+        // Only parse messages mu has flagged as having attachments
+        let attachments = if envelope.has_attachment() {
+            mime_render::list_attachments(&envelope.path)
+                .map(|a| a.len())
+                .unwrap_or_default()
+        } else {
+            0
+        };
+        Self {
+            envelope,
+            body: None,
+            expanded,
+            attachments,
+        }
+    }
 }
 
 pub struct ThreadView<'a> {
     pub messages: &'a [ThreadMessage],
     pub selected: usize,
     pub scroll: u16,
+    /// Tint sender names with [`sender_color`].
+    pub color_senders: bool,
+    /// Draw an initials badge before each sender.
+    pub sender_badges: bool,
+    /// Headers shown for collapsed and expanded messages.
+    pub headers: &'a ThreadViewConfig,
+    /// Body snippets keyed by message-id, for collapsed summary lines.
+    pub snippets: &'a HashMap<String, String>,
 }
 
-impl<'a> Widget for ThreadView<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
+impl<'a> ThreadView<'a> {
+    /// One-line body preview for a collapsed message: the cached snippet,
+    /// or the first non-blank body line if the body has been rendered.
+    fn snippet_for(&self, msg: &ThreadMessage) -> String {
+        if let Some(snippet) = self.snippets.get(&msg.envelope.message_id) {
+            return snippet.clone();
+        }
+        msg.body
+            .as_ref()
+            .and_then(|body| {
+                body.lines
+                    .iter()
+                    .map(|line| line.iter().map(|s| s.text.as_str()).collect::<String>())
+                    .find(|text| !text.trim().is_empty())
+            })
+            .map(|text| text.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Total number of rendered lines at the given width (for scroll clamping).
+    pub fn line_count(&self, width: u16) -> usize {
         if self.messages.is_empty() {
-            let style = Style::default().fg(Color::DarkGray);
-            buf.set_string(area.x + 2, area.y + area.height / 2, "No messages", style);
-            return;
+            return 0;
         }
+        self.build_lines(width).len()
+    }
 
-        // Thread header: "[N messages in thread]"
-        let header = format!("[{} messages in thread]", self.messages.len());
-        let header_style = Style::default()
-            .fg(Color::DarkGray)
-            .add_modifier(Modifier::ITALIC);
+    /// Lay out the thread header and every message card as lines.
+    fn build_lines(&self, width: u16) -> Vec<RenderedLine> {
+        // Summary bar across the top of the thread
+        let header = text::truncate(
+            &format!(" {}", thread_summary(self.messages)),
+            width.saturating_sub(2) as usize,
+        );
+        let pad = (width.saturating_sub(2) as usize).saturating_sub(header.width());
+        let header = format!("{}{}", header, " ".repeat(pad));
+        let header_style = Style::default().fg(Color::Gray).bg(Color::Indexed(238));
 
         // Collect all lines to render, then apply scroll
         let mut lines: Vec<RenderedLine> = Vec::new();
@@ -49,7 +114,7 @@ impl<'a> Widget for ThreadView<'a> {
         for (idx, msg) in self.messages.iter().enumerate() {
             // Separator between cards (skip before the first one)
             if idx > 0 {
-                let sep: String = "\u{2500}".repeat(area.width.saturating_sub(2) as usize);
+                let sep: String = "\u{2500}".repeat(width.saturating_sub(2) as usize);
                 let sep_style = Style::default().fg(Color::DarkGray);
                 lines.push(RenderedLine {
                     content: vec![(sep, sep_style)],
@@ -59,9 +124,13 @@ impl<'a> Widget for ThreadView<'a> {
 
             let is_selected = idx == self.selected;
 
-            // Build header line: From | Date | expand indicator
+            let headers = if msg.expanded {
+                &self.headers.expanded
+            } else {
+                &self.headers.collapsed
+            };
             let from = msg.envelope.sender_display();
-            let date = msg.envelope.date_display();
+            let date = format_date(&msg.envelope, headers.date_format.as_deref());
             let expand_indicator = if msg.expanded { "[-]" } else { "[+]" };
 
             let bg = if is_selected {
@@ -71,22 +140,80 @@ impl<'a> Widget for ThreadView<'a> {
             };
             let header_base = Style::default().bg(bg);
 
+            let from_color = match msg.envelope.from.first() {
+                Some(addr) if self.color_senders => sender_color(&addr.email),
+                _ => Color::White,
+            };
             let from_style = header_base
-                .fg(Color::White)
+                .fg(from_color)
                 .add_modifier(Modifier::BOLD);
-            let date_style = header_base.fg(Color::DarkGray);
+            let dim_style = header_base.fg(Color::DarkGray);
             let indicator_style = header_base.fg(Color::Cyan);
 
-            lines.push(RenderedLine {
-                content: vec![
-                    (from.to_string(), from_style),
-                    (" | ".to_string(), header_base.fg(Color::DarkGray)),
-                    (date.to_string(), date_style),
+            let mut content = Vec::new();
+            if let (true, Some(addr)) = (self.sender_badges, msg.envelope.from.first()) {
+                content.push(sender_badge(addr));
+                content.push((" ".to_string(), header_base));
+            }
+            content.push((from, from_style));
+
+            if msg.expanded {
+                // Header line: From | Date [-], then optional To/Cc lines
+                content.extend([
+                    (" | ".to_string(), dim_style),
+                    (date, dim_style),
                     (" ".to_string(), header_base),
                     (expand_indicator.to_string(), indicator_style),
-                ],
-                msg_index: Some(idx),
-            });
+                ]);
+                lines.push(RenderedLine {
+                    content,
+                    msg_index: Some(idx),
+                });
+                for (label, addrs, shown) in [
+                    ("To: ", &msg.envelope.to, headers.show_to),
+                    ("Cc: ", &msg.envelope.cc, headers.show_cc),
+                ] {
+                    if shown && !addrs.is_empty() {
+                        lines.push(RenderedLine {
+                            content: vec![
+                                (label.to_string(), dim_style),
+                                (recipient_list(addrs), header_base.fg(Color::Gray)),
+                            ],
+                            msg_index: Some(idx),
+                        });
+                    }
+                }
+            } else {
+                // Summary line: sender [to/cc] snippet ... date [+]
+                for (label, addrs, shown) in [
+                    (" to ", &msg.envelope.to, headers.show_to),
+                    (" cc ", &msg.envelope.cc, headers.show_cc),
+                ] {
+                    if shown && !addrs.is_empty() {
+                        content.push((label.to_string(), dim_style));
+                        content.push((recipient_list(addrs), header_base.fg(Color::Gray)));
+                    }
+                }
+                let right = format!(" {} {}", date, expand_indicator);
+                let used: usize = content.iter().map(|(text, _)| text.width()).sum();
+                let room = (width.saturating_sub(2) as usize).saturating_sub(used + right.width());
+                let snippet = self.snippet_for(msg);
+                let snippet = if snippet.is_empty() || room < 4 {
+                    String::new()
+                } else {
+                    format!("  {}", snippet)
+                };
+                let snippet = text::truncate(&snippet, room);
+                let pad = room.saturating_sub(snippet.width());
+                content.push((snippet, dim_style));
+                content.push((" ".repeat(pad), header_base));
+                content.push((format!(" {} ", date), dim_style));
+                content.push((expand_indicator.to_string(), indicator_style));
+                lines.push(RenderedLine {
+                    content,
+                    msg_index: Some(idx),
+                });
+            }
 
             // If expanded, show body
             if msg.expanded {
@@ -116,6 +243,9 @@ impl<'a> Widget for ThreadView<'a> {
                                         .fg(Color::White)
                                         .add_modifier(Modifier::BOLD),
                                     SpanKind::Code => header_base.fg(Color::Green),
+                                    SpanKind::DiffAdded => header_base.fg(Color::Green),
+                                    SpanKind::DiffRemoved => header_base.fg(Color::Red),
+                                    SpanKind::DiffMeta => header_base.fg(Color::Cyan),
                                     SpanKind::Normal => header_base.fg(Color::White),
                                 };
                                 (span.text.clone(), style)
@@ -129,7 +259,7 @@ impl<'a> Widget for ThreadView<'a> {
                 } else {
                     lines.push(RenderedLine {
                         content: vec![(
-                            "Loading\u{2026}".to_string(),
+                            "Rendering\u{2026}".to_string(),
                             header_base.fg(Color::DarkGray),
                         )],
                         msg_index: Some(idx),
@@ -143,6 +273,20 @@ impl<'a> Widget for ThreadView<'a> {
             }
         }
 
+        lines
+    }
+}
+
+impl<'a> Widget for ThreadView<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.messages.is_empty() {
+            let style = Style::default().fg(Color::DarkGray);
+            buf.set_string(area.x + 2, area.y + area.height / 2, "No messages", style);
+            return;
+        }
+
+        let lines = self.build_lines(area.width);
+
         // Render with scroll offset
         let scroll = self.scroll as usize;
         let visible_height = area.height as usize;
@@ -160,13 +304,15 @@ impl<'a> Widget for ThreadView<'a> {
 
             // Render spans
             let mut x = area.x + 1; // 1 char left padding
-            for (text, style) in &line.content {
+            for (segment, style) in &line.content {
                 let max_chars = (area.x + area.width).saturating_sub(x) as usize;
-                let truncated = truncate_str(text, max_chars);
+                let truncated = text::truncate(segment, max_chars);
                 buf.set_string(x, y, &truncated, *style);
-                x += unicode_width::UnicodeWidthStr::width(truncated.as_str()) as u16;
+                x += truncated.width() as u16;
             }
         }
+
+        draw_scroll_indicator(buf, area, scroll, lines.len());
     }
 }
 
@@ -178,19 +324,292 @@ struct RenderedLine {
     msg_index: Option<usize>,
 }
 
-/// Truncate a string to fit within `max_width` characters, adding "\u{2026}" if needed.
-fn truncate_str(s: &str, max_width: usize) -> String {
-    if max_width == 0 {
-        return String::new();
-    }
-    let chars: Vec<char> = s.chars().collect();
-    if chars.len() <= max_width {
-        s.to_string()
-    } else if max_width <= 1 {
-        "\u{2026}".to_string()
-    } else {
-        let mut result: String = chars[..max_width - 1].iter().collect();
-        result.push('\u{2026}');
-        result
+/// One-line thread summary: message count, date range, attachment count
+/// and the deduplicated participants (From, To and Cc, in order of first
+/// appearance), e.g. "4 messages · Mar 03 – Mar 07 · 2 attachments ·
+/// 3 participants: Alice, Bob, Carol".
+pub fn thread_summary(messages: &[ThreadMessage]) -> String {
+    let count = messages.len();
+    let mut parts = vec![format!(
+        "{} message{}",
+        count,
+        if count == 1 { "" } else { "s" }
+    )];
+
+    let first = messages.iter().map(|m| m.envelope.date).min();
+    let last = messages.iter().map(|m| m.envelope.date).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        let format = if first.format("%Y").to_string() == last.format("%Y").to_string() {
+            "%b %d"
+        } else {
+            "%Y-%m-%d"
+        };
+        let (from, to) = (first.format(format).to_string(), last.format(format).to_string());
+        if from == to {
+            parts.push(from);
+        } else {
+            parts.push(format!("{} \u{2013} {}", from, to));
+        }
+    }
+
+    let attachments: usize = messages.iter().map(|m| m.attachments).sum();
+    if attachments > 0 {
+        parts.push(format!(
+            "{} attachment{}",
+            attachments,
+            if attachments == 1 { "" } else { "s" }
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    let participants: Vec<String> = messages
+        .iter()
+        .flat_map(|m| m.envelope.from.iter().chain(&m.envelope.to).chain(&m.envelope.cc))
+        .filter(|a| seen.insert(a.email.to_lowercase()))
+        .map(Address::short_display)
+        .collect();
+    if !participants.is_empty() {
+        parts.push(format!(
+            "{} participant{}: {}",
+            participants.len(),
+            if participants.len() == 1 { "" } else { "s" },
+            participants.join(", ")
+        ));
+    }
+
+    parts.join(" \u{b7} ")
+}
+
+/// Rows above the body of an expanded message: the header line plus
+/// any To/Cc lines the config asks for.
+pub fn expanded_header_rows(envelope: &Envelope, headers: &ThreadHeaders) -> usize {
+    1 + (headers.show_to && !envelope.to.is_empty()) as usize
+        + (headers.show_cc && !envelope.cc.is_empty()) as usize
+}
+
+/// Format a message date with a strftime-style string, falling back to
+/// the relative list form when no format is set or it is invalid.
+fn format_date(envelope: &Envelope, format: Option<&str>) -> String {
+    if let Some(format) = format {
+        let mut out = String::new();
+        if write!(out, "{}", envelope.date.format(format)).is_ok() {
+            return out;
+        }
+    }
+    envelope.date_display()
+}
+
+/// Recipient names joined for a header line.
+fn recipient_list(addrs: &[Address]) -> String {
+    addrs
+        .iter()
+        .map(Address::short_display)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// An expanded thread message whose body is to be rendered.
+pub struct RenderRequest {
+    pub message_id: String,
+    pub path: PathBuf,
+    pub width: u16,
+    pub max_width: Option<u16>,
+    /// Show a placeholder instead when the message is over this many
+    /// megabytes; 0 renders it whatever its size.
+    pub max_size_mb: u64,
+    /// Text direction forced with `toggle_direction`, if any.
+    pub direction: Option<bidi::Direction>,
+}
+
+/// A rendered body, with the direction it was laid out for.
+pub struct RenderedBody {
+    pub message_id: String,
+    pub direction: Option<bidi::Direction>,
+    pub body: RenderedMessage,
+}
+
+/// Render a body for the thread view; errors become the body's text.
+pub fn render_body(request: &RenderRequest) -> RenderedMessage {
+    if let Some(size) = mime_render::oversized(&request.path, request.max_size_mb) {
+        return mime_render::too_large(size);
+    }
+    match mime_render::render_message(&request.path, &request.message_id, request.width, request.max_width) {
+        Ok(mut rendered) => {
+            let measure = mime_render::text_measure(request.width, request.max_width);
+            bidi::apply(&mut rendered, measure, request.direction);
+            rendered
+        }
+        Err(e) => RenderedMessage {
+            lines: vec![vec![mime_render::RichSpan {
+                text: format!("[Error: {}]", e),
+                kind: SpanKind::Normal,
+            }]],
+            links: Vec::new(),
+            is_html: false,
+            reply_to: Vec::new(),
+            sent_offset: None,
+        },
+    }
+}
+
+/// Spawn a background task rendering thread bodies, so expanding a long
+/// thread doesn't stall the UI. Requests queued together are rendered
+/// in one batch, each result sent as soon as it is ready.
+pub fn spawn_renderer() -> (UnboundedSender<RenderRequest>, UnboundedReceiver<RenderedBody>) {
+    let (req_tx, mut req_rx) = unbounded_channel::<RenderRequest>();
+    let (res_tx, res_rx) = unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(first) = req_rx.recv().await {
+            let mut batch = vec![first];
+            while let Ok(request) = req_rx.try_recv() {
+                batch.push(request);
+            }
+            let res_tx = res_tx.clone();
+            let sent = tokio::task::spawn_blocking(move || {
+                batch.into_iter().all(|request| {
+                    let body = render_body(&request);
+                    res_tx
+                        .send(RenderedBody {
+                            message_id: request.message_id,
+                            direction: request.direction,
+                            body,
+                        })
+                        .is_ok()
+                })
+            })
+            .await
+            .unwrap_or(false);
+            if !sent {
+                break;
+            }
+        }
+    });
+    (req_tx, res_rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(name: &str, expanded: bool) -> ThreadMessage {
+        ThreadMessage {
+            envelope: Envelope {
+                message_id: format!("{}@example.com", name),
+                from: vec![Address {
+                    name: Some(name.to_string()),
+                    email: format!("{}@example.com", name),
+                }],
+                to: vec![Address {
+                    name: Some("Bob".to_string()),
+                    email: "bob@example.com".to_string(),
+                }],
+                date: chrono::DateTime::from_timestamp(0, 0).unwrap(),
+                ..Default::default()
+            },
+            body: None,
+            expanded,
+            attachments: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn renderer_renders_bodies_in_order() {
+        let dir = std::env::temp_dir().join(format!("hutt-render-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("msg");
+        std::fs::write(&path, "Subject: hi\r\n\r\nSee you at noon\r\n").unwrap();
+
+        let (tx, mut rx) = spawn_renderer();
+        for id in ["m1", "m2"] {
+            let path = if id == "m1" { path.clone() } else { dir.join("missing") };
+            tx.send(RenderRequest { message_id: id.into(), path, width: 40, max_width: None, max_size_mb: 0, direction: None })
+                .unwrap();
+        }
+        let first = rx.recv().await.unwrap();
+        assert_eq!(first.message_id, "m1");
+        let text: String = first.body.lines[0].iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(text, "See you at noon");
+        let second = rx.recv().await.unwrap();
+        assert_eq!(second.message_id, "m2");
+        assert!(second.body.lines[0][0].text.starts_with("[Error:"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn line_text(line: &RenderedLine) -> String {
+        line.content.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    #[test]
+    fn collapsed_message_is_a_summary_line() {
+        let messages = vec![message("alice", false)];
+        let mut snippets = HashMap::new();
+        snippets.insert("alice@example.com".to_string(), "Lunch on Friday?".to_string());
+        let headers = ThreadViewConfig {
+            collapsed: ThreadHeaders {
+                date_format: Some("%Y".to_string()),
+                show_to: true,
+                show_cc: true,
+            },
+            ..Default::default()
+        };
+        let view = ThreadView {
+            messages: &messages,
+            selected: 0,
+            scroll: 0,
+            color_senders: false,
+            sender_badges: false,
+            headers: &headers,
+            snippets: &snippets,
+        };
+        let lines = view.build_lines(60);
+        // Summary bar, blank line, then the single summary line
+        assert_eq!(lines.len(), 3);
+        assert!(line_text(&lines[0]).starts_with(" 1 message \u{b7} Jan 01"));
+        let text = line_text(&lines[2]);
+        assert!(text.starts_with("alice to Bob  Lunch on Friday?"));
+        assert!(text.ends_with(" 1970 [+]"));
+        assert_eq!(text.width(), 58);
+    }
+
+    #[test]
+    fn summary_counts_and_dedupes_participants() {
+        let mut first = message("alice", false);
+        first.envelope.cc = vec![Address {
+            name: None,
+            email: "Bob@Example.com".to_string(),
+        }];
+        let mut second = message("bob", true);
+        second.envelope.date = chrono::DateTime::from_timestamp(86_400 * 3, 0).unwrap();
+        second.envelope.to = vec![first.envelope.from[0].clone()];
+        second.attachments = 2;
+        assert_eq!(
+            thread_summary(&[first, second]),
+            "2 messages \u{b7} Jan 01 \u{2013} Jan 04 \u{b7} 2 attachments \u{b7} \
+             2 participants: alice, Bob"
+        );
+        assert_eq!(
+            thread_summary(&[message("carol", true)]),
+            "1 message \u{b7} Jan 01 \u{b7} 2 participants: carol, Bob"
+        );
+    }
+
+    #[test]
+    fn expanded_header_rows_follow_config() {
+        let msg = message("alice", true);
+        let mut headers = ThreadHeaders::default();
+        assert_eq!(expanded_header_rows(&msg.envelope, &headers), 1);
+        headers.show_to = true;
+        headers.show_cc = true;
+        // No Cc recipients, so only the To line is added
+        assert_eq!(expanded_header_rows(&msg.envelope, &headers), 2);
+    }
+
+    #[test]
+    fn invalid_date_format_falls_back() {
+        let envelope = message("alice", true).envelope;
+        assert_eq!(format_date(&envelope, Some("%Y-%m")), "1970-01");
+        assert_eq!(format_date(&envelope, Some("%Q")), envelope.date_display());
+        assert_eq!(format_date(&envelope, None), envelope.date_display());
     }
 }
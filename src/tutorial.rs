@@ -0,0 +1,340 @@
+//! `hutt --tutorial`: a guided first run against a small made-up maildir,
+//! written to a temporary directory with its own mu database and state so
+//! nothing touches real mail. Each step's prompt sits in the status bar
+//! until the app shows the step was done (the selection moved, the
+//! message left the list, the search ran), then the next one is shown.
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Local};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+/// The tutorial's one account.
+pub const ACCOUNT: &str = "Tutorial";
+
+/// Maildir folders of the fixture, all created even when empty.
+const FOLDERS: [&str; 5] = ["Inbox", "Archive", "Sent", "Drafts", "Trash"];
+
+/// One message of the fixture maildir.
+struct Fixture {
+    folder: &'static str,
+    from: &'static str,
+    subject: &'static str,
+    /// Hours before now it was sent.
+    hours_ago: i64,
+    read: bool,
+    body: &'static str,
+}
+
+const MESSAGES: &[Fixture] = &[
+    Fixture {
+        folder: "Inbox",
+        from: "Alice Liddell <alice@example.org>",
+        subject: "Lunch on Thursday?",
+        hours_ago: 1,
+        read: false,
+        body: "Are you free for lunch on Thursday? The new place by the station.\n\nAlice",
+    },
+    Fixture {
+        folder: "Inbox",
+        from: "Weekly Digest <news@lists.example.net>",
+        subject: "This week: 12 stories you missed",
+        hours_ago: 3,
+        read: false,
+        body: "Our pick of the week's stories.\n\nUnsubscribe: https://lists.example.net/u",
+    },
+    Fixture {
+        folder: "Inbox",
+        from: "Bob Builder <bob@example.com>",
+        subject: "Quarterly report draft",
+        hours_ago: 5,
+        read: true,
+        body: "Here's the draft of the report. Comments by Friday, please.\n\nBob",
+    },
+    Fixture {
+        folder: "Inbox",
+        from: "Alice Liddell <alice@example.org>",
+        subject: "Photos from the trip",
+        hours_ago: 26,
+        read: true,
+        body: "I finally sorted the photos. The ones from the lake came out best.\n\nAlice",
+    },
+    Fixture {
+        folder: "Inbox",
+        from: "Shop <orders@shop.example>",
+        subject: "Your order has shipped",
+        hours_ago: 30,
+        read: false,
+        body: "Your order #1234 is on its way and should arrive on Monday.",
+    },
+    Fixture {
+        folder: "Inbox",
+        from: "Carol Danvers <carol@example.com>",
+        subject: "Re: Project kickoff",
+        hours_ago: 50,
+        read: true,
+        body: "Sounds good. I've booked the big meeting room for 10:00.\n\nCarol",
+    },
+    Fixture {
+        folder: "Archive",
+        from: "Bob Builder <bob@example.com>",
+        subject: "Welcome to the team",
+        hours_ago: 24 * 30,
+        read: true,
+        body: "Welcome aboard! Let me know if you need anything.\n\nBob",
+    },
+    Fixture {
+        folder: "Sent",
+        from: "You <you@example.com>",
+        subject: "Project kickoff",
+        hours_ago: 52,
+        read: true,
+        body: "Shall we meet on Wednesday to kick off the project?",
+    },
+];
+
+/// What a step's check looks at, taken from the app.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub folder: String,
+    pub selected: usize,
+    /// Rows in the list.
+    pub listed: usize,
+    /// Actions that can be undone.
+    pub undo_depth: usize,
+}
+
+/// One step: what to do, and whether it's done, judged from the app as
+/// the step began and as it is now.
+struct Step {
+    prompt: &'static str,
+    done: fn(&Snapshot, &Snapshot) -> bool,
+}
+
+const STEPS: &[Step] = &[
+    Step {
+        prompt: "Press j to move down the list (k moves back up)",
+        done: |start, now| now.selected > start.selected,
+    },
+    Step {
+        prompt: "Press e to archive the selected message",
+        done: |start, now| now.undo_depth > start.undo_depth && now.listed < start.listed,
+    },
+    Step {
+        prompt: "Changed your mind? Press z to undo the archive",
+        done: |start, now| now.undo_depth < start.undo_depth && now.listed > start.listed,
+    },
+    Step {
+        prompt: "Press /, type from:alice and press Enter to search",
+        done: |_, now| !now.folder.starts_with('/') && now.folder.to_lowercase().contains("alice"),
+    },
+    Step {
+        prompt: "Press g then i to go back to the inbox",
+        done: |start, now| now.folder != start.folder && now.folder.eq_ignore_ascii_case("/Inbox"),
+    },
+    Step {
+        prompt: "Smart folders are saved searches: press g l, type unread, press Enter",
+        done: |_, now| now.folder.starts_with('@'),
+    },
+];
+
+/// Where the user is in the tutorial.
+#[derive(Debug, Clone)]
+pub struct Tutorial {
+    step: usize,
+    /// The app as the current step began.
+    start: Snapshot,
+}
+
+impl Tutorial {
+    pub fn new(now: Snapshot) -> Self {
+        Self { step: 0, start: now }
+    }
+
+    /// The current step's prompt, or None once they're all done.
+    pub fn prompt(&self) -> Option<String> {
+        let step = STEPS.get(self.step)?;
+        Some(format!("Tutorial {}/{}: {}", self.step + 1, STEPS.len(), step.prompt))
+    }
+
+    pub fn finished(&self) -> bool {
+        self.step >= STEPS.len()
+    }
+
+    /// Move past the current step if `now` shows it done. Returns whether
+    /// it did.
+    pub fn advance(&mut self, now: &Snapshot) -> bool {
+        let Some(step) = STEPS.get(self.step) else {
+            return false;
+        };
+        if !(step.done)(&self.start, now) {
+            return false;
+        }
+        self.step += 1;
+        self.start = now.clone();
+        true
+    }
+}
+
+/// Write the fixture maildir under `root`: each folder with `cur`, `new`
+/// and `tmp`, unread messages in `new`.
+pub fn write_maildir(root: &Path) -> Result<()> {
+    for folder in FOLDERS {
+        for sub in ["cur", "new", "tmp"] {
+            let dir = root.join(folder).join(sub);
+            std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+    }
+    let now = Local::now();
+    for (i, message) in MESSAGES.iter().enumerate() {
+        let date = now - Duration::hours(message.hours_ago);
+        let to = if message.folder == "Sent" { "Carol Danvers <carol@example.com>" } else { "You <you@example.com>" };
+        let raw = format!(
+            "From: {}\nTo: {}\nSubject: {}\nDate: {}\nMessage-ID: <tutorial-{}@hutt.example>\n\
+             Content-Type: text/plain; charset=utf-8\n\n{}\n",
+            message.from,
+            to,
+            message.subject,
+            date.to_rfc2822(),
+            i + 1,
+            message.body
+        );
+        let name = format!("{}.tutorial{}.hutt", date.timestamp(), i + 1);
+        let path = if message.read {
+            root.join(message.folder).join("cur").join(format!("{}:2,S", name))
+        } else {
+            root.join(message.folder).join("new").join(name)
+        };
+        std::fs::write(&path, raw).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Set up a fresh tutorial under `dir`: the maildir, and a config with
+/// one account on it and its own mu database. hutt's config, state and
+/// cache go there too, so the tutorial leaves no trace once `dir` is
+/// removed. Call before anything else reads them.
+pub fn prepare(dir: &Path) -> Result<Config> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir).with_context(|| format!("failed to clear {}", dir.display()))?;
+    }
+    let maildir = dir.join("mail");
+    write_maildir(&maildir)?;
+    crate::paths::use_root(dir);
+    config(dir)
+}
+
+/// The tutorial's config, for its maildir and mu database under `dir`.
+fn config(dir: &Path) -> Result<Config> {
+    let toml = format!(
+        "restore_session = false\n\
+         single_instance = \"allow\"\n\
+         compose_dir = \"{}\"\n\
+         [[accounts]]\n\
+         name = \"{}\"\n\
+         email = \"you@example.com\"\n\
+         maildir = \"{}\"\n\
+         muhome = \"{}\"\n\
+         smtp = {{}}\n",
+        dir.join("compose").display(),
+        ACCOUNT,
+        dir.join("mail").display(),
+        dir.join("mu").display()
+    );
+    toml::from_str(&toml).context("failed to build the tutorial config")
+}
+
+/// Where the tutorial is set up for this run.
+pub fn dir() -> PathBuf {
+    std::env::temp_dir().join(format!("hutt-tutorial-{}", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tutorial_steps_follow_the_app() {
+        let dir = std::env::temp_dir().join(format!("hutt-tutorial-test-{}", std::process::id()));
+        let maildir = dir.join("mail");
+        write_maildir(&maildir).unwrap();
+        let count = |folder: &str, sub: &str| std::fs::read_dir(maildir.join(folder).join(sub)).unwrap().count();
+        assert_eq!(count("Inbox", "new") + count("Inbox", "cur"), 6);
+        assert_eq!(count("Inbox", "new"), 3);
+        assert_eq!(count("Trash", "cur"), 0);
+        let config = config(&dir).unwrap();
+        assert_eq!(config.accounts[0].name, ACCOUNT);
+        assert_eq!(config.accounts[0].maildir, maildir.display().to_string());
+        assert!(!config.restore_session);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let inbox = Snapshot { folder: "/Inbox".into(), selected: 0, listed: 6, undo_depth: 0 };
+        let mut tutorial = Tutorial::new(inbox.clone());
+        assert_eq!(tutorial.prompt().unwrap(), "Tutorial 1/6: Press j to move down the list (k moves back up)");
+        assert!(!tutorial.advance(&inbox));
+
+        let moved = Snapshot { selected: 1, ..inbox.clone() };
+        assert!(tutorial.advance(&moved));
+        let archived = Snapshot { listed: 5, undo_depth: 1, ..moved.clone() };
+        assert!(tutorial.advance(&archived));
+        assert!(!tutorial.advance(&archived));
+        assert!(tutorial.advance(&moved));
+
+        let searched = Snapshot { folder: "from:alice".into(), listed: 2, ..moved.clone() };
+        assert!(!tutorial.advance(&Snapshot { folder: "from:bob".into(), ..moved.clone() }));
+        assert!(tutorial.advance(&searched));
+        assert!(tutorial.advance(&moved));
+        assert!(!tutorial.finished());
+        assert!(tutorial.advance(&Snapshot { folder: "@Unread".into(), ..moved }));
+        assert!(tutorial.finished());
+        assert_eq!(tutorial.prompt(), None);
+    }
+
+    #[test]
+    fn bundled_maildir_walks_through_the_steps() {
+        let dir = std::env::temp_dir().join(format!("hutt-tutorial-walk-{}", std::process::id()));
+        let maildir = dir.join("mail");
+        write_maildir(&maildir).unwrap();
+        for folder in FOLDERS {
+            assert!(crate::maildir::folder_exists(&maildir, folder), "{}", folder);
+        }
+
+        // Every message parses, with the headers the steps rely on
+        let mut inbox = Vec::new();
+        for sub in ["new", "cur"] {
+            for entry in std::fs::read_dir(maildir.join("Inbox").join(sub)).unwrap() {
+                let path = entry.unwrap().path();
+                let raw = std::fs::read(&path).unwrap();
+                let message = mail_parser::MessageParser::default().parse(&raw).unwrap();
+                let from = message.from().and_then(|a| a.first()).and_then(|a| a.address()).unwrap().to_string();
+                assert!(message.message_id().unwrap().ends_with("@hutt.example"));
+                assert_eq!(sub == "cur", path.to_string_lossy().ends_with(":2,S"));
+                assert!(!crate::mime_render::snippet_for_path(&path, 40).is_empty());
+                inbox.push(from);
+            }
+        }
+        let config = config(&dir).unwrap();
+        assert_eq!(config.compose_dir.as_deref(), Some(dir.join("compose").to_str().unwrap()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Play the steps against what the maildir holds
+        let alice = inbox.iter().filter(|from| from.as_str() == "alice@example.org").count();
+        assert_eq!(alice, 2);
+        let start = Snapshot { folder: "/Inbox".into(), selected: 0, listed: inbox.len(), undo_depth: 0 };
+        let moved = Snapshot { selected: 1, ..start.clone() };
+        let walk = [
+            moved.clone(),
+            Snapshot { listed: inbox.len() - 1, undo_depth: 1, ..moved.clone() },
+            moved.clone(),
+            Snapshot { folder: "from:alice".into(), listed: alice, ..moved.clone() },
+            moved.clone(),
+            Snapshot { folder: "@Unread".into(), ..moved },
+        ];
+        let mut tutorial = Tutorial::new(start);
+        for now in &walk {
+            assert!(tutorial.advance(now), "{:?}", tutorial.prompt());
+        }
+        assert!(tutorial.finished());
+    }
+}
@@ -10,6 +10,15 @@ pub enum UndoAction {
         original_maildir: String,
         original_flags: String,
     },
+    /// Moves undone together, as `(docid, original_maildir, original_flags)`.
+    MoveMessages {
+        moves: Vec<(u32, String, String)>,
+    },
+    /// Triage held back while mu indexes; undoing it drops the change.
+    /// Replaced by the real move once the change is made.
+    HeldTriage {
+        id: u64,
+    },
     DeleteSmartFolder {
         folder: SmartFolder,
     },
@@ -45,13 +54,53 @@ impl UndoStack {
         self.entries.pop()
     }
 
+    /// Put `entry` where the held triage `id` was in the stack, or drop
+    /// that entry when the change came to nothing. Changes saved at the
+    /// last quit have no held entry, so theirs goes on top.
+    pub fn resolve_held(&mut self, id: u64, entry: Option<UndoEntry>) {
+        let held = self
+            .entries
+            .iter()
+            .position(|e| matches!(e.action, UndoAction::HeldTriage { id: held } if held == id));
+        match (held, entry) {
+            (Some(i), Some(entry)) => self.entries[i] = entry,
+            (Some(i), None) => {
+                self.entries.remove(i);
+            }
+            (None, Some(entry)) => self.entries.push(entry),
+            (None, None) => {}
+        }
+    }
+
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
         self.entries.is_empty()
     }
 
-    #[allow(dead_code)]
     pub fn len(&self) -> usize {
         self.entries.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_triage_is_replaced_in_place() {
+        let entry = |action, description: &str| UndoEntry { action, description: description.to_string() };
+        let mut stack = UndoStack::new();
+        stack.push(entry(UndoAction::HeldTriage { id: 0 }, "Archive"));
+        stack.push(entry(UndoAction::HeldTriage { id: 1 }, "toggle star"));
+        stack.push(entry(UndoAction::DeleteMaildirFolder { path: "/Old".into() }, "delete"));
+
+        let moved = UndoAction::MoveMessage { docid: 7, original_maildir: "/Inbox".into(), original_flags: String::new() };
+        stack.resolve_held(0, Some(entry(moved, "Archive")));
+        stack.resolve_held(1, None);
+        stack.resolve_held(5, Some(entry(UndoAction::MoveMessages { moves: Vec::new() }, "saved")));
+        assert_eq!(stack.len(), 3);
+        assert!(matches!(stack.pop().unwrap().action, UndoAction::MoveMessages { .. }));
+        assert!(matches!(stack.pop().unwrap().action, UndoAction::DeleteMaildirFolder { .. }));
+        assert!(matches!(stack.pop().unwrap().action, UndoAction::MoveMessage { docid: 7, .. }));
+    }
+}
@@ -0,0 +1,127 @@
+//! The envelope list on screen when hutt quit, saved so the next launch
+//! can draw it straight away while the mu server starts and the real
+//! query runs, instead of showing a blank screen.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::envelope::Envelope;
+use crate::session::ViewState;
+
+/// Envelopes kept: more than fit on a screen, few enough to load quickly.
+const MAX_ENVELOPES: usize = 200;
+
+/// A saved envelope list and the view it was showing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmStart {
+    pub view: ViewState,
+    pub conversations: bool,
+    pub envelopes: Vec<Envelope>,
+}
+
+impl WarmStart {
+    pub fn new(view: ViewState, conversations: bool, envelopes: &[Envelope]) -> Self {
+        Self {
+            view,
+            conversations,
+            envelopes: envelopes.iter().take(MAX_ENVELOPES).cloned().collect(),
+        }
+    }
+
+    /// Whether this list is what opening `view` will show.
+    pub fn matches(&self, view: &ViewState) -> bool {
+        self.view.folder == view.folder
+            && self.view.filter_unread == view.filter_unread
+            && self.view.filter_starred == view.filter_starred
+            && self.view.filter_needs_reply == view.filter_needs_reply
+    }
+}
+
+/// Return the path to the saved list for a given account name.
+///
+/// Per-account files: `warm_start.<account_name>.json`, or plain
+/// `warm_start.json` when `account_name` is empty.
+pub fn warm_start_path(account_name: &str) -> PathBuf {
+    let dir = crate::paths::state_dir();
+    if account_name.is_empty() {
+        dir.join("warm_start.json")
+    } else {
+        dir.join(format!("warm_start.{}.json", account_name))
+    }
+}
+
+/// Load an account's saved list. `None` if there is none or it is unreadable.
+pub fn load_warm_start(account_name: &str) -> Option<WarmStart> {
+    load_warm_start_from(&warm_start_path(account_name))
+}
+
+fn load_warm_start_from(path: &Path) -> Option<WarmStart> {
+    let contents = crate::state_crypt::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Save an account's list. Creates parent directories if needed.
+pub fn save_warm_start(warm: &WarmStart, account_name: &str) {
+    save_warm_start_to(warm, &warm_start_path(account_name));
+}
+
+fn save_warm_start_to(warm: &WarmStart, path: &Path) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string(warm) {
+        let _ = crate::state_crypt::write(path, &contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::{flags_from_string, Address};
+
+    #[test]
+    fn warm_start_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("hutt-warm-{}", std::process::id()));
+        let path = dir.join("warm_start.work.json");
+
+        let envelopes: Vec<Envelope> = (0..300)
+            .map(|i| Envelope {
+                docid: i,
+                message_id: format!("{}@example.com", i),
+                subject: format!("Message {}", i),
+                from: vec![Address {
+                    name: Some("Alice".to_string()),
+                    email: "alice@example.com".to_string(),
+                }],
+                flags: flags_from_string("SF"),
+                ..Envelope::default()
+            })
+            .collect();
+        let view = ViewState {
+            folder: "/Inbox".to_string(),
+            selected: 3,
+            ..ViewState::default()
+        };
+        save_warm_start_to(&WarmStart::new(view.clone(), true, &envelopes), &path);
+
+        let loaded = load_warm_start_from(&path).unwrap();
+        assert_eq!(loaded.view, view);
+        assert!(loaded.conversations);
+        assert_eq!(loaded.envelopes.len(), MAX_ENVELOPES);
+        assert_eq!(loaded.envelopes[1].subject, "Message 1");
+        assert!(loaded.envelopes[1].is_flagged());
+        assert_eq!(loaded.envelopes[1].date, envelopes[1].date);
+
+        // The same folder with other filters, or another folder, doesn't match
+        assert!(loaded.matches(&ViewState { folder: "/Inbox".to_string(), ..ViewState::default() }));
+        assert!(!loaded.matches(&ViewState {
+            folder: "/Inbox".to_string(),
+            filter_unread: true,
+            ..ViewState::default()
+        }));
+        assert!(!loaded.matches(&ViewState { folder: "/Archive".to_string(), ..ViewState::default() }));
+        assert!(load_warm_start_from(&dir.join("missing.json")).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}